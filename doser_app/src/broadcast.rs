@@ -0,0 +1,91 @@
+//! `[broadcast]`: a low-rate UDP datagram per interval while a dose runs, so
+//! a plant dashboard can aggregate many stations from one multicast group
+//! instead of polling each.
+//!
+//! Each datagram is one JSON object:
+//! `{"station", "seq", "state", "phase", "weight_g", "target_g"}`. A last one
+//! with the final state is sent when the broadcaster is dropped. Sending is
+//! best-effort: a lost datagram is not retried and a send error is logged
+//! once.
+
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use doser_config::BroadcastCfg;
+use serde_json::json;
+
+use crate::DoserHandle;
+
+pub struct Broadcaster {
+    shutdown: Arc<AtomicBool>,
+    join_handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Broadcaster {
+    /// Send the status of `handle`'s run to `cfg.group` every `cfg.interval_ms`.
+    pub fn spawn(cfg: &BroadcastCfg, handle: DoserHandle) -> std::io::Result<Self> {
+        let group: SocketAddr = cfg
+            .group
+            .parse()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        let socket = match group {
+            SocketAddr::V4(_) => {
+                let s = UdpSocket::bind("0.0.0.0:0")?;
+                s.set_multicast_ttl_v4(cfg.ttl)?;
+                s
+            }
+            SocketAddr::V6(_) => UdpSocket::bind("[::]:0")?,
+        };
+        let station = cfg.station.clone();
+        let interval = Duration::from_millis(cfg.interval_ms);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_bg = Arc::clone(&shutdown);
+
+        let join_handle = std::thread::spawn(move || {
+            let mut seq: u64 = 0;
+            let mut warned = false;
+            loop {
+                // Read the flag first: the final datagram carries the final state.
+                let last = shutdown_bg.load(Ordering::Acquire);
+                let status = handle.poll_status();
+                let msg = json!({
+                    "station": station,
+                    "seq": seq,
+                    "state": status.state.name(),
+                    "phase": status.phase.map(crate::handle::phase_name),
+                    "weight_g": status.weight_g,
+                    "target_g": status.target_g,
+                });
+                if let Err(e) = socket.send_to(msg.to_string().as_bytes(), group)
+                    && !warned
+                {
+                    tracing::warn!(error = %e, %group, "weight broadcast failed");
+                    warned = true;
+                }
+                seq += 1;
+                if last {
+                    break;
+                }
+                std::thread::park_timeout(interval);
+            }
+        });
+        Ok(Self {
+            shutdown,
+            join_handle: Some(join_handle),
+        })
+    }
+}
+
+impl Drop for Broadcaster {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Release);
+        if let Some(handle) = self.join_handle.take() {
+            handle.thread().unpark();
+            if handle.join().is_err() {
+                tracing::warn!("broadcast thread panicked");
+            }
+        }
+    }
+}
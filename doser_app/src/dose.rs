@@ -0,0 +1,791 @@
+//! Dose execution: config mapping, real-time setup, the control loop, and stats.
+
+use crate::procinfo;
+use crate::rt::setup_rt_once;
+use crate::{DoserHandle, JsonTelemetry, RtLock, RunSafety};
+use doser_config::Calibration;
+use doser_core::error::Result as CoreResult;
+use doser_core::runner::{RunClock, RunParams, SamplingMode};
+use doser_core::{RunState, StatusBoard};
+
+pub fn abort_reason_name(r: &doser_core::error::AbortReason) -> &'static str {
+    use doser_core::error::AbortReason::*;
+    match r {
+        Estop => "Estop",
+        NoProgress => "NoProgress",
+        MaxRuntime => "MaxRuntime",
+        Overshoot => "Overshoot",
+        MaxAttempts => "MaxAttempts",
+        Undershoot => "Undershoot",
+    }
+}
+
+/// Control loop statistics for a dose.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Stats {
+    /// Use the core runner; no per-step timing
+    #[default]
+    Off,
+    /// Time each step and report the jitter in the telemetry
+    Collect,
+    /// As `Collect`, and print the stats block to stderr
+    Print,
+}
+
+/// How [`run_dose`] runs a dose. The default is a plain dose: the config's
+/// safety limits, a sampler thread, no real-time setup, no stats and no
+/// warm-up progress output.
+#[derive(Clone, Debug, Default)]
+pub struct DoseOptions {
+    /// Overrides `safety.max_run_ms`
+    pub max_run_ms: Option<u64>,
+    /// Overrides `safety.max_overshoot_g`
+    pub max_overshoot_g: Option<f32>,
+    /// Read the scale in the control loop instead of on a sampler thread
+    pub direct: bool,
+    /// Real-time scheduling, with its priority, memory locking and CPU
+    pub rt: bool,
+    pub rt_prio: Option<i32>,
+    pub rt_lock: Option<RtLock>,
+    pub rt_cpu: Option<usize>,
+    pub stats: Stats,
+    /// Run on virtual time (simulated hardware only; see `RunClock`)
+    pub sim_clock: bool,
+    /// Receives scale warm-up events ("stabilizing…"); none by default
+    pub progress: doser_core::warmup::ProgressSink,
+}
+
+/// Run a dose of `grams` on `hw`. An abort carries the safety limits it ran
+/// under as context (`report.downcast_ref::<RunSafety>()`).
+pub fn run_dose(
+    cfg: &doser_config::Config,
+    calib: Option<&Calibration>,
+    grams: f32,
+    hw: (
+        impl doser_traits::Scale + Send + 'static,
+        impl doser_traits::Motor + 'static,
+    ),
+    opts: DoseOptions,
+    handle: &DoserHandle,
+) -> CoreResult<(f32, JsonTelemetry)> {
+    let safety = effective_safety(cfg, &opts);
+    let limits = RunSafety {
+        max_run_ms: safety.max_run_ms,
+        max_overshoot_g: safety.max_overshoot_g,
+        no_progress_ms: safety.no_progress_ms,
+        no_progress_epsilon_g: safety.no_progress_epsilon_g,
+    };
+    run(cfg, calib, grams, hw, opts, safety, handle).map_err(|e| {
+        if matches!(
+            e.downcast_ref::<doser_core::error::DoserError>(),
+            Some(doser_core::error::DoserError::Abort(_))
+        ) {
+            e.wrap_err(limits)
+        } else {
+            e
+        }
+    })
+}
+
+/// The config's safety limits with the overrides in `opts` and the core
+/// defaults for the unset (zero) ones.
+fn effective_safety(cfg: &doser_config::Config, opts: &DoseOptions) -> doser_core::SafetyCfg {
+    let defaults = doser_core::SafetyCfg::default();
+    let mut safety: doser_core::SafetyCfg = (&cfg.safety).into();
+    if let Some(ms) = opts.max_run_ms {
+        safety.max_run_ms = ms;
+    } else if safety.max_run_ms == 0 {
+        safety.max_run_ms = defaults.max_run_ms;
+    }
+    if let Some(g) = opts.max_overshoot_g {
+        safety.max_overshoot_g = g;
+    } else if safety.max_overshoot_g == 0.0 {
+        safety.max_overshoot_g = defaults.max_overshoot_g;
+    }
+    safety
+}
+
+#[allow(clippy::type_complexity)]
+fn run(
+    _cfg: &doser_config::Config,
+    calib: Option<&Calibration>,
+    grams: f32,
+    hw: (
+        impl doser_traits::Scale + Send + 'static,
+        impl doser_traits::Motor + 'static,
+    ),
+    opts: DoseOptions,
+    safety: doser_core::SafetyCfg,
+    handle: &DoserHandle,
+) -> CoreResult<(f32, JsonTelemetry)> {
+    let DoseOptions {
+        max_run_ms: max_run_ms_override,
+        direct,
+        rt,
+        rt_prio,
+        rt_lock,
+        rt_cpu,
+        stats,
+        sim_clock,
+        progress,
+        ..
+    } = opts;
+    // Declared before `finished`: dropped after it, so the last datagram
+    // carries the final state.
+    let _broadcast = _cfg.broadcast.as_ref().and_then(|b| {
+        crate::broadcast::Broadcaster::spawn(b, handle.clone())
+            .map_err(|e| tracing::warn!(error = %e, "weight broadcast not started"))
+            .ok()
+    });
+    let mut finished = Finish {
+        status: &handle.status,
+        state: RunState::Aborted,
+    };
+    // Real-time mode setup (Linux/macOS) — run once per process
+    #[cfg(target_os = "linux")]
+    {
+        let mode = rt_lock.unwrap_or(RtLock::os_default());
+        setup_rt_once(rt, rt_prio, mode, rt_cpu);
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let mode = rt_lock.unwrap_or(RtLock::os_default());
+        let _rt_prio = rt_prio; // silence unused on non-Linux builds
+        let _rt_cpu = rt_cpu; // silence unused on non-Linux builds
+        setup_rt_once(rt, mode);
+    }
+
+    // Stats: control loop latency, jitter, missed deadlines
+    let mut latencies = Vec::new();
+    let mut missed_deadlines = 0;
+    let mut sample_count = 0;
+
+    // Builder/config mapping — use From impls from doser_core::conversions
+    let filter: doser_core::FilterCfg = (&_cfg.filter).into();
+    let control: doser_core::ControlCfg = (&_cfg.control).into();
+    let timeouts: doser_core::Timeouts = (&_cfg.timeouts).into();
+    let read_retry: doser_core::ReadRetryCfg = (&_cfg.timeouts.retry).into();
+    let calibration_core = calib.map(doser_core::Calibration::from);
+    let (mut scale, mut motor) = hw;
+    handle.set_stop_latch(motor.stop_latch());
+    let estop_check = if crate::hw::is_sim(_cfg) {
+        sim_estop()
+    } else {
+        gpio_estop(_cfg)
+    };
+    // Stop the motor from a monitor thread on a press, rather than at the
+    // loop's next step; the loop still sees the press and aborts.
+    let (estop_check, _estop_monitor) = match (estop_check, motor.stop_latch()) {
+        (Some(check), Some(latch)) => {
+            let check: doser_core::estop::EstopCheck = std::sync::Arc::from(check);
+            let monitor = doser_core::EstopMonitor::spawn(
+                std::sync::Arc::clone(&check),
+                latch,
+                std::time::Duration::from_millis(_cfg.estop.poll_ms),
+                _cfg.estop.debounce_n,
+            );
+            let check: Box<dyn Fn() -> bool + Send + Sync> = Box::new(move || check());
+            (Some(check), Some(monitor))
+        }
+        (check, _) => (check, None),
+    };
+    // Virtual time needs the single-threaded direct loop (see `RunClock`).
+    let clock = sim_clock.then(doser_traits::clock::VirtualClock::new);
+    let sampling_mode = if direct || sim_clock {
+        SamplingMode::Direct
+    } else {
+        #[cfg(all(any(feature = "hardware", feature = "gpiod"), target_os = "linux"))]
+        {
+            SamplingMode::Event
+        }
+        #[cfg(not(all(any(feature = "hardware", feature = "gpiod"), target_os = "linux")))]
+        {
+            SamplingMode::Paced(_cfg.filter.sample_rate_hz)
+        }
+    };
+    let prefer_timeout_first = max_run_ms_override.is_none();
+
+    // Map predictor config
+    let predictor_core: doser_core::PredictorCfg = (&_cfg.predictor).into();
+    let overrun = doser_core::conversions::overrun_policy(_cfg.runner.overrun);
+    let safe_state = build_safe_state(_cfg);
+    let preflight: doser_core::PreflightCfg = (&_cfg.preflight).into();
+    let warmup: doser_core::WarmupCfg = (&_cfg.warmup).into();
+
+    #[inline]
+    fn record_sample(
+        latencies: &mut Vec<u64>,
+        missed_deadlines: &mut usize,
+        period_us: u64,
+        t_start: std::time::Instant,
+    ) {
+        let latency = t_start.elapsed().as_micros() as u64;
+        latencies.push(latency);
+        if latency > period_us {
+            *missed_deadlines = missed_deadlines.saturating_add(1);
+        }
+    }
+
+    // The core runner gates itself; the stats loops below run the same checks.
+    let collect = stats != Stats::Off;
+    let resources = procinfo::Snapshot::take();
+    if collect {
+        doser_core::warmup::run(
+            &mut scale,
+            calibration_core.as_ref(),
+            &warmup,
+            std::time::Duration::from_millis(timeouts.sensor_ms),
+            &progress,
+        )?;
+        doser_core::preflight::check(
+            &mut scale,
+            &mut motor,
+            estop_check.as_deref().map(|f| f as &dyn Fn() -> bool),
+            calibration_core.as_ref(),
+            &preflight,
+            std::time::Duration::from_millis(timeouts.sensor_ms),
+        )?;
+    }
+
+    // Stats collection for direct mode
+    if matches!(sampling_mode, SamplingMode::Direct) && collect {
+        // Direct mode: wrap control loop manually
+        let estop_check_core: Option<Box<dyn Fn() -> bool>> =
+            estop_check.map(|f| -> Box<dyn Fn() -> bool> { Box::new(f) });
+        let mut doser = doser_core::build_doser(
+            scale,
+            motor,
+            filter.clone(),
+            control.clone(),
+            safety.clone(),
+            timeouts.clone(),
+            calibration_core.clone(),
+            grams,
+            estop_check_core,
+            Some(predictor_core.clone()),
+            clock
+                .clone()
+                .map(|c| -> Box<dyn doser_traits::clock::Clock + Send + Sync> { Box::new(c) }),
+            Some(_cfg.estop.debounce_n),
+        )?;
+        doser.set_overrun_policy(overrun);
+        doser.set_safe_state(safe_state);
+        doser.set_read_retry(read_retry);
+        doser.begin();
+        tracing::info!(target_g = grams, mode = "direct", "dose start");
+        // Compute expected period only when collecting stats
+        let period_us = doser_core::util::period_us(_cfg.filter.sample_rate_hz);
+        loop {
+            // Check for shutdown signal
+            if handle.stop_requested() {
+                let _ = doser.motor_halt();
+                let err =
+                    doser_core::error::DoserError::Abort(doser_core::error::AbortReason::Estop);
+                doser.enter_safe_state(&err);
+                return Err(err.into());
+            }
+
+            let t_start = std::time::Instant::now();
+            let status = doser.step()?;
+            handle.status.publish(&doser);
+            record_sample(&mut latencies, &mut missed_deadlines, period_us, t_start);
+            sample_count += 1;
+            match status {
+                doser_core::DosingStatus::Running => continue,
+                doser_core::DosingStatus::Complete => {
+                    let final_g = doser.last_weight();
+                    tracing::info!(final_g, "dose complete");
+                    if stats == Stats::Print && !latencies.is_empty() {
+                        print_stats(
+                            &latencies,
+                            sample_count,
+                            missed_deadlines,
+                            _cfg.filter.sample_rate_hz,
+                            doser.loop_overruns(),
+                            overrun,
+                            doser.phase_timings(),
+                            doser.motor_diagnostics(),
+                            &resources.usage_since(),
+                        );
+                    }
+                    let tel = JsonTelemetry {
+                        hold_g: doser.hold_weight(),
+                        display_g: Some(doser.display_weight()),
+                        sigma_g: Some(doser.current_weight().sigma_g),
+                        slope_ema_gps: doser.last_slope_ema_gps(),
+                        stop_at_g: doser.early_stop_at_g(),
+                        coast_comp_g: doser.last_inflight_g(),
+                        undershoot_g: doser.undershoot_g(),
+                        phases: Some(doser.phase_timings()),
+                        read_retries: doser.read_retries(),
+                        reads_recovered: doser.reads_recovered(),
+                        jitter_us: Some(latency_stdev_us(&latencies) as f32),
+                        motor_steps: Some(doser.motor_steps()),
+                        coast_g: doser.coast_g(),
+                        attempts: doser.attempt_count(),
+                    };
+                    finished.state = RunState::Complete;
+                    return Ok((final_g, tel));
+                }
+                doser_core::DosingStatus::Aborted(e) => {
+                    let _ = doser.motor_stop();
+                    tracing::error!(error = %e, "dose aborted");
+                    return Err(doser.abort_report(e));
+                }
+            }
+        }
+    } else if collect {
+        // Sampler mode: wrap control loop manually
+        let period_us = doser_core::util::period_us(_cfg.filter.sample_rate_hz);
+        let sampler_timeout = std::time::Duration::from_millis(timeouts.sensor_ms);
+        let sampler = match sampling_mode {
+            SamplingMode::Event => doser_core::sampler::Sampler::spawn_event(
+                scale,
+                sampler_timeout,
+                doser_traits::clock::MonotonicClock::new(),
+            ),
+            SamplingMode::Paced(hz) => doser_core::sampler::Sampler::spawn(
+                scale,
+                hz,
+                sampler_timeout,
+                doser_traits::clock::MonotonicClock::new(),
+            ),
+            SamplingMode::Direct => unreachable!(),
+        };
+        let estop_check_core: Option<Box<dyn Fn() -> bool>> =
+            estop_check.map(|f| -> Box<dyn Fn() -> bool> { Box::new(f) });
+        // Use shared NoopScale for sampler-driven mode
+        use doser_core::mocks::NoopScale;
+        let mut doser = doser_core::build_doser(
+            NoopScale,
+            motor,
+            filter.clone(),
+            control.clone(),
+            safety.clone(),
+            timeouts.clone(),
+            calibration_core.clone(),
+            grams,
+            estop_check_core,
+            Some(predictor_core.clone()),
+            None,
+            Some(_cfg.estop.debounce_n),
+        )?;
+        doser.set_overrun_policy(overrun);
+        doser.set_safe_state(safe_state);
+        // Control ticks between samples (`[control] loop_hz`).
+        let hold_ticks = control.loop_hz > _cfg.filter.sample_rate_hz;
+        if hold_ticks {
+            doser.set_loop_hz(control.loop_hz);
+        }
+        doser.begin();
+        tracing::info!(target_g = grams, mode = "sampler", "dose start");
+        loop {
+            // Check for shutdown signal
+            if handle.stop_requested() {
+                let _ = doser.motor_halt();
+                let err =
+                    doser_core::error::DoserError::Abort(doser_core::error::AbortReason::Estop);
+                doser.enter_safe_state(&err);
+                return Err(err.into());
+            }
+
+            // Out-of-band E-stop poll (decoupled from sample arrival).
+            if doser.poll_estop_stop() {
+                return Err(doser_core::error::DoserError::Abort(
+                    doser_core::error::AbortReason::Estop,
+                )
+                .into());
+            }
+            let t_start = std::time::Instant::now();
+            let status = if let Some(raw) = sampler.latest() {
+                sample_count += 1;
+                let status = doser.step_from_raw(raw)?;
+                handle.status.publish(&doser);
+                record_sample(&mut latencies, &mut missed_deadlines, period_us, t_start);
+                status
+            } else if hold_ticks {
+                let status = doser.step_hold()?;
+                handle.status.publish(&doser);
+                status
+            } else {
+                std::thread::sleep(std::time::Duration::from_micros(period_us));
+                continue;
+            };
+            match status {
+                doser_core::DosingStatus::Running => continue,
+                doser_core::DosingStatus::Complete => {
+                    let final_g = doser.last_weight();
+                    tracing::info!(final_g, "dose complete");
+                    if stats == Stats::Print && !latencies.is_empty() {
+                        print_stats(
+                            &latencies,
+                            sample_count,
+                            missed_deadlines,
+                            _cfg.filter.sample_rate_hz,
+                            doser.loop_overruns(),
+                            overrun,
+                            doser.phase_timings(),
+                            doser.motor_diagnostics(),
+                            &resources.usage_since(),
+                        );
+                    }
+                    let tel = JsonTelemetry {
+                        hold_g: doser.hold_weight(),
+                        display_g: Some(doser.display_weight()),
+                        sigma_g: Some(doser.current_weight().sigma_g),
+                        slope_ema_gps: doser.last_slope_ema_gps(),
+                        stop_at_g: doser.early_stop_at_g(),
+                        coast_comp_g: doser.last_inflight_g(),
+                        undershoot_g: doser.undershoot_g(),
+                        phases: Some(doser.phase_timings()),
+                        jitter_us: Some(latency_stdev_us(&latencies) as f32),
+                        motor_steps: Some(doser.motor_steps()),
+                        coast_g: doser.coast_g(),
+                        attempts: doser.attempt_count(),
+                        ..JsonTelemetry::default()
+                    };
+                    finished.state = RunState::Complete;
+                    return Ok((final_g, tel));
+                }
+                doser_core::DosingStatus::Aborted(e) => {
+                    let _ = doser.motor_stop();
+                    tracing::error!(error = %e, "dose aborted");
+                    return Err(doser.abort_report(e));
+                }
+            }
+        }
+    } else {
+        // No stats: use core runner
+        let report = doser_core::runner::run_report(
+            scale,
+            motor,
+            estop_check,
+            RunParams {
+                filter,
+                control,
+                safety,
+                timeouts,
+                read_retry,
+                calibration: calibration_core,
+                target_g: grams,
+                estop_debounce_n: _cfg.estop.debounce_n,
+                prefer_timeout_first,
+                mode: sampling_mode,
+                predictor: Some(predictor_core),
+                overrun,
+                safe_state,
+                warmup,
+                progress,
+                preflight,
+                status: handle.status.clone(),
+                shutdown: Some(handle.shutdown_flag()),
+                clock: clock.map(RunClock::new).unwrap_or_default(),
+            },
+        )?;
+        let tel = JsonTelemetry {
+            hold_g: report.hold_g,
+            display_g: Some(report.display_g),
+            sigma_g: Some(report.sigma_g),
+            slope_ema_gps: report.slope_ema_gps,
+            stop_at_g: report.stop_at_g,
+            coast_comp_g: report.coast_comp_g,
+            undershoot_g: report.undershoot_g,
+            phases: Some(report.phases),
+            read_retries: report.read_retries,
+            reads_recovered: report.reads_recovered,
+            jitter_us: None,
+            motor_steps: Some(report.motor_steps),
+            coast_g: report.coast_g,
+            attempts: report.attempts,
+        };
+        finished.state = RunState::Complete;
+        return Ok((report.final_g, tel));
+    }
+    // Unreachable
+    #[allow(unreachable_code)]
+    Ok((0.0, JsonTelemetry::default()))
+}
+
+/// Run the controller in shadow mode against `scale` until both it and the
+/// external process have stopped (or `max_run_ms` elapses).
+pub fn run_shadow(
+    cfg: &doser_config::Config,
+    calib: Option<&Calibration>,
+    grams: f32,
+    max_run_ms: Option<u64>,
+    flow_threshold_gps: f32,
+    scale: impl doser_traits::Scale + 'static,
+    shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>,
+) -> CoreResult<doser_core::shadow::ShadowReport> {
+    use doser_core::shadow::{ShadowMonitor, ShadowMotor};
+    use doser_traits::clock::{Clock, MonotonicClock};
+
+    let control: doser_core::ControlCfg = (&cfg.control).into();
+    let mut safety: doser_core::SafetyCfg = (&cfg.safety).into();
+    let defaults = doser_core::SafetyCfg::default();
+    safety.max_run_ms = max_run_ms.unwrap_or(if safety.max_run_ms == 0 {
+        defaults.max_run_ms
+    } else {
+        safety.max_run_ms
+    });
+    if safety.max_overshoot_g == 0.0 {
+        safety.max_overshoot_g = defaults.max_overshoot_g;
+    }
+    // Stop comparing once the line has been quiet as long as a settle takes.
+    let quiet_ms = control.stable_ms.max(200);
+    let (motor, probe) = ShadowMotor::new();
+    let mut doser = doser_core::build_doser(
+        scale,
+        motor,
+        (&cfg.filter).into(),
+        control,
+        safety,
+        (&cfg.timeouts).into(),
+        calib.map(doser_core::Calibration::from),
+        grams,
+        None,
+        Some((&cfg.predictor).into()),
+        None,
+        None,
+    )?;
+    let mut monitor = ShadowMonitor::new(flow_threshold_gps);
+    let clock = MonotonicClock::new();
+    let epoch = clock.now();
+    doser.begin();
+    tracing::info!(target_g = grams, "shadow start (motor is not driven)");
+    loop {
+        let status = doser.step()?;
+        let now = clock.ms_since(epoch);
+        monitor.observe(now, doser.last_weight(), probe.running());
+        if let doser_core::DosingStatus::Aborted(e) = &status {
+            tracing::warn!(error = %e, "shadow: controller would have aborted");
+        }
+        if !matches!(status, doser_core::DosingStatus::Running)
+            || monitor.settled(now, quiet_ms)
+            || shutdown.load(std::sync::atomic::Ordering::Relaxed)
+        {
+            return Ok(monitor.finish(now));
+        }
+    }
+}
+
+/// Records how `run_dose` ended on its handle, whichever way it returns.
+struct Finish<'a> {
+    status: &'a StatusBoard,
+    state: RunState,
+}
+
+impl Drop for Finish<'_> {
+    fn drop(&mut self) {
+        self.status.finish(self.state);
+    }
+}
+
+/// The E-stop input on the configured GPIO pin, if any.
+fn gpio_estop(cfg: &doser_config::Config) -> Option<Box<dyn Fn() -> bool + Send + Sync>> {
+    #[cfg(all(feature = "hardware", target_os = "linux"))]
+    {
+        if let Some(pin) = cfg.pins.estop_in {
+            match doser_hardware::make_estop_checker(pin, cfg.estop.active_low, cfg.estop.poll_ms) {
+                Ok(c) => {
+                    tracing::info!(
+                        pin,
+                        active_low = cfg.estop.active_low,
+                        poll_ms = cfg.estop.poll_ms,
+                        "E-stop enabled"
+                    );
+                    Some(c)
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "failed to init E-stop; continuing without it");
+                    None
+                }
+            }
+        } else {
+            None
+        }
+    }
+    #[cfg(all(feature = "gpiod", not(feature = "hardware"), target_os = "linux"))]
+    {
+        if let Some(pin) = cfg.pins.estop_in {
+            let chip = cfg.pins.chip.as_deref().unwrap_or("/dev/gpiochip0");
+            match doser_hardware::gpiod::make_estop_checker(
+                chip,
+                pin,
+                cfg.estop.active_low,
+                cfg.estop.poll_ms,
+            ) {
+                Ok(c) => {
+                    tracing::info!(chip, pin, "E-stop enabled");
+                    Some(c)
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "failed to init E-stop; continuing without it");
+                    None
+                }
+            }
+        } else {
+            None
+        }
+    }
+    #[cfg(not(all(any(feature = "hardware", feature = "gpiod"), target_os = "linux")))]
+    {
+        let _ = cfg;
+        sim_estop()
+    }
+}
+
+/// The simulated E-stop (`DOSER_TEST_SIM_ESTOP_*`), if enabled.
+fn sim_estop() -> Option<Box<dyn Fn() -> bool + Send + Sync>> {
+    let c = doser_hardware::sim_estop_from_env();
+    if c.is_some() {
+        tracing::info!("simulated E-stop enabled");
+    }
+    c
+}
+
+/// Assemble the abort safe-state sequence and the actuators it may drive.
+fn build_safe_state(cfg: &doser_config::Config) -> doser_core::SafeState {
+    let mut safe_state = doser_core::SafeState::new((&cfg.safety.on_abort).into());
+    #[cfg(all(any(feature = "hardware", feature = "gpiod"), target_os = "linux"))]
+    let mut expanders = std::collections::BTreeMap::new();
+    for (name, a) in &cfg.actuators {
+        let sim: doser_core::SharedActuator = std::sync::Arc::new(std::sync::Mutex::new(
+            doser_hardware::SimulatedActuator::new(),
+        ));
+        #[cfg(all(any(feature = "hardware", feature = "gpiod"), target_os = "linux"))]
+        let actuator = if crate::hw::is_sim(cfg) {
+            sim
+        } else {
+            match open_actuator(cfg, a, &mut expanders) {
+                Ok(h) => h,
+                Err(e) => {
+                    tracing::warn!(error = %e, actuator = %name, "failed to init actuator; skipping");
+                    continue;
+                }
+            }
+        };
+        #[cfg(not(all(any(feature = "hardware", feature = "gpiod"), target_os = "linux")))]
+        let actuator = {
+            let _ = a;
+            sim
+        };
+        safe_state = safe_state.with_actuator(name.clone(), actuator);
+    }
+    safe_state
+}
+
+/// Open one actuator on a header GPIO or an I2C expander; each expander is
+/// opened once and shared by all its outputs.
+#[cfg(all(any(feature = "hardware", feature = "gpiod"), target_os = "linux"))]
+fn open_actuator(
+    cfg: &doser_config::Config,
+    a: &doser_config::ActuatorCfg,
+    expanders: &mut std::collections::BTreeMap<String, doser_hardware::SharedExpander>,
+) -> doser_hardware::error::Result<doser_core::SharedActuator> {
+    use doser_config::OutputPin;
+    use std::sync::{Arc, Mutex};
+    match &a.pin {
+        #[cfg(feature = "hardware")]
+        OutputPin::Gpio(pin) => {
+            let _ = cfg;
+            let h = doser_hardware::HardwareActuator::try_new(*pin, a.active_low)?;
+            Ok(Arc::new(Mutex::new(h)))
+        }
+        #[cfg(not(feature = "hardware"))]
+        OutputPin::Gpio(pin) => {
+            let chip = cfg.pins.chip.as_deref().unwrap_or("/dev/gpiochip0");
+            let h = doser_hardware::GpiodActuator::try_new(chip, *pin, a.active_low)?;
+            Ok(Arc::new(Mutex::new(h)))
+        }
+        OutputPin::Expander(p) => {
+            let dev = match expanders.get(&p.expander) {
+                Some(dev) => dev.clone(),
+                None => {
+                    // Validation guarantees the expander is declared.
+                    let e = cfg.expanders.get(&p.expander).ok_or_else(|| {
+                        doser_hardware::error::HwError::Gpio(format!(
+                            "unknown expander {:?}",
+                            p.expander
+                        ))
+                    })?;
+                    let dev = doser_hardware::mcp23017::open(e.bus, e.address)?;
+                    expanders.insert(p.expander.clone(), dev.clone());
+                    dev
+                }
+            };
+            let h = doser_hardware::ExpanderActuator::try_new(dev, p.pin, a.active_low)?;
+            Ok(Arc::new(Mutex::new(h)))
+        }
+    }
+}
+
+/// Sample standard deviation of the step latencies (the loop jitter), in us.
+fn latency_stdev_us(latencies: &[u64]) -> f64 {
+    if latencies.len() < 2 {
+        return 0.0;
+    }
+    let mean = latencies.iter().sum::<u64>() as f64 / latencies.len() as f64;
+    let var = latencies
+        .iter()
+        .map(|&x| (x as f64 - mean).powi(2))
+        .sum::<f64>()
+        / (latencies.len() as f64 - 1.0);
+    var.sqrt()
+}
+
+/// Print latency/jitter and resource stats to stderr.
+#[allow(clippy::too_many_arguments)]
+fn print_stats(
+    latencies: &[u64],
+    sample_count: usize,
+    missed_deadlines: usize,
+    sample_rate_hz: u32,
+    loop_overruns: u64,
+    overrun: doser_core::OverrunPolicy,
+    phases: doser_core::PhaseTimings,
+    motor: Option<doser_traits::MotorDiagSnapshot>,
+    usage: &procinfo::Usage,
+) {
+    let expected_period_us = doser_core::util::period_us(sample_rate_hz);
+    let min = *latencies.iter().min().unwrap_or(&0);
+    let max = *latencies.iter().max().unwrap_or(&0);
+    let avg = latencies.iter().sum::<u64>() as f64 / latencies.len() as f64;
+    let stdev = latency_stdev_us(latencies);
+    eprintln!("\n--- Doser Stats ---");
+    eprintln!("Samples: {sample_count}");
+    eprintln!("Period (us): {expected_period_us}");
+    eprintln!("Latency min/avg/max/stdev (us): {min:.0} / {avg:.1} / {max:.0} / {stdev:.1}");
+    eprintln!("Missed deadlines (> period): {missed_deadlines}");
+    eprintln!("Loop overruns: {loop_overruns} (policy: {overrun:?})");
+    eprintln!(
+        "Phases coarse/fine/settle/verify (ms): {} / {} / {} / {}",
+        phases.coarse_ms, phases.fine_ms, phases.settle_ms, phases.verify_ms
+    );
+    match motor {
+        Some(m) => eprintln!(
+            "Motor steps / jitter avg/max (us) / missed periods: {} / {} / {} / {}",
+            m.steps_issued, m.avg_jitter_us, m.max_jitter_us, m.missed_periods
+        ),
+        None => eprintln!("Motor diagnostics: n/a"),
+    }
+    match procinfo::peak_rss_kb() {
+        Some(kb) => eprintln!("Peak RSS: {kb} kB"),
+        None => eprintln!("Peak RSS: n/a"),
+    }
+    let cpu = |t: Option<std::time::Duration>| match t {
+        Some(t) => format!("{:.1} ms ({:.1}%)", t.as_secs_f64() * 1e3, usage.percent(t)),
+        None => "n/a".to_string(),
+    };
+    eprintln!(
+        "CPU process / control thread: {} / {} over {} ms",
+        cpu(usage.process_cpu),
+        cpu(usage.thread_cpu),
+        usage.wall.as_millis()
+    );
+    match usage.allocs {
+        Some((n, bytes)) => eprintln!("Allocations: {n} ({bytes} bytes)"),
+        None => eprintln!("Allocations: n/a (build with --features alloc-stats)"),
+    }
+    eprintln!("-------------------\n");
+}
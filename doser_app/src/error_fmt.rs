@@ -0,0 +1,241 @@
+//! Human-readable error descriptions and structured JSON error formatting.
+
+use crate::RunSafety;
+use crate::dose::abort_reason_name;
+use doser_core::Remediation;
+
+/// Map an eyre::Report to a human-readable explanation with likely causes and fix hints.
+pub fn humanize(err: &eyre::Report) -> String {
+    use doser_core::error::{BuildError, DoserError, PreflightError, PreflightFailure};
+
+    // Typed matches first
+    if let Some(be) = err.downcast_ref::<BuildError>() {
+        return match be {
+            BuildError::MissingScale => {
+                "What happened: No scale was provided to the dosing engine.\nLikely causes: Hardware scale failed to initialize or was not wired into the builder.\nHow to fix: Ensure the HX711 scale is created successfully and passed via with_scale(...).".to_string()
+            }
+            BuildError::MissingMotor => {
+                "What happened: No motor was provided to the dosing engine.\nLikely causes: Motor driver failed to initialize or was not wired into the builder.\nHow to fix: Ensure the motor is created successfully and passed via with_motor(...).".to_string()
+            }
+            BuildError::MissingTarget => {
+                "What happened: Target grams not set.\nLikely causes: The CLI did not pass --grams or the builder was not configured.\nHow to fix: Provide the desired grams (e.g., `doser dose --grams 10`).".to_string()
+            }
+            BuildError::InvalidConfig(msg) => format!(
+                "What happened: Invalid configuration ({msg}).\nLikely causes: Missing or out-of-range values in the TOML.\nHow to fix: Edit the config file, then rerun. See README for a sample."
+            ),
+        };
+    }
+
+    if let Some(pe) = err.downcast_ref::<PreflightError>() {
+        if pe.failures.contains(&PreflightFailure::ScaleTimeout) {
+            return "What happened: Scale read timed out during pre-flight; the motor was not started.\nLikely causes: HX711 not wired correctly, no power/ground, or timeout too low.\nHow to fix: Verify DT/SCK pins and power, and consider increasing timeouts.sample_ms in the config.".to_string();
+        }
+        if pe
+            .failures
+            .contains(&PreflightFailure::ScaleDataReadyTimeout)
+        {
+            return "What happened: The HX711 never signalled data-ready during pre-flight; the motor was not started.\nLikely causes: DT not wired, no power/ground, or a data-ready timeout shorter than one conversion (100 ms at 10 SPS).\nHow to fix: Verify DT/SCK pins and power, and consider increasing hardware.sensor_read_timeout_ms in the config.".to_string();
+        }
+        if let Some(low @ PreflightFailure::HopperLow { .. }) = pe
+            .failures
+            .iter()
+            .find(|f| matches!(f, PreflightFailure::HopperLow { .. }))
+        {
+            return format!(
+                "What happened: Not enough material for this dose ({low}); the motor was not started.\nHow to fix: Refill the hopper, or check [hopper] calibration (gain_g_per_count, zero_counts) and reserve_g."
+            );
+        }
+        if let Some(units) = pe
+            .failures
+            .iter()
+            .find(|f| matches!(f, PreflightFailure::UnitsMismatch { .. }))
+        {
+            return format!(
+                "What happened: The empty scale does not read its known weight ({units}); the motor was not started.\nHow to fix: Check that --calibration (or [calibration]) belongs to this scale, that the container is on it and empty, and [preflight] container_g."
+            );
+        }
+        let list: String = pe.failures.iter().map(|f| format!("\n  - {f}")).collect();
+        return format!(
+            "What happened: Pre-flight checks failed; the motor was not started.{list}\nHow to fix: Release the E-stop, check the load cell wiring and that nothing is moving on the scale, or tune [preflight] in the config."
+        );
+    }
+
+    if let Some(de) = err.downcast_ref::<DoserError>() {
+        // Specific domain cases first
+        if matches!(de, DoserError::Timeout) {
+            return "What happened: Scale read timed out.\nLikely causes: HX711 not wired correctly, no power/ground, or timeout too low.\nHow to fix: Verify DT/SCK pins and power, and consider increasing timeouts.sample_ms in the config.".to_string();
+        }
+        if matches!(de, DoserError::DataReadyTimeout) {
+            return "What happened: The HX711 never signalled data-ready.\nLikely causes: DT not wired, no power/ground, or a data-ready timeout shorter than one conversion (100 ms at 10 SPS).\nHow to fix: Verify DT/SCK pins and power, and consider increasing hardware.sensor_read_timeout_ms in the config.".to_string();
+        }
+        if let DoserError::Abort(reason) = de {
+            use doser_core::error::AbortReason::*;
+            return match reason {
+                Estop => "What happened: Emergency stop was triggered.\nLikely causes: E-stop button pressed or input pin active.\nHow to fix: Release E-stop, ensure wiring is correct, then start a new run.".to_string(),
+                NoProgress => "What happened: No progress watchdog tripped.\nLikely causes: Jammed auger, empty hopper, or scale not changing within threshold.\nHow to fix: Check mechanics and materials; adjust safety.no_progress_* in config if needed.".to_string(),
+                MaxRuntime => "max run time was exceeded.\nLikely causes: Too conservative speeds, high target, or stalls.\nHow to fix: Increase safety.max_run_ms or adjust speeds/target.".to_string(),
+                Overshoot => "What happened: Overshoot beyond safety limit.\nLikely causes: Inertia or too high coarse/fine speed near target.\nHow to fix: Lower speeds or increase safety.max_overshoot_g and tune epsilon/slow_at.".to_string(),
+                MaxAttempts => "What happened: The dose used every approach control.max_attempts allows and was still short.\nLikely causes: The predictor or epsilon stops the motor too early, or the weight drifts back out of the settle zone.\nHow to fix: Lower predictor.extra_latency_ms or control.epsilon_g, or raise control.max_attempts.".to_string(),
+                Undershoot => "What happened: The predictor stopped the motor early and the weight settled below target.\nLikely causes: predictor.extra_latency_ms too high, or a noisy slope estimate.\nHow to fix: Lower predictor.extra_latency_ms, try predictor.model = \"decel\", or set predictor.undershoot = \"top-up\".".to_string(),
+            };
+        }
+        // Fallback to generic for other domain errors
+        return format!(
+            "What happened: {de}.\nLikely causes: See logs.\nHow to fix: Re-run with --log-level=debug or set RUST_LOG for more detail."
+        );
+    }
+
+    if let Some(doser_hardware::LockError::Busy(b)) =
+        err.downcast_ref::<doser_hardware::LockError>()
+    {
+        return format!(
+            "What happened: Another dose is running on this hardware ({b}); the motor was not started.\nHow to fix: Wait for it to finish, or stop that process. The lock is released when it exits."
+        );
+    }
+    if let Some(b) = busy(err) {
+        return format!(
+            "What happened: Another process has the GPIO hardware open ({b}); nothing was started.\nLikely causes: A second doser instance (CLI or service) is running.\nHow to fix: Stop that process or wait for it to exit; the lock is released when it does."
+        );
+    }
+
+    // String-based heuristics for errors coming from init or config
+    let msg = err.to_string();
+    let lower = msg.to_ascii_lowercase();
+
+    if (lower.contains("hx711") && lower.contains("timeout")) || lower.contains("datareadytimeout")
+    {
+        return "What happened: HX711 did not produce data within the configured timeout.\nLikely causes: Wrong DT/SCK pins, wiring/power issues, or timeout configured too low.\nHow to fix: Check [pins] in the config, verify 5V/GND, and raise hardware.sensor_read_timeout_ms.".to_string();
+    }
+
+    if lower.contains("open hx711") || lower.contains("open motor pins") {
+        return "What happened: Failed to initialize hardware pins.\nLikely causes: Incorrect pin numbers or insufficient GPIO permissions.\nHow to fix: Fix the [pins] values in the config; ensure the process has permission to access GPIO.".to_string();
+    }
+
+    if lower.contains("invalid configuration")
+        || (lower.contains("pin") && lower.contains("missing"))
+    {
+        return "What happened: Configuration is invalid or incomplete.\nLikely causes: Missing [pins] (hx711_dt, hx711_sck, motor_step, motor_dir, ...), or out-of-range values.\nHow to fix: Edit the TOML config and try again.".to_string();
+    }
+
+    // Calibration CSV header special-case
+    if lower.contains("calibration csv must have headers") {
+        return "Invalid headers in calibration CSV. Expected 'raw,grams'.".to_string();
+    }
+
+    // Generic fallback
+    let mut cause = String::new();
+    if let Some(src) = err.source() {
+        cause = format!(" Cause: {src}");
+    }
+    format!(
+        "Something went wrong.{cause}\nHow to fix: Re-run with --log-level=debug for details. Original: {msg}"
+    )
+}
+
+/// Map AbortReason (if present) to stable exit codes; non-abort errors return 2.
+pub fn exit_code_for_error(err: &eyre::Report) -> i32 {
+    use doser_core::error::DoserError;
+    if let Some(DoserError::Abort(reason)) = err.downcast_ref::<DoserError>() {
+        return match reason {
+            doser_core::error::AbortReason::Estop => 2,
+            doser_core::error::AbortReason::NoProgress => 3,
+            doser_core::error::AbortReason::MaxRuntime => 4,
+            doser_core::error::AbortReason::Overshoot => 5,
+            doser_core::error::AbortReason::MaxAttempts => 6,
+            doser_core::error::AbortReason::Undershoot => 7,
+        };
+    }
+    1
+}
+
+/// Structured JSON for errors when --json is enabled.
+pub fn format_error_json(err: &eyre::Report) -> String {
+    use doser_core::error::DoserError;
+    use serde_json::json;
+
+    if let Some(DoserError::Abort(reason)) = err.downcast_ref::<DoserError>() {
+        let msg = humanize(err);
+        let details = err.downcast_ref::<RunSafety>();
+        let reason_name = abort_reason_name(reason);
+
+        let detail_obj = match reason {
+            doser_core::error::AbortReason::Overshoot => {
+                details.map(|s| json!({ "max_overshoot_g": s.max_overshoot_g }))
+            }
+            doser_core::error::AbortReason::MaxRuntime => {
+                details.map(|s| json!({ "max_run_ms": s.max_run_ms }))
+            }
+            doser_core::error::AbortReason::NoProgress => details.map(|s| {
+                json!({ "no_progress_ms": s.no_progress_ms, "no_progress_epsilon_g": s.no_progress_epsilon_g })
+            }),
+            _ => None,
+        };
+
+        let code = remediation(err).map(|r| r.code());
+        let obj = if let Some(d) = detail_obj {
+            json!({ "reason": reason_name, "remediation": code, "details": d, "message": msg })
+        } else {
+            json!({ "reason": reason_name, "remediation": code, "message": msg })
+        };
+        return obj.to_string();
+    }
+
+    let code = remediation(err).map(|r| r.code());
+    if let Some(pe) = err.downcast_ref::<doser_core::error::PreflightError>() {
+        let failures: Vec<String> = pe.failures.iter().map(ToString::to_string).collect();
+        return json!({ "reason": "Preflight", "remediation": code, "failures": failures, "message": humanize(err) })
+            .to_string();
+    }
+
+    if let Some(b) = busy(err) {
+        return json!({ "reason": "Busy", "remediation": code, "pid": b.pid, "lock_file": b.path, "message": humanize(err) })
+            .to_string();
+    }
+
+    // Generic error JSON
+    json!({ "reason": "Error", "remediation": code, "message": humanize(err) }).to_string()
+}
+
+/// Remediation code for `err`: attached by the controller on an abort, else
+/// derived from the error type. `None` when nothing more specific is known.
+pub fn remediation(err: &eyre::Report) -> Option<Remediation> {
+    use doser_core::error::{BuildError, DoserError, PreflightError};
+    if let Some(r) = err.downcast_ref::<Remediation>() {
+        return Some(*r);
+    }
+    if let Some(de) = err.downcast_ref::<DoserError>() {
+        return match de {
+            DoserError::Abort(reason) => Some(Remediation::for_abort(reason)),
+            DoserError::Timeout | DoserError::DataReadyTimeout => Some(Remediation::Sensor),
+            DoserError::Config(_) => Some(Remediation::Config),
+            _ => None,
+        };
+    }
+    if let Some(pe) = err.downcast_ref::<PreflightError>() {
+        // The first failure is the one to fix first (checks run in order).
+        return pe.failures.first().map(Remediation::for_preflight);
+    }
+    if busy(err).is_some() {
+        return Some(Remediation::Busy);
+    }
+    if matches!(
+        err.downcast_ref::<BuildError>(),
+        Some(BuildError::InvalidConfig(_))
+    ) || err.to_string().starts_with("invalid configuration")
+    {
+        return Some(Remediation::Config);
+    }
+    None
+}
+
+/// The lock held by another process behind `err`: the run lock or a GPIO chip lock.
+fn busy(err: &eyre::Report) -> Option<&doser_hardware::BusyError> {
+    use doser_hardware::{LockError, error::HwError};
+    if let Some(LockError::Busy(b)) = err.downcast_ref::<LockError>() {
+        return Some(b);
+    }
+    match err.downcast_ref::<HwError>() {
+        Some(HwError::Busy(b)) => Some(b),
+        _ => None,
+    }
+}
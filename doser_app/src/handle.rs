@@ -0,0 +1,118 @@
+//! [`DoserHandle`]: what a supervisor (a GUI, a service) keeps while a dose
+//! runs on another thread — poll its status, or ask it to stop.
+//!
+//! ```no_run
+//! # fn main() -> eyre::Result<()> {
+//! # let cfg = doser_config::load_toml(&std::fs::read_to_string("doser.toml")?)?;
+//! let handle = doser_app::DoserHandle::new();
+//! let h = handle.clone();
+//! let run = std::thread::spawn(move || -> eyre::Result<f32> {
+//!     let (scale, motor) = doser_app::hw::open_hw(&cfg)?;
+//!     let (grams, _) = doser_app::dose::run_dose(
+//!         &cfg, None, 10.0, (scale, motor), Default::default(), &h,
+//!     )?;
+//!     Ok(grams)
+//! });
+//! while !run.is_finished() {
+//!     println!("{}", doser_app::handle::status_json(&handle.poll_status()));
+//!     std::thread::sleep(std::time::Duration::from_millis(250));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use doser_core::{RunStatus, StatusBoard};
+use doser_traits::StopLatch;
+use serde_json::json;
+
+/// Shared status and stop flag of a run. Clones refer to the same run.
+#[derive(Clone, Debug)]
+pub struct DoserHandle {
+    pub(crate) status: StatusBoard,
+    pub(crate) shutdown: Arc<AtomicBool>,
+    /// The running motor's stop latch, for [`Self::halt`].
+    pub(crate) latch: Arc<Mutex<Option<StopLatch>>>,
+}
+
+impl Default for DoserHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DoserHandle {
+    pub fn new() -> Self {
+        Self {
+            status: StatusBoard::new(),
+            shutdown: Arc::default(),
+            latch: Arc::default(),
+        }
+    }
+
+    /// Status as of the latest control step; never blocks the loop.
+    pub fn poll_status(&self) -> RunStatus {
+        self.status.latest()
+    }
+
+    /// Ask the run to stop: the motor is stopped and the dose aborts.
+    pub fn stop(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop the motor now, from any thread, through its stop latch, and ask
+    /// the run to stop. For a process about to exit without unwinding: the
+    /// latch holds the motor stopped even though the run never gets to.
+    pub fn halt(&self) {
+        if let Some(latch) = &*self.latch.lock().unwrap_or_else(PoisonError::into_inner) {
+            latch.trip();
+        }
+        self.stop();
+    }
+
+    /// Keep the running motor's stop latch for [`Self::halt`].
+    pub(crate) fn set_stop_latch(&self, latch: Option<StopLatch>) {
+        *self.latch.lock().unwrap_or_else(PoisonError::into_inner) = latch;
+    }
+
+    pub fn stop_requested(&self) -> bool {
+        self.shutdown.load(Ordering::Relaxed)
+    }
+
+    /// The stop flag, for APIs that take one directly.
+    pub fn shutdown_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.shutdown)
+    }
+}
+
+pub(crate) fn phase_name(phase: doser_core::DosePhase) -> &'static str {
+    match phase {
+        doser_core::DosePhase::Coarse => "coarse",
+        doser_core::DosePhase::Fine => "fine",
+        doser_core::DosePhase::Settle => "settle",
+        doser_core::DosePhase::Verify => "verify",
+    }
+}
+
+/// `status` as a JSON object.
+pub fn status_json(status: &RunStatus) -> serde_json::Value {
+    json!({
+        "state": status.state.name(),
+        "phase": status.phase.map(phase_name),
+        "weight_g": status.weight_g,
+        "target_g": status.target_g,
+        "est_final_g": status.est_final_g,
+        "pct": status.pct,
+        "eta_ms": status.eta_ms,
+        "elapsed_ms": status.elapsed_ms,
+        "motor": status.motor.map(|m| json!({
+            "steps_issued": m.steps_issued,
+            "avg_jitter_us": m.avg_jitter_us,
+            "max_jitter_us": m.max_jitter_us,
+            "missed_periods": m.missed_periods,
+        })),
+        "warnings": status.warnings(),
+    })
+}
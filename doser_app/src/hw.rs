@@ -0,0 +1,259 @@
+//! Hardware assembly: open the configured drivers (built-in registry or a
+//! `[plugin]` library) the way every dosing command does.
+
+use doser_config::Config;
+use doser_hardware::registry::{BoxedMotor, BoxedScale};
+use eyre::WrapErr;
+
+/// Which drivers a command runs on (`--backend`).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Backend {
+    /// The configured drivers; the simulation when this build's GPIO backend
+    /// finds no GPIO chip and the config names no driver
+    #[default]
+    Auto,
+    /// The simulated scale, motor, E-stop and actuators, whatever the config says
+    Sim,
+    /// The configured drivers, failing rather than simulating
+    Hardware,
+}
+
+/// Point `cfg` at the drivers `backend` selects. Run after validation: the
+/// overrides may combine settings validation rejects in a file (e.g. a
+/// driver next to `[plugin]`).
+pub fn select_backend(cfg: &mut Config, backend: Backend) -> eyre::Result<()> {
+    use doser_hardware::registry;
+    match backend {
+        Backend::Sim => force_sim(cfg),
+        Backend::Hardware => {
+            if cfg.plugin.is_none() && registry::DEFAULT_MOTOR == "sim" {
+                eyre::bail!(
+                    "--backend hardware: this build has no GPIO backend (build with --features hardware or gpiod)"
+                );
+            }
+            if is_sim(cfg) {
+                eyre::bail!("--backend hardware: the config selects the sim drivers");
+            }
+        }
+        Backend::Auto => {
+            let chip = cfg.pins.chip.as_deref().unwrap_or("/dev/gpiochip0");
+            if cfg.plugin.is_none()
+                && cfg.scale.driver.is_none()
+                && cfg.motor.driver.is_none()
+                && registry::DEFAULT_MOTOR != "sim"
+                && !std::path::Path::new(chip).exists()
+            {
+                tracing::warn!(chip, "no GPIO chip found; running on the simulated backend");
+                force_sim(cfg);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn force_sim(cfg: &mut Config) {
+    cfg.plugin = None;
+    cfg.scale.driver = Some("sim".into());
+    cfg.motor.driver = Some("sim".into());
+    if let Some(h) = &mut cfg.hopper {
+        h.driver = Some("sim".into());
+    }
+}
+
+/// Whether `cfg` runs on the simulated drivers (so the E-stop and actuators
+/// are simulated too).
+pub fn is_sim(cfg: &Config) -> bool {
+    use doser_hardware::registry;
+    let motor = cfg
+        .motor
+        .driver
+        .as_deref()
+        .unwrap_or(registry::DEFAULT_MOTOR);
+    cfg.plugin.is_none() && motor == "sim"
+}
+
+/// Open the configured scale (with re-init on timeout) and motor.
+pub fn open_hw(
+    cfg: &Config,
+) -> eyre::Result<(doser_hardware::RetryingScale<BoxedScale>, BoxedMotor)> {
+    let (scale, motor) = match &cfg.plugin {
+        Some(p) => open_plugin(p)?,
+        None => open_registered(cfg)?,
+    };
+    Ok((retrying(cfg, scale), motor))
+}
+
+/// Open the configured scale alone (with re-init on timeout), for weighing
+/// without dosing.
+pub fn open_scale(cfg: &Config) -> eyre::Result<doser_hardware::RetryingScale<BoxedScale>> {
+    let scale = match &cfg.plugin {
+        Some(p) => open_plugin_scale(p)?,
+        None => open_registered_scale(cfg)?,
+    };
+    Ok(retrying(cfg, scale))
+}
+
+fn retrying(cfg: &Config, scale: BoxedScale) -> doser_hardware::RetryingScale<BoxedScale> {
+    doser_hardware::RetryingScale::new(
+        scale,
+        cfg.hardware.retry.max_attempts,
+        std::time::Duration::from_millis(cfg.hardware.retry.settle_ms),
+    )
+}
+
+/// `--sim-clock` only makes sense against the simulated drivers: real
+/// hardware keeps real time, whatever the loop's clock says.
+pub fn check_sim_clock(cfg: &Config) -> eyre::Result<()> {
+    use doser_hardware::registry;
+    let scale = cfg
+        .scale
+        .driver
+        .as_deref()
+        .unwrap_or_else(|| registry::default_scale_driver(cfg.scale.composite.is_some()));
+    let motor = cfg
+        .motor
+        .driver
+        .as_deref()
+        .unwrap_or(registry::DEFAULT_MOTOR);
+    if cfg.plugin.is_some() || scale != "sim" || motor != "sim" {
+        eyre::bail!("--sim-clock needs the sim scale and motor drivers");
+    }
+    Ok(())
+}
+
+/// The motor of a dosing command; only weigh-only commands open none.
+pub fn dosing_motor(motor: Option<BoxedMotor>) -> eyre::Result<BoxedMotor> {
+    motor.ok_or_else(|| eyre::eyre!("no motor was opened for this command"))
+}
+
+/// Mean scale reading right after an interrupted dose, through freshly opened
+/// drivers (the dose consumed its own); `None` if the scale still does not answer.
+pub fn pause_counts(cfg: &Config, samples: usize) -> Option<i32> {
+    let mut scale = open_scale(cfg).ok()?;
+    let timeout = std::time::Duration::from_millis(cfg.timeouts.sample_ms);
+    doser_core::resume::read_mean_counts(&mut scale, samples, timeout)
+        .map_err(|e| tracing::debug!(error = %e, "pause weight not captured"))
+        .ok()
+}
+
+/// Driver wiring from the `[pins]`, `[scale]` and `[motor]` config.
+pub fn wiring(cfg: &Config) -> doser_hardware::registry::Wiring {
+    use doser_hardware::registry::{CompositeWiring, Wiring};
+    Wiring {
+        chip: cfg.pins.chip.clone(),
+        hx711_dt: cfg.pins.hx711_dt,
+        hx711_sck: cfg.pins.hx711_sck,
+        sensor_read_timeout_ms: cfg.hardware.sensor_read_timeout_ms,
+        composite: cfg.scale.composite.map(|c| CompositeWiring {
+            dt: c.hx711_dt,
+            sck: c.hx711_sck,
+            tolerance_counts: c.tolerance_counts,
+            fault_after: c.fault_after,
+            weight: c.weight,
+        }),
+        motor_step: cfg.pins.motor_step,
+        motor_dir: cfg.pins.motor_dir,
+        motor_en: cfg.pins.motor_en,
+        polarity: doser_hardware::MotorPolarity {
+            invert_direction: cfg.motor.invert_direction,
+            invert_enable: cfg.motor.invert_enable,
+        },
+        lock_dir: Some(cfg.hardware.lock_dir.clone()),
+        socket: cfg.scale.socket.clone(),
+    }
+}
+
+/// Open the `[scale] driver` and `[motor] driver` from the built-in registry.
+fn open_registered(cfg: &Config) -> eyre::Result<(BoxedScale, BoxedMotor)> {
+    use doser_hardware::registry::{self, Registry};
+    let scale = cfg
+        .scale
+        .driver
+        .as_deref()
+        .unwrap_or_else(|| registry::default_scale_driver(cfg.scale.composite.is_some()));
+    let motor = cfg
+        .motor
+        .driver
+        .as_deref()
+        .unwrap_or(registry::DEFAULT_MOTOR);
+    tracing::info!(scale, motor, "opening drivers");
+    Registry::builtin()
+        .open(scale, motor, &wiring(cfg))
+        .wrap_err_with(|| format!("open drivers (scale {scale:?}, motor {motor:?})"))
+}
+
+/// Open the `[scale] driver` alone from the built-in registry.
+fn open_registered_scale(cfg: &Config) -> eyre::Result<BoxedScale> {
+    use doser_hardware::registry::{self, Registry};
+    let scale = cfg
+        .scale
+        .driver
+        .as_deref()
+        .unwrap_or_else(|| registry::default_scale_driver(cfg.scale.composite.is_some()));
+    tracing::info!(scale, "opening scale driver");
+    Registry::builtin()
+        .open_scale(scale, &wiring(cfg))
+        .wrap_err_with(|| format!("open scale driver {scale:?}"))
+}
+
+/// Open the scale and motor of a `[plugin]` backend library.
+#[cfg(feature = "plugin")]
+fn open_plugin(p: &doser_config::PluginCfg) -> eyre::Result<(BoxedScale, BoxedMotor)> {
+    let lib = doser_hardware::PluginLibrary::open(&p.path).wrap_err("load backend plugin")?;
+    let scale = lib
+        .create_scale(&p.scale_args)
+        .wrap_err("create plugin scale")?;
+    let motor = lib
+        .create_motor(&p.motor_args)
+        .wrap_err("create plugin motor")?;
+    Ok((Box::new(scale), Box::new(motor)))
+}
+
+/// Open the scale of a `[plugin]` backend library alone.
+#[cfg(feature = "plugin")]
+fn open_plugin_scale(p: &doser_config::PluginCfg) -> eyre::Result<BoxedScale> {
+    let lib = doser_hardware::PluginLibrary::open(&p.path).wrap_err("load backend plugin")?;
+    let scale = lib
+        .create_scale(&p.scale_args)
+        .wrap_err("create plugin scale")?;
+    Ok(Box::new(scale))
+}
+
+#[cfg(not(feature = "plugin"))]
+fn open_plugin(p: &doser_config::PluginCfg) -> eyre::Result<(BoxedScale, BoxedMotor)> {
+    eyre::bail!(
+        "[plugin] {:?} requires a build with --features plugin",
+        p.path
+    )
+}
+
+#[cfg(not(feature = "plugin"))]
+fn open_plugin_scale(p: &doser_config::PluginCfg) -> eyre::Result<BoxedScale> {
+    open_plugin(p).map(|(scale, _)| scale)
+}
+
+/// Run the `[hopper]` feasibility check for a dose of `grams`, if configured.
+pub fn check_hopper(cfg: &Config, grams: f32) -> eyre::Result<()> {
+    use doser_hardware::registry::{self, Registry, Wiring};
+    let Some(h) = &cfg.hopper else {
+        return Ok(());
+    };
+    let driver = h.driver.as_deref().unwrap_or(registry::DEFAULT_SCALE);
+    let wiring = Wiring {
+        hx711_dt: h.hx711_dt,
+        hx711_sck: h.hx711_sck,
+        composite: None,
+        ..wiring(cfg)
+    };
+    let mut hopper = Registry::builtin()
+        .open_scale(driver, &wiring)
+        .wrap_err_with(|| format!("open hopper scale {driver:?}"))?;
+    doser_core::preflight::check_hopper(
+        &mut hopper,
+        &h.into(),
+        grams,
+        std::time::Duration::from_millis(cfg.timeouts.sample_ms),
+    )?;
+    Ok(())
+}
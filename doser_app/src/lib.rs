@@ -0,0 +1,124 @@
+//! Dosing orchestration shared by the `doser` CLI and embedders: config
+//! mapping, hardware assembly, running a dose (with stats and real-time
+//! setup), and error formatting. The CLI is a thin argument layer over this
+//! crate; a GUI or service links it to run the same doses.
+//!
+//! ```no_run
+//! # fn main() -> eyre::Result<()> {
+//! let cfg = doser_config::load_toml(&std::fs::read_to_string("doser.toml")?)?;
+//! cfg.validate()?;
+//! let (scale, motor) = doser_app::hw::open_hw(&cfg)?;
+//! let (final_g, _telemetry) = doser_app::dose::run_dose(
+//!     &cfg,
+//!     None,
+//!     10.0,
+//!     (scale, motor),
+//!     doser_app::dose::DoseOptions::default(),
+//!     &doser_app::DoserHandle::new(),
+//! )?;
+//! println!("{final_g:.2} g");
+//! # Ok(())
+//! # }
+//! ```
+
+use doser_config::Calibration;
+
+pub use handle::DoserHandle;
+
+#[cfg(feature = "alloc-stats")]
+pub mod alloc_count;
+pub mod broadcast;
+pub mod dose;
+pub mod error_fmt;
+pub mod handle;
+pub mod hw;
+pub mod procinfo;
+pub mod rt;
+
+/// Effective safety limits of a dose, attached to its abort (for JSON details).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RunSafety {
+    pub max_run_ms: u64,
+    pub max_overshoot_g: f32,
+    pub no_progress_ms: u64,
+    pub no_progress_epsilon_g: f32,
+}
+
+impl std::fmt::Display for RunSafety {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "safety limits: max_run_ms {}, max_overshoot_g {}",
+            self.max_run_ms, self.max_overshoot_g
+        )
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct JsonTelemetry {
+    /// Mean weight over the final settle window
+    pub hold_g: Option<f32>,
+    /// Display weight at completion (`[filter.display]`)
+    pub display_g: Option<f32>,
+    /// Uncertainty (one sigma) of the final weight from the reading noise
+    pub sigma_g: Option<f32>,
+    pub slope_ema_gps: Option<f32>,
+    pub stop_at_g: Option<f32>,
+    pub coast_comp_g: Option<f32>,
+    pub undershoot_g: Option<f32>,
+    pub phases: Option<doser_core::PhaseTimings>,
+    /// Timed-out scale reads retried (direct mode)
+    pub read_retries: u64,
+    pub reads_recovered: u64,
+    /// Standard deviation of the control step latency (only when collecting stats)
+    pub jitter_us: Option<f32>,
+    /// Motor steps commanded during the dose
+    pub motor_steps: Option<u64>,
+    /// Weight that landed after the motor's last stop
+    pub coast_g: Option<f32>,
+    /// Motor approaches (1 plus the top-ups)
+    pub attempts: u32,
+}
+
+/// Memory locking mode for real-time operation.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum RtLock {
+    /// Do not lock memory
+    None,
+    /// Lock currently resident pages
+    Current,
+    /// Lock current and future pages
+    All,
+}
+
+impl RtLock {
+    #[inline]
+    pub fn os_default() -> Self {
+        #[cfg(target_os = "linux")]
+        {
+            return RtLock::Current;
+        }
+        #[cfg(target_os = "macos")]
+        {
+            return RtLock::None;
+        }
+        #[allow(unreachable_code)]
+        RtLock::None
+    }
+}
+
+/// The calibration a dose runs against (core default when none is configured).
+pub fn core_calibration(calib: Option<&Calibration>) -> doser_core::Calibration {
+    calib.map(doser_core::Calibration::from).unwrap_or_default()
+}
+
+/// The config calibration for a core one (e.g. after a re-tare).
+pub fn config_calibration(cal: &doser_core::Calibration) -> Calibration {
+    Calibration {
+        offset: cal.zero_counts,
+        scale_factor: cal.gain_g_per_count,
+        offset_g: cal.offset_g,
+        uncertainty: None,
+    }
+}
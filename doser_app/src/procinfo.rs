@@ -0,0 +1,122 @@
+//! Process resource usage: memory from `/proc` (Linux), CPU time from
+//! `getrusage` (Unix; per thread on Linux), and allocation counts with
+//! `--features alloc-stats`. Unavailable values are `None`.
+
+use std::time::{Duration, Instant};
+
+/// Resident set size of this process, in kB.
+pub fn rss_kb() -> Option<u64> {
+    status_kb("VmRSS:")
+}
+
+/// Peak resident set size of this process, in kB.
+pub fn peak_rss_kb() -> Option<u64> {
+    status_kb("VmHWM:")
+}
+
+/// A `kB` line of `/proc/self/status`.
+fn status_kb(key: &str) -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix(key))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Resource counters at one instant; see [`Snapshot::usage_since`].
+pub struct Snapshot {
+    at: Instant,
+    process_cpu: Option<Duration>,
+    thread_cpu: Option<Duration>,
+    allocs: Option<(u64, u64)>,
+}
+
+/// Usage between two snapshots.
+pub struct Usage {
+    pub wall: Duration,
+    /// User + system CPU time of the whole process
+    pub process_cpu: Option<Duration>,
+    /// User + system CPU time of the calling (control) thread
+    pub thread_cpu: Option<Duration>,
+    /// Allocations and bytes requested
+    pub allocs: Option<(u64, u64)>,
+}
+
+impl Snapshot {
+    pub fn take() -> Self {
+        Self {
+            at: Instant::now(),
+            process_cpu: cpu_time(false),
+            thread_cpu: cpu_time(true),
+            allocs: alloc_counts(),
+        }
+    }
+
+    /// Usage since `self`; call from the thread that took the snapshot.
+    pub fn usage_since(&self) -> Usage {
+        let now = Self::take();
+        let delta = |a: Option<Duration>, b: Option<Duration>| Some(b?.saturating_sub(a?));
+        Usage {
+            wall: now.at - self.at,
+            process_cpu: delta(self.process_cpu, now.process_cpu),
+            thread_cpu: delta(self.thread_cpu, now.thread_cpu),
+            allocs: self
+                .allocs
+                .zip(now.allocs)
+                .map(|((n0, b0), (n1, b1))| (n1 - n0, b1 - b0)),
+        }
+    }
+}
+
+impl Usage {
+    /// `cpu` as a percentage of the wall time.
+    pub fn percent(&self, cpu: Duration) -> f64 {
+        100.0 * cpu.as_secs_f64() / self.wall.as_secs_f64().max(1e-9)
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+fn alloc_counts() -> Option<(u64, u64)> {
+    Some(crate::alloc_count::counts())
+}
+
+#[cfg(not(feature = "alloc-stats"))]
+fn alloc_counts() -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(unix)]
+fn cpu_time(thread: bool) -> Option<Duration> {
+    #[cfg(target_os = "linux")]
+    let who = if thread {
+        libc::RUSAGE_THREAD
+    } else {
+        libc::RUSAGE_SELF
+    };
+    #[cfg(not(target_os = "linux"))]
+    let who = if thread {
+        return None;
+    } else {
+        libc::RUSAGE_SELF
+    };
+    let mut ru = std::mem::MaybeUninit::<libc::rusage>::uninit();
+    // SAFETY: getrusage fills the struct on success.
+    let ru = unsafe {
+        if libc::getrusage(who, ru.as_mut_ptr()) != 0 {
+            return None;
+        }
+        ru.assume_init()
+    };
+    let tv = |t: libc::timeval| {
+        Duration::from_secs(t.tv_sec as u64) + Duration::from_micros(t.tv_usec as u64)
+    };
+    Some(tv(ru.ru_utime) + tv(ru.ru_stime))
+}
+
+#[cfg(not(unix))]
+fn cpu_time(_thread: bool) -> Option<Duration> {
+    None
+}
@@ -1,6 +1,6 @@
 //! Real-time scheduling helpers (Linux SCHED_FIFO / affinity / mlockall; macOS mlockall).
 
-use crate::cli::RtLock;
+use crate::RtLock;
 
 #[cfg(target_os = "linux")]
 /// Capacity of cpu_set_t in CPU indices (bits).
@@ -229,22 +229,22 @@ pub fn setup_rt_once(rt: bool, prio: Option<i32>, lock: RtLock, rt_cpu: Option<u
         // Memory lock
         match try_apply_mem_lock(lock) {
             Ok(()) => match lock {
-                RtLock::None => eprintln!("RT: memory locking disabled (none)"),
-                RtLock::Current => eprintln!("RT: memory lock = current"),
-                RtLock::All => eprintln!("RT: memory lock = all (current|future)"),
+                RtLock::None => tracing::info!("RT: memory locking disabled (none)"),
+                RtLock::Current => tracing::info!("RT: memory lock = current"),
+                RtLock::All => tracing::info!("RT: memory lock = all (current|future)"),
             },
-            Err(err) => eprintln!("Warning: mlockall failed: {err}"),
+            Err(err) => tracing::warn!(error = %err, "mlockall failed"),
         }
         // FIFO priority
         if let Err(err) = try_apply_fifo_priority(prio) {
             let prio_dbg = prio
                 .map(|p| p.to_string())
                 .unwrap_or_else(|| "(max)".into());
-            eprintln!("Warning: sched_setscheduler(SCHED_FIFO, prio={prio_dbg}) failed: {err}");
+            tracing::warn!(error = %err, "sched_setscheduler(SCHED_FIFO, prio={prio_dbg}) failed");
         }
         // Affinity
         if let Err(err) = try_apply_affinity(rt_cpu, &ONLINE_CPUS, &CPUSET) {
-            eprintln!("Warning: affinity not applied: {err}");
+            tracing::warn!(error = %err, "CPU affinity not applied");
         }
     });
 }
@@ -260,27 +260,27 @@ pub fn setup_rt_once(rt: bool, lock: RtLock) {
     RT_ONCE.get_or_init(|| {
         match lock {
             RtLock::None => {
-                eprintln!("RT: memory locking disabled (none)");
+                tracing::info!("RT: memory locking disabled (none)");
             }
             RtLock::Current => {
                 let rc = unsafe { mlockall(MCL_CURRENT) };
                 if rc != 0 {
                     let err = std::io::Error::last_os_error();
-                    eprintln!("Warning: mlockall(MCL_CURRENT) failed: {err}");
+                    tracing::warn!(error = %err, "mlockall(MCL_CURRENT) failed");
                 } else {
-                    eprintln!("RT: memory lock = current");
+                    tracing::info!("RT: memory lock = current");
                 }
             }
             RtLock::All => {
                 let rc = unsafe { mlockall(MCL_CURRENT | MCL_FUTURE) };
                 if rc != 0 {
                     let err = std::io::Error::last_os_error();
-                    eprintln!("Warning: mlockall(MCL_CURRENT|MCL_FUTURE) failed: {err}");
+                    tracing::warn!(error = %err, "mlockall(MCL_CURRENT|MCL_FUTURE) failed");
                 } else {
-                    eprintln!("RT: memory lock = all (current|future)");
+                    tracing::info!("RT: memory lock = all (current|future)");
                 }
             }
         }
-        eprintln!("Warning: macOS does not support SCHED_FIFO or affinity; only mlockall applied.");
+        tracing::warn!("macOS does not support SCHED_FIFO or affinity; only mlockall applied");
     });
 }
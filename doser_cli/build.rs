@@ -0,0 +1,43 @@
+//! Records the git commit and target triple for `doser version --verbose`.
+//!
+//! Builds from a source tarball have no `.git`; set `DOSER_GIT_HASH` to name
+//! the commit, else it reads "unknown".
+
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=DOSER_GIT_HASH");
+    let git_dir = Path::new("../.git");
+    // Rebuild when HEAD moves (checkout or commit), not on every index change.
+    for f in ["HEAD", "packed-refs"] {
+        if git_dir.join(f).exists() {
+            println!("cargo:rerun-if-changed=../.git/{f}");
+        }
+    }
+    if let Ok(head) = std::fs::read_to_string(git_dir.join("HEAD"))
+        && let Some(r) = head.trim().strip_prefix("ref: ")
+        && git_dir.join(r).exists()
+    {
+        println!("cargo:rerun-if-changed=../.git/{r}");
+    }
+
+    let hash = std::env::var("DOSER_GIT_HASH").ok().or_else(|| {
+        let out = Command::new("git")
+            .args(["rev-parse", "--short=12", "HEAD"])
+            .output()
+            .ok()?;
+        out.status
+            .success()
+            .then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+    });
+    println!(
+        "cargo:rustc-env=DOSER_GIT_HASH={}",
+        hash.filter(|h| !h.is_empty())
+            .unwrap_or_else(|| "unknown".into())
+    );
+    println!(
+        "cargo:rustc-env=DOSER_TARGET={}",
+        std::env::var("TARGET").unwrap_or_default()
+    );
+}
@@ -0,0 +1,78 @@
+//! Command gating by role: with `[access]` configured, commands that change
+//! the calibration, the config or the learned values, and any command given a
+//! calibration CSV with `--calibration`, are reserved for technicians, who
+//! identify with the PIN from `--pin` or `DOSER_PIN`. Operators (no PIN) can
+//! dose, resume and monitor as before.
+
+use doser_config::AccessCfg;
+
+use crate::cli::{Cli, Commands, ConfigAction, LearnedAction};
+
+/// Who may run a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Operator,
+    Technician,
+}
+
+/// The role `cli` requires: `--calibration` swaps the scale's calibration,
+/// so it takes a technician whatever the command.
+pub fn required_role(cli: &Cli) -> Role {
+    if cli.calibration.is_some() {
+        return Role::Technician;
+    }
+    match &cli.cmd {
+        Commands::VerifyCal { .. } => Role::Technician,
+        Commands::SelfCheck {
+            write_filter_defaults: true,
+        } => Role::Technician,
+        Commands::Learned {
+            action: LearnedAction::Reset { .. },
+        } => Role::Technician,
+        Commands::Config {
+            action: ConfigAction::Migrate { dry_run: false },
+        } => Role::Technician,
+        _ => Role::Operator,
+    }
+}
+
+/// Fail unless `pin` grants the role `cmd` requires. Without `[access]` every
+/// command is allowed.
+pub fn authorize(access: Option<&AccessCfg>, cli: &Cli, pin: Option<&str>) -> eyre::Result<()> {
+    let Some(access) = access else {
+        return Ok(());
+    };
+    if required_role(cli) == Role::Operator {
+        return Ok(());
+    }
+    match pin {
+        Some(p) if pin_eq(p, &access.technician_pin) => Ok(()),
+        Some(_) => eyre::bail!("permission denied: wrong technician PIN"),
+        None if cli.calibration.is_some() => eyre::bail!(
+            "permission denied: --calibration is a technician option; pass the PIN with --pin or DOSER_PIN"
+        ),
+        None => eyre::bail!(
+            "permission denied: {} is a technician command; pass the PIN with --pin or DOSER_PIN",
+            command_name(&cli.cmd)
+        ),
+    }
+}
+
+/// Compare without exiting at the first differing byte.
+fn pin_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+fn command_name(cmd: &Commands) -> &'static str {
+    match cmd {
+        Commands::VerifyCal { .. } => "verify-cal",
+        Commands::SelfCheck { .. } => "self-check --write-filter-defaults",
+        Commands::Learned { .. } => "learned reset",
+        Commands::Config { .. } => "config migrate",
+        _ => "this",
+    }
+}
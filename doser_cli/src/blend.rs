@@ -0,0 +1,163 @@
+//! `doser blend`: several materials dosed one after another onto the same
+//! scale, each to a running total ("A to 10 g, then B to 25 g total"), as in
+//! small-batch blending.
+//!
+//! There is one feed motor, so the feed is switched to the next material
+//! between components (the operator confirms unless `--yes`). Before each
+//! component the scale must have settled; its weight then becomes the tare for
+//! that component's dose, and each component is recorded with what it added
+//! and the total it reached.
+
+use std::time::{Duration, Instant};
+
+use doser_core::Calibration;
+use doser_core::hw_error::map_hw_error;
+use doser_traits::Scale;
+use serde_json::json;
+
+/// Readings in one settle window.
+const SETTLE_SAMPLES: usize = 5;
+
+/// One `--part NAME=GRAMS`: dose `name` until the scale holds `total_g`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub name: String,
+    pub total_g: f32,
+}
+
+impl std::str::FromStr for Part {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, grams) = s
+            .split_once('=')
+            .ok_or_else(|| format!("{s:?}: expected NAME=GRAMS"))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("{s:?}: the component needs a name"));
+        }
+        let total_g = grams
+            .trim()
+            .parse::<f32>()
+            .map_err(|e| format!("{s:?}: {e}"))?;
+        Ok(Self {
+            name: name.to_string(),
+            total_g,
+        })
+    }
+}
+
+/// Totals must rise from component to component.
+pub fn check_parts(parts: &[Part]) -> eyre::Result<()> {
+    let mut prev = 0.0;
+    for p in parts {
+        if !(p.total_g.is_finite() && p.total_g > prev) {
+            eyre::bail!(
+                "--part {}={}: totals must be positive and rise from component to component",
+                p.name,
+                p.total_g
+            );
+        }
+        prev = p.total_g;
+    }
+    Ok(())
+}
+
+/// A settled reading.
+#[derive(Debug, Clone, Copy)]
+pub struct Settled {
+    /// Mean of the settle window (raw counts)
+    pub raw: i32,
+    /// Largest minus smallest reading in the window (grams)
+    pub spread_g: f32,
+}
+
+/// Read windows of readings until one spreads no more than `tolerance_g`,
+/// for at most `wait`.
+pub fn wait_settled<S: Scale + ?Sized>(
+    scale: &mut S,
+    calibration: &Calibration,
+    tolerance_g: f32,
+    read_timeout: Duration,
+    wait: Duration,
+) -> eyre::Result<Settled> {
+    let deadline = Instant::now() + wait;
+    loop {
+        let mut raws = Vec::with_capacity(SETTLE_SAMPLES);
+        for _ in 0..SETTLE_SAMPLES {
+            raws.push(
+                scale
+                    .read(read_timeout)
+                    .map_err(|e| eyre::Report::new(map_hw_error(&*e)))?,
+            );
+        }
+        let (lo, hi) = raws
+            .iter()
+            .fold((i32::MAX, i32::MIN), |(lo, hi), &r| (lo.min(r), hi.max(r)));
+        let spread_g = (calibration.to_grams(hi) - calibration.to_grams(lo)).abs();
+        if spread_g <= tolerance_g {
+            let mean = raws.iter().map(|&r| i64::from(r)).sum::<i64>() / raws.len() as i64;
+            return Ok(Settled {
+                raw: mean as i32,
+                spread_g,
+            });
+        }
+        if Instant::now() >= deadline {
+            eyre::bail!(
+                "scale did not settle: readings spread {spread_g:.3} g (tolerance {tolerance_g} g)"
+            );
+        }
+    }
+}
+
+/// One dosed component.
+#[derive(Debug, Clone)]
+pub struct Component {
+    pub name: String,
+    /// Total the component doses to
+    pub target_total_g: f32,
+    /// What this component added
+    pub dosed_g: f32,
+    /// Total on the scale after it
+    pub total_g: f32,
+    /// Spread of the settle window before it
+    pub settle_spread_g: f32,
+}
+
+impl Component {
+    pub fn error_g(&self) -> f32 {
+        self.total_g - self.target_total_g
+    }
+}
+
+pub fn print(components: &[Component], run_id: &str, json: bool, decimals: usize) {
+    let total_g = components.last().map_or(0.0, |c| c.total_g);
+    let target_g = components.last().map_or(0.0, |c| c.target_total_g);
+    if json {
+        let parts: Vec<_> = components
+            .iter()
+            .map(|c| {
+                json!({
+                    "name": c.name,
+                    "target_total_g": c.target_total_g,
+                    "dosed_g": c.dosed_g,
+                    "total_g": c.total_g,
+                    "error_g": c.error_g(),
+                    "settle_spread_g": c.settle_spread_g,
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "run_id": run_id,
+                "target_g": target_g,
+                "total_g": total_g,
+                "error_g": total_g - target_g,
+                "components": parts,
+            })
+        );
+    } else {
+        println!("blend: {total_g:.decimals$} of {target_g:.decimals$} g");
+    }
+}
@@ -0,0 +1,86 @@
+//! What this binary was built from: crate versions, enabled cargo features,
+//! git commit and target. Shown by `doser version --verbose`, and carried in
+//! every JSON dose report and diagnostic bundle so field behaviour can be tied
+//! to a build.
+
+use serde_json::json;
+
+/// Cargo features of this binary (forwarded to `doser_hardware` where they
+/// select drivers).
+pub fn features() -> Vec<&'static str> {
+    [
+        ("hardware", cfg!(feature = "hardware")),
+        ("gpiod", cfg!(feature = "gpiod")),
+        ("plugin", cfg!(feature = "plugin")),
+        ("rt", cfg!(feature = "rt")),
+        ("alloc-stats", cfg!(feature = "alloc-stats")),
+        ("otel", cfg!(feature = "otel")),
+    ]
+    .into_iter()
+    .filter_map(|(name, on)| on.then_some(name))
+    .collect()
+}
+
+fn crates() -> [(&'static str, &'static str); 5] {
+    [
+        ("doser_cli", env!("CARGO_PKG_VERSION")),
+        ("doser_core", doser_core::VERSION),
+        ("doser_config", doser_config::VERSION),
+        ("doser_hardware", doser_hardware::VERSION),
+        ("doser_traits", doser_traits::VERSION),
+    ]
+}
+
+/// The build block, as embedded in JSON output.
+pub fn json() -> serde_json::Value {
+    let crates: serde_json::Map<_, _> = crates()
+        .into_iter()
+        .map(|(name, v)| (name.to_string(), v.into()))
+        .collect();
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "git": env!("DOSER_GIT_HASH"),
+        "target": env!("DOSER_TARGET"),
+        "debug": cfg!(debug_assertions),
+        "features": features(),
+        "crates": crates,
+    })
+}
+
+/// `doser version [--verbose]`.
+pub fn run(verbose: bool, json: bool) {
+    if json {
+        if verbose {
+            println!("{}", self::json());
+        } else {
+            println!("{}", json!({ "version": env!("CARGO_PKG_VERSION") }));
+        }
+        return;
+    }
+    println!("doser {}", env!("CARGO_PKG_VERSION"));
+    if !verbose {
+        return;
+    }
+    let features = features();
+    println!("git:      {}", env!("DOSER_GIT_HASH"));
+    println!("target:   {}", env!("DOSER_TARGET"));
+    println!(
+        "build:    {}",
+        if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        }
+    );
+    println!(
+        "features: {}",
+        if features.is_empty() {
+            "(none)".to_string()
+        } else {
+            features.join(", ")
+        }
+    );
+    for (name, v) in crates() {
+        println!("  {name:<16}{v}");
+    }
+}
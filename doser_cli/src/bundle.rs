@@ -0,0 +1,183 @@
+//! Diagnostic bundles for field support: one directory holding the config
+//! (with the technician PIN redacted), the last control steps before an
+//! abort, the motor's stepping counters, the tail of the log file, the error
+//! and the versions.
+//!
+//! Written for every failed dose when `[bundle]` is configured, and on demand
+//! by `doser bundle`.
+
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use doser_config::Config;
+use doser_core::AbortTrace;
+use eyre::WrapErr;
+use serde_json::json;
+
+/// Bytes read from the end of the log file when looking for its last lines.
+const LOG_TAIL_BYTES: u64 = 256 * 1024;
+
+/// What a bundle is about.
+pub struct Incident<'a> {
+    pub run_id: Option<&'a str>,
+    /// Wall-clock start of the run (RFC 3339); telemetry `ms` count from it
+    pub started_at: Option<&'a str>,
+    pub error: Option<&'a eyre::Report>,
+}
+
+/// Write a bundle under `dir` and return its path.
+pub fn write(
+    dir: &Path,
+    cfg: &Config,
+    cfg_text: &str,
+    log_lines: usize,
+    incident: &Incident<'_>,
+) -> eyre::Result<PathBuf> {
+    let stamp = time::OffsetDateTime::now_utc()
+        .format(time::macros::format_description!(
+            "[year][month][day]T[hour][minute][second]Z"
+        ))
+        .unwrap_or_default();
+    let mut name = format!("doser-bundle-{stamp}");
+    if let Some(id) = incident.run_id {
+        name.push('-');
+        name.extend(id.chars().take(8));
+    }
+    let path = dir.join(name);
+    fs::create_dir_all(&path).wrap_err_with(|| format!("create bundle {path:?}"))?;
+    let write = |file: &str, text: &str| {
+        fs::write(path.join(file), text).wrap_err_with(|| format!("write {file} in {path:?}"))
+    };
+
+    write("config.toml", &redacted(cfg_text))?;
+
+    let trace = incident.error.and_then(|e| e.downcast_ref::<AbortTrace>());
+    if let Some(t) = trace {
+        let mut csv = String::from("ms,weight_g,sps,phase\n");
+        for s in &t.steps {
+            let phase = s
+                .phase
+                .map_or(String::new(), |p| format!("{p:?}").to_lowercase());
+            csv.push_str(&format!("{},{:.2},{},{phase}\n", s.ms, s.weight_g, s.sps));
+        }
+        write("telemetry.csv", &csv)?;
+    }
+
+    let log = cfg.logging.file.as_deref().and_then(latest_log);
+    if let Some(log) = &log {
+        match tail(log, log_lines) {
+            Ok(text) => write("log-tail.txt", &text)?,
+            Err(e) => tracing::warn!(error = %e, log = %log.display(), "log tail not bundled"),
+        }
+    }
+
+    let error = incident.error.map(|e| {
+        serde_json::from_str::<serde_json::Value>(&doser_app::error_fmt::format_error_json(e))
+            .unwrap_or_else(|_| e.to_string().into())
+    });
+    let manifest = json!({
+        "created": crate::summary::utc_now(),
+        "created_at": crate::wallclock::now(),
+        "clock": crate::wallclock::json(),
+        "run_id": incident.run_id,
+        "run_started_at": incident.started_at,
+        "error": error,
+        "versions": versions(),
+        "telemetry_steps": trace.map(|t| t.steps.len()),
+        "motor": trace.and_then(|t| t.motor).map(|m| json!({
+            "steps_issued": m.steps_issued,
+            "avg_jitter_us": m.avg_jitter_us,
+            "max_jitter_us": m.max_jitter_us,
+            "missed_periods": m.missed_periods,
+        })),
+        "log_file": log,
+    });
+    write("manifest.json", &format!("{manifest:#}\n"))?;
+    Ok(path)
+}
+
+/// The `[bundle]` for a failed dose, if configured. A bundle that cannot be
+/// written is logged and does not mask the dose's own error.
+pub fn after_failure(
+    cfg: &Config,
+    cfg_text: &str,
+    run_id: &str,
+    started_at: &str,
+    err: &eyre::Report,
+) -> Option<PathBuf> {
+    let b = cfg.bundle.as_ref()?;
+    let incident = Incident {
+        run_id: Some(run_id),
+        started_at: Some(started_at),
+        error: Some(err),
+    };
+    match write(Path::new(&b.dir), cfg, cfg_text, b.log_lines, &incident) {
+        Ok(path) => {
+            tracing::info!(bundle = %path.display(), "diagnostic bundle written");
+            Some(path)
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "diagnostic bundle not written");
+            None
+        }
+    }
+}
+
+fn versions() -> serde_json::Value {
+    let mut v = crate::build_info::json();
+    v["os"] = std::env::consts::OS.into();
+    v["arch"] = std::env::consts::ARCH.into();
+    v
+}
+
+/// `text` with `[access] technician_pin` replaced. Text that does not parse
+/// is kept as it is; the bundle is for a config that failed, too.
+fn redacted(text: &str) -> String {
+    let Ok(mut doc) = text.parse::<toml_edit::DocumentMut>() else {
+        return text.to_string();
+    };
+    if let Some(access) = doc.get_mut("access").and_then(|a| a.as_table_like_mut())
+        && access.contains_key("technician_pin")
+    {
+        access.insert("technician_pin", toml_edit::value("<redacted>"));
+    }
+    doc.to_string()
+}
+
+/// The file `[logging] file` currently writes to: the path itself, or with
+/// rotation the newest `<path>.<date>` beside it.
+pub fn latest_log(file: &str) -> Option<PathBuf> {
+    let path = Path::new(file);
+    let name = path.file_name()?.to_string_lossy().into_owned();
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    fs::read_dir(parent)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|e| {
+            let n = e.file_name().to_string_lossy().into_owned();
+            n == name || crate::storage::is_rotated(&n, &name)
+        })
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .max()
+        .map(|(_, p)| p)
+}
+
+/// The last `lines` lines of `path`.
+fn tail(path: &Path, lines: usize) -> std::io::Result<String> {
+    let mut f = fs::File::open(path)?;
+    let len = f.metadata()?.len();
+    f.seek(SeekFrom::Start(len.saturating_sub(LOG_TAIL_BYTES)))?;
+    let mut buf = Vec::new();
+    f.read_to_end(&mut buf)?;
+    let text = String::from_utf8_lossy(&buf);
+    // Reading from the middle of the file starts with a partial line.
+    let skip = usize::from(len > LOG_TAIL_BYTES);
+    let all: Vec<&str> = text.lines().skip(skip).collect();
+    let mut out = all[all.len().saturating_sub(lines)..].join("\n");
+    out.push('\n');
+    Ok(out)
+}
@@ -1,30 +1,17 @@
 //! CLI argument definitions and shared statics.
 
-use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use clap::{ArgAction, Parser, Subcommand};
+pub use doser_app::RtLock;
+pub use doser_app::hw::Backend;
+use doser_config::CalibrationFit;
 use std::path::PathBuf;
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock};
 
-pub static FILE_GUARD: OnceLock<tracing_appender::non_blocking::WorkerGuard> = OnceLock::new();
+/// Keeps the log file writer flushing; taken by `tracing_setup::flush_logs`.
+pub static FILE_GUARD: Mutex<Option<tracing_appender::non_blocking::WorkerGuard>> =
+    Mutex::new(None);
 /// Whether the user asked for JSON output (controls structured error output).
 pub static JSON_MODE: OnceLock<bool> = OnceLock::new();
-/// Effective safety knobs used for the current run (for JSON details).
-pub static LAST_SAFETY: OnceLock<CliSafety> = OnceLock::new();
-
-#[derive(Copy, Clone, Debug)]
-pub struct CliSafety {
-    pub max_run_ms: u64,
-    pub max_overshoot_g: f32,
-    pub no_progress_ms: u64,
-    pub no_progress_epsilon_g: f32,
-}
-
-#[derive(Clone, Copy, Default)]
-pub struct JsonTelemetry {
-    pub slope_ema_gps: Option<f32>,
-    pub stop_at_g: Option<f32>,
-    pub coast_comp_g: Option<f32>,
-}
-
 #[derive(Parser, Debug)]
 #[command(name = "doser", version, about = "Doser CLI")]
 pub struct Cli {
@@ -36,6 +23,11 @@ pub struct Cli {
     #[arg(long, value_name = "FILE")]
     pub calibration: Option<PathBuf>,
 
+    /// Line fit for the calibration CSV: ols (2σ outlier refit), theil-sen or
+    /// ransac (for heavily contaminated points)
+    #[arg(long, value_name = "FIT", default_value = "ols")]
+    pub calibration_fit: CalibrationFit,
+
     /// Log as JSON lines instead of pretty
     #[arg(long, action = ArgAction::SetTrue)]
     pub json: bool,
@@ -44,38 +36,26 @@ pub struct Cli {
     #[arg(long = "log-level", value_name = "LEVEL", default_value = "info")]
     pub log_level: String,
 
+    /// Technician PIN for restricted commands (`[access]`; default: DOSER_PIN)
+    #[arg(long, value_name = "PIN")]
+    pub pin: Option<String>,
+
+    /// Drivers to run on: `sim` forces the simulation (training, demos),
+    /// `hardware` refuses to simulate, `auto` follows the config and falls
+    /// back to the simulation only when no GPIO chip exists
+    #[arg(long, value_enum, value_name = "BACKEND", default_value = "auto")]
+    pub backend: Backend,
+
+    /// Run the control loop on virtual time, so a simulated dose finishes in
+    /// milliseconds (CI only; needs the `sim` drivers, implies direct sampling)
+    #[arg(long, action = ArgAction::SetTrue, hide = true)]
+    pub sim_clock: bool,
+
     /// Command to execute
     #[command(subcommand)]
     pub cmd: Commands,
 }
 
-/// Memory locking mode for real-time operation.
-#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
-pub enum RtLock {
-    /// Do not lock memory
-    None,
-    /// Lock currently resident pages
-    Current,
-    /// Lock current and future pages
-    All,
-}
-
-impl RtLock {
-    #[inline]
-    pub fn os_default() -> Self {
-        #[cfg(target_os = "linux")]
-        {
-            return RtLock::Current;
-        }
-        #[cfg(target_os = "macos")]
-        {
-            return RtLock::None;
-        }
-        #[allow(unreachable_code)]
-        RtLock::None
-    }
-}
-
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Dispense a target amount of material
@@ -127,9 +107,257 @@ pub enum Commands {
         /// Print control loop and sampling stats
         #[arg(long, action = ArgAction::SetTrue)]
         stats: bool,
+        /// Operator name for the `[ticket]` (overrides `ticket.operator`)
+        #[arg(long, value_name = "NAME")]
+        operator: Option<String>,
+        /// Print the result as a one-line template instead of `final: X g`,
+        /// e.g. "final={final_g} took {duration_ms}ms"
+        #[arg(
+            long,
+            value_name = "TEMPLATE",
+            long_help = "Print the result as a one-line template instead of `final: X g`, e.g. \"final={final_g} took {duration_ms}ms\".\n\nPlaceholders: target_g, final_g, hold_g, display_g, sigma_g, duration_ms, date, operator, slope_ema, stop_at_g, coast_comp_g, undershoot_g, coarse_ms, fine_ms, settle_ms, verify_ms, read_retries, reads_recovered, run_id. Values not recorded for the run are empty; `{{` and `}}` are literal braces. Not combinable with --json."
+        )]
+        format: Option<String>,
+        /// Client request id (needs `[requests]`): a retry with the same id
+        /// returns the first run's result instead of dosing again
+        #[arg(long, value_name = "ID")]
+        request_id: Option<String>,
+        /// Refuse (instead of warning) when the completion tolerance is
+        /// tighter than the calibration's uncertainty supports
+        #[arg(long, action = ArgAction::SetTrue)]
+        strict: bool,
+    },
+    /// Finish a dose interrupted by a sensor timeout: re-tare at the current
+    /// weight and dose what is left of the original target (needs `[resume]`)
+    Resume {
+        /// Do not ask for confirmation
+        #[arg(long, action = ArgAction::SetTrue)]
+        yes: bool,
+        /// Use direct control loop (no sampler)
+        #[arg(long, action = ArgAction::SetTrue)]
+        direct: bool,
+    },
+    /// Run repeated doses and fail if the final error drifts or memory use or
+    /// control loop jitter grows over the run
+    Soak {
+        /// Number of doses
+        #[arg(long, default_value_t = 100)]
+        cycles: u32,
+        /// Target grams per dose
+        #[arg(long)]
+        target: f32,
+        /// Use direct control loop (no sampler)
+        #[arg(long, action = ArgAction::SetTrue)]
+        direct: bool,
+        /// Pause between doses, e.g. while the receptacle empties
+        #[arg(long, value_name = "MS", default_value_t = 0)]
+        pause_ms: u64,
+        /// Fail if the mean final error changes by more than this (g)
+        #[arg(long, value_name = "GRAMS", default_value_t = 0.05)]
+        max_drift_g: f32,
+        /// Fail if process RSS grows by more than this (kB; Linux only)
+        #[arg(long, value_name = "KB", default_value_t = 1024)]
+        max_rss_growth_kb: u64,
+        /// Fail if mean loop jitter grows by more than this factor
+        #[arg(long, value_name = "RATIO", default_value_t = 2.0)]
+        max_jitter_ratio: f32,
+    },
+    /// Dose several materials onto the same scale in turn, each to a running
+    /// total (e.g. `--part A=10 --part B=25`)
+    Blend {
+        /// Component and the total to dose it to, in order (repeatable)
+        #[arg(long = "part", value_name = "NAME=GRAMS", required = true)]
+        parts: Vec<crate::blend::Part>,
+        /// Switch to the next material without asking
+        #[arg(long, action = ArgAction::SetTrue)]
+        yes: bool,
+        /// Use direct control loop (no sampler)
+        #[arg(long, action = ArgAction::SetTrue)]
+        direct: bool,
+        /// Largest spread of the readings before a component counts as settled (g)
+        #[arg(long, value_name = "GRAMS", default_value_t = 0.05)]
+        settle_tolerance_g: f32,
+        /// How long to wait for the scale to settle before a component (ms)
+        #[arg(long, value_name = "MS", default_value_t = 5000)]
+        settle_wait_ms: u64,
+    },
+    /// Quick health check (hardware presence / sim ok); detects the HX711 rate
+    /// and compares `[filter]` with the defaults for it
+    SelfCheck {
+        /// Write the filter defaults for the detected rate into the config file
+        #[arg(long, action = ArgAction::SetTrue)]
+        write_filter_defaults: bool,
     },
-    /// Quick health check (hardware presence / sim ok)
-    SelfCheck,
     /// Health check for operational monitoring
     Health,
+    /// Check the calibration against a reference weight; the result is
+    /// recorded in `[calibration.verified]` when the calibration is in the config
+    VerifyCal {
+        /// Reference weight in grams
+        #[arg(long, value_name = "GRAMS")]
+        weight: f32,
+        /// Pass within this many grams of the reference (default: verify.tolerance_g)
+        #[arg(long = "tolerance-g", value_name = "GRAMS")]
+        tolerance_g: Option<f32>,
+    },
+    /// Shadow an externally driven dose: read the scale, compute what the
+    /// controller would command (the motor is never driven), and log divergence
+    Shadow {
+        /// Target grams the external process is dosing
+        #[arg(long)]
+        grams: f32,
+        /// Give up after this many ms (default: safety.max_run_ms)
+        #[arg(long, value_name = "MS")]
+        max_run_ms: Option<u64>,
+        /// Observed flow (g/s) above which the external process counts as dispensing
+        #[arg(long, value_name = "GPS", default_value_t = 0.2)]
+        flow_threshold_gps: f32,
+    },
+    /// Preview the speed bands a dose would run through, with estimated
+    /// durations when the flow per motor step is known (no hardware is opened)
+    Plan {
+        /// Target grams to plan for
+        #[arg(long)]
+        grams: f32,
+        /// Material flow per motor step (grams), for duration estimates
+        #[arg(long, value_name = "GRAMS")]
+        g_per_step: Option<f32>,
+    },
+    /// Run seeded Monte Carlo doses of this tuning against a plant model and
+    /// check the results, e.g. `--assert overshoot_p95<0.15` (no hardware is
+    /// opened)
+    Simulate {
+        /// Target grams to simulate
+        #[arg(long)]
+        grams: f32,
+        /// Number of simulated doses
+        #[arg(long, default_value_t = 100)]
+        runs: u32,
+        /// Seed; the same seed and config give the same results
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        #[command(flatten)]
+        plant: crate::simulate::PlantArgs,
+        /// Threshold to check, `<metric><op><value>` (repeatable); fails the
+        /// command when one does not hold
+        #[arg(long = "assert", value_name = "EXPR")]
+        asserts: Vec<crate::simulate::Assertion>,
+    },
+    /// Search speed bands and predictor settings for this config against a
+    /// plant model and print the cheapest tuning found (no hardware is opened)
+    Tune {
+        /// Run the seeded search over simulated doses
+        #[arg(long, required = true)]
+        optimize: bool,
+        /// Target grams to tune for
+        #[arg(long)]
+        grams: f32,
+        /// Candidate tunings to simulate
+        #[arg(long, default_value_t = 64)]
+        candidates: u32,
+        /// Simulated doses per candidate
+        #[arg(long, default_value_t = 50)]
+        runs: u32,
+        /// Seed; the same seed and config give the same recommendation
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        #[command(flatten)]
+        plant: crate::simulate::PlantArgs,
+        /// Cost per gram of 95th-percentile overshoot
+        #[arg(long, value_name = "WEIGHT", default_value_t = 1.0)]
+        overshoot_weight: f64,
+        /// Cost per second of 95th-percentile dose time
+        #[arg(long, value_name = "WEIGHT", default_value_t = 0.01)]
+        time_weight: f64,
+    },
+    /// Serve synthetic scale readings from a weight profile on a Unix socket,
+    /// for the `socket` scale driver in another process (no hardware is opened)
+    SimScale {
+        /// Weight profile CSV (`ms,grams`)
+        #[arg(long, value_name = "FILE")]
+        profile: PathBuf,
+        /// Socket to serve on
+        #[arg(long, value_name = "PATH")]
+        serve: PathBuf,
+        /// Readings per second (default: filter.sample_rate_hz)
+        #[arg(long, value_name = "HZ")]
+        rate_hz: Option<u32>,
+        /// Restart the profile after its last row instead of holding it
+        #[arg(long, action = ArgAction::SetTrue)]
+        repeat: bool,
+    },
+    /// Collect a diagnostic bundle (config, log tail, versions) for support
+    Bundle {
+        /// Directory to write it under (default: bundle.dir, else the current directory)
+        #[arg(long, value_name = "DIR")]
+        out: Option<PathBuf>,
+    },
+    /// Show or prune the disk space taken by bundles and rotated logs
+    /// (`[retention]`)
+    Storage {
+        #[command(subcommand)]
+        action: StorageAction,
+    },
+    /// Print the version; with --verbose also the git commit, target, cargo
+    /// features and crate versions (no config is read)
+    Version {
+        #[arg(long, action = ArgAction::SetTrue)]
+        verbose: bool,
+    },
+    /// Show or reset the constants learned across doses (`[learned]`)
+    Learned {
+        #[command(subcommand)]
+        action: LearnedAction,
+    },
+    /// Check the config file (no hardware is touched)
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Compare two recorded dose sets (JSONL from `--json dose`)
+    Compare {
+        /// Baseline dose set (A)
+        #[arg(value_name = "A.jsonl")]
+        a: PathBuf,
+        /// Candidate dose set (B)
+        #[arg(value_name = "B.jsonl")]
+        b: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone, Copy)]
+pub enum StorageAction {
+    /// Disk usage per kind, the limits and what the next prune removes
+    Status,
+    /// Prune now (also done before each dose and resume)
+    Prune,
+}
+
+#[derive(Subcommand, Debug, Clone, Copy)]
+pub enum ConfigAction {
+    /// Parse and validate the config; unknown keys are reported as warnings
+    Validate {
+        /// Fail on unknown keys (typos), as `strict = true` in the file does
+        #[arg(long, action = ArgAction::SetTrue)]
+        strict: bool,
+    },
+    /// Rewrite older layouts (renamed keys, tuple speed bands) into the
+    /// current schema, keeping comments
+    Migrate {
+        /// Print the migrated file instead of writing it
+        #[arg(long, action = ArgAction::SetTrue)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone, Copy)]
+pub enum LearnedAction {
+    /// Print the learned values of every material (* = this config's)
+    Show,
+    /// Forget this config's material
+    Reset {
+        /// Forget every material
+        #[arg(long, action = ArgAction::SetTrue)]
+        all: bool,
+    },
 }
@@ -0,0 +1,278 @@
+//! `doser compare`: A/B comparison of two recorded dose sets.
+//!
+//! Inputs are JSONL files as produced by `doser --json dose ...` (one object per
+//! dose; non-JSON lines and aborted doses are skipped). For each metric the two
+//! sets are summarized and compared with Welch's t-test, so a tuning change can
+//! be judged against run-to-run noise.
+
+use std::fs;
+use std::path::Path;
+
+use eyre::WrapErr;
+use serde_json::{Value, json};
+
+/// Two-sided p-value below which a difference is flagged as significant.
+const ALPHA: f64 = 0.05;
+
+/// Extracts one metric from a dose; `None` when it was not recorded.
+type Metric = fn(&DoseRecord) -> Option<f64>;
+
+/// One completed dose parsed from a JSONL line.
+#[derive(Debug, Clone, Copy)]
+struct DoseRecord {
+    /// `final_g - target_g`; positive means overshoot.
+    error_g: f64,
+    duration_ms: f64,
+    /// Time with the motor running (coarse + fine), when phases were recorded.
+    time_to_target_ms: Option<f64>,
+    /// Time from the motor's stop to completion (settle + verify).
+    settle_ms: Option<f64>,
+}
+
+/// A parsed dose set.
+#[derive(Debug, Default)]
+struct DoseSet {
+    doses: Vec<DoseRecord>,
+    aborted: usize,
+}
+
+impl DoseSet {
+    fn load(path: &Path) -> eyre::Result<Self> {
+        let text = fs::read_to_string(path).wrap_err_with(|| format!("read {path:?}"))?;
+        let mut set = Self::default();
+        for line in text.lines() {
+            let Ok(v) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            if !v.get("abort_reason").is_none_or(Value::is_null) {
+                set.aborted += 1;
+                continue;
+            }
+            let (Some(target), Some(final_g)) = (
+                v.get("target_g").and_then(Value::as_f64),
+                v.get("final_g").and_then(Value::as_f64),
+            ) else {
+                continue;
+            };
+            let phase = |key: &str| {
+                v.get("phases")
+                    .and_then(|p| p.get(key))
+                    .and_then(Value::as_f64)
+            };
+            set.doses.push(DoseRecord {
+                error_g: final_g - target,
+                duration_ms: v.get("duration_ms").and_then(Value::as_f64).unwrap_or(0.0),
+                time_to_target_ms: phase("coarse_ms").zip(phase("fine_ms")).map(|(c, f)| c + f),
+                // Older records have no `verify_ms`: it was part of `settle_ms`.
+                settle_ms: phase("settle_ms").map(|s| s + phase("verify_ms").unwrap_or(0.0)),
+            });
+        }
+        if set.doses.is_empty() {
+            eyre::bail!("no completed doses found in {path:?}");
+        }
+        Ok(set)
+    }
+
+    fn metric(&self, f: Metric) -> Vec<f64> {
+        self.doses.iter().filter_map(f).collect()
+    }
+}
+
+/// Mean and sample standard deviation.
+#[derive(Debug, Clone, Copy)]
+struct Summary {
+    n: usize,
+    mean: f64,
+    stdev: f64,
+}
+
+impl Summary {
+    fn of(xs: &[f64]) -> Option<Self> {
+        if xs.is_empty() {
+            return None;
+        }
+        let n = xs.len();
+        let mean = xs.iter().sum::<f64>() / n as f64;
+        let stdev = if n > 1 {
+            let var = xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n as f64 - 1.0);
+            var.sqrt()
+        } else {
+            0.0
+        };
+        Some(Self { n, mean, stdev })
+    }
+}
+
+/// Welch's unequal-variance t-test: returns `(t, df, two-sided p)`, or `None`
+/// when either set has fewer than two samples or both have zero variance.
+fn welch(a: Summary, b: Summary) -> Option<(f64, f64, f64)> {
+    if a.n < 2 || b.n < 2 {
+        return None;
+    }
+    let va = a.stdev.powi(2) / a.n as f64;
+    let vb = b.stdev.powi(2) / b.n as f64;
+    let se2 = va + vb;
+    if se2 <= 0.0 {
+        return None;
+    }
+    let t = (b.mean - a.mean) / se2.sqrt();
+    let df = se2.powi(2) / (va.powi(2) / (a.n as f64 - 1.0) + vb.powi(2) / (b.n as f64 - 1.0));
+    let p = inc_beta(df / 2.0, 0.5, df / (df + t * t));
+    Some((t, df, p))
+}
+
+/// Regularized incomplete beta function `I_x(a, b)` (continued fraction).
+fn inc_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    // The continued fraction converges quickly for x < (a+1)/(a+b+2); use the
+    // symmetry I_x(a,b) = 1 - I_{1-x}(b,a) otherwise.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_cf(a, b, x) / a
+    } else {
+        1.0 - ln_front.exp() * beta_cf(b, a, 1.0 - x) / b
+    }
+}
+
+/// Continued fraction for the incomplete beta function (modified Lentz).
+fn beta_cf(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..200 {
+        let m = f64::from(m);
+        let m2 = 2.0 * m;
+        for num in [
+            m * (b - m) * x / ((a + m2 - 1.0) * (a + m2)),
+            -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0)),
+        ] {
+            d = 1.0 + num * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + num / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    h
+}
+
+/// Natural log of the gamma function (Lanczos approximation).
+fn ln_gamma(x: f64) -> f64 {
+    const COEF: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut ser = 1.000_000_000_190_015;
+    let mut y = x;
+    for c in COEF {
+        y += 1.0;
+        ser += c / y;
+    }
+    -tmp + (2.506_628_274_631_000_5 * ser / x).ln()
+}
+
+/// Compare two dose sets and print the report (JSON object with `json`).
+pub fn run(a_path: &Path, b_path: &Path, json: bool) -> eyre::Result<()> {
+    let a = DoseSet::load(a_path)?;
+    let b = DoseSet::load(b_path)?;
+
+    let metrics: [(&str, Metric); 5] = [
+        ("error_g", |d| Some(d.error_g)),
+        ("abs_error_g", |d| Some(d.error_g.abs())),
+        ("duration_ms", |d| Some(d.duration_ms)),
+        ("time_to_target_ms", |d| d.time_to_target_ms),
+        ("settle_ms", |d| d.settle_ms),
+    ];
+
+    let mut rows = Vec::new();
+    for (name, f) in metrics {
+        let (Some(sa), Some(sb)) = (Summary::of(&a.metric(f)), Summary::of(&b.metric(f))) else {
+            continue;
+        };
+        rows.push((name, sa, sb, welch(sa, sb)));
+    }
+
+    if json {
+        let metrics: serde_json::Map<String, Value> = rows
+            .iter()
+            .map(|(name, sa, sb, test)| {
+                let side = |s: &Summary| json!({ "n": s.n, "mean": s.mean, "stdev": s.stdev });
+                let obj = json!({
+                    "a": side(sa),
+                    "b": side(sb),
+                    "delta": sb.mean - sa.mean,
+                    "t": test.map(|(t, _, _)| t),
+                    "df": test.map(|(_, df, _)| df),
+                    "p": test.map(|(_, _, p)| p),
+                    "significant": test.is_some_and(|(_, _, p)| p < ALPHA),
+                });
+                ((*name).to_string(), obj)
+            })
+            .collect();
+        let obj = json!({
+            "a": { "path": a_path, "doses": a.doses.len(), "aborted": a.aborted },
+            "b": { "path": b_path, "doses": b.doses.len(), "aborted": b.aborted },
+            "metrics": metrics,
+        });
+        println!("{obj}");
+        return Ok(());
+    }
+
+    println!(
+        "A: {} ({} doses, {} aborted)",
+        a_path.display(),
+        a.doses.len(),
+        a.aborted
+    );
+    println!(
+        "B: {} ({} doses, {} aborted)",
+        b_path.display(),
+        b.doses.len(),
+        b.aborted
+    );
+    println!(
+        "\n{:<18} {:>20} {:>20} {:>12} {:>8}",
+        "metric", "A mean ± sd", "B mean ± sd", "delta", "p"
+    );
+    for (name, sa, sb, test) in &rows {
+        let p = match test {
+            Some((_, _, p)) if *p < ALPHA => format!("{p:.3}*"),
+            Some((_, _, p)) => format!("{p:.3}"),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:<18} {:>20} {:>20} {:>+12.3} {:>8}",
+            name,
+            format!("{:.3} ± {:.3}", sa.mean, sa.stdev),
+            format!("{:.3} ± {:.3}", sb.mean, sb.stdev),
+            sb.mean - sa.mean,
+            p
+        );
+    }
+    println!("\n* significant at p < {ALPHA} (Welch's t-test)");
+    Ok(())
+}
@@ -0,0 +1,272 @@
+//! Loading the config file, and `doser config validate|migrate`.
+//!
+//! A file may start with `include = ["base.toml", "site.toml"]` (paths
+//! relative to it): the includes are merged in order, then the file itself on
+//! top. Tables merge key by key; any other value, arrays included, replaces
+//! the one before. So a fleet shares one tuning file and each machine's file
+//! only holds its pins and calibration.
+//!
+//! Keys no field reads are ignored by default, so an old file keeps working;
+//! with `strict = true` in the file (or `config validate --strict`) they are
+//! an error, so a typo like `epsilom_g` is caught instead of leaving the
+//! default in place.
+//!
+//! `config migrate` rewrites the layouts older files still use into the
+//! current form, editing the file in place so comments and order are kept:
+//! renamed keys (`timeouts.sensor_ms`, `hardware.data_ready_timeout_ms`),
+//! the ignored `timeouts.settle_ms`, and tuple `speed_bands`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use doser_config::Config;
+use eyre::WrapErr;
+use serde_json::json;
+use toml_edit::{DocumentMut, InlineTable, Key, Table, Value};
+
+/// Largest config file read; real configs are a few KB.
+const MAX_CONFIG_BYTES: u64 = 1 << 20;
+
+/// The text of the config at `path` with its includes merged in; the file
+/// as written when it includes nothing.
+pub fn read(path: &Path) -> eyre::Result<String> {
+    let text = read_file(path)?;
+    // Syntax errors are reported by `parse`, with their position.
+    let Ok(table) = toml::from_str::<toml::Table>(&text) else {
+        return Ok(text);
+    };
+    if !table.contains_key("include") {
+        return Ok(text);
+    }
+    let merged = resolve(path, table, &mut vec![canonical(path)?])?;
+    toml::to_string(&merged).wrap_err_with(|| format!("merge config {path:?}"))
+}
+
+/// The text of one config file (with a size cap so a huge file can't OOM).
+fn read_file(path: &Path) -> eyre::Result<String> {
+    if let Ok(meta) = fs::metadata(path)
+        && meta.len() > MAX_CONFIG_BYTES
+    {
+        eyre::bail!(
+            "config file {:?} is too large ({} bytes > {} byte limit)",
+            path,
+            meta.len(),
+            MAX_CONFIG_BYTES
+        );
+    }
+    fs::read_to_string(path).wrap_err_with(|| format!("read config {path:?}"))
+}
+
+fn canonical(path: &Path) -> eyre::Result<PathBuf> {
+    fs::canonicalize(path).wrap_err_with(|| format!("read config {path:?}"))
+}
+
+/// `table` (read from `path`) on top of its includes; `chain` holds the files
+/// being resolved, to refuse a cycle.
+fn resolve(
+    path: &Path,
+    mut table: toml::Table,
+    chain: &mut Vec<PathBuf>,
+) -> eyre::Result<toml::Table> {
+    let Some(include) = table.remove("include") else {
+        return Ok(table);
+    };
+    let files: Vec<&str> = include
+        .as_array()
+        .and_then(|a| a.iter().map(toml::Value::as_str).collect())
+        .ok_or_else(|| eyre::eyre!("{path:?}: include must be an array of file paths"))?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut merged = toml::Table::new();
+    for file in files {
+        let inc = dir.join(file);
+        let id = canonical(&inc)?;
+        if chain.contains(&id) {
+            eyre::bail!("{path:?}: include cycle through {inc:?}");
+        }
+        let text = read_file(&inc)?;
+        let t: toml::Table =
+            toml::from_str(&text).wrap_err_with(|| format!("parse config {inc:?}"))?;
+        chain.push(id);
+        let t = resolve(&inc, t, chain)?;
+        chain.pop();
+        overlay(&mut merged, t);
+    }
+    overlay(&mut merged, table);
+    Ok(merged)
+}
+
+/// Merge `top` into `base`: tables key by key, other values replaced.
+fn overlay(base: &mut toml::Table, top: toml::Table) {
+    for (key, value) in top {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(b)), toml::Value::Table(t)) => overlay(b, t),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Parse the config `text` read from `path`; unknown keys fail it when
+/// `strict` or when the file sets `strict = true`.
+pub fn parse(path: &Path, text: &str, strict: bool) -> eyre::Result<Config> {
+    let cfg: Config = toml::from_str(text).wrap_err_with(|| format!("parse config {path:?}"))?;
+    if strict || cfg.strict {
+        let unknown =
+            doser_config::unknown_keys(text).wrap_err_with(|| format!("parse config {path:?}"))?;
+        if !unknown.is_empty() {
+            eyre::bail!(
+                "unknown config keys in {path:?} (strict): {}",
+                unknown.join(", ")
+            );
+        }
+    }
+    Ok(cfg)
+}
+
+/// `doser config validate`: parse and validate the config without touching
+/// hardware; unknown keys are warnings, or errors when `strict`.
+pub fn validate(path: &Path, strict: bool, json: bool) -> eyre::Result<()> {
+    let text = read(path)?;
+    let cfg = parse(path, &text, strict)?;
+    // Not "invalid configuration": the generic message would hide which check failed.
+    cfg.validate()
+        .wrap_err_with(|| format!("validate config {path:?}"))?;
+    let unknown = doser_config::unknown_keys(&text)?;
+    if json {
+        println!(
+            "{}",
+            json!({ "ok": true, "path": path, "strict": strict || cfg.strict, "unknown_keys": unknown })
+        );
+    } else {
+        for key in &unknown {
+            eprintln!("Warning: unknown config key {key} (ignored)");
+        }
+        println!("config OK: {}", path.display());
+    }
+    Ok(())
+}
+
+/// Keys renamed since older schemas: `(table, old, new)`.
+const RENAMED: &[(&str, &str, &str)] = &[
+    ("timeouts", "sensor_ms", "sample_ms"),
+    (
+        "hardware",
+        "data_ready_timeout_ms",
+        "sensor_read_timeout_ms",
+    ),
+];
+
+/// `text` in the current schema, and what was changed.
+pub fn migrate_text(text: &str) -> eyre::Result<(String, Vec<String>)> {
+    let mut doc: DocumentMut = text.parse().wrap_err("parse config")?;
+    let mut changes = Vec::new();
+    for (table, old, new) in RENAMED {
+        if let Some(t) = doc.get_mut(table).and_then(|t| t.as_table_mut())
+            && rename(t, old, new)
+        {
+            changes.push(format!("{table}.{old} -> {table}.{new}"));
+        }
+    }
+    if let Some(t) = doc.get_mut("timeouts").and_then(|t| t.as_table_like_mut())
+        && t.remove("settle_ms").is_some()
+    {
+        changes.push(
+            "removed timeouts.settle_ms (never read; the settle window is control.stable_ms)"
+                .to_string(),
+        );
+    }
+    if let Some(bands) = doc
+        .get_mut("control")
+        .and_then(|c| c.get_mut("speed_bands"))
+        .and_then(|b| b.as_array_mut())
+    {
+        let mut n = 0;
+        for band in bands.iter_mut() {
+            if let Value::Array(pair) = band
+                && pair.len() == 2
+                && let (Some(threshold), Some(sps)) = (pair.get(0), pair.get(1))
+            {
+                let mut t = InlineTable::new();
+                t.insert("threshold_g", threshold.clone());
+                t.insert("sps", sps.clone());
+                t.fmt();
+                let decor = band.decor().clone();
+                *band = Value::InlineTable(t);
+                *band.decor_mut() = decor;
+                n += 1;
+            }
+        }
+        if n > 0 {
+            changes.push(format!(
+                "control.speed_bands: {n} [threshold, sps] pairs -> {{ threshold_g, sps }} tables"
+            ));
+        }
+    }
+    let out = doc.to_string();
+    // A file with includes may hold only part of the config.
+    if doc.contains_key("include") {
+        out.parse::<toml::Table>()
+            .wrap_err("migrated config does not parse")?;
+    } else {
+        doser_config::load_toml(&out).wrap_err("migrated config does not parse")?;
+    }
+    Ok((out, changes))
+}
+
+/// Rename `old` to `new` in `table`, keeping its place and comments; not
+/// when `new` is already set.
+fn rename(table: &mut Table, old: &str, new: &str) -> bool {
+    if !table.contains_key(old) || table.contains_key(new) {
+        return false;
+    }
+    let keys: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+    let mut entries = Vec::with_capacity(keys.len());
+    for k in &keys {
+        let key = table
+            .key(k)
+            .cloned()
+            .unwrap_or_else(|| Key::new(k.as_str()));
+        if let Some(item) = table.remove(k) {
+            entries.push((key, item));
+        }
+    }
+    for (key, item) in entries {
+        let key = if key.get() == old {
+            Key::new(new).with_leaf_decor(key.leaf_decor().clone())
+        } else {
+            key
+        };
+        table.insert_formatted(&key, item);
+    }
+    true
+}
+
+/// `doser config migrate`: rewrite the config file at `path` (not its
+/// includes) in the current schema, or with `dry_run` print the result instead.
+pub fn migrate(path: &Path, dry_run: bool, json: bool) -> eyre::Result<()> {
+    let text = read_file(path)?;
+    let (out, changes) = migrate_text(&text).wrap_err_with(|| format!("migrate {path:?}"))?;
+    let write = !dry_run && !changes.is_empty();
+    if write {
+        fs::write(path, &out).wrap_err_with(|| format!("write {path:?}"))?;
+    }
+    if json {
+        println!(
+            "{}",
+            json!({ "path": path, "changes": changes, "written": write })
+        );
+        return Ok(());
+    }
+    for change in &changes {
+        eprintln!("  {change}");
+    }
+    if dry_run {
+        print!("{out}");
+    } else if write {
+        println!("migrated {} ({} changes)", path.display(), changes.len());
+    } else {
+        println!("{} is already current", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! `self-check --write-filter-defaults`: compare `[filter]` with the profile
+//! for the detected HX711 rate and persist it into the config file. Only the
+//! three profile keys are touched; comments and other sections are kept.
+
+use std::fs;
+use std::path::Path;
+
+use doser_config::FilterCfg;
+use eyre::WrapErr;
+
+/// Whether `cur` already uses the windows and rate of `profile`.
+pub fn matches(cur: &FilterCfg, profile: &FilterCfg) -> bool {
+    cur.ma_window == profile.ma_window
+        && cur.median_window == profile.median_window
+        && cur.sample_rate_hz == profile.sample_rate_hz
+}
+
+/// One-line `key = value` rendering of the profile keys.
+pub fn describe(f: &FilterCfg) -> String {
+    format!(
+        "ma_window = {}, median_window = {}, sample_rate_hz = {}",
+        f.ma_window, f.median_window, f.sample_rate_hz
+    )
+}
+
+/// Write `profile` into the `[filter]` table of the config at `path`.
+pub fn write(path: &Path, profile: &FilterCfg) -> eyre::Result<()> {
+    let text = fs::read_to_string(path).wrap_err_with(|| format!("read {path:?}"))?;
+    let mut doc: toml_edit::DocumentMut =
+        text.parse().wrap_err_with(|| format!("parse {path:?}"))?;
+    let Some(filter) = doc.get_mut("filter").and_then(|f| f.as_table_like_mut()) else {
+        eyre::bail!("{path:?} has no [filter] table");
+    };
+    for (key, value) in [
+        ("ma_window", profile.ma_window as i64),
+        ("median_window", profile.median_window as i64),
+        ("sample_rate_hz", i64::from(profile.sample_rate_hz)),
+    ] {
+        // Keep a trailing comment on an existing key.
+        match filter.get_mut(key).and_then(|i| i.as_value_mut()) {
+            Some(v) => {
+                let decor = v.decor().clone();
+                *v = value.into();
+                *v.decor_mut() = decor;
+            }
+            None => {
+                filter.insert(key, toml_edit::value(value));
+            }
+        }
+    }
+    fs::write(path, doc.to_string()).wrap_err_with(|| format!("write {path:?}"))
+}
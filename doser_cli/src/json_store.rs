@@ -0,0 +1,72 @@
+//! A JSON object kept in one file: the `[learned]` and `[requests]` stores.
+//!
+//! Changes go through [`update`], which holds an exclusive lock on
+//! `<file>.lock` across the read-modify-write, so two processes never
+//! interleave theirs, and replaces the file atomically ([`replace`], also used
+//! for the shutdown marker), so a crash or power loss leaves either the old
+//! object or the new one.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use eyre::WrapErr;
+use serde_json::{Map, Value};
+
+/// The object in `file` (empty when the file does not exist yet); `what`
+/// names the store in errors.
+pub fn load(file: &str, what: &str) -> eyre::Result<Map<String, Value>> {
+    let text = match fs::read_to_string(file) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Map::new()),
+        Err(e) => return Err(e).wrap_err_with(|| format!("read {file:?}")),
+    };
+    match serde_json::from_str(&text) {
+        Ok(Value::Object(m)) => Ok(m),
+        _ => eyre::bail!("{what} {file:?} is not a JSON object"),
+    }
+}
+
+/// Apply `f` to the object in `file` and write it back, under the store's
+/// lock; nothing is written when `f` fails.
+pub fn update<T>(
+    file: &str,
+    what: &str,
+    f: impl FnOnce(&mut Map<String, Value>) -> eyre::Result<T>,
+) -> eyre::Result<T> {
+    let _lock = doser_hardware::LockFile::acquire_wait(format!("{file}.lock"))
+        .wrap_err_with(|| format!("lock {what} {file:?}"))?;
+    let mut all = load(file, what)?;
+    let out = f(&mut all)?;
+    store(file, &all)?;
+    Ok(out)
+}
+
+/// Replace the file with a synced copy.
+fn store(file: &str, all: &Map<String, Value>) -> eyre::Result<()> {
+    replace(
+        Path::new(file),
+        &format!("{:#}\n", Value::Object(all.clone())),
+    )
+}
+
+/// Replace `path` with `text` atomically: write a synced `<path>.tmp`, rename
+/// it over `path`, then sync the directory so the rename itself survives a
+/// power loss. Afterwards the file holds either the old contents or `text`.
+pub fn replace(path: &Path, text: &str) -> eyre::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut f = fs::File::create(&tmp).wrap_err_with(|| format!("create {tmp:?}"))?;
+    f.write_all(text.as_bytes())
+        .and_then(|()| f.sync_all())
+        .wrap_err_with(|| format!("write {tmp:?}"))?;
+    fs::rename(&tmp, path).wrap_err_with(|| format!("replace {path:?}"))?;
+    let dir = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    fs::File::open(dir)
+        .and_then(|d| d.sync_all())
+        .wrap_err_with(|| format!("sync {dir:?}"))
+}
@@ -0,0 +1,293 @@
+//! `[learned]`: constants measured on every completed dose and kept across
+//! runs, one entry per material: grams per motor step (what `doser plan`
+//! needs for durations) and the coast mass that lands after the motor stops.
+//!
+//! The file is a single JSON object keyed by material:
+//! `{"<material>": {"g_per_step", "coast_g", "doses", "updated"}}`. Each dose
+//! moves a value part of the way towards its own measurement: the plain mean
+//! over the first doses, then a running average, so one odd dose does not
+//! undo many good ones.
+//!
+//! With `epsilon_min_g`/`epsilon_max_g` set, the entry also keeps
+//! `epsilon_g`, the completion tolerance the next dose uses, and the errors
+//! (final - target) of the doses since it last changed. Once
+//! `epsilon_window` doses all landed off target the same way, epsilon moves
+//! by their mean bias, at most `epsilon_step_g` and within the bounds: a
+//! material that keeps landing over stops sooner, one that keeps landing
+//! under stops later. Every change is appended to `epsilon_log`.
+
+use doser_config::LearnedCfg;
+use serde_json::{Map, Value, json};
+
+use crate::json_store;
+use doser_app::JsonTelemetry;
+
+/// The store's name in errors.
+const STORE: &str = "learned store";
+
+/// Smallest weight a new dose gets (after five doses).
+const MIN_WEIGHT: f64 = 0.2;
+
+/// Smallest bias that moves epsilon; below it the doses are on target.
+const MIN_NUDGE_G: f64 = 0.001;
+
+/// Epsilon changes kept in `epsilon_log`.
+const MAX_LOG: usize = 50;
+
+/// What has been learned for one material.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Learned {
+    pub g_per_step: Option<f64>,
+    pub coast_g: Option<f64>,
+    pub doses: u64,
+    pub updated: Option<String>,
+    /// Learned completion tolerance (with `epsilon_min_g`/`epsilon_max_g`).
+    pub epsilon_g: Option<f64>,
+    /// Errors (final - target) of the doses since epsilon last changed.
+    pub errors_g: Vec<f64>,
+    /// Past epsilon changes, oldest first.
+    pub epsilon_log: Vec<Value>,
+}
+
+impl Learned {
+    fn from_json(v: &Value) -> Self {
+        let floats = |v: &Value| {
+            v.as_array()
+                .map_or_else(Vec::new, |a| a.iter().filter_map(Value::as_f64).collect())
+        };
+        Self {
+            g_per_step: v["g_per_step"].as_f64(),
+            coast_g: v["coast_g"].as_f64(),
+            doses: v["doses"].as_u64().unwrap_or(0),
+            updated: v["updated"].as_str().map(str::to_string),
+            epsilon_g: v["epsilon_g"].as_f64(),
+            errors_g: floats(&v["errors_g"]),
+            epsilon_log: v["epsilon_log"].as_array().cloned().unwrap_or_default(),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut v = json!({
+            "g_per_step": self.g_per_step,
+            "coast_g": self.coast_g,
+            "doses": self.doses,
+            "updated": self.updated,
+        });
+        if self.epsilon_g.is_some() || !self.errors_g.is_empty() {
+            v["epsilon_g"] = json!(self.epsilon_g);
+            v["errors_g"] = json!(self.errors_g);
+        }
+        if !self.epsilon_log.is_empty() {
+            v["epsilon_log"] = json!(self.epsilon_log);
+        }
+        v
+    }
+
+    /// Fold in the error of a dose that ran with `epsilon_g`; the change of
+    /// epsilon it causes, as logged.
+    fn nudge_epsilon(&mut self, cfg: &LearnedCfg, error_g: f64, epsilon_g: f32) -> Option<Value> {
+        let (min, max) = cfg.epsilon_bounds()?;
+        self.errors_g.push(round4(error_g));
+        let window = cfg.epsilon_window;
+        if self.errors_g.len() > window {
+            self.errors_g.drain(..self.errors_g.len() - window);
+        }
+        if self.errors_g.len() < window {
+            return None;
+        }
+        let bias = self.errors_g.iter().sum::<f64>() / window as f64;
+        let same_way = self.errors_g.iter().all(|e| e.signum() == bias.signum());
+        if !same_way || bias.abs() < MIN_NUDGE_G {
+            return None;
+        }
+        let step = f64::from(cfg.epsilon_step_g);
+        let from = self.epsilon_g.unwrap_or(f64::from(epsilon_g));
+        // Landing over target: stop sooner, i.e. a wider epsilon.
+        let to = (from + bias.clamp(-step, step)).clamp(f64::from(min), f64::from(max));
+        if (to - from).abs() < 1e-6 {
+            // Already at the bound; keep the history for when it moves.
+            return None;
+        }
+        let entry = json!({
+            "at": crate::wallclock::now(),
+            "clock": crate::wallclock::json(),
+            "from_g": round4(from),
+            "to_g": round4(to),
+            "bias_g": round4(bias),
+            "doses": window,
+        });
+        self.epsilon_g = Some(round4(to));
+        self.errors_g.clear();
+        self.epsilon_log.push(entry.clone());
+        if self.epsilon_log.len() > MAX_LOG {
+            self.epsilon_log.remove(0);
+        }
+        Some(entry)
+    }
+
+    /// Fold in one dose that dispensed `dispensed_g`.
+    fn update(&mut self, dispensed_g: f32, tel: &JsonTelemetry) {
+        let w = (1.0 / (self.doses + 1) as f64).max(MIN_WEIGHT);
+        let blend = |old: Option<f64>, new: f64| Some(old.map_or(new, |o| o + w * (new - o)));
+        if let Some(steps) = tel.motor_steps.filter(|&s| s > 0)
+            && dispensed_g > 0.0
+        {
+            self.g_per_step = blend(self.g_per_step, f64::from(dispensed_g) / steps as f64);
+        }
+        if let Some(coast) = tel.coast_g {
+            self.coast_g = blend(self.coast_g, f64::from(coast));
+        }
+        self.doses += 1;
+        self.updated = Some(crate::summary::utc_now());
+    }
+}
+
+/// Grams as stored for epsilon: to 0.1 mg.
+fn round4(g: f64) -> f64 {
+    (g * 1e4).round() / 1e4
+}
+
+/// Every material in the store (empty when the file does not exist yet).
+fn load_all(cfg: &LearnedCfg) -> eyre::Result<Map<String, Value>> {
+    json_store::load(&cfg.file, STORE)
+}
+
+/// Change the store with `f`, under its lock.
+fn update_all<T>(
+    cfg: &LearnedCfg,
+    f: impl FnOnce(&mut Map<String, Value>) -> eyre::Result<T>,
+) -> eyre::Result<T> {
+    json_store::update(&cfg.file, STORE, f)
+}
+
+/// The values learned for this config's material.
+pub fn get(cfg: &LearnedCfg) -> eyre::Result<Learned> {
+    Ok(load_all(cfg)?
+        .get(&cfg.material)
+        .map(Learned::from_json)
+        .unwrap_or_default())
+}
+
+/// The learned epsilon for the next dose, within the configured bounds; `None`
+/// when its adjustment is off or nothing was learned yet. A store that cannot
+/// be read is logged and leaves the configured epsilon.
+pub fn epsilon(cfg: &LearnedCfg) -> Option<f32> {
+    let (min, max) = cfg.epsilon_bounds()?;
+    match get(cfg) {
+        Ok(l) => l.epsilon_g.map(|g| (g as f32).clamp(min, max)),
+        Err(e) => {
+            tracing::warn!(error = %e, "learned epsilon not read; using control.epsilon_g");
+            None
+        }
+    }
+}
+
+/// Fold a completed dose of `target_g` that ran with `epsilon_g` and
+/// dispensed `dispensed_g` into the store. Best-effort: a store that cannot
+/// be written is logged and does not fail the dose.
+pub fn after_dose(
+    cfg: &LearnedCfg,
+    target_g: f32,
+    dispensed_g: f32,
+    epsilon_g: f32,
+    tel: &JsonTelemetry,
+) {
+    let res = update_all(cfg, |all| {
+        let mut l = all
+            .get(&cfg.material)
+            .map(Learned::from_json)
+            .unwrap_or_default();
+        l.update(dispensed_g, tel);
+        let change = l.nudge_epsilon(cfg, f64::from(dispensed_g - target_g), epsilon_g);
+        all.insert(cfg.material.clone(), l.to_json());
+        Ok((l, change))
+    });
+    match res {
+        Ok((l, change)) => {
+            tracing::info!(
+                material = %cfg.material,
+                g_per_step = ?l.g_per_step,
+                coast_g = ?l.coast_g,
+                doses = l.doses,
+                "learned values updated"
+            );
+            if let Some(c) = change {
+                tracing::info!(
+                    material = %cfg.material,
+                    from_g = c["from_g"].as_f64(),
+                    to_g = c["to_g"].as_f64(),
+                    bias_g = c["bias_g"].as_f64(),
+                    "learned epsilon adjusted"
+                );
+            }
+        }
+        Err(e) => tracing::warn!(error = %e, "learned values not updated"),
+    }
+}
+
+/// `doser learned show`: every material in the store.
+pub fn show(cfg: &LearnedCfg, json: bool) -> eyre::Result<()> {
+    let all = load_all(cfg)?;
+    if json {
+        println!("{}", Value::Object(all));
+        return Ok(());
+    }
+    if all.is_empty() {
+        println!("nothing learned yet ({})", cfg.file);
+        return Ok(());
+    }
+    let num = |v: Option<f64>, d: usize| v.map_or_else(|| "-".to_string(), |x| format!("{x:.d$}"));
+    for (material, v) in &all {
+        let l = Learned::from_json(v);
+        let mark = if *material == cfg.material { "*" } else { " " };
+        println!(
+            "{mark} {material}: {} g/step, coast {} g, {} doses, updated {}",
+            num(l.g_per_step, 6),
+            num(l.coast_g, 3),
+            l.doses,
+            l.updated.as_deref().unwrap_or("-")
+        );
+        if l.epsilon_g.is_some() {
+            println!(
+                "    epsilon {} g ({} changes, {} doses since)",
+                num(l.epsilon_g, 3),
+                l.epsilon_log.len(),
+                l.errors_g.len()
+            );
+        }
+        for c in &l.epsilon_log {
+            println!(
+                "    {}: {} -> {} g (bias {} g over {} doses)",
+                c["at"].as_str().unwrap_or("-"),
+                num(c["from_g"].as_f64(), 3),
+                num(c["to_g"].as_f64(), 3),
+                num(c["bias_g"].as_f64(), 3),
+                c["doses"]
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `doser learned reset`: forget this config's material, or every material.
+pub fn reset(cfg: &LearnedCfg, all: bool, json: bool) -> eyre::Result<()> {
+    let removed: Vec<String> = update_all(cfg, |store| {
+        Ok(if all {
+            std::mem::take(store).into_iter().map(|(k, _)| k).collect()
+        } else {
+            store
+                .remove(&cfg.material)
+                .map(|_| cfg.material.clone())
+                .into_iter()
+                .collect()
+        })
+    })?;
+    if json {
+        println!("{}", json!({ "reset": removed }));
+    } else if removed.is_empty() {
+        println!("nothing learned for {}", cfg.material);
+    } else {
+        println!("reset: {}", removed.join(", "));
+    }
+    Ok(())
+}
@@ -14,22 +14,46 @@
 //! - Provide optional RT helpers via libc on supported OSes, with safety docs
 //! - Map domain abort reasons to stable exit codes
 
+mod access;
+mod blend;
+mod build_info;
+mod bundle;
 mod cli;
-mod dose;
-mod error_fmt;
-mod rt;
+mod compare;
+mod config;
+mod filter_defaults;
+mod json_store;
+mod learned;
+mod plan;
+mod requests;
+mod resume;
+mod run_id;
+mod shutdown;
+#[cfg(unix)]
+mod sim_scale;
+mod simulate;
+mod soak;
+mod storage;
+mod summary;
+mod template;
+mod ticket;
 mod tracing_setup;
-
-use std::fs;
+mod tune;
+mod verify_cal;
+mod wallclock;
 
 use clap::Parser;
-use doser_config::{Calibration, Config, load_calibration_csv};
+use doser_config::{Calibration, Config, load_calibration_csv_with};
 use eyre::WrapErr;
 use serde_json::json;
 
-use cli::{Cli, Commands, JSON_MODE};
-use dose::abort_reason_name;
-use error_fmt::{exit_code_for_error, format_error_json, humanize};
+use cli::{Cli, Commands, ConfigAction, JSON_MODE, LearnedAction, StorageAction};
+use doser_app::dose::{self, abort_reason_name};
+use doser_app::error_fmt::{exit_code_for_error, format_error_json, humanize};
+use doser_app::hw::{
+    check_hopper, check_sim_clock, dosing_motor, open_hw, open_scale, pause_counts, select_backend,
+};
+use doser_app::{DoserHandle, JsonTelemetry, config_calibration, core_calibration, procinfo};
 use tracing_setup::init_tracing;
 
 fn main() -> eyre::Result<()> {
@@ -37,17 +61,28 @@ fn main() -> eyre::Result<()> {
     let _ = color_eyre::install();
 
     // Set up graceful shutdown handler
-    let shutdown = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
-    let shutdown_clone = std::sync::Arc::clone(&shutdown);
+    let handle = DoserHandle::new();
+    let on_signal = handle.clone();
 
+    // SIGINT, SIGTERM and SIGHUP: the run halts the motor, runs the safe-state
+    // sequence and records how it ended. A second signal halts the motor
+    // through its stop latch and exits at once.
     if let Err(e) = ctrlc::set_handler(move || {
+        if on_signal.stop_requested() {
+            on_signal.halt();
+            eprintln!("\nReceived second shutdown signal, exiting now");
+            tracing_setup::flush_logs();
+            std::process::exit(130);
+        }
         eprintln!("\nReceived shutdown signal, stopping gracefully...");
-        shutdown_clone.store(true, std::sync::atomic::Ordering::SeqCst);
+        on_signal.stop();
     }) {
         eprintln!("Warning: Failed to set signal handler: {e}");
     }
 
-    if let Err(e) = real_main(shutdown) {
+    let res = real_main(&handle);
+    tracing_setup::flush_logs();
+    if let Err(e) = res {
         let json = *JSON_MODE.get().unwrap_or(&false);
         let code = exit_code_for_error(&e);
         if json {
@@ -60,80 +95,240 @@ fn main() -> eyre::Result<()> {
     Ok(())
 }
 
-fn real_main(shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>) -> eyre::Result<()> {
+fn real_main(handle: &DoserHandle) -> eyre::Result<()> {
     let cli = Cli::parse();
     let _ = JSON_MODE.set(cli.json);
 
-    // 1) Load typed config from TOML (with a size cap so a huge file can't OOM)
-    const MAX_CONFIG_BYTES: u64 = 1 << 20; // 1 MiB; real configs are a few KB.
-    if let Ok(meta) = fs::metadata(&cli.config)
-        && meta.len() > MAX_CONFIG_BYTES
+    // Offline analysis needs neither config nor hardware.
+    if let Commands::Compare { a, b } = &cli.cmd {
+        return compare::run(a, b, cli.json);
+    }
+    if let Commands::Config {
+        action: ConfigAction::Validate { strict },
+    } = cli.cmd
     {
-        eyre::bail!(
-            "config file {:?} is too large ({} bytes > {} byte limit)",
-            cli.config,
-            meta.len(),
-            MAX_CONFIG_BYTES
-        );
+        return config::validate(&cli.config, strict, cli.json);
     }
-    let cfg_text = fs::read_to_string(&cli.config)
-        .wrap_err_with(|| format!("read config {:?}", cli.config))?;
-    let cfg: Config =
-        toml::from_str(&cfg_text).wrap_err_with(|| format!("parse config {:?}", cli.config))?;
+    if let Commands::Version { verbose } = cli.cmd {
+        build_info::run(verbose, cli.json);
+        return Ok(());
+    }
+
+    // 1) Load typed config from TOML
+    let cfg_text = config::read(&cli.config)?;
+    let mut cfg = config::parse(&cli.config, &cfg_text, false)?;
 
     // Validate configuration with clear errors
     cfg.validate().wrap_err("invalid configuration")?;
+    select_backend(&mut cfg, cli.backend)?;
+
+    let pin = cli.pin.clone().or_else(|| std::env::var("DOSER_PIN").ok());
+    access::authorize(cfg.access.as_ref(), &cli, pin.as_deref())?;
+    if let Commands::Config {
+        action: ConfigAction::Migrate { dry_run },
+    } = cli.cmd
+    {
+        return config::migrate(&cli.config, dry_run, cli.json);
+    }
+
+    // A plan needs the config but no hardware.
+    if let Commands::Plan { grams, g_per_step } = cli.cmd {
+        let learned = match (g_per_step, &cfg.learned) {
+            (None, Some(l)) => learned::get(l)?.g_per_step.map(|g| g as f32),
+            _ => None,
+        };
+        if let Some(g) = learned
+            && !cli.json
+        {
+            println!("using learned {g:.6} g/step");
+        }
+        return plan::run(&cfg, grams, g_per_step.or(learned), cli.json);
+    }
+    if let Commands::Simulate {
+        grams,
+        runs,
+        seed,
+        plant,
+        asserts,
+    } = &cli.cmd
+    {
+        let params = simulate::Params {
+            grams: *grams,
+            runs: *runs,
+            seed: *seed,
+            plant: plant.plant(&cfg)?,
+            asserts: asserts.clone(),
+        };
+        return simulate::run(&cfg, &params, cli.json);
+    }
+    if let Commands::Tune {
+        optimize: _,
+        grams,
+        candidates,
+        runs,
+        seed,
+        plant,
+        overshoot_weight,
+        time_weight,
+    } = &cli.cmd
+    {
+        let params = tune::Params {
+            grams: *grams,
+            plant: plant.plant(&cfg)?,
+            search: doser_core::tune::Search {
+                candidates: *candidates,
+                runs: *runs,
+                seed: *seed,
+                weights: doser_core::tune::Weights {
+                    overshoot_per_g: *overshoot_weight,
+                    per_s: *time_weight,
+                },
+            },
+        };
+        return tune::run(&cfg, &params, cli.json);
+    }
+    if let Commands::Learned { action } = cli.cmd {
+        let Some(l) = &cfg.learned else {
+            eyre::bail!("no [learned] section in the config");
+        };
+        return match action {
+            LearnedAction::Show => learned::show(l, cli.json),
+            LearnedAction::Reset { all } => learned::reset(l, all, cli.json),
+        };
+    }
+    if let Commands::Storage { action } = cli.cmd {
+        return match action {
+            StorageAction::Status => storage::status(&cfg, cli.json),
+            StorageAction::Prune => {
+                let removed = storage::prune(&cfg);
+                if cli.json {
+                    println!("{}", json!({ "pruned": removed }));
+                } else {
+                    for p in &removed {
+                        println!("pruned {}", p.display());
+                    }
+                }
+                Ok(())
+            }
+        };
+    }
+    if let Commands::Bundle { out } = &cli.cmd {
+        let dir = out
+            .clone()
+            .or_else(|| cfg.bundle.as_ref().map(|b| b.dir.clone().into()))
+            .unwrap_or_else(|| ".".into());
+        let log_lines = cfg
+            .bundle
+            .as_ref()
+            .map_or_else(doser_config::BundleCfg::default_log_lines, |b| b.log_lines);
+        let incident = bundle::Incident {
+            run_id: None,
+            started_at: None,
+            error: None,
+        };
+        let path = bundle::write(&dir, &cfg, &cfg_text, log_lines, &incident)?;
+        if cli.json {
+            println!("{}", json!({ "bundle": path }));
+        } else {
+            println!("{}", path.display());
+        }
+        return Ok(());
+    }
 
     init_tracing(
         cli.json,
         &cli.log_level,
         cfg.logging.file.as_deref(),
         cfg.logging.rotation.as_deref(),
+        cfg.telemetry.as_ref(),
     );
 
     // 2) Load calibration: prefer persisted in TOML if present; else optional CSV
-    let calib: Option<Calibration> = if let Some(pc) = cfg.calibration {
+    let calib: Option<Calibration> = if let Some(pc) = cfg.calibration.clone() {
         // Use the From impl so the persisted additive `offset_g` is preserved
         // (manual field construction previously dropped it).
         Some(Calibration::from(pc))
     } else if let Some(p) = &cli.calibration {
-        let c =
-            load_calibration_csv(p).map_err(|e| eyre::eyre!("parse calibration {:?}: {}", p, e))?;
+        let c = load_calibration_csv_with(p, cli.calibration_fit)
+            .map_err(|e| eyre::eyre!("parse calibration {:?}: {}", p, e))?;
         Some(c)
     } else {
         None
     };
 
-    // 3) Build hardware (feature-gated) or sim
-    #[cfg(all(feature = "hardware", target_os = "linux"))]
-    let hw = {
-        use doser_hardware::{HardwareMotor, HardwareScale};
-        let scale = HardwareScale::try_new_with_timeout(
-            cfg.pins.hx711_dt,
-            cfg.pins.hx711_sck,
-            cfg.hardware.sensor_read_timeout_ms,
-        )
-        .wrap_err("open HX711")?;
-        let motor = HardwareMotor::try_new_with_en(
-            cfg.pins.motor_step,
-            cfg.pins.motor_dir,
-            cfg.pins.motor_en,
-        )
-        .wrap_err("open motor pins")?;
-        (scale, motor)
+    if let Commands::SimScale {
+        profile,
+        serve,
+        rate_hz,
+        repeat,
+    } = &cli.cmd
+    {
+        #[cfg(unix)]
+        {
+            let params = sim_scale::Params {
+                profile: sim_scale::Profile::load(profile)?,
+                calibration: core_calibration(calib.as_ref()),
+                rate_hz: rate_hz.unwrap_or(cfg.filter.sample_rate_hz),
+                repeat: *repeat,
+            };
+            return sim_scale::serve(serve, params, handle);
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (profile, serve, rate_hz, repeat);
+            eyre::bail!("sim-scale needs Unix sockets");
+        }
+    }
+
+    // Only one dose at a time per lock file; taken before the hardware opens so
+    // a refused run never touches the motor. Released when this returns.
+    let _run_lock = match (&cli.cmd, cfg.runner.lock_file.as_deref()) {
+        (
+            Commands::Dose { .. }
+            | Commands::Resume { .. }
+            | Commands::Soak { .. }
+            | Commands::Blend { .. },
+            Some(path),
+        ) => Some(doser_hardware::LockFile::acquire(path)?),
+        _ => None,
     };
 
-    #[cfg(any(not(feature = "hardware"), not(target_os = "linux")))]
-    // Linked sim pair so the simulated scale responds to the simulated motor.
-    let hw = doser_hardware::sim_pair();
+    if cli.sim_clock {
+        check_sim_clock(&cfg)?;
+    }
+
+    // 3) Build hardware: a plugin backend when configured, else the drivers
+    //    named in config from the registry of compiled-in backends. Commands
+    //    that only weigh open the scale alone; no motor is constructed.
+    let weigh_only = matches!(
+        cli.cmd,
+        Commands::SelfCheck { .. } | Commands::VerifyCal { .. } | Commands::Shadow { .. }
+    );
+    let (scale, motor) = if weigh_only {
+        (open_scale(&cfg)?, None)
+    } else {
+        let (scale, motor) = open_hw(&cfg)?;
+        (scale, Some(motor))
+    };
+    // Re-init counters for telemetry, readable after the scale moves into a run.
+    let scale_retries = scale.stats();
+    // Round human-readable weights to what the scale resolves.
+    let resolution_g = {
+        use doser_traits::Scale;
+        let cal = core_calibration(calib.as_ref());
+        scale.resolution_counts().map(|c| cal.counts_to_g(c))
+    };
+    let decimals = doser_core::util::display_decimals(resolution_g);
 
     match cli.cmd {
-        Commands::SelfCheck => {
+        Commands::SelfCheck {
+            write_filter_defaults,
+        } => {
             tracing::info!("self-check starting");
             use doser_traits::Scale;
             use std::time::{Duration, Instant};
 
-            let (mut scale, _motor) = hw;
+            let mut scale = scale;
 
             // Attempt RT elevation on Linux when built with hardware
             #[cfg(all(target_os = "linux", feature = "hardware", feature = "rt"))]
@@ -208,6 +403,23 @@ fn real_main(shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>) -> eyre::R
             // Classify: <50ms => 80 SPS, else 10 SPS
             let sps = if median_us < 50_000 { 80 } else { 10 };
             println!("Detected HX711 rate: {sps} SPS");
+
+            let profile = doser_config::FilterCfg::for_sps(sps);
+            if filter_defaults::matches(&cfg.filter, &profile) {
+                println!("[filter] matches the {sps} SPS defaults");
+            } else if write_filter_defaults {
+                filter_defaults::write(&cli.config, &profile)?;
+                println!(
+                    "Wrote {sps} SPS filter defaults to {}: {}",
+                    cli.config.display(),
+                    filter_defaults::describe(&profile)
+                );
+            } else {
+                println!(
+                    "[filter] differs from the {sps} SPS defaults ({}); `self-check --write-filter-defaults` applies them",
+                    filter_defaults::describe(&profile)
+                );
+            }
             Ok(())
         }
         Commands::Health => {
@@ -215,7 +427,8 @@ fn real_main(shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>) -> eyre::R
             use doser_traits::{Motor, Scale};
             use std::time::Duration;
 
-            let (mut scale, mut motor) = hw;
+            let mut scale = scale;
+            let mut motor = dosing_motor(motor)?;
 
             let scale_ok = match scale.read(Duration::from_millis(500)) {
                 Ok(raw) => {
@@ -252,6 +465,542 @@ fn real_main(shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>) -> eyre::R
                 Err(eyre::eyre!("Health check failed"))
             }
         }
+        Commands::VerifyCal {
+            weight,
+            tolerance_g,
+        } => {
+            let tolerance_g = tolerance_g.unwrap_or(cfg.verify.tolerance_g);
+            if !weight.is_finite() || weight <= 0.0 {
+                eyre::bail!("--weight must be a positive number of grams");
+            }
+            if !tolerance_g.is_finite() || tolerance_g <= 0.0 {
+                eyre::bail!("--tolerance-g must be a positive number of grams");
+            }
+            let Some(cal) = calib.as_ref() else {
+                eyre::bail!(
+                    "`doser verify-cal` needs a calibration ([calibration] or --calibration)"
+                );
+            };
+            let mut reader = doser_core::WeightReader::new(
+                scale,
+                &(&cfg.filter).into(),
+                core_calibration(Some(cal)),
+                (&cfg.timeouts).into(),
+            );
+
+            verify_cal::wait_for_enter("Clear the scale")?;
+            reader
+                .tare(cfg.verify.samples)
+                .wrap_err("read the empty scale")?;
+            verify_cal::wait_for_enter(&format!(
+                "Place the {weight:.decimals$} g reference weight"
+            ))?;
+            let measured = reader
+                .read_mean_weight(cfg.verify.samples)
+                .wrap_err("read the reference weight")?;
+            let check = verify_cal::check(weight, measured.grams, tolerance_g);
+            tracing::info!(
+                reference_g = check.reference_g,
+                measured_g = check.measured_g,
+                error_g = check.error_g,
+                passed = check.passed,
+                "calibration verified"
+            );
+
+            // Only a calibration persisted in the config has somewhere to keep it.
+            let recorded = cfg.calibration.is_some();
+            if recorded {
+                verify_cal::record(&cli.config, &check)?;
+            }
+            if cli.json {
+                println!(
+                    "{}",
+                    json!({
+                        "at": check.at,
+                        "reference_g": check.reference_g,
+                        "measured_g": check.measured_g,
+                        "sigma_g": measured.sigma_g,
+                        "error_g": check.error_g,
+                        "tolerance_g": check.tolerance_g,
+                        "passed": check.passed,
+                        "recorded": recorded,
+                    })
+                );
+            } else {
+                println!(
+                    "Reference {:.d$} g, measured {:.d$} ± {:.3} g, error {:+.d$} g (tolerance ±{} g): {}",
+                    check.reference_g,
+                    check.measured_g,
+                    measured.sigma_g,
+                    check.error_g,
+                    check.tolerance_g,
+                    if check.passed { "PASS" } else { "FAIL" },
+                    d = decimals
+                );
+                if recorded {
+                    println!(
+                        "Recorded in [calibration.verified] of {}",
+                        cli.config.display()
+                    );
+                } else {
+                    println!("Not recorded: the calibration is not stored in the config");
+                }
+            }
+            if !check.passed {
+                eyre::bail!(
+                    "calibration verification failed: error {:+.d$} g exceeds ±{} g",
+                    check.error_g,
+                    check.tolerance_g,
+                    d = decimals
+                );
+            }
+            Ok(())
+        }
+        Commands::Compare { .. } | Commands::Version { .. } | Commands::Config { .. } => {
+            unreachable!("handled before loading config")
+        }
+        Commands::Plan { .. }
+        | Commands::Simulate { .. }
+        | Commands::Tune { .. }
+        | Commands::Bundle { .. }
+        | Commands::Storage { .. }
+        | Commands::Learned { .. }
+        | Commands::SimScale { .. } => {
+            unreachable!("handled before opening hardware")
+        }
+        Commands::Resume { yes, direct } => {
+            use doser_core::resume::{read_mean_counts, retare};
+            let Some(rc) = &cfg.resume else {
+                eyre::bail!("`doser resume` needs a [resume] section in the config");
+            };
+            if let Some(t) = &cfg.ticket {
+                ticket::check(t)?;
+            }
+            storage::prune(&cfg);
+            let t0 = std::time::Instant::now();
+            let started_at = wallclock::now();
+            let recorded = resume::load(rc)?;
+            let point = recorded.point;
+            let run_id = run_id::new();
+            let _span = tracing::info_span!(
+                "resume",
+                %run_id,
+                resumed_run_id = recorded.run_id.as_deref()
+            )
+            .entered();
+            let cal = core_calibration(calib.as_ref());
+            let mut scale = scale;
+            let motor = dosing_motor(motor)?;
+            let raw = read_mean_counts(
+                &mut scale,
+                rc.samples,
+                std::time::Duration::from_millis(cfg.timeouts.sample_ms),
+            )
+            .wrap_err("read scale before resuming")?;
+            // The reading the dose is measured at: now, or the pause weight when a
+            // change since then is re-zeroed as a container disturbance.
+            let mut counted = raw;
+            if rc.track_zero
+                && let (Some(paused), Some(d)) =
+                    (point.paused_counts, point.disturbance_g(&cal, raw))
+                && d.abs() > cfg.control.epsilon_g
+            {
+                if d.abs() > rc.max_rezero_g {
+                    eprintln!(
+                        "Weight changed by {d:+.decimals$} g while paused; above resume.max_rezero_g, so it counts as dosed."
+                    );
+                } else if yes
+                    || resume::confirm(&format!(
+                        "Weight changed by {d:+.decimals$} g while paused. Re-zero it as a container disturbance?"
+                    ))?
+                {
+                    tracing::info!(disturbance_g = d, "re-zeroed disturbance while paused");
+                    counted = paused;
+                }
+            }
+            let dosed_g = point.dosed_g(&cal, counted);
+            let remaining_g = point.remaining_g(&cal, counted);
+            eprintln!(
+                "Interrupted dose: {dosed_g:.decimals$} of {:.decimals$} g dispensed, {remaining_g:.decimals$} g to go.",
+                point.target_g
+            );
+            if remaining_g <= cfg.control.epsilon_g {
+                resume::clear(rc);
+                println!("final: {dosed_g:.decimals$} g");
+                return Ok(());
+            }
+            if !yes && !resume::confirm("Re-tare at the current weight and continue?")? {
+                eyre::bail!("resume cancelled; the interrupted dose is still recorded");
+            }
+            let retared = retare(&cal, raw);
+            let retared = Calibration {
+                offset: retared.zero_counts,
+                scale_factor: retared.gain_g_per_count,
+                offset_g: retared.offset_g,
+                uncertainty: None,
+            };
+            let use_direct = direct || matches!(cfg.runner.mode, doser_config::RunMode::Direct);
+            tracing::info!(
+                target_g = point.target_g,
+                dosed_g,
+                remaining_g,
+                "resuming dose"
+            );
+            if let Some(s) = &cfg.shutdown {
+                shutdown::begin(s, &run_id)?;
+            }
+            let res = check_hopper(&cfg, remaining_g).and_then(|()| {
+                dose::run_dose(
+                    &cfg,
+                    Some(&retared),
+                    remaining_g,
+                    (scale, motor),
+                    dose::DoseOptions {
+                        direct: use_direct,
+                        sim_clock: cli.sim_clock,
+                        progress: warmup_progress(),
+                        ..Default::default()
+                    },
+                    handle,
+                )
+            });
+            if let Some(s) = &cfg.shutdown {
+                shutdown::finish(
+                    s,
+                    &run_id,
+                    shutdown::Outcome::of(&res, handle.stop_requested()),
+                );
+            }
+            match res {
+                Ok((final_g, tel)) => {
+                    resume::clear(rc);
+                    let total_g = dosed_g + final_g;
+                    let operator = cfg.ticket.as_ref().and_then(|t| t.operator.as_deref());
+                    let ticket = emit_ticket(
+                        &cfg,
+                        &summary::DoseSummary {
+                            target_g: point.target_g,
+                            final_g: total_g,
+                            duration_ms: t0.elapsed().as_millis() as u64,
+                            operator,
+                            run_id: &run_id,
+                            // The hold was weighed against the re-tare.
+                            tel: JsonTelemetry {
+                                hold_g: tel.hold_g.map(|h| dosed_g + h),
+                                display_g: tel.display_g.map(|g| dosed_g + g),
+                                ..tel
+                            },
+                            decimals,
+                        },
+                        cli.json,
+                    );
+                    if cli.json {
+                        let mut obj = json!({
+                            "run_id": run_id,
+                            "resumed_run_id": recorded.run_id,
+                            "target_g": point.target_g,
+                            "final_g": total_g,
+                            "resumed_from_g": dosed_g,
+                            "started_at": started_at,
+                            "ended_at": wallclock::now(),
+                            "clock": wallclock::json(),
+                            "build": build_info::json(),
+                        });
+                        if let Some(text) = ticket {
+                            obj["ticket"] = text.into();
+                        }
+                        println!("{obj}");
+                    } else {
+                        println!("final: {total_g:.decimals$} g");
+                        if let Some(text) = ticket {
+                            println!("{text}");
+                        }
+                    }
+                    Ok(())
+                }
+                Err(e) => {
+                    if let Some(path) =
+                        bundle::after_failure(&cfg, &cfg_text, &run_id, &started_at, &e)
+                        && !cli.json
+                    {
+                        eprintln!("Diagnostic bundle: {}", path.display());
+                    }
+                    if doser_core::resume::is_resumable(&e) {
+                        eprintln!(
+                            "Dose interrupted again; the original target is still recorded for `doser resume`."
+                        );
+                    } else {
+                        resume::clear(rc);
+                    }
+                    Err(e)
+                }
+            }
+        }
+        Commands::Soak {
+            cycles,
+            target,
+            direct,
+            pause_ms,
+            max_drift_g,
+            max_rss_growth_kb,
+            max_jitter_ratio,
+        } => {
+            use doser_core::resume::{read_mean_counts, retare};
+            if cycles < 2 {
+                eyre::bail!("soak needs at least 2 cycles");
+            }
+            let use_direct = direct || matches!(cfg.runner.mode, doser_config::RunMode::Direct);
+            let cal = core_calibration(calib.as_ref());
+            let sample_timeout = std::time::Duration::from_millis(cfg.timeouts.sample_ms);
+            let mut hw = Some((scale, dosing_motor(motor)?));
+            let mut results = Vec::with_capacity(cycles as usize);
+            for cycle in 1..=cycles {
+                if handle.stop_requested() {
+                    eyre::bail!("soak interrupted after {} cycle(s)", cycle - 1);
+                }
+                let run_id = run_id::new();
+                let _span = tracing::info_span!("soak", cycle, %run_id).entered();
+                // Each dose consumes the drivers; reopen them for the next.
+                let (mut scale, motor) = match hw.take() {
+                    Some(hw) => hw,
+                    None => open_hw(&cfg)?,
+                };
+                // Dose from whatever the receptacle holds now.
+                let raw = read_mean_counts(&mut scale, 5, sample_timeout)
+                    .wrap_err_with(|| format!("soak cycle {cycle}: tare"))?;
+                let retared = retare(&cal, raw);
+                let retared = Calibration {
+                    offset: retared.zero_counts,
+                    scale_factor: retared.gain_g_per_count,
+                    offset_g: retared.offset_g,
+                    uncertainty: None,
+                };
+                let (final_g, tel) = check_hopper(&cfg, target)
+                    .and_then(|()| {
+                        dose::run_dose(
+                            &cfg,
+                            Some(&retared),
+                            target,
+                            (scale, motor),
+                            dose::DoseOptions {
+                                direct: use_direct,
+                                stats: dose::Stats::Collect,
+                                sim_clock: cli.sim_clock,
+                                progress: warmup_progress(),
+                                ..Default::default()
+                            },
+                            handle,
+                        )
+                    })
+                    .inspect_err(|_| eprintln!("Soak stopped: dose {cycle} of {cycles} failed."))?;
+                let c = soak::Cycle {
+                    error_g: final_g - target,
+                    jitter_us: tel.jitter_us.unwrap_or(0.0),
+                    rss_kb: procinfo::rss_kb(),
+                };
+                tracing::info!(
+                    cycle,
+                    error_g = c.error_g,
+                    jitter_us = c.jitter_us,
+                    rss_kb = c.rss_kb,
+                    "soak cycle"
+                );
+                results.push(c);
+                if pause_ms > 0 && cycle < cycles {
+                    std::thread::sleep(std::time::Duration::from_millis(pause_ms));
+                }
+            }
+            let r = soak::evaluate(
+                &results,
+                &soak::Limits {
+                    max_drift_g,
+                    max_rss_growth_kb,
+                    max_jitter_ratio,
+                },
+            );
+            if cli.json {
+                let obj = json!({
+                    "cycles": r.cycles,
+                    "error_first_g": r.error_first_g,
+                    "error_last_g": r.error_last_g,
+                    "drift_g": r.drift_g(),
+                    "rss_first_kb": r.rss_first_kb,
+                    "rss_last_kb": r.rss_last_kb,
+                    "jitter_first_us": r.jitter_first_us,
+                    "jitter_last_us": r.jitter_last_us,
+                    "failures": r.failures,
+                });
+                println!("{obj}");
+            } else {
+                println!("soak: {} cycles of {target} g", r.cycles);
+                println!(
+                    "final error: {:+.3} g -> {:+.3} g (drift {:+.3} g)",
+                    r.error_first_g,
+                    r.error_last_g,
+                    r.drift_g()
+                );
+                match (r.rss_first_kb, r.rss_last_kb, r.rss_growth_kb()) {
+                    (Some(a), Some(b), Some(d)) => println!("RSS: {a} kB -> {b} kB ({d:+} kB)"),
+                    _ => println!("RSS: unavailable"),
+                }
+                println!(
+                    "jitter: {:.1} us -> {:.1} us",
+                    r.jitter_first_us, r.jitter_last_us
+                );
+            }
+            if !r.failures.is_empty() {
+                eyre::bail!("soak failed: {}", r.failures.join("; "));
+            }
+            if !cli.json {
+                println!("soak: OK");
+            }
+            Ok(())
+        }
+        Commands::Blend {
+            parts,
+            yes,
+            direct,
+            settle_tolerance_g,
+            settle_wait_ms,
+        } => {
+            use doser_core::resume::retare;
+            blend::check_parts(&parts)?;
+            if let Some(t) = &cfg.ticket {
+                ticket::check(t)?;
+            }
+            let run_id = run_id::new();
+            let _span = tracing::info_span!("blend", %run_id).entered();
+            let use_direct = direct || matches!(cfg.runner.mode, doser_config::RunMode::Direct);
+            let cal = core_calibration(calib.as_ref());
+            let sample_timeout = std::time::Duration::from_millis(cfg.timeouts.sample_ms);
+            let settle_wait = std::time::Duration::from_millis(settle_wait_ms);
+            let mut hw = Some((scale, dosing_motor(motor)?));
+            let mut done: Vec<blend::Component> = Vec::with_capacity(parts.len());
+            for part in &parts {
+                let total_g = done.last().map_or(0.0, |c| c.total_g);
+                if handle.stop_requested() {
+                    eyre::bail!("blend interrupted before {}", part.name);
+                }
+                if !done.is_empty()
+                    && !yes
+                    && !resume::confirm(&format!("Switch the feed to {} and continue?", part.name))?
+                {
+                    eyre::bail!("blend cancelled before {}", part.name);
+                }
+                let grams = part.total_g - total_g;
+                if grams <= cfg.control.epsilon_g {
+                    eyre::bail!(
+                        "{} is already at {total_g:.decimals$} g of its {} g total",
+                        part.name,
+                        part.total_g
+                    );
+                }
+                let _part_span = tracing::info_span!("component", material = %part.name).entered();
+                // Each dose consumes the drivers; reopen them for the next.
+                let (mut scale, motor) = match hw.take() {
+                    Some(hw) => hw,
+                    None => open_hw(&cfg)?,
+                };
+                // The first component starts on the container, once placed.
+                let placed = if done.is_empty() {
+                    container_tare(&cfg, &mut scale, calib.as_ref(), cli.json)?
+                } else {
+                    None
+                };
+                let settled = match placed {
+                    Some(p) => blend::Settled {
+                        raw: p.raw,
+                        spread_g: p.spread_g,
+                    },
+                    None => blend::wait_settled(
+                        &mut scale,
+                        &cal,
+                        settle_tolerance_g,
+                        sample_timeout,
+                        settle_wait,
+                    )
+                    .wrap_err_with(|| format!("before {}", part.name))?,
+                };
+                let retared = config_calibration(&retare(&cal, settled.raw));
+                let (dosed_g, _tel) = check_hopper(&cfg, grams)
+                    .and_then(|()| {
+                        dose::run_dose(
+                            &cfg,
+                            Some(&retared),
+                            grams,
+                            (scale, motor),
+                            dose::DoseOptions {
+                                direct: use_direct,
+                                sim_clock: cli.sim_clock,
+                                progress: warmup_progress(),
+                                ..Default::default()
+                            },
+                            handle,
+                        )
+                    })
+                    .inspect_err(|_| eprintln!("Blend stopped: {} failed.", part.name))?;
+                let c = blend::Component {
+                    name: part.name.clone(),
+                    target_total_g: part.total_g,
+                    dosed_g,
+                    total_g: total_g + dosed_g,
+                    settle_spread_g: settled.spread_g,
+                };
+                tracing::info!(
+                    dosed_g,
+                    total_g = c.total_g,
+                    error_g = c.error_g(),
+                    settle_spread_g = c.settle_spread_g,
+                    "blend component"
+                );
+                if !cli.json {
+                    println!(
+                        "{}: {dosed_g:.decimals$} g, total {:.decimals$} of {} g",
+                        c.name, c.total_g, c.target_total_g
+                    );
+                }
+                done.push(c);
+            }
+            blend::print(&done, &run_id, cli.json, decimals);
+            Ok(())
+        }
+        Commands::Shadow {
+            grams,
+            max_run_ms,
+            flow_threshold_gps,
+        } => {
+            // The controller commands a probe motor; only the scale is real.
+            let r = dose::run_shadow(
+                &cfg,
+                calib.as_ref(),
+                grams,
+                max_run_ms,
+                flow_threshold_gps,
+                scale,
+                handle.shutdown_flag(),
+            )?;
+            if cli.json {
+                let obj = json!({
+                    "target_g": grams,
+                    "would_stop_at_g": r.would_stop_at_g,
+                    "last_g": r.last_g,
+                    "divergent_ms": r.divergent_ms,
+                    "episodes": r.episodes,
+                });
+                println!("{obj}");
+            } else {
+                match r.would_stop_at_g {
+                    Some(g) => println!("controller would stop at: {g:.decimals$} g"),
+                    None => println!("controller would not have stopped"),
+                }
+                println!("external final: {:.decimals$} g", r.last_g);
+                println!(
+                    "divergence: {} episode(s), {} ms total",
+                    r.episodes, r.divergent_ms
+                );
+            }
+            Ok(())
+        }
         Commands::Dose {
             grams,
             max_run_ms,
@@ -263,7 +1012,50 @@ fn real_main(shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>) -> eyre::R
             rt_lock,
             rt_cpu,
             stats,
+            operator,
+            format,
+            request_id,
+            strict,
         } => {
+            if let Some(eps) = cfg.learned.as_ref().and_then(learned::epsilon) {
+                tracing::info!(
+                    epsilon_g = eps,
+                    configured_g = cfg.control.epsilon_g,
+                    "using learned epsilon"
+                );
+                cfg.control.epsilon_g = eps;
+            }
+            check_tolerance(calib.as_ref(), &cfg.control, grams, strict)?;
+            if let Some(t) = &cfg.ticket {
+                ticket::check(t)?;
+            }
+            storage::prune(&cfg);
+            if let Some(f) = &format {
+                if cli.json {
+                    eyre::bail!("--format cannot be combined with --json");
+                }
+                template::check(f, summary::FIELDS).wrap_err("invalid --format template")?;
+            }
+            let operator = operator
+                .as_deref()
+                .or_else(|| cfg.ticket.as_ref().and_then(|t| t.operator.as_deref()));
+            let run_id = run_id::new();
+            let _span = tracing::info_span!("dose", %run_id).entered();
+            let request = match &request_id {
+                Some(id) => {
+                    let rc = cfg
+                        .requests
+                        .as_ref()
+                        .ok_or_else(|| eyre::eyre!("--request-id needs a [requests] section"))?;
+                    match requests::claim(rc, id, grams, &run_id)? {
+                        requests::Claim::Done(entry) => {
+                            return requests::replay(id, &entry, cli.json, decimals);
+                        }
+                        requests::Claim::New(pending) => Some(pending),
+                    }
+                }
+                None => None,
+            };
             let use_direct = if direct {
                 true
             } else {
@@ -272,28 +1064,86 @@ fn real_main(shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>) -> eyre::R
                     doser_config::RunMode::Direct => true,
                 }
             };
+            // A new dose supersedes any interrupted one.
+            if let Some(r) = &cfg.resume {
+                resume::clear(r);
+            }
+            let mut scale = scale;
+            let tared = container_tare(&cfg, &mut scale, calib.as_ref(), cli.json)?
+                .map(|p| config_calibration(&p.tare(&core_calibration(calib.as_ref()))));
+            let calib = tared.or(calib);
+            let resume_point =
+                doser_core::resume::ResumePoint::new(grams, &core_calibration(calib.as_ref()));
+            if let Some(s) = &cfg.shutdown {
+                shutdown::begin(s, &run_id)?;
+            }
+            // Until here nothing moved and an error released the request.
+            let request = request.map(requests::Pending::start);
             let t0 = std::time::Instant::now();
-            let res = dose::run_dose(
-                &cfg,
-                calib.as_ref(),
-                grams,
-                max_run_ms,
-                max_overshoot_g,
-                use_direct,
-                hw,
-                rt,
-                rt_prio,
-                rt_lock,
-                rt_cpu,
-                stats,
-                shutdown,
-            );
+            let started_at = wallclock::now();
+            let res = check_hopper(&cfg, grams).and_then(|()| {
+                dose::run_dose(
+                    &cfg,
+                    calib.as_ref(),
+                    grams,
+                    (scale, dosing_motor(motor)?),
+                    dose::DoseOptions {
+                        max_run_ms,
+                        max_overshoot_g,
+                        direct: use_direct,
+                        rt,
+                        rt_prio,
+                        rt_lock,
+                        rt_cpu,
+                        stats: if stats {
+                            dose::Stats::Print
+                        } else {
+                            dose::Stats::Off
+                        },
+                        sim_clock: cli.sim_clock,
+                        progress: warmup_progress(),
+                    },
+                    handle,
+                )
+            });
+            if let Some(s) = &cfg.shutdown {
+                shutdown::finish(
+                    s,
+                    &run_id,
+                    shutdown::Outcome::of(&res, handle.stop_requested()),
+                );
+            }
+            if let Some(r) = request {
+                r.finish(match &res {
+                    Ok((final_g, _)) => requests::completed(*final_g),
+                    Err(e) => requests::failed(e),
+                });
+            }
             match res {
                 Ok((final_g, tel)) => {
+                    if let Some(l) = &cfg.learned {
+                        learned::after_dose(l, grams, final_g, cfg.control.epsilon_g, &tel);
+                    }
+                    // The result line shows no more digits than the reading noise
+                    // leaves meaningful; tickets and templates keep a fixed format.
+                    let final_decimals = doser_core::util::display_decimals_for(
+                        resolution_g,
+                        tel.sigma_g.unwrap_or(0.0),
+                    );
                     if print_runtime {
                         let ms = t0.elapsed().as_millis();
                         eprintln!("runtime: {ms} ms");
                     }
+                    let summary = summary::DoseSummary {
+                        target_g: grams,
+                        final_g,
+                        duration_ms: t0.elapsed().as_millis() as u64,
+                        operator,
+                        run_id: &run_id,
+                        tel,
+                        decimals,
+                    };
+                    let ticket = emit_ticket(&cfg, &summary, cli.json);
                     if cli.json {
                         use std::time::{SystemTime, UNIX_EPOCH};
                         let ts_ms = SystemTime::now()
@@ -302,7 +1152,8 @@ fn real_main(shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>) -> eyre::R
                             .unwrap_or(0);
                         let profile =
                             std::env::var("PROFILE").unwrap_or_else(|_| "debug".to_string());
-                        let obj = json!({
+                        let mut obj = json!({
+                            "run_id": run_id,
                             "timestamp": ts_ms,
                             "target_g": format!("{grams:.3}").parse::<f64>().unwrap_or(0.0),
                             "final_g": format!("{final_g:.3}").parse::<f64>().unwrap_or(0.0),
@@ -311,15 +1162,65 @@ fn real_main(shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>) -> eyre::R
                             "slope_ema": tel.slope_ema_gps,
                             "stop_at_g": tel.stop_at_g,
                             "coast_comp_g": tel.coast_comp_g,
-                            "abort_reason": serde_json::Value::Null
+                            "undershoot_g": tel.undershoot_g,
+                            "hold_g": tel.hold_g,
+                            "display_g": tel.display_g,
+                            "sigma_g": tel.sigma_g,
+                            "phases": tel.phases.map(|p| json!({
+                                "coarse_ms": p.coarse_ms,
+                                "fine_ms": p.fine_ms,
+                                "settle_ms": p.settle_ms,
+                                "verify_ms": p.verify_ms,
+                            })),
+                            "scale_reinits": scale_retries.reinits(),
+                            "scale_recovered": scale_retries.recovered(),
+                            "read_retries": tel.read_retries,
+                            "reads_recovered": tel.reads_recovered,
+                            "attempts": tel.attempts,
+                            "abort_reason": serde_json::Value::Null,
+                            "started_at": started_at,
+                            "ended_at": wallclock::now(),
+                            "clock": wallclock::json(),
+                            "build": build_info::json(),
                         });
+                        if let Some(text) = ticket {
+                            obj["ticket"] = text.into();
+                        }
                         println!("{obj}");
                     } else {
-                        println!("final: {final_g:.2} g");
+                        match &format {
+                            Some(f) => println!("{}", template::render(f, |n| summary.field(n))?),
+                            None => println!("final: {final_g:.final_decimals$} g"),
+                        }
+                        if let Some(text) = ticket {
+                            println!("{text}");
+                        }
                     }
                     Ok(())
                 }
                 Err(e) => {
+                    let bundle = bundle::after_failure(&cfg, &cfg_text, &run_id, &started_at, &e);
+                    if let Some(path) = &bundle
+                        && !cli.json
+                    {
+                        eprintln!("Diagnostic bundle: {}", path.display());
+                    }
+                    if let Some(r) = &cfg.resume
+                        && doser_core::resume::is_resumable(&e)
+                    {
+                        let resume_point = doser_core::resume::ResumePoint {
+                            paused_counts: pause_counts(&cfg, r.samples),
+                            ..resume_point
+                        };
+                        match resume::record(r, &resume_point, &run_id) {
+                            Ok(()) => eprintln!(
+                                "Dose interrupted by a sensor timeout; `doser resume` finishes it."
+                            ),
+                            Err(re) => {
+                                tracing::warn!(error = %re, "could not record dose for resume")
+                            }
+                        }
+                    }
                     if cli.json {
                         use std::time::{SystemTime, UNIX_EPOCH};
                         let ts_ms = SystemTime::now()
@@ -332,10 +1233,13 @@ fn real_main(shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>) -> eyre::R
                             e.downcast_ref::<doser_core::error::DoserError>()
                         {
                             abort_reason_name(reason)
+                        } else if e.is::<doser_core::error::PreflightError>() {
+                            "Preflight"
                         } else {
                             "Error"
                         };
                         let obj = json!({
+                            "run_id": run_id,
                             "timestamp": ts_ms,
                             "target_g": format!("{grams:.3}").parse::<f64>().unwrap_or(0.0),
                             "final_g": serde_json::Value::Null,
@@ -344,7 +1248,15 @@ fn real_main(shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>) -> eyre::R
                             "slope_ema": serde_json::Value::Null,
                             "stop_at_g": serde_json::Value::Null,
                             "coast_comp_g": serde_json::Value::Null,
-                            "abort_reason": abort
+                            "undershoot_g": serde_json::Value::Null,
+                            "phases": serde_json::Value::Null,
+                            "scale_reinits": scale_retries.reinits(),
+                            "scale_recovered": scale_retries.recovered(),
+                            "abort_reason": abort,
+                            "started_at": started_at,
+                            "ended_at": wallclock::now(),
+                            "clock": wallclock::json(),
+                            "bundle": bundle,
                         });
                         println!("{obj}");
                     }
@@ -354,3 +1266,99 @@ fn real_main(shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>) -> eyre::R
         }
     }
 }
+
+/// Warn, or with `strict` refuse, when the completion tolerance
+/// (`max(epsilon_g, hysteresis_g)`) is tighter than the calibration's
+/// uncertainty supports for a dose of `grams`. Silent when the uncertainty is
+/// unknown (persisted or two-point calibrations).
+/// Show scale warm-up progress on stderr.
+fn warmup_progress() -> doser_core::warmup::ProgressSink {
+    use doser_core::warmup::WarmupEvent;
+    doser_core::warmup::ProgressSink::new(|ev| match ev {
+        WarmupEvent::Started => eprintln!("stabilizing…"),
+        WarmupEvent::Progress { .. } => {}
+        WarmupEvent::Done {
+            samples,
+            elapsed_ms,
+            stable: true,
+        } => eprintln!("scale stable ({samples} samples, {elapsed_ms} ms)"),
+        WarmupEvent::Done { elapsed_ms, .. } => {
+            eprintln!("scale still settling after {elapsed_ms} ms; continuing")
+        }
+    })
+}
+
+fn check_tolerance(
+    calib: Option<&Calibration>,
+    control: &doser_config::ControlCfg,
+    grams: f32,
+    strict: bool,
+) -> eyre::Result<()> {
+    let Some(min_g) = calib.and_then(|c| c.min_tolerance_g(grams)) else {
+        return Ok(());
+    };
+    let tolerance_g = control.epsilon_g.max(control.hysteresis_g);
+    if tolerance_g >= min_g {
+        return Ok(());
+    }
+    let msg = format!(
+        "completion tolerance {tolerance_g} g is tighter than the calibration supports for {grams} g (±{min_g:.4} g at 95 %)"
+    );
+    if strict {
+        eyre::bail!("{msg}; recalibrate with more or better points, or widen control.epsilon_g");
+    }
+    tracing::warn!(
+        tolerance_g,
+        min_tolerance_g = min_g,
+        "completion tolerance is tighter than the calibration uncertainty"
+    );
+    eprintln!("Warning: {msg}");
+    Ok(())
+}
+
+/// Render the `[ticket]` for a completed dose and send it to its device. Without
+/// a device the text is returned for the caller to print (or embed in JSON).
+/// A ticket failure is reported but does not fail the dose that already ran.
+fn emit_ticket(cfg: &Config, dose: &summary::DoseSummary<'_>, json: bool) -> Option<String> {
+    let tc = cfg.ticket.as_ref()?;
+    let text = ticket::render(tc, dose)
+        .map_err(|e| tracing::error!(error = %e, "ticket render failed"))
+        .ok()?;
+    let Some(dev) = &tc.device else {
+        return Some(text);
+    };
+    if let Err(e) = ticket::print(dev, &text) {
+        tracing::error!(error = %e, "ticket print failed");
+        if !json {
+            eprintln!("Warning: dose complete, but the ticket was not printed: {e:#}");
+        }
+    }
+    None
+}
+
+/// With `[autotare]`, wait for the container to settle and return `calib`
+/// zeroed on it; `None` without the section.
+fn container_tare<S: doser_traits::Scale + ?Sized>(
+    cfg: &Config,
+    scale: &mut S,
+    calib: Option<&Calibration>,
+    json: bool,
+) -> eyre::Result<Option<doser_core::autotare::Plateau>> {
+    let Some(at) = &cfg.autotare else {
+        return Ok(None);
+    };
+    if !json {
+        eprintln!("Waiting for a container...");
+    }
+    let p = doser_core::autotare::wait_for_container(
+        scale,
+        &core_calibration(calib),
+        &at.into(),
+        cfg.filter.sample_rate_hz,
+        std::time::Duration::from_millis(cfg.timeouts.sample_ms),
+    )?;
+    if !json {
+        eprintln!("Container: {:.2} g, tared.", p.container_g);
+    }
+    Ok(Some(p))
+}
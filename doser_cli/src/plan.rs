@@ -0,0 +1,77 @@
+//! `doser plan`: preview the speed bands a dose would run through, with
+//! estimated durations when the flow per motor step is known, so a tuning can
+//! be checked before anything is dispensed. Needs no hardware.
+
+use doser_config::Config;
+use doser_core::plan::{SpeedPlan, plan};
+use serde_json::json;
+
+pub fn run(cfg: &Config, grams: f32, g_per_step: Option<f32>, json: bool) -> eyre::Result<()> {
+    if !(grams.is_finite() && grams > 0.0) {
+        eyre::bail!("--grams must be a positive number of grams");
+    }
+    if let Some(g) = g_per_step
+        && !(g.is_finite() && g > 0.0)
+    {
+        eyre::bail!("--g-per-step must be a positive number of grams");
+    }
+    let control = doser_core::ControlCfg::from(&cfg.control);
+    let p = plan(&control, grams, g_per_step);
+    if json {
+        println!("{}", to_json(&p, g_per_step));
+    } else {
+        print!("{}", to_text(&p));
+    }
+    Ok(())
+}
+
+fn to_json(p: &SpeedPlan, g_per_step: Option<f32>) -> serde_json::Value {
+    let steps: Vec<_> = p
+        .steps
+        .iter()
+        .map(|s| {
+            json!({
+                "from_g": s.from_g,
+                "to_g": s.to_g,
+                "sps": s.sps,
+                "exit_sps": s.exit_sps,
+                "est_ms": s.est_ms,
+            })
+        })
+        .collect();
+    json!({
+        "target_g": p.target_g,
+        "stop_at_g": p.stop_at_g,
+        "g_per_step": g_per_step,
+        "steps": steps,
+        "settle_ms": p.settle_ms,
+        "est_ms": p.est_ms,
+    })
+}
+
+fn to_text(p: &SpeedPlan) -> String {
+    let mut out = format!(
+        "plan: {:.2} g, motor stops at {:.2} g\n",
+        p.target_g, p.stop_at_g
+    );
+    for s in &p.steps {
+        let speed = if s.exit_sps == s.sps {
+            format!("{} sps", s.sps)
+        } else {
+            format!("{}→{} sps", s.sps, s.exit_sps)
+        };
+        let time = s
+            .est_ms
+            .map_or_else(String::new, |ms| format!("  ~{ms} ms"));
+        out.push_str(&format!(
+            "  {:>8.2} → {:>8.2} g  {speed:>14}{time}\n",
+            s.from_g, s.to_g
+        ));
+    }
+    out.push_str(&format!("  settle {} ms\n", p.settle_ms));
+    match p.est_ms {
+        Some(ms) => out.push_str(&format!("estimated: ~{ms} ms\n")),
+        None => out.push_str("estimated: unknown (pass --g-per-step for durations)\n"),
+    }
+    out
+}
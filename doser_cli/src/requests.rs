@@ -0,0 +1,214 @@
+//! `[requests]`: at-most-once doses under a client-supplied request id.
+//!
+//! A PLC that loses the connection mid-dose retries with the same id. The
+//! file is a single JSON object keyed by id:
+//! `{"<id>": {"state", "run_id", "grams", "started", "ended", "result"}}`.
+//! An id is claimed (`"running"`) before anything moves and settled
+//! (`"done"`, with the result) once the dose returns. A retry of a settled id
+//! gets the recorded result; a retry of one still `"running"` (the process
+//! died mid-dose) is refused, since material may already have been dispensed.
+//! A claim given up before the dose starts (no container, a failed
+//! pre-dose step) is released, so the retry doses.
+//!
+//! Claims and results are written under the store's lock, synced (see
+//! [`json_store`]), so concurrent retries cannot both see an id as new.
+
+use doser_config::RequestsCfg;
+use serde_json::{Map, Value, json};
+
+use crate::json_store;
+
+/// Longest accepted request id.
+const MAX_ID_LEN: usize = 128;
+
+/// The store's name in errors.
+const STORE: &str = "request store";
+
+/// What `claim` found for an id.
+#[derive(Debug)]
+pub enum Claim<'a> {
+    /// The id is new and now recorded as running.
+    New(Pending<'a>),
+    /// The id already ran; its recorded entry.
+    Done(Value),
+}
+
+/// A claimed request whose dose has not started. Dropped, the claim is
+/// released: nothing moved, so a retry may dose.
+#[derive(Debug)]
+pub struct Pending<'a> {
+    cfg: &'a RequestsCfg,
+    id: &'a str,
+}
+
+impl<'a> Pending<'a> {
+    /// The dose is about to start: from here the claim is only settled by
+    /// [`Started::finish`], never released.
+    pub fn start(self) -> Started<'a> {
+        let started = Started {
+            cfg: self.cfg,
+            id: self.id,
+        };
+        std::mem::forget(self);
+        started
+    }
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        let res = json_store::update(&self.cfg.file, STORE, |all| {
+            all.remove(self.id);
+            Ok(())
+        });
+        match res {
+            Ok(()) => tracing::info!(request_id = self.id, "request released before dosing"),
+            Err(e) => tracing::warn!(error = %e, request_id = self.id, "request not released"),
+        }
+    }
+}
+
+/// A claimed request whose dose ran (or may have).
+#[derive(Debug)]
+pub struct Started<'a> {
+    cfg: &'a RequestsCfg,
+    id: &'a str,
+}
+
+impl Started<'_> {
+    /// Record the dose's result (see [`finish`]).
+    pub fn finish(self, result: Value) {
+        finish(self.cfg, self.id, result);
+    }
+}
+
+/// Claim `id` for a dose of `grams` run as `run_id`, unless it already ran.
+/// Fails for an id still marked running or reused for another target.
+pub fn claim<'a>(
+    cfg: &'a RequestsCfg,
+    id: &'a str,
+    grams: f32,
+    run_id: &str,
+) -> eyre::Result<Claim<'a>> {
+    if id.is_empty() || id.len() > MAX_ID_LEN {
+        eyre::bail!("--request-id must be 1..={MAX_ID_LEN} characters");
+    }
+    let done = json_store::update(&cfg.file, STORE, |all| {
+        claim_in(all, cfg, id, grams, run_id)
+    })?;
+    // Only once the claim is stored: a `Pending` dropped earlier would release it.
+    Ok(match done {
+        Some(entry) => Claim::Done(entry),
+        None => Claim::New(Pending { cfg, id }),
+    })
+}
+
+/// Record `id` as running in `all`; the entry of an id that already ran.
+fn claim_in(
+    all: &mut Map<String, Value>,
+    cfg: &RequestsCfg,
+    id: &str,
+    grams: f32,
+    run_id: &str,
+) -> eyre::Result<Option<Value>> {
+    if let Some(prev) = all.get(id) {
+        let prev_run = prev["run_id"].as_str().unwrap_or("unknown");
+        if prev["grams"].as_f64() != Some(round3(grams)) {
+            eyre::bail!(
+                "request {id} was already used by run {prev_run} for {} g",
+                prev["grams"]
+            );
+        }
+        if prev["state"] == "done" {
+            tracing::info!(request_id = id, run_id = prev_run, "request already done");
+            return Ok(Some(prev.clone()));
+        }
+        eyre::bail!(
+            "request {id} was started by run {prev_run} and did not finish; not dosing it again (check the container)"
+        );
+    }
+    all.insert(
+        id.to_string(),
+        json!({
+            "state": "running",
+            "run_id": run_id,
+            "grams": round3(grams),
+            "started": crate::summary::utc_now(),
+        }),
+    );
+    prune(all, cfg.keep);
+    Ok(None)
+}
+
+/// Record the result of request `id`. Best-effort: a store that cannot be
+/// written is logged, and the id stays running, so a retry is refused rather
+/// than dosed twice.
+fn finish(cfg: &RequestsCfg, id: &str, result: Value) {
+    let res = json_store::update(&cfg.file, STORE, |all| {
+        if let Some(Value::Object(entry)) = all.get_mut(id) {
+            entry.insert("state".into(), "done".into());
+            entry.insert("ended".into(), crate::summary::utc_now().into());
+            entry.insert("result".into(), result);
+        }
+        Ok(())
+    });
+    if let Err(e) = res {
+        tracing::warn!(error = %e, request_id = id, "request result not recorded");
+    }
+}
+
+/// The result of a dose that completed with `final_g`.
+pub fn completed(final_g: f32) -> Value {
+    json!({ "ok": true, "final_g": round3(final_g) })
+}
+
+/// The result of a dose that failed with `err`.
+pub fn failed(err: &eyre::Report) -> Value {
+    json!({ "ok": false, "error": err.to_string() })
+}
+
+/// Report the recorded outcome of a request that already ran, as the first
+/// run did: its final weight (to `decimals` places), or its error.
+pub fn replay(id: &str, entry: &Value, json: bool, decimals: usize) -> eyre::Result<()> {
+    let run_id = entry["run_id"].as_str().unwrap_or("unknown");
+    let result = &entry["result"];
+    if result["ok"].as_bool() != Some(true) {
+        let error = result["error"].as_str().unwrap_or("unknown error");
+        eyre::bail!("request {id} already ran (run {run_id}) and failed: {error}");
+    }
+    if json {
+        println!(
+            "{}",
+            json!({
+                "request_id": id,
+                "duplicate": true,
+                "run_id": run_id,
+                "final_g": result["final_g"],
+            })
+        );
+    } else {
+        let final_g = result["final_g"].as_f64().unwrap_or(f64::NAN);
+        println!("final: {final_g:.decimals$} g (request {id} already done by run {run_id})");
+    }
+    Ok(())
+}
+
+/// Drop the oldest finished requests beyond `keep`; running ones stay.
+fn prune(all: &mut Map<String, Value>, keep: usize) {
+    let mut done: Vec<(String, String)> = all
+        .iter()
+        .filter(|(_, v)| v["state"] == "done")
+        .map(|(k, v)| (v["started"].as_str().unwrap_or("").to_string(), k.clone()))
+        .collect();
+    if done.len() <= keep {
+        return;
+    }
+    done.sort();
+    for (_, id) in &done[..done.len() - keep] {
+        all.remove(id);
+    }
+}
+
+/// Grams as recorded: three decimals, like the JSON dose result.
+fn round3(g: f32) -> f64 {
+    (f64::from(g) * 1000.0).round() / 1000.0
+}
@@ -0,0 +1,91 @@
+//! `doser resume`: the state file recording a dose interrupted by a sensor
+//! timeout, and the operator prompt before it is continued.
+//!
+//! The file is a single JSON object (`target_g`, `zero_counts`,
+//! `paused_counts`, `run_id`). A new `dose` discards it; a resumed dose that
+//! completes removes it.
+
+use std::fs;
+use std::io::{BufRead, Write};
+
+use doser_config::ResumeCfg;
+use doser_core::resume::ResumePoint;
+use eyre::WrapErr;
+use serde_json::{Value, json};
+
+/// An interrupted dose as recorded.
+pub struct Recorded {
+    pub point: ResumePoint,
+    /// Run ID of the interrupted dose (absent in older state files)
+    pub run_id: Option<String>,
+}
+
+/// Record `point` so `doser resume` can pick it up.
+pub fn record(cfg: &ResumeCfg, point: &ResumePoint, run_id: &str) -> eyre::Result<()> {
+    let obj = json!({
+        "target_g": point.target_g,
+        "zero_counts": point.zero_counts,
+        "paused_counts": point.paused_counts,
+        "run_id": run_id,
+    });
+    fs::write(&cfg.state_file, format!("{obj}\n"))
+        .wrap_err_with(|| format!("write resume state {:?}", cfg.state_file))?;
+    tracing::info!(path = %cfg.state_file, target_g = point.target_g, "dose recorded for resume");
+    Ok(())
+}
+
+/// The recorded interrupted dose.
+pub fn load(cfg: &ResumeCfg) -> eyre::Result<Recorded> {
+    let text = match fs::read_to_string(&cfg.state_file) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            eyre::bail!(
+                "no interrupted dose to resume ({} not found)",
+                cfg.state_file
+            )
+        }
+        Err(e) => return Err(e).wrap_err_with(|| format!("read {:?}", cfg.state_file)),
+    };
+    let v: Value = serde_json::from_str(&text)
+        .wrap_err_with(|| format!("parse resume state {:?}", cfg.state_file))?;
+    let target_g = v["target_g"].as_f64();
+    let zero_counts = v["zero_counts"]
+        .as_i64()
+        .and_then(|z| i32::try_from(z).ok());
+    match (target_g, zero_counts) {
+        (Some(t), Some(zero_counts)) => Ok(Recorded {
+            point: ResumePoint {
+                target_g: t as f32,
+                zero_counts,
+                paused_counts: v["paused_counts"]
+                    .as_i64()
+                    .and_then(|p| i32::try_from(p).ok()),
+            },
+            run_id: v["run_id"].as_str().map(str::to_string),
+        }),
+        _ => eyre::bail!("resume state {:?} is incomplete", cfg.state_file),
+    }
+}
+
+/// Forget the recorded dose (best-effort).
+pub fn clear(cfg: &ResumeCfg) {
+    match fs::remove_file(&cfg.state_file) {
+        Ok(()) => tracing::info!(path = %cfg.state_file, "resume state cleared"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => {
+            tracing::warn!(error = %e, path = %cfg.state_file, "could not clear resume state")
+        }
+    }
+}
+
+/// Ask the operator on stderr/stdin; anything but "y"/"yes" declines.
+pub fn confirm(question: &str) -> eyre::Result<bool> {
+    eprint!("{question} [y/N] ");
+    std::io::stderr().flush()?;
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    Ok(matches!(
+        line.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
@@ -0,0 +1,27 @@
+//! Per-dose run IDs: a random (version 4) UUID that tags the dose's tracing
+//! span, JSON output and ticket, so logs from several systems can be joined.
+
+/// A new run ID, e.g. `0b4f7a52-6c1e-4d0a-9b8e-2f6d3c1a9e77`.
+pub fn new() -> String {
+    let mut b = [0u8; 16];
+    if getrandom::fill(&mut b).is_err() {
+        // No OS randomness: time and pid still keep IDs apart in practice.
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        b[..12].copy_from_slice(&nanos.to_le_bytes()[..12]);
+        b[12..].copy_from_slice(&std::process::id().to_le_bytes());
+    }
+    b[6] = (b[6] & 0x0f) | 0x40; // version 4
+    b[8] = (b[8] & 0x3f) | 0x80; // RFC 4122 variant
+    let hex: String = b.iter().map(|x| format!("{x:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
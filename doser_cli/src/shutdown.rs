@@ -0,0 +1,102 @@
+//! `[shutdown]`: the clean-shutdown marker of dose runs.
+//!
+//! The marker is a single JSON object. A dose rewrites it as
+//! `{"state":"running",...}` before the motor moves and as
+//! `{"state":"clean","outcome":...}` once the run has returned with the motor
+//! stopped, whether it completed, aborted or was stopped by SIGINT/SIGTERM.
+//! A marker still saying `running` when the next dose starts means the last
+//! one was cut short by a power loss or a kill: no safe-state sequence ran
+//! and its record may be missing.
+//!
+//! Writes go to a temporary file that is synced and renamed over the marker,
+//! and the directory is synced after the rename, so a power loss leaves
+//! either the old marker or the new one.
+
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use doser_config::ShutdownCfg;
+use eyre::WrapErr;
+use serde_json::{Value, json};
+
+/// How a dose run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Complete,
+    Abort,
+    /// Stopped by SIGINT, SIGTERM or SIGHUP
+    Signal,
+}
+
+impl Outcome {
+    /// The outcome of a run that returned `res`, `signalled` when a shutdown
+    /// signal arrived during it.
+    pub fn of<T>(res: &eyre::Result<T>, signalled: bool) -> Self {
+        match res {
+            Ok(_) => Self::Complete,
+            Err(_) if signalled => Self::Signal,
+            Err(_) => Self::Abort,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Complete => "complete",
+            Self::Abort => "abort",
+            Self::Signal => "signal",
+        }
+    }
+}
+
+/// Record that dose `run_id` is starting, after warning when the previous one
+/// did not shut down cleanly.
+pub fn begin(cfg: &ShutdownCfg, run_id: &str) -> eyre::Result<()> {
+    if let Ok(text) = fs::read_to_string(&cfg.marker)
+        && let Ok(prev) = serde_json::from_str::<Value>(&text)
+        && prev["state"] == "running"
+    {
+        let prev_run = prev["run_id"].as_str().unwrap_or("unknown");
+        tracing::warn!(run_id = prev_run, path = %cfg.marker, "previous dose did not shut down cleanly");
+        eprintln!(
+            "Warning: dose {prev_run} did not shut down cleanly (power loss or kill); check the motor and the container."
+        );
+    }
+    write(
+        &cfg.marker,
+        &json!({
+            "state": "running",
+            "run_id": run_id,
+            "pid": std::process::id(),
+            "started_ms": now_ms(),
+        }),
+    )
+}
+
+/// Record that dose `run_id` ended with `outcome` and the motor is halted.
+pub fn finish(cfg: &ShutdownCfg, run_id: &str, outcome: Outcome) {
+    let marker = json!({
+        "state": "clean",
+        "run_id": run_id,
+        "outcome": outcome.name(),
+        "ended_ms": now_ms(),
+    });
+    match write(&cfg.marker, &marker) {
+        Ok(()) => {
+            tracing::info!(path = %cfg.marker, outcome = outcome.name(), "clean shutdown recorded")
+        }
+        Err(e) => tracing::warn!(error = %e, "could not write the shutdown marker"),
+    }
+}
+
+fn write(path: &str, obj: &Value) -> eyre::Result<()> {
+    crate::json_store::replace(Path::new(path), &format!("{obj}\n"))
+        .wrap_err("replace shutdown marker")
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
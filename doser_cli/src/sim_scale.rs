@@ -0,0 +1,154 @@
+//! `doser sim-scale`: serve synthetic scale readings on a Unix socket for the
+//! `socket` scale driver, so a dose, a UI or a supervisor can run against a
+//! scripted weight in another process without hardware.
+//!
+//! The weight follows a profile CSV (`ms,grams`), interpolated linearly
+//! between rows and held at the last row (or restarted with `--repeat`). Each
+//! client gets the profile from its own connection time, at the config's
+//! sample rate, converted to raw counts with the config's calibration.
+
+use std::io::ErrorKind;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use doser_app::DoserHandle;
+use doser_core::Calibration;
+use doser_hardware::socket::write_reading;
+use eyre::WrapErr;
+
+/// Rows a profile may have (bounded like the calibration CSV).
+const MAX_PROFILE_ROWS: usize = 100_000;
+
+/// Weight over time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    /// `(ms, grams)`, strictly increasing in time
+    points: Vec<(u64, f32)>,
+}
+
+impl Profile {
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let text =
+            std::fs::read_to_string(path).wrap_err_with(|| format!("read profile {path:?}"))?;
+        Self::parse(&text).wrap_err_with(|| format!("profile {path:?}"))
+    }
+
+    pub fn parse(text: &str) -> eyre::Result<Self> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+        let header = lines.next().map(|(_, l)| l.trim()).unwrap_or_default();
+        if header != "ms,grams" {
+            eyre::bail!("profile CSV must have headers 'ms,grams', got: {header}");
+        }
+        let mut points: Vec<(u64, f32)> = Vec::new();
+        for (idx, line) in lines {
+            if points.len() >= MAX_PROFILE_ROWS {
+                eyre::bail!("profile has too many rows (> {MAX_PROFILE_ROWS})");
+            }
+            let row = || -> Option<(u64, f32)> {
+                let (ms, grams) = line.split_once(',')?;
+                Some((ms.trim().parse().ok()?, grams.trim().parse().ok()?))
+            };
+            let Some((ms, grams)) = row().filter(|(_, g)| g.is_finite()) else {
+                eyre::bail!("invalid profile row {}: {line:?}", idx + 1);
+            };
+            if points.last().is_some_and(|&(prev, _)| ms <= prev) {
+                eyre::bail!("profile row {}: ms must increase", idx + 1);
+            }
+            points.push((ms, grams));
+        }
+        if points.is_empty() {
+            eyre::bail!("profile has no rows");
+        }
+        Ok(Self { points })
+    }
+
+    /// Time of the last row.
+    pub fn duration_ms(&self) -> u64 {
+        self.points.last().map_or(0, |&(ms, _)| ms)
+    }
+
+    /// Weight `ms` into the profile.
+    pub fn grams_at(&self, ms: u64, repeat: bool) -> f32 {
+        let end = self.duration_ms();
+        let ms = if repeat && end > 0 { ms % end } else { ms };
+        let i = self.points.partition_point(|&(t, _)| t <= ms);
+        match (i.checked_sub(1).map(|j| self.points[j]), self.points.get(i)) {
+            (Some((t0, g0)), Some(&(t1, g1))) => {
+                g0 + (g1 - g0) * (ms - t0) as f32 / (t1 - t0) as f32
+            }
+            (Some((_, g)), None) => g,
+            (None, Some(&(_, g))) => g,
+            (None, None) => 0.0,
+        }
+    }
+}
+
+/// What `doser sim-scale` serves.
+pub struct Params {
+    pub profile: Profile,
+    pub calibration: Calibration,
+    pub rate_hz: u32,
+    pub repeat: bool,
+}
+
+/// Serve `params` on `path` until stopped (Ctrl-C).
+pub fn serve(path: &Path, params: Params, handle: &DoserHandle) -> eyre::Result<()> {
+    // A socket left behind by an earlier server would fail the bind.
+    if let Ok(meta) = std::fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            eyre::bail!("{path:?} exists and is not a socket");
+        }
+        std::fs::remove_file(path).wrap_err_with(|| format!("remove stale socket {path:?}"))?;
+    }
+    let listener = UnixListener::bind(path).wrap_err_with(|| format!("bind {path:?}"))?;
+    listener.set_nonblocking(true)?;
+    tracing::info!(
+        socket = %path.display(),
+        rate_hz = params.rate_hz,
+        profile_ms = params.profile.duration_ms(),
+        "serving simulated scale"
+    );
+    eprintln!("serving simulated scale on {}", path.display());
+
+    let params = Arc::new(params);
+    while !handle.stop_requested() {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let (params, handle) = (Arc::clone(&params), handle.clone());
+                std::thread::spawn(move || stream_readings(stream, &params, &handle));
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            Err(e) => return Err(e).wrap_err("accept scale client"),
+        }
+    }
+    let _ = std::fs::remove_file(path);
+    Ok(())
+}
+
+/// Send readings to one client until it disconnects or the server stops.
+fn stream_readings(mut stream: UnixStream, p: &Params, handle: &DoserHandle) {
+    tracing::info!("scale client connected");
+    // Some platforms hand out accepted sockets non-blocking, like the listener.
+    let _ = stream.set_nonblocking(false);
+    let period = Duration::from_secs_f64(1.0 / f64::from(p.rate_hz.max(1)));
+    let start = Instant::now();
+    let mut next = start;
+    while !handle.stop_requested() {
+        let ms = start.elapsed().as_millis() as u64;
+        let raw = p.calibration.to_counts(p.profile.grams_at(ms, p.repeat));
+        if write_reading(&mut stream, raw).is_err() {
+            break;
+        }
+        next += period;
+        std::thread::sleep(next.saturating_duration_since(Instant::now()));
+    }
+    tracing::info!("scale client disconnected");
+}
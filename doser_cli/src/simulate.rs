@@ -0,0 +1,214 @@
+//! `doser simulate`: run the seeded Monte Carlo suite (`doser_core::sim`)
+//! against this config's tuning and check the results against thresholds,
+//! e.g. `--assert overshoot_p95<0.15`, so a tuning change can be gated in CI
+//! before it reaches a machine. Needs no hardware.
+
+use doser_config::Config;
+use doser_core::sim::{NoiseModel, Plant, SimReport, Tuning, monte_carlo};
+use serde_json::json;
+
+/// One `--assert` threshold: `<metric><op><value>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assertion {
+    pub metric: String,
+    pub op: &'static str,
+    pub value: f64,
+}
+
+impl std::str::FromStr for Assertion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Two-character operators first, so `<=` is not read as `<`.
+        let (i, op) = ["<=", ">=", "<", ">"]
+            .iter()
+            .find_map(|op| s.find(op).map(|i| (i, *op)))
+            .ok_or_else(|| format!("{s:?}: expected <metric><op><value>, op one of < <= > >="))?;
+        let metric = s[..i].trim();
+        if !SimReport::METRICS.contains(&metric) {
+            return Err(format!(
+                "unknown metric {metric:?}; one of {}",
+                SimReport::METRICS.join(", ")
+            ));
+        }
+        let value = s[i + op.len()..]
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| format!("{s:?}: {e}"))?;
+        Ok(Self {
+            metric: metric.to_string(),
+            op,
+            value,
+        })
+    }
+}
+
+impl Assertion {
+    fn holds(&self, actual: f64) -> bool {
+        match self.op {
+            "<" => actual < self.value,
+            "<=" => actual <= self.value,
+            ">" => actual > self.value,
+            _ => actual >= self.value,
+        }
+    }
+}
+
+impl core::fmt::Display for Assertion {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}{}{}", self.metric, self.op, self.value)
+    }
+}
+
+/// Plant model options, shared with `doser tune`.
+#[derive(Debug, Clone, clap::Args)]
+pub struct PlantArgs {
+    /// Mean material flow per motor step (grams; default: the learned value)
+    #[arg(long, value_name = "GRAMS")]
+    g_per_step: Option<f32>,
+    /// Run-to-run spread of the flow, as a fraction
+    #[arg(long, value_name = "FRACTION", default_value_t = 0.1)]
+    flow_spread: f32,
+    /// Time from the auger to the scale (ms)
+    #[arg(long, value_name = "MS", default_value_t = 100)]
+    latency_ms: u64,
+    /// Scale reading noise, white, one sigma (grams)
+    #[arg(long, value_name = "GRAMS", default_value_t = 0.01)]
+    noise_g: f32,
+    /// Slow pink (1/f) drift of the reading, one sigma (grams)
+    #[arg(long, value_name = "GRAMS", default_value_t = 0.0)]
+    pink_g: f32,
+    /// Mains hum on the reading, amplitude (grams)
+    #[arg(long, value_name = "GRAMS", default_value_t = 0.0)]
+    hum_g: f32,
+    /// Mains frequency of the hum (Hz)
+    #[arg(long, value_name = "HZ", default_value_t = 50.0)]
+    mains_hz: f32,
+}
+
+impl PlantArgs {
+    /// The plant these options describe; without `--g-per-step`, with the
+    /// flow learned under `[learned]`.
+    pub fn plant(&self, cfg: &Config) -> eyre::Result<Plant> {
+        let g_per_step = match (self.g_per_step, &cfg.learned) {
+            (Some(g), _) => g,
+            (None, Some(l)) => match crate::learned::get(l)?.g_per_step {
+                Some(g) => g as f32,
+                None => eyre::bail!("nothing learned yet; pass --g-per-step"),
+            },
+            (None, None) => eyre::bail!("--g-per-step is required without [learned]"),
+        };
+        if !(g_per_step.is_finite() && g_per_step > 0.0) {
+            eyre::bail!("--g-per-step must be a positive number of grams");
+        }
+        Ok(Plant {
+            g_per_step,
+            flow_spread: self.flow_spread,
+            latency_ms: self.latency_ms,
+            noise: NoiseModel {
+                white_g: self.noise_g,
+                pink_g: self.pink_g,
+                hum_g: self.hum_g,
+                mains_hz: self.mains_hz,
+            },
+        })
+    }
+}
+
+/// What `doser simulate` runs.
+pub struct Params {
+    pub grams: f32,
+    pub runs: u32,
+    pub seed: u64,
+    pub plant: Plant,
+    pub asserts: Vec<Assertion>,
+}
+
+/// The tuning a dose with this config would run with.
+pub fn tuning(cfg: &Config) -> Tuning {
+    let defaults = doser_core::SafetyCfg::default();
+    let mut safety: doser_core::SafetyCfg = (&cfg.safety).into();
+    if safety.max_run_ms == 0 {
+        safety.max_run_ms = defaults.max_run_ms;
+    }
+    if safety.max_overshoot_g == 0.0 {
+        safety.max_overshoot_g = defaults.max_overshoot_g;
+    }
+    Tuning {
+        filter: (&cfg.filter).into(),
+        control: (&cfg.control).into(),
+        safety,
+        timeouts: (&cfg.timeouts).into(),
+        predictor: Some((&cfg.predictor).into()),
+    }
+}
+
+pub fn run(cfg: &Config, p: &Params, json: bool) -> eyre::Result<()> {
+    if !(p.grams.is_finite() && p.grams > 0.0) {
+        eyre::bail!("--grams must be a positive number of grams");
+    }
+    if p.runs == 0 {
+        eyre::bail!("--runs must be at least 1");
+    }
+    let report = monte_carlo(&tuning(cfg), &p.plant, p.grams, p.runs, p.seed)?;
+    let checked: Vec<(&Assertion, Option<f64>, bool)> = p
+        .asserts
+        .iter()
+        .map(|a| {
+            let v = report.metric(&a.metric);
+            (a, v, v.is_some_and(|v| a.holds(v)))
+        })
+        .collect();
+
+    if json {
+        let metrics: serde_json::Map<_, _> = SimReport::METRICS
+            .iter()
+            .map(|m| (m.to_string(), json!(report.metric(m))))
+            .collect();
+        let asserts: Vec<_> = checked
+            .iter()
+            .map(|(a, v, ok)| json!({ "expr": a.to_string(), "value": v, "ok": ok }))
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "target_g": p.grams,
+                "runs": p.runs,
+                "seed": p.seed,
+                "g_per_step": p.plant.g_per_step,
+                "latency_ms": p.plant.latency_ms,
+                "noise_g": p.plant.noise.white_g,
+                "pink_g": p.plant.noise.pink_g,
+                "hum_g": p.plant.noise.hum_g,
+                "mains_hz": p.plant.noise.mains_hz,
+                "metrics": metrics,
+                "asserts": asserts,
+            })
+        );
+    } else {
+        println!(
+            "simulate: {:.2} g, {} runs, seed {}",
+            p.grams, p.runs, p.seed
+        );
+        for m in SimReport::METRICS {
+            let v = report
+                .metric(m)
+                .map_or_else(|| "-".to_string(), |v| format!("{v:.3}"));
+            println!("  {m:<16} {v}");
+        }
+        for (a, v, ok) in &checked {
+            let v = v.map_or_else(|| "-".to_string(), |v| format!("{v:.3}"));
+            println!("{} {a} (got {v})", if *ok { "PASS" } else { "FAIL" });
+        }
+    }
+
+    let failed: Vec<String> = checked
+        .iter()
+        .filter(|(_, _, ok)| !ok)
+        .map(|(a, _, _)| a.to_string())
+        .collect();
+    if !failed.is_empty() {
+        eyre::bail!("simulation assertion failed: {}", failed.join(", "));
+    }
+    Ok(())
+}
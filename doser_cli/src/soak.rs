@@ -0,0 +1,96 @@
+//! `doser soak`: repeated doses to catch slow degradation before deployment,
+//! such as drift of the final error (e.g. a warming load cell or motor), growth
+//! of process memory (leaks), and growth of control loop jitter.
+//!
+//! Each metric compares the first and the last quarter of the cycles, so
+//! start-up effects in the first cycle do not count as growth.
+
+/// One soak cycle.
+#[derive(Copy, Clone, Debug)]
+pub struct Cycle {
+    /// Final weight minus target
+    pub error_g: f32,
+    /// Control step latency standard deviation
+    pub jitter_us: f32,
+    /// Process RSS after the cycle (Linux)
+    pub rss_kb: Option<u64>,
+}
+
+/// Thresholds beyond which the soak fails.
+#[derive(Copy, Clone, Debug)]
+pub struct Limits {
+    /// Largest change in mean final error
+    pub max_drift_g: f32,
+    /// Largest RSS growth
+    pub max_rss_growth_kb: u64,
+    /// Largest ratio of late to early mean jitter
+    pub max_jitter_ratio: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub cycles: usize,
+    pub error_first_g: f32,
+    pub error_last_g: f32,
+    pub rss_first_kb: Option<u64>,
+    pub rss_last_kb: Option<u64>,
+    pub jitter_first_us: f32,
+    pub jitter_last_us: f32,
+    /// Metrics that degraded beyond their limit
+    pub failures: Vec<String>,
+}
+
+impl Report {
+    pub fn drift_g(&self) -> f32 {
+        self.error_last_g - self.error_first_g
+    }
+
+    pub fn rss_growth_kb(&self) -> Option<i64> {
+        Some(self.rss_last_kb? as i64 - self.rss_first_kb? as i64)
+    }
+}
+
+/// Compare the early and late cycles against `limits`.
+pub fn evaluate(cycles: &[Cycle], limits: &Limits) -> Report {
+    let window = (cycles.len() / 4).max(1);
+    let (first, last) = (
+        &cycles[..window.min(cycles.len())],
+        &cycles[cycles.len().saturating_sub(window)..],
+    );
+    let mean =
+        |cs: &[Cycle], f: fn(&Cycle) -> f32| cs.iter().map(f).sum::<f32>() / cs.len().max(1) as f32;
+    let mut r = Report {
+        cycles: cycles.len(),
+        error_first_g: mean(first, |c| c.error_g),
+        error_last_g: mean(last, |c| c.error_g),
+        // Memory after the early cycles, once allocations have warmed up.
+        rss_first_kb: first.last().and_then(|c| c.rss_kb),
+        rss_last_kb: last.last().and_then(|c| c.rss_kb),
+        jitter_first_us: mean(first, |c| c.jitter_us),
+        jitter_last_us: mean(last, |c| c.jitter_us),
+        failures: Vec::new(),
+    };
+    if r.drift_g().abs() > limits.max_drift_g {
+        r.failures.push(format!(
+            "final error drifted {:+.3} g (limit {} g)",
+            r.drift_g(),
+            limits.max_drift_g
+        ));
+    }
+    if let Some(growth) = r.rss_growth_kb()
+        && growth > limits.max_rss_growth_kb as i64
+    {
+        r.failures.push(format!(
+            "RSS grew {growth} kB (limit {} kB)",
+            limits.max_rss_growth_kb
+        ));
+    }
+    // Below 1 us the ratio is noise.
+    if r.jitter_last_us > r.jitter_first_us.max(1.0) * limits.max_jitter_ratio {
+        r.failures.push(format!(
+            "jitter rose from {:.1} to {:.1} us (limit x{})",
+            r.jitter_first_us, r.jitter_last_us, limits.max_jitter_ratio
+        ));
+    }
+    r
+}
@@ -0,0 +1,247 @@
+//! `[retention]`: keep the run data a long-running unit writes (diagnostic
+//! bundles under `[bundle] dir`, rotated files of `[logging] file`) within a
+//! disk budget and an age, so a kiosk does not fill its SD card.
+//!
+//! Files older than `max_age_days` are pruned, then the oldest of the rest
+//! until the total fits in `max_mb`. The log file currently written is never
+//! pruned. Pruning runs before each dose and resume; `doser storage status`
+//! shows the usage and what the next prune would remove.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use doser_config::{Config, RetentionCfg};
+use serde_json::json;
+
+const MIB: u64 = 1024 * 1024;
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// What a kept file is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Bundle,
+    Log,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Bundle => "bundle",
+            Self::Log => "log",
+        }
+    }
+}
+
+/// A bundle directory or rotated log file.
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub path: PathBuf,
+    pub kind: Kind,
+    pub bytes: u64,
+    pub modified: SystemTime,
+}
+
+/// Everything retention manages, oldest first.
+pub fn collect(cfg: &Config) -> Vec<Item> {
+    let mut items = Vec::new();
+    if let Some(b) = &cfg.bundle {
+        for (path, ty) in entries(Path::new(&b.dir)) {
+            // Only real directories: a symlink is never followed or removed.
+            let is_bundle = ty.is_dir()
+                && path
+                    .file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with("doser-bundle-"));
+            if is_bundle && let Some(item) = item(path, Kind::Bundle) {
+                items.push(item);
+            }
+        }
+    }
+    if let Some(file) = cfg.logging.file.as_deref() {
+        let current = crate::bundle::latest_log(file);
+        let log = Path::new(file);
+        let name = log
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let dir = match log.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        for (path, ty) in entries(dir) {
+            let rotated = path
+                .file_name()
+                .is_some_and(|n| is_rotated(&n.to_string_lossy(), &name));
+            if ty.is_file()
+                && rotated
+                && current.as_deref() != Some(path.as_path())
+                && let Some(item) = item(path, Kind::Log)
+            {
+                items.push(item);
+            }
+        }
+    }
+    items.sort_by_key(|i| i.modified);
+    items
+}
+
+/// The items `r` would prune from `items` (oldest first) at `now`.
+pub fn to_prune<'a>(items: &'a [Item], r: &RetentionCfg, now: SystemTime) -> Vec<&'a Item> {
+    let too_old = |i: &Item| {
+        r.max_age_days.is_some_and(|d| {
+            now.duration_since(i.modified)
+                .is_ok_and(|age| age > Duration::from_secs(d.saturating_mul(DAY_SECS)))
+        })
+    };
+    let mut kept: u64 = items.iter().map(|i| i.bytes).sum();
+    let budget = r.max_mb.map_or(u64::MAX, |mb| mb.saturating_mul(MIB));
+    items
+        .iter()
+        .filter(|i| {
+            let prune = too_old(i) || kept > budget;
+            if prune {
+                kept -= i.bytes;
+            }
+            prune
+        })
+        .collect()
+}
+
+/// Prune per `[retention]` and return what was removed. Best-effort: what
+/// cannot be removed is logged and does not stop the caller.
+pub fn prune(cfg: &Config) -> Vec<PathBuf> {
+    let Some(r) = &cfg.retention else {
+        return Vec::new();
+    };
+    let items = collect(cfg);
+    let mut removed = Vec::new();
+    for i in to_prune(&items, r, SystemTime::now()) {
+        let res = match i.kind {
+            Kind::Bundle => fs::remove_dir_all(&i.path),
+            Kind::Log => fs::remove_file(&i.path),
+        };
+        match res {
+            Ok(()) => {
+                tracing::info!(path = %i.path.display(), bytes = i.bytes, "pruned");
+                removed.push(i.path.clone());
+            }
+            Err(e) => tracing::warn!(path = %i.path.display(), error = %e, "not pruned"),
+        }
+    }
+    removed
+}
+
+/// `doser storage status`.
+pub fn status(cfg: &Config, json: bool) -> eyre::Result<()> {
+    let items = collect(cfg);
+    let total: u64 = items.iter().map(|i| i.bytes).sum();
+    let by_kind = |k: Kind| {
+        let of: Vec<&Item> = items.iter().filter(|i| i.kind == k).collect();
+        (of.len(), of.iter().map(|i| i.bytes).sum::<u64>())
+    };
+    let retention = cfg.retention.clone().unwrap_or_default();
+    let pending = to_prune(&items, &retention, SystemTime::now());
+    if json {
+        let kinds: serde_json::Map<_, _> = [Kind::Bundle, Kind::Log]
+            .into_iter()
+            .map(|k| {
+                let (files, bytes) = by_kind(k);
+                (
+                    k.name().to_string(),
+                    json!({ "files": files, "bytes": bytes }),
+                )
+            })
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "bytes": total,
+                "kinds": kinds,
+                "max_mb": retention.max_mb,
+                "max_age_days": retention.max_age_days,
+                "prunable": pending.iter().map(|i| &i.path).collect::<Vec<_>>(),
+            })
+        );
+        return Ok(());
+    }
+    let mb = |b: u64| b as f64 / MIB as f64;
+    for k in [Kind::Bundle, Kind::Log] {
+        let (files, bytes) = by_kind(k);
+        println!("{:<7} {files:>5} files  {:>9.2} MiB", k.name(), mb(bytes));
+    }
+    let limit =
+        |v: Option<u64>, unit: &str| v.map_or_else(|| "-".to_string(), |v| format!("{v} {unit}"));
+    println!(
+        "total   {:>5} files  {:>9.2} MiB  (limit {}, max age {})",
+        items.len(),
+        mb(total),
+        limit(retention.max_mb, "MiB"),
+        limit(retention.max_age_days, "days")
+    );
+    if cfg.retention.is_none() {
+        println!("no [retention] section: nothing is pruned");
+    } else if pending.is_empty() {
+        println!("within limits");
+    } else {
+        println!("next prune removes {} files:", pending.len());
+        for i in pending {
+            println!("  {}", i.path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Whether `file` is a rotated copy of the log `name`: the appender's
+/// `{name}.YYYY-MM-DD`, with `-HH` (hourly) and `-MM` (minutely) appended.
+/// Anything else next to the log (the config, state files) is not a log.
+pub fn is_rotated(file: &str, name: &str) -> bool {
+    let Some(date) = file
+        .strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix('.'))
+    else {
+        return false;
+    };
+    let fields: Vec<&str> = date.split('-').collect();
+    let widths: &[usize] = match fields.len() {
+        3 => &[4, 2, 2],
+        4 => &[4, 2, 2, 2],
+        5 => &[4, 2, 2, 2, 2],
+        _ => return false,
+    };
+    fields
+        .iter()
+        .zip(widths)
+        .all(|(f, &w)| f.len() == w && f.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Entries of `dir` (nothing when it cannot be read) with their type; a
+/// symlink is reported as one, not as its target.
+fn entries(dir: &Path) -> Vec<(PathBuf, fs::FileType)> {
+    fs::read_dir(dir)
+        .map(|rd| {
+            rd.filter_map(Result::ok)
+                .filter_map(|e| Some((e.path(), e.file_type().ok()?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn item(path: PathBuf, kind: Kind) -> Option<Item> {
+    let meta = fs::symlink_metadata(&path).ok()?;
+    Some(Item {
+        bytes: size(&path),
+        modified: meta.modified().ok()?,
+        path,
+        kind,
+    })
+}
+
+/// Bytes in a file, or in all files under a directory. Symlinks are not
+/// followed (a link loop would never end) and count as their own size.
+fn size(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(m) if m.is_dir() => entries(path).iter().map(|(p, _)| size(p)).sum(),
+        Ok(m) => m.len(),
+        Err(_) => 0,
+    }
+}
@@ -0,0 +1,82 @@
+//! Named values of a completed dose, as used by `[ticket]` templates and
+//! `dose --format`. Weights use the scale's display decimals; values that were
+//! not recorded (e.g. predictor telemetry with the predictor off) are empty.
+
+use doser_app::JsonTelemetry;
+
+/// Placeholders a dose template may use.
+pub const FIELDS: &[&str] = &[
+    "target_g",
+    "final_g",
+    "hold_g",
+    "display_g",
+    "sigma_g",
+    "duration_ms",
+    "date",
+    "operator",
+    "slope_ema",
+    "stop_at_g",
+    "coast_comp_g",
+    "undershoot_g",
+    "coarse_ms",
+    "fine_ms",
+    "settle_ms",
+    "verify_ms",
+    "read_retries",
+    "reads_recovered",
+    "run_id",
+];
+
+/// One completed dose.
+pub struct DoseSummary<'a> {
+    pub target_g: f32,
+    pub final_g: f32,
+    pub duration_ms: u64,
+    pub operator: Option<&'a str>,
+    pub run_id: &'a str,
+    pub tel: JsonTelemetry,
+    /// Decimals for weights (the scale's display resolution)
+    pub decimals: usize,
+}
+
+impl DoseSummary<'_> {
+    /// The value of placeholder `name`, if recorded.
+    pub fn field(&self, name: &str) -> Option<String> {
+        let d = self.decimals;
+        let grams = |g: Option<f32>| g.map(|g| format!("{g:.d$}"));
+        let phases = self.tel.phases;
+        match name {
+            "target_g" => grams(Some(self.target_g)),
+            "final_g" => grams(Some(self.final_g)),
+            // The stable-hold weight, else the final reading.
+            "hold_g" => grams(Some(self.tel.hold_g.unwrap_or(self.final_g))),
+            // The display-filtered weight, else the final reading.
+            "display_g" => grams(Some(self.tel.display_g.unwrap_or(self.final_g))),
+            "sigma_g" => self.tel.sigma_g.map(|v| format!("{v:.3}")),
+            "duration_ms" => Some(self.duration_ms.to_string()),
+            "date" => Some(utc_now()),
+            "operator" => self.operator.map(str::to_string),
+            "slope_ema" => self.tel.slope_ema_gps.map(|v| format!("{v:.3}")),
+            "stop_at_g" => grams(self.tel.stop_at_g),
+            "coast_comp_g" => grams(self.tel.coast_comp_g),
+            "undershoot_g" => grams(self.tel.undershoot_g),
+            "coarse_ms" => phases.map(|p| p.coarse_ms.to_string()),
+            "fine_ms" => phases.map(|p| p.fine_ms.to_string()),
+            "settle_ms" => phases.map(|p| p.settle_ms.to_string()),
+            "verify_ms" => phases.map(|p| p.verify_ms.to_string()),
+            "read_retries" => Some(self.tel.read_retries.to_string()),
+            "reads_recovered" => Some(self.tel.reads_recovered.to_string()),
+            "run_id" => Some(self.run_id.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Current time in UTC, `YYYY-MM-DD HH:MM:SS UTC`.
+pub fn utc_now() -> String {
+    let fmt =
+        time::macros::format_description!("[year]-[month]-[day] [hour]:[minute]:[second] UTC");
+    time::OffsetDateTime::now_utc()
+        .format(fmt)
+        .unwrap_or_default()
+}
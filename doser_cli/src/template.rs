@@ -0,0 +1,63 @@
+//! Minimal text templates for dose output: `{name}` is replaced by the named
+//! value, `{{` and `}}` are literal braces. Nothing else is interpreted.
+
+/// Placeholder names in `template`, in order; errors on unbalanced braces.
+pub fn fields(template: &str) -> eyre::Result<Vec<&str>> {
+    let mut out = Vec::new();
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        let (brace, tail) = (&rest[i..i + 1], &rest[i + 1..]);
+        if tail.starts_with(brace) {
+            rest = &tail[1..];
+            continue;
+        }
+        if brace == "}" {
+            eyre::bail!("unmatched '}}' in template {template:?}");
+        }
+        let Some(end) = tail.find('}') else {
+            eyre::bail!("unclosed '{{' in template {template:?}");
+        };
+        let name = tail[..end].trim();
+        if name.is_empty() || name.contains('{') {
+            eyre::bail!("bad placeholder in template {template:?}");
+        }
+        out.push(name);
+        rest = &tail[end + 1..];
+    }
+    Ok(out)
+}
+
+/// Check that `template` only names placeholders from `known`.
+pub fn check(template: &str, known: &[&str]) -> eyre::Result<()> {
+    for name in fields(template)? {
+        if !known.contains(&name) {
+            eyre::bail!(
+                "unknown placeholder {{{name}}} in template; available: {}",
+                known.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Fill `template` with `value(name)` for each placeholder (empty when `None`).
+pub fn render(template: &str, value: impl Fn(&str) -> Option<String>) -> eyre::Result<String> {
+    fields(template)?;
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        let (brace, tail) = (&rest[i..i + 1], &rest[i + 1..]);
+        if tail.starts_with(brace) {
+            out.push_str(brace);
+            rest = &tail[1..];
+            continue;
+        }
+        // `fields` accepted the template, so this is an opening brace with a close.
+        let end = tail.find('}').unwrap_or(tail.len());
+        out.push_str(&value(tail[..end].trim()).unwrap_or_default());
+        rest = tail.get(end + 1..).unwrap_or_default();
+    }
+    out.push_str(rest);
+    Ok(out)
+}
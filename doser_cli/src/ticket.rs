@@ -0,0 +1,33 @@
+//! `[ticket]`: a formatted record of each completed dose, written to stdout or
+//! appended to a printer device (e.g. `/dev/usb/lp0`, or a serial port already
+//! set to the printer's baud rate). Placeholders are those of
+//! [`summary::FIELDS`].
+
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use doser_config::TicketCfg;
+use eyre::WrapErr;
+
+use crate::summary::{self, DoseSummary};
+use crate::template;
+
+/// Reject templates with unknown or malformed placeholders (before dosing).
+pub fn check(cfg: &TicketCfg) -> eyre::Result<()> {
+    template::check(&cfg.template, summary::FIELDS).wrap_err("invalid [ticket] template")
+}
+
+/// Render the ticket text.
+pub fn render(cfg: &TicketCfg, dose: &DoseSummary<'_>) -> eyre::Result<String> {
+    template::render(&cfg.template, |name| dose.field(name))
+}
+
+/// Append `text` to the printer device `dev`.
+pub fn print(dev: &str, text: &str) -> eyre::Result<()> {
+    let mut f = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(dev)
+        .wrap_err_with(|| format!("open ticket device {dev:?}"))?;
+    writeln!(f, "{text}").wrap_err_with(|| format!("write ticket to {dev:?}"))
+}
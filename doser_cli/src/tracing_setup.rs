@@ -3,7 +3,7 @@
 use crate::cli::FILE_GUARD;
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
-/// Build a file sink writer with optional rotation, storing the non-blocking guard in OnceLock.
+/// Build a file sink writer with optional rotation, storing the non-blocking guard in `FILE_GUARD`.
 fn file_layer(
     file: Option<&str>,
     rotation: Option<&str>,
@@ -19,17 +19,100 @@ fn file_layer(
         _ => tracing_appender::rolling::never(".", path),
     };
     let (nb_writer, guard) = tracing_appender::non_blocking(file_appender);
-    let _ = FILE_GUARD.set(guard);
+    if let Ok(mut slot) = FILE_GUARD.lock() {
+        *slot = Some(guard);
+    }
     Some(nb_writer)
 }
 
+/// OTLP span export for `[telemetry]`. Spans go out in batches from the SDK's
+/// own thread over blocking HTTP, so no async runtime is needed; span fields
+/// such as the dose span's `run_id` become span attributes.
+#[cfg(feature = "otel")]
+mod otel {
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
+    use tracing_subscriber::registry::LookupSpan;
+
+    /// The provider behind the layer; shut down (flushing queued spans) by
+    /// `flush_logs`.
+    static PROVIDER: Mutex<Option<SdkTracerProvider>> = Mutex::new(None);
+
+    pub fn layer<S>(
+        cfg: &doser_config::TelemetryCfg,
+    ) -> eyre::Result<tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>>
+    where
+        S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(&cfg.endpoint)
+            .with_timeout(Duration::from_millis(cfg.timeout_ms))
+            .build()?;
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(cfg.service_name.clone())
+                    .build(),
+            )
+            .build();
+        let tracer = provider.tracer("doser");
+        if let Ok(mut slot) = PROVIDER.lock() {
+            *slot = Some(provider);
+        }
+        Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+    }
+
+    /// Export the spans still queued.
+    pub fn shutdown() {
+        let provider = PROVIDER.lock().ok().and_then(|mut slot| slot.take());
+        if let Some(p) = provider {
+            let _ = p.shutdown();
+        }
+    }
+}
+
+/// Write out the buffered log file lines (and, with `[telemetry]`, queued
+/// spans). `process::exit` skips destructors, so this must run before it;
+/// later log lines only reach the console.
+pub fn flush_logs() {
+    let guard = FILE_GUARD.lock().ok().and_then(|mut slot| slot.take());
+    drop(guard);
+    #[cfg(feature = "otel")]
+    otel::shutdown();
+}
+
 /// Initialize tracing once for the whole app.
-pub fn init_tracing(json: bool, level: &str, file: Option<&str>, rotation: Option<&str>) {
+pub fn init_tracing(
+    json: bool,
+    level: &str,
+    file: Option<&str>,
+    rotation: Option<&str>,
+    telemetry: Option<&doser_config::TelemetryCfg>,
+) {
     // Prefer RUST_LOG if set; otherwise use CLI level
     let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
 
     let registry = tracing_subscriber::registry().with(filter);
 
+    // Logged once the subscriber is up; telemetry never stops a run.
+    #[cfg(feature = "otel")]
+    let (registry, telemetry_note) = match telemetry.map(otel::layer).transpose() {
+        Ok(layer) => (registry.with(layer), None),
+        Err(e) => (
+            registry.with(None),
+            Some(format!("telemetry: OTLP export disabled: {e:#}")),
+        ),
+    };
+    #[cfg(not(feature = "otel"))]
+    let telemetry_note =
+        telemetry.map(|_| "[telemetry] is ignored: this build has no `otel` feature".to_string());
+
     if json {
         let console = fmt::layer().json().with_target(false);
         if let Some(nb_writer) = file_layer(file, rotation) {
@@ -53,4 +136,7 @@ pub fn init_tracing(json: bool, level: &str, file: Option<&str>, rotation: Optio
             registry.with(console).init();
         }
     }
+    if let Some(note) = telemetry_note {
+        tracing::warn!("{note}");
+    }
 }
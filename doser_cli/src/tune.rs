@@ -0,0 +1,134 @@
+//! `doser tune --optimize`: search speed bands and predictor settings for this
+//! config against a plant model (`doser_core::tune`), scoring each candidate
+//! by the seeded Monte Carlo batch `doser simulate` runs, and print the
+//! cheapest tuning found as config keys to paste. Needs no hardware.
+
+use doser_config::Config;
+use doser_core::sim::{Plant, SimReport};
+use doser_core::tune::{Scored, Search, optimize};
+use serde_json::json;
+
+/// What `doser tune` runs.
+pub struct Params {
+    pub grams: f32,
+    pub plant: Plant,
+    pub search: Search,
+}
+
+/// Metrics shown for the current and the recommended tuning.
+const SHOWN: [&str; 4] = [
+    "overshoot_p95",
+    "duration_p95_ms",
+    "abs_error_p95",
+    "abort_rate",
+];
+
+fn metrics(report: &SimReport) -> serde_json::Map<String, serde_json::Value> {
+    SHOWN
+        .iter()
+        .map(|m| (m.to_string(), json!(report.metric(m))))
+        .collect()
+}
+
+/// The tuned keys, `[control]` and `[predictor]`.
+fn keys(s: &Scored) -> serde_json::Value {
+    let p = s.tuning.predictor.clone().unwrap_or_default();
+    json!({
+        "control": { "speed_bands": s.tuning.control.speed_bands },
+        "predictor": {
+            "enabled": p.enabled,
+            "window": p.window,
+            "extra_latency_ms": p.extra_latency_ms,
+            "min_progress_ratio": p.min_progress_ratio,
+        },
+    })
+}
+
+/// The tuned keys as TOML to paste over the config's.
+fn toml(s: &Scored) -> String {
+    let bands: Vec<String> = s
+        .tuning
+        .control
+        .speed_bands
+        .iter()
+        .map(|(g, sps)| format!("[{g}, {sps}]"))
+        .collect();
+    let p = s.tuning.predictor.clone().unwrap_or_default();
+    format!(
+        "[control]\nspeed_bands = [{}]\n\n[predictor]\nenabled = {}\nwindow = {}\nextra_latency_ms = {}\nmin_progress_ratio = {}",
+        bands.join(", "),
+        p.enabled,
+        p.window,
+        p.extra_latency_ms,
+        p.min_progress_ratio
+    )
+}
+
+pub fn run(cfg: &Config, p: &Params, json: bool) -> eyre::Result<()> {
+    if !(p.grams.is_finite() && p.grams > 0.0) {
+        eyre::bail!("--grams must be a positive number of grams");
+    }
+    if p.search.runs == 0 {
+        eyre::bail!("--runs must be at least 1");
+    }
+    let w = p.search.weights;
+    if !(w.overshoot_per_g >= 0.0 && w.per_s >= 0.0) {
+        eyre::bail!("--overshoot-weight and --time-weight must not be negative");
+    }
+    let report = optimize(&crate::simulate::tuning(cfg), &p.plant, p.grams, &p.search)?;
+    // An infinite cost (no run completed) has no JSON number.
+    let cost = |s: &Scored| s.cost.is_finite().then_some(s.cost);
+
+    if json {
+        println!(
+            "{}",
+            json!({
+                "target_g": p.grams,
+                "candidates": p.search.candidates,
+                "evaluated": report.evaluated,
+                "runs": p.search.runs,
+                "seed": p.search.seed,
+                "g_per_step": p.plant.g_per_step,
+                "latency_ms": p.plant.latency_ms,
+                "overshoot_weight": w.overshoot_per_g,
+                "time_weight": w.per_s,
+                "current": { "cost": cost(&report.baseline), "metrics": metrics(&report.baseline.report) },
+                "best": { "cost": cost(&report.best), "metrics": metrics(&report.best.report) },
+                "recommended": report.improved().then(|| keys(&report.best)),
+            })
+        );
+        return Ok(());
+    }
+
+    println!(
+        "tune: {:.2} g, {} candidates x {} runs, seed {}",
+        p.grams, report.evaluated, p.search.runs, p.search.seed
+    );
+    println!(
+        "  {:<9} {:>8} {}",
+        "",
+        "cost",
+        SHOWN.map(|m| format!("{m:>16}")).join("")
+    );
+    for (name, s) in [("current", &report.baseline), ("best", &report.best)] {
+        let cost = cost(s).map_or_else(|| "-".to_string(), |c| format!("{c:.3}"));
+        let values: String = SHOWN
+            .iter()
+            .map(|m| {
+                let v = s
+                    .report
+                    .metric(m)
+                    .map_or_else(|| "-".to_string(), |v| format!("{v:.3}"));
+                format!("{v:>16}")
+            })
+            .collect();
+        println!("  {name:<9} {cost:>8} {values}");
+    }
+    if report.improved() {
+        println!("\nrecommended (replace these keys in the config):\n");
+        println!("{}", toml(&report.best));
+    } else {
+        println!("\nno candidate beat the current tuning");
+    }
+    Ok(())
+}
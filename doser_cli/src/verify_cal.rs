@@ -0,0 +1,59 @@
+//! `doser verify-cal`: check the calibration against a reference weight and
+//! record the outcome in `[calibration.verified]`, for QA procedures that
+//! require a daily verification rather than a full recalibration.
+
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use doser_config::CalibrationCheck;
+use eyre::WrapErr;
+
+/// Compare `measured_g` with `reference_g`.
+pub fn check(reference_g: f32, measured_g: f32, tolerance_g: f32) -> CalibrationCheck {
+    let error_g = measured_g - reference_g;
+    CalibrationCheck {
+        at: crate::summary::utc_now(),
+        reference_g,
+        measured_g,
+        error_g,
+        tolerance_g,
+        passed: error_g.abs() <= tolerance_g,
+    }
+}
+
+/// Show `prompt` on stderr and wait for Enter.
+pub fn wait_for_enter(prompt: &str) -> eyre::Result<()> {
+    eprint!("{prompt}, then press Enter ");
+    std::io::stderr().flush()?;
+    let mut line = String::new();
+    if std::io::stdin().lock().read_line(&mut line)? == 0 {
+        eyre::bail!("verification cancelled (end of input)");
+    }
+    Ok(())
+}
+
+/// Write `c` into `[calibration.verified]` of the config at `path`, replacing
+/// the previous result. Comments and other keys are kept.
+pub fn record(path: &Path, c: &CalibrationCheck) -> eyre::Result<()> {
+    let text = fs::read_to_string(path).wrap_err_with(|| format!("read {path:?}"))?;
+    let mut doc: toml_edit::DocumentMut =
+        text.parse().wrap_err_with(|| format!("parse {path:?}"))?;
+    let Some(cal) = doc
+        .get_mut("calibration")
+        .and_then(|t| t.as_table_like_mut())
+    else {
+        eyre::bail!("{path:?} has no [calibration] table");
+    };
+    // Rounded so the file shows e.g. 99.96 rather than 99.95999908447266.
+    let grams = |g: f32| toml_edit::value((f64::from(g) * 1e4).round() / 1e4);
+    let mut t = toml_edit::Table::new();
+    t.insert("at", toml_edit::value(c.at.as_str()));
+    t.insert("reference_g", grams(c.reference_g));
+    t.insert("measured_g", grams(c.measured_g));
+    t.insert("error_g", grams(c.error_g));
+    t.insert("tolerance_g", grams(c.tolerance_g));
+    t.insert("passed", toml_edit::value(c.passed));
+    cal.insert("verified", toml_edit::Item::Table(t));
+    fs::write(path, doc.to_string()).wrap_err_with(|| format!("write {path:?}"))
+}
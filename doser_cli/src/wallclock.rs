@@ -0,0 +1,51 @@
+//! Wall-clock annotations for records that leave the machine.
+//!
+//! Run-relative times (`duration_ms`, telemetry `ms`) come from the monotonic
+//! clock and never jump. Correlating doses across machines also needs the wall
+//! clock, and whether it can be trusted: records carry RFC 3339 UTC times
+//! plus a `clock` object, `{"source": "ntp"|"local"|"unknown", "synced": bool|null}`.
+//! On Linux the kernel's NTP state (`adjtimex`) says whether a time daemon
+//! keeps the clock disciplined; elsewhere it is unknown.
+
+use serde_json::{Value, json};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+/// `t` as RFC 3339 UTC, e.g. `2026-10-16T14:18:28.160166Z`.
+pub fn rfc3339(t: OffsetDateTime) -> String {
+    t.format(&Rfc3339).unwrap_or_default()
+}
+
+/// The current time as RFC 3339 UTC.
+pub fn now() -> String {
+    rfc3339(OffsetDateTime::now_utc())
+}
+
+/// Whether the kernel reports the clock as NTP-synchronized; `None` when it
+/// cannot tell.
+#[cfg(target_os = "linux")]
+fn synced() -> Option<bool> {
+    // SAFETY: `modes = 0` only reads the kernel's state into `tx`.
+    let mut tx: libc::timex = unsafe { std::mem::zeroed() };
+    let state = unsafe { libc::adjtimex(&mut tx) };
+    if state < 0 {
+        return None;
+    }
+    Some(state != libc::TIME_ERROR && tx.status & libc::STA_UNSYNC == 0)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn synced() -> Option<bool> {
+    None
+}
+
+/// The `clock` object of a record.
+pub fn json() -> Value {
+    let synced = synced();
+    let source = match synced {
+        Some(true) => "ntp",
+        Some(false) => "local",
+        None => "unknown",
+    };
+    json!({ "source": source, "synced": synced })
+}
@@ -122,6 +122,18 @@ fn cli_reports_bad_calibration_header() {
         .stderr(predicate::str::contains("Invalid headers"));
 }
 
+#[test]
+fn cli_weighing_commands_open_the_scale_alone() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config").arg(&cfg).arg("self-check");
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let s = String::from_utf8_lossy(&out);
+    assert!(s.contains("opening scale driver"), "{s}");
+    assert!(!s.contains("opening drivers"), "no motor is opened: {s}");
+}
+
 #[rstest]
 fn cli_self_check_reports_sps() {
     let dir = tempdir().unwrap();
@@ -133,3 +145,1652 @@ fn cli_self_check_reports_sps() {
     // Sim backend increments per read and should easily meet <50ms median for 80 SPS classification
     assert!(s.contains("Detected HX711 rate: 80 SPS") || s.contains("Detected HX711 rate: 10 SPS"));
 }
+
+#[rstest]
+fn cli_preflight_blocks_dose_and_lists_failures() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    // The sim scale starts at 0 g, below the plausible band.
+    writeln!(f, "\n[preflight]\nmin_g = 5.0").unwrap();
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "1"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("motor was not started"))
+        .stderr(predicate::str::contains("outside sanity band"));
+}
+
+#[rstest]
+fn cli_sim_estop_mid_run_aborts_with_estop_code() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "50"])
+        .env("DOSER_TEST_SIM_INC", "0.01")
+        .env("DOSER_TEST_SIM_ESTOP_AFTER_MS", "200");
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("Emergency stop"));
+}
+
+#[rstest]
+fn cli_sim_estop_file_blocks_preflight() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let toggle = dir.path().join("estop");
+    fs::write(&toggle, "").unwrap();
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "1"])
+        .env("DOSER_TEST_SIM_INC", "0.5")
+        .env("DOSER_TEST_SIM_ESTOP_FILE", &toggle);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("motor was not started"))
+        .stderr(predicate::str::contains("E-stop is active"));
+}
+
+#[rstest]
+fn cli_sim_jammed_motor_trips_no_progress() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "5"])
+        .env("DOSER_TEST_SIM_INC", "0.5")
+        .env("DOSER_TEST_SIM_LOAD", "1.0");
+    cmd.assert()
+        .code(3)
+        .stderr(predicate::str::contains("No progress"));
+}
+
+#[rstest]
+fn cli_reports_a_remediation_code_for_an_abort() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--json")
+        .arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "5"])
+        .env("DOSER_TEST_SIM_INC", "0.5")
+        .env("DOSER_TEST_SIM_LOAD", "1.0");
+    let out = cmd.assert().code(3).get_output().stdout.clone();
+    let err: serde_json::Value = String::from_utf8_lossy(&out)
+        .lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .find(|v| v.get("reason").is_some())
+        .expect("JSON error line");
+    assert_eq!(err["reason"], "NoProgress", "{err}");
+    // The jammed sim motor never moves material.
+    assert_eq!(err["remediation"], "E_NO_FLOW", "{err}");
+    // The limits this dose ran under.
+    assert_eq!(err["details"]["no_progress_ms"], 1200, "{err}");
+}
+
+#[rstest]
+fn cli_writes_a_diagnostic_bundle_when_a_dose_fails() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let bundles = dir.path().join("bundles");
+    let log = dir.path().join("doser.log");
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(
+        f,
+        "\n[logging]\nfile = {:?}\n\n[access]\ntechnician_pin = \"4711\"\n\n[bundle]\ndir = {:?}",
+        log.to_str().unwrap(),
+        bundles.to_str().unwrap()
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "5"])
+        .env("DOSER_TEST_SIM_INC", "0.5")
+        .env("DOSER_TEST_SIM_LOAD", "1.0");
+    cmd.assert()
+        .code(3)
+        .stderr(predicate::str::contains("Diagnostic bundle: "));
+    let bundle = fs::read_dir(&bundles)
+        .unwrap()
+        .next()
+        .expect("bundle directory")
+        .unwrap()
+        .path();
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(bundle.join("manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["error"]["remediation"], "E_NO_FLOW", "{manifest}");
+    assert!(manifest["run_id"].is_string(), "{manifest}");
+    assert!(
+        manifest["motor"]["steps_issued"].as_u64() > Some(0),
+        "{manifest}"
+    );
+    let telemetry = fs::read_to_string(bundle.join("telemetry.csv")).unwrap();
+    assert!(
+        telemetry.starts_with("ms,weight_g,sps,phase\n"),
+        "{telemetry}"
+    );
+    assert!(telemetry.lines().count() > 10, "{telemetry}");
+    let config = fs::read_to_string(bundle.join("config.toml")).unwrap();
+    assert!(
+        config.contains("<redacted>") && !config.contains("4711"),
+        "{config}"
+    );
+
+    // On demand: config, versions and the log the failed dose left.
+    let out = dir.path().join("manual");
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["bundle", "--out", out.to_str().unwrap()]);
+    let stdout = cmd.assert().success().get_output().stdout.clone();
+    let bundle = std::path::PathBuf::from(String::from_utf8_lossy(&stdout).trim());
+    assert!(bundle.starts_with(&out), "{bundle:?}");
+    let tail = fs::read_to_string(bundle.join("log-tail.txt")).unwrap();
+    assert!(tail.contains("dose aborted"), "{tail}");
+    assert!(!bundle.join("telemetry.csv").exists());
+}
+
+#[rstest]
+fn cli_storage_prunes_old_and_excess_bundles() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let bundles = dir.path().join("bundles");
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(
+        f,
+        "\n[bundle]\ndir = {:?}\n\n[retention]\nmax_mb = 1\nmax_age_days = 7",
+        bundles.to_str().unwrap()
+    )
+    .unwrap();
+    // Three 600 KiB bundles; the first ten days old, the others a minute apart.
+    let now = std::time::SystemTime::now();
+    let ages = [10 * 24 * 3600, 120, 60];
+    for (name, age) in ["a", "b", "c"].iter().zip(ages) {
+        let b = bundles.join(format!("doser-bundle-{name}"));
+        fs::create_dir_all(&b).unwrap();
+        fs::write(b.join("telemetry.csv"), vec![b'0'; 600 * 1024]).unwrap();
+        let t = now - std::time::Duration::from_secs(age);
+        fs::File::open(&b)
+            .unwrap()
+            .set_times(fs::FileTimes::new().set_modified(t))
+            .unwrap();
+    }
+    let storage = |action: &str| -> serde_json::Value {
+        let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+        cmd.arg("--config")
+            .arg(&cfg)
+            .args(["--json", "storage", action]);
+        let out = cmd.assert().success().get_output().stdout.clone();
+        serde_json::from_slice(&out).expect("storage JSON")
+    };
+    let status = storage("status");
+    assert_eq!(status["kinds"]["bundle"]["files"], 3, "{status}");
+    // a is too old; without it, b still leaves 1.2 MiB.
+    let prunable: Vec<String> = status["prunable"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p.as_str().unwrap().rsplit('-').next().unwrap().to_string())
+        .collect();
+    assert_eq!(prunable, ["a", "b"], "{status}");
+
+    assert_eq!(storage("prune")["pruned"].as_array().unwrap().len(), 2);
+    assert!(bundles.join("doser-bundle-c").exists());
+    let status = storage("status");
+    assert_eq!(status["kinds"]["bundle"]["files"], 1, "{status}");
+    assert_eq!(status["prunable"], serde_json::json!([]));
+}
+
+#[rstest]
+fn cli_storage_prunes_only_rotated_logs_and_real_bundle_dirs() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let bundles = dir.path().join("bundles");
+    fs::create_dir_all(&bundles).unwrap();
+    // The log is named like the config beside it: `cfg` next to `cfg.toml`.
+    let log = dir.path().join("cfg");
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(
+        f,
+        "\n[logging]\nfile = {:?}\nrotation = \"daily\"\n\n[bundle]\ndir = {:?}\n\n[retention]\nmax_age_days = 7",
+        log.to_str().unwrap(),
+        bundles.to_str().unwrap()
+    )
+    .unwrap();
+    let old = std::time::SystemTime::now() - std::time::Duration::from_secs(10 * 24 * 3600);
+    let age = |p: &std::path::Path| {
+        fs::File::open(p)
+            .unwrap()
+            .set_times(fs::FileTimes::new().set_modified(old))
+            .unwrap();
+    };
+    for name in [
+        "cfg.2026-01-01",
+        "cfg.2026-01-02-07",
+        "cfg.state.json",
+        "cfg.2026-01-01.bak",
+    ] {
+        fs::write(dir.path().join(name), "x\n").unwrap();
+        age(&dir.path().join(name));
+    }
+    age(&cfg);
+    fs::write(dir.path().join("cfg.2026-01-03"), "current\n").unwrap();
+    // A link loop named like a bundle is neither followed nor removed.
+    std::os::unix::fs::symlink(&bundles, bundles.join("doser-bundle-loop")).unwrap();
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--json", "storage", "prune"]);
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&out).expect("storage JSON");
+    let mut pruned: Vec<String> = report["pruned"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p.as_str().unwrap().rsplit('/').next().unwrap().to_string())
+        .collect();
+    pruned.sort();
+    assert_eq!(pruned, ["cfg.2026-01-01", "cfg.2026-01-02-07"], "{report}");
+    for kept in [
+        "cfg.toml",
+        "cfg.state.json",
+        "cfg.2026-01-01.bak",
+        "cfg.2026-01-03",
+    ] {
+        assert!(dir.path().join(kept).exists(), "{kept}");
+    }
+    assert!(bundles.join("doser-bundle-loop").symlink_metadata().is_ok());
+}
+
+#[rstest]
+#[cfg_attr(feature = "plugin", ignore = "plugin builds load the library")]
+fn cli_refuses_plugin_config_without_plugin_feature() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "\n[plugin]\npath = \"/nonexistent/libdriver.so\"").unwrap();
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config").arg(&cfg).args(["dose", "--grams", "1"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--features plugin"));
+}
+
+#[rstest]
+fn cli_rejects_unknown_driver_and_lists_available() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "\n[scale]\ndriver = \"nau7802\"").unwrap();
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config").arg(&cfg).args(["dose", "--grams", "1"]);
+    cmd.assert().failure().stderr(
+        predicate::str::contains("\"nau7802\" is not available")
+            .and(predicate::str::contains("sim")),
+    );
+}
+
+#[test]
+fn cli_backend_flag_overrides_the_configured_drivers() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "\n[scale]\ndriver = \"nau7802\"").unwrap();
+
+    // Forced simulation ignores the driver this build lacks.
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--backend", "sim", "--sim-clock", "dose", "--grams", "5"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert().success();
+
+    #[cfg(not(any(feature = "hardware", feature = "gpiod")))]
+    {
+        let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+        cmd.arg("--config")
+            .arg(&cfg)
+            .args(["--backend", "hardware", "dose", "--grams", "5"]);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("this build has no GPIO backend"));
+    }
+}
+
+#[test]
+fn cli_config_validate_reports_unknown_keys() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "\n[preflite]\nenabled = false").unwrap();
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config").arg(&cfg).args(["config", "validate"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("config OK"))
+        .stderr(predicate::str::contains("unknown config key preflite"));
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["config", "validate", "--strict"]);
+    cmd.assert().failure().stderr(
+        predicate::str::contains("unknown config keys").and(predicate::str::contains("preflite")),
+    );
+
+    // `strict = true` in the file makes every command strict.
+    let text = fs::read_to_string(&cfg).unwrap();
+    fs::write(&cfg, format!("strict = true\n{text}")).unwrap();
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--sim-clock", "dose", "--grams", "5"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("preflite"));
+}
+
+#[test]
+fn cli_config_migrate_rewrites_old_layouts_keeping_comments() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let old = fs::read_to_string(&cfg)
+        .unwrap()
+        .replace(
+            "sample_ms = 50",
+            "# per-read timeout\nsensor_ms = 50\nsettle_ms = 300",
+        )
+        .replace(
+            "epsilon_g = 0.02\n",
+            "epsilon_g = 0.02\nspeed_bands = [[1.0, 900], [0.5, 400]] # coarse to fine\n",
+        );
+    fs::write(&cfg, &old).unwrap();
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["config", "migrate", "--dry-run"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "# per-read timeout\nsample_ms = 50\n",
+    ));
+    assert_eq!(fs::read_to_string(&cfg).unwrap(), old);
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config").arg(&cfg).args(["config", "migrate"]);
+    cmd.assert().success().stderr(predicate::str::contains(
+        "timeouts.sensor_ms -> timeouts.sample_ms",
+    ));
+    let new = fs::read_to_string(&cfg).unwrap();
+    assert!(
+        new.contains("# per-read timeout\nsample_ms = 50\n"),
+        "{new}"
+    );
+    assert!(!new.contains("settle_ms"), "{new}");
+    assert!(
+        new.contains(
+            "speed_bands = [{ threshold_g = 1.0, sps = 900 }, { threshold_g = 0.5, sps = 400 }] # coarse to fine"
+        ),
+        "{new}"
+    );
+    assert!(new.contains("# pins are unused in sim backend"), "{new}");
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config").arg(&cfg).args(["config", "migrate"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("already current"));
+}
+
+#[test]
+fn cli_config_include_merges_the_base_under_the_file() {
+    let dir = tempdir().unwrap();
+    let base = write_valid_config(&dir);
+    let site = dir.path().join("site.toml");
+
+    // Tables merge key by key: only the rate is overridden.
+    fs::write(
+        &site,
+        "include = [\"cfg.toml\"]\n\n[filter]\nsample_rate_hz = 0\n",
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config").arg(&site).args(["config", "validate"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("sample_rate_hz"));
+
+    fs::write(
+        &site,
+        "include = [\"cfg.toml\"]\n\n[safety]\nmax_run_ms = 2500\n",
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&site)
+        .args(["--sim-clock", "dose", "--grams", "5"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert().success();
+
+    let text = fs::read_to_string(&base).unwrap();
+    fs::write(&base, format!("include = [\"site.toml\"]\n{text}")).unwrap();
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config").arg(&site).args(["config", "validate"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("include cycle"));
+}
+
+#[test]
+fn cli_dose_checks_the_tolerance_against_the_calibration_uncertainty() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    // ~0.01 g/count with about a gram of scatter: the gain is known to ~2 %.
+    let csv = dir.path().join("calib.csv");
+    let mut f = fs::File::create(&csv).unwrap();
+    writeln!(f, "raw,grams").unwrap();
+    for (i, noise) in [0.8, -1.1, 0.4, 1.2, -0.9, -0.3].iter().enumerate() {
+        writeln!(f, "{},{}", i * 1000, i as f64 * 10.0 + noise).unwrap();
+    }
+    drop(f);
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .arg("--calibration")
+        .arg(&csv)
+        .args(["--sim-clock", "dose", "--grams", "5", "--strict"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "completion tolerance 0.05 g is tighter than the calibration supports for 5 g",
+    ));
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .arg("--calibration")
+        .arg(&csv)
+        .args(["--sim-clock", "dose", "--grams", "5"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    let out = cmd.output().unwrap();
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("Warning: completion tolerance"), "{stderr}");
+}
+
+/// The sim hopper reads 0 counts, so `zero_counts` sets its contents.
+#[rstest]
+#[case::enough(-100_000, 0, "complete")]
+#[case::too_little(-300, 1, "hopper holds 3.0 g, dose needs 5.0 g")]
+fn cli_checks_hopper_before_dosing(
+    #[case] zero_counts: i32,
+    #[case] exit_code: i32,
+    #[case] needle: &str,
+) {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(
+        f,
+        "\n[hopper]\nhx711_dt = 16\nhx711_sck = 20\ngain_g_per_count = 0.01\nzero_counts = {zero_counts}"
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "5"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    let out = cmd.assert().code(exit_code).get_output().clone();
+    let text = String::from_utf8_lossy(&out.stdout) + String::from_utf8_lossy(&out.stderr);
+    assert!(text.contains(needle), "{text}");
+}
+
+#[rstest]
+fn cli_resumes_a_dose_interrupted_by_a_timeout() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let state = dir.path().join("resume.json");
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(
+        f,
+        "\n[preflight]\nenabled = false\n\n[resume]\nstate_file = {:?}",
+        state.to_str().unwrap()
+    )
+    .unwrap();
+    let doser = |args: &[&str]| {
+        let mut cmd = assert_cmd::Command::cargo_bin("doser_cli").unwrap();
+        cmd.arg("--config").arg(&cfg).args(args);
+        cmd
+    };
+
+    doser(&["dose", "--grams", "5"])
+        .env("DOSER_TEST_SIM_TIMEOUT", "1")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("doser resume"));
+    let recorded = fs::read_to_string(&state).unwrap();
+    assert!(recorded.contains("\"target_g\":5"), "{recorded}");
+
+    // Declining leaves the dose recorded.
+    doser(&["resume"])
+        .write_stdin("n\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("resume cancelled"));
+    assert!(state.exists());
+
+    doser(&["resume", "--yes"])
+        .env("DOSER_TEST_SIM_INC", "0.5")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("5.00 g to go"))
+        .stdout(predicate::str::contains("final:"));
+    assert!(!state.exists(), "completed dose clears the state");
+
+    doser(&["resume", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no interrupted dose"));
+}
+
+#[rstest]
+fn cli_resume_rezeroes_a_disturbance_while_paused() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let state = dir.path().join("resume.json");
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(
+        f,
+        "\n[preflight]\nenabled = false\n\n[resume]\nstate_file = {:?}\ntrack_zero = true",
+        state.to_str().unwrap()
+    )
+    .unwrap();
+    // The sim reads 0 counts (0.01 g each): 3 g above the tare, 1 g above the pause.
+    fs::write(
+        &state,
+        r#"{"target_g":5.0,"zero_counts":-300,"paused_counts":-100,"run_id":"r1"}"#,
+    )
+    .unwrap();
+    let doser = |stdin: &str| {
+        let mut cmd = assert_cmd::Command::cargo_bin("doser_cli").unwrap();
+        cmd.arg("--config")
+            .arg(&cfg)
+            .arg("resume")
+            .env("DOSER_TEST_SIM_INC", "0.5")
+            .write_stdin(stdin);
+        cmd
+    };
+
+    // Kept as product: 3 g dosed.
+    doser("n\nn\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("+1.00 g while paused"))
+        .stderr(predicate::str::contains("3.00 of 5.00 g dispensed"));
+    // Re-zeroed: the dose stood at 2 g when it stopped.
+    doser("y\ny\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "2.00 of 5.00 g dispensed, 3.00 g to go",
+        ));
+}
+
+#[rstest]
+fn cli_prints_a_ticket_after_the_dose() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let printer = dir.path().join("lp0");
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(
+        f,
+        "\n[ticket]\ntemplate = \"\"\"\nTARGET {{target_g}} g\nNET {{hold_g}} g\nBY {{operator}} AT {{date}}\"\"\"\noperator = \"line 2\""
+    )
+    .unwrap();
+    drop(f);
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "2", "--operator", "kim"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("TARGET 2.00 g"))
+        .stdout(predicate::str::contains("BY kim AT 20"));
+
+    // With a device the ticket goes there, and the config operator applies.
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "device = {:?}", printer.to_str().unwrap()).unwrap();
+    drop(f);
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "2"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("TARGET").not());
+    let printed = fs::read_to_string(&printer).unwrap();
+    assert!(printed.contains("NET 2."), "{printed}");
+    assert!(printed.contains("BY line 2 AT"), "{printed}");
+}
+
+#[rstest]
+fn cli_rejects_unknown_ticket_placeholders_before_dosing() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "\n[ticket]\ntemplate = \"{{final_g}} {{batch}}\"").unwrap();
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "2"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("{batch}"));
+}
+
+#[rstest]
+fn cli_formats_the_dose_result_with_a_template() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args([
+            "dose",
+            "--grams",
+            "2",
+            "--format",
+            "final={final_g} took {duration_ms}ms {{ok}}",
+        ])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"(?m)^final=2\.\d+ took \d+ms \{ok\}$").unwrap());
+
+    // Unknown placeholders fail before dosing, as does mixing with --json.
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "2", "--format", "{weight}"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("{weight}"));
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--json", "dose", "--grams", "2", "--format", "{final_g}"]);
+    cmd.assert().failure();
+}
+
+#[rstest]
+fn cli_self_check_writes_filter_defaults_for_the_detected_rate() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config").arg(&cfg).arg("self-check");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("--write-filter-defaults"));
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["self-check", "--write-filter-defaults"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote"));
+
+    // Only the filter keys change; comments and other sections survive.
+    let text = fs::read_to_string(&cfg).unwrap();
+    assert!(text.contains("# pins are unused"), "{text}");
+    assert!(!text.contains("ma_window = 1\n"), "{text}");
+    assert!(text.contains("sensor_read_timeout_ms = 100"), "{text}");
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config").arg(&cfg).arg("self-check");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("matches the"));
+}
+
+#[rstest]
+fn cli_soak_reports_drift_memory_and_jitter() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+
+    // Loop timing on a shared test host is noisy, so only drift is held tight.
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--json", "soak", "--cycles", "4", "--target", "1"])
+        .args(["--max-drift-g", "0.5", "--max-jitter-ratio", "1000000"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let line = String::from_utf8_lossy(&out)
+        .lines()
+        .find(|l| l.contains("\"cycles\""))
+        .expect("soak report")
+        .to_string();
+    let v: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(v["cycles"], 4);
+    assert!(v["drift_g"].as_f64().unwrap().abs() <= 0.5, "{v}");
+    assert!(v["jitter_last_us"].is_number(), "{v}");
+    assert_eq!(v["failures"].as_array().unwrap().len(), 0, "{v}");
+
+    // A dose that fails stops the soak, names the cycle and keeps its exit code.
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["soak", "--cycles", "3", "--target", "5"])
+        .env("DOSER_TEST_SIM_INC", "0.5")
+        .env("DOSER_TEST_SIM_LOAD", "1.0");
+    cmd.assert()
+        .code(3)
+        .stderr(predicate::str::contains("dose 1 of 3 failed"));
+}
+
+#[rstest]
+#[case::sampler(&[])]
+#[case::direct(&["--direct"])]
+fn cli_stats_report_resource_usage(#[case] extra: &[&str]) {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "2", "--stats"])
+        .args(extra)
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    let allocs = if cfg!(feature = "alloc-stats") {
+        predicate::str::is_match(r"Allocations: \d+ \(\d+ bytes\)").unwrap()
+    } else {
+        predicate::str::is_match(r"Allocations: n/a").unwrap()
+    };
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Peak RSS:"))
+        .stderr(predicate::str::contains("CPU process / control thread:"))
+        .stderr(
+            predicate::str::is_match(
+                r"Motor steps / jitter avg/max \(us\) / missed periods: \d+ / 0 / 0 / 0",
+            )
+            .unwrap(),
+        )
+        .stderr(allocs);
+}
+
+#[rstest]
+fn cli_tags_the_dose_with_a_run_id() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--json", "dose", "--grams", "2"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let out = String::from_utf8_lossy(&out);
+    let result: serde_json::Value = out
+        .lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .find(|v| v.get("final_g").is_some())
+        .expect("dose result");
+    let run_id = result["run_id"].as_str().unwrap();
+    assert!(is_uuid_v4(run_id), "{run_id}");
+    // The log lines of the run carry the same ID.
+    let tagged = out
+        .lines()
+        .filter(|l| l.contains("\"level\"") && l.contains(run_id))
+        .count();
+    assert!(tagged >= 2, "{out}");
+
+    // Every dose gets its own ID.
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "2", "--format", "{run_id}"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(run_id).not());
+}
+
+#[rstest]
+fn cli_reports_the_display_weight_beside_the_control_weight() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "\n[filter.display]\nma_window = 4").unwrap();
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--json", "dose", "--grams", "2"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let result: serde_json::Value = String::from_utf8_lossy(&out)
+        .lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .find(|v| v.get("final_g").is_some())
+        .expect("dose result");
+    // The control loop saw the unfiltered ramp; the display averages the
+    // last four 0.5 g steps and trails it.
+    let final_g = result["final_g"].as_f64().unwrap();
+    let display_g = result["display_g"].as_f64().unwrap();
+    assert!(final_g >= 1.9, "{result}");
+    assert!(display_g < final_g - 0.5, "{result}");
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "2", "--format", "{final_g}/{display_g}"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert()
+        .success()
+        // How far the display trails depends on how the sampler's readings
+        // line up with the loop, so only that it trails is checked.
+        .stdout(predicate::str::is_match(r"(?m)^2\.\d+/[01]\.\d+$").unwrap());
+}
+
+#[test]
+fn cli_reports_the_weight_uncertainty() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--json", "dose", "--grams", "2"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let result: serde_json::Value = String::from_utf8_lossy(&out)
+        .lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .find(|v| v.get("final_g").is_some())
+        .expect("dose result");
+    // The simulated scale is noiseless, but how its ramp lines up with the
+    // control loop depends on scheduling, so only the shape is checked.
+    let sigma = result["sigma_g"].as_f64().expect("sigma_g");
+    assert!(sigma.is_finite() && sigma >= 0.0, "{result}");
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "2", "--format", "{final_g} ± {sigma_g}"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"(?m)^\d+(\.\d+)? ± \d+\.\d{3}$").unwrap());
+}
+
+#[test]
+fn cli_reports_the_build_in_version_and_dose_output() {
+    let dir = tempdir().unwrap();
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    // No config is needed (the default path does not exist here).
+    cmd.current_dir(dir.path())
+        .args(["--json", "version", "--verbose"]);
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let build: serde_json::Value = serde_json::from_slice(&out).expect("version JSON");
+    assert_eq!(build["version"], env!("CARGO_PKG_VERSION"), "{build}");
+    assert!(
+        build["git"].as_str().is_some_and(|g| !g.is_empty()),
+        "{build}"
+    );
+    assert!(build["features"].is_array(), "{build}");
+    assert_eq!(build["crates"]["doser_core"], "0.1.0", "{build}");
+
+    let cfg = write_valid_config(&dir);
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--json", "dose", "--grams", "2"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let result: serde_json::Value = String::from_utf8_lossy(&out)
+        .lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .find(|v| v.get("final_g").is_some())
+        .expect("dose result");
+    assert_eq!(result["build"], build, "{result}");
+}
+
+#[test]
+fn cli_sim_clock_runs_a_dose_on_virtual_time() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    // 1000 reads at 80 Hz: 12.5 s of loop time.
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--sim-clock", "--json", "dose", "--grams", "500"])
+        .args(["--max-run-ms", "600000"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let result: serde_json::Value = String::from_utf8_lossy(&out)
+        .lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .find(|v| v.get("final_g").is_some())
+        .expect("dose result");
+    let coarse_ms = result["phases"]["coarse_ms"].as_u64().unwrap();
+    assert!(coarse_ms >= 12_000, "{result}");
+    assert!(
+        result["duration_ms"].as_u64().unwrap() < coarse_ms,
+        "{result}"
+    );
+
+    // Real drivers keep real time.
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "\n[scale]\ndriver = \"hx711\"").unwrap();
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--sim-clock", "dose", "--grams", "5"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--sim-clock needs the sim"));
+}
+
+#[test]
+fn cli_request_id_runs_a_dose_at_most_once() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let store = dir.path().join("requests.json");
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "\n[requests]\nfile = {:?}", store.to_str().unwrap()).unwrap();
+    let dose = |id: &str, grams: &str| {
+        let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+        cmd.arg("--config")
+            .arg(&cfg)
+            .args(["--sim-clock", "dose", "--grams", grams, "--request-id", id])
+            .env("DOSER_TEST_SIM_INC", "0.5");
+        cmd.assert()
+    };
+    let first = dose("plc-1", "5").success().get_output().stdout.clone();
+    let first = String::from_utf8_lossy(&first);
+    let entry = &serde_json::from_str::<serde_json::Value>(&fs::read_to_string(&store).unwrap())
+        .unwrap()["plc-1"];
+    assert_eq!(entry["state"], "done", "{entry}");
+    assert!(
+        entry["result"]["final_g"].as_f64().unwrap() > 4.0,
+        "{entry}"
+    );
+    let shown = first
+        .lines()
+        .find(|l| l.starts_with("final: "))
+        .unwrap()
+        .to_string();
+
+    // The retry reports the first run's result without dosing.
+    dose("plc-1", "5")
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "{shown} (request plc-1 already done by run"
+        )));
+    dose("plc-1", "7")
+        .failure()
+        .stderr(predicate::str::contains("request plc-1 was already used"));
+
+    // A request whose run died mid-dose is not dosed again.
+    fs::write(
+        &store,
+        r#"{"plc-2": {"state": "running", "run_id": "r-dead", "grams": 5.0}}"#,
+    )
+    .unwrap();
+    dose("plc-2", "5")
+        .failure()
+        .stderr(predicate::str::contains(
+            "started by run r-dead and did not finish",
+        ));
+}
+
+#[test]
+fn cli_request_given_up_before_dosing_can_be_retried() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let store = dir.path().join("requests.json");
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "\n[requests]\nfile = {:?}", store.to_str().unwrap()).unwrap();
+    // The simulated scale stays empty: no container ever settles.
+    writeln!(f, "\n[autotare]\nmax_wait_ms = 500").unwrap();
+    let dose = || {
+        let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+        cmd.arg("--config")
+            .arg(&cfg)
+            .args([
+                "--sim-clock",
+                "dose",
+                "--grams",
+                "5",
+                "--request-id",
+                "plc-1",
+            ])
+            .env("DOSER_TEST_SIM_INC", "0.5");
+        cmd.assert()
+    };
+    dose()
+        .failure()
+        .stderr(predicate::str::contains("no container settled"));
+    let all: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&store).unwrap()).unwrap();
+    assert!(all.get("plc-1").is_none(), "claim not released: {all}");
+
+    // Nothing moved, so the retry doses.
+    writeln!(f, "min_container_g = 0").unwrap();
+    dose()
+        .success()
+        .stdout(predicate::str::contains("final: 5.00 g"));
+    let all: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&store).unwrap()).unwrap();
+    assert_eq!(all["plc-1"]["state"], "done", "{all}");
+}
+
+#[test]
+fn cli_broadcasts_weight_datagrams_during_a_dose() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let rx = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(
+        f,
+        "\n[broadcast]\ngroup = \"{}\"\nstation = \"line1-st3\"",
+        rx.local_addr().unwrap()
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--sim-clock", "dose", "--grams", "5"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert().success();
+
+    // Loopback datagrams are queued by the time the process has exited.
+    let mut buf = [0u8; 1024];
+    let mut msgs = Vec::new();
+    rx.set_nonblocking(true).unwrap();
+    while let Ok(n) = rx.recv(&mut buf) {
+        msgs.push(serde_json::from_slice::<serde_json::Value>(&buf[..n]).unwrap());
+    }
+    let last = msgs.last().expect("at least one datagram");
+    assert!(msgs.iter().all(|m| m["station"] == "line1-st3"), "{msgs:?}");
+    assert_eq!(last["state"], "complete", "{msgs:?}");
+    assert_eq!(last["target_g"], 5.0);
+    assert_eq!(last["seq"], msgs.len() as u64 - 1);
+}
+
+#[test]
+fn cli_learns_constants_across_doses() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let store = dir.path().join("learned.json");
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "\n[learned]\nfile = {:?}", store.to_str().unwrap()).unwrap();
+    let run = |args: &[&str]| -> serde_json::Value {
+        let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+        cmd.arg("--config")
+            .arg(&cfg)
+            .args(["--sim-clock", "--json"])
+            .args(args)
+            .env("DOSER_TEST_SIM_INC", "0.5");
+        let out = cmd.assert().success().get_output().stdout.clone();
+        String::from_utf8_lossy(&out)
+            .lines()
+            .rev()
+            .find_map(|l| serde_json::from_str(l).ok())
+            .expect("JSON output")
+    };
+    run(&["dose", "--grams", "10"]);
+    run(&["dose", "--grams", "20"]);
+    let learned = run(&["learned", "show"]);
+    let default = &learned["default"];
+    assert_eq!(default["doses"], 2, "{learned}");
+    let g_per_step = default["g_per_step"].as_f64().unwrap();
+    assert!(g_per_step > 0.0, "{learned}");
+
+    // A plan without --g-per-step uses the learned value.
+    let plan = run(&["plan", "--grams", "10"]);
+    assert!(plan["est_ms"].as_u64().is_some(), "{plan}");
+
+    assert_eq!(run(&["learned", "reset"])["reset"][0], "default");
+    assert_eq!(run(&["learned", "show"]), serde_json::json!({}));
+}
+
+#[test]
+fn cli_learns_epsilon_from_the_dose_bias() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let store = dir.path().join("learned.json");
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(
+        f,
+        "\n[learned]\nfile = {:?}\nepsilon_min_g = 0.01\nepsilon_max_g = 0.03\nepsilon_window = 2",
+        store.to_str().unwrap()
+    )
+    .unwrap();
+    let run = |args: &[&str]| -> serde_json::Value {
+        let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+        cmd.arg("--config")
+            .arg(&cfg)
+            .args(["--sim-clock", "--json"])
+            .args(args)
+            // Coarse steps: every dose lands ~0.5 g over.
+            .env("DOSER_TEST_SIM_INC", "0.7");
+        let out = cmd.assert().success().get_output().stdout.clone();
+        String::from_utf8_lossy(&out)
+            .lines()
+            .rev()
+            .find_map(|l| serde_json::from_str(l).ok())
+            .expect("JSON output")
+    };
+    for _ in 0..4 {
+        let dose = run(&["dose", "--grams", "10"]);
+        assert!(dose["final_g"].as_f64().unwrap() > 10.0, "{dose}");
+    }
+    let learned = run(&["learned", "show"]);
+    let default = &learned["default"];
+    // Widened by at most the step and held at the bound, with one change logged.
+    assert_eq!(default["epsilon_g"], 0.03, "{learned}");
+    let log = default["epsilon_log"].as_array().unwrap();
+    assert_eq!(log.len(), 1, "{learned}");
+    assert_eq!(
+        (log[0]["from_g"].as_f64(), log[0]["doses"].as_u64()),
+        (Some(0.02), Some(2))
+    );
+    assert!(log[0]["at"].as_str().unwrap().ends_with('Z'), "{learned}");
+    assert!(log[0]["clock"]["source"].is_string(), "{learned}");
+    assert_eq!(
+        default["errors_g"].as_array().unwrap().len(),
+        2,
+        "{learned}"
+    );
+}
+
+#[rstest]
+fn cli_plans_a_dose_without_hardware() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    // A scale driver that cannot open proves no hardware is touched.
+    writeln!(f, "\n[scale]\ndriver = \"no-such-driver\"").unwrap();
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config").arg(&cfg).args([
+        "--json",
+        "plan",
+        "--grams",
+        "10",
+        "--g-per-step",
+        "0.001",
+    ]);
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let plan: serde_json::Value = serde_json::from_slice(&out).expect("plan JSON");
+    let sps: Vec<_> = plan["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["sps"].as_u64().unwrap())
+        .collect();
+    // Coarse to slow_at_g, then the tapered fine speed.
+    assert_eq!(sps, vec![1000, 200], "{plan}");
+    assert_eq!(plan["steps"][0]["est_ms"].as_u64(), Some(9000), "{plan}");
+    assert!(plan["est_ms"].as_u64().unwrap() > 9000, "{plan}");
+
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["plan", "--grams", "10"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("motor stops at 9.98 g"))
+        .stdout(predicate::str::contains("pass --g-per-step"));
+}
+
+#[rstest]
+fn cli_autotare_waits_for_a_container() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let dose = || {
+        let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+        cmd.arg("--config")
+            .arg(&cfg)
+            .args(["--sim-clock", "dose", "--grams", "5"])
+            .env("DOSER_TEST_SIM_INC", "0.5");
+        cmd
+    };
+    // The simulated scale stays empty: no container ever settles.
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "\n[autotare]\nmax_wait_ms = 500").unwrap();
+    dose().assert().failure().stderr(predicate::str::contains(
+        "no container settled within 500 ms",
+    ));
+
+    // Any settled weight counts as the container: tare there and dose.
+    writeln!(f, "min_container_g = 0").unwrap();
+    dose()
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("tared"))
+        .stdout(predicate::str::contains("final: 5.00 g"));
+}
+
+#[rstest]
+fn cli_blends_components_to_running_totals() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["--sim-clock", "--json", "blend", "--yes"])
+        .args(["--part", "A=10", "--part", "B=25"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let blend: serde_json::Value = String::from_utf8_lossy(&out)
+        .lines()
+        .rev()
+        .find_map(|l| serde_json::from_str(l).ok())
+        .expect("blend JSON");
+    let parts = blend["components"].as_array().unwrap();
+    assert_eq!(parts.len(), 2, "{blend}");
+    assert_eq!(parts[0]["name"], "A");
+    assert_eq!(parts[1]["target_total_g"], 25.0);
+    // B doses only what A left to reach the total.
+    let a = parts[0]["dosed_g"].as_f64().unwrap();
+    let b = parts[1]["dosed_g"].as_f64().unwrap();
+    assert!(
+        (a - 10.0).abs() < 0.5 && (a + b - 25.0).abs() < 0.5,
+        "{blend}"
+    );
+    assert_eq!(blend["total_g"], parts[1]["total_g"]);
+
+    // Totals must rise.
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["blend", "--yes", "--part", "A=10", "--part", "B=5"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("totals must be positive and rise"));
+}
+
+#[rstest]
+fn cli_simulate_checks_assertions() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let simulate = |assert: &str| {
+        let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+        cmd.arg("--config").arg(&cfg).args([
+            "--json",
+            "simulate",
+            "--grams",
+            "5",
+            "--runs",
+            "20",
+            "--g-per-step",
+            "0.01",
+            "--assert",
+            assert,
+        ]);
+        cmd
+    };
+    let out = simulate("abort_rate<=0")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&out).expect("simulate JSON");
+    assert_eq!(report["runs"], 20, "{report}");
+    assert_eq!(report["asserts"][0]["ok"], true, "{report}");
+    assert!(report["metrics"]["overshoot_p95"].is_number(), "{report}");
+
+    // Same seed, same numbers.
+    let again = simulate("abort_rate<=0").output().unwrap().stdout;
+    assert_eq!(out, again);
+
+    simulate("overshoot_p95<0")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("\"ok\":false"));
+}
+
+#[rstest]
+fn cli_tune_optimize_recommends_keys_that_simulate_as_scored() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let tune = || {
+        let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+        cmd.arg("--config").arg(&cfg).args([
+            "--json",
+            "tune",
+            "--optimize",
+            "--grams",
+            "1",
+            "--candidates",
+            "16",
+            "--runs",
+            "10",
+            "--g-per-step",
+            "0.005",
+        ]);
+        cmd
+    };
+    let out = tune().assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&out).expect("tune JSON");
+    assert_eq!(report["evaluated"], 16, "{report}");
+    assert!(
+        report["best"]["cost"].as_f64().unwrap() < report["current"]["cost"].as_f64().unwrap(),
+        "{report}"
+    );
+    let rec = &report["recommended"];
+    assert_eq!(
+        rec["control"]["speed_bands"].as_array().unwrap().len(),
+        3,
+        "{report}"
+    );
+    assert_eq!(rec["predictor"]["enabled"], true, "{report}");
+
+    // Same seed, same recommendation.
+    assert_eq!(out, tune().output().unwrap().stdout);
+
+    // Pasted into the config, the keys simulate to the metrics they were
+    // scored with.
+    let p = &rec["predictor"];
+    let text = fs::read_to_string(&cfg).unwrap().replace(
+        "[control]\n",
+        &format!(
+            "[control]\nspeed_bands = {}\n",
+            rec["control"]["speed_bands"]
+        ),
+    ) + &format!(
+        "\n[predictor]\nenabled = true\nwindow = {}\nextra_latency_ms = {}\nmin_progress_ratio = {}\n",
+        p["window"], p["extra_latency_ms"], p["min_progress_ratio"]
+    );
+    fs::write(&cfg, text).unwrap();
+    let out = Command::cargo_bin("doser_cli")
+        .unwrap()
+        .arg("--config")
+        .arg(&cfg)
+        .args([
+            "--json",
+            "simulate",
+            "--grams",
+            "1",
+            "--runs",
+            "10",
+            "--g-per-step",
+            "0.005",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let sim: serde_json::Value = serde_json::from_slice(&out).expect("simulate JSON");
+    for m in ["overshoot_p95", "duration_p95_ms", "abort_rate"] {
+        assert_eq!(
+            sim["metrics"][m], report["best"]["metrics"][m],
+            "{m}: {sim}"
+        );
+    }
+}
+
+#[rstest]
+fn cli_tune_needs_optimize() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["tune", "--grams", "1", "--g-per-step", "0.005"]);
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("--optimize"));
+}
+
+#[rstest]
+fn cli_verifies_the_calibration_and_records_the_result() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(
+        f,
+        "\n# bench scale\n[calibration]\ngain_g_per_count = 0.01\nzero_counts = 0"
+    )
+    .unwrap();
+    let verify = |weight: &str| {
+        let mut cmd = assert_cmd::Command::cargo_bin("doser_cli").unwrap();
+        cmd.arg("--config")
+            .arg(&cfg)
+            .args(["verify-cal", "--weight", weight])
+            .write_stdin("\n\n");
+        cmd
+    };
+
+    // The simulated scale stays empty: a 0.05 g reference is within the
+    // default 0.1 g tolerance, a 100 g one is not.
+    verify("0.05")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("PASS"));
+    let text = fs::read_to_string(&cfg).unwrap();
+    assert!(text.contains("# bench scale"), "{text}");
+    let parsed: toml::Value = toml::from_str(&text).unwrap();
+    let v = &parsed["calibration"]["verified"];
+    assert_eq!(v["passed"].as_bool(), Some(true), "{text}");
+    assert_eq!(v["reference_g"].as_float(), Some(0.05));
+
+    verify("100")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("FAIL"))
+        .stderr(predicate::str::contains("calibration verification failed"));
+    let parsed: toml::Value = toml::from_str(&fs::read_to_string(&cfg).unwrap()).unwrap();
+    assert_eq!(
+        parsed["calibration"]["verified"]["passed"].as_bool(),
+        Some(false)
+    );
+
+    // Without input there is nothing to measure.
+    let mut cmd = assert_cmd::Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["verify-cal", "--weight", "1"])
+        .write_stdin("");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cancelled"));
+}
+
+#[test]
+fn cli_reserves_calibration_commands_for_technicians() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(
+        f,
+        "\n[calibration]\ngain_g_per_count = 0.01\nzero_counts = 0\n\n[access]\ntechnician_pin = \"4711\""
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        let mut cmd = assert_cmd::Command::cargo_bin("doser_cli").unwrap();
+        cmd.env_remove("DOSER_PIN")
+            .arg("--config")
+            .arg(&cfg)
+            .args(args)
+            .write_stdin("\n\n");
+        cmd
+    };
+
+    // Operator commands need no PIN.
+    run(&["health"]).assert().success();
+
+    run(&["verify-cal", "--weight", "0.05"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("technician command"));
+    run(&["--pin", "1234", "verify-cal", "--weight", "0.05"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("wrong technician PIN"));
+    run(&["self-check", "--write-filter-defaults"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("technician command"));
+    assert!(!fs::read_to_string(&cfg).unwrap().contains("verified"));
+
+    run(&["--pin", "4711", "verify-cal", "--weight", "0.05"])
+        .assert()
+        .success();
+    run(&["verify-cal", "--weight", "0.05"])
+        .env("DOSER_PIN", "4711")
+        .assert()
+        .success();
+
+    // A calibration CSV takes a technician whatever the command.
+    let csv = dir.path().join("calib.csv");
+    fs::write(&csv, "raw,grams\n0,0\n1000,10\n2000,20\n").unwrap();
+    let csv = csv.to_str().unwrap();
+    run(&["--calibration", csv, "health"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--calibration is a technician option",
+        ));
+    run(&["--pin", "4711", "--calibration", csv, "health"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn cli_refuses_a_dose_while_another_holds_the_run_lock() {
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let lock = dir.path().join("motor.lock");
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(f, "\n[runner]\nlock_file = {:?}", lock.to_str().unwrap()).unwrap();
+    let dose = |json: bool| {
+        let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+        if json {
+            cmd.arg("--json");
+        }
+        cmd.arg("--config")
+            .arg(&cfg)
+            .args(["dose", "--grams", "5"])
+            .env("DOSER_TEST_SIM_INC", "0.5");
+        cmd
+    };
+
+    let held = doser_hardware::LockFile::acquire(&lock).unwrap();
+    let pid = format!("pid {}", std::process::id());
+    dose(false)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Another dose is running"))
+        .stderr(predicate::str::contains(pid.as_str()));
+    let out = dose(true).output().unwrap();
+    let err: serde_json::Value = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .find_map(|l| serde_json::from_str(l).ok())
+        .expect("JSON error line");
+    assert_eq!(err["reason"], "Busy");
+    assert_eq!(err["pid"], std::process::id());
+
+    drop(held);
+    dose(false).assert().success();
+}
+
+/// `xxxxxxxx-xxxx-4xxx-[89ab]xxx-xxxxxxxxxxxx`
+fn is_uuid_v4(id: &str) -> bool {
+    predicate::str::is_match(
+        r"^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$",
+    )
+    .unwrap()
+    .eval(id)
+}
+
+#[cfg(unix)]
+#[rstest]
+fn cli_sigterm_halts_the_dose_and_records_a_clean_shutdown() {
+    use std::time::{Duration, Instant};
+
+    let dir = tempdir().unwrap();
+    let cfg = write_valid_config(&dir);
+    let marker = dir.path().join("shutdown.json");
+    let log = dir.path().join("doser.log");
+    let mut f = fs::OpenOptions::new().append(true).open(&cfg).unwrap();
+    writeln!(
+        f,
+        "\n[logging]\nfile = {:?}\n\n[shutdown]\nmarker = {:?}",
+        log.to_str().unwrap(),
+        marker.to_str().unwrap()
+    )
+    .unwrap();
+    let read_marker = || -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(&marker).unwrap_or_default()).unwrap_or_default()
+    };
+
+    let mut child = Command::cargo_bin("doser_cli")
+        .unwrap()
+        .arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "50"])
+        .env("DOSER_TEST_SIM_INC", "0.01")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while read_marker()["state"] != "running" {
+        assert!(Instant::now() < deadline, "dose did not start");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    std::thread::sleep(Duration::from_millis(100));
+    // SAFETY: signalling a child process this test spawned.
+    assert_eq!(unsafe { libc::kill(child.id() as i32, libc::SIGTERM) }, 0);
+    let status = child.wait().unwrap();
+    assert_eq!(status.code(), Some(2));
+
+    let m = read_marker();
+    assert_eq!(m["state"], "clean", "{m}");
+    assert_eq!(m["outcome"], "signal", "{m}");
+    // The buffered log file was flushed before exit.
+    let text = fs::read_to_string(&log).unwrap();
+    assert!(text.contains("clean shutdown recorded"), "{text}");
+
+    // A marker left `running` is reported by the next dose.
+    fs::write(&marker, r#"{"state":"running","run_id":"lost"}"#).unwrap();
+    let mut cmd = Command::cargo_bin("doser_cli").unwrap();
+    cmd.arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "5"])
+        .env("DOSER_TEST_SIM_INC", "0.5");
+    cmd.assert().success().stderr(predicate::str::contains(
+        "dose lost did not shut down cleanly",
+    ));
+    assert_eq!(read_marker()["outcome"], "complete");
+}
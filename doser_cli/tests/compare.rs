@@ -0,0 +1,84 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn write_set(dir: &tempfile::TempDir, name: &str, finals: &[f64], settle_ms: u64) -> PathBuf {
+    let mut text = String::from("not json: log noise is ignored\n");
+    for (i, f) in finals.iter().enumerate() {
+        text.push_str(&format!(
+            r#"{{"timestamp":{i},"target_g":10.0,"final_g":{f},"duration_ms":{d},"profile":"test","slope_ema":null,"stop_at_g":null,"coast_comp_g":null,"phases":{{"coarse_ms":400,"fine_ms":300,"settle_ms":{settle_ms}}},"abort_reason":null}}"#,
+            d = 700 + settle_ms
+        ));
+        text.push('\n');
+    }
+    text.push_str(r#"{"timestamp":9,"target_g":10.0,"final_g":null,"duration_ms":50,"profile":"test","phases":null,"abort_reason":"NoProgress"}"#);
+    text.push('\n');
+    let path = dir.path().join(name);
+    fs::write(&path, text).unwrap();
+    path
+}
+
+#[test]
+fn compare_reports_significant_overshoot_change() {
+    let dir = tempdir().unwrap();
+    let a = write_set(&dir, "a.jsonl", &[10.30, 10.32, 10.28, 10.31, 10.29], 200);
+    let b = write_set(&dir, "b.jsonl", &[10.05, 10.04, 10.06, 10.05, 10.05], 200);
+
+    // Compare needs no config file or hardware.
+    let out = Command::cargo_bin("doser_cli")
+        .unwrap()
+        .args(["--config", "/nonexistent.toml", "--json", "compare"])
+        .arg(&a)
+        .arg(&b)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let v: serde_json::Value = serde_json::from_slice(&out).expect("valid JSON");
+
+    assert_eq!(v["a"]["doses"], 5);
+    assert_eq!(v["a"]["aborted"], 1);
+    let err = &v["metrics"]["error_g"];
+    assert!((err["delta"].as_f64().unwrap() + 0.25).abs() < 1e-6);
+    assert!(err["p"].as_f64().unwrap() < 0.001);
+    assert_eq!(err["significant"], true);
+
+    // Identical settle times: no variance, so no test is possible.
+    let settle = &v["metrics"]["settle_ms"];
+    assert_eq!(settle["delta"].as_f64().unwrap(), 0.0);
+    assert!(settle["p"].is_null());
+    assert_eq!(settle["significant"], false);
+    assert_eq!(v["metrics"]["time_to_target_ms"]["a"]["mean"], 700.0);
+}
+
+#[test]
+fn compare_human_output_and_empty_set_error() {
+    let dir = tempdir().unwrap();
+    let a = write_set(&dir, "a.jsonl", &[10.1, 10.2, 10.0], 150);
+    let b = write_set(&dir, "b.jsonl", &[10.1, 10.0, 10.2], 250);
+
+    Command::cargo_bin("doser_cli")
+        .unwrap()
+        .arg("compare")
+        .arg(&a)
+        .arg(&b)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("settle_ms"))
+        .stdout(predicate::str::contains("Welch"));
+
+    let empty = dir.path().join("empty.jsonl");
+    fs::write(&empty, "").unwrap();
+    Command::cargo_bin("doser_cli")
+        .unwrap()
+        .arg("compare")
+        .arg(&a)
+        .arg(&empty)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no completed doses"));
+}
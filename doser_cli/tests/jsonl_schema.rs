@@ -96,8 +96,23 @@ fn jsonl_success_schema() {
     // Profile string
     assert!(v.get("profile").and_then(|x| x.as_str()).is_some());
 
+    // Wall-clock times (RFC 3339 UTC) and whether the clock can be trusted
+    for key in ["started_at", "ended_at"] {
+        let t = v[key].as_str().unwrap_or_default();
+        assert!(
+            t.len() >= 20 && t.ends_with('Z') && t.contains('T'),
+            "{key}: {t:?}"
+        );
+    }
+    assert!(v["started_at"].as_str() <= v["ended_at"].as_str());
+    assert!(
+        ["ntp", "local", "unknown"].contains(&v["clock"]["source"].as_str().unwrap_or_default()),
+        "{v}"
+    );
+    assert!(v["clock"]["synced"].is_boolean() || v["clock"]["synced"].is_null());
+
     // Telemetry fields are number or null
-    for key in ["slope_ema", "stop_at_g", "coast_comp_g"] {
+    for key in ["slope_ema", "stop_at_g", "coast_comp_g", "undershoot_g"] {
         let ok = match v.get(key) {
             Some(serde_json::Value::Null) => true,
             Some(serde_json::Value::Number(n)) => n.as_f64().is_some(),
@@ -106,6 +121,19 @@ fn jsonl_success_schema() {
         assert!(ok, "{key} should be number or null");
     }
 
+    // Per-phase timings are reported on success
+    let phases = v.get("phases").and_then(|x| x.as_object()).expect("phases");
+    for key in ["coarse_ms", "fine_ms", "settle_ms", "verify_ms"] {
+        assert!(
+            phases.get(key).and_then(|x| x.as_u64()).is_some(),
+            "phases.{key} should be an integer"
+        );
+    }
+
+    // Scale re-init counters: a healthy sim read never needs one
+    assert_eq!(v.get("scale_reinits").and_then(|x| x.as_u64()), Some(0));
+    assert_eq!(v.get("scale_recovered").and_then(|x| x.as_u64()), Some(0));
+
     // Abort reason must be null on success
     assert!(v.get("abort_reason").is_some());
     assert!(v.get("abort_reason").unwrap().is_null());
@@ -148,6 +176,7 @@ fn jsonl_abort_schema() {
     let abort = v.get("abort_reason").and_then(|x| x.as_str()).unwrap_or("");
     assert!(!abort.is_empty());
 
-    // Final must be null on abort
+    // Final and phase timings must be null on abort
     assert!(v.get("final_g").unwrap().is_null());
+    assert!(v.get("phases").unwrap().is_null());
 }
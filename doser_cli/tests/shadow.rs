@@ -0,0 +1,66 @@
+use assert_cmd::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempfile::tempdir;
+
+const CFG: &str = r#"
+[pins]
+hx711_dt = 5
+hx711_sck = 6
+motor_step = 13
+motor_dir = 19
+motor_en = 26
+
+[filter]
+ma_window = 1
+median_window = 1
+sample_rate_hz = 50
+
+[control]
+coarse_speed = 1000
+fine_speed = 200
+slow_at_g = 1.0
+hysteresis_g = 0.05
+stable_ms = 0
+epsilon_g = 0.02
+
+[timeouts]
+sample_ms = 10
+
+[safety]
+max_run_ms = 5000
+max_overshoot_g = 5.0
+no_progress_epsilon_g = 0.02
+no_progress_ms = 2000
+"#;
+
+/// The sim scale only moves while the sim motor runs; shadow mode never drives
+/// it, so the controller wants to run against an idle line the whole time.
+#[test]
+fn shadow_never_drives_motor_and_reports_divergence() {
+    let dir = tempdir().unwrap();
+    let cfg = dir.path().join("cfg.toml");
+    fs::write(&cfg, CFG).unwrap();
+
+    let out = Command::cargo_bin("doser_cli")
+        .unwrap()
+        .args(["--json", "--log-level", "error", "--config"])
+        .arg(&cfg)
+        .args(["shadow", "--grams", "1.0", "--max-run-ms", "300"])
+        .env("DOSER_TEST_SIM_INC", "0.5")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8_lossy(&out);
+    let line = stdout
+        .lines()
+        .find(|l| l.contains("\"divergent_ms\""))
+        .unwrap_or_else(|| panic!("no report line; stdout was: {stdout}"));
+    let v: serde_json::Value = serde_json::from_str(line).unwrap();
+
+    assert_eq!(v["last_g"].as_f64(), Some(0.0), "{v}");
+    assert_eq!(v["episodes"].as_u64(), Some(1), "{v}");
+    assert!(v["divergent_ms"].as_u64().unwrap() >= 200, "{v}");
+}
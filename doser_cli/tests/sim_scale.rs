@@ -0,0 +1,104 @@
+#![cfg(unix)]
+//! `doser sim-scale` serving the `socket` scale driver of another process.
+
+use assert_cmd::prelude::*;
+use std::fs;
+use std::process::Command;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+const CFG: &str = r#"
+[pins]
+hx711_dt = 5
+hx711_sck = 6
+motor_step = 13
+motor_dir = 19
+motor_en = 26
+
+[filter]
+ma_window = 1
+median_window = 1
+sample_rate_hz = 50
+
+[control]
+coarse_speed = 1000
+fine_speed = 200
+slow_at_g = 1.0
+hysteresis_g = 0.05
+stable_ms = 0
+epsilon_g = 0.02
+
+[timeouts]
+sample_ms = 100
+
+[safety]
+max_run_ms = 5000
+max_overshoot_g = 5.0
+no_progress_epsilon_g = 0.02
+no_progress_ms = 2000
+"#;
+
+/// Kills the server when the test ends, pass or fail.
+struct Server(std::process::Child);
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[test]
+fn shadow_follows_a_served_profile() {
+    let dir = tempdir().unwrap();
+    let server_cfg = dir.path().join("server.toml");
+    fs::write(&server_cfg, CFG).unwrap();
+    let sock = dir.path().join("scale.sock");
+    let client_cfg = dir.path().join("client.toml");
+    fs::write(
+        &client_cfg,
+        format!(
+            "{CFG}\n[scale]\ndriver = \"socket\"\nsocket = {:?}\n",
+            sock.display().to_string()
+        ),
+    )
+    .unwrap();
+    let profile = dir.path().join("ramp.csv");
+    fs::write(&profile, "ms,grams\n0,0\n400,5\n").unwrap();
+
+    let _server = Server(
+        Command::cargo_bin("doser_cli")
+            .unwrap()
+            .args(["--log-level", "error", "--config"])
+            .arg(&server_cfg)
+            .args(["sim-scale", "--rate-hz", "100", "--profile"])
+            .arg(&profile)
+            .arg("--serve")
+            .arg(&sock)
+            .spawn()
+            .unwrap(),
+    );
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !sock.exists() {
+        assert!(Instant::now() < deadline, "server did not start");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    let out = Command::cargo_bin("doser_cli")
+        .unwrap()
+        .args(["--json", "--log-level", "error", "--config"])
+        .arg(&client_cfg)
+        .args(["shadow", "--grams", "5", "--max-run-ms", "4000"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8_lossy(&out);
+    let line = stdout
+        .lines()
+        .find(|l| l.contains("\"divergent_ms\""))
+        .unwrap_or_else(|| panic!("no report line; stdout was: {stdout}"));
+    let v: serde_json::Value = serde_json::from_str(line).unwrap();
+    let last_g = v["last_g"].as_f64().unwrap();
+    assert!((last_g - 5.0).abs() < 0.05, "{v}");
+}
@@ -6,7 +6,9 @@ use std::process::Command;
 use tempfile::tempdir;
 
 #[rstest]
-fn hx711_timeout_bubbles_to_cli() {
+#[case::read_timeout("DOSER_TEST_SIM_TIMEOUT", "What happened: Scale read timed out")]
+#[case::data_ready("DOSER_TEST_SIM_DRDY_TIMEOUT", "never signalled data-ready")]
+fn hx711_timeout_bubbles_to_cli(#[case] env: &str, #[case] needle: &str) {
     let dir = tempdir().unwrap();
     let toml = r#"
 [pins]
@@ -46,13 +48,109 @@ sensor_read_timeout_ms = 50
     fs::write(&cfg, toml).unwrap();
 
     let mut cmd = Command::cargo_bin("doser_cli").unwrap();
-    cmd.env("DOSER_TEST_SIM_TIMEOUT", "1");
+    cmd.env(env, "1");
     cmd.arg("--config")
         .arg(&cfg)
         .arg("dose")
         .arg("--grams")
         .arg("0.5");
-    cmd.assert().failure().stderr(predicate::str::contains(
-        "What happened: Scale read timed out",
-    ));
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(needle));
+}
+
+/// A persistent timeout is retried with sensor re-inits before it surfaces, and
+/// the attempts are reported in the JSON line.
+#[rstest]
+fn hx711_timeout_reports_reinit_attempts() {
+    let dir = tempdir().unwrap();
+    let toml = r#"
+[pins]
+hx711_dt = 5
+hx711_sck = 6
+motor_step = 13
+motor_dir = 19
+
+[filter]
+ma_window = 1
+median_window = 1
+sample_rate_hz = 10
+
+[timeouts]
+sample_ms = 5
+
+[safety]
+max_run_ms = 50
+
+[hardware]
+sensor_read_timeout_ms = 50
+
+[hardware.retry]
+max_attempts = 2
+settle_ms = 0
+"#;
+    let cfg = dir.path().join("cfg.toml");
+    fs::write(&cfg, toml).unwrap();
+
+    let out = Command::cargo_bin("doser_cli")
+        .unwrap()
+        .env("DOSER_TEST_SIM_TIMEOUT", "1")
+        .args(["--json", "--log-level", "error", "--config"])
+        .arg(&cfg)
+        .args(["dose", "--grams", "0.5"])
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8_lossy(&out);
+    let line = stdout
+        .lines()
+        .find(|l| l.contains("\"scale_reinits\""))
+        .unwrap_or_else(|| panic!("no JSON line; stdout was: {stdout}"));
+    let v: serde_json::Value = serde_json::from_str(line).unwrap();
+    assert!(v["scale_reinits"].as_u64().unwrap() >= 2, "{v}");
+    assert_eq!(v["scale_recovered"].as_u64(), Some(0), "{v}");
+}
+
+/// With pre-flight off, a data-ready stall is still named as such by the
+/// sampler watchdog.
+#[rstest]
+fn data_ready_stall_is_reported_as_data_ready() {
+    let dir = tempdir().unwrap();
+    let toml = r#"
+[pins]
+hx711_dt = 5
+hx711_sck = 6
+motor_step = 13
+motor_dir = 19
+
+[filter]
+ma_window = 1
+median_window = 1
+sample_rate_hz = 50
+
+[timeouts]
+sample_ms = 20
+
+[safety]
+max_run_ms = 2000
+
+[preflight]
+enabled = false
+"#;
+    let cfg = dir.path().join("cfg.toml");
+    fs::write(&cfg, toml).unwrap();
+
+    Command::cargo_bin("doser_cli")
+        .unwrap()
+        .env("DOSER_TEST_SIM_DRDY_TIMEOUT", "1")
+        .arg("--config")
+        .arg(&cfg)
+        .args(["dose", "--grams", "0.5"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The HX711 never signalled data-ready.",
+        ));
 }
@@ -9,13 +9,24 @@
 //! - `Config` and sub-structs are deserialized from TOML and validated.
 //! - Calibration CSV loader enforces headers and performs a robust refit
 //!   to reduce outlier influence before slope/intercept estimation.
+//! - Durations and masses accept unit suffixes (`"1.5s"`, `"80mg"`), see `units`.
+use std::collections::BTreeMap;
+
 use serde::Deserialize;
 use serde::de::Deserializer;
 
+mod strict;
+mod units;
+
+pub use strict::unknown_keys;
+
+/// This crate's version.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 /// Calibration CSV schema.
 ///
 /// Expected headers:
-/// raw,grams
+/// raw,grams (optionally followed by unit,replicate; see `load_calibration_csv`)
 ///
 /// Example:
 /// raw,grams
@@ -28,7 +39,24 @@ pub struct CalibrationRow {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(try_from = "RawPins")]
 pub struct Pins {
+    /// Board profile the pins were taken from (see [`PIN_PRESETS`])
+    pub preset: Option<String>,
+    pub hx711_dt: u8,
+    pub hx711_sck: u8,
+    pub motor_step: u8,
+    pub motor_dir: u8,
+    pub motor_en: Option<u8>,
+    pub estop_in: Option<u8>,
+    /// GPIO character device for the `gpiod` backend (e.g. "/dev/gpiochip0");
+    /// pin numbers are then line offsets on this chip
+    pub chip: Option<String>,
+}
+
+/// Wiring of a known board, selected with `[pins] preset = "<name>"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinPreset {
     pub hx711_dt: u8,
     pub hx711_sck: u8,
     pub motor_step: u8,
@@ -37,6 +65,80 @@ pub struct Pins {
     pub estop_in: Option<u8>,
 }
 
+/// Board profiles accepted by `[pins] preset` (BCM numbering).
+pub const PIN_PRESETS: &[(&str, PinPreset)] = &[
+    (
+        // The wiring of docs/guides/HARDWARE_SETUP.md
+        "doser-reference",
+        PinPreset {
+            hx711_dt: 5,
+            hx711_sck: 6,
+            motor_step: 23,
+            motor_dir: 24,
+            motor_en: None,
+            estop_in: None,
+        },
+    ),
+    (
+        "pi-hx711-hat-v2",
+        PinPreset {
+            hx711_dt: 5,
+            hx711_sck: 6,
+            motor_step: 23,
+            motor_dir: 24,
+            motor_en: Some(25),
+            estop_in: Some(12),
+        },
+    ),
+];
+
+/// `[pins]` as written: a preset and any keys overriding it.
+#[derive(Deserialize)]
+struct RawPins {
+    preset: Option<String>,
+    hx711_dt: Option<u8>,
+    hx711_sck: Option<u8>,
+    motor_step: Option<u8>,
+    motor_dir: Option<u8>,
+    motor_en: Option<u8>,
+    estop_in: Option<u8>,
+    chip: Option<String>,
+}
+
+impl TryFrom<RawPins> for Pins {
+    type Error = String;
+
+    fn try_from(raw: RawPins) -> Result<Self, Self::Error> {
+        let preset = match &raw.preset {
+            Some(name) => Some(
+                PIN_PRESETS
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, p)| *p)
+                    .ok_or_else(|| {
+                        let known: Vec<&str> = PIN_PRESETS.iter().map(|(n, _)| *n).collect();
+                        format!("unknown pins.preset {name:?} (known: {})", known.join(", "))
+                    })?,
+            ),
+            None => None,
+        };
+        let required = |v: Option<u8>, from_preset: fn(&PinPreset) -> u8, key: &str| {
+            v.or_else(|| preset.as_ref().map(from_preset))
+                .ok_or_else(|| format!("missing field `{key}`"))
+        };
+        Ok(Self {
+            hx711_dt: required(raw.hx711_dt, |p| p.hx711_dt, "hx711_dt")?,
+            hx711_sck: required(raw.hx711_sck, |p| p.hx711_sck, "hx711_sck")?,
+            motor_step: required(raw.motor_step, |p| p.motor_step, "motor_step")?,
+            motor_dir: required(raw.motor_dir, |p| p.motor_dir, "motor_dir")?,
+            motor_en: raw.motor_en.or(preset.and_then(|p| p.motor_en)),
+            estop_in: raw.estop_in.or(preset.and_then(|p| p.estop_in)),
+            chip: raw.chip,
+            preset: raw.preset,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct FilterCfg {
     pub ma_window: usize,
@@ -45,6 +147,70 @@ pub struct FilterCfg {
     /// Optional EMA smoothing factor; when set, EMA is used in the core smoothing stage.
     /// Range: (0.0, 1.0]. If absent or <= 0, EMA is disabled.
     pub ema_alpha: Option<f32>,
+    /// Average over `ma_window` when EMA is off
+    #[serde(default)]
+    pub smoothing: Smoothing,
+    /// Samples dropped from each end of the window by `smoothing = "trimmed-mean"`
+    #[serde(default)]
+    pub trim: usize,
+    /// `[filter.fine]`: filter for the fine and settle phases
+    #[serde(default)]
+    pub fine: Option<SubFilterCfg>,
+    /// `[filter.display]`: filter for the displayed weight only
+    #[serde(default)]
+    pub display: Option<SubFilterCfg>,
+}
+
+/// Smoothing keys of `[filter]`, for `[filter.fine]` and `[filter.display]`.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SubFilterCfg {
+    pub ma_window: usize,
+    pub median_window: usize,
+    pub ema_alpha: Option<f32>,
+    pub smoothing: Smoothing,
+    pub trim: usize,
+}
+
+impl Default for SubFilterCfg {
+    fn default() -> Self {
+        Self {
+            ma_window: 1,
+            median_window: 1,
+            ema_alpha: None,
+            smoothing: Smoothing::MovingAverage,
+            trim: 0,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Smoothing {
+    #[default]
+    MovingAverage,
+    Weighted,
+    TrimmedMean,
+}
+
+impl FilterCfg {
+    /// Recommended windows for an HX711 running at `sps` (10 or 80; anything
+    /// above 10 gets the 80 SPS profile). The filter delay is about 50 ms at
+    /// 80 SPS and 150 ms at 10 SPS, where longer windows would lag the flow.
+    pub fn for_sps(sps: u32) -> Self {
+        let (ma_window, median_window, sample_rate_hz) =
+            if sps > 10 { (5, 5, 80) } else { (2, 3, 10) };
+        Self {
+            ma_window,
+            median_window,
+            sample_rate_hz,
+            ema_alpha: None,
+            smoothing: Smoothing::MovingAverage,
+            trim: 0,
+            fine: None,
+            display: None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -52,37 +218,86 @@ pub struct FilterCfg {
 pub struct ControlCfg {
     pub coarse_speed: u32,
     pub fine_speed: u32,
+    #[serde(deserialize_with = "units::grams")]
     pub slow_at_g: f32,
+    #[serde(deserialize_with = "units::grams")]
     pub hysteresis_g: f32,
+    #[serde(deserialize_with = "units::ms")]
     pub stable_ms: u64,
     /// Additional control epsilon in grams used for stability/approach decisions
+    #[serde(deserialize_with = "units::grams")]
     pub epsilon_g: f32,
     /// Optional speed table. Accepts either:
     /// - array of tables: [{ threshold_g = 1.0, sps = 1100 }, ...]
     /// - array of tuples: [[1.0, 1100], [0.5, 450], ...]
     #[serde(default, deserialize_with = "de_speed_bands")]
     pub speed_bands: Vec<(f32, u32)>,
+    /// Extra error (grams) needed to return to a faster speed band (0 = off)
+    #[serde(deserialize_with = "units::grams")]
+    pub band_hysteresis_g: f32,
+    /// Minimum time (ms) in a speed band before returning to a faster one (0 = off)
+    #[serde(deserialize_with = "units::ms")]
+    pub band_min_dwell_ms: u64,
+    /// Only re-command the motor when the speed changes by at least this many sps
+    pub min_speed_delta_sps: u32,
+    /// Control loop rate (Hz) in the sampler modes, above the sample rate
+    /// (0 = once per sample)
+    pub loop_hz: u32,
+    /// Motor starts allowed per dose: the first approach plus re-approaches
+    /// (top-ups, and restarts after the weight fell back out of the settle
+    /// zone); the dose aborts before one more (0 = unbounded)
+    pub max_attempts: u32,
 }
 
 #[derive(Debug, Deserialize, Default)]
 pub struct Timeouts {
     /// Sampling timeout per read (ms). Also accepts alias "sensor_ms".
-    #[serde(alias = "sensor_ms")]
+    #[serde(alias = "sensor_ms", deserialize_with = "units::ms")]
     pub sample_ms: u64,
     /// Optional settle window override mistakenly placed under [timeouts] in some configs.
     /// Parsed and ignored to keep backward compatibility.
-    #[serde(default)]
+    #[serde(default, deserialize_with = "units::opt_ms")]
     pub settle_ms: Option<u64>,
+    /// Retries of a timed-out read before the dose aborts
+    #[serde(default)]
+    pub retry: ReadRetryCfg,
+}
+
+/// `[timeouts.retry]`: retry a timed-out scale read (with doubling backoff)
+/// before aborting the dose, so one missed data-ready does not end it.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct ReadRetryCfg {
+    /// Extra reads after a timeout (0 = abort on the first one)
+    pub max_retries: u32,
+    /// Wait before the first retry (ms); doubles on each further retry
+    #[serde(deserialize_with = "units::ms")]
+    pub backoff_ms: u64,
+}
+
+impl Default for ReadRetryCfg {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            backoff_ms: 5,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(default)]
 pub struct Safety {
+    #[serde(deserialize_with = "units::ms")]
     pub max_run_ms: u64,
+    #[serde(deserialize_with = "units::grams")]
     pub max_overshoot_g: f32,
     // Abort if weight change < epsilon for at least this many ms (0 disables)
+    #[serde(deserialize_with = "units::grams")]
     pub no_progress_epsilon_g: f32,
+    #[serde(deserialize_with = "units::ms")]
     pub no_progress_ms: u64,
+    /// Safe-state sequence run after the motor stop when a dose aborts
+    pub on_abort: OnAbort,
 }
 
 impl Default for Safety {
@@ -92,7 +307,138 @@ impl Default for Safety {
             max_overshoot_g: 0.0,
             no_progress_epsilon_g: 0.02,
             no_progress_ms: 1200,
+            on_abort: OnAbort::default(),
+        }
+    }
+}
+
+/// `[safety.on_abort]`: best-effort actions run, in this order, after an abort.
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct OnAbort {
+    /// Reverse the motor this many steps (0 = off); skipped after an E-stop
+    pub reverse_steps: u32,
+    /// Step rate for the reversal (steps per second)
+    pub reverse_sps: u32,
+    /// Names of `[actuators]` entries to release (e.g. close a valve)
+    pub release: Vec<String>,
+    /// Disable the motor driver (EN pin) at the end
+    pub disable_driver: bool,
+}
+
+/// An `[actuators.<name>]` entry: a GPIO-driven binary output.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ActuatorCfg {
+    /// BCM pin number, or `"<expander>:<pin>"` for an `[expanders]` output
+    pub pin: OutputPin,
+    /// Output is active when the pin is low (e.g. many relay boards)
+    #[serde(default)]
+    pub active_low: bool,
+}
+
+/// Where a binary output lives: a header GPIO or a pin on an I/O expander.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum OutputPin {
+    /// BCM pin number (TOML integer)
+    Gpio(u8),
+    /// Expander pin (TOML string such as `"mcp0:7"`)
+    Expander(ExpanderPin),
+}
+
+/// `"<expander>:<pin>"`: pin 0..=15 (A0..A7 = 0..7, B0..B7 = 8..15) on a named
+/// `[expanders]` entry.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub struct ExpanderPin {
+    pub expander: String,
+    pub pin: u8,
+}
+
+impl TryFrom<String> for ExpanderPin {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let (name, pin) = s
+            .split_once(':')
+            .ok_or_else(|| format!("expected \"<expander>:<pin>\", got {s:?}"))?;
+        let pin: u8 = pin
+            .parse()
+            .map_err(|_| format!("invalid expander pin number in {s:?}"))?;
+        if name.is_empty() || pin > 15 {
+            return Err(format!("expander pin {s:?} must be \"<name>:0..15\""));
         }
+        Ok(Self {
+            expander: name.to_string(),
+            pin,
+        })
+    }
+}
+
+/// `[plugin]`: replace the built-in scale and motor with a backend from a
+/// shared library (requires the CLI `plugin` feature).
+#[derive(Debug, Deserialize, Clone)]
+pub struct PluginCfg {
+    /// Path to the library (`.so`)
+    pub path: String,
+    /// Argument string passed verbatim to the plugin's scale factory
+    #[serde(default)]
+    pub scale_args: String,
+    /// Argument string passed verbatim to the plugin's motor factory
+    #[serde(default)]
+    pub motor_args: String,
+}
+
+/// An `[expanders.<name>]` entry: an MCP23017 16-bit I2C GPIO expander.
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct ExpanderCfg {
+    /// I2C bus number (`/dev/i2c-<bus>`)
+    #[serde(default = "default_i2c_bus")]
+    pub bus: u8,
+    /// 7-bit I2C address, 0x20..=0x27 depending on the A0..A2 straps
+    pub address: u8,
+}
+
+fn default_i2c_bus() -> u8 {
+    1
+}
+
+/// `[scale]`: load-cell topology.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct ScaleCfg {
+    /// Registered scale driver name (default: `hx711` on GPIO builds, else `sim`)
+    pub driver: Option<String>,
+    /// Second load cell/HX711 read alongside the primary one (`pins.hx711_*`)
+    pub composite: Option<CompositeScaleCfg>,
+    /// Unix socket the `socket` driver reads from (`doser sim-scale --serve`)
+    pub socket: Option<String>,
+}
+
+/// `[scale.composite]`: dual load cells combined into one reading.
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct CompositeScaleCfg {
+    /// HX711 data pin of the second cell
+    pub hx711_dt: u8,
+    /// HX711 clock pin of the second cell
+    pub hx711_sck: u8,
+    /// Max allowed |primary - secondary| in raw counts
+    pub tolerance_counts: u32,
+    /// Share of the primary cell in the weighted average (0.0..=1.0)
+    #[serde(default = "CompositeScaleCfg::default_weight")]
+    pub weight: f32,
+    /// Consecutive out-of-tolerance reads before the scale reports a fault
+    #[serde(default = "CompositeScaleCfg::default_fault_after")]
+    pub fault_after: u32,
+}
+
+impl CompositeScaleCfg {
+    fn default_weight() -> f32 {
+        0.5
+    }
+
+    fn default_fault_after() -> u32 {
+        3
     }
 }
 
@@ -108,14 +454,43 @@ pub struct Logging {
 #[derive(Debug, Deserialize)]
 #[serde(default)]
 pub struct Hardware {
-    /// Max time to wait for HX711 data-ready (DT low) before failing
+    /// Max time to wait for HX711 data-ready (DT low) before failing; distinct
+    /// from the per-read `timeouts.sample_ms`. Also accepts `data_ready_timeout_ms`.
+    #[serde(alias = "data_ready_timeout_ms", deserialize_with = "units::ms")]
     pub sensor_read_timeout_ms: u64,
+    /// Automatic sensor re-init on transient read failures
+    pub retry: RetryCfg,
+    /// Directory for the per-chip lock files taken when GPIO drivers open
+    pub lock_dir: String,
 }
 
 impl Default for Hardware {
     fn default() -> Self {
         Self {
             sensor_read_timeout_ms: 150,
+            retry: RetryCfg::default(),
+            lock_dir: "/run/doser".to_string(),
+        }
+    }
+}
+
+/// `[hardware.retry]`: bounded scale re-initialization before a read error is
+/// surfaced.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct RetryCfg {
+    /// Re-init attempts per failed read (0 = off)
+    pub max_attempts: u32,
+    /// Wait after each re-init before reading again (ADC settling)
+    #[serde(deserialize_with = "units::ms")]
+    pub settle_ms: u64,
+}
+
+impl Default for RetryCfg {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            settle_ms: 50,
         }
     }
 }
@@ -128,6 +503,7 @@ pub struct EstopCfg {
     /// Number of consecutive polls required to latch E-stop
     pub debounce_n: u8,
     /// Polling interval in milliseconds for GPIO E-stop checker
+    #[serde(deserialize_with = "units::ms")]
     pub poll_ms: u64,
 }
 
@@ -149,17 +525,36 @@ pub enum RunMode {
     Direct,
 }
 
+/// What the control loop does when an iteration takes longer than its period.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverrunPolicy {
+    /// Start the next iteration immediately (lowest latency)
+    #[default]
+    SkipSleep,
+    /// Keep the original schedule; run late iterations back-to-back
+    CatchUp,
+    /// Log a warning and wait a full period before the next iteration
+    LogAndDegrade,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(default)]
 pub struct RunnerCfg {
     /// Default orchestration mode: "sampler" (event/rate-paced) or "direct"
     pub mode: RunMode,
+    /// Loop overrun policy: "skip-sleep" (default), "catch-up", or "log-and-degrade"
+    pub overrun: OverrunPolicy,
+    /// Held locked while dosing; a second dose using the same file fails as busy
+    pub lock_file: Option<String>,
 }
 
 impl Default for RunnerCfg {
     fn default() -> Self {
         Self {
             mode: RunMode::Sampler,
+            overrun: OverrunPolicy::SkipSleep,
+            lock_file: None,
         }
     }
 }
@@ -172,9 +567,43 @@ pub struct PredictorCfg {
     /// Rolling window size (samples) for slope estimate
     pub window: usize,
     /// Extra latency margin to account for filtering/IO (ms)
+    #[serde(deserialize_with = "units::ms")]
     pub extra_latency_ms: u64,
     /// Minimum fraction of target progress before predictor activates (0.0..=1.0)
     pub min_progress_ratio: f32,
+    /// In-flight model: "linear" (slope continues) or "decel" (band ramp-down aware)
+    pub model: InflightModel,
+    /// When an early stop settles below target - epsilon: "top-up" (resume fine
+    /// dosing), "accept" (complete short) or "abort"
+    pub undershoot: UndershootPolicy,
+    /// Slope input: "filtered" (the controller's weight) or "fast" (median
+    /// prefilter only, without the smoothing lag)
+    pub slope_source: SlopeSource,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InflightModel {
+    #[default]
+    Linear,
+    Decel,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SlopeSource {
+    #[default]
+    Filtered,
+    Fast,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum UndershootPolicy {
+    #[default]
+    TopUp,
+    Accept,
+    Abort,
 }
 
 impl Default for PredictorCfg {
@@ -184,6 +613,332 @@ impl Default for PredictorCfg {
             window: 6,
             extra_latency_ms: 20,
             min_progress_ratio: 0.10,
+            model: InflightModel::Linear,
+            undershoot: UndershootPolicy::TopUp,
+            slope_source: SlopeSource::Filtered,
+        }
+    }
+}
+
+/// `[preflight]`: health gate run before the motor first starts.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct PreflightCfg {
+    /// Run the checks (scale readable/stable, driver enable, E-stop released)
+    pub enabled: bool,
+    /// Scale samples taken to judge readability and stability
+    pub samples: usize,
+    /// Max spread (max - min) across the samples, in grams
+    #[serde(deserialize_with = "units::grams")]
+    pub max_spread_g: f32,
+    /// Optional plausible range for the starting weight, in grams
+    #[serde(deserialize_with = "units::opt_grams")]
+    pub min_g: Option<f32>,
+    #[serde(deserialize_with = "units::opt_grams")]
+    pub max_g: Option<f32>,
+    /// Units self-test: the calibrated no-load reading must be within this
+    /// many grams of zero, or of `container_g`
+    #[serde(deserialize_with = "units::opt_grams")]
+    pub zero_band_g: Option<f32>,
+    /// Weight of the empty container on the scale at the start, in grams
+    #[serde(deserialize_with = "units::opt_grams")]
+    pub container_g: Option<f32>,
+}
+
+impl Default for PreflightCfg {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            samples: 5,
+            max_spread_g: 1.0,
+            min_g: None,
+            max_g: None,
+            zero_band_g: None,
+            container_g: None,
+        }
+    }
+}
+
+/// `[hopper]`: scale under the supply hopper, checked before each dose.
+#[derive(Debug, Deserialize, Clone)]
+pub struct HopperCfg {
+    /// Scale driver (default: the `[scale]` default for this build)
+    #[serde(default)]
+    pub driver: Option<String>,
+    /// HX711 data pin of the hopper cell
+    pub hx711_dt: u8,
+    /// HX711 clock pin of the hopper cell
+    pub hx711_sck: u8,
+    /// Grams per raw count of the hopper scale
+    pub gain_g_per_count: f32,
+    /// Raw reading of the empty hopper
+    #[serde(default)]
+    pub zero_counts: i32,
+    /// Material that must remain after the dose, in grams
+    #[serde(default, deserialize_with = "units::grams")]
+    pub reserve_g: f32,
+    /// Samples averaged for the estimate
+    #[serde(default = "HopperCfg::default_samples")]
+    pub samples: usize,
+}
+
+impl HopperCfg {
+    fn default_samples() -> usize {
+        5
+    }
+}
+
+/// `[resume]`: record a dose interrupted by a sensor timeout so `doser resume`
+/// can finish it instead of starting over.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ResumeCfg {
+    /// Where the interrupted dose is recorded (removed once it completes)
+    pub state_file: String,
+    /// Samples averaged when re-taring on resume
+    #[serde(default = "ResumeCfg::default_samples")]
+    pub samples: usize,
+    /// Offer to re-zero a weight change while paused (e.g. a bumped container)
+    #[serde(default)]
+    pub track_zero: bool,
+    /// Largest change (grams) that may be re-zeroed; larger ones count as dosed
+    #[serde(
+        default = "ResumeCfg::default_max_rezero_g",
+        deserialize_with = "units::grams"
+    )]
+    pub max_rezero_g: f32,
+}
+
+impl ResumeCfg {
+    fn default_samples() -> usize {
+        5
+    }
+
+    fn default_max_rezero_g() -> f32 {
+        2.0
+    }
+}
+
+/// `[bundle]`: write a diagnostic bundle (config, last control steps, log
+/// tail, versions) for every dose that fails, for field support.
+#[derive(Debug, Deserialize, Clone)]
+pub struct BundleCfg {
+    /// Directory the timestamped bundles are written under
+    pub dir: String,
+    /// Lines from the end of `[logging] file` to include
+    #[serde(default = "BundleCfg::default_log_lines")]
+    pub log_lines: usize,
+}
+
+impl BundleCfg {
+    pub fn default_log_lines() -> usize {
+        200
+    }
+}
+
+/// `[learned]`: constants measured on each completed dose (grams per motor
+/// step, coast mass), kept per material across runs; optionally also the
+/// completion tolerance, nudged against the recent dose bias.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LearnedCfg {
+    /// JSON file holding the learned values of every material
+    pub file: String,
+    /// Key this config's doses are learned under
+    #[serde(default = "LearnedCfg::default_material")]
+    pub material: String,
+    /// Bounds of the learned `control.epsilon_g`; set both to let the dose
+    /// bias adjust it
+    #[serde(default, deserialize_with = "units::opt_grams")]
+    pub epsilon_min_g: Option<f32>,
+    #[serde(default, deserialize_with = "units::opt_grams")]
+    pub epsilon_max_g: Option<f32>,
+    /// Largest change of epsilon per adjustment (grams)
+    #[serde(
+        default = "LearnedCfg::default_epsilon_step_g",
+        deserialize_with = "units::grams"
+    )]
+    pub epsilon_step_g: f32,
+    /// Completed doses, all off target the same way, before an adjustment
+    #[serde(default = "LearnedCfg::default_epsilon_window")]
+    pub epsilon_window: usize,
+}
+
+impl LearnedCfg {
+    fn default_material() -> String {
+        "default".to_string()
+    }
+
+    fn default_epsilon_step_g() -> f32 {
+        0.02
+    }
+
+    fn default_epsilon_window() -> usize {
+        5
+    }
+
+    /// `(min, max)` of the learned epsilon, when its adjustment is on.
+    pub fn epsilon_bounds(&self) -> Option<(f32, f32)> {
+        Some((self.epsilon_min_g?, self.epsilon_max_g?))
+    }
+}
+
+/// `[retention]`: caps on the disk space kept run data (diagnostic bundles,
+/// rotated log files) may take; the oldest are pruned first.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RetentionCfg {
+    /// Largest total size of the kept files (MiB)
+    pub max_mb: Option<u64>,
+    /// Prune files older than this (days)
+    pub max_age_days: Option<u64>,
+}
+
+/// `[shutdown]`: a marker file recording whether the last dose shut down
+/// cleanly, so one cut short by a power loss or a kill is reported.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ShutdownCfg {
+    /// Marker file, rewritten when a dose starts and when it ends
+    pub marker: String,
+}
+
+/// `[requests]`: doses run under a client-supplied request id, so a retried
+/// request returns its first result instead of dosing again.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RequestsCfg {
+    /// JSON file holding the state and result of each request id
+    pub file: String,
+    /// Finished requests kept; the oldest are dropped beyond this
+    #[serde(default = "RequestsCfg::default_keep")]
+    pub keep: usize,
+}
+
+impl RequestsCfg {
+    fn default_keep() -> usize {
+        1000
+    }
+}
+
+/// `[broadcast]`: a low-rate UDP datagram of station, weight and state while a
+/// dose runs, usually to a multicast group many stations share.
+#[derive(Debug, Deserialize, Clone)]
+pub struct BroadcastCfg {
+    /// Destination `ip:port`, e.g. "239.255.42.1:5005"
+    pub group: String,
+    /// Station id carried in every datagram
+    pub station: String,
+    /// Time between datagrams (ms)
+    #[serde(
+        default = "BroadcastCfg::default_interval_ms",
+        deserialize_with = "units::ms"
+    )]
+    pub interval_ms: u64,
+    /// Multicast TTL (IPv4); 1 keeps datagrams on the local network
+    #[serde(default = "BroadcastCfg::default_ttl")]
+    pub ttl: u32,
+}
+
+impl BroadcastCfg {
+    fn default_interval_ms() -> u64 {
+        1000
+    }
+
+    fn default_ttl() -> u32 {
+        1
+    }
+}
+
+/// `[telemetry]`: export tracing spans (each dose's carries its `run_id`) to
+/// an OTLP collector. Only read by builds with the `otel` feature.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TelemetryCfg {
+    /// OTLP/HTTP traces endpoint, e.g. "http://tempo:4318/v1/traces"
+    pub endpoint: String,
+    /// `service.name` resource attribute
+    #[serde(default = "TelemetryCfg::default_service_name")]
+    pub service_name: String,
+    /// Time allowed for one export request, including the last one at exit (ms)
+    #[serde(
+        default = "TelemetryCfg::default_timeout_ms",
+        deserialize_with = "units::ms"
+    )]
+    pub timeout_ms: u64,
+}
+
+impl TelemetryCfg {
+    fn default_service_name() -> String {
+        "doser".to_string()
+    }
+
+    fn default_timeout_ms() -> u64 {
+        3000
+    }
+}
+
+/// `[autotare]`: before dosing, wait for a container to be placed and its
+/// weight to settle, then tare there.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct AutoTareCfg {
+    /// Weight above the calibrated zero that counts as a container (grams)
+    #[serde(deserialize_with = "units::grams")]
+    pub min_container_g: f32,
+    /// Largest spread of the readings on a plateau (grams)
+    #[serde(deserialize_with = "units::grams")]
+    pub plateau_g: f32,
+    /// How long the readings must stay on the plateau (ms)
+    #[serde(deserialize_with = "units::ms")]
+    pub plateau_ms: u64,
+    /// Give up when no container settles within this long (ms)
+    #[serde(deserialize_with = "units::ms")]
+    pub max_wait_ms: u64,
+}
+
+impl Default for AutoTareCfg {
+    fn default() -> Self {
+        Self {
+            min_container_g: 5.0,
+            plateau_g: 0.05,
+            plateau_ms: 500,
+            max_wait_ms: 60_000,
+        }
+    }
+}
+
+/// `[ticket]`: formatted record printed after each completed dose.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TicketCfg {
+    /// Ticket text with `{field}` placeholders (see the config reference)
+    pub template: String,
+    /// Printer device or file the ticket is appended to (default: stdout)
+    #[serde(default)]
+    pub device: Option<String>,
+    /// Operator name for `{operator}` (overridden by `dose --operator`)
+    #[serde(default)]
+    pub operator: Option<String>,
+}
+
+/// `[warmup]`: scale warm-up before the pre-flight checks (off by default).
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct WarmupCfg {
+    /// Samples to read and discard after power-on
+    pub discard_samples: u32,
+    /// Also wait until the rolling stddev (grams) is at or below this
+    #[serde(deserialize_with = "units::opt_grams")]
+    pub stable_stddev_g: Option<f32>,
+    /// Rolling window (samples) for the stddev
+    pub window: usize,
+    /// Upper bound on the warm-up (ms)
+    #[serde(deserialize_with = "units::ms")]
+    pub max_ms: u64,
+}
+
+impl Default for WarmupCfg {
+    fn default() -> Self {
+        Self {
+            discard_samples: 0,
+            stable_stddev_g: None,
+            window: 10,
+            max_ms: 10_000,
         }
     }
 }
@@ -213,9 +968,84 @@ pub struct Config {
     /// Optional persisted calibration; preferred at runtime over CSV when present.
     #[serde(default)]
     pub calibration: Option<PersistedCalibration>,
+    /// Named binary outputs (valves, gates) usable by `[safety.on_abort]`
+    #[serde(default)]
+    pub actuators: BTreeMap<String, ActuatorCfg>,
+    /// I2C GPIO expanders, addressable from output pins as `"<name>:<pin>"`
+    #[serde(default)]
+    pub expanders: BTreeMap<String, ExpanderCfg>,
+    /// Load-cell topology (single or composite)
+    #[serde(default)]
+    pub scale: ScaleCfg,
+    /// Pre-flight health gate
+    #[serde(default)]
+    pub preflight: PreflightCfg,
+    /// Scale warm-up before pre-flight
+    #[serde(default)]
+    pub warmup: WarmupCfg,
+    /// Motor driver wiring options
+    #[serde(default)]
+    pub motor: MotorCfg,
+    /// Out-of-tree scale/motor backend loaded from a shared library
+    #[serde(default)]
+    pub plugin: Option<PluginCfg>,
+    /// Hopper scale for the pre-dose feasibility check
+    #[serde(default)]
+    pub hopper: Option<HopperCfg>,
+    /// Resuming a dose interrupted by a sensor timeout
+    #[serde(default)]
+    pub resume: Option<ResumeCfg>,
+    /// Ticket printed after each completed dose
+    #[serde(default)]
+    pub ticket: Option<TicketCfg>,
+    /// Reference-weight calibration checks
+    #[serde(default)]
+    pub verify: VerifyCfg,
+    /// Technician-only commands
+    #[serde(default)]
+    pub access: Option<AccessCfg>,
+    /// Diagnostic bundles for failed doses
+    #[serde(default)]
+    pub bundle: Option<BundleCfg>,
+    /// Constants learned across doses
+    #[serde(default)]
+    pub learned: Option<LearnedCfg>,
+    /// Tare on a placed container before dosing
+    #[serde(default)]
+    pub autotare: Option<AutoTareCfg>,
+    /// Disk usage caps for bundles and rotated logs
+    #[serde(default)]
+    pub retention: Option<RetentionCfg>,
+    /// Clean-shutdown marker for dose runs
+    #[serde(default)]
+    pub shutdown: Option<ShutdownCfg>,
+    /// Results of client-identified dose requests (`dose --request-id`)
+    #[serde(default)]
+    pub requests: Option<RequestsCfg>,
+    /// Periodic weight datagrams for plant dashboards
+    #[serde(default)]
+    pub broadcast: Option<BroadcastCfg>,
+    /// OTLP span export (`otel` builds)
+    #[serde(default)]
+    pub telemetry: Option<TelemetryCfg>,
+    /// Refuse keys no field reads (see [`unknown_keys`]) instead of ignoring them
+    #[serde(default)]
+    pub strict: bool,
 }
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+/// `[motor]`: driver selection and wiring variations.
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct MotorCfg {
+    /// Registered motor driver name (default: `gpio-thread` on GPIO builds, else `sim`)
+    pub driver: Option<String>,
+    /// Dispense with DIR high instead of low (motor mounted or wired reversed)
+    pub invert_direction: bool,
+    /// EN is active-high instead of active-low
+    pub invert_enable: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
 pub struct PersistedCalibration {
     /// grams per count
     pub gain_g_per_count: f32,
@@ -224,6 +1054,50 @@ pub struct PersistedCalibration {
     /// additive offset in grams (rarely needed; default 0.0)
     #[serde(default)]
     pub offset_g: f32,
+    /// Last `doser verify-cal` result (`[calibration.verified]`)
+    #[serde(default)]
+    pub verified: Option<CalibrationCheck>,
+}
+
+/// Outcome of checking the calibration against a reference weight.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct CalibrationCheck {
+    /// When the check ran (UTC, `YYYY-MM-DD HH:MM:SS UTC`)
+    pub at: String,
+    pub reference_g: f32,
+    pub measured_g: f32,
+    /// `measured_g - reference_g`
+    pub error_g: f32,
+    pub tolerance_g: f32,
+    pub passed: bool,
+}
+
+/// `[verify]`: settings for `doser verify-cal`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct VerifyCfg {
+    /// Largest accepted |measured - reference| in grams
+    #[serde(deserialize_with = "units::grams")]
+    pub tolerance_g: f32,
+    /// Reads averaged for the zero and for the reference weight
+    pub samples: usize,
+}
+
+impl Default for VerifyCfg {
+    fn default() -> Self {
+        Self {
+            tolerance_g: 0.1,
+            samples: 10,
+        }
+    }
+}
+
+/// `[access]`: restrict commands that change the calibration or the config to
+/// technicians. Without this section every command is open to everyone.
+#[derive(Debug, Deserialize, Clone)]
+pub struct AccessCfg {
+    /// PIN or token technicians pass with `--pin` (or `DOSER_PIN`)
+    pub technician_pin: String,
 }
 
 impl From<PersistedCalibration> for Calibration {
@@ -232,6 +1106,7 @@ impl From<PersistedCalibration> for Calibration {
             offset: p.zero_counts,
             scale_factor: p.gain_g_per_count,
             offset_g: p.offset_g,
+            uncertainty: None,
         }
     }
 }
@@ -250,6 +1125,11 @@ impl Default for ControlCfg {
             stable_ms: 250,
             epsilon_g: 0.0,
             speed_bands: Vec::new(),
+            band_hysteresis_g: 0.0,
+            band_min_dwell_ms: 0,
+            min_speed_delta_sps: 0,
+            loop_hz: 0,
+            max_attempts: 0,
         }
     }
 }
@@ -288,6 +1168,54 @@ pub struct Calibration {
     /// The CSV path folds the OLS intercept into `offset` (tare), so it sets this to 0.0;
     /// persisted TOML calibration may carry a non-zero value.
     pub offset_g: f32,
+    /// Standard errors of the fit (CSV calibrations of three or more points)
+    pub uncertainty: Option<FitUncertainty>,
+}
+
+/// Standard errors of a calibration fit, from the residuals of the points it
+/// rests on (those within 3 robust σ of the fitted line).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FitUncertainty {
+    /// Standard error of the gain (grams per count)
+    pub gain_se: f64,
+    /// Standard error of the intercept (grams)
+    pub offset_se_g: f64,
+    /// Standard deviation of the residuals (grams)
+    pub residual_sd_g: f64,
+    /// Points the errors are estimated from
+    pub points: usize,
+}
+
+/// Line fit used to turn calibration rows into a gain and tare.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CalibrationFit {
+    /// Least squares, refit once without the points beyond 2σ. Fine for a
+    /// few gross outliers; pulled off the line by many.
+    #[default]
+    Ols,
+    /// Median of the pairwise slopes: tolerates up to ~29 % outliers (at most
+    /// 2000 points).
+    TheilSen,
+    /// Candidate lines through pairs of points; the one with the smallest
+    /// median residual wins and its inliers are refit by least squares.
+    /// Tolerates up to half the points being outliers.
+    Ransac,
+}
+
+impl std::str::FromStr for CalibrationFit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ols" => Ok(Self::Ols),
+            "theil-sen" => Ok(Self::TheilSen),
+            "ransac" => Ok(Self::Ransac),
+            _ => Err(format!(
+                "unknown calibration fit {s:?} (ols, theil-sen, ransac)"
+            )),
+        }
+    }
 }
 
 impl Calibration {
@@ -295,6 +1223,21 @@ impl Calibration {
     /// Fits grams = a*raw + b, then converts to core form grams = a*(raw - offset) + 0,
     /// where offset = round(-b/a) is the tare baseline in raw counts.
     pub fn from_rows(rows: Vec<CalibrationRow>) -> eyre::Result<Self> {
+        Self::from_rows_with(rows, CalibrationFit::Ols)
+    }
+
+    /// Smallest completion tolerance this calibration supports for a dose of
+    /// `target_g`: twice (~95 %) the standard error of the dosed weight. The
+    /// dose is weighed from a tare, so only the gain's error carries into it.
+    /// `None` when the uncertainty is unknown.
+    pub fn min_tolerance_g(&self, target_g: f32) -> Option<f32> {
+        let u = self.uncertainty?;
+        let rel = u.gain_se / f64::from(self.scale_factor).abs();
+        Some((2.0 * f64::from(target_g).abs() * rel) as f32)
+    }
+
+    /// [`Self::from_rows`] with the line fitted by `fit`.
+    pub fn from_rows_with(rows: Vec<CalibrationRow>, fit: CalibrationFit) -> eyre::Result<Self> {
         if rows.len() < 2 {
             eyre::bail!("calibration requires at least two rows, got {}", rows.len());
         }
@@ -320,60 +1263,20 @@ impl Calibration {
             }
         }
 
-        // Closure: OLS fit in f64 for numerical stability
-        let fit = |pts: &[(i64, f32)]| -> eyre::Result<(f64, f64)> {
-            let n = pts.len() as f64;
-            let sum_x: f64 = pts.iter().map(|r| r.0 as f64).sum();
-            let sum_y: f64 = pts.iter().map(|r| r.1 as f64).sum();
-            let mean_x = sum_x / n;
-            let mean_y = sum_y / n;
-            let mut sxx = 0.0f64;
-            let mut sxy = 0.0f64;
-            for (rx, gy) in pts {
-                let x = *rx as f64 - mean_x;
-                let y = *gy as f64 - mean_y;
-                sxx += x * x;
-                sxy += x * y;
-            }
-            if !sxx.is_finite() || sxx == 0.0 {
-                eyre::bail!("calibration cannot determine slope (degenerate X variance)");
-            }
-            let a = sxy / sxx;
-            if !a.is_finite() {
-                eyre::bail!("calibration produced non-finite slope");
-            }
-            if a == 0.0 {
-                eyre::bail!("calibration produced zero slope (invalid scale factor)");
-            }
-            let b = mean_y - a * mean_x;
-            Ok((a, b))
-        };
-
-        // Initial fit
         let pts: Vec<(i64, f32)> = rows.iter().map(|r| (r.raw, r.grams)).collect();
-        let (a0, b0) = fit(&pts)?;
-        // Compute robust sigma estimate (RMS of residuals) without allocating residuals
-        let mut sumsq: f64 = 0.0;
-        for (x, y) in &pts {
-            let r = (*y as f64) - (a0 * (*x as f64) + b0);
-            sumsq += r * r;
-        }
-        let n_pts = pts.len();
-        let rms = if n_pts == 0 {
-            0.0
-        } else {
-            (sumsq / (n_pts as f64)).sqrt()
+        let (a, b) = match fit {
+            CalibrationFit::Ols => ols_with_refit(&pts)?,
+            CalibrationFit::TheilSen => theil_sen(&pts)?,
+            CalibrationFit::Ransac => ransac(&pts)?,
         };
-
-        // Reject outliers with |residual| > 2σ and refit if at least 2 remain.
-        let (a, b) = robust_refit(&pts, a0, b0, rms, 2.0).unwrap_or((a0, b0));
-
+        if !a.is_finite() || !b.is_finite() {
+            eyre::bail!("calibration produced non-finite slope");
+        }
+        if a == 0.0 {
+            eyre::bail!("calibration produced zero slope (invalid scale factor)");
+        }
         // Convert to core representation: grams = a * (raw - offset) + 0
-        let zero_counts = if a == 0.0 {
-            eyre::bail!("calibration slope is zero, cannot compute tare baseline");
-        } else {
-            -b / a // where grams==0
-        };
+        let zero_counts = -b / a; // where grams==0
         if !zero_counts.is_finite() {
             eyre::bail!("calibration produced invalid tare baseline");
         }
@@ -382,12 +1285,195 @@ impl Calibration {
         Ok(Calibration {
             offset: offset_i32,
             scale_factor: a as f32,
-            // The OLS intercept is folded into `offset` (tare counts); no extra grams offset.
+            // The intercept is folded into `offset` (tare counts); no extra grams offset.
             offset_g: 0.0,
+            uncertainty: fit_uncertainty(&pts, a, b),
         })
     }
 }
 
+/// OLS fit of `pts` in f64 for numerical stability.
+fn ols(pts: &[(i64, f32)]) -> eyre::Result<(f64, f64)> {
+    let n = pts.len() as f64;
+    let sum_x: f64 = pts.iter().map(|r| r.0 as f64).sum();
+    let sum_y: f64 = pts.iter().map(|r| r.1 as f64).sum();
+    let mean_x = sum_x / n;
+    let mean_y = sum_y / n;
+    let mut sxx = 0.0f64;
+    let mut sxy = 0.0f64;
+    for (rx, gy) in pts {
+        let x = *rx as f64 - mean_x;
+        let y = *gy as f64 - mean_y;
+        sxx += x * x;
+        sxy += x * y;
+    }
+    if !sxx.is_finite() || sxx == 0.0 {
+        eyre::bail!("calibration cannot determine slope (degenerate X variance)");
+    }
+    let a = sxy / sxx;
+    if !a.is_finite() {
+        eyre::bail!("calibration produced non-finite slope");
+    }
+    if a == 0.0 {
+        eyre::bail!("calibration produced zero slope (invalid scale factor)");
+    }
+    let b = mean_y - a * mean_x;
+    Ok((a, b))
+}
+
+/// [`CalibrationFit::Ols`]: OLS, then one refit without |residual| > 2σ.
+fn ols_with_refit(pts: &[(i64, f32)]) -> eyre::Result<(f64, f64)> {
+    let (a0, b0) = ols(pts)?;
+    // Compute robust sigma estimate (RMS of residuals) without allocating residuals
+    let mut sumsq: f64 = 0.0;
+    for (x, y) in pts {
+        let r = (*y as f64) - (a0 * (*x as f64) + b0);
+        sumsq += r * r;
+    }
+    let n_pts = pts.len();
+    let rms = if n_pts == 0 {
+        0.0
+    } else {
+        (sumsq / (n_pts as f64)).sqrt()
+    };
+
+    // Reject outliers with |residual| > 2σ and refit if at least 2 remain.
+    Ok(robust_refit(pts, a0, b0, rms, 2.0).unwrap_or((a0, b0)))
+}
+
+/// Most points [`CalibrationFit::TheilSen`] takes (it keeps every pairwise slope).
+const MAX_THEIL_SEN_POINTS: usize = 2000;
+
+fn median(v: &mut [f64]) -> f64 {
+    v.sort_by(f64::total_cmp);
+    let n = v.len();
+    if n % 2 == 1 {
+        v[n / 2]
+    } else {
+        (v[n / 2 - 1] + v[n / 2]) / 2.0
+    }
+}
+
+/// [`CalibrationFit::TheilSen`]: the median pairwise slope, and the median
+/// intercept under it.
+fn theil_sen(pts: &[(i64, f32)]) -> eyre::Result<(f64, f64)> {
+    if pts.len() > MAX_THEIL_SEN_POINTS {
+        eyre::bail!(
+            "theil-sen fit takes at most {MAX_THEIL_SEN_POINTS} points, got {}",
+            pts.len()
+        );
+    }
+    let mut slopes = Vec::with_capacity(pts.len() * (pts.len() - 1) / 2);
+    for (i, (x1, y1)) in pts.iter().enumerate() {
+        for (x2, y2) in &pts[i + 1..] {
+            // Raw values are strictly monotonic, so x2 != x1
+            slopes.push((*y2 as f64 - *y1 as f64) / (*x2 - *x1) as f64);
+        }
+    }
+    let a = median(&mut slopes);
+    let mut intercepts: Vec<f64> = pts.iter().map(|(x, y)| *y as f64 - a * *x as f64).collect();
+    Ok((a, median(&mut intercepts)))
+}
+
+/// Pairs [`CalibrationFit::Ransac`] draws when there are too many to try all.
+const RANSAC_SAMPLES: usize = 2000;
+
+/// [`CalibrationFit::Ransac`]. Deterministic: every pair of up to 63 points
+/// is tried, otherwise a fixed pseudo-random sequence of pairs.
+fn ransac(pts: &[(i64, f32)]) -> eyre::Result<(f64, f64)> {
+    let n = pts.len();
+    let line = |i: usize, j: usize| {
+        let (x1, y1) = (pts[i].0 as f64, pts[i].1 as f64);
+        let (x2, y2) = (pts[j].0 as f64, pts[j].1 as f64);
+        let a = (y2 - y1) / (x2 - x1);
+        (a, y1 - a * x1)
+    };
+    let mut residuals = vec![0.0f64; n];
+    let mut median_residual = |(a, b): (f64, f64)| {
+        for (r, (x, y)) in residuals.iter_mut().zip(pts) {
+            *r = (*y as f64 - (a * *x as f64 + b)).abs();
+        }
+        median(&mut residuals)
+    };
+    let mut best = (f64::INFINITY, (0.0, 0.0));
+    let mut consider = |i: usize, j: usize| {
+        let candidate = line(i, j);
+        if candidate.0 != 0.0 {
+            let m = median_residual(candidate);
+            if m < best.0 {
+                best = (m, candidate);
+            }
+        }
+    };
+    if n * (n - 1) / 2 <= RANSAC_SAMPLES {
+        for i in 0..n {
+            for j in i + 1..n {
+                consider(i, j);
+            }
+        }
+    } else {
+        // xorshift64 with a fixed seed, so a CSV always gives the same fit
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % n as u64) as usize
+        };
+        for _ in 0..RANSAC_SAMPLES {
+            let (i, j) = (next(), next());
+            if i != j {
+                consider(i, j);
+            }
+        }
+    }
+    let (m, (a, b)) = best;
+    if !m.is_finite() {
+        eyre::bail!("calibration produced zero slope (invalid scale factor)");
+    }
+    // Inliers: within 2.5 robust sigma (1.4826 * median |residual|) of the line
+    let y_scale = pts.iter().map(|p| (p.1 as f64).abs()).fold(1.0, f64::max);
+    let thr = (2.5 * 1.4826 * m).max(1e-9 * y_scale);
+    let inliers: Vec<(i64, f32)> = pts
+        .iter()
+        .copied()
+        .filter(|(x, y)| (*y as f64 - (a * *x as f64 + b)).abs() <= thr)
+        .collect();
+    if inliers.len() < 2 {
+        return Ok((a, b));
+    }
+    ols(&inliers)
+}
+
+/// Standard errors of the line `a*raw + b` from the points within 3 robust σ
+/// (1.4826 × median |residual|) of it, so the outliers a robust fit ignored
+/// do not inflate them. `None` with fewer than three such points.
+fn fit_uncertainty(pts: &[(i64, f32)], a: f64, b: f64) -> Option<FitUncertainty> {
+    let residual = |(x, y): &(i64, f32)| *y as f64 - (a * *x as f64 + b);
+    let mut abs: Vec<f64> = pts.iter().map(|p| residual(p).abs()).collect();
+    let sigma = 1.4826 * median(&mut abs);
+    let y_scale = pts.iter().map(|p| (p.1 as f64).abs()).fold(1.0, f64::max);
+    let thr = (3.0 * sigma).max(1e-9 * y_scale);
+    let kept: Vec<&(i64, f32)> = pts.iter().filter(|p| residual(p).abs() <= thr).collect();
+    let n = kept.len();
+    if n < 3 {
+        return None;
+    }
+    let mean_x = kept.iter().map(|p| p.0 as f64).sum::<f64>() / n as f64;
+    let sxx: f64 = kept.iter().map(|p| (p.0 as f64 - mean_x).powi(2)).sum();
+    let ssr: f64 = kept.iter().map(|p| residual(p).powi(2)).sum();
+    if !(sxx.is_finite() && sxx > 0.0 && ssr.is_finite()) {
+        return None;
+    }
+    let s = (ssr / (n - 2) as f64).sqrt();
+    Some(FitUncertainty {
+        gain_se: s / sxx.sqrt(),
+        offset_se_g: s * (1.0 / n as f64 + mean_x * mean_x / sxx).sqrt(),
+        residual_sd_g: s,
+        points: n,
+    })
+}
+
 /// Perform a single-step robust refit by rejecting outliers defined by |residual| > k * rms
 /// around the initial line y = a0*x + b0. Uses an online (Welford/Chan) covariance update
 /// over inliers only to compute slope and intercept. Returns None when refit is not applicable
@@ -462,50 +1548,227 @@ impl TryFrom<&[CalibrationRow]> for Calibration {
     }
 }
 
+/// A calibration CSV record: the reference weight in `unit` (default grams)
+/// and an optional replicate label.
+#[derive(Deserialize)]
+struct CsvRow {
+    raw: i64,
+    grams: f64,
+    #[serde(default)]
+    unit: Option<String>,
+    #[serde(default)]
+    replicate: Option<String>,
+}
+
+/// One weight point of the CSV: its replicates, averaged before the fit.
+struct CsvPoint {
+    grams: f64,
+    raw_sum: f64,
+    /// CSV row of each replicate, by label
+    replicates: Vec<(String, usize)>,
+}
+
+impl CsvPoint {
+    fn raw(&self) -> i64 {
+        (self.raw_sum / self.replicates.len() as f64).round() as i64
+    }
+
+    fn rows(&self) -> String {
+        let rows: Vec<String> = self.replicates.iter().map(|(_, r)| r.to_string()).collect();
+        rows.join("/")
+    }
+}
+
+/// Load a calibration CSV: headers `raw,grams`, optionally followed by `unit`
+/// (`mg`, `cg`, `g`, `kg`; the unit of `grams`, default `g`) and `replicate`.
+/// With a `replicate` column, rows of the same weight are replicates of one
+/// point and their raw readings are averaged before the fit.
 pub fn load_calibration_csv(path: &std::path::Path) -> eyre::Result<Calibration> {
+    load_calibration_csv_with(path, CalibrationFit::Ols)
+}
+
+/// [`load_calibration_csv`] with the line fitted by `fit`.
+pub fn load_calibration_csv_with(
+    path: &std::path::Path,
+    fit: CalibrationFit,
+) -> eyre::Result<Calibration> {
     let mut rdr = csv::ReaderBuilder::new()
         .has_headers(true)
         .from_path(path)
         .map_err(|e| eyre::eyre!("open calibration CSV {:?}: {}", path, e))?;
 
-    // Enforce exact headers
+    // Enforce the headers: raw,grams first, then optional columns once each
     let headers = rdr
         .headers()
         .map_err(|e| eyre::eyre!("read CSV headers {:?}: {}", path, e))?
         .clone();
-    let expected = ["raw", "grams"];
     let actual: Vec<String> = headers.iter().map(|s| s.to_string()).collect();
-    if actual != expected {
+    let optional = ["unit", "replicate"];
+    let extra = actual.get(2..).unwrap_or_default();
+    let extra_ok = extra.iter().all(|h| optional.contains(&h.as_str()))
+        && !(extra.len() == 2 && extra[0] == extra[1]);
+    if actual.len() < 2 || actual[..2] != ["raw", "grams"] || !extra_ok {
         eyre::bail!(
-            "calibration CSV must have headers 'raw,grams', got: {}",
+            "calibration CSV must have headers 'raw,grams' (optionally followed by 'unit' and 'replicate'), got: {}",
             actual.join(",")
         );
     }
+    let replicated = extra.iter().any(|h| h == "replicate");
 
     // Bound the number of rows so a malformed/huge CSV cannot exhaust memory.
     const MAX_CALIBRATION_ROWS: usize = 100_000;
-    let mut rows = Vec::new();
-    for (idx, rec) in rdr.deserialize::<CalibrationRow>().enumerate() {
-        if rows.len() >= MAX_CALIBRATION_ROWS {
+    let mut points: Vec<CsvPoint> = Vec::new();
+    for (idx, rec) in rdr.deserialize::<CsvRow>().enumerate() {
+        let line = idx + 2;
+        if idx >= MAX_CALIBRATION_ROWS {
             eyre::bail!("calibration CSV has too many rows (> {MAX_CALIBRATION_ROWS})");
         }
-        match rec {
-            Ok(row) => rows.push(row),
-            Err(e) => {
-                eyre::bail!("invalid CSV row {}: {}", idx + 2, e);
+        let row = rec.map_err(|e| eyre::eyre!("invalid CSV row {line}: {e}"))?;
+        let unit = row.unit.as_deref().map(str::trim).filter(|u| !u.is_empty());
+        let per = units::grams_per(unit.unwrap_or("g"))
+            .map_err(|e| eyre::eyre!("invalid CSV row {line}: {e}"))?;
+        let grams = row.grams * per;
+        if !grams.is_finite() {
+            eyre::bail!("invalid CSV row {line}: weight {} is not finite", row.grams);
+        }
+        let label = row.replicate.unwrap_or_default().trim().to_string();
+        // The same weight written in another unit may differ in the last bits
+        let same = points
+            .iter_mut()
+            .find(|p| (p.grams - grams).abs() <= 1e-9 * grams.abs().max(1.0));
+        match same {
+            Some(p) if replicated => {
+                if let Some((_, prev)) = p.replicates.iter().find(|(l, _)| *l == label) {
+                    eyre::bail!(
+                        "invalid CSV row {line}: replicate {label:?} of {grams} g is already on row {prev}"
+                    );
+                }
+                p.raw_sum += row.raw as f64;
+                p.replicates.push((label, line));
             }
+            _ => points.push(CsvPoint {
+                grams,
+                raw_sum: row.raw as f64,
+                replicates: vec![(label, line)],
+            }),
         }
     }
 
-    Calibration::try_from(rows)
+    // Check the order here, where the rows are known, rather than in `from_rows`
+    let mut dir = 0i64;
+    for w in points.windows(2) {
+        let (a, b) = (w[0].raw(), w[1].raw());
+        let step = (b - a).signum();
+        if step == 0 {
+            eyre::bail!(
+                "calibration CSV rows {} and {} have the same raw value {a}",
+                w[0].rows(),
+                w[1].rows()
+            );
+        }
+        if dir != 0 && step != dir {
+            eyre::bail!(
+                "calibration raw values must be monotonic (strictly increasing or strictly decreasing): rows {} and {} go from {a} to {b}",
+                w[0].rows(),
+                w[1].rows()
+            );
+        }
+        dir = step;
+    }
+
+    let rows: Vec<CalibrationRow> = points
+        .iter()
+        .map(|p| CalibrationRow {
+            raw: p.raw(),
+            grams: p.grams as f32,
+        })
+        .collect();
+    Calibration::from_rows_with(rows, fit)
 }
 
+/// Highest BCM GPIO on the Raspberry Pi 40-pin header.
+const MAX_HEADER_GPIO: u8 = 27;
+
 /// Upper bound for filter/predictor window sizes. These size `Vec`/`VecDeque`
 /// allocations in the core, so an untrusted config must not request an
 /// unbounded window (memory-exhaustion guard). Real configs use single digits.
 const MAX_WINDOW: usize = 10_000;
 
 impl Config {
+    /// Every configured pin with the key that names it: the built-in drivers'
+    /// `[pins]` (unless `[plugin]` replaces them), the extra HX711s and the
+    /// actuators.
+    fn pin_uses(&self) -> Vec<(OutputPin, String)> {
+        let mut uses = Vec::new();
+        let mut gpio = |pin: Option<u8>, key: &str| {
+            if let Some(pin) = pin {
+                uses.push((OutputPin::Gpio(pin), key.to_string()));
+            }
+        };
+        if self.plugin.is_none() {
+            let p = &self.pins;
+            gpio(Some(p.hx711_dt), "pins.hx711_dt");
+            gpio(Some(p.hx711_sck), "pins.hx711_sck");
+            gpio(Some(p.motor_step), "pins.motor_step");
+            gpio(Some(p.motor_dir), "pins.motor_dir");
+            gpio(p.motor_en, "pins.motor_en");
+        }
+        gpio(self.pins.estop_in, "pins.estop_in");
+        if let Some(c) = &self.scale.composite {
+            gpio(Some(c.hx711_dt), "scale.composite.hx711_dt");
+            gpio(Some(c.hx711_sck), "scale.composite.hx711_sck");
+        }
+        if let Some(h) = &self.hopper {
+            gpio(Some(h.hx711_dt), "hopper.hx711_dt");
+            gpio(Some(h.hx711_sck), "hopper.hx711_sck");
+        }
+        uses.extend(
+            self.actuators
+                .iter()
+                .map(|(name, a)| (a.pin.clone(), format!("actuators.{name}.pin"))),
+        );
+        uses
+    }
+
+    /// Pins claimed more than once, and header pins the board does not have
+    /// (only without `pins.chip`: `gpiod` line offsets depend on the chip).
+    fn pin_conflicts(&self) -> Vec<String> {
+        let uses = self.pin_uses();
+        let mut out = Vec::new();
+        for (i, (pin, key)) in uses.iter().enumerate() {
+            if uses[..i].iter().any(|(p, _)| p == pin) {
+                continue;
+            }
+            let keys: Vec<&str> = std::iter::once(key.as_str())
+                .chain(
+                    uses[i + 1..]
+                        .iter()
+                        .filter(|(p, _)| p == pin)
+                        .map(|(_, k)| k.as_str()),
+                )
+                .collect();
+            if keys.len() > 1 {
+                let pin = match pin {
+                    OutputPin::Gpio(n) => format!("GPIO {n}"),
+                    OutputPin::Expander(p) => format!("{}:{}", p.expander, p.pin),
+                };
+                out.push(format!("{pin} is used by {}", keys.join(", ")));
+            }
+        }
+        if self.pins.chip.is_none() {
+            for (pin, key) in &uses {
+                if let OutputPin::Gpio(n) = pin
+                    && *n > MAX_HEADER_GPIO
+                {
+                    out.push(format!(
+                        "{key} = {n} is not a header GPIO (0..={MAX_HEADER_GPIO})"
+                    ));
+                }
+            }
+        }
+        out
+    }
+
     pub fn validate(&self) -> eyre::Result<()> {
         // Control
         if self.control.coarse_speed == 0 {
@@ -529,6 +1792,35 @@ impl Config {
         {
             eyre::bail!("control.epsilon_g must be finite and in [0.0, 1.0]");
         }
+        if !self.control.band_hysteresis_g.is_finite()
+            || self.control.band_hysteresis_g < 0.0
+            || self.control.band_hysteresis_g > 10.0
+        {
+            eyre::bail!("control.band_hysteresis_g must be finite and in [0.0, 10.0]");
+        }
+        if self.control.band_min_dwell_ms > 60_000 {
+            eyre::bail!("control.band_min_dwell_ms must be <= 60000");
+        }
+        // A delta wider than a band step would hold the faster speed near target.
+        let mut speeds: Vec<u32> = if self.control.speed_bands.is_empty() {
+            vec![self.control.coarse_speed, self.control.fine_speed]
+        } else {
+            self.control
+                .speed_bands
+                .iter()
+                .map(|&(_, sps)| sps)
+                .collect()
+        };
+        speeds.sort_unstable();
+        speeds.dedup();
+        if let Some(gap) = speeds.windows(2).map(|w| w[1] - w[0]).min()
+            && self.control.min_speed_delta_sps > gap
+        {
+            eyre::bail!(
+                "control.min_speed_delta_sps ({}) must not exceed the smallest speed step ({gap} sps)",
+                self.control.min_speed_delta_sps
+            );
+        }
         for (thr_g, sps) in &self.control.speed_bands {
             if !thr_g.is_finite() || *thr_g < 0.0 {
                 eyre::bail!("control.speed_bands threshold must be finite and >= 0");
@@ -555,26 +1847,284 @@ impl Config {
             eyre::bail!("safety.no_progress_ms is unreasonably large (>24h)");
         }
 
-        // Filter
-        if self.filter.ma_window == 0 {
-            eyre::bail!("filter.ma_window must be >= 1");
+        let on_abort = &self.safety.on_abort;
+        if on_abort.reverse_steps > 0 && !(1..=5_000).contains(&on_abort.reverse_sps) {
+            eyre::bail!("safety.on_abort.reverse_sps must be in 1..=5000 when reverse_steps > 0");
+        }
+        if on_abort.reverse_steps > 100_000 {
+            eyre::bail!("safety.on_abort.reverse_steps is unreasonably large (>100000)");
+        }
+        for name in &on_abort.release {
+            if !self.actuators.contains_key(name) {
+                eyre::bail!("safety.on_abort.release refers to unknown actuator {name:?}");
+            }
+        }
+
+        if let Some(p) = &self.plugin
+            && p.path.trim().is_empty()
+        {
+            eyre::bail!("plugin.path must not be empty");
+        }
+
+        if let Some(h) = &self.hopper {
+            if !h.gain_g_per_count.is_finite() || h.gain_g_per_count == 0.0 {
+                eyre::bail!("hopper.gain_g_per_count must be finite and non-zero");
+            }
+            if !h.reserve_g.is_finite() || h.reserve_g < 0.0 {
+                eyre::bail!("hopper.reserve_g must be finite and >= 0");
+            }
+            if h.samples == 0 {
+                eyre::bail!("hopper.samples must be >= 1");
+            }
+            if h.driver.as_deref().is_some_and(|d| d.trim().is_empty()) {
+                eyre::bail!("hopper.driver must not be empty");
+            }
+        }
+
+        if self.hardware.lock_dir.trim().is_empty() {
+            eyre::bail!("hardware.lock_dir must not be empty");
+        }
+        if self
+            .runner
+            .lock_file
+            .as_deref()
+            .is_some_and(|f| f.trim().is_empty())
+        {
+            eyre::bail!("runner.lock_file must not be empty");
+        }
+
+        if let Some(r) = &self.resume {
+            if r.state_file.trim().is_empty() {
+                eyre::bail!("resume.state_file must not be empty");
+            }
+            if r.samples == 0 {
+                eyre::bail!("resume.samples must be >= 1");
+            }
+            if !r.max_rezero_g.is_finite() || r.max_rezero_g <= 0.0 {
+                eyre::bail!("resume.max_rezero_g must be finite and > 0");
+            }
+        }
+
+        if !self.verify.tolerance_g.is_finite() || self.verify.tolerance_g <= 0.0 {
+            eyre::bail!("verify.tolerance_g must be finite and > 0");
+        }
+        if self.verify.samples == 0 {
+            eyre::bail!("verify.samples must be >= 1");
+        }
+        if let Some(a) = &self.access
+            && a.technician_pin.trim().is_empty()
+        {
+            eyre::bail!("access.technician_pin must not be empty");
+        }
+
+        if self
+            .bundle
+            .as_ref()
+            .is_some_and(|b| b.dir.trim().is_empty())
+        {
+            eyre::bail!("bundle.dir must not be empty");
+        }
+        if let Some(l) = &self.learned {
+            if l.file.trim().is_empty() {
+                eyre::bail!("learned.file must not be empty");
+            }
+            if l.material.trim().is_empty() {
+                eyre::bail!("learned.material must not be empty");
+            }
+            if l.epsilon_min_g.is_some() != l.epsilon_max_g.is_some() {
+                eyre::bail!("learned.epsilon_min_g and learned.epsilon_max_g must be set together");
+            }
+            if let Some((min, max)) = l.epsilon_bounds()
+                && !(min >= 0.0 && min <= max && max <= 1.0)
+            {
+                eyre::bail!(
+                    "learned.epsilon_min_g..epsilon_max_g must be an ordered range within [0.0, 1.0]"
+                );
+            }
+            if !(l.epsilon_step_g.is_finite() && l.epsilon_step_g > 0.0) {
+                eyre::bail!("learned.epsilon_step_g must be finite and > 0");
+            }
+            if l.epsilon_window < 2 {
+                eyre::bail!("learned.epsilon_window must be >= 2");
+            }
+        }
+        if let Some(a) = &self.autotare {
+            if !(a.min_container_g.is_finite() && a.min_container_g >= 0.0) {
+                eyre::bail!("autotare.min_container_g must be finite and >= 0");
+            }
+            if !(a.plateau_g.is_finite() && a.plateau_g > 0.0) {
+                eyre::bail!("autotare.plateau_g must be finite and > 0");
+            }
+            if a.plateau_ms == 0 {
+                eyre::bail!("autotare.plateau_ms must be > 0");
+            }
+            if a.max_wait_ms < a.plateau_ms {
+                eyre::bail!("autotare.max_wait_ms must be at least autotare.plateau_ms");
+            }
+        }
+        if let Some(r) = &self.retention {
+            if r.max_mb.is_none() && r.max_age_days.is_none() {
+                eyre::bail!("retention needs max_mb, max_age_days or both");
+            }
+            if r.max_mb == Some(0) || r.max_age_days == Some(0) {
+                eyre::bail!("retention.max_mb and retention.max_age_days must be > 0");
+            }
+        }
+        if let Some(s) = &self.shutdown
+            && s.marker.trim().is_empty()
+        {
+            eyre::bail!("shutdown.marker must not be empty");
+        }
+        if let Some(b) = &self.broadcast {
+            if b.group.parse::<std::net::SocketAddr>().is_err() {
+                eyre::bail!(
+                    "broadcast.group must be an ip:port address, got {:?}",
+                    b.group
+                );
+            }
+            if b.station.trim().is_empty() {
+                eyre::bail!("broadcast.station must not be empty");
+            }
+            if b.interval_ms < 100 {
+                eyre::bail!("broadcast.interval_ms must be >= 100");
+            }
+            if !(1..=255).contains(&b.ttl) {
+                eyre::bail!("broadcast.ttl must be in 1..=255");
+            }
+        }
+        if let Some(t) = &self.telemetry {
+            if !(t.endpoint.starts_with("http://") || t.endpoint.starts_with("https://")) {
+                eyre::bail!(
+                    "telemetry.endpoint must be an http:// or https:// URL, got {:?}",
+                    t.endpoint
+                );
+            }
+            if t.service_name.trim().is_empty() {
+                eyre::bail!("telemetry.service_name must not be empty");
+            }
+            if t.timeout_ms == 0 {
+                eyre::bail!("telemetry.timeout_ms must be >= 1");
+            }
         }
-        if self.filter.ma_window > MAX_WINDOW {
-            eyre::bail!("filter.ma_window must be <= {MAX_WINDOW}");
+        if let Some(r) = &self.requests {
+            if r.file.trim().is_empty() {
+                eyre::bail!("requests.file must not be empty");
+            }
+            if r.keep == 0 {
+                eyre::bail!("requests.keep must be >= 1");
+            }
         }
-        if self.filter.median_window == 0 {
-            eyre::bail!("filter.median_window must be >= 1");
+
+        if let Some(t) = &self.ticket {
+            if t.template.trim().is_empty() {
+                eyre::bail!("ticket.template must not be empty");
+            }
+            if t.device.as_deref().is_some_and(|d| d.trim().is_empty()) {
+                eyre::bail!("ticket.device must not be empty");
+            }
         }
-        if self.filter.median_window > MAX_WINDOW {
-            eyre::bail!("filter.median_window must be <= {MAX_WINDOW}");
+
+        // Driver names are resolved against the backend registry at startup
+        for (key, driver) in [
+            ("scale.driver", &self.scale.driver),
+            ("motor.driver", &self.motor.driver),
+        ] {
+            match driver {
+                Some(d) if d.trim().is_empty() => eyre::bail!("{key} must not be empty"),
+                Some(_) if self.plugin.is_some() => {
+                    eyre::bail!("{key} conflicts with [plugin], which replaces both drivers")
+                }
+                _ => {}
+            }
         }
+
+        if self
+            .scale
+            .socket
+            .as_deref()
+            .is_some_and(|p| p.trim().is_empty())
+        {
+            eyre::bail!("scale.socket must not be empty");
+        }
+        if self.scale.driver.as_deref() == Some("socket") && self.scale.socket.is_none() {
+            eyre::bail!("scale.driver = \"socket\" needs scale.socket");
+        }
+
+        // Expanders
+        for (name, e) in &self.expanders {
+            if !(0x20..=0x27).contains(&e.address) {
+                eyre::bail!("expanders.{name}.address must be in 0x20..=0x27");
+            }
+        }
+        for (name, a) in &self.actuators {
+            if let OutputPin::Expander(p) = &a.pin
+                && !self.expanders.contains_key(&p.expander)
+            {
+                eyre::bail!(
+                    "actuators.{name}.pin refers to unknown expander {:?}",
+                    p.expander
+                );
+            }
+        }
+
+        // Filter
         if self.filter.sample_rate_hz == 0 {
             eyre::bail!("filter.sample_rate_hz must be > 0");
         }
-        if let Some(alpha) = self.filter.ema_alpha
-            && !(alpha > 0.0 && alpha <= 1.0)
-        {
-            eyre::bail!("filter.ema_alpha must be in (0.0, 1.0]");
+        let loop_hz = self.control.loop_hz;
+        if loop_hz != 0 && (loop_hz < self.filter.sample_rate_hz || loop_hz > 5000) {
+            eyre::bail!(
+                "control.loop_hz ({loop_hz}) must be 0 or between filter.sample_rate_hz ({}) and 5000",
+                self.filter.sample_rate_hz
+            );
+        }
+        let f = &self.filter;
+        let subs = [("filter.fine", &f.fine), ("filter.display", &f.display)];
+        let filters = std::iter::once((
+            "filter",
+            f.ma_window,
+            f.median_window,
+            f.ema_alpha,
+            f.smoothing,
+            f.trim,
+        ))
+        .chain(subs.into_iter().filter_map(|(sec, sub)| {
+            let f = sub.as_ref()?;
+            Some((
+                sec,
+                f.ma_window,
+                f.median_window,
+                f.ema_alpha,
+                f.smoothing,
+                f.trim,
+            ))
+        }));
+        for (sec, ma_window, median_window, ema_alpha, smoothing, trim) in filters {
+            if ma_window == 0 {
+                eyre::bail!("{sec}.ma_window must be >= 1");
+            }
+            if ma_window > MAX_WINDOW {
+                eyre::bail!("{sec}.ma_window must be <= {MAX_WINDOW}");
+            }
+            if median_window == 0 {
+                eyre::bail!("{sec}.median_window must be >= 1");
+            }
+            if median_window > MAX_WINDOW {
+                eyre::bail!("{sec}.median_window must be <= {MAX_WINDOW}");
+            }
+            if let Some(alpha) = ema_alpha
+                && !(alpha > 0.0 && alpha <= 1.0)
+            {
+                eyre::bail!("{sec}.ema_alpha must be in (0.0, 1.0]");
+            }
+            if trim > 0 {
+                if smoothing != Smoothing::TrimmedMean {
+                    eyre::bail!("{sec}.trim needs {sec}.smoothing = \"trimmed-mean\"");
+                }
+                if 2 * trim >= ma_window {
+                    eyre::bail!("{sec}.trim must leave at least one sample: 2 * trim < ma_window");
+                }
+            }
         }
 
         // Predictor
@@ -591,6 +2141,47 @@ impl Config {
             eyre::bail!("predictor.min_progress_ratio must be finite and in [0.0, 1.0]");
         }
 
+        // Pre-flight
+        if self.preflight.enabled {
+            if !(1..=MAX_WINDOW).contains(&self.preflight.samples) {
+                eyre::bail!("preflight.samples must be in 1..={MAX_WINDOW}");
+            }
+            if !(self.preflight.max_spread_g.is_finite() && self.preflight.max_spread_g >= 0.0) {
+                eyre::bail!("preflight.max_spread_g must be finite and >= 0");
+            }
+            if let (Some(lo), Some(hi)) = (self.preflight.min_g, self.preflight.max_g)
+                && lo > hi
+            {
+                eyre::bail!("preflight.min_g must be <= preflight.max_g");
+            }
+            if self
+                .preflight
+                .zero_band_g
+                .is_some_and(|b| !(b.is_finite() && b > 0.0))
+            {
+                eyre::bail!("preflight.zero_band_g must be finite and > 0");
+            }
+            if self.preflight.container_g.is_some_and(|g| !g.is_finite()) {
+                eyre::bail!("preflight.container_g must be finite");
+            }
+            if self.preflight.container_g.is_some() && self.preflight.zero_band_g.is_none() {
+                eyre::bail!("preflight.container_g needs preflight.zero_band_g");
+            }
+        }
+
+        // Warm-up
+        if !(2..=MAX_WINDOW).contains(&self.warmup.window) {
+            eyre::bail!("warmup.window must be in 2..={MAX_WINDOW}");
+        }
+        if let Some(sd) = self.warmup.stable_stddev_g
+            && !(sd.is_finite() && sd > 0.0)
+        {
+            eyre::bail!("warmup.stable_stddev_g must be finite and > 0");
+        }
+        if self.warmup.max_ms > 5 * 60 * 1000 {
+            eyre::bail!("warmup.max_ms is unreasonably large (>5min)");
+        }
+
         // Timeouts
         if self.timeouts.sample_ms == 0 {
             eyre::bail!("timeouts.sample_ms must be >= 1");
@@ -600,6 +2191,40 @@ impl Config {
         if self.hardware.sensor_read_timeout_ms == 0 {
             eyre::bail!("hardware.sensor_read_timeout_ms must be >= 1");
         }
+        if self.timeouts.retry.max_retries > 10 {
+            eyre::bail!("timeouts.retry.max_retries must be <= 10");
+        }
+        if self.timeouts.retry.backoff_ms > 1_000 {
+            eyre::bail!("timeouts.retry.backoff_ms must be <= 1000");
+        }
+        if self.hardware.retry.max_attempts > 10 {
+            eyre::bail!("hardware.retry.max_attempts must be <= 10");
+        }
+        if self.hardware.retry.settle_ms > 5_000 {
+            eyre::bail!("hardware.retry.settle_ms must be <= 5000");
+        }
+
+        if let Some(c) = &self.scale.composite {
+            if !(0.0..=1.0).contains(&c.weight) {
+                eyre::bail!("scale.composite.weight must be in [0.0, 1.0]");
+            }
+            if c.tolerance_counts == 0 {
+                eyre::bail!("scale.composite.tolerance_counts must be >= 1");
+            }
+            if c.fault_after == 0 {
+                eyre::bail!("scale.composite.fault_after must be >= 1");
+            }
+        }
+
+        if let Some(chip) = &self.pins.chip
+            && !chip.starts_with("/dev/")
+        {
+            eyre::bail!("pins.chip must be a device path such as /dev/gpiochip0");
+        }
+        let conflicts = self.pin_conflicts();
+        if !conflicts.is_empty() {
+            eyre::bail!("pin conflicts: {}", conflicts.join("; "));
+        }
 
         // E-stop
         if self.estop.debounce_n == 0 {
@@ -0,0 +1,169 @@
+//! Strict parsing: the keys of a config file that no field reads.
+//!
+//! Serde ignores unknown keys, so a typo such as `epsilom_g` silently leaves
+//! the default in place. [`unknown_keys`] deserializes the file once more
+//! through a wrapper that compares each table with the fields its struct
+//! declares, and reports the rest as dotted paths (`control.epsilom_g`).
+//! Tables read through untagged enums (table-form `speed_bands`) are not
+//! checked.
+
+use std::cell::RefCell;
+
+use serde::Deserialize;
+use serde::de::value::StringDeserializer;
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use toml::Value;
+
+type Error = toml::de::Error;
+
+/// Dotted paths of the keys in `s` that the config does not read, sorted.
+pub fn unknown_keys(s: &str) -> Result<Vec<String>, Error> {
+    let table: toml::Table = toml::from_str(s)?;
+    let unknown = RefCell::new(Vec::new());
+    crate::Config::deserialize(Tracked {
+        value: Value::Table(table),
+        path: String::new(),
+        unknown: &unknown,
+    })?;
+    let mut keys = unknown.into_inner();
+    keys.sort();
+    Ok(keys)
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// A value at `path`, recording the unknown keys of the structs read from it.
+struct Tracked<'a> {
+    value: Value,
+    path: String,
+    unknown: &'a RefCell<Vec<String>>,
+}
+
+impl<'de> Deserializer<'de> for Tracked<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Table(t) => visitor.visit_map(Table {
+                iter: t.into_iter(),
+                path: self.path,
+                unknown: self.unknown,
+                pending: None,
+            }),
+            Value::Array(a) => visitor.visit_seq(Array {
+                iter: a.into_iter().enumerate(),
+                path: self.path,
+                unknown: self.unknown,
+            }),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        if let Value::Table(t) = &self.value {
+            let mut unknown = self.unknown.borrow_mut();
+            for key in t.keys().filter(|k| !fields.contains(&k.as_str())) {
+                unknown.push(join(&self.path, key));
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map identifier
+        ignored_any
+    }
+}
+
+struct Table<'a> {
+    iter: toml::map::IntoIter,
+    path: String,
+    unknown: &'a RefCell<Vec<String>>,
+    pending: Option<(String, Value)>,
+}
+
+impl<'de> MapAccess<'de> for Table<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some((key, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+        let k = seed.deserialize(StringDeserializer::<Error>::new(key.clone()))?;
+        self.pending = Some((key, value));
+        Ok(Some(k))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let (key, value) = self
+            .pending
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before its key"))?;
+        seed.deserialize(Tracked {
+            value,
+            path: join(&self.path, &key),
+            unknown: self.unknown,
+        })
+    }
+}
+
+struct Array<'a> {
+    iter: std::iter::Enumerate<std::vec::IntoIter<Value>>,
+    path: String,
+    unknown: &'a RefCell<Vec<String>>,
+}
+
+impl<'de> SeqAccess<'de> for Array<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        let Some((i, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+        seed.deserialize(Tracked {
+            value,
+            path: format!("{}[{i}]", self.path),
+            unknown: self.unknown,
+        })
+        .map(Some)
+    }
+}
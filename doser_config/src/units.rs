@@ -0,0 +1,149 @@
+//! Unit-suffixed config values.
+//!
+//! Durations (`*_ms` keys) and masses (`*_g` keys) take a bare number in the
+//! key's unit, as they always have, or a string naming its unit:
+//! `stable_ms = "1.5s"`, `epsilon_g = "80mg"`. The value is converted to the
+//! key's unit; a unit of the wrong kind (`stable_ms = "0.08g"`) is an error.
+
+use std::fmt;
+
+use serde::Deserialize;
+use serde::de::{self, Deserializer, Visitor};
+
+/// Duration units, in milliseconds.
+const DURATION_UNITS: &[(&str, f64)] = &[("ms", 1.0), ("s", 1000.0), ("min", 60_000.0)];
+
+/// Mass units, in grams.
+const MASS_UNITS: &[(&str, f64)] = &[("mg", 0.001), ("cg", 0.01), ("g", 1.0), ("kg", 1000.0)];
+
+/// Split `"150 ms"` into `(150.0, "ms")` and convert with `units`.
+fn parse(s: &str, units: &[(&str, f64)], kind: &str) -> Result<f64, String> {
+    let s = s.trim();
+    let number = s.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = &s[number.len()..];
+    let value: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid {kind} {s:?} (expected e.g. \"150ms\" or \"0.08g\")"))?;
+    let factor =
+        factor(units, unit).map_err(|known| format!("{s:?} needs a {kind} unit ({known})"))?;
+    Ok(value * factor)
+}
+
+/// The factor of `unit` in `units`, or the known units.
+fn factor(units: &[(&str, f64)], unit: &str) -> Result<f64, String> {
+    units
+        .iter()
+        .find(|(u, _)| *u == unit)
+        .map(|(_, f)| *f)
+        .ok_or_else(|| {
+            let known: Vec<&str> = units.iter().map(|(u, _)| *u).collect();
+            known.join(", ")
+        })
+}
+
+/// Grams per `unit` (`"mg"`, `"g"`, ...), e.g. for the calibration CSV.
+pub(crate) fn grams_per(unit: &str) -> Result<f64, String> {
+    factor(MASS_UNITS, unit).map_err(|known| format!("unknown mass unit {unit:?} ({known})"))
+}
+
+/// Milliseconds from `s`, e.g. `"1.5s"`.
+fn parse_ms(s: &str) -> Result<u64, String> {
+    let ms = parse(s, DURATION_UNITS, "duration")?;
+    if !(ms >= 0.0 && ms <= u64::MAX as f64) {
+        return Err(format!("duration {s:?} is out of range"));
+    }
+    if (ms - ms.round()).abs() > 1e-6 {
+        return Err(format!(
+            "duration {s:?} is not a whole number of milliseconds"
+        ));
+    }
+    Ok(ms.round() as u64)
+}
+
+/// Grams from `s`, e.g. `"80mg"`.
+fn parse_grams(s: &str) -> Result<f32, String> {
+    parse(s, MASS_UNITS, "mass").map(|g| g as f32)
+}
+
+struct Millis(u64);
+
+impl<'de> Deserialize<'de> for Millis {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct V;
+        impl Visitor<'_> for V {
+            type Value = Millis;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("milliseconds or a duration such as \"150ms\" or \"1.5s\"")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Millis, E> {
+                Ok(Millis(v))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Millis, E> {
+                u64::try_from(v)
+                    .map(Millis)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Millis, E> {
+                parse_ms(v).map(Millis).map_err(E::custom)
+            }
+        }
+        d.deserialize_any(V)
+    }
+}
+
+struct Grams(f32);
+
+impl<'de> Deserialize<'de> for Grams {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct V;
+        impl Visitor<'_> for V {
+            type Value = Grams;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("grams or a mass such as \"0.08g\" or \"80mg\"")
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Grams, E> {
+                Ok(Grams(v as f32))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Grams, E> {
+                Ok(Grams(v as f32))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Grams, E> {
+                Ok(Grams(v as f32))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Grams, E> {
+                parse_grams(v).map(Grams).map_err(E::custom)
+            }
+        }
+        d.deserialize_any(V)
+    }
+}
+
+/// `deserialize_with` for a `*_ms` key.
+pub(crate) fn ms<'de, D: Deserializer<'de>>(d: D) -> Result<u64, D::Error> {
+    Millis::deserialize(d).map(|m| m.0)
+}
+
+/// `deserialize_with` for an optional `*_ms` key (needs a field or struct `default`).
+pub(crate) fn opt_ms<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u64>, D::Error> {
+    Option::<Millis>::deserialize(d).map(|m| m.map(|m| m.0))
+}
+
+/// `deserialize_with` for a `*_g` key.
+pub(crate) fn grams<'de, D: Deserializer<'de>>(d: D) -> Result<f32, D::Error> {
+    Grams::deserialize(d).map(|g| g.0)
+}
+
+/// `deserialize_with` for an optional `*_g` key (needs a field or struct `default`).
+pub(crate) fn opt_grams<'de, D: Deserializer<'de>>(d: D) -> Result<Option<f32>, D::Error> {
+    Option::<Grams>::deserialize(d).map(|g| g.map(|g| g.0))
+}
@@ -1,7 +1,9 @@
 use std::fs::File;
 use std::io::Write;
 
-use doser_config::{Calibration, CalibrationRow, load_calibration_csv};
+use doser_config::{
+    Calibration, CalibrationFit, CalibrationRow, load_calibration_csv, load_calibration_csv_with,
+};
 use rstest::rstest;
 use tempfile::tempdir;
 
@@ -195,3 +197,159 @@ fn calibration_all_inliers_no_refit_matches_ols() {
         ((c.offset as f32) - (true_offset_raw as f32)).abs() / (true_offset_raw as f32);
     assert!(rel_err_off <= 0.02, "offset rel err {rel_err_off}");
 }
+
+#[rstest]
+fn csv_units_and_replicates_are_averaged_per_point() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("replicates.csv");
+    let mut f = File::create(&path).unwrap();
+    writeln!(f, "raw,grams,unit,replicate").unwrap();
+    for line in [
+        "1000,0,g,1",
+        "1002,0,,2",
+        "51000,50,g,1",
+        "50998,50000,mg,2",
+        "101000,0.1,kg,1",
+        "101002,100,g,2",
+    ] {
+        writeln!(f, "{line}").unwrap();
+    }
+    drop(f);
+
+    let c = load_calibration_csv(&path).unwrap();
+    // Points (1001, 0 g), (50999, 50 g), (101001, 100 g)
+    assert!((c.scale_factor - 0.001).abs() < 1e-7, "{}", c.scale_factor);
+    assert!((c.offset - 1001).abs() <= 1, "{}", c.offset);
+}
+
+#[rstest]
+#[case::unknown_unit(
+    "raw,grams,unit\n100,0,g\n200,1,lb\n",
+    "row 3: unknown mass unit \"lb\""
+)]
+#[case::repeated_replicate(
+    "raw,grams,replicate\n100,0,a\n101,0,a\n200,1,a\n",
+    "row 3: replicate \"a\" of 0 g is already on row 2"
+)]
+#[case::not_monotonic("raw,grams\n100,0\n300,1\n200,2\n", "rows 3 and 4 go from 300 to 200")]
+#[case::same_raw(
+    "raw,grams\n100,0\n100,1\n",
+    "rows 2 and 3 have the same raw value 100"
+)]
+#[case::unknown_column("raw,grams,note\n100,0,x\n", "headers 'raw,grams'")]
+fn csv_errors_point_at_rows(#[case] csv: &str, #[case] needle: &str) {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("bad.csv");
+    std::fs::write(&path, csv).unwrap();
+    let err = load_calibration_csv(&path).expect_err(needle).to_string();
+    assert!(err.contains(needle), "{err}");
+}
+
+/// `n` points on grams = 0.5 * (raw - 100) with small noise, those where
+/// `outliers(i)` replaced by gross outliers.
+fn contaminated(n: i64, outliers: impl Fn(i64) -> bool) -> Vec<CalibrationRow> {
+    (0..n)
+        .map(|i| {
+            let raw = 50 + i * 10;
+            let noise = (i as f32 * 37.0).sin() * 0.05;
+            let grams = if outliers(i) {
+                300.0 + (i as f32 * 11.0).cos() * 200.0
+            } else {
+                0.5 * (raw - 100) as f32 + noise
+            };
+            CalibrationRow { raw, grams }
+        })
+        .collect()
+}
+
+fn assert_recovers(c: &Calibration) {
+    let rel_err_gain = (c.scale_factor - 0.5).abs() / 0.5;
+    assert!(rel_err_gain <= 0.01, "gain rel err {rel_err_gain}");
+    assert!((c.offset - 100).abs() <= 2, "offset {}", c.offset);
+}
+
+#[rstest]
+#[case::theil_sen_quarter(CalibrationFit::TheilSen, 4)]
+#[case::ransac_quarter(CalibrationFit::Ransac, 4)]
+fn robust_fits_recover_a_quarter_of_outliers(#[case] fit: CalibrationFit, #[case] every: i64) {
+    let rows = contaminated(60, |i| i % every == 1);
+    assert_recovers(&Calibration::from_rows_with(rows, fit).unwrap());
+}
+
+#[rstest]
+fn ransac_recovers_with_forty_percent_outliers() {
+    // 2 in 5 points are outliers: beyond the 2σ refit and Theil–Sen.
+    let rows = contaminated(100, |i| i % 5 < 2);
+    let ols = Calibration::from_rows(rows.clone()).unwrap();
+    assert!(
+        (ols.scale_factor - 0.5).abs() / 0.5 > 0.01,
+        "ols unexpectedly recovered: {}",
+        ols.scale_factor
+    );
+    assert_recovers(&Calibration::from_rows_with(rows.clone(), CalibrationFit::Ransac).unwrap());
+
+    // Beyond the exhaustive pair search (sampled pairs), still deterministic.
+    let many = contaminated(400, |i| i % 5 < 2);
+    let a = Calibration::from_rows_with(many.clone(), CalibrationFit::Ransac).unwrap();
+    let b = Calibration::from_rows_with(many, CalibrationFit::Ransac).unwrap();
+    assert_recovers(&a);
+    assert_eq!((a.scale_factor, a.offset), (b.scale_factor, b.offset));
+}
+
+#[rstest]
+fn csv_fit_is_selectable() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("contaminated.csv");
+    let mut f = File::create(&path).unwrap();
+    writeln!(f, "raw,grams").unwrap();
+    for r in contaminated(60, |i| i % 4 == 1) {
+        writeln!(f, "{},{}", r.raw, r.grams).unwrap();
+    }
+    drop(f);
+    assert_recovers(&load_calibration_csv_with(&path, CalibrationFit::TheilSen).unwrap());
+    assert_eq!("ransac".parse(), Ok(CalibrationFit::Ransac));
+    assert!("lms".parse::<CalibrationFit>().is_err());
+    assert!(load_calibration_csv(&path).is_ok());
+}
+
+#[rstest]
+fn calibration_uncertainty_bounds_the_tolerance() {
+    let two = Calibration::from_rows(vec![
+        CalibrationRow { raw: 0, grams: 0.0 },
+        CalibrationRow {
+            raw: 1000,
+            grams: 10.0,
+        },
+    ])
+    .unwrap();
+    assert!(two.uncertainty.is_none());
+    assert_eq!(two.min_tolerance_g(5.0), None);
+
+    // Residuals of about ±1 g on a 0.01 g/count line over 5000 counts.
+    let rows: Vec<CalibrationRow> = [0.8f32, -1.1, 0.4, 1.2, -0.9, -0.3]
+        .iter()
+        .enumerate()
+        .map(|(i, noise)| CalibrationRow {
+            raw: i as i64 * 1000,
+            grams: i as f32 * 10.0 + noise,
+        })
+        .collect();
+    let c = Calibration::from_rows(rows.clone()).unwrap();
+    let u = c.uncertainty.unwrap();
+    assert_eq!(u.points, 6);
+    assert!(u.residual_sd_g > 0.5 && u.residual_sd_g < 1.5, "{u:?}");
+    // s / sqrt(Sxx), Sxx = 17.5e6 counts²
+    assert!((u.gain_se - u.residual_sd_g / 17.5e6f64.sqrt()).abs() < 1e-12);
+    let tol = c.min_tolerance_g(5.0).unwrap();
+    assert!(tol > 0.05 && tol < 0.5, "{tol}");
+    assert!((c.min_tolerance_g(50.0).unwrap() - 10.0 * tol).abs() < 1e-4);
+
+    // An outlier the robust fit drops does not inflate the errors.
+    let mut dirty = rows;
+    dirty.push(CalibrationRow {
+        raw: 6000,
+        grams: 400.0,
+    });
+    let r = Calibration::from_rows_with(dirty, CalibrationFit::Ransac).unwrap();
+    assert_eq!(r.uncertainty.unwrap().points, 6);
+}
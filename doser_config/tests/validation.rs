@@ -1,14 +1,20 @@
-use doser_config::load_toml;
+use doser_config::{ExpanderPin, FilterCfg, OutputPin, Smoothing, load_toml};
+
+/// The driver pins every config needs.
+const PINS: &str = "[pins]\nhx711_dt = 5\nhx711_sck = 6\nmotor_step = 23\nmotor_dir = 24\n";
+
+/// An unsmoothed 50 Hz filter.
+const FILTER: &str = "[filter]\nma_window = 1\nmedian_window = 1\nsample_rate_hz = 50\n";
+
+/// The smallest valid config; tests append the sections they check.
+fn base() -> String {
+    format!("{PINS}\n{FILTER}\n[timeouts]\nsample_ms = 150\n")
+}
 
 #[test]
 fn rejects_zero_sample_rate_hz() {
-    let toml = r#"
-[pins]
-hx711_dt = 5
-hx711_sck = 6
-motor_step = 23
-motor_dir = 24
-
+    let toml = format!(
+        r#"{PINS}
 [filter]
 ma_window = 3
 median_window = 3
@@ -30,9 +36,10 @@ max_run_ms = 60000
 max_overshoot_g = 1.0
 no_progress_epsilon_g = 0.02
 no_progress_ms = 1200
-"#;
+"#
+    );
 
-    let cfg = load_toml(toml).expect("parse TOML");
+    let cfg = load_toml(&toml).expect("parse TOML");
     let err = cfg.validate().expect_err("should reject sample_rate_hz=0");
     assert!(
         format!("{err}")
@@ -43,13 +50,8 @@ no_progress_ms = 1200
 
 #[test]
 fn accepts_positive_sample_rate_hz() {
-    let toml = r#"
-[pins]
-hx711_dt = 5
-hx711_sck = 6
-motor_step = 23
-motor_dir = 24
-
+    let toml = format!(
+        r#"{PINS}
 [filter]
 ma_window = 3
 median_window = 3
@@ -63,21 +65,17 @@ no_progress_epsilon_g = 0.02
 no_progress_ms = 1200
 max_run_ms = 60000
 max_overshoot_g = 1.0
-"#;
+"#
+    );
 
-    let cfg = load_toml(toml).expect("parse TOML");
+    let cfg = load_toml(&toml).expect("parse TOML");
     cfg.validate().expect("valid config should pass");
 }
 
 #[test]
 fn rejects_non_finite_epsilon_g() {
-    let toml = r#"
-[pins]
-hx711_dt = 5
-hx711_sck = 6
-motor_step = 23
-motor_dir = 24
-
+    let toml = format!(
+        r#"{PINS}
 [filter]
 ma_window = 3
 median_window = 3
@@ -99,9 +97,10 @@ max_run_ms = 60000
 max_overshoot_g = 1.0
 no_progress_epsilon_g = 0.02
 no_progress_ms = 1200
-"#;
+"#
+    );
 
-    let cfg = load_toml(toml).expect("parse TOML");
+    let cfg = load_toml(&toml).expect("parse TOML");
     let err = cfg.validate().expect_err("should reject NaN epsilon_g");
     assert!(
         format!("{err}").contains("epsilon_g"),
@@ -111,13 +110,8 @@ no_progress_ms = 1200
 
 #[test]
 fn rejects_oversized_filter_window() {
-    let toml = r#"
-[pins]
-hx711_dt = 5
-hx711_sck = 6
-motor_step = 23
-motor_dir = 24
-
+    let toml = format!(
+        r#"{PINS}
 [filter]
 ma_window = 1000000
 median_window = 3
@@ -131,9 +125,10 @@ no_progress_epsilon_g = 0.02
 no_progress_ms = 1200
 max_run_ms = 60000
 max_overshoot_g = 1.0
-"#;
+"#
+    );
 
-    let cfg = load_toml(toml).expect("parse TOML");
+    let cfg = load_toml(&toml).expect("parse TOML");
     let err = cfg
         .validate()
         .expect_err("should reject an oversized ma_window");
@@ -142,3 +137,747 @@ max_overshoot_g = 1.0
         "unexpected error: {err}"
     );
 }
+
+#[test]
+fn parses_runner_overrun_policy() {
+    let base = base();
+    let cfg = load_toml(&base).expect("parse TOML");
+    assert_eq!(cfg.runner.overrun, doser_config::OverrunPolicy::SkipSleep);
+
+    let cfg = load_toml(&format!(
+        "{base}\n[runner]\noverrun = \"log-and-degrade\"\n"
+    ))
+    .expect("parse TOML");
+    assert_eq!(
+        cfg.runner.overrun,
+        doser_config::OverrunPolicy::LogAndDegrade
+    );
+
+    assert!(load_toml(&format!("{base}\n[runner]\noverrun = \"sometimes\"\n")).is_err());
+}
+
+#[test]
+fn validates_safety_on_abort() {
+    let base = base();
+    let cfg = load_toml(&base).expect("parse TOML");
+    assert_eq!(cfg.safety.on_abort.reverse_steps, 0);
+    assert!(cfg.validate().is_ok());
+
+    let full = format!(
+        "{base}\n[safety.on_abort]\nreverse_steps = 200\nreverse_sps = 400\nrelease = [\"valve\"]\ndisable_driver = true\n\n[actuators]\nvalve = {{ pin = 17, active_low = true }}\n"
+    );
+    let cfg = load_toml(&full).expect("parse TOML");
+    assert!(cfg.validate().is_ok());
+    assert_eq!(cfg.safety.on_abort.release, ["valve"]);
+    assert!(cfg.actuators["valve"].active_low);
+
+    // Releasing an undeclared actuator is a config error.
+    let cfg = load_toml(&format!(
+        "{base}\n[safety.on_abort]\nrelease = [\"gate\"]\n"
+    ))
+    .unwrap();
+    let err = cfg.validate().unwrap_err().to_string();
+    assert!(err.contains("unknown actuator"), "{err}");
+
+    // A reversal needs a step rate.
+    let cfg = load_toml(&format!("{base}\n[safety.on_abort]\nreverse_steps = 50\n")).unwrap();
+    assert!(cfg.validate().is_err());
+}
+
+#[test]
+fn validates_scale_composite() {
+    let base = base();
+    let cfg = load_toml(&base).expect("parse TOML");
+    assert!(cfg.scale.composite.is_none());
+
+    let cfg = load_toml(&format!(
+        "{base}\n[scale.composite]\nhx711_dt = 16\nhx711_sck = 20\ntolerance_counts = 500\n"
+    ))
+    .expect("parse TOML");
+    assert!(cfg.validate().is_ok());
+    let c = cfg.scale.composite.unwrap();
+    assert_eq!((c.weight, c.fault_after), (0.5, 3));
+
+    // Reusing a primary HX711 pin is rejected.
+    let cfg = load_toml(&format!(
+        "{base}\n[scale.composite]\nhx711_dt = 5\nhx711_sck = 20\ntolerance_counts = 500\n"
+    ))
+    .unwrap();
+    let err = cfg.validate().unwrap_err().to_string();
+    assert!(err.contains("scale.composite.hx711_dt"), "{err}");
+
+    let cfg = load_toml(&format!(
+        "{base}\n[scale.composite]\nhx711_dt = 16\nhx711_sck = 20\ntolerance_counts = 500\nweight = 1.5\n"
+    ))
+    .unwrap();
+    assert!(cfg.validate().is_err());
+}
+
+#[test]
+fn rejects_speed_delta_wider_than_band_step() {
+    let base = format!(
+        "{}\n[control]\nspeed_bands = [[1.0, 1100], [0.5, 450], [0.2, 200]]\n",
+        base()
+    );
+    let cfg = load_toml(&format!("{base}min_speed_delta_sps = 250\n")).unwrap();
+    assert!(cfg.validate().is_ok());
+
+    let cfg = load_toml(&format!("{base}min_speed_delta_sps = 300\n")).unwrap();
+    let err = cfg.validate().unwrap_err().to_string();
+    assert!(err.contains("min_speed_delta_sps"), "{err}");
+}
+
+#[test]
+fn loop_hz_is_at_least_the_sample_rate() {
+    let base = format!(
+        "{PINS}\n[filter]\nma_window = 1\nmedian_window = 1\nsample_rate_hz = 10\n\n[timeouts]\nsample_ms = 150\n\n[control]\n"
+    );
+    for ok in [0, 10, 200] {
+        let cfg = load_toml(&format!("{base}loop_hz = {ok}\n")).unwrap();
+        assert!(cfg.validate().is_ok(), "{ok}");
+    }
+    for bad in [5, 10_000] {
+        let cfg = load_toml(&format!("{base}loop_hz = {bad}\n")).unwrap();
+        let err = cfg.validate().unwrap_err().to_string();
+        assert!(err.contains("loop_hz"), "{err}");
+    }
+}
+
+#[test]
+fn parses_motor_polarity() {
+    let base = base();
+    let cfg = load_toml(&base).unwrap();
+    assert!(!cfg.motor.invert_direction && !cfg.motor.invert_enable);
+
+    let cfg = load_toml(&format!("{base}\n[motor]\ninvert_direction = true\n")).unwrap();
+    assert!(cfg.motor.invert_direction && !cfg.motor.invert_enable);
+}
+
+#[test]
+fn pins_chip_must_be_a_device_path() {
+    let with_chip =
+        |chip: &str| format!("{PINS}chip = \"{chip}\"\n\n{FILTER}\n[timeouts]\nsample_ms = 150\n");
+    let cfg = load_toml(&with_chip("/dev/gpiochip1")).unwrap();
+    assert_eq!(cfg.pins.chip.as_deref(), Some("/dev/gpiochip1"));
+    cfg.validate().expect("device path should pass");
+    let cfg = load_toml(&with_chip("gpiochip1")).unwrap();
+    let err = cfg
+        .validate()
+        .expect_err("bare chip name should be rejected");
+    assert!(err.to_string().contains("pins.chip"));
+}
+
+#[test]
+fn actuator_pins_can_live_on_an_expander() {
+    let base = base();
+    let cfg = load_toml(&format!(
+        "{base}\n[expanders]\nmcp0 = {{ address = 0x20 }}\n\n[actuators]\nvalve = {{ pin = \"mcp0:7\" }}\ngate = {{ pin = 17 }}\n"
+    ))
+    .expect("parse TOML");
+    cfg.validate().expect("expander pin should pass");
+    assert_eq!(cfg.expanders["mcp0"].bus, 1);
+    assert_eq!(
+        cfg.actuators["valve"].pin,
+        OutputPin::Expander(ExpanderPin {
+            expander: "mcp0".into(),
+            pin: 7
+        })
+    );
+    assert_eq!(cfg.actuators["gate"].pin, OutputPin::Gpio(17));
+
+    // Undeclared expander.
+    let cfg = load_toml(&format!(
+        "{base}\n[actuators]\nvalve = {{ pin = \"mcp1:7\" }}\n"
+    ))
+    .expect("parse TOML");
+    let err = cfg.validate().expect_err("unknown expander");
+    assert!(err.to_string().contains("unknown expander"));
+
+    // Malformed or out-of-range pin references fail to parse.
+    for bad in ["mcp0", "mcp0:16", ":3"] {
+        let toml = format!(
+            "{base}\n[expanders]\nmcp0 = {{ address = 0x20 }}\n\n[actuators]\nvalve = {{ pin = \"{bad}\" }}\n"
+        );
+        assert!(load_toml(&toml).is_err(), "{bad} should be rejected");
+    }
+}
+
+#[test]
+fn parses_plugin_backend() {
+    let base = base();
+    assert!(load_toml(&base).unwrap().plugin.is_none());
+
+    let cfg = load_toml(&format!(
+        "{base}\n[plugin]\npath = \"/opt/acme/libacme.so\"\nscale_args = \"port=/dev/ttyUSB0\"\n"
+    ))
+    .unwrap();
+    cfg.validate().expect("plugin config should pass");
+    let p = cfg.plugin.as_ref().unwrap();
+    assert_eq!(p.path, "/opt/acme/libacme.so");
+    assert_eq!(p.scale_args, "port=/dev/ttyUSB0");
+    assert_eq!(p.motor_args, "");
+
+    let cfg = load_toml(&format!("{base}\n[plugin]\npath = \" \"\n")).unwrap();
+    let err = cfg.validate().expect_err("empty path");
+    assert!(err.to_string().contains("plugin.path"));
+}
+
+#[test]
+fn parses_driver_selection() {
+    let base = base();
+    let cfg = load_toml(&base).unwrap();
+    assert!(cfg.scale.driver.is_none() && cfg.motor.driver.is_none());
+
+    let cfg = load_toml(&format!(
+        "{base}\n[scale]\ndriver = \"hx711\"\n[motor]\ndriver = \"gpio-thread\"\n"
+    ))
+    .unwrap();
+    cfg.validate().expect("driver names should pass");
+    assert_eq!(cfg.scale.driver.as_deref(), Some("hx711"));
+    assert_eq!(cfg.motor.driver.as_deref(), Some("gpio-thread"));
+
+    let cfg = load_toml(&format!("{base}\n[motor]\ndriver = \"\"\n")).unwrap();
+    let err = cfg.validate().expect_err("empty driver");
+    assert!(err.to_string().contains("motor.driver must not be empty"));
+
+    let cfg = load_toml(&format!(
+        "{base}\n[scale]\ndriver = \"sim\"\n[plugin]\npath = \"/opt/libacme.so\"\n"
+    ))
+    .unwrap();
+    let err = cfg.validate().expect_err("driver with plugin");
+    assert!(err.to_string().contains("conflicts with [plugin]"));
+}
+
+#[test]
+fn validates_hopper() {
+    let base = base();
+    assert!(load_toml(&base).unwrap().hopper.is_none());
+
+    let cfg = load_toml(&format!(
+        "{base}\n[hopper]\nhx711_dt = 16\nhx711_sck = 20\ngain_g_per_count = 0.002\nreserve_g = 50\n"
+    ))
+    .unwrap();
+    cfg.validate().expect("hopper config should pass");
+    let h = cfg.hopper.as_ref().unwrap();
+    assert_eq!((h.zero_counts, h.samples), (0, 5));
+    assert_eq!(h.reserve_g, 50.0);
+
+    let cfg = load_toml(&format!(
+        "{base}\n[hopper]\nhx711_dt = 5\nhx711_sck = 20\ngain_g_per_count = 0.002\n"
+    ))
+    .unwrap();
+    let err = cfg.validate().expect_err("shared pin");
+    assert!(err.to_string().contains("hopper.hx711_dt"));
+
+    let cfg = load_toml(&format!(
+        "{base}\n[hopper]\nhx711_dt = 16\nhx711_sck = 20\ngain_g_per_count = 0.0\n"
+    ))
+    .unwrap();
+    let err = cfg.validate().expect_err("zero gain");
+    assert!(err.to_string().contains("hopper.gain_g_per_count"));
+}
+
+#[test]
+fn validates_read_retry() {
+    let base = base();
+    let r = load_toml(&base).unwrap().timeouts.retry;
+    assert_eq!((r.max_retries, r.backoff_ms), (2, 5));
+
+    let cfg = load_toml(&format!("{base}\n[timeouts.retry]\nmax_retries = 0\n")).unwrap();
+    cfg.validate().expect("retries can be disabled");
+    assert_eq!(cfg.timeouts.retry.backoff_ms, 5);
+
+    let cfg = load_toml(&format!("{base}\n[timeouts.retry]\nmax_retries = 11\n")).unwrap();
+    let err = cfg.validate().expect_err("too many retries");
+    assert!(err.to_string().contains("timeouts.retry.max_retries"));
+
+    let cfg = load_toml(&format!("{base}\n[timeouts.retry]\nbackoff_ms = 2000\n")).unwrap();
+    let err = cfg.validate().expect_err("backoff too long");
+    assert!(err.to_string().contains("timeouts.retry.backoff_ms"));
+}
+
+#[test]
+fn validates_resume() {
+    let base = base();
+    assert!(load_toml(&base).unwrap().resume.is_none());
+
+    let cfg = load_toml(&format!(
+        "{base}\n[resume]\nstate_file = \"/var/lib/doser/resume.json\"\n"
+    ))
+    .unwrap();
+    cfg.validate().expect("resume config should pass");
+    assert_eq!(cfg.resume.as_ref().unwrap().samples, 5);
+
+    let cfg = load_toml(&format!("{base}\n[resume]\nstate_file = \" \"\n")).unwrap();
+    let err = cfg.validate().expect_err("empty state file");
+    assert!(err.to_string().contains("resume.state_file"));
+
+    let cfg = load_toml(&format!(
+        "{base}\n[resume]\nstate_file = \"r.json\"\nsamples = 0\n"
+    ))
+    .unwrap();
+    let err = cfg.validate().expect_err("zero samples");
+    assert!(err.to_string().contains("resume.samples"));
+
+    let r = load_toml(&format!("{base}\n[resume]\nstate_file = \"r.json\"\n"))
+        .unwrap()
+        .resume
+        .unwrap();
+    assert!(!r.track_zero);
+    assert_eq!(r.max_rezero_g, 2.0);
+    let cfg = load_toml(&format!(
+        "{base}\n[resume]\nstate_file = \"r.json\"\ntrack_zero = true\nmax_rezero_g = 0.0\n"
+    ))
+    .unwrap();
+    let err = cfg.validate().expect_err("zero re-zero bound");
+    assert!(err.to_string().contains("resume.max_rezero_g"));
+}
+
+#[test]
+fn validates_ticket() {
+    let base = base();
+    let cfg = load_toml(&format!("{base}\n[ticket]\ntemplate = \"{{final_g}} g\"\n")).unwrap();
+    cfg.validate().expect("ticket config should pass");
+    assert!(cfg.ticket.as_ref().unwrap().device.is_none());
+
+    let cfg = load_toml(&format!("{base}\n[ticket]\ntemplate = \"\"\n")).unwrap();
+    let err = cfg.validate().expect_err("empty template");
+    assert!(err.to_string().contains("ticket.template"));
+
+    let cfg = load_toml(&format!(
+        "{base}\n[ticket]\ntemplate = \"x\"\ndevice = \"\"\n"
+    ))
+    .unwrap();
+    let err = cfg.validate().expect_err("empty device");
+    assert!(err.to_string().contains("ticket.device"));
+}
+
+#[test]
+fn filter_profiles_validate_at_their_rate() {
+    for (sps, rate) in [(10, 10), (80, 80), (40, 80)] {
+        let f = FilterCfg::for_sps(sps);
+        assert_eq!(f.sample_rate_hz, rate);
+        let cfg = load_toml(&format!(
+            "{PINS}\n[filter]\nma_window = {}\nmedian_window = {}\nsample_rate_hz = {}\n\n[timeouts]\nsample_ms = 150\n",
+            f.ma_window, f.median_window, f.sample_rate_hz
+        ))
+        .unwrap();
+        cfg.validate().expect("profile should pass");
+    }
+    // The slow rate gets shorter windows.
+    assert!(FilterCfg::for_sps(10).ma_window < FilterCfg::for_sps(80).ma_window);
+}
+
+#[test]
+fn validates_filter_smoothing() {
+    let parse = |filter: &str| {
+        load_toml(&format!(
+            "{PINS}\n[filter]\nma_window = 5\nmedian_window = 1\nsample_rate_hz = 50\n{filter}\n\n[timeouts]\nsample_ms = 150\n"
+        ))
+    };
+    let with = |filter: &str| parse(filter).unwrap();
+    let cfg = with("");
+    assert_eq!(cfg.filter.smoothing, Smoothing::MovingAverage);
+    with("smoothing = \"weighted\"").validate().unwrap();
+    let cfg = with("smoothing = \"trimmed-mean\"\ntrim = 2");
+    cfg.validate().unwrap();
+    assert_eq!(cfg.filter.smoothing, Smoothing::TrimmedMean);
+
+    let err = with("smoothing = \"trimmed-mean\"\ntrim = 3")
+        .validate()
+        .expect_err("trim leaves no samples");
+    assert!(err.to_string().contains("filter.trim"));
+    let err = with("trim = 1")
+        .validate()
+        .expect_err("trim without trimmed mean");
+    assert!(err.to_string().contains("trimmed-mean"));
+    assert!(parse("smoothing = \"wma\"").is_err());
+
+    let cfg = with("\n[filter.fine]\nma_window = 8\nsmoothing = \"trimmed-mean\"\ntrim = 2");
+    cfg.validate().unwrap();
+    let fine = cfg.filter.fine.as_ref().unwrap();
+    assert_eq!((fine.ma_window, fine.median_window), (8, 1));
+    let err = with("\n[filter.fine]\nma_window = 0")
+        .validate()
+        .expect_err("empty fine window");
+    assert!(err.to_string().contains("filter.fine.ma_window"));
+    let err = with("\n[filter.fine]\nema_alpha = 1.5")
+        .validate()
+        .expect_err("fine alpha out of range");
+    assert!(err.to_string().contains("filter.fine.ema_alpha"));
+}
+
+#[test]
+fn validates_verify() {
+    let base = base();
+    let cfg = load_toml(&base).unwrap();
+    cfg.validate().unwrap();
+    assert_eq!(cfg.verify.samples, 10);
+
+    let cfg = load_toml(&format!("{base}\n[verify]\ntolerance_g = 0\n")).unwrap();
+    let err = cfg.validate().expect_err("zero tolerance");
+    assert!(err.to_string().contains("verify.tolerance_g"));
+    let cfg = load_toml(&format!("{base}\n[verify]\nsamples = 0\n")).unwrap();
+    let err = cfg.validate().expect_err("no samples");
+    assert!(err.to_string().contains("verify.samples"));
+
+    // A recorded check parses back.
+    let cfg = load_toml(&format!(
+        "{base}\n[calibration]\ngain_g_per_count = 0.01\nzero_counts = 0\n\n[calibration.verified]\nat = \"2026-10-16 07:02:11 UTC\"\nreference_g = 100.0\nmeasured_g = 99.96\nerror_g = -0.04\ntolerance_g = 0.1\npassed = true\n"
+    ))
+    .unwrap();
+    let v = cfg.calibration.unwrap().verified.unwrap();
+    assert!(v.passed);
+    assert_eq!(v.reference_g, 100.0);
+}
+
+#[test]
+fn validates_access() {
+    let base = base();
+    let cfg = load_toml(&format!("{base}\n[access]\ntechnician_pin = \"4711\"\n")).unwrap();
+    cfg.validate().unwrap();
+    assert_eq!(cfg.access.unwrap().technician_pin, "4711");
+
+    let cfg = load_toml(&format!("{base}\n[access]\ntechnician_pin = \" \"\n")).unwrap();
+    let err = cfg.validate().expect_err("blank PIN");
+    assert!(err.to_string().contains("access.technician_pin"));
+}
+
+#[test]
+fn validates_learned() {
+    let base = base();
+    let cfg = load_toml(&format!(
+        "{base}\n[learned]\nfile = \"/var/lib/doser/learned.json\"\n"
+    ))
+    .unwrap();
+    cfg.validate().unwrap();
+    assert_eq!(cfg.learned.unwrap().material, "default");
+
+    let cfg = load_toml(&format!(
+        "{base}\n[learned]\nfile = \"/tmp/l.json\"\nmaterial = \" \"\n"
+    ))
+    .unwrap();
+    let err = cfg.validate().expect_err("blank material");
+    assert!(err.to_string().contains("learned.material"));
+
+    let learned = "[learned]\nfile = \"/tmp/l.json\"\n";
+    let cfg = load_toml(&format!(
+        "{base}\n{learned}epsilon_min_g = \"20mg\"\nepsilon_max_g = 0.15\n"
+    ))
+    .unwrap();
+    cfg.validate().unwrap();
+    let l = cfg.learned.unwrap();
+    assert_eq!(l.epsilon_bounds(), Some((0.02, 0.15)));
+    assert_eq!((l.epsilon_step_g, l.epsilon_window), (0.02, 5));
+
+    for (extra, key) in [
+        ("epsilon_min_g = 0.02\n", "epsilon_max_g"),
+        (
+            "epsilon_min_g = 0.2\nepsilon_max_g = 0.1\n",
+            "ordered range",
+        ),
+        ("epsilon_step_g = 0\n", "epsilon_step_g"),
+        ("epsilon_window = 1\n", "epsilon_window"),
+    ] {
+        let cfg = load_toml(&format!("{base}\n{learned}{extra}")).unwrap();
+        let err = cfg.validate().expect_err(extra);
+        assert!(err.to_string().contains(key), "{extra}: {err}");
+    }
+}
+
+#[test]
+fn validates_autotare() {
+    let base = base();
+    let cfg = load_toml(&format!("{base}\n[autotare]\n")).unwrap();
+    cfg.validate().unwrap();
+    let a = cfg.autotare.unwrap();
+    assert_eq!((a.plateau_ms, a.max_wait_ms), (500, 60_000));
+
+    let cfg = load_toml(&format!("{base}\n[autotare]\nplateau_g = 0.0\n")).unwrap();
+    let err = cfg.validate().expect_err("zero plateau");
+    assert!(err.to_string().contains("autotare.plateau_g"));
+
+    let cfg = load_toml(&format!(
+        "{base}\n[autotare]\nplateau_ms = 2000\nmax_wait_ms = 1000\n"
+    ))
+    .unwrap();
+    let err = cfg.validate().expect_err("wait shorter than plateau");
+    assert!(err.to_string().contains("autotare.max_wait_ms"));
+}
+
+#[test]
+fn validates_retention() {
+    let base = base();
+    let cfg = load_toml(&format!("{base}\n[retention]\nmax_mb = 512\n")).unwrap();
+    cfg.validate().unwrap();
+
+    let cfg = load_toml(&format!("{base}\n[retention]\n")).unwrap();
+    let err = cfg.validate().expect_err("no limit");
+    assert!(err.to_string().contains("retention needs"));
+
+    let cfg = load_toml(&format!("{base}\n[retention]\nmax_age_days = 0\n")).unwrap();
+    let err = cfg.validate().expect_err("zero age");
+    assert!(err.to_string().contains("retention.max_age_days"));
+}
+
+#[test]
+fn socket_driver_needs_a_socket() {
+    let base = base();
+    let cfg = load_toml(&format!(
+        "{base}\n[scale]\ndriver = \"socket\"\nsocket = \"/tmp/scale.sock\"\n"
+    ))
+    .unwrap();
+    cfg.validate().unwrap();
+
+    let cfg = load_toml(&format!("{base}\n[scale]\ndriver = \"socket\"\n")).unwrap();
+    let err = cfg.validate().expect_err("no socket");
+    assert!(err.to_string().contains("needs scale.socket"));
+
+    let cfg = load_toml(&format!("{base}\n[scale]\nsocket = \" \"\n")).unwrap();
+    let err = cfg.validate().expect_err("empty socket");
+    assert!(err.to_string().contains("scale.socket must not be empty"));
+}
+
+#[test]
+fn validates_bundle() {
+    let base = base();
+    let cfg = load_toml(&format!("{base}\n[bundle]\ndir = \"/var/lib/doser\"\n")).unwrap();
+    cfg.validate().unwrap();
+    assert_eq!(cfg.bundle.unwrap().log_lines, 200);
+
+    let cfg = load_toml(&format!("{base}\n[bundle]\ndir = \"\"\n")).unwrap();
+    let err = cfg.validate().expect_err("empty dir");
+    assert!(err.to_string().contains("bundle.dir"));
+}
+
+#[test]
+fn rejects_empty_lock_paths() {
+    let base = base();
+    let cfg = load_toml(&format!(
+        "{base}\n[runner]\nlock_file = \"/run/doser/motor0.lock\"\n"
+    ))
+    .unwrap();
+    cfg.validate().unwrap();
+    let cfg = load_toml(&format!("{base}\n[runner]\nlock_file = \"\"\n")).unwrap();
+    let err = cfg.validate().expect_err("empty lock file");
+    assert!(err.to_string().contains("runner.lock_file"));
+
+    let cfg = load_toml(&format!("{base}\n[hardware]\nlock_dir = \" \"\n")).unwrap();
+    let err = cfg.validate().expect_err("empty lock dir");
+    assert!(err.to_string().contains("hardware.lock_dir"));
+}
+
+#[test]
+fn shutdown_marker_must_not_be_empty() {
+    let base = base();
+    let cfg = load_toml(&format!("{base}\n[shutdown]\nmarker = \"run.json\"\n")).unwrap();
+    cfg.validate().unwrap();
+
+    let cfg = load_toml(&format!("{base}\n[shutdown]\nmarker = \" \"\n")).unwrap();
+    let err = cfg.validate().expect_err("empty marker");
+    assert!(err.to_string().contains("shutdown.marker"));
+}
+
+#[test]
+fn pin_conflicts_are_reported_together() {
+    let base = format!("{PINS}estop_in = 24\n\n{FILTER}\n[timeouts]\nsample_ms = 150\n");
+    let cfg = load_toml(&format!(
+        "{base}\n[actuators]\nvalve = {{ pin = 5 }}\ngate = {{ pin = 40 }}\n"
+    ))
+    .unwrap();
+    let err = cfg.validate().expect_err("conflicts").to_string();
+    assert!(
+        err.contains("GPIO 5 is used by pins.hx711_dt, actuators.valve.pin"),
+        "{err}"
+    );
+    assert!(
+        err.contains("GPIO 24 is used by pins.motor_dir, pins.estop_in"),
+        "{err}"
+    );
+    assert!(
+        err.contains("actuators.gate.pin = 40 is not a header GPIO"),
+        "{err}"
+    );
+
+    // Line offsets of a named gpiod chip are not header GPIOs.
+    let cfg = load_toml(&format!(
+        "{PINS}chip = \"/dev/gpiochip1\"\n\n{FILTER}\n[timeouts]\nsample_ms = 150\n\n[actuators]\ngate = {{ pin = 40 }}\n"
+    ))
+    .unwrap();
+    cfg.validate().unwrap();
+}
+
+#[test]
+fn pin_presets_fill_pins_and_allow_overrides() {
+    let rest = format!("\n{FILTER}\n[timeouts]\nsample_ms = 150\n");
+    let cfg = load_toml(&format!("[pins]\npreset = \"pi-hx711-hat-v2\"\n{rest}")).unwrap();
+    cfg.validate().unwrap();
+    let p = &cfg.pins;
+    assert_eq!(p.preset.as_deref(), Some("pi-hx711-hat-v2"));
+    assert_eq!(
+        (p.hx711_dt, p.hx711_sck, p.motor_step, p.motor_dir),
+        (5, 6, 23, 24)
+    );
+    assert_eq!((p.motor_en, p.estop_in), (Some(25), Some(12)));
+
+    let cfg = load_toml(&format!(
+        "[pins]\npreset = \"pi-hx711-hat-v2\"\nmotor_dir = 16\nestop_in = 21\n{rest}"
+    ))
+    .unwrap();
+    assert_eq!((cfg.pins.motor_dir, cfg.pins.estop_in), (16, Some(21)));
+    assert_eq!(cfg.pins.motor_step, 23);
+
+    let err = load_toml(&format!("[pins]\npreset = \"no-such-hat\"\n{rest}")).unwrap_err();
+    assert!(err.to_string().contains("unknown pins.preset"), "{err}");
+
+    // Without a preset every driver pin is still required.
+    let err = load_toml(&format!(
+        "[pins]\nhx711_dt = 5\nhx711_sck = 6\nmotor_step = 23\n{rest}"
+    ))
+    .unwrap_err();
+    assert!(err.to_string().contains("motor_dir"), "{err}");
+}
+
+#[test]
+fn preflight_units_self_test_is_validated() {
+    let base = base();
+    let cfg = load_toml(&format!(
+        "{base}\n[preflight]\nzero_band_g = 2.0\ncontainer_g = 48.5\n"
+    ))
+    .unwrap();
+    cfg.validate().unwrap();
+    assert_eq!(cfg.preflight.container_g, Some(48.5));
+
+    for (body, needle) in [
+        ("zero_band_g = 0.0", "preflight.zero_band_g"),
+        ("container_g = 48.5", "needs preflight.zero_band_g"),
+    ] {
+        let cfg = load_toml(&format!("{base}\n[preflight]\n{body}\n")).unwrap();
+        let err = cfg.validate().expect_err(body).to_string();
+        assert!(err.contains(needle), "{err}");
+    }
+}
+
+#[test]
+fn broadcast_needs_an_address_and_a_station() {
+    let base = base();
+    let cfg = load_toml(&format!(
+        "{base}\n[broadcast]\ngroup = \"239.255.42.1:5005\"\nstation = \"st-3\"\n"
+    ))
+    .unwrap();
+    cfg.validate().unwrap();
+    let b = cfg.broadcast.unwrap();
+    assert_eq!((b.interval_ms, b.ttl), (1000, 1));
+
+    for (body, needle) in [
+        (
+            "group = \"239.255.42.1\"\nstation = \"st-3\"",
+            "broadcast.group",
+        ),
+        (
+            "group = \"239.255.42.1:5005\"\nstation = \"\"",
+            "broadcast.station",
+        ),
+        (
+            "group = \"239.255.42.1:5005\"\nstation = \"st-3\"\ninterval_ms = 10",
+            "broadcast.interval_ms",
+        ),
+    ] {
+        let cfg = load_toml(&format!("{base}\n[broadcast]\n{body}\n")).unwrap();
+        let err = cfg.validate().expect_err(body).to_string();
+        assert!(err.contains(needle), "{err}");
+    }
+}
+
+#[test]
+fn telemetry_needs_an_http_endpoint() {
+    let base = base();
+    let cfg = load_toml(&format!(
+        "{base}\n[telemetry]\nendpoint = \"http://tempo:4318/v1/traces\"\n"
+    ))
+    .unwrap();
+    cfg.validate().unwrap();
+    let t = cfg.telemetry.unwrap();
+    assert_eq!((t.service_name.as_str(), t.timeout_ms), ("doser", 3000));
+
+    for (body, needle) in [
+        ("endpoint = \"tempo:4318\"", "telemetry.endpoint"),
+        (
+            "endpoint = \"http://tempo:4318\"\nservice_name = \" \"",
+            "telemetry.service_name",
+        ),
+        (
+            "endpoint = \"http://tempo:4318\"\ntimeout_ms = 0",
+            "telemetry.timeout_ms",
+        ),
+    ] {
+        let cfg = load_toml(&format!("{base}\n[telemetry]\n{body}\n")).unwrap();
+        let err = cfg.validate().expect_err(body).to_string();
+        assert!(err.contains(needle), "{err}");
+    }
+}
+
+#[test]
+fn durations_and_masses_accept_unit_suffixes() {
+    let cfg = load_toml(&format!(
+        "{PINS}\n{FILTER}\n[timeouts]\nsample_ms = \"0.15s\"\n\n[control]\nstable_ms = \"250 ms\"\nepsilon_g = \"80mg\"\nslow_at_g = 2\n\n[safety]\nmax_run_ms = \"2min\"\nmax_overshoot_g = \"0.5g\"\n\n[preflight]\nmax_g = \"1.5kg\"\n"
+    ))
+    .unwrap();
+    cfg.validate().unwrap();
+    assert_eq!(cfg.timeouts.sample_ms, 150);
+    assert_eq!(cfg.control.stable_ms, 250);
+    assert!((cfg.control.epsilon_g - 0.08).abs() < 1e-6);
+    assert_eq!(cfg.control.slow_at_g, 2.0);
+    assert_eq!(cfg.safety.max_run_ms, 120_000);
+    assert_eq!(cfg.safety.max_overshoot_g, 0.5);
+    assert_eq!(cfg.preflight.max_g, Some(1500.0));
+
+    for (body, needle) in [
+        ("[timeouts]\nsample_ms = \"0.08g\"", "duration unit"),
+        ("[timeouts]\nsample_ms = \"150\"", "duration unit"),
+        ("[timeouts]\nsample_ms = \"0.5ms\"", "whole number"),
+        ("[timeouts]\nsample_ms = -5", "milliseconds"),
+        (
+            "[timeouts]\nsample_ms = 150\n[control]\nepsilon_g = \"80ms\"",
+            "mass unit",
+        ),
+        (
+            "[timeouts]\nsample_ms = 150\n[control]\nepsilon_g = \"g\"",
+            "invalid mass",
+        ),
+    ] {
+        let err = load_toml(&format!("{PINS}\n{FILTER}\n{body}\n"))
+            .expect_err(body)
+            .to_string();
+        assert!(err.contains(needle), "{body}: {err}");
+    }
+}
+
+#[test]
+fn unknown_keys_are_listed_with_their_path() {
+    let clean = format!(
+        "strict = true\n{PINS}\n{FILTER}\n[timeouts]\nsensor_ms = 150\n\n[safety.on_abort]\nreverse_steps = 10\n\n[[control.speed_bands]]\nthreshold_g = 1.0\nsps = 500\n"
+    );
+    assert!(load_toml(&clean).unwrap().strict);
+    assert_eq!(
+        doser_config::unknown_keys(&clean).unwrap(),
+        Vec::<String>::new()
+    );
+
+    let typos = format!(
+        "{PINS}colour = \"red\"\n\n{FILTER}\n[timeouts]\nsample_ms = 150\n\n[control]\nepsilom_g = 0.1\n\n[safety.on_abort]\nreverse_step = 10\n\n[preflite]\nenabled = false\n\n[actuators.gate]\npin = 17\nactive_lwo = true\n"
+    );
+    let cfg = load_toml(&typos).unwrap();
+    assert!(!cfg.strict);
+    assert_eq!(cfg.control.epsilon_g, 0.0);
+    assert_eq!(
+        doser_config::unknown_keys(&typos).unwrap(),
+        [
+            "actuators.gate.active_lwo",
+            "control.epsilom_g",
+            "pins.colour",
+            "preflite",
+            "safety.on_abort.reverse_step"
+        ]
+    );
+}
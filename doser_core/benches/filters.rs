@@ -0,0 +1,82 @@
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use doser_core::filter::{
+    EmaStage, FilterStage, MedianStage, MovingAverageStage, TrimmedMeanStage,
+    WeightedMovingAverageStage,
+};
+
+// Synthetic weight trace in centigrams: a ramp with white noise and occasional spikes
+fn synth_trace(n: usize, seed: u32) -> Vec<i32> {
+    let mut state = seed.max(1);
+    let mut next = || {
+        let mut x = state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        state = x;
+        x
+    };
+    (0..n)
+        .map(|i| {
+            let noise = (next() % 41) as i32 - 20;
+            let spike = if next() % 100 == 0 { 2_000 } else { 0 };
+            (i as i32) * 3 + noise + spike
+        })
+        .collect()
+}
+
+fn run(stage: &mut dyn FilterStage, trace: &[i32]) -> i32 {
+    stage.reset();
+    trace.iter().fold(0, |_, &x| stage.process(x))
+}
+
+pub fn bench_filters(c: &mut Criterion) {
+    let mut g = c.benchmark_group("filters");
+    // Same knobs as the predictor bench:
+    //   BENCH_SAMPLE_SIZE=10 BENCH_MEAS_MS=50 cargo bench -p doser_core --bench filters
+    let sample_size = std::env::var("BENCH_SAMPLE_SIZE")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(50);
+    // Criterion needs at least 10 samples.
+    g.sample_size(sample_size.max(10));
+    if let Ok(ms) = std::env::var("BENCH_MEAS_MS")
+        && let Ok(ms_u64) = ms.parse::<u64>()
+    {
+        g.measurement_time(std::time::Duration::from_millis(ms_u64));
+    }
+
+    let trace = synth_trace(10_000, 0xC0FFEE);
+    for window in [5usize, 15] {
+        let stages: Vec<(String, Box<dyn FilterStage>)> = vec![
+            (
+                format!("ma_{window}"),
+                Box::new(MovingAverageStage::new(window)),
+            ),
+            (
+                format!("wma_{window}"),
+                Box::new(WeightedMovingAverageStage::new(window)),
+            ),
+            (
+                format!("trimmed_{window}_{}", window / 4),
+                Box::new(TrimmedMeanStage::new(window, window / 4)),
+            ),
+            (
+                format!("median_{window}"),
+                Box::new(MedianStage::new(window)),
+            ),
+        ];
+        for (name, mut stage) in stages {
+            g.bench_function(name, |b| {
+                b.iter(|| black_box(run(stage.as_mut(), black_box(&trace))))
+            });
+        }
+    }
+    let mut ema = EmaStage::new(0.3);
+    g.bench_function("ema_0.3", |b| {
+        b.iter(|| black_box(run(&mut ema, black_box(&trace))))
+    });
+    g.finish();
+}
+
+criterion_group!(filters, bench_filters);
+criterion_main!(filters);
@@ -0,0 +1,35 @@
+//! Await a dose from async code.
+//!
+//! The control loop blocks on sensor reads, so it runs on its own thread and
+//! hands the result back through a oneshot channel. With tokio, the same
+//! shape is `tokio::task::spawn_blocking(move || runner::run_report(..))`.
+//!
+//! Run with `cargo run -p doser_core --example async_dose`.
+
+mod common;
+
+use doser_core::runner::{self, DoseReport};
+use futures::channel::oneshot;
+
+/// Start a dose on a worker thread and resolve when it finishes.
+async fn dose(target_g: f32) -> eyre::Result<DoseReport> {
+    let (tx, rx) = oneshot::channel();
+    std::thread::spawn(move || {
+        let (scale, motor) = common::bench();
+        let _ = tx.send(runner::run_report(
+            scale,
+            motor,
+            None,
+            common::run_params(target_g),
+        ));
+    });
+    rx.await?
+}
+
+fn main() -> eyre::Result<()> {
+    futures::executor::block_on(async {
+        let report = dose(4.0).await?;
+        println!("dosed {:.2} g", report.final_g);
+        Ok(())
+    })
+}
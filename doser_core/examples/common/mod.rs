@@ -0,0 +1,105 @@
+//! Toy bench shared by the examples: an auger that drops material onto a
+//! scale while the motor turns. A real integration implements the same two
+//! traits over its own drivers; see `custom_backend.rs`.
+#![allow(dead_code)]
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use doser_traits::{Motor, Scale};
+
+/// Grams delivered per motor step.
+const G_PER_STEP: f32 = 0.002;
+/// ADC conversion time (the scale reads at 100 Hz).
+const SAMPLE_PERIOD: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Default)]
+struct Bench {
+    grams: f32,
+    running: bool,
+    sps: u32,
+}
+
+/// Load cell on the bench. Reports centigram counts, which matches the
+/// default [`doser_core::Calibration`].
+pub struct BenchScale(Arc<Mutex<Bench>>);
+
+/// Auger motor on the bench.
+pub struct BenchMotor(Arc<Mutex<Bench>>);
+
+/// A fresh bench with an empty cup.
+pub fn bench() -> (BenchScale, BenchMotor) {
+    let b = Arc::new(Mutex::new(Bench::default()));
+    (BenchScale(b.clone()), BenchMotor(b))
+}
+
+fn lock(
+    b: &Mutex<Bench>,
+) -> Result<std::sync::MutexGuard<'_, Bench>, Box<dyn Error + Send + Sync>> {
+    b.lock().map_err(|_| "bench state poisoned".into())
+}
+
+impl Scale for BenchScale {
+    fn read(&mut self, _timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        std::thread::sleep(SAMPLE_PERIOD);
+        let mut b = lock(&self.0)?;
+        if b.running {
+            b.grams += b.sps as f32 * G_PER_STEP * SAMPLE_PERIOD.as_secs_f32();
+        }
+        Ok((b.grams * 100.0).round() as i32)
+    }
+}
+
+impl Motor for BenchMotor {
+    fn set_speed(&mut self, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        lock(&self.0)?.sps = sps;
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        lock(&self.0)?.running = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        lock(&self.0)?.running = false;
+        Ok(())
+    }
+}
+
+/// Run parameters for a bench dose: library defaults plus a watchdog tuned
+/// to the bench's flow rate.
+pub fn run_params(target_g: f32) -> doser_core::runner::RunParams {
+    use doser_core::runner::{RunParams, SamplingMode};
+    use doser_core::{
+        ControlCfg, FilterCfg, OverrunPolicy, PreflightCfg, ReadRetryCfg, SafeState, SafetyCfg,
+        Timeouts, WarmupCfg,
+    };
+    RunParams {
+        filter: FilterCfg::default(),
+        control: ControlCfg::default(),
+        safety: SafetyCfg {
+            max_run_ms: 30_000,
+            no_progress_epsilon_g: 0.02,
+            no_progress_ms: 2_000,
+            ..SafetyCfg::default()
+        },
+        timeouts: Timeouts::default(),
+        read_retry: ReadRetryCfg::default(),
+        calibration: None,
+        target_g,
+        estop_debounce_n: 2,
+        prefer_timeout_first: false,
+        mode: SamplingMode::Direct,
+        predictor: None,
+        overrun: OverrunPolicy::default(),
+        safe_state: SafeState::default(),
+        warmup: WarmupCfg::default(),
+        progress: Default::default(),
+        status: Default::default(),
+        preflight: PreflightCfg::default(),
+        shutdown: None,
+        clock: Default::default(),
+    }
+}
@@ -0,0 +1,34 @@
+//! Drive a dose step by step over your own `Scale` and `Motor` implementations.
+//!
+//! The bench in `common` implements both traits; swap in your ADC and
+//! stepper drivers. `Doser::step()` reads one sample and updates the motor,
+//! so the loop runs at the scale's sample rate.
+//!
+//! Run with `cargo run -p doser_core --example custom_backend`.
+
+mod common;
+
+use doser_core::{Doser, DosingStatus};
+
+fn main() -> eyre::Result<()> {
+    let (scale, motor) = common::bench();
+    let mut doser = Doser::builder()
+        .with_scale(scale)
+        .with_motor(motor)
+        .with_target_grams(5.0)
+        .build()?;
+
+    doser.begin();
+    loop {
+        match doser.step()? {
+            DosingStatus::Running => continue,
+            DosingStatus::Complete => break,
+            DosingStatus::Aborted(e) => {
+                doser.motor_stop()?;
+                return Err(e.into());
+            }
+        }
+    }
+    println!("dosed {:.2} g", doser.last_weight());
+    Ok(())
+}
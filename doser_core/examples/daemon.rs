@@ -0,0 +1,55 @@
+//! Embed the doser in a long-running service.
+//!
+//! Jobs arrive on a channel (stand-in for a socket or HTTP handler) and run one
+//! at a time through `runner::run_report`, which adds warm-up, pre-flight and
+//! the sampling/watchdog orchestration around the control loop. A shared
+//! shutdown flag (set from a signal handler in a real service) stops a dose
+//! in progress.
+//!
+//! Run with `cargo run -p doser_core --example daemon`.
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use doser_core::runner::{self, ShutdownFlag};
+
+fn serve(jobs: mpsc::Receiver<f32>, shutdown: ShutdownFlag) {
+    for target_g in jobs {
+        if shutdown.load(Ordering::Relaxed) {
+            break;
+        }
+        // Backends are moved into each run; reopen them (or hand out cheap
+        // handles to a shared driver) per job.
+        let (scale, motor) = common::bench();
+        let mut params = common::run_params(target_g);
+        params.shutdown = Some(shutdown.clone());
+        match runner::run_report(scale, motor, None, params) {
+            Ok(report) => println!(
+                "job {target_g:.1} g: dosed {:.2} g in {} ms",
+                report.final_g,
+                report.phases.total_ms()
+            ),
+            Err(e) => eprintln!("job {target_g:.1} g failed: {e}"),
+        }
+    }
+}
+
+fn main() {
+    let shutdown: ShutdownFlag = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+    let worker = {
+        let shutdown = shutdown.clone();
+        thread::spawn(move || serve(rx, shutdown))
+    };
+
+    for target_g in [2.0, 3.5] {
+        let _ = tx.send(target_g);
+    }
+    // Closing the channel lets the worker drain the queue and exit.
+    drop(tx);
+    let _ = worker.join();
+}
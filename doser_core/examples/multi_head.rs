@@ -0,0 +1,35 @@
+//! Run several dosing heads at once.
+//!
+//! Each head owns its scale and motor and runs on its own thread; nothing in
+//! the controller is global, so heads are independent. One E-stop input is
+//! shared by all heads.
+//!
+//! Run with `cargo run -p doser_core --example multi_head`.
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use doser_core::runner;
+
+fn main() {
+    let estop = Arc::new(AtomicBool::new(false));
+    let targets = [("head A", 3.0_f32), ("head B", 4.5)];
+
+    thread::scope(|s| {
+        for (name, target_g) in targets {
+            let estop = estop.clone();
+            s.spawn(move || {
+                let (scale, motor) = common::bench();
+                let check: Box<dyn Fn() -> bool + Send + Sync> =
+                    Box::new(move || estop.load(Ordering::Acquire));
+                match runner::run_report(scale, motor, Some(check), common::run_params(target_g)) {
+                    Ok(r) => println!("{name}: dosed {:.2} g (target {target_g} g)", r.final_g),
+                    Err(e) => eprintln!("{name}: {e}"),
+                }
+            });
+        }
+    });
+}
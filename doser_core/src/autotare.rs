@@ -0,0 +1,99 @@
+//! Auto-tare on container placement.
+//!
+//! Before a dose, wait until something at least `min_container_g` heavy sits
+//! on the scale and its readings stay within `plateau_g` of each other for
+//! `plateau_ms`, then take the plateau as the new zero. Time is counted in
+//! readings at the scale's sample rate (each read blocks until the next
+//! sample), so the wait behaves the same on a virtual clock.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use doser_traits::Scale;
+
+use crate::calibration::Calibration;
+use crate::config::AutoTareCfg;
+use crate::hw_error::map_hw_error;
+use crate::resume::retare;
+
+/// A settled container.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plateau {
+    /// Mean of the plateau readings (raw counts)
+    pub raw: i32,
+    /// Weight of the container against the old zero (grams)
+    pub container_g: f32,
+    /// Largest minus smallest plateau reading (grams)
+    pub spread_g: f32,
+    /// Readings taken until the plateau was found
+    pub samples: u64,
+}
+
+impl Plateau {
+    /// `calibration` zeroed on the container.
+    pub fn tare(&self, calibration: &Calibration) -> Calibration {
+        retare(calibration, self.raw)
+    }
+}
+
+/// Wait for a container to be placed and settle. Fails when none settles
+/// within `cfg.max_wait_ms`; read errors are returned mapped like control-loop
+/// reads.
+pub fn wait_for_container<S: Scale + ?Sized>(
+    scale: &mut S,
+    calibration: &Calibration,
+    cfg: &AutoTareCfg,
+    sample_rate_hz: u32,
+    read_timeout: Duration,
+) -> crate::error::Result<Plateau> {
+    let per_ms = |ms: u64| {
+        (ms * u64::from(sample_rate_hz.max(1)))
+            .div_ceil(1000)
+            .max(1)
+    };
+    let window = usize::try_from(per_ms(cfg.plateau_ms))
+        .unwrap_or(usize::MAX)
+        .max(2);
+    let max_samples = per_ms(cfg.max_wait_ms);
+    let mut recent: VecDeque<i32> = VecDeque::with_capacity(window);
+    tracing::info!(?cfg, "waiting for a container");
+
+    for samples in 1..=max_samples {
+        let raw = scale
+            .read(read_timeout)
+            .map_err(|e| eyre::Report::new(map_hw_error(&*e)))?;
+        if recent.len() == window {
+            recent.pop_front();
+        }
+        recent.push_back(raw);
+        if recent.len() < window {
+            continue;
+        }
+        let grams = recent.iter().map(|&r| calibration.to_grams(r));
+        let (lo, hi) = grams.fold((f32::MAX, f32::MIN), |(lo, hi), g| (lo.min(g), hi.max(g)));
+        if hi - lo > cfg.plateau_g || lo < cfg.min_container_g {
+            continue;
+        }
+        let mean = recent.iter().map(|&r| i64::from(r)).sum::<i64>() / recent.len() as i64;
+        let raw = mean as i32;
+        let p = Plateau {
+            raw,
+            container_g: calibration.to_grams(raw),
+            spread_g: hi - lo,
+            samples,
+        };
+        tracing::info!(
+            container_g = p.container_g,
+            samples,
+            "container settled; tared"
+        );
+        return Ok(p);
+    }
+    eyre::bail!(
+        "no container settled within {} ms (at least {} g, within {} g for {} ms)",
+        cfg.max_wait_ms,
+        cfg.min_container_g,
+        cfg.plateau_g,
+        cfg.plateau_ms
+    )
+}
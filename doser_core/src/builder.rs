@@ -8,13 +8,16 @@ use std::marker::PhantomData;
 use std::sync::Arc;
 
 use doser_traits::clock::{Clock, MonotonicClock};
+use doser_traits::pacing::{OverrunPolicy, Pacer};
 
 use crate::calibration::Calibration;
 use crate::config::*;
 use crate::core::DoserCore;
 use crate::error::{BuildError, Result};
-use crate::fixed_point::{gain_to_scaled_cg_per_count, grams_to_cg, quantize_to_cg_i32};
-use crate::status::DosingStatus;
+use crate::filter::FilterPipeline;
+use crate::fixed_point::{ShiftedGain, grams_to_cg, quantize_to_cg_i32};
+use crate::safe_state::SafeState;
+use crate::status::{DosingStatus, PhaseTimings};
 
 // ── Public dynamic-dispatch wrapper ──────────────────────────────────────────
 
@@ -47,6 +50,16 @@ impl Doser {
         self.inner.last_weight()
     }
 
+    /// Last display weight in grams (see `FilterCfg::display`).
+    pub fn display_weight(&self) -> f32 {
+        self.inner.display_weight()
+    }
+
+    /// The control weight with its uncertainty.
+    pub fn current_weight(&self) -> crate::Weight {
+        self.inner.current_weight()
+    }
+
     /// Optionally set the tare baseline in raw counts.
     pub fn set_tare_counts(&mut self, zero_counts: i32) {
         self.inner.set_tare_counts(zero_counts);
@@ -62,6 +75,17 @@ impl Doser {
         self.inner.step_from_raw(raw)
     }
 
+    /// One control tick between samples (see [`DoserCore::step_hold`]).
+    pub fn step_hold(&mut self) -> Result<DosingStatus> {
+        self.inner.step_hold()
+    }
+
+    /// Pace the loop at `hz` rather than the sample rate (see
+    /// [`DoserCore::set_loop_hz`]).
+    pub fn set_loop_hz(&mut self, hz: u32) {
+        self.inner.set_loop_hz(hz);
+    }
+
     /// Reset per-run state. Call before a new dose.
     pub fn begin(&mut self) {
         self.inner.begin();
@@ -77,6 +101,22 @@ impl Doser {
         self.inner.motor_stop()
     }
 
+    /// Halt the motor: stop and de-energize at once (best-effort).
+    pub fn motor_halt(&mut self) -> Result<()> {
+        self.inner.motor_halt()
+    }
+
+    /// Suggested remediation for the last abort (see [`crate::diagnosis`]).
+    pub fn remediation(&self) -> Option<crate::diagnosis::Remediation> {
+        self.inner.remediation()
+    }
+
+    /// The report for `err`, an abort of this dose, with its remediation
+    /// attached as context.
+    pub fn abort_report(&self, err: crate::error::DoserError) -> eyre::Report {
+        self.inner.abort_report(err)
+    }
+
     /// Telemetry: last slope EMA in grams per second (approx), if available.
     pub fn last_slope_ema_gps(&self) -> Option<f32> {
         self.inner
@@ -93,6 +133,82 @@ impl Doser {
     pub fn early_stop_at_g(&self) -> Option<f32> {
         self.inner.early_stop_at_cg.map(|cg| (cg as f32) * 0.01)
     }
+
+    /// Stable-hold weight in grams (mean over the final settle window), if settled.
+    pub fn hold_weight(&self) -> Option<f32> {
+        self.inner.hold_weight()
+    }
+
+    /// Telemetry: shortfall in grams when an early stop settled below target, if any.
+    pub fn undershoot_g(&self) -> Option<f32> {
+        self.inner.undershoot_g()
+    }
+
+    /// Telemetry: motor steps commanded since `begin()`.
+    pub fn motor_steps(&self) -> u64 {
+        self.inner.motor_steps()
+    }
+
+    /// Telemetry: the motor backend's stepping counters, if it keeps them.
+    pub fn motor_diagnostics(&self) -> Option<doser_traits::MotorDiagSnapshot> {
+        self.inner.motor_diagnostics()
+    }
+
+    /// Telemetry: weight gained since the motor last stopped, in grams.
+    pub fn coast_g(&self) -> Option<f32> {
+        self.inner.coast_g()
+    }
+
+    /// Telemetry: average loop wake-up jitter (µs) over the last full window.
+    pub fn avg_jitter_us(&self) -> u32 {
+        self.inner.avg_jitter_us()
+    }
+
+    /// Telemetry: control iterations that overran their period this dose.
+    pub fn loop_overruns(&self) -> u64 {
+        self.inner.loop_overruns()
+    }
+
+    /// Run the abort safe-state sequence (at most once per dose).
+    pub fn enter_safe_state(&mut self, err: &crate::error::DoserError) {
+        self.inner.enter_safe_state(err);
+    }
+
+    /// Telemetry: time spent in each dosing phase this dose.
+    pub fn phase_timings(&self) -> PhaseTimings {
+        self.inner.phase_timings()
+    }
+
+    /// Telemetry: timed-out scale reads retried this dose.
+    pub fn read_retries(&self) -> u64 {
+        self.inner.read_retries()
+    }
+
+    /// Telemetry: timed-out scale reads that a retry recovered this dose.
+    pub fn reads_recovered(&self) -> u64 {
+        self.inner.reads_recovered()
+    }
+
+    /// Telemetry: the motor's approaches this dose, oldest first (at most 16:
+    /// the first ones and the latest).
+    pub fn attempts(&self) -> &[crate::Attempt] {
+        self.inner.attempts()
+    }
+
+    /// Telemetry: how many approaches started this dose.
+    pub fn attempt_count(&self) -> u32 {
+        self.inner.attempt_count()
+    }
+
+    /// Phase of the latest step (`None` before the first one).
+    pub fn phase(&self) -> Option<crate::DosePhase> {
+        self.inner.phase()
+    }
+
+    /// Speed band of the latest step; `None` while the motor is stopped.
+    pub fn current_band(&self) -> Option<crate::SpeedBand> {
+        self.inner.current_band()
+    }
 }
 
 // ── Type-state markers ───────────────────────────────────────────────────────
@@ -116,6 +232,10 @@ pub struct DoserBuilder<S, M, T> {
     clock: Option<Box<dyn Clock + Send + Sync>>,
     estop_debounce_n: Option<u8>,
     predictor: Option<PredictorCfg>,
+    pipeline: Option<FilterPipeline>,
+    overrun_policy: Option<OverrunPolicy>,
+    safe_state: Option<SafeState>,
+    read_retry: Option<ReadRetryCfg>,
     _s: PhantomData<S>,
     _m: PhantomData<M>,
     _t: PhantomData<T>,
@@ -137,6 +257,10 @@ impl Default for DoserBuilder<Missing, Missing, Missing> {
             clock: None,
             estop_debounce_n: None,
             predictor: None,
+            pipeline: None,
+            overrun_policy: None,
+            safe_state: None,
+            read_retry: None,
             _s: PhantomData,
             _m: PhantomData,
             _t: PhantomData,
@@ -148,6 +272,25 @@ impl Default for DoserBuilder<Missing, Missing, Missing> {
 ///
 /// This is the single source of truth for validation and construction,
 /// used by both `DoserBuilder::try_build()` and `build_doser()`.
+/// Warn when the completion tolerance is finer than the scale resolves: the
+/// dose then settles on noise rather than on the target.
+pub(crate) fn warn_if_below_resolution(
+    resolution_counts: Option<u32>,
+    calibration: &Calibration,
+    epsilon_g: f32,
+) {
+    if let Some(counts) = resolution_counts {
+        let resolution_g = calibration.counts_to_g(counts);
+        if epsilon_g > 0.0 && epsilon_g < resolution_g {
+            tracing::warn!(
+                epsilon_g,
+                resolution_g,
+                "control.epsilon_g is below the scale resolution"
+            );
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn validate_and_build<S: doser_traits::Scale, M: doser_traits::Motor>(
     scale: S,
@@ -162,6 +305,7 @@ fn validate_and_build<S: doser_traits::Scale, M: doser_traits::Motor>(
     predictor: PredictorCfg,
     clock: Option<Box<dyn Clock + Send + Sync>>,
     estop_debounce_n: u8,
+    pipeline: Option<FilterPipeline>,
 ) -> Result<DoserCore<S, M>> {
     // ── Validation ───────────────────────────────────────────────────────────
     if !(0.1..=5000.0).contains(&target_g) {
@@ -221,6 +365,25 @@ fn validate_and_build<S: doser_traits::Scale, M: doser_traits::Motor>(
             "predictor window must be <= 10000",
         )));
     }
+    if !control.band_hysteresis_g.is_finite() || control.band_hysteresis_g < 0.0 {
+        return Err(eyre::Report::new(BuildError::InvalidConfig(
+            "band hysteresis must be finite and >= 0",
+        )));
+    }
+    {
+        // A delta wider than a band step would hold the faster speed near target.
+        let mut speeds: Vec<u32> = control.speed_bands.iter().map(|&(_, sps)| sps).collect();
+        speeds.sort_unstable();
+        speeds.dedup();
+        if speeds
+            .windows(2)
+            .any(|w| w[1] - w[0] < control.min_speed_delta_sps)
+        {
+            return Err(eyre::Report::new(BuildError::InvalidConfig(
+                "min speed delta must not exceed the smallest speed band step",
+            )));
+        }
+    }
     // Validate speed band entries
     for (thr_g, sps) in &control.speed_bands {
         if !thr_g.is_finite() {
@@ -241,8 +404,13 @@ fn validate_and_build<S: doser_traits::Scale, M: doser_traits::Motor>(
     }
 
     // ── Precompute ───────────────────────────────────────────────────────────
-    let ma_cap = filter.ma_window.max(1);
-    let med_cap = filter.median_window.max(1);
+    let pipeline = pipeline.unwrap_or_else(|| FilterPipeline::from_cfg(&filter));
+    let fine_pipeline = filter.fine.as_deref().map(FilterPipeline::from_cfg);
+    let display_pipeline = filter.display.as_deref().map(FilterPipeline::from_cfg);
+    let fast_pipeline = match predictor.slope_source {
+        SlopeSource::Fast => Some(FilterPipeline::prefilter(&filter)),
+        SlopeSource::Filtered => None,
+    };
 
     let clock: Arc<dyn Clock + Send + Sync> = match clock {
         Some(b) => Arc::from(b),
@@ -267,13 +435,17 @@ fn validate_and_build<S: doser_traits::Scale, M: doser_traits::Motor>(
     let max_overshoot_cg = grams_to_cg(safety.max_overshoot_g);
     let no_progress_epsilon_cg = grams_to_cg(safety.no_progress_epsilon_g);
     let slow_at_cg = grams_to_cg(control.slow_at_g);
+    let band_hysteresis_cg = grams_to_cg(control.band_hysteresis_g);
     let speed_bands_cg: Vec<(i32, u32)> = control
         .speed_bands
         .iter()
         .map(|(g, sps)| (grams_to_cg(*g), *sps))
         .collect();
 
-    let cal_gain_scaled = gain_to_scaled_cg_per_count(calibration.gain_g_per_count);
+    warn_if_below_resolution(scale.resolution_counts(), &calibration, control.epsilon_g);
+    let raw_range = scale.raw_range();
+
+    let cal_gain = ShiftedGain::from_g_per_count(calibration.gain_g_per_count);
     let cal_offset_cg = quantize_to_cg_i32(calibration.offset_g);
 
     Ok(DoserCore {
@@ -283,19 +455,31 @@ fn validate_and_build<S: doser_traits::Scale, M: doser_traits::Motor>(
         control,
         safety,
         timeouts,
+        read_retry: ReadRetryCfg::default(),
+        read_retries: 0,
+        reads_recovered: 0,
+        raw_range,
         calibration,
         target_cg,
         clock,
         epoch,
         last_weight_cg: 0,
         settled_since_ms: None,
+        settle_sum_cg: 0,
+        settle_samples: 0,
         start_ms: now,
-        ma_buf: VecDeque::with_capacity(ma_cap),
-        med_buf: VecDeque::with_capacity(med_cap),
-        tmp_med_buf: Vec::with_capacity(med_cap),
-        ema_prev_cg: None,
+        pipeline,
+        fine_pipeline,
+        fine_filter_active: false,
+        display_pipeline,
+        display_weight_cg: 0,
+        noise: Default::default(),
+        flow: Default::default(),
+        remediation: None,
+        recent_steps: VecDeque::with_capacity(crate::diagnosis::TRACE_STEPS),
+        fast_pipeline,
         period_us,
-        cal_gain_scaled,
+        cal_gain,
         cal_offset_cg,
         slow_at_cg,
         epsilon_cg,
@@ -311,11 +495,29 @@ fn validate_and_build<S: doser_traits::Scale, M: doser_traits::Motor>(
         estop_count: 0,
         predictor,
         pred_hist: VecDeque::with_capacity(8),
+        commanded_sps: 0,
         pred_latency_ms,
         speed_bands_cg,
+        band_hysteresis_cg,
+        band_idx: None,
+        band_since_ms: now,
+        hold_from: None,
+        hold_slope_cg_per_ms: 0.0,
+        attempts: Vec::with_capacity(crate::core::ATTEMPT_RECORDS),
+        attempts_started: 0,
+        pacer: Pacer::new().with_overrun_policy(OverrunPolicy::SkipSleep),
+        overruns_at_begin: 0,
+        phase: None,
+        phase_mark_ms: now,
+        phase_timings: PhaseTimings::default(),
+        safe_state: SafeState::default(),
+        safe_state_done: false,
         last_slope_ema_cg_per_ms: None,
         last_inflight_cg: None,
         early_stop_at_cg: None,
+        stopped_at_cg: None,
+        early_stop_ms: None,
+        undershoot_cg: None,
     })
 }
 
@@ -332,7 +534,7 @@ impl<S, M, T> DoserBuilder<S, M, T> {
             .target_g
             .ok_or_else(|| eyre::Report::new(BuildError::MissingTarget))?;
 
-        let inner = validate_and_build(
+        let mut inner = validate_and_build(
             scale,
             motor,
             self.filter.unwrap_or_default(),
@@ -345,7 +547,17 @@ impl<S, M, T> DoserBuilder<S, M, T> {
             self.predictor.unwrap_or_default(),
             self.clock,
             self.estop_debounce_n.unwrap_or(2),
+            self.pipeline,
         )?;
+        if let Some(policy) = self.overrun_policy {
+            inner.set_overrun_policy(policy);
+        }
+        if let Some(safe_state) = self.safe_state {
+            inner.set_safe_state(safe_state);
+        }
+        if let Some(read_retry) = self.read_retry {
+            inner.set_read_retry(read_retry);
+        }
 
         Ok(Doser { inner })
     }
@@ -404,6 +616,28 @@ impl<S, M, T> DoserBuilder<S, M, T> {
         self.predictor = Some(predictor);
         self
     }
+    /// Replace the filter pipeline derived from `FilterCfg` with a custom one.
+    pub fn with_filter_pipeline(mut self, pipeline: FilterPipeline) -> Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+    /// How the control loop handles an iteration that overruns its period
+    /// (default: [`OverrunPolicy::SkipSleep`]).
+    pub fn with_overrun_policy(mut self, policy: OverrunPolicy) -> Self {
+        self.overrun_policy = Some(policy);
+        self
+    }
+    /// Safe-state sequence to run when a dose aborts (default: stop only).
+    pub fn with_safe_state(mut self, safe_state: SafeState) -> Self {
+        self.safe_state = Some(safe_state);
+        self
+    }
+    /// Retries of timed-out scale reads before a dose aborts (default:
+    /// [`ReadRetryCfg::default`]).
+    pub fn with_read_retry(mut self, read_retry: ReadRetryCfg) -> Self {
+        self.read_retry = Some(read_retry);
+        self
+    }
     /// Provide a custom clock implementation; defaults to `MonotonicClock` when not provided.
     pub fn with_clock(mut self, clock: Box<dyn Clock + Send + Sync>) -> Self {
         self.clock = Some(clock);
@@ -433,6 +667,10 @@ impl<M, T> DoserBuilder<Missing, M, T> {
             clock: self.clock,
             estop_debounce_n: self.estop_debounce_n,
             predictor: self.predictor,
+            pipeline: self.pipeline,
+            overrun_policy: self.overrun_policy,
+            safe_state: self.safe_state,
+            read_retry: self.read_retry,
             _s: PhantomData,
             _m: PhantomData,
             _t: PhantomData,
@@ -456,6 +694,10 @@ impl<S, T> DoserBuilder<S, Missing, T> {
             clock: self.clock,
             estop_debounce_n: self.estop_debounce_n,
             predictor: self.predictor,
+            pipeline: self.pipeline,
+            overrun_policy: self.overrun_policy,
+            safe_state: self.safe_state,
+            read_retry: self.read_retry,
             _s: PhantomData,
             _m: PhantomData,
             _t: PhantomData,
@@ -479,6 +721,10 @@ impl<S, M> DoserBuilder<S, M, Missing> {
             clock: self.clock,
             estop_debounce_n: self.estop_debounce_n,
             predictor: self.predictor,
+            pipeline: self.pipeline,
+            overrun_policy: self.overrun_policy,
+            safe_state: self.safe_state,
+            read_retry: self.read_retry,
             _s: PhantomData,
             _m: PhantomData,
             _t: PhantomData,
@@ -531,5 +777,6 @@ where
         predictor.unwrap_or_default(),
         clock,
         estop_debounce_n.unwrap_or(2),
+        None,
     )
 }
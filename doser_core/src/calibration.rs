@@ -3,7 +3,7 @@
 //! The core representation uses centigrams (cg, 1 cg = 0.01 g) with `i32`
 //! fixed-point arithmetic for deterministic, allocation-free control loop math.
 
-use crate::fixed_point::{cg_from_delta_scaled, gain_to_scaled_cg_per_count, quantize_to_cg_i32};
+use crate::fixed_point::{ShiftedGain, quantize_to_cg_i32};
 
 /// Simple linear calibration from raw scale counts to grams.
 ///
@@ -23,6 +23,17 @@ impl Calibration {
         self.gain_g_per_count * ((raw - self.zero_counts) as f32) + self.offset_g
     }
 
+    /// Raw counts that read as `grams` (the inverse of [`to_grams`](Self::to_grams)).
+    pub fn to_counts(&self, grams: f32) -> i32 {
+        ((grams - self.offset_g) / self.gain_g_per_count).round() as i32 + self.zero_counts
+    }
+
+    /// Grams spanned by `counts`, e.g. a scale's
+    /// [`resolution_counts`](doser_traits::Scale::resolution_counts).
+    pub fn counts_to_g(&self, counts: u32) -> f32 {
+        self.gain_g_per_count.abs() * counts as f32
+    }
+
     /// Convert raw counts directly to centigrams (cg, where 1 cg = 0.01 g)
     /// using integer fixed-point arithmetic.
     ///
@@ -31,19 +42,20 @@ impl Calibration {
     ///   centigrams = round(100 * grams)
     ///
     /// Implementation (fixed-point):
-    /// - gain_scaled = round(100 * gain_g_per_count * GAIN_SCALE)  (cg/count × GAIN_SCALE)
+    /// - gain = round(100 * gain_g_per_count * 2^shift)  (cg/count × 2^shift)
     /// - offset_cg = round(100 * offset_g)
-    /// - result_cg = round((raw - zero_counts) * gain_scaled / GAIN_SCALE) + offset_cg
+    /// - result_cg = round((raw - zero_counts) * gain / 2^shift) + offset_cg
     ///
     /// Rationale:
     /// - Avoids per-sample floating-point math in the control loop.
     /// - Keeps all controller thresholds and comparisons in one integer unit (cg).
-    /// - The gain is stored as a *scaled* integer (see `fixed_point::GAIN_SCALE`)
-    ///   so that realistic sub-centigram-per-count gains are not rounded to zero.
+    /// - The gain is stored as a *shifted* integer (see `fixed_point::ShiftedGain`),
+    ///   the shift chosen for this gain, so that fine sub-centigram-per-count
+    ///   gains keep their relative precision instead of being rounded off.
     ///   The multiply uses an `i128` intermediate and the result saturates to `i32`.
     ///
     /// Rounding and error bounds:
-    /// - `gain_g_per_count` is preserved to ~6 extra decimal digits; `offset_g`
+    /// - `gain_g_per_count` is preserved to 31 significant bits; `offset_g`
     ///   is rounded to the nearest centigram. The returned value is within ~1 cg
     ///   of `round(100 * to_grams(raw))` for stable parameters.
     /// - Non-finite parameters (NaN/±Inf) are treated as 0.
@@ -57,9 +69,8 @@ impl Calibration {
     ///   and `raw = 123`, then `to_cg(123) == 123` (i.e., 1.23 g).
     pub fn to_cg(&self, raw: i32) -> i32 {
         let delta = (raw as i64) - (self.zero_counts as i64);
-        let gain_scaled = gain_to_scaled_cg_per_count(self.gain_g_per_count);
-        let offset_cg = quantize_to_cg_i32(self.offset_g);
-        cg_from_delta_scaled(delta, gain_scaled, offset_cg)
+        let gain = ShiftedGain::from_g_per_count(self.gain_g_per_count);
+        gain.cg_from_delta(delta, quantize_to_cg_i32(self.offset_g))
     }
 }
 
@@ -3,6 +3,8 @@
 //! These are the runtime configuration structs used by `DoserCore`.
 //! They are separate from the TOML-deserialized config in `doser_config`.
 
+use crate::calibration::Calibration;
+
 /// Filter configuration for signal conditioning.
 #[derive(Debug, Clone)]
 pub struct FilterCfg {
@@ -13,8 +15,20 @@ pub struct FilterCfg {
     /// Sampling rate in Hz (informational; drives loop period).
     pub sample_rate_hz: u32,
     /// EMA smoothing factor; when > 0, EMA is used instead of moving average.
-    /// Range: (0.0, 1.0]. 0.0 disables EMA and uses moving average when `ma_window > 1`.
+    /// Range: (0.0, 1.0]. 0.0 disables EMA and uses `smoothing` when `ma_window > 1`.
     pub ema_alpha: f32,
+    /// Averaging over the `ma_window` samples when EMA is off.
+    pub smoothing: Smoothing,
+    /// Samples dropped from each end of the sorted window by
+    /// [`Smoothing::TrimmedMean`]; ignored otherwise.
+    pub trim: usize,
+    /// Filter for the fine and settle phases (its `sample_rate_hz` and `fine`
+    /// are ignored). `None` uses this one throughout the dose.
+    pub fine: Option<Box<FilterCfg>>,
+    /// Filter for the display weight, run beside the control filter on the
+    /// unfiltered weight (its `sample_rate_hz`, `fine` and `display` are
+    /// ignored). `None` displays the control weight.
+    pub display: Option<Box<FilterCfg>>,
 }
 
 impl Default for FilterCfg {
@@ -24,10 +38,28 @@ impl Default for FilterCfg {
             median_window: 1,
             sample_rate_hz: 50,
             ema_alpha: 0.0,
+            smoothing: Smoothing::MovingAverage,
+            trim: 0,
+            fine: None,
+            display: None,
         }
     }
 }
 
+/// Window average used by the smoothing stage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Smoothing {
+    /// Plain mean of the window.
+    #[default]
+    MovingAverage,
+    /// Linearly weighted mean (newest sample weighs `window`, oldest 1): less
+    /// lag than the plain mean for the same noise reduction.
+    Weighted,
+    /// Mean of the window without its `trim` smallest and largest samples:
+    /// rejects spikes like a median while averaging the rest.
+    TrimmedMean,
+}
+
 /// Filter selection for the smoothing stage (after optional median).
 /// Informational; the active variant is derived from `FilterCfg`.
 #[derive(Debug, Clone, Copy)]
@@ -35,6 +67,8 @@ pub enum FilterKind {
     MovingAverage { window: usize },
     Median { window: usize },
     Ema { alpha: f32 },
+    WeightedMovingAverage { window: usize },
+    TrimmedMean { window: usize, trim: usize },
 }
 
 /// Control configuration (speed management, settling).
@@ -55,6 +89,29 @@ pub struct ControlCfg {
     pub fine_speed: u32,
     /// Tolerance below target (grams) to enter completion zone. Default: 0.08 g.
     pub epsilon_g: f32,
+    /// Returning to a faster speed band requires the error to exceed that band's
+    /// threshold by this much, so noise around a threshold does not toggle the
+    /// speed. Default: 0 (off).
+    pub band_hysteresis_g: f32,
+    /// Minimum time in a speed band before returning to a faster one. Slowing
+    /// down is never delayed. Default: 0 (off).
+    pub band_min_dwell_ms: u64,
+    /// While running, a new speed is only sent to the motor when it differs from
+    /// the last command by at least this many sps; an unchanged speed is never
+    /// re-sent. Default: 0 (any change is sent).
+    pub min_speed_delta_sps: u32,
+    /// Control loop rate (Hz) in the sampler modes, when above the sample
+    /// rate: between samples the loop polls the E-stop, enforces the run cap
+    /// and slows the motor for the last sample extrapolated along its slope.
+    /// Default: 0 (once per sample).
+    pub loop_hz: u32,
+    /// Approaches allowed per dose: each start of the motor towards the
+    /// target is one, so the first run plus every top-up (an early stop that
+    /// settled short) and every restart after the weight fell back out of the
+    /// settle zone.
+    /// Starting one more aborts with `AbortReason::MaxAttempts`. Default: 0
+    /// (unbounded).
+    pub max_attempts: u32,
 }
 
 impl Default for ControlCfg {
@@ -67,6 +124,11 @@ impl Default for ControlCfg {
             coarse_speed: 1200,
             fine_speed: 250,
             epsilon_g: 0.08,
+            band_hysteresis_g: 0.0,
+            band_min_dwell_ms: 0,
+            min_speed_delta_sps: 0,
+            loop_hz: 0,
+            max_attempts: 0,
         }
     }
 }
@@ -84,6 +146,49 @@ pub struct PredictorCfg {
     pub extra_latency_ms: u64,
     /// Minimum fraction of target progress before predictor activates (0.0..=1.0).
     pub min_progress_ratio: f32,
+    /// How the in-flight mass is extrapolated.
+    pub model: InflightModel,
+    /// What to do when an early stop settles below `target - epsilon`.
+    pub undershoot: UndershootPolicy,
+    /// Which weight the slope is estimated from.
+    pub slope_source: SlopeSource,
+}
+
+/// In-flight mass model used by the predictor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InflightModel {
+    /// The slope over the window continues unchanged for the latency.
+    #[default]
+    Linear,
+    /// Mass per commanded step (measured over the window) times the speed the
+    /// controller plans at the current error: accounts for band ramp-down, so a
+    /// slope measured at a faster band does not trigger an early stop.
+    Decel,
+}
+
+/// Weight signal the predictor works on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SlopeSource {
+    /// The fully filtered weight the controller uses. Smooth, but a long
+    /// smoothing window delays the slope by about half its length.
+    #[default]
+    Filtered,
+    /// The weight after the median prefilter only (raw when
+    /// `median_window = 1`): no smoothing lag, but noisier, so it wants a
+    /// longer predictor `window`.
+    Fast,
+}
+
+/// Action taken when the mass settles short of target after a predictor early stop.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UndershootPolicy {
+    /// Resume fine dosing to close the gap.
+    #[default]
+    TopUp,
+    /// Complete the dose at the settled (short) weight.
+    Accept,
+    /// Abort with `AbortReason::Undershoot`.
+    Abort,
 }
 
 impl Default for PredictorCfg {
@@ -93,6 +198,9 @@ impl Default for PredictorCfg {
             window: 6,
             extra_latency_ms: 20,
             min_progress_ratio: 0.10,
+            model: InflightModel::Linear,
+            undershoot: UndershootPolicy::TopUp,
+            slope_source: SlopeSource::Filtered,
         }
     }
 }
@@ -135,6 +243,133 @@ impl Default for SafetyCfg {
     }
 }
 
+/// Safe-state sequence run (best-effort, once) when a dose aborts, after the
+/// motor has been stopped. Steps run in field order; the default does nothing
+/// beyond the stop.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SafeStateCfg {
+    /// Reverse the motor this many steps (0 disables), e.g. to relieve auger
+    /// pressure. Skipped when the abort was an E-stop.
+    pub reverse_steps: u32,
+    /// Step rate for the reversal (steps per second).
+    pub reverse_sps: u32,
+    /// Actuators (by name) to release, in order, e.g. to close a solenoid valve.
+    pub release: Vec<String>,
+    /// Disable the motor driver (de-energize the coils).
+    pub disable_driver: bool,
+}
+
+/// Pre-flight checks run before the motor is first started.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreflightCfg {
+    /// Run the checks at all.
+    pub enabled: bool,
+    /// Scale samples taken to judge readability and stability.
+    pub samples: usize,
+    /// Max spread (max - min, grams) across the samples.
+    pub max_spread_g: f32,
+    /// Plausible range for the starting weight (grams); `None` = unbounded.
+    pub min_g: Option<f32>,
+    pub max_g: Option<f32>,
+    /// Units self-test: the no-load reading must convert to within this many
+    /// grams of zero (or of `container_g`); `None` = off.
+    pub zero_band_g: Option<f32>,
+    /// Weight of the container on the scale at the start (grams).
+    pub container_g: Option<f32>,
+}
+
+impl Default for PreflightCfg {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            samples: 5,
+            max_spread_g: 1.0,
+            min_g: None,
+            max_g: None,
+            zero_band_g: None,
+            container_g: None,
+        }
+    }
+}
+
+/// Hopper feasibility check: a second scale weighing the material left to dose.
+#[derive(Debug, Clone)]
+pub struct HopperCfg {
+    /// Counts-to-grams mapping of the hopper scale.
+    pub calibration: Calibration,
+    /// Material that must remain after the dose (grams), e.g. what the auger
+    /// cannot reach.
+    pub reserve_g: f32,
+    /// Hopper samples averaged for the estimate.
+    pub samples: usize,
+}
+
+impl Default for HopperCfg {
+    fn default() -> Self {
+        Self {
+            calibration: Calibration::default(),
+            reserve_g: 0.0,
+            samples: 5,
+        }
+    }
+}
+
+/// Scale warm-up run before the pre-flight checks. The default does nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarmupCfg {
+    /// Samples to read and discard unconditionally.
+    pub discard_samples: u32,
+    /// Also wait until the rolling standard deviation (grams) over `window`
+    /// samples is at or below this.
+    pub stable_stddev_g: Option<f32>,
+    /// Rolling window for the standard deviation.
+    pub window: usize,
+    /// Give up waiting after this long (the pre-flight stability check still applies).
+    pub max_ms: u64,
+}
+
+impl WarmupCfg {
+    /// True when the warm-up has anything to do.
+    pub fn is_active(&self) -> bool {
+        self.discard_samples > 0 || self.stable_stddev_g.is_some()
+    }
+}
+
+impl Default for WarmupCfg {
+    fn default() -> Self {
+        Self {
+            discard_samples: 0,
+            stable_stddev_g: None,
+            window: 10,
+            max_ms: 10_000,
+        }
+    }
+}
+
+/// Waiting for a placed container to settle before taring on it.
+#[derive(Debug, Clone)]
+pub struct AutoTareCfg {
+    /// Weight above the calibrated zero that counts as a container (grams).
+    pub min_container_g: f32,
+    /// Largest spread (max - min) of the readings on a plateau (grams).
+    pub plateau_g: f32,
+    /// How long the readings must stay on the plateau.
+    pub plateau_ms: u64,
+    /// Give up when no container settles within this long.
+    pub max_wait_ms: u64,
+}
+
+impl Default for AutoTareCfg {
+    fn default() -> Self {
+        Self {
+            min_container_g: 5.0,
+            plateau_g: 0.05,
+            plateau_ms: 500,
+            max_wait_ms: 60_000,
+        }
+    }
+}
+
 /// Timeouts and watchdogs.
 #[derive(Debug, Clone)]
 pub struct Timeouts {
@@ -147,3 +382,22 @@ impl Default for Timeouts {
         Self { sensor_ms: 150 }
     }
 }
+
+/// Retries of a timed-out scale read in the control loop before the dose
+/// aborts. Only timeouts are retried; other read errors abort at once.
+#[derive(Debug, Clone, Copy)]
+pub struct ReadRetryCfg {
+    /// Extra reads after a timeout (0 = abort on the first one).
+    pub max_retries: u32,
+    /// Wait before the first retry (ms); doubles on each further retry.
+    pub backoff_ms: u64,
+}
+
+impl Default for ReadRetryCfg {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            backoff_ms: 5,
+        }
+    }
+}
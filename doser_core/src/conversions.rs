@@ -3,7 +3,12 @@
 //! These eliminate the manual field-by-field mapping previously scattered in the CLI.
 
 use crate::calibration::Calibration;
-use crate::config::{ControlCfg, FilterCfg, PredictorCfg, SafetyCfg, Timeouts};
+use crate::config::{
+    AutoTareCfg, ControlCfg, FilterCfg, HopperCfg, InflightModel, PredictorCfg, PreflightCfg,
+    ReadRetryCfg, SafeStateCfg, SafetyCfg, SlopeSource, Smoothing, Timeouts, UndershootPolicy,
+    WarmupCfg,
+};
+use doser_traits::pacing::OverrunPolicy;
 
 // ── FilterCfg ────────────────────────────────────────────────────────────────
 
@@ -14,10 +19,35 @@ impl From<&doser_config::FilterCfg> for FilterCfg {
             median_window: c.median_window,
             sample_rate_hz: c.sample_rate_hz,
             ema_alpha: c.ema_alpha.unwrap_or(0.0),
+            smoothing: smoothing(c.smoothing),
+            trim: c.trim,
+            fine: c.fine.as_ref().map(|f| sub_filter(f, c.sample_rate_hz)),
+            display: c.display.as_ref().map(|f| sub_filter(f, c.sample_rate_hz)),
         }
     }
 }
 
+fn sub_filter(f: &doser_config::SubFilterCfg, sample_rate_hz: u32) -> Box<FilterCfg> {
+    Box::new(FilterCfg {
+        ma_window: f.ma_window,
+        median_window: f.median_window,
+        sample_rate_hz,
+        ema_alpha: f.ema_alpha.unwrap_or(0.0),
+        smoothing: smoothing(f.smoothing),
+        trim: f.trim,
+        fine: None,
+        display: None,
+    })
+}
+
+fn smoothing(s: doser_config::Smoothing) -> Smoothing {
+    match s {
+        doser_config::Smoothing::MovingAverage => Smoothing::MovingAverage,
+        doser_config::Smoothing::Weighted => Smoothing::Weighted,
+        doser_config::Smoothing::TrimmedMean => Smoothing::TrimmedMean,
+    }
+}
+
 // ── ControlCfg ───────────────────────────────────────────────────────────────
 
 impl From<&doser_config::ControlCfg> for ControlCfg {
@@ -30,6 +60,11 @@ impl From<&doser_config::ControlCfg> for ControlCfg {
             hysteresis_g: c.hysteresis_g,
             stable_ms: c.stable_ms,
             epsilon_g: c.epsilon_g,
+            band_hysteresis_g: c.band_hysteresis_g,
+            band_min_dwell_ms: c.band_min_dwell_ms,
+            min_speed_delta_sps: c.min_speed_delta_sps,
+            loop_hz: c.loop_hz,
+            max_attempts: c.max_attempts,
         }
     }
 }
@@ -47,6 +82,19 @@ impl From<&doser_config::Safety> for SafetyCfg {
     }
 }
 
+// ── SafeStateCfg ─────────────────────────────────────────────────────────────
+
+impl From<&doser_config::OnAbort> for SafeStateCfg {
+    fn from(c: &doser_config::OnAbort) -> Self {
+        Self {
+            reverse_steps: c.reverse_steps,
+            reverse_sps: c.reverse_sps,
+            release: c.release.clone(),
+            disable_driver: c.disable_driver,
+        }
+    }
+}
+
 // ── Timeouts ─────────────────────────────────────────────────────────────────
 
 impl From<&doser_config::Timeouts> for Timeouts {
@@ -57,6 +105,15 @@ impl From<&doser_config::Timeouts> for Timeouts {
     }
 }
 
+impl From<&doser_config::ReadRetryCfg> for ReadRetryCfg {
+    fn from(c: &doser_config::ReadRetryCfg) -> Self {
+        Self {
+            max_retries: c.max_retries,
+            backoff_ms: c.backoff_ms,
+        }
+    }
+}
+
 // ── PredictorCfg ─────────────────────────────────────────────────────────────
 
 impl From<&doser_config::PredictorCfg> for PredictorCfg {
@@ -66,10 +123,94 @@ impl From<&doser_config::PredictorCfg> for PredictorCfg {
             window: c.window,
             extra_latency_ms: c.extra_latency_ms,
             min_progress_ratio: c.min_progress_ratio,
+            model: match c.model {
+                doser_config::InflightModel::Linear => InflightModel::Linear,
+                doser_config::InflightModel::Decel => InflightModel::Decel,
+            },
+            undershoot: match c.undershoot {
+                doser_config::UndershootPolicy::TopUp => UndershootPolicy::TopUp,
+                doser_config::UndershootPolicy::Accept => UndershootPolicy::Accept,
+                doser_config::UndershootPolicy::Abort => UndershootPolicy::Abort,
+            },
+            slope_source: match c.slope_source {
+                doser_config::SlopeSource::Filtered => SlopeSource::Filtered,
+                doser_config::SlopeSource::Fast => SlopeSource::Fast,
+            },
+        }
+    }
+}
+
+// ── HopperCfg ────────────────────────────────────────────────────────────────
+
+impl From<&doser_config::HopperCfg> for HopperCfg {
+    fn from(c: &doser_config::HopperCfg) -> Self {
+        Self {
+            calibration: Calibration {
+                gain_g_per_count: c.gain_g_per_count,
+                zero_counts: c.zero_counts,
+                offset_g: 0.0,
+            },
+            reserve_g: c.reserve_g,
+            samples: c.samples,
         }
     }
 }
 
+// ── PreflightCfg ─────────────────────────────────────────────────────────────
+
+impl From<&doser_config::PreflightCfg> for PreflightCfg {
+    fn from(c: &doser_config::PreflightCfg) -> Self {
+        Self {
+            enabled: c.enabled,
+            samples: c.samples,
+            max_spread_g: c.max_spread_g,
+            min_g: c.min_g,
+            max_g: c.max_g,
+            zero_band_g: c.zero_band_g,
+            container_g: c.container_g,
+        }
+    }
+}
+
+// ── WarmupCfg ────────────────────────────────────────────────────────────────
+
+impl From<&doser_config::WarmupCfg> for WarmupCfg {
+    fn from(c: &doser_config::WarmupCfg) -> Self {
+        Self {
+            discard_samples: c.discard_samples,
+            stable_stddev_g: c.stable_stddev_g,
+            window: c.window,
+            max_ms: c.max_ms,
+        }
+    }
+}
+
+// ── AutoTareCfg ──────────────────────────────────────────────────────────────
+
+impl From<&doser_config::AutoTareCfg> for AutoTareCfg {
+    fn from(c: &doser_config::AutoTareCfg) -> Self {
+        Self {
+            min_container_g: c.min_container_g,
+            plateau_g: c.plateau_g,
+            plateau_ms: c.plateau_ms,
+            max_wait_ms: c.max_wait_ms,
+        }
+    }
+}
+
+// ── OverrunPolicy ────────────────────────────────────────────────────────────
+
+// Both enums are foreign to this crate, so this is a function rather than `From`.
+
+/// Map the TOML `runner.overrun` setting to the pacer's policy.
+pub fn overrun_policy(p: doser_config::OverrunPolicy) -> OverrunPolicy {
+    match p {
+        doser_config::OverrunPolicy::SkipSleep => OverrunPolicy::SkipSleep,
+        doser_config::OverrunPolicy::CatchUp => OverrunPolicy::CatchUp,
+        doser_config::OverrunPolicy::LogAndDegrade => OverrunPolicy::LogAndDegrade,
+    }
+}
+
 // ── Calibration ──────────────────────────────────────────────────────────────
 
 impl From<&doser_config::Calibration> for Calibration {
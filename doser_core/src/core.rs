@@ -1,23 +1,33 @@
 //! The unified dosing control loop (`DoserCore`).
 //!
 //! Contains the state machine that drives each iteration of the dosing process:
-//! calibration caching, filtering (median + EMA/MA), speed selection, safety
+//! calibration caching, filtering (`FilterPipeline`), speed selection, safety
 //! watchdogs, predictive early stop, and settle detection.
 
 use std::collections::VecDeque;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use doser_traits::clock::Clock;
+use doser_traits::pacing::{OverrunPolicy, Pacer};
 use eyre::WrapErr;
 
 use crate::calibration::Calibration;
 use crate::config::*;
+use crate::diagnosis::{AbortTrace, FlowTrace, Remediation, StepSample, TRACE_STEPS};
 use crate::error::{AbortReason, DoserError, Result};
-use crate::fixed_point::{abs_diff_i32_u32, avg2_round_nearest_i32};
+use crate::filter::FilterPipeline;
+use crate::fixed_point::abs_diff_i32_u32;
 use crate::hw_error::map_hw_error;
-use crate::status::DosingStatus;
-use crate::util::div_round_nearest_i32;
+use crate::safe_state::SafeState;
+use crate::status::{Attempt, DosePhase, DosingStatus, PhaseTimings, SpeedBand};
+use crate::weigh::{NoiseWindow, Weight};
+
+/// Approach records kept per dose. Past this many the newest replaces the last
+/// kept one, so an unbounded `max_attempts` cannot grow the list and starting
+/// an approach never allocates once the dose has begun.
+pub(crate) const ATTEMPT_RECORDS: usize = 16;
 
 /// Unified core for both dynamic (boxed) and generic (static dispatch) variants.
 pub struct DoserCore<S: doser_traits::Scale, M: doser_traits::Motor> {
@@ -27,6 +37,13 @@ pub struct DoserCore<S: doser_traits::Scale, M: doser_traits::Motor> {
     pub(crate) control: ControlCfg,
     pub(crate) safety: SafetyCfg,
     pub(crate) timeouts: Timeouts,
+    pub(crate) read_retry: ReadRetryCfg,
+    /// Timed-out reads retried since `begin()`, and how many of those
+    /// retries eventually returned a sample.
+    pub(crate) read_retries: u64,
+    pub(crate) reads_recovered: u64,
+    /// Raw counts the scale's ADC can produce; readings outside are faults.
+    pub(crate) raw_range: Option<RangeInclusive<i32>>,
     pub(crate) calibration: Calibration,
     pub(crate) target_cg: i32,
     pub(crate) clock: Arc<dyn Clock + Send + Sync>,
@@ -34,13 +51,43 @@ pub struct DoserCore<S: doser_traits::Scale, M: doser_traits::Motor> {
 
     pub(crate) last_weight_cg: i32,
     pub(crate) settled_since_ms: Option<u64>,
+    /// Sum and count of the weights since `settled_since_ms` (the hold value).
+    pub(crate) settle_sum_cg: i64,
+    pub(crate) settle_samples: u32,
     pub(crate) start_ms: u64,
-    pub(crate) ma_buf: VecDeque<i32>,
-    pub(crate) med_buf: VecDeque<i32>,
-    pub(crate) ema_prev_cg: Option<f32>,
-    pub(crate) tmp_med_buf: Vec<i32>,
+    pub(crate) pipeline: FilterPipeline,
+    /// Pipeline for the fine and settle phases (`FilterCfg::fine`), started
+    /// empty when the loop leaves the coarse phase.
+    pub(crate) fine_pipeline: Option<FilterPipeline>,
+    /// The loop has switched to `fine_pipeline`; it stays switched until
+    /// `begin()`, so the filter never toggles at a band boundary.
+    pub(crate) fine_filter_active: bool,
+    /// Display-only pipeline (`FilterCfg::display`) and its last output.
+    pub(crate) display_pipeline: Option<FilterPipeline>,
+    pub(crate) display_weight_cg: i32,
+    /// Noise of the unfiltered readings, for `current_weight()`.
+    pub(crate) noise: NoiseWindow,
+    /// Weight against commanded steps, to classify a stall.
+    pub(crate) flow: FlowTrace,
+    /// Suggested remediation for the last abort.
+    pub(crate) remediation: Option<Remediation>,
+    /// The last `TRACE_STEPS` control steps, for an abort's post-mortem.
+    pub(crate) recent_steps: VecDeque<StepSample>,
+    /// Prefilter-only path feeding the predictor (`SlopeSource::Fast`).
+    pub(crate) fast_pipeline: Option<FilterPipeline>,
     pub(crate) period_us: u64,
-    pub(crate) cal_gain_scaled: i64,
+    /// Absolute-deadline pacing of loop iterations.
+    pub(crate) pacer: Pacer,
+    /// `pacer.overruns()` at `begin()`, so telemetry is per dose.
+    pub(crate) overruns_at_begin: u64,
+    /// Phase the loop is in since `phase_mark_ms` (`None` before the first step).
+    pub(crate) phase: Option<DosePhase>,
+    pub(crate) phase_mark_ms: u64,
+    pub(crate) phase_timings: PhaseTimings,
+    /// Follow-up to the motor stop on abort; run at most once per dose.
+    pub(crate) safe_state: SafeState,
+    pub(crate) safe_state_done: bool,
+    pub(crate) cal_gain: crate::fixed_point::ShiftedGain,
     pub(crate) cal_offset_cg: i32,
     pub(crate) slow_at_cg: i32,
     pub(crate) epsilon_cg: i32,
@@ -55,12 +102,35 @@ pub struct DoserCore<S: doser_traits::Scale, M: doser_traits::Motor> {
     pub(crate) estop_debounce_n: u8,
     pub(crate) estop_count: u8,
     pub(crate) predictor: PredictorCfg,
-    pub(crate) pred_hist: VecDeque<(u64, i32)>,
+    /// Predictor window: `(ms, weight_cg, sps commanded when sampled)`.
+    pub(crate) pred_hist: VecDeque<(u64, i32, u32)>,
+    /// Last speed passed to `Motor::set_speed` (0 once stopped).
+    pub(crate) commanded_sps: u32,
     pub(crate) pred_latency_ms: u64,
     pub(crate) last_slope_ema_cg_per_ms: Option<f32>,
     pub(crate) last_inflight_cg: Option<i32>,
     pub(crate) early_stop_at_cg: Option<i32>,
+    /// Control weight when the running motor last stopped.
+    pub(crate) stopped_at_cg: Option<i32>,
+    /// When the current early stop was issued; cleared once its in-flight mass
+    /// has had `pred_latency_ms` to land.
+    pub(crate) early_stop_ms: Option<u64>,
+    /// Shortfall of the first early stop that settled below `target - epsilon`.
+    pub(crate) undershoot_cg: Option<i32>,
     pub(crate) speed_bands_cg: Vec<(i32, u32)>,
+    pub(crate) band_hysteresis_cg: i32,
+    /// Index into `speed_bands_cg` currently held, and since when.
+    pub(crate) band_idx: Option<usize>,
+    pub(crate) band_since_ms: u64,
+    /// Last sample `(ms, weight_cg)` and the slope into it (cg/ms, >= 0),
+    /// extrapolated by `step_hold` between samples.
+    pub(crate) hold_from: Option<(u64, i32)>,
+    pub(crate) hold_slope_cg_per_ms: f32,
+    /// Motor approaches since `begin()` (see `ControlCfg::max_attempts`), the
+    /// first [`ATTEMPT_RECORDS`] minus one and the latest.
+    pub(crate) attempts: Vec<Attempt>,
+    /// Motor approaches started since `begin()`, recorded or not.
+    pub(crate) attempts_started: u32,
 }
 
 impl<S: doser_traits::Scale, M: doser_traits::Motor> core::fmt::Debug for DoserCore<S, M> {
@@ -74,11 +144,28 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> core::fmt::Debug for DoserC
 }
 
 impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
-    /// Return the last observed weight in grams.
+    /// Return the last observed weight in grams: the control weight the loop
+    /// acts on.
     pub fn last_weight(&self) -> f32 {
         (self.last_weight_cg as f32) / 100.0
     }
 
+    /// Last display weight in grams: the unfiltered weight through
+    /// `FilterCfg::display`, for operator displays and tickets. Never used for
+    /// control; equals [`Self::last_weight`] without a display filter.
+    pub fn display_weight(&self) -> f32 {
+        (self.display_weight_cg as f32) / 100.0
+    }
+
+    /// The control weight with its uncertainty: the noise of one unfiltered
+    /// reading over the recent readings, a bound the filter only tightens.
+    pub fn current_weight(&self) -> Weight {
+        Weight {
+            grams: self.last_weight(),
+            sigma_g: self.noise.sigma_g(),
+        }
+    }
+
     /// Optionally set the tare baseline in raw counts.
     pub fn set_tare_counts(&mut self, zero_counts: i32) {
         self.calibration.zero_counts = zero_counts;
@@ -89,6 +176,102 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
         &self.filter
     }
 
+    /// Replace the active filter pipeline (state starts fresh). A fine-phase
+    /// filter from `FilterCfg::fine` still takes over in the fine phase.
+    pub fn set_filter_pipeline(&mut self, pipeline: FilterPipeline) {
+        self.pipeline = pipeline;
+    }
+
+    /// Telemetry: average loop wake-up jitter (µs) over the last full window.
+    pub fn avg_jitter_us(&self) -> u32 {
+        self.pacer.avg_jitter_us
+    }
+
+    /// Choose how an iteration that overruns its period is handled.
+    pub fn set_overrun_policy(&mut self, policy: OverrunPolicy) {
+        self.pacer.set_overrun_policy(policy);
+    }
+
+    /// The active loop overrun policy.
+    pub fn overrun_policy(&self) -> OverrunPolicy {
+        self.pacer.overrun_policy()
+    }
+
+    /// Telemetry: control iterations that overran their period since `begin()`.
+    pub fn loop_overruns(&self) -> u64 {
+        self.pacer.overruns() - self.overruns_at_begin
+    }
+
+    /// Telemetry: time spent in each dosing phase since `begin()`, up to the
+    /// latest step.
+    pub fn phase_timings(&self) -> PhaseTimings {
+        self.phase_timings
+    }
+
+    /// Telemetry: the motor's approaches since `begin()`, oldest first. At
+    /// most 16 are kept: the first ones and the latest.
+    pub fn attempts(&self) -> &[Attempt] {
+        &self.attempts
+    }
+
+    /// Telemetry: how many approaches started since `begin()`, including
+    /// those [`attempts`](Self::attempts) no longer lists.
+    pub fn attempt_count(&self) -> u32 {
+        self.attempts_started
+    }
+
+    /// Phase of the latest step (`None` before the first one).
+    pub fn phase(&self) -> Option<DosePhase> {
+        self.phase
+    }
+
+    /// Speed band of the latest step; `None` while the motor is stopped.
+    pub fn current_band(&self) -> Option<SpeedBand> {
+        if !matches!(self.phase, Some(DosePhase::Coarse | DosePhase::Fine)) {
+            return None;
+        }
+        if self.speed_bands_cg.is_empty() {
+            return Some(match self.phase {
+                Some(DosePhase::Coarse) => SpeedBand::Coarse,
+                _ => SpeedBand::Fine {
+                    sps: self.commanded_sps,
+                },
+            });
+        }
+        self.band_idx.map(|index| {
+            let (thr_cg, sps) = self.speed_bands_cg[index];
+            SpeedBand::Band {
+                index,
+                threshold_g: (thr_cg as f32) / 100.0,
+                sps,
+            }
+        })
+    }
+
+    /// Target of the current dose, in grams.
+    pub fn target_g(&self) -> f32 {
+        (self.target_cg as f32) / 100.0
+    }
+
+    /// Suggested remediation for the last abort (see [`crate::diagnosis`]).
+    pub fn remediation(&self) -> Option<Remediation> {
+        self.remediation
+    }
+
+    /// The report for `err`, an abort of this dose, with its remediation and
+    /// the last control steps attached as context
+    /// (`report.downcast_ref::<Remediation>()`, `::<AbortTrace>()`).
+    pub fn abort_report(&self, err: DoserError) -> eyre::Report {
+        let mut report = eyre::Report::new(err);
+        if let Some(r) = self.remediation {
+            report = report.wrap_err(r);
+        }
+        report.wrap_err(AbortTrace {
+            steps: self.recent_steps.iter().copied().collect(),
+            motor: self.motor_diagnostics(),
+        })
+    }
+
     /// Telemetry: last slope EMA in grams per second.
     pub fn last_slope_ema_gps(&self) -> Option<f32> {
         self.last_slope_ema_cg_per_ms.map(|v| v * 0.01 * 1000.0)
@@ -102,34 +285,216 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
         self.early_stop_at_cg.map(|cg| (cg as f32) * 0.01)
     }
 
+    /// Telemetry: motor steps commanded since `begin()` (speed × time while
+    /// running).
+    pub fn motor_steps(&self) -> u64 {
+        self.flow.steps().round() as u64
+    }
+
+    /// Telemetry: the motor backend's own stepping counters, if it keeps them
+    /// ([`doser_traits::Motor::diagnostics`]).
+    pub fn motor_diagnostics(&self) -> Option<doser_traits::MotorDiagSnapshot> {
+        self.motor.diagnostics().map(|d| d.snapshot())
+    }
+
+    /// Telemetry: weight gained since the motor last stopped, in grams: the
+    /// in-flight mass that landed after the stop (`None` if it never ran).
+    pub fn coast_g(&self) -> Option<f32> {
+        self.stopped_at_cg
+            .map(|cg| (self.last_weight_cg - cg) as f32 * 0.01)
+    }
+
+    /// Stable-hold weight in grams: the mean over the final settle window,
+    /// steadier than the last reading (`None` before the settle zone).
+    pub fn hold_weight(&self) -> Option<f32> {
+        (self.settled_since_ms.is_some() && self.settle_samples > 0)
+            .then(|| self.settle_sum_cg as f32 / self.settle_samples as f32 / 100.0)
+    }
+
+    /// Telemetry: how far below target the mass settled after a predictor early
+    /// stop, in grams, if it undershot.
+    pub fn undershoot_g(&self) -> Option<f32> {
+        self.undershoot_cg.map(|cg| (cg as f32) * 0.01)
+    }
+
+    /// Retry policy for timed-out scale reads in [`Self::step`].
+    pub fn set_read_retry(&mut self, read_retry: ReadRetryCfg) {
+        self.read_retry = read_retry;
+    }
+
+    /// Telemetry: timed-out scale reads retried since `begin()`.
+    pub fn read_retries(&self) -> u64 {
+        self.read_retries
+    }
+
+    /// Telemetry: timed-out scale reads that a retry recovered since `begin()`.
+    pub fn reads_recovered(&self) -> u64 {
+        self.reads_recovered
+    }
+
+    /// Legal raw counts of the scale (see [`Scale::raw_range`]); set by
+    /// orchestrators that read the real scale outside the core.
+    ///
+    /// [`Scale::raw_range`]: doser_traits::Scale::raw_range
+    pub fn set_raw_range(&mut self, raw_range: Option<RangeInclusive<i32>>) {
+        self.raw_range = raw_range;
+    }
+
     /// Process a pre-sampled raw reading (for sampler integration).
     pub fn step_from_raw(&mut self, raw: i32) -> Result<DosingStatus> {
         if self.estop_latched || self.poll_estop() {
-            self.motor_stop_best_effort("estop");
-            return Ok(DosingStatus::Aborted(DoserError::Abort(AbortReason::Estop)));
+            return Ok(self.abort("estop", AbortReason::Estop));
         }
-        let w_cg_raw = self.to_cg_cached(raw);
-        let w_cg = self.apply_filter(w_cg_raw);
-        self.process_weight(w_cg)
+        self.check_raw(raw)?;
+        self.process_raw_cg(self.to_cg_cached(raw))
     }
 
     /// One iteration of the dosing loop (reads the scale internally).
     pub fn step(&mut self) -> Result<DosingStatus> {
         if self.estop_latched || self.poll_estop() {
-            self.motor_stop_best_effort("estop");
-            return Ok(DosingStatus::Aborted(DoserError::Abort(AbortReason::Estop)));
+            return Ok(self.abort("estop", AbortReason::Estop));
         }
 
+        let raw = self.read_scale().wrap_err("reading scale")?;
+        self.check_raw(raw)?;
+
+        self.process_raw_cg(self.to_cg_cached(raw))
+    }
+
+    /// Filter an unfiltered weight for the controller and, with
+    /// `SlopeSource::Fast`, separately for the predictor.
+    fn process_raw_cg(&mut self, w_cg_raw: i32) -> Result<DosingStatus> {
+        self.noise.push(w_cg_raw);
+        let w_cg = match self.fine_pipeline.as_mut() {
+            Some(fine) if self.fine_filter_active => fine.process(w_cg_raw),
+            Some(fine)
+                if matches!(
+                    self.phase,
+                    Some(DosePhase::Fine | DosePhase::Settle | DosePhase::Verify)
+                ) =>
+            {
+                // Start from an empty history: coarse-phase samples would pull
+                // the heavier filter's output back down the ramp.
+                fine.reset();
+                self.fine_filter_active = true;
+                tracing::debug!(w_cg_raw, "switched to the fine-phase filter");
+                fine.process(w_cg_raw)
+            }
+            _ => self.pipeline.process(w_cg_raw),
+        };
+        self.display_weight_cg = match self.display_pipeline.as_mut() {
+            Some(display) => display.process(w_cg_raw),
+            None => w_cg,
+        };
+        let pred_cg = match self.fast_pipeline.as_mut() {
+            Some(fast) => fast.process(w_cg_raw),
+            None => w_cg,
+        };
+        self.process_weight(w_cg, pred_cg)
+    }
+
+    /// Fail on a reading outside the ADC's range: the conversion to grams
+    /// would saturate it into a plausible-looking weight.
+    fn check_raw(&self, raw: i32) -> Result<()> {
+        match &self.raw_range {
+            Some(range) if !range.contains(&raw) => {
+                let err = DoserError::HardwareFault(format!(
+                    "raw reading {raw} outside the ADC range {}..={}",
+                    range.start(),
+                    range.end()
+                ));
+                tracing::error!(raw, "scale reading out of range");
+                Err(eyre::Report::new(err))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Read the scale, retrying timeouts with backoff per `read_retry`.
+    fn read_scale(&mut self) -> Result<i32> {
         let timeout = Duration::from_millis(self.timeouts.sensor_ms);
-        let raw = self
-            .scale
-            .read(timeout)
-            .map_err(|e| eyre::Report::new(map_hw_error(&*e)))
-            .wrap_err("reading scale")?;
+        let mut backoff = Duration::from_millis(self.read_retry.backoff_ms);
+        let mut attempt = 0;
+        loop {
+            let err = match self.scale.read(timeout) {
+                Ok(raw) => {
+                    if attempt > 0 {
+                        self.reads_recovered += 1;
+                        tracing::info!(attempt, "scale read recovered after retry");
+                    }
+                    return Ok(raw);
+                }
+                Err(e) => map_hw_error(&*e),
+            };
+            let transient = matches!(err, DoserError::Timeout | DoserError::DataReadyTimeout);
+            if !transient || attempt >= self.read_retry.max_retries {
+                return Err(eyre::Report::new(err));
+            }
+            attempt += 1;
+            self.read_retries += 1;
+            tracing::warn!(error = %err, attempt, "scale read timed out; retrying");
+            if !backoff.is_zero() {
+                self.clock.sleep(backoff);
+                backoff = backoff.saturating_mul(2);
+            }
+        }
+    }
 
-        let w_cg_raw = self.to_cg_cached(raw);
-        let w_cg = self.apply_filter(w_cg_raw);
-        self.process_weight(w_cg)
+    /// One control tick between samples (`ControlCfg::loop_hz`): polls the
+    /// E-stop, enforces the run cap and, while the motor runs, slows it for the
+    /// last sample extrapolated along its slope (at most one sample period
+    /// ahead). It never speeds up, stops for the target or completes: those
+    /// wait for a real sample in `step_from_raw`.
+    pub fn step_hold(&mut self) -> Result<DosingStatus> {
+        if self.estop_latched || self.poll_estop() {
+            return Ok(self.abort("estop", AbortReason::Estop));
+        }
+        let now = self.clock.ms_since(self.epoch);
+        if now.saturating_sub(self.start_ms) >= self.safety.max_run_ms {
+            return Ok(self.abort("max-run cap", AbortReason::MaxRuntime));
+        }
+        self.check_motor_alive()?;
+        if let Some((t0, w0)) = self.hold_from
+            && self.commanded_sps > 0
+            && self.early_stop_ms.is_none()
+        {
+            let ahead_ms = now
+                .saturating_sub(t0)
+                .min(crate::util::period_ms(self.filter.sample_rate_hz));
+            let est_cg = w0.saturating_add((self.hold_slope_cg_per_ms * ahead_ms as f32) as i32);
+            let err_cg = self.target_cg - est_cg;
+            if est_cg + self.epsilon_cg < self.target_cg {
+                self.update_band(err_cg, now);
+                let sps = self.select_speed(err_cg, err_cg.unsigned_abs());
+                if sps > 0 && sps < self.commanded_sps && self.speed_change_due(sps) {
+                    self.account_phase(now);
+                    self.flow
+                        .record(now, self.last_weight_cg, self.commanded_sps);
+                    self.motor
+                        .set_speed(sps)
+                        .map_err(|e| eyre::Report::new(map_hw_error(&*e)))
+                        .wrap_err("set_speed")?;
+                    tracing::trace!(est_cg, sps, "slowed between samples");
+                    self.commanded_sps = sps;
+                    if sps < self.coarse_sps() {
+                        self.phase = Some(DosePhase::Fine);
+                    }
+                }
+            }
+        }
+        self.pace();
+        Ok(DosingStatus::Running)
+    }
+
+    /// Pace the loop at `hz` rather than the sample rate, for [`Self::step_hold`]
+    /// ticks between samples; 0 restores the sample rate.
+    pub fn set_loop_hz(&mut self, hz: u32) {
+        let hz = if hz == 0 {
+            self.filter.sample_rate_hz
+        } else {
+            hz
+        };
+        self.period_us = crate::util::period_us(hz);
     }
 
     /// Reset per-run state. Call before a new dose.
@@ -138,11 +503,24 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
         let now = self.clock.ms_since(self.epoch);
         self.start_ms = now;
         self.settled_since_ms = None;
-        self.ma_buf.clear();
-        self.med_buf.clear();
-        self.ema_prev_cg = None;
+        self.settle_sum_cg = 0;
+        self.settle_samples = 0;
+        self.pipeline.reset();
+        self.fine_filter_active = false;
+        if let Some(display) = self.display_pipeline.as_mut() {
+            display.reset();
+        }
+        self.display_weight_cg = 0;
+        self.noise.clear();
+        self.flow.clear();
+        self.remediation = None;
+        self.recent_steps.clear();
+        if let Some(fast) = self.fast_pipeline.as_mut() {
+            fast.reset();
+        }
         self.last_weight_cg = 0;
         self.motor_started = false;
+        self.commanded_sps = 0;
         self.last_progress_cg = 0;
         self.last_progress_at_ms = now;
         self.estop_latched = false;
@@ -151,6 +529,24 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
         self.last_slope_ema_cg_per_ms = None;
         self.last_inflight_cg = None;
         self.early_stop_at_cg = None;
+        self.stopped_at_cg = None;
+        self.early_stop_ms = None;
+        self.undershoot_cg = None;
+        self.band_idx = None;
+        self.band_since_ms = now;
+        self.hold_from = None;
+        self.hold_slope_cg_per_ms = 0.0;
+        self.pacer.reset_at(self.epoch);
+        self.overruns_at_begin = self.pacer.overruns();
+        self.phase = None;
+        self.phase_mark_ms = now;
+        self.phase_timings = PhaseTimings::default();
+        self.safe_state_done = false;
+        self.read_retries = 0;
+        self.reads_recovered = 0;
+        self.attempts.clear();
+        self.attempts.reserve(ATTEMPT_RECORDS);
+        self.attempts_started = 0;
     }
 
     /// Stop the motor, returning any hardware error (used on the success path).
@@ -161,6 +557,68 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
             .wrap_err("motor_stop")
     }
 
+    /// Halt the motor through [`doser_traits::Motor::halt`], returning any
+    /// hardware error (used when a shutdown or E-stop ends the run early).
+    pub fn motor_halt(&mut self) -> Result<()> {
+        self.motor
+            .halt()
+            .map_err(|e| eyre::Report::new(map_hw_error(&*e)))
+            .wrap_err("motor_halt")
+    }
+
+    /// Replace the abort safe-state sequence (default: stop only).
+    pub fn set_safe_state(&mut self, safe_state: SafeState) {
+        self.safe_state = safe_state;
+    }
+
+    /// Run the configured safe-state sequence for an abort caused by `err`.
+    ///
+    /// Called automatically on aborts detected by the loop; orchestrators call it
+    /// for aborts they detect themselves (timeouts, shutdown). Runs at most once
+    /// per dose, and never moves the motor after an E-stop.
+    pub fn enter_safe_state(&mut self, err: &DoserError) {
+        if self.safe_state_done || self.safe_state.is_noop() {
+            return;
+        }
+        self.safe_state_done = true;
+        let allow_motion = !matches!(err, DoserError::Abort(AbortReason::Estop));
+        self.safe_state.run(&mut self.motor, allow_motion);
+    }
+
+    /// Stop the motor, run the safe-state sequence, and report the abort.
+    fn abort(&mut self, ctx: &'static str, reason: AbortReason) -> DosingStatus {
+        self.remediation = Some(match reason {
+            AbortReason::NoProgress => self
+                .flow
+                .classify_stall(self.last_progress_at_ms, self.no_progress_epsilon_cg),
+            ref r => Remediation::for_abort(r),
+        });
+        // A press or a spill calls for a hard stop; the rest may stop gracefully.
+        if matches!(reason, AbortReason::Estop | AbortReason::Overshoot) {
+            self.motor_halt_best_effort(ctx);
+        } else {
+            self.motor_stop_best_effort(ctx);
+        }
+        let err = DoserError::Abort(reason);
+        self.enter_safe_state(&err);
+        DosingStatus::Aborted(err)
+    }
+
+    /// Halt and fail when the motor backend reports it can no longer drive a
+    /// running motor ([`doser_traits::Motor::check_alive`]).
+    fn check_motor_alive(&mut self) -> Result<()> {
+        if !self.motor_started {
+            return Ok(());
+        }
+        if let Err(e) = self.motor.check_alive() {
+            let err = map_hw_error(&*e);
+            tracing::error!(error = %err, "motor backend not alive; halting");
+            self.motor_halt_best_effort("motor check");
+            return Err(eyre::Report::new(err)).wrap_err("motor check");
+        }
+        Ok(())
+    }
+
     /// Best-effort motor stop for safety/abort paths.
     ///
     /// Unlike [`Self::motor_stop`], this never returns an error: the caller is
@@ -168,9 +626,36 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
     /// a bounded number of times and escalates to an error-level log if every
     /// attempt fails, so a stuck motor is loud rather than silently ignored.
     fn motor_stop_best_effort(&mut self, ctx: &'static str) {
+        self.stop_best_effort(ctx, false);
+    }
+
+    /// [`Self::motor_stop_best_effort`] through [`doser_traits::Motor::halt`]:
+    /// de-energize at once, with no ramp down.
+    fn motor_halt_best_effort(&mut self, ctx: &'static str) {
+        self.stop_best_effort(ctx, true);
+    }
+
+    fn stop_best_effort(&mut self, ctx: &'static str, halt: bool) {
         const MAX_ATTEMPTS: u32 = 3;
+        if self.motor_started {
+            // Count the steps run since the last record.
+            let now = self.clock.ms_since(self.epoch);
+            self.flow
+                .record(now, self.last_weight_cg, self.commanded_sps);
+            self.stopped_at_cg = Some(self.last_weight_cg);
+            self.end_attempt(now);
+        }
+        self.commanded_sps = 0;
+        // Resuming (top-up, or a reading falling back out of the settle zone)
+        // must start the motor again, not just set a speed.
+        self.motor_started = false;
         for attempt in 1..=MAX_ATTEMPTS {
-            match self.motor.stop() {
+            let stopped = if halt {
+                self.motor.halt()
+            } else {
+                self.motor.stop()
+            };
+            match stopped {
                 Ok(()) => return,
                 Err(e) => {
                     if attempt == MAX_ATTEMPTS {
@@ -192,31 +677,43 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
 
     /// Core weight-processing logic shared by `step()` and `step_from_raw()`.
     /// Handles safety checks, speed selection, settling, and motor commands.
-    fn process_weight(&mut self, w_cg: i32) -> Result<DosingStatus> {
+    /// `pred_cg` is the predictor's input (see `PredictorCfg::slope_source`).
+    fn process_weight(&mut self, w_cg: i32, pred_cg: i32) -> Result<DosingStatus> {
         self.last_weight_cg = w_cg;
         let err_cg = self.target_cg - w_cg;
         let abs_err_cg = err_cg.unsigned_abs();
         let now = self.clock.ms_since(self.epoch);
+        if let Some((t0, w0)) = self.hold_from
+            && now > t0
+        {
+            self.hold_slope_cg_per_ms = ((w_cg - w0) as f32 / (now - t0) as f32).max(0.0);
+        }
+        self.hold_from = Some((now, w_cg));
+        self.account_phase(now);
+        if self.recent_steps.len() == TRACE_STEPS {
+            self.recent_steps.pop_front();
+        }
+        self.recent_steps.push_back(StepSample {
+            ms: now.saturating_sub(self.start_ms),
+            weight_g: w_cg as f32 / 100.0,
+            sps: self.commanded_sps,
+            phase: self.phase,
+        });
 
         // Safety: hard runtime cap
         if now.saturating_sub(self.start_ms) >= self.safety.max_run_ms {
-            self.motor_stop_best_effort("max-run cap");
-            return Ok(DosingStatus::Aborted(DoserError::Abort(
-                AbortReason::MaxRuntime,
-            )));
+            return Ok(self.abort("max-run cap", AbortReason::MaxRuntime));
         }
 
         // Safety: excessive overshoot guard
         if w_cg > self.target_cg + self.max_overshoot_cg {
-            self.motor_stop_best_effort("overshoot");
-            return Ok(DosingStatus::Aborted(DoserError::Abort(
-                AbortReason::Overshoot,
-            )));
+            return Ok(self.abort("overshoot", AbortReason::Overshoot));
         }
 
         // Predictive early stop to reduce overshoot under latency
-        if self.maybe_early_stop(now, w_cg) {
-            self.clock.sleep(Duration::from_micros(self.period_us));
+        if self.maybe_early_stop(now, pred_cg) {
+            self.phase = Some(DosePhase::Settle);
+            self.pace();
             return Ok(DosingStatus::Running);
         }
 
@@ -225,6 +722,7 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
         // before completion is declared, so a noisy reading that dips below the band
         // restarts the settle timer (the documented hysteresis behavior).
         if w_cg + self.epsilon_cg >= self.target_cg {
+            self.early_stop_ms = None;
             self.motor_stop_best_effort("entering settle zone");
             // Acceptance half-band. At least `epsilon` so the epsilon-based stop point
             // (w ≈ target - epsilon) is in-band; `hysteresis_g` widens it to reject
@@ -234,25 +732,52 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
             // continuously. Restarting (rather than clearing) preserves the invariant
             // that `stable_ms == 0` completes as soon as the completion zone is entered.
             let band_cg = self.hysteresis_cg.max(self.epsilon_cg).unsigned_abs();
-            match self.settled_since_ms {
-                None => self.settled_since_ms = Some(now),
-                Some(_) if abs_err_cg > band_cg => self.settled_since_ms = Some(now),
-                Some(_) => {}
+            let in_band = abs_err_cg <= band_cg;
+            self.phase = Some(if in_band {
+                DosePhase::Verify
+            } else {
+                DosePhase::Settle
+            });
+            if self.settled_since_ms.is_none() || !in_band {
+                self.settled_since_ms = Some(now);
+                self.settle_sum_cg = 0;
+                self.settle_samples = 0;
+            }
+            if in_band {
+                self.settle_sum_cg += i64::from(w_cg);
+                self.settle_samples += 1;
             }
             if let Some(since) = self.settled_since_ms
                 && now.saturating_sub(since) >= self.control.stable_ms
             {
                 return Ok(DosingStatus::Complete);
             }
-            self.clock.sleep(Duration::from_micros(self.period_us));
+            self.pace();
             return Ok(DosingStatus::Running);
         } else {
             self.settled_since_ms = None;
         }
 
+        // After an early stop, give the in-flight mass the predictor's latency to
+        // land before judging the shortfall.
+        if let Some(stopped_ms) = self.early_stop_ms {
+            if now.saturating_sub(stopped_ms) < self.pred_latency_ms {
+                self.phase = Some(DosePhase::Settle);
+                self.pace();
+                return Ok(DosingStatus::Running);
+            }
+            self.early_stop_ms = None;
+            if let Some(status) = self.on_undershoot(err_cg) {
+                return Ok(status);
+            }
+        }
+
         // Speed selection via bands or legacy fallback
+        self.update_band(err_cg, now);
         let target_speed = self.select_speed(err_cg, abs_err_cg);
 
+        self.flow.record(now, w_cg, self.commanded_sps);
+
         // No-progress watchdog
         if self.safety.no_progress_ms > 0 && self.no_progress_epsilon_cg > 0 && target_speed > 0 {
             let progress_delta_cg = abs_diff_i32_u32(w_cg, self.last_progress_cg);
@@ -260,52 +785,149 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
                 self.last_progress_cg = w_cg;
                 self.last_progress_at_ms = now;
             } else if now.saturating_sub(self.last_progress_at_ms) >= self.safety.no_progress_ms {
-                self.motor_stop_best_effort("no-progress watchdog");
-                return Ok(DosingStatus::Aborted(DoserError::Abort(
-                    AbortReason::NoProgress,
-                )));
+                return Ok(self.abort("no-progress watchdog", AbortReason::NoProgress));
             }
         }
 
+        self.check_motor_alive()?;
+
         // Motor commands
         if !self.motor_started {
+            let max = self.control.max_attempts;
+            if max > 0 && self.attempts_started >= max {
+                tracing::warn!(attempts = max, "no approach left");
+                return Ok(self.abort("max attempts", AbortReason::MaxAttempts));
+            }
             self.motor
                 .start()
                 .map_err(|e| eyre::Report::new(map_hw_error(&*e)))
                 .wrap_err("motor start")?;
             self.motor_started = true;
+            self.begin_attempt(now, w_cg);
         }
-        self.motor
-            .set_speed(target_speed)
-            .map_err(|e| eyre::Report::new(map_hw_error(&*e)))
-            .wrap_err("set_speed")?;
+        if self.speed_change_due(target_speed) {
+            self.motor
+                .set_speed(target_speed)
+                .map_err(|e| eyre::Report::new(map_hw_error(&*e)))
+                .wrap_err("set_speed")?;
+            self.commanded_sps = target_speed;
+        }
+        self.phase = Some(if self.commanded_sps >= self.coarse_sps() {
+            DosePhase::Coarse
+        } else {
+            DosePhase::Fine
+        });
 
-        self.clock.sleep(Duration::from_micros(self.period_us));
+        self.pace();
         Ok(DosingStatus::Running)
     }
 
+    /// Record a settled shortfall after an early stop and apply the configured
+    /// policy. `None` means keep dosing (top-up).
+    fn on_undershoot(&mut self, short_cg: i32) -> Option<DosingStatus> {
+        self.undershoot_cg.get_or_insert(short_cg);
+        let policy = self.predictor.undershoot;
+        tracing::warn!(
+            undershoot_g = (short_cg as f32) * 0.01,
+            ?policy,
+            "early stop settled below target"
+        );
+        match policy {
+            UndershootPolicy::TopUp => None,
+            UndershootPolicy::Accept => Some(DosingStatus::Complete),
+            UndershootPolicy::Abort => Some(self.abort("undershoot", AbortReason::Undershoot)),
+        }
+    }
+
+    /// Record the start of an approach at weight `w_cg`.
+    fn begin_attempt(&mut self, now: u64, w_cg: i32) {
+        self.attempts_started = self.attempts_started.saturating_add(1);
+        let attempt = Attempt {
+            n: self.attempts_started,
+            start_ms: now.saturating_sub(self.start_ms),
+            start_g: w_cg as f32 / 100.0,
+            stop_ms: None,
+            stop_g: None,
+        };
+        tracing::info!(
+            attempt = attempt.n,
+            start_g = attempt.start_g,
+            "approach started"
+        );
+        if self.attempts.len() >= ATTEMPT_RECORDS {
+            self.attempts.pop();
+        }
+        self.attempts.push(attempt);
+    }
+
+    /// Close the running approach, if any.
+    fn end_attempt(&mut self, now: u64) {
+        let ms = now.saturating_sub(self.start_ms);
+        let w_g = self.last_weight_cg as f32 / 100.0;
+        if let Some(a) = self.attempts.last_mut()
+            && a.stop_ms.is_none()
+        {
+            a.stop_ms = Some(ms);
+            a.stop_g = Some(w_g);
+            tracing::info!(attempt = a.n, stop_g = w_g, "approach stopped");
+        }
+    }
+
+    /// Whether `target_sps` should be sent to the motor: always after a stop,
+    /// otherwise only when it moved by at least `min_speed_delta_sps`.
+    fn speed_change_due(&self, target_sps: u32) -> bool {
+        if self.commanded_sps == 0 {
+            return true;
+        }
+        let delta = target_sps.abs_diff(self.commanded_sps);
+        delta > 0 && delta >= self.control.min_speed_delta_sps
+    }
+
+    /// Charge the time since the previous step to the phase the loop was in.
+    fn account_phase(&mut self, now: u64) {
+        if let Some(phase) = self.phase {
+            self.phase_timings
+                .add(phase, now.saturating_sub(self.phase_mark_ms));
+        }
+        self.phase_mark_ms = now;
+    }
+
+    /// Fastest configured speed; running at it counts as the coarse phase.
+    fn coarse_sps(&self) -> u32 {
+        self.speed_bands_cg
+            .iter()
+            .map(|&(_, sps)| sps)
+            .max()
+            .unwrap_or(self.control.coarse_speed)
+    }
+
+    /// Sleep until the next iteration deadline. Deadlines advance by exactly one
+    /// period from the previous one, so time spent reading and computing is not
+    /// added on top of the period. Overruns are handled per the `OverrunPolicy`.
+    fn pace(&mut self) {
+        let before = self.pacer.overruns();
+        let _ = self.pacer.tick(&*self.clock, self.period_us);
+        if self.pacer.overruns() != before {
+            let overruns = self.loop_overruns();
+            if self.pacer.overrun_policy() == OverrunPolicy::LogAndDegrade {
+                tracing::warn!(
+                    period_us = self.period_us,
+                    overruns,
+                    "control loop overran its period; degrading cadence"
+                );
+            } else {
+                tracing::trace!(period_us = self.period_us, overruns, "control loop overrun");
+            }
+        }
+    }
+
     /// Select motor speed based on error magnitude.
     fn select_speed(&self, err_cg: i32, abs_err_cg: u32) -> u32 {
         if !self.speed_bands_cg.is_empty() {
-            let mut selected_band: Option<(i32, u32)> = None;
-            let mut target_speed = self.control.coarse_speed;
+            let idx = self.band_idx.unwrap_or_else(|| self.band_index(err_cg));
+            let (thr_cg, target_speed) = self.speed_bands_cg[idx];
             let err_g = (err_cg.max(0) as f32) / 100.0;
-            for (thr_cg, sps) in &self.speed_bands_cg {
-                if err_cg >= *thr_cg {
-                    selected_band = Some((*thr_cg, *sps));
-                    target_speed = *sps;
-                    break;
-                }
-            }
-            if selected_band.is_none()
-                && let Some((_thr_cg, sps)) = self.speed_bands_cg.last().copied()
-            {
-                selected_band = Some((_thr_cg, sps));
-                target_speed = sps;
-            }
-            let thr_g = selected_band
-                .map(|(cg, _)| (cg as f32) / 100.0)
-                .unwrap_or(0.0);
+            let thr_g = (thr_cg as f32) / 100.0;
             tracing::trace!(
                 err_g,
                 band_threshold_g = thr_g,
@@ -333,10 +955,47 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
         }
     }
 
+    /// Band for `err_cg`: the first whose threshold it reaches, else the slowest.
+    /// Requires non-empty `speed_bands_cg`.
+    fn band_index(&self, err_cg: i32) -> usize {
+        self.speed_bands_cg
+            .iter()
+            .position(|&(thr_cg, _)| err_cg >= thr_cg)
+            .unwrap_or(self.speed_bands_cg.len() - 1)
+    }
+
+    /// Move the held speed band. Going to a faster band (the error grew) needs
+    /// the error to clear its threshold by `band_hysteresis_g` and the current
+    /// band to have been held for `band_min_dwell_ms`; going slower is immediate.
+    fn update_band(&mut self, err_cg: i32, now: u64) {
+        if self.speed_bands_cg.is_empty() {
+            return;
+        }
+        let raw = self.band_index(err_cg);
+        let next = match self.band_idx {
+            Some(cur) if raw < cur => {
+                let dwelled =
+                    now.saturating_sub(self.band_since_ms) >= self.control.band_min_dwell_ms;
+                let up = self
+                    .band_index(err_cg.saturating_sub(self.band_hysteresis_cg))
+                    .min(cur);
+                if dwelled { up } else { cur }
+            }
+            _ => raw,
+        };
+        if self.band_idx != Some(next) {
+            if let Some(cur) = self.band_idx {
+                tracing::debug!(from = cur, to = next, "speed band change");
+            }
+            self.band_idx = Some(next);
+            self.band_since_ms = now;
+        }
+    }
+
     #[inline]
     fn to_cg_cached(&self, raw: i32) -> i32 {
         let delta = (raw as i64) - (self.calibration.zero_counts as i64);
-        crate::fixed_point::cg_from_delta_scaled(delta, self.cal_gain_scaled, self.cal_offset_cg)
+        self.cal_gain.cg_from_delta(delta, self.cal_offset_cg)
     }
 
     /// Out-of-band E-stop poll for orchestrators (e.g. the sampler runner).
@@ -348,7 +1007,7 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
     /// E-stop is (or becomes) latched, stopping the motor best-effort.
     pub fn poll_estop_stop(&mut self) -> bool {
         if self.estop_latched || self.poll_estop() {
-            self.motor_stop_best_effort("estop (out-of-band)");
+            self.abort("estop (out-of-band)", AbortReason::Estop);
             true
         } else {
             false
@@ -370,88 +1029,27 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
         self.estop_latched
     }
 
-    fn apply_filter(&mut self, w_cg: i32) -> i32 {
-        let med_win = self.filter.median_window.max(1);
-        let ma_win = self.filter.ma_window.max(1);
-        let ema_alpha = if self.filter.ema_alpha.is_finite() {
-            self.filter.ema_alpha
-        } else {
-            0.0
-        };
-
-        // Median prefilter
-        let after_median = if med_win > 1 {
-            self.med_buf.push_back(w_cg);
-            if self.med_buf.len() > med_win {
-                self.med_buf.pop_front();
-            }
-            self.tmp_med_buf.clear();
-            self.tmp_med_buf.extend(self.med_buf.iter().copied());
-            let n = self.tmp_med_buf.len();
-            #[cfg(debug_assertions)]
-            {
-                let med_len = self.med_buf.len();
-                debug_assert!(n > 0, "median buffer unexpectedly empty");
-                debug_assert_eq!(med_len, n);
-                debug_assert!(n <= med_win, "median buffer exceeded window size");
-            }
-            let mid = n / 2;
-            // O(n) selection rather than a full O(n log n) sort: same result, less
-            // per-sample work and jitter on the control loop.
-            if n.is_multiple_of(2) {
-                let (lo, mid_val, _) = self.tmp_med_buf.select_nth_unstable(mid);
-                let mid_val = *mid_val;
-                // Even window: the lower-middle order statistic is the max of the
-                // lower partition (non-empty since mid >= 1 when n is even and > 0).
-                let lower = lo.iter().copied().max().unwrap_or(mid_val);
-                avg2_round_nearest_i32(lower, mid_val)
-            } else {
-                *self.tmp_med_buf.select_nth_unstable(mid).1
-            }
-        } else {
-            w_cg
-        };
-
-        // Smoothing: EMA, Moving Average, or passthrough
-        if ema_alpha > 0.0 {
-            let x = after_median as f32;
-            let alpha = ema_alpha.clamp(0.0, 1.0);
-            let y = match self.ema_prev_cg {
-                None => x,
-                Some(prev) => alpha * x + (1.0 - alpha) * prev,
-            };
-            self.ema_prev_cg = Some(y);
-            y.round() as i32
-        } else if ma_win > 1 {
-            self.ma_buf.push_back(after_median);
-            if self.ma_buf.len() > ma_win {
-                self.ma_buf.pop_front();
-            }
-            let sum_i128: i128 = self.ma_buf.iter().map(|&v| v as i128).sum();
-            let len_i32 = self.ma_buf.len() as i32;
-            if len_i32 > 0 {
-                if (i32::MIN as i128..=i32::MAX as i128).contains(&sum_i128) {
-                    div_round_nearest_i32(sum_i128 as i32, len_i32)
-                } else {
-                    let n = len_i32 as i128;
-                    let q = if sum_i128 >= 0 {
-                        (sum_i128 + n / 2) / n
-                    } else {
-                        (sum_i128 - n / 2) / n
-                    };
-                    #[cfg(debug_assertions)]
-                    debug_assert!(
-                        (i32::MIN as i128..=i32::MAX as i128).contains(&q),
-                        "moving-average result out of i32 range"
-                    );
-                    q as i32
-                }
-            } else {
-                0
-            }
-        } else {
-            after_median
+    /// For [`InflightModel::Decel`]: the speed the controller would command at
+    /// weight `w_cg` and the steps (as sps·ms) commanded across the predictor
+    /// window. `None` selects the linear model (also when the window saw no
+    /// commanded motion).
+    fn decel_rate(&self, w_cg: i32) -> Option<(u32, i64)> {
+        if self.predictor.model != InflightModel::Decel {
+            return None;
         }
+        let step_ms: i64 = self
+            .pred_hist
+            .iter()
+            .zip(self.pred_hist.iter().skip(1))
+            .map(|(&(t0, _, _), &(t1, _, sps))| {
+                i64::from(sps).saturating_mul(t1.saturating_sub(t0) as i64)
+            })
+            .sum();
+        if step_ms <= 0 {
+            return None;
+        }
+        let err_cg = self.target_cg - w_cg;
+        Some((self.select_speed(err_cg, err_cg.unsigned_abs()), step_ms))
     }
 
     /// Update predictor history and decide whether to stop early this iteration.
@@ -464,7 +1062,7 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
         if self.target_cg > 0 {
             let progress = (w_cg as f32) / (self.target_cg as f32);
             if progress < self.predictor.min_progress_ratio {
-                self.pred_hist.push_back((now_ms, w_cg));
+                self.pred_hist.push_back((now_ms, w_cg, self.commanded_sps));
                 if self.pred_hist.len() > self.predictor.window.max(1) {
                     self.pred_hist.pop_front();
                 }
@@ -473,7 +1071,7 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
         }
 
         // Maintain rolling window
-        self.pred_hist.push_back((now_ms, w_cg));
+        self.pred_hist.push_back((now_ms, w_cg, self.commanded_sps));
         let max_len = self.predictor.window.max(1);
         if self.pred_hist.len() > max_len {
             self.pred_hist.pop_front();
@@ -482,7 +1080,7 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
             return false;
         }
 
-        let Some((t0, w0)) = self.pred_hist.front().copied() else {
+        let Some((t0, w0, _)) = self.pred_hist.front().copied() else {
             return false;
         };
         let dt_ms = now_ms.saturating_sub(t0);
@@ -494,8 +1092,21 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
             return false;
         }
 
-        let num: i64 = dw_cg.saturating_mul(self.pred_latency_ms as i64);
-        let den: i64 = (dt_ms as i64).max(1);
+        let (num, den) = match self.decel_rate(w_cg) {
+            // Mass per step from the window, times the steps the planned speed
+            // will deliver over the latency.
+            Some((planned_sps, step_ms)) => (
+                dw_cg
+                    .saturating_mul(i64::from(planned_sps))
+                    .saturating_mul(self.pred_latency_ms as i64),
+                step_ms.max(1),
+            ),
+            // Current slope continues for the latency.
+            None => (
+                dw_cg.saturating_mul(self.pred_latency_ms as i64),
+                (dt_ms as i64).max(1),
+            ),
+        };
         let half = den >> 1;
         let inflight_i64 = if num >= 0 {
             (num + half) / den
@@ -505,7 +1116,7 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
         let inflight_cg = inflight_i64.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
 
         // Telemetry
-        let slope_cg_per_ms = (dw_cg as f32) / (den as f32);
+        let slope_cg_per_ms = (dw_cg as f32) / (dt_ms.max(1) as f32);
         let alpha = if self.filter.ema_alpha.is_finite() && self.filter.ema_alpha > 0.0 {
             self.filter.ema_alpha
         } else {
@@ -523,6 +1134,7 @@ impl<S: doser_traits::Scale, M: doser_traits::Motor> DoserCore<S, M> {
         if predicted >= self.target_cg {
             self.motor_stop_best_effort("predictor early-stop");
             self.early_stop_at_cg = Some(w_cg);
+            self.early_stop_ms.get_or_insert(now_ms);
             tracing::debug!(
                 w_cg,
                 inflight_cg,
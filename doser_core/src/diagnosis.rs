@@ -0,0 +1,235 @@
+//! Abort classification: a stable, machine-readable remediation code per
+//! failure, so an HMI can show a targeted instruction ("refill the hopper",
+//! "clear the auger") instead of the generic abort text.
+//!
+//! Most codes follow from the abort reason or error type alone. A no-progress
+//! abort is told apart from the flow the dose had before it stalled, kept in
+//! a [`FlowTrace`]: nothing ever landed ([`Remediation::NoFlow`]), the flow per
+//! motor step faded out ([`Remediation::HopperEmpty`]), or it stopped while
+//! still running at its usual rate ([`Remediation::Jam`]).
+//!
+//! The runner attaches the code to an abort's report as context; find it with
+//! `err.downcast_ref::<Remediation>()`. The last control steps before the
+//! abort ride along as an [`AbortTrace`], for post-mortem bundles.
+
+use std::collections::VecDeque;
+
+use crate::error::{AbortReason, PreflightFailure};
+use crate::status::DosePhase;
+
+/// Suggested remediation for a failed dose. The codes are stable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Remediation {
+    Estop,
+    /// The flow faded out: the hopper (or feed) ran empty.
+    HopperEmpty,
+    /// The flow stopped abruptly: auger or outlet blocked.
+    Jam,
+    /// Nothing reached the scale: empty hopper, blocked outlet or motor not
+    /// turning.
+    NoFlow,
+    /// No progress, cause unknown (no flow history).
+    NoProgress,
+    /// Scale timeouts, unreadable or saturated readings.
+    Sensor,
+    /// Readings spread while nothing should move (vibration, draught).
+    ScaleUnstable,
+    Overshoot,
+    Undershoot,
+    MaxRuntime,
+    MaxAttempts,
+    /// The motor driver did not respond.
+    Motor,
+    /// Another process holds the hardware.
+    Busy,
+    /// The configuration is invalid.
+    Config,
+}
+
+impl Remediation {
+    /// The stable code, e.g. `E_HOPPER_EMPTY`.
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::Estop => "E_ESTOP",
+            Self::HopperEmpty => "E_HOPPER_EMPTY",
+            Self::Jam => "E_JAM",
+            Self::NoFlow => "E_NO_FLOW",
+            Self::NoProgress => "E_NO_PROGRESS",
+            Self::Sensor => "E_SENSOR",
+            Self::ScaleUnstable => "E_SCALE_UNSTABLE",
+            Self::Overshoot => "E_OVERSHOOT",
+            Self::Undershoot => "E_UNDERSHOOT",
+            Self::MaxRuntime => "E_MAX_RUNTIME",
+            Self::MaxAttempts => "E_MAX_ATTEMPTS",
+            Self::Motor => "E_MOTOR",
+            Self::Busy => "E_BUSY",
+            Self::Config => "E_CONFIG",
+        }
+    }
+
+    /// The code for an abort known only by its reason.
+    pub const fn for_abort(reason: &AbortReason) -> Self {
+        match reason {
+            AbortReason::Estop => Self::Estop,
+            AbortReason::NoProgress => Self::NoProgress,
+            AbortReason::MaxRuntime => Self::MaxRuntime,
+            AbortReason::Overshoot => Self::Overshoot,
+            AbortReason::MaxAttempts => Self::MaxAttempts,
+            AbortReason::Undershoot => Self::Undershoot,
+        }
+    }
+
+    /// The code for a failed pre-flight check.
+    pub const fn for_preflight(failure: &PreflightFailure) -> Self {
+        match failure {
+            PreflightFailure::EstopActive => Self::Estop,
+            PreflightFailure::ScaleTimeout
+            | PreflightFailure::ScaleDataReadyTimeout
+            | PreflightFailure::ScaleUnreadable(_)
+            | PreflightFailure::ScaleSaturated(_)
+            | PreflightFailure::WeightOutOfBand { .. }
+            | PreflightFailure::HopperUnreadable(_) => Self::Sensor,
+            PreflightFailure::ScaleUnstable { .. } => Self::ScaleUnstable,
+            PreflightFailure::UnitsMismatch { .. } => Self::Config,
+            PreflightFailure::MotorEnable(_) => Self::Motor,
+            PreflightFailure::HopperLow { .. } => Self::HopperEmpty,
+        }
+    }
+}
+
+impl core::fmt::Display for Remediation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "remediation {}", self.code())
+    }
+}
+
+/// Control steps kept for an [`AbortTrace`] (5 s at 100 Hz).
+pub const TRACE_STEPS: usize = 500;
+
+/// One control step, as kept for a post-mortem.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepSample {
+    /// Milliseconds since the dose began
+    pub ms: u64,
+    /// Control weight in grams
+    pub weight_g: f32,
+    /// Motor speed in effect (0 = stopped)
+    pub sps: u32,
+    pub phase: Option<DosePhase>,
+}
+
+/// The last control steps before an abort, oldest first; attached to the
+/// abort's report as context (`err.downcast_ref::<AbortTrace>()`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AbortTrace {
+    pub steps: Vec<StepSample>,
+    /// The motor backend's stepping counters at the abort, if it keeps them
+    pub motor: Option<doser_traits::MotorDiagSnapshot>,
+}
+
+impl core::fmt::Display for AbortTrace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "last {} control steps", self.steps.len())
+    }
+}
+
+/// Spacing of the kept flow samples.
+const TRACE_SPACING_MS: u64 = 100;
+/// Samples kept (10 s at the spacing above).
+const TRACE_LEN: usize = 100;
+/// Span the flow rate is measured over.
+const RATE_SPAN_MS: u64 = 500;
+/// A stall whose last flow was below this fraction of the dose's peak flow
+/// per step counts as faded out rather than stopped.
+const FADED_RATIO: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy)]
+struct FlowSample {
+    ms: u64,
+    cg: i32,
+    /// Motor steps commanded since the dose began.
+    steps: f64,
+}
+
+/// Recent weight against motor steps commanded, for telling why a dose stalled.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FlowTrace {
+    start_cg: Option<i32>,
+    samples: VecDeque<FlowSample>,
+    steps: f64,
+    last_ms: Option<u64>,
+}
+
+impl FlowTrace {
+    /// Note the weight at `ms`, the motor having run at `sps` since the
+    /// previous call.
+    pub(crate) fn record(&mut self, ms: u64, cg: i32, sps: u32) {
+        if let Some(last_ms) = self.last_ms {
+            self.steps += f64::from(sps) * ms.saturating_sub(last_ms) as f64 / 1000.0;
+        }
+        self.last_ms = Some(ms);
+        self.start_cg.get_or_insert(cg);
+        if self
+            .samples
+            .back()
+            .is_some_and(|s| ms.saturating_sub(s.ms) < TRACE_SPACING_MS)
+        {
+            return;
+        }
+        if self.samples.len() == TRACE_LEN {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(FlowSample {
+            ms,
+            cg,
+            steps: self.steps,
+        });
+    }
+
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Motor steps commanded since the dose began, up to the last record.
+    pub(crate) fn steps(&self) -> f64 {
+        self.steps
+    }
+
+    /// Grams per motor step over the span ending at `to_ms`.
+    fn g_per_step(&self, to_ms: u64) -> Option<f32> {
+        let end = self.samples.iter().rev().find(|s| s.ms <= to_ms)?;
+        let start = self
+            .samples
+            .iter()
+            .rev()
+            .find(|s| s.ms + RATE_SPAN_MS <= end.ms)?;
+        let steps = end.steps - start.steps;
+        (steps > 0.0).then(|| ((end.cg - start.cg) as f64 / 100.0 / steps) as f32)
+    }
+
+    /// Why the flow stalled at `stalled_since_ms`, given that less than
+    /// `epsilon_cg` counts as nothing landing.
+    pub(crate) fn classify_stall(&self, stalled_since_ms: u64, epsilon_cg: i32) -> Remediation {
+        let (Some(start), Some(last)) = (self.start_cg, self.samples.back()) else {
+            return Remediation::NoProgress;
+        };
+        if last.cg - start < epsilon_cg.max(1) {
+            return Remediation::NoFlow;
+        }
+        let peak = self
+            .samples
+            .iter()
+            .filter(|s| s.ms <= stalled_since_ms)
+            .filter_map(|s| self.g_per_step(s.ms))
+            .fold(None, |acc: Option<f32>, r| {
+                Some(acc.map_or(r, |a| a.max(r)))
+            });
+        match (self.g_per_step(stalled_since_ms), peak) {
+            (Some(recent), Some(peak)) if peak > 0.0 && recent < FADED_RATIO * peak => {
+                Remediation::HopperEmpty
+            }
+            (Some(_), Some(_)) => Remediation::Jam,
+            _ => Remediation::NoProgress,
+        }
+    }
+}
@@ -9,6 +9,9 @@ pub enum AbortReason {
     MaxRuntime,
     Overshoot,
     MaxAttempts,
+    /// The predictor stopped early and the mass settled short of target
+    /// (`UndershootPolicy::Abort`).
+    Undershoot,
 }
 
 impl core::fmt::Display for AbortReason {
@@ -19,6 +22,7 @@ impl core::fmt::Display for AbortReason {
             AbortReason::MaxRuntime => write!(f, "max run time exceeded"),
             AbortReason::Overshoot => write!(f, "max overshoot exceeded"),
             AbortReason::MaxAttempts => write!(f, "max attempts exceeded"),
+            AbortReason::Undershoot => write!(f, "undershoot after early stop"),
         }
     }
 }
@@ -31,8 +35,12 @@ pub enum DoserError {
     HardwareFault(String),
     #[error("configuration error: {0}")]
     Config(String),
+    /// No reading within the core sensor timeout (`Timeouts::sensor_ms`).
     #[error("timeout waiting for sensor")]
     Timeout,
+    /// The ADC never signalled data-ready within its own (hardware) timeout.
+    #[error("timeout waiting for sensor data-ready")]
+    DataReadyTimeout,
     #[error("aborted: {0}")]
     Abort(AbortReason),
     #[error("io error: {0}")]
@@ -51,6 +59,92 @@ pub enum BuildError {
     InvalidConfig(&'static str),
 }
 
+/// One failed pre-flight check.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreflightFailure {
+    /// The E-stop input is active.
+    EstopActive,
+    /// The scale timed out.
+    ScaleTimeout,
+    /// The ADC never signalled data-ready.
+    ScaleDataReadyTimeout,
+    /// The scale returned an error.
+    ScaleUnreadable(String),
+    /// The ADC reported a rail value (disconnected or overloaded cell).
+    ScaleSaturated(i32),
+    /// Readings spread more than allowed while nothing should be moving.
+    ScaleUnstable { spread_g: f32, max_spread_g: f32 },
+    /// The starting weight is outside the configured plausible range.
+    WeightOutOfBand { grams: f32 },
+    /// The no-load reading does not convert to the expected weight: most
+    /// likely the calibration of another scale or in other units.
+    UnitsMismatch {
+        grams: f32,
+        expected_g: f32,
+        band_g: f32,
+    },
+    /// Toggling the motor driver enable failed.
+    MotorEnable(String),
+    /// The hopper scale could not be read.
+    HopperUnreadable(String),
+    /// The hopper holds less than the dose plus the reserve.
+    HopperLow { available_g: f32, needed_g: f32 },
+}
+
+impl core::fmt::Display for PreflightFailure {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::EstopActive => write!(f, "E-stop is active"),
+            Self::ScaleTimeout => write!(f, "scale read timed out"),
+            Self::ScaleDataReadyTimeout => write!(f, "scale data-ready timed out"),
+            Self::ScaleUnreadable(e) => write!(f, "scale unreadable: {e}"),
+            Self::ScaleSaturated(raw) => write!(f, "scale saturated (raw {raw})"),
+            Self::ScaleUnstable {
+                spread_g,
+                max_spread_g,
+            } => write!(
+                f,
+                "scale unstable (spread {spread_g:.2} g > {max_spread_g:.2} g)"
+            ),
+            Self::WeightOutOfBand { grams } => {
+                write!(f, "starting weight {grams:.2} g outside sanity band")
+            }
+            Self::UnitsMismatch {
+                grams,
+                expected_g,
+                band_g,
+            } => write!(
+                f,
+                "no-load reading {grams:.2} g, expected {expected_g:.2} ± {band_g:.2} g (wrong calibration file?)"
+            ),
+            Self::MotorEnable(e) => write!(f, "motor enable toggle failed: {e}"),
+            Self::HopperUnreadable(e) => write!(f, "hopper scale unreadable: {e}"),
+            Self::HopperLow {
+                available_g,
+                needed_g,
+            } => write!(
+                f,
+                "hopper holds {available_g:.1} g, dose needs {needed_g:.1} g"
+            ),
+        }
+    }
+}
+
+/// Pre-flight checks failed; the motor was never started.
+#[derive(Debug, Error, Clone, PartialEq)]
+#[error("pre-flight failed: {}", list(.failures))]
+pub struct PreflightError {
+    pub failures: Vec<PreflightFailure>,
+}
+
+fn list(failures: &[PreflightFailure]) -> String {
+    failures
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 pub type Result<T> = eyre::Result<T>;
 pub use eyre::Report;
 
@@ -64,5 +158,6 @@ mod tests {
         assert_eq!(MaxRuntime.to_string(), "max run time exceeded");
         assert_eq!(Overshoot.to_string(), "max overshoot exceeded");
         assert_eq!(MaxAttempts.to_string(), "max attempts exceeded");
+        assert_eq!(Undershoot.to_string(), "undershoot after early stop");
     }
 }
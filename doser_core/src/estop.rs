@@ -0,0 +1,82 @@
+//! Out-of-band E-stop monitor.
+//!
+//! The control loop polls the E-stop once per step, so at 10 SPS a press can
+//! go unseen for up to 100 ms while the loop waits on the scale. The monitor
+//! polls the same input on its own thread and, once the press is debounced,
+//! trips the motor's [`StopLatch`]: the backend stops stepping within one
+//! poll period, without waiting for the loop. The loop still latches the
+//! E-stop and aborts on its next step.
+//!
+//! Like `Sampler`, each monitor owns one thread, stopped when it is dropped.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use doser_traits::StopLatch;
+
+/// E-stop input, shared between the monitor and the control loop.
+pub type EstopCheck = Arc<dyn Fn() -> bool + Send + Sync>;
+
+pub struct EstopMonitor {
+    tripped: Arc<AtomicBool>,
+    shutdown: Arc<AtomicBool>,
+    join_handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl EstopMonitor {
+    /// Poll `check` every `poll` and trip `latch` after `debounce_n`
+    /// consecutive active polls. Resets the latch first: a new monitor starts a
+    /// new run.
+    pub fn spawn(check: EstopCheck, latch: StopLatch, poll: Duration, debounce_n: u8) -> Self {
+        latch.reset();
+        let tripped = Arc::new(AtomicBool::new(false));
+        let tripped_bg = Arc::clone(&tripped);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_bg = Arc::clone(&shutdown);
+        let poll = poll.max(Duration::from_millis(1));
+        let debounce_n = debounce_n.max(1);
+
+        let span = tracing::Span::current();
+        let join_handle = std::thread::spawn(move || {
+            let _span = span.entered();
+            let mut count: u8 = 0;
+            while !shutdown_bg.load(Ordering::Relaxed) {
+                if check() {
+                    count = count.saturating_add(1);
+                    if count >= debounce_n {
+                        latch.trip();
+                        tripped_bg.store(true, Ordering::Release);
+                        tracing::error!("E-stop: motor stopped by the monitor");
+                        break;
+                    }
+                } else {
+                    count = 0;
+                }
+                std::thread::sleep(poll);
+            }
+            tracing::trace!("E-stop monitor exiting");
+        });
+        Self {
+            tripped,
+            shutdown,
+            join_handle: Some(join_handle),
+        }
+    }
+
+    /// True once the monitor has tripped the latch.
+    pub fn tripped(&self) -> bool {
+        self.tripped.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for EstopMonitor {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.join_handle.take()
+            && handle.join().is_err()
+        {
+            tracing::warn!("E-stop monitor thread panicked");
+        }
+    }
+}
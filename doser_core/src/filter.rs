@@ -0,0 +1,512 @@
+//! Composable signal-conditioning pipeline for the control loop.
+//!
+//! Each sample (in centigrams) flows through an ordered list of [`FilterStage`]s.
+//! The default pipeline is derived from [`FilterCfg`] (median prefilter, then
+//! EMA or a moving, weighted or trimmed average), but stages can also be assembled programmatically,
+//! e.g. `median → notch → EMA → custom rate limiter`.
+//!
+//! All buffers are sized when a stage is constructed, so `process()` never
+//! allocates on the control-loop hot path.
+
+use std::collections::VecDeque;
+
+use crate::config::{FilterCfg, Smoothing};
+use crate::error::BuildError;
+use crate::fixed_point::avg2_round_nearest_i32;
+use crate::util::div_round_nearest_i32;
+
+/// One step of the filter pipeline.
+///
+/// Implementations receive and return weights in centigrams. `process` is called
+/// once per sample on the control loop and must not allocate or block.
+pub trait FilterStage {
+    /// Feed one sample and return the filtered value.
+    fn process(&mut self, x_cg: i32) -> i32;
+    /// Clear any internal state (called at the start of each dose).
+    fn reset(&mut self);
+}
+
+/// Ordered chain of filter stages applied to every sample.
+#[derive(Default)]
+pub struct FilterPipeline {
+    stages: Vec<Box<dyn FilterStage>>,
+}
+
+impl core::fmt::Debug for FilterPipeline {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FilterPipeline")
+            .field("stages", &self.stages.len())
+            .finish()
+    }
+}
+
+impl FilterPipeline {
+    /// Empty pipeline (passthrough).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the standard pipeline described by `cfg`:
+    /// median prefilter (when `median_window > 1`), then EMA (when `ema_alpha > 0`)
+    /// or the `smoothing` average (when `ma_window > 1`).
+    pub fn from_cfg(cfg: &FilterCfg) -> Self {
+        let mut p = Self::prefilter(cfg);
+        let alpha = if cfg.ema_alpha.is_finite() {
+            cfg.ema_alpha
+        } else {
+            0.0
+        };
+        if alpha > 0.0 {
+            p.push(EmaStage::new(alpha));
+        } else if cfg.ma_window > 1 {
+            match cfg.smoothing {
+                Smoothing::MovingAverage => p.push(MovingAverageStage::new(cfg.ma_window)),
+                Smoothing::Weighted => p.push(WeightedMovingAverageStage::new(cfg.ma_window)),
+                Smoothing::TrimmedMean => p.push(TrimmedMeanStage::new(cfg.ma_window, cfg.trim)),
+            }
+        }
+        p
+    }
+
+    /// Only the median prefilter of [`Self::from_cfg`] (passthrough when
+    /// `median_window <= 1`): spike rejection without smoothing lag.
+    pub fn prefilter(cfg: &FilterCfg) -> Self {
+        let mut p = Self::new();
+        if cfg.median_window > 1 {
+            p.push(MedianStage::new(cfg.median_window));
+        }
+        p
+    }
+
+    /// Append a stage to the end of the pipeline.
+    pub fn push(&mut self, stage: impl FilterStage + 'static) {
+        self.stages.push(Box::new(stage));
+    }
+
+    /// Chainable variant of [`Self::push`].
+    pub fn with_stage(mut self, stage: impl FilterStage + 'static) -> Self {
+        self.push(stage);
+        self
+    }
+
+    /// Number of stages in the pipeline.
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// True when the pipeline is a passthrough.
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Run one sample through every stage in order.
+    #[inline]
+    pub fn process(&mut self, x_cg: i32) -> i32 {
+        self.stages.iter_mut().fold(x_cg, |x, s| s.process(x))
+    }
+
+    /// Reset the state of every stage.
+    pub fn reset(&mut self) {
+        for s in &mut self.stages {
+            s.reset();
+        }
+    }
+}
+
+// ── Built-in stages ──────────────────────────────────────────────────────────
+
+/// Sliding-window median; rejects isolated spikes.
+///
+/// Even windows return the rounded average of the two middle order statistics.
+#[derive(Debug, Clone)]
+pub struct MedianStage {
+    window: usize,
+    buf: VecDeque<i32>,
+    scratch: Vec<i32>,
+}
+
+impl MedianStage {
+    /// Median of the last `window` samples; 0 is clamped to 1, and 1 passes
+    /// samples through unchanged. Until the window fills, the median is over
+    /// the samples seen so far.
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            buf: VecDeque::with_capacity(window),
+            scratch: Vec::with_capacity(window),
+        }
+    }
+}
+
+impl FilterStage for MedianStage {
+    fn process(&mut self, x_cg: i32) -> i32 {
+        if self.buf.len() == self.window {
+            self.buf.pop_front();
+        }
+        self.buf.push_back(x_cg);
+        self.scratch.clear();
+        self.scratch.extend(self.buf.iter().copied());
+        let n = self.scratch.len();
+        debug_assert!(n > 0 && n <= self.window, "median buffer out of bounds");
+        let mid = n / 2;
+        // O(n) selection rather than a full O(n log n) sort: same result, less
+        // per-sample work and jitter on the control loop.
+        if n.is_multiple_of(2) {
+            let (lo, mid_val, _) = self.scratch.select_nth_unstable(mid);
+            let mid_val = *mid_val;
+            // Even window: the lower-middle order statistic is the max of the
+            // lower partition (non-empty since mid >= 1 when n is even and > 0).
+            let lower = lo.iter().copied().max().unwrap_or(mid_val);
+            avg2_round_nearest_i32(lower, mid_val)
+        } else {
+            *self.scratch.select_nth_unstable(mid).1
+        }
+    }
+
+    fn reset(&mut self) {
+        self.buf.clear();
+    }
+}
+
+/// Simple moving average over the last `window` samples.
+#[derive(Debug, Clone)]
+pub struct MovingAverageStage {
+    window: usize,
+    buf: VecDeque<i32>,
+}
+
+impl MovingAverageStage {
+    /// Mean of the last `window` samples, rounded to the nearest centigram;
+    /// 0 is clamped to 1, and 1 passes samples through unchanged. Any size,
+    /// even or odd, works. Until the window fills, the mean is over the
+    /// samples seen so far.
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            buf: VecDeque::with_capacity(window),
+        }
+    }
+}
+
+impl FilterStage for MovingAverageStage {
+    fn process(&mut self, x_cg: i32) -> i32 {
+        if self.buf.len() == self.window {
+            self.buf.pop_front();
+        }
+        self.buf.push_back(x_cg);
+        let sum_i128: i128 = self.buf.iter().map(|&v| v as i128).sum();
+        let len_i32 = self.buf.len() as i32;
+        if (i32::MIN as i128..=i32::MAX as i128).contains(&sum_i128) {
+            div_round_nearest_i32(sum_i128 as i32, len_i32)
+        } else {
+            let n = len_i32 as i128;
+            let q = if sum_i128 >= 0 {
+                (sum_i128 + n / 2) / n
+            } else {
+                (sum_i128 - n / 2) / n
+            };
+            debug_assert!(
+                (i32::MIN as i128..=i32::MAX as i128).contains(&q),
+                "moving-average result out of i32 range"
+            );
+            q as i32
+        }
+    }
+
+    fn reset(&mut self) {
+        self.buf.clear();
+    }
+}
+
+/// Linearly weighted moving average: the newest sample weighs `window`, the
+/// oldest 1. Integer arithmetic throughout; the weighted sum is kept in `i128`
+/// so no window or weight range can overflow.
+#[derive(Debug, Clone)]
+pub struct WeightedMovingAverageStage {
+    window: usize,
+    buf: VecDeque<i32>,
+}
+
+impl WeightedMovingAverageStage {
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            buf: VecDeque::with_capacity(window),
+        }
+    }
+}
+
+impl FilterStage for WeightedMovingAverageStage {
+    fn process(&mut self, x_cg: i32) -> i32 {
+        if self.buf.len() == self.window {
+            self.buf.pop_front();
+        }
+        self.buf.push_back(x_cg);
+        // Until the window fills, weights run 1..=len over the samples so far.
+        let sum: i128 = (1i128..)
+            .zip(self.buf.iter())
+            .map(|(w, &v)| w * v as i128)
+            .sum();
+        let n = self.buf.len() as i128;
+        div_round_nearest_i128(sum, n * (n + 1) / 2)
+    }
+
+    fn reset(&mut self) {
+        self.buf.clear();
+    }
+}
+
+/// Trimmed mean: sorts the window, drops the `trim` smallest and largest
+/// samples and averages the rest. `trim = 0` is a plain moving average; the
+/// largest trim (`(window - 1) / 2`) is a median.
+///
+/// Until the window fills, the trim shrinks so at least one sample remains.
+#[derive(Debug, Clone)]
+pub struct TrimmedMeanStage {
+    window: usize,
+    trim: usize,
+    buf: VecDeque<i32>,
+    scratch: Vec<i32>,
+}
+
+impl TrimmedMeanStage {
+    /// `trim` is clamped to `(window - 1) / 2`.
+    pub fn new(window: usize, trim: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            trim: trim.min((window - 1) / 2),
+            buf: VecDeque::with_capacity(window),
+            scratch: Vec::with_capacity(window),
+        }
+    }
+}
+
+impl FilterStage for TrimmedMeanStage {
+    fn process(&mut self, x_cg: i32) -> i32 {
+        if self.buf.len() == self.window {
+            self.buf.pop_front();
+        }
+        self.buf.push_back(x_cg);
+        self.scratch.clear();
+        self.scratch.extend(self.buf.iter().copied());
+        self.scratch.sort_unstable();
+        let n = self.scratch.len();
+        let trim = self.trim.min((n - 1) / 2);
+        let kept = &self.scratch[trim..n - trim];
+        let sum: i128 = kept.iter().map(|&v| v as i128).sum();
+        div_round_nearest_i128(sum, kept.len() as i128)
+    }
+
+    fn reset(&mut self) {
+        self.buf.clear();
+    }
+}
+
+/// `sum / n` rounded half away from zero, for `n > 0` and a quotient within
+/// `i32` (a mean of `i32` samples always is).
+fn div_round_nearest_i128(sum: i128, n: i128) -> i32 {
+    let q = if sum >= 0 {
+        (sum + n / 2) / n
+    } else {
+        (sum - n / 2) / n
+    };
+    debug_assert!(
+        (i32::MIN as i128..=i32::MAX as i128).contains(&q),
+        "average out of i32 range"
+    );
+    q as i32
+}
+
+/// Exponential moving average: `y = alpha * x + (1 - alpha) * y_prev`.
+#[derive(Debug, Clone)]
+pub struct EmaStage {
+    alpha: f32,
+    prev: Option<f32>,
+}
+
+impl EmaStage {
+    /// `alpha` is clamped to `[0.0, 1.0]`; non-finite values act as passthrough.
+    pub fn new(alpha: f32) -> Self {
+        let alpha = if alpha.is_finite() {
+            alpha.clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        Self { alpha, prev: None }
+    }
+}
+
+impl FilterStage for EmaStage {
+    fn process(&mut self, x_cg: i32) -> i32 {
+        let x = x_cg as f32;
+        let y = match self.prev {
+            None => x,
+            Some(prev) => self.alpha * x + (1.0 - self.alpha) * prev,
+        };
+        self.prev = Some(y);
+        y.round() as i32
+    }
+
+    fn reset(&mut self) {
+        self.prev = None;
+    }
+}
+
+/// Second-order IIR notch (RBJ biquad) for rejecting a narrow interference band,
+/// e.g. mains hum aliased into the sample stream or auger vibration.
+///
+/// Unity gain at DC, so a settled weight passes through unchanged.
+#[derive(Debug, Clone)]
+pub struct NotchStage {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    state: Option<[f32; 4]>, // x1, x2, y1, y2
+}
+
+impl NotchStage {
+    /// Build a notch centered at `notch_hz` for a stream sampled at `sample_rate_hz`.
+    /// `q` controls the width (higher = narrower); 0.7–5 is typical.
+    pub fn new(sample_rate_hz: u32, notch_hz: f32, q: f32) -> Result<Self, BuildError> {
+        let fs = sample_rate_hz as f32;
+        if sample_rate_hz == 0 {
+            return Err(BuildError::InvalidConfig("sample_rate_hz must be > 0"));
+        }
+        if !notch_hz.is_finite() || notch_hz <= 0.0 || notch_hz >= fs / 2.0 {
+            return Err(BuildError::InvalidConfig(
+                "notch frequency must be in (0, sample_rate_hz / 2)",
+            ));
+        }
+        if !q.is_finite() || q <= 0.0 {
+            return Err(BuildError::InvalidConfig("notch q must be finite and > 0"));
+        }
+        let w0 = 2.0 * core::f32::consts::PI * notch_hz / fs;
+        let cos_w0 = w0.cos();
+        let alpha = w0.sin() / (2.0 * q);
+        let a0 = 1.0 + alpha;
+        Ok(Self {
+            b0: 1.0 / a0,
+            b1: -2.0 * cos_w0 / a0,
+            b2: 1.0 / a0,
+            a1: -2.0 * cos_w0 / a0,
+            a2: (1.0 - alpha) / a0,
+            state: None,
+        })
+    }
+}
+
+impl FilterStage for NotchStage {
+    fn process(&mut self, x_cg: i32) -> i32 {
+        let x = x_cg as f32;
+        // Start from steady state at the first sample to avoid a startup transient.
+        let [x1, x2, y1, y2] = self.state.unwrap_or([x, x, x, x]);
+        let y = self.b0 * x + self.b1 * x1 + self.b2 * x2 - self.a1 * y1 - self.a2 * y2;
+        self.state = Some([x, x1, y, y1]);
+        y.round() as i32
+    }
+
+    fn reset(&mut self) {
+        self.state = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_pipeline_is_passthrough() {
+        let mut p = FilterPipeline::new();
+        assert_eq!(p.process(123), 123);
+        assert_eq!(p.process(-7), -7);
+    }
+
+    #[test]
+    fn from_cfg_prefers_ema_over_ma() {
+        let cfg = FilterCfg {
+            ma_window: 5,
+            median_window: 3,
+            sample_rate_hz: 50,
+            ema_alpha: 0.5,
+            ..FilterCfg::default()
+        };
+        let p = FilterPipeline::from_cfg(&cfg);
+        assert_eq!(p.len(), 2);
+        let p = FilterPipeline::from_cfg(&FilterCfg::default());
+        assert!(p.is_empty());
+    }
+
+    #[test]
+    fn weighted_average_favors_recent_samples() {
+        let mut w = WeightedMovingAverageStage::new(3);
+        assert_eq!(w.process(0), 0);
+        // (1*0 + 2*300) / 3
+        assert_eq!(w.process(300), 200);
+        // (1*0 + 2*300 + 3*600) / 6
+        assert_eq!(w.process(600), 400);
+        // Window full: (1*300 + 2*600 + 3*600) / 6 = 550, vs. 500 for the plain mean
+        assert_eq!(w.process(600), 550);
+        // (1*600 + 2*600 - 3*5) / 6 = 297.5, rounded half away from zero
+        assert_eq!(w.process(-5), 298);
+        w.reset();
+        assert_eq!(w.process(42), 42);
+    }
+
+    #[test]
+    fn trimmed_mean_drops_outliers() {
+        let mut t = TrimmedMeanStage::new(5, 1);
+        for x in [100, 102, 98, 101] {
+            t.process(x);
+        }
+        // Spike of +5000 is trimmed along with the smallest sample (98).
+        assert_eq!(t.process(5100), 101);
+        // Samples equal → result equals them regardless of trim
+        let mut t = TrimmedMeanStage::new(4, 9);
+        for _ in 0..4 {
+            assert_eq!(t.process(-250), -250);
+        }
+    }
+
+    #[test]
+    fn from_cfg_selects_the_smoothing_kind() {
+        for smoothing in [Smoothing::Weighted, Smoothing::TrimmedMean] {
+            let cfg = FilterCfg {
+                ma_window: 5,
+                smoothing,
+                trim: 1,
+                ..FilterCfg::default()
+            };
+            assert_eq!(FilterPipeline::from_cfg(&cfg).len(), 1);
+        }
+    }
+
+    #[test]
+    fn notch_passes_dc_and_rejects_center_frequency() {
+        let mut n = NotchStage::new(80, 20.0, 2.0).unwrap();
+        for _ in 0..50 {
+            assert_eq!(n.process(1000), 1000);
+        }
+        // 20 Hz at 80 SPS is the sequence +A, 0, -A, 0, ...
+        let mut n = NotchStage::new(80, 20.0, 2.0).unwrap();
+        let seq = [1000, 0, -1000, 0];
+        let mut last = 0;
+        for i in 0..400 {
+            last = n.process(seq[i % 4]);
+        }
+        assert!(last.abs() < 20, "residual hum too large: {last}");
+    }
+
+    #[test]
+    fn notch_rejects_invalid_parameters() {
+        assert!(NotchStage::new(80, 40.0, 1.0).is_err());
+        assert!(NotchStage::new(80, 0.0, 1.0).is_err());
+        assert!(NotchStage::new(80, 10.0, 0.0).is_err());
+        assert!(NotchStage::new(0, 10.0, 1.0).is_err());
+    }
+}
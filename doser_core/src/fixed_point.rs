@@ -57,63 +57,65 @@ pub fn grams_to_cg(g: f32) -> i32 {
     ((g * 100.0).round()) as i32
 }
 
-/// Fixed-point scaling applied to the calibration gain (centigrams-per-count)
-/// before it is stored as an integer.
+/// Significant bits kept in a [`ShiftedGain`] mantissa: finer than the `f32`
+/// gain it comes from, and small enough that `delta * mantissa` stays far
+/// inside `i128`.
+const GAIN_BITS: i32 = 31;
+
+/// A gain in centigrams-per-count as `mantissa / 2^shift`, the shift chosen
+/// per calibration so the mantissa keeps [`GAIN_BITS`] significant bits.
 ///
-/// A plain `round(100 * gain_g_per_count)` (integer cg/count) destroys all
-/// sub-centigram-per-count resolution. Realistic load cells have gains far below
-/// 0.005 g/count (e.g. ~5e-4 g/count for ~100 g over a 24-bit ADC span), which
-/// would quantize to `0` — making every reading collapse to the offset. Scaling
-/// the gain by this factor keeps ~6 extra decimal digits of resolution while the
-/// per-sample multiply stays integer (i128) and deterministic.
-pub const GAIN_SCALE: i64 = 1_000_000;
+/// A fixed scale (say 1e-6 cg/count) would be a 5% error on a 1e-7 g/count
+/// cell; a shifted gain has the same relative precision (~5e-10) at any
+/// magnitude, and the per-sample conversion is still one integer multiply and
+/// shift.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ShiftedGain {
+    pub mantissa: i64,
+    pub shift: u32,
+}
 
-/// Quantize a gain expressed in grams-per-count into a scaled integer of
-/// `centigrams-per-count * GAIN_SCALE`, rounding to nearest and clamping to the
-/// `i64` range. Non-finite inputs (NaN/±Inf) map to `0`.
-#[inline]
-pub fn gain_to_scaled_cg_per_count(gain_g_per_count: f32) -> i64 {
-    if !gain_g_per_count.is_finite() {
-        return 0;
+impl ShiftedGain {
+    /// The gain `gain_g_per_count` (grams per count). Non-finite and zero
+    /// gains map to zero; gains beyond `i64::MAX` cg/count saturate.
+    pub fn from_g_per_count(gain_g_per_count: f32) -> Self {
+        let cg = f64::from(gain_g_per_count) * 100.0;
+        if !cg.is_finite() || cg == 0.0 {
+            return Self::default();
+        }
+        let exp = cg.abs().log2().floor() as i32;
+        let shift = (GAIN_BITS - 1 - exp).clamp(0, 62) as u32;
+        let scaled = (cg * (1u64 << shift) as f64).round();
+        let mantissa = if scaled >= i64::MAX as f64 {
+            i64::MAX
+        } else if scaled <= i64::MIN as f64 {
+            i64::MIN
+        } else {
+            scaled as i64
+        };
+        Self { mantissa, shift }
     }
-    // cg-per-count = 100 * g-per-count; scale up for fractional resolution.
-    let scaled = ((gain_g_per_count as f64) * 100.0 * (GAIN_SCALE as f64)).round();
-    if scaled >= i64::MAX as f64 {
-        i64::MAX
-    } else if scaled <= i64::MIN as f64 {
-        i64::MIN
-    } else {
-        scaled as i64
-    }
-}
 
-/// Convert a raw-count delta to centigrams using a scaled gain (see
-/// [`GAIN_SCALE`]) plus an integer centigram offset, rounding to nearest with
-/// ties away from zero and saturating to the `i32` range.
-///
-/// Uses an `i128` intermediate so the multiply cannot overflow for any `i64`
-/// delta and gain.
-#[inline]
-pub fn cg_from_delta_scaled(
-    delta_counts: i64,
-    gain_scaled_cg_per_count: i64,
-    offset_cg: i32,
-) -> i32 {
-    let num = (delta_counts as i128) * (gain_scaled_cg_per_count as i128);
-    let den = GAIN_SCALE as i128;
-    let half = den / 2;
-    let q = if num >= 0 {
-        (num + half) / den
-    } else {
-        (num - half) / den
-    };
-    let cg = q + (offset_cg as i128);
-    if cg > i32::MAX as i128 {
-        i32::MAX
-    } else if cg < i32::MIN as i128 {
-        i32::MIN
-    } else {
-        cg as i32
+    /// Centigrams for a raw-count `delta` plus `offset_cg`, rounded to nearest
+    /// with ties away from zero and saturated to the `i32` range.
+    #[inline]
+    pub fn cg_from_delta(self, delta_counts: i64, offset_cg: i32) -> i32 {
+        let num = (delta_counts as i128) * (self.mantissa as i128);
+        let q = if self.shift == 0 {
+            num
+        } else {
+            let half = 1i128 << (self.shift - 1);
+            let mag = (num.abs() + half) >> self.shift;
+            if num < 0 { -mag } else { mag }
+        };
+        let cg = q + (offset_cg as i128);
+        if cg > i32::MAX as i128 {
+            i32::MAX
+        } else if cg < i32::MIN as i128 {
+            i32::MIN
+        } else {
+            cg as i32
+        }
     }
 }
 
@@ -150,31 +152,42 @@ mod tests {
     }
 
     #[test]
-    fn scaled_gain_preserves_small_gains() {
-        // Sim/test gain: 0.01 g/count -> exactly 1 cg/count.
-        assert_eq!(gain_to_scaled_cg_per_count(0.01), GAIN_SCALE);
-        // Realistic load cell from the README example: 100 g over 182000 counts.
-        let gain = 100.0_f32 / 182_000.0; // ~0.000549 g/count
-        let scaled = gain_to_scaled_cg_per_count(gain);
-        assert!(scaled > 0, "small gain must not quantize to zero");
-        // 182000 counts from zero must read ~100.00 g (10000 cg), not 0.
-        let cg = cg_from_delta_scaled(182_000, scaled, 0);
-        assert!((9995..=10005).contains(&cg), "expected ~10000 cg, got {cg}");
-        // Non-finite gain is treated as zero gain.
-        assert_eq!(gain_to_scaled_cg_per_count(f32::NAN), 0);
-        assert_eq!(gain_to_scaled_cg_per_count(f32::INFINITY), 0);
-    }
-
-    #[test]
-    fn cg_from_delta_rounds_and_saturates() {
-        // 1 cg/count, delta 123 -> 123 cg, plus offset.
-        assert_eq!(cg_from_delta_scaled(123, GAIN_SCALE, 0), 123);
-        assert_eq!(cg_from_delta_scaled(123, GAIN_SCALE, 7), 130);
-        // Round to nearest, ties away from zero.
-        assert_eq!(cg_from_delta_scaled(1, GAIN_SCALE / 2, 0), 1); // 0.5 -> 1
-        assert_eq!(cg_from_delta_scaled(-1, GAIN_SCALE / 2, 0), -1); // -0.5 -> -1
-        // Saturation on extreme gain/delta.
-        assert_eq!(cg_from_delta_scaled(i64::MAX, i64::MAX, 0), i32::MAX);
-        assert_eq!(cg_from_delta_scaled(i64::MIN, i64::MAX, 0), i32::MIN);
+    fn shifted_gain_keeps_fine_gains_precise() {
+        // 1 cg/count is exact; ties round away from zero.
+        let unit = ShiftedGain::from_g_per_count(0.01);
+        assert_eq!(unit.cg_from_delta(123, 7), 130);
+        let half = ShiftedGain {
+            mantissa: 1,
+            shift: 1,
+        };
+        assert_eq!(half.cg_from_delta(1, 0), 1);
+        assert_eq!(half.cg_from_delta(-1, 0), -1);
+        // 1.23e-7 g/count over the full 24-bit span reads 2.06 g (a gain
+        // fixed to 1e-6 cg/count would round to 12e-6 and read 2.01 g).
+        let delta = 1i64 << 24;
+        let fine = 1.23e-7_f32;
+        assert_eq!(
+            ShiftedGain::from_g_per_count(fine).cg_from_delta(delta, 0),
+            206
+        );
+        // Realistic load cell from the README example: 100 g over 182000
+        // counts reads 100.00 g.
+        let cell = ShiftedGain::from_g_per_count(100.0 / 182_000.0);
+        assert_eq!(cell.cg_from_delta(182_000, 0), 10_000);
+        for gain in [1e-7_f32, fine, 4e-4, 0.000_549_45, 2.5] {
+            let g = ShiftedGain::from_g_per_count(gain);
+            let d = delta / 64;
+            let expected = (f64::from(gain) * 100.0 * d as f64).round();
+            assert_eq!(f64::from(g.cg_from_delta(d, 0)), expected, "gain {gain}");
+        }
+        // Degenerate gains convert to the offset; huge ones saturate.
+        assert_eq!(
+            ShiftedGain::from_g_per_count(f32::NAN).cg_from_delta(5, 3),
+            3
+        );
+        assert_eq!(ShiftedGain::from_g_per_count(0.0).cg_from_delta(5, 3), 3);
+        let huge = ShiftedGain::from_g_per_count(f32::MAX);
+        assert_eq!(huge.cg_from_delta(1, 0), i32::MAX);
+        assert_eq!(huge.cg_from_delta(-1, 0), i32::MIN);
     }
 }
@@ -17,7 +17,7 @@ pub fn map_hw_error(e: &(dyn std::error::Error + 'static)) -> DoserError {
         if let Some(hw) = e.downcast_ref::<doser_hardware::error::HwError>() {
             return match hw {
                 doser_hardware::error::HwError::Timeout => DoserError::Timeout,
-                doser_hardware::error::HwError::DataReadyTimeout => DoserError::Timeout,
+                doser_hardware::error::HwError::DataReadyTimeout => DoserError::DataReadyTimeout,
                 other => DoserError::HardwareFault(other.to_string()),
             };
         }
@@ -25,7 +25,10 @@ pub fn map_hw_error(e: &(dyn std::error::Error + 'static)) -> DoserError {
 
     // Fallback: string-based detection
     let s = e.to_string();
-    if s.to_lowercase().contains("timeout") {
+    let lower = s.to_lowercase();
+    if lower.contains("data-ready") && lower.contains("timeout") {
+        DoserError::DataReadyTimeout
+    } else if lower.contains("timeout") {
         DoserError::Timeout
     } else {
         DoserError::Hardware(s)
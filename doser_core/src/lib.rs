@@ -15,10 +15,22 @@
 //! - **Calibration**: Linear model for raw→grams conversion (`calibration` module)
 //! - **Configuration**: All config structs (`config` module)
 //! - **Fixed-point**: Centigram arithmetic helpers (`fixed_point` module)
-//! - **Filtering**: Median, moving average, EMA smoothing
+//! - **Filtering**: Composable stage pipeline: median, notch, EMA, moving average (`filter` module)
 //! - **Control**: Multi-speed control with hysteresis (`DoserCore`)
-//! - **Safety**: Watchdogs for runtime, overshoot, no-progress
+//! - **Safety**: Watchdogs for runtime, overshoot, no-progress; optional
+//!   abort safe-state sequence (`safe_state` module); an E-stop monitor that
+//!   stops the motor between steps (`estop` module)
+//! - **Auto-tare**: Tare on a placed container once it settles (`autotare` module)
+//! - **Pre-flight**: Scale warm-up (`warmup` module), then scale, driver and
+//!   E-stop checks before the motor starts (`preflight` module)
+//! - **Resume**: Continuing a dose after a transient (sensor) abort (`resume` module)
 //! - **Status**: Dosing state machine (`status` module)
+//! - **Diagnosis**: Remediation codes for aborts (`diagnosis` module)
+//! - **Shadow**: Read-only piloting next to an external controller (`shadow` module)
+//! - **Weighing**: Scale, calibration and filter without a motor (`weigh` module)
+//! - **Planning**: Speed band preview with estimated durations (`plan` module)
+//! - **Simulation**: Seeded Monte Carlo doses against a plant model (`sim` module)
+//! - **Tuning**: Seeded search for speed bands and predictor settings (`tune` module)
 //! - **Builder**: Type-state builder pattern (`builder` module)
 //!
 //! ## Fixed-Point Arithmetic
@@ -28,24 +40,55 @@
 
 // ── Module declarations ──────────────────────────────────────────────────────
 
+pub mod autotare;
 pub mod builder;
 pub mod calibration;
 pub mod config;
 pub mod conversions;
 mod core;
+pub mod diagnosis;
 pub mod error;
+pub mod estop;
+pub mod filter;
 pub mod fixed_point;
+pub mod handoff;
 pub mod hw_error;
 pub mod mocks;
+pub mod plan;
+pub mod preflight;
+pub mod resume;
 pub mod runner;
+pub mod safe_state;
 pub mod sampler;
+pub mod shadow;
+pub mod sim;
 pub mod status;
+mod sync;
+pub mod tune;
 pub mod util;
+pub mod warmup;
+pub mod weigh;
 
 // ── Public re-exports (backward-compatible API) ──────────────────────────────
 
 pub use builder::{Doser, DoserBuilder, DoserG, Missing, Set, build_doser};
 pub use calibration::Calibration;
-pub use config::{ControlCfg, FilterCfg, FilterKind, PredictorCfg, SafetyCfg, Timeouts};
+pub use config::{
+    AutoTareCfg, ControlCfg, FilterCfg, FilterKind, HopperCfg, InflightModel, PredictorCfg,
+    PreflightCfg, ReadRetryCfg, SafeStateCfg, SafetyCfg, SlopeSource, Smoothing, Timeouts,
+    UndershootPolicy, WarmupCfg,
+};
 pub use core::DoserCore;
-pub use status::DosingStatus;
+pub use diagnosis::{AbortTrace, Remediation};
+pub use doser_traits::pacing::OverrunPolicy;
+pub use estop::EstopMonitor;
+pub use filter::{FilterPipeline, FilterStage};
+pub use plan::{PlanStep, SpeedPlan};
+pub use safe_state::{SafeState, SharedActuator};
+pub use status::{
+    Attempt, DosePhase, DosingStatus, PhaseTimings, RunState, RunStatus, SpeedBand, StatusBoard,
+};
+pub use weigh::{Weight, WeightReader};
+
+/// This crate's version.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,110 @@
+//! Speed plan preview: which speed bands a dose passes through, and roughly
+//! how long each takes, computed from the `[control]` settings alone so a
+//! tuning can be sanity-checked before anything is dispensed.
+//!
+//! Durations need the material's flow per motor step (grams per step); without
+//! it the plan lists the bands only. The estimate assumes the flow follows the
+//! commanded speed at once and ignores the predictor's early stop and the
+//! in-flight material, so real doses end a little sooner than planned.
+
+use crate::config::ControlCfg;
+
+/// One leg of the plan: the motor runs from `from_g` to `to_g` on the scale.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanStep {
+    /// Weight at which the step starts
+    pub from_g: f32,
+    /// Weight at which the next step (or the stop) takes over
+    pub to_g: f32,
+    /// Speed entering the step (steps per second)
+    pub sps: u32,
+    /// Speed leaving the step; below `sps` where the legacy two-speed mode
+    /// tapers the fine speed
+    pub exit_sps: u32,
+    /// Estimated duration (with a grams-per-step figure)
+    pub est_ms: Option<u64>,
+}
+
+/// The planned course of a dose.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeedPlan {
+    pub target_g: f32,
+    /// Where the motor stops: `target_g - epsilon_g`
+    pub stop_at_g: f32,
+    pub steps: Vec<PlanStep>,
+    /// Time the weight must hold in band before the dose completes
+    pub settle_ms: u64,
+    /// Estimated total, settling included (with a grams-per-step figure)
+    pub est_ms: Option<u64>,
+}
+
+/// Plan a dose of `target_g` under `control`, estimating durations from
+/// `g_per_step` when it is known.
+pub fn plan(control: &ControlCfg, target_g: f32, g_per_step: Option<f32>) -> SpeedPlan {
+    let g_per_step = g_per_step.filter(|g| g.is_finite() && *g > 0.0);
+    let stop_err = control.epsilon_g.max(0.0);
+    let mut steps = Vec::new();
+    // Steps are bounded by the error (grams below target), clipped to the dose.
+    let mut push = |hi: f32, lo: f32, sps: u32, exit_sps: u32, ms: Option<f64>| {
+        let (hi, lo) = (hi.min(target_g), lo.max(stop_err));
+        if hi > lo {
+            steps.push(PlanStep {
+                from_g: target_g - hi,
+                to_g: target_g - lo,
+                sps,
+                exit_sps,
+                est_ms: ms.map(|ms| ms.round() as u64),
+            });
+        }
+    };
+    let flat_ms = |hi: f32, lo: f32, sps: u32| {
+        let grams = f64::from(hi.min(target_g) - lo.max(stop_err));
+        g_per_step.map(|g| grams / (f64::from(sps.max(1)) * f64::from(g)) * 1000.0)
+    };
+
+    if control.speed_bands.is_empty() {
+        let slow = control.slow_at_g.max(0.0);
+        push(
+            f32::INFINITY,
+            slow,
+            control.coarse_speed,
+            control.coarse_speed,
+            flat_ms(f32::INFINITY, slow, control.coarse_speed),
+        );
+        if slow > 0.0 {
+            // Fine speed scaled by 0.2 + 0.8·err/slow_at_g (see DoserCore):
+            // the time is the integral of d(err) / (g·speed(err)).
+            let frac = |err: f32| 0.2 + 0.8 * (err / slow).clamp(0.0, 1.0);
+            let speed = |err: f32| ((control.fine_speed as f32 * frac(err)).max(1.0)) as u32;
+            let (hi, lo) = (slow.min(target_g), stop_err);
+            let ms = g_per_step.map(|g| {
+                let rate = f64::from(g) * f64::from(control.fine_speed.max(1));
+                let (f_hi, f_lo) = (f64::from(frac(hi)), f64::from(frac(lo)));
+                f64::from(slow) / (0.8 * rate) * (f_hi / f_lo).ln() * 1000.0
+            });
+            push(hi, lo, speed(hi), speed(lo), ms);
+        }
+    } else {
+        // Thresholds descending, as the builder sorts them. A band covers the
+        // errors from its threshold up to the previous one; the last band also
+        // covers everything below its threshold.
+        let mut bands = control.speed_bands.clone();
+        bands.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let mut hi = f32::INFINITY;
+        for (i, &(thr, sps)) in bands.iter().enumerate() {
+            let lo = if i + 1 == bands.len() { stop_err } else { thr };
+            push(hi, lo, sps, sps, flat_ms(hi, lo, sps));
+            hi = lo;
+        }
+    }
+
+    let est_ms =
+        g_per_step.map(|_| steps.iter().filter_map(|s| s.est_ms).sum::<u64>() + control.stable_ms);
+    SpeedPlan {
+        target_g,
+        stop_at_g: (target_g - stop_err).max(0.0),
+        steps,
+        settle_ms: control.stable_ms,
+        est_ms,
+    }
+}
@@ -0,0 +1,155 @@
+//! Pre-flight health gate, run before the motor is first started.
+//!
+//! Checks that the E-stop is released, the scale is readable, not railed and
+//! stable (optionally within a plausible weight band, and reading the known
+//! starting weight through the calibration), and that the motor driver
+//! enable can be toggled. Every check runs; all failures are returned together
+//! in a [`PreflightError`], so a dead scale is reported before anything moves.
+//!
+//! [`check_hopper`] is the optional feasibility check against a hopper scale.
+
+use std::time::Duration;
+
+use doser_traits::{Motor, Scale};
+
+use crate::calibration::Calibration;
+use crate::config::{HopperCfg, PreflightCfg};
+use crate::error::{DoserError, PreflightError, PreflightFailure};
+use crate::hw_error::map_hw_error;
+
+/// Rail values of a 24-bit ADC (HX711): a reading here means a disconnected or
+/// overloaded cell rather than a weight.
+const ADC_RAILS: [i32; 2] = [0x7F_FFFF, -0x80_0000];
+
+/// Run the pre-flight checks. A disabled `cfg` always passes.
+pub fn check<S, M>(
+    scale: &mut S,
+    motor: &mut M,
+    estop: Option<&dyn Fn() -> bool>,
+    calibration: Option<&Calibration>,
+    cfg: &PreflightCfg,
+    read_timeout: Duration,
+) -> Result<(), PreflightError>
+where
+    S: Scale + ?Sized,
+    M: Motor + ?Sized,
+{
+    if !cfg.enabled {
+        return Ok(());
+    }
+    let mut failures = Vec::new();
+
+    if estop.is_some_and(|f| f()) {
+        failures.push(PreflightFailure::EstopActive);
+    }
+
+    check_scale(scale, calibration, cfg, read_timeout, &mut failures);
+
+    // Toggle the driver enable and leave it off; `start()` enables it again.
+    if let Err(e) = motor.enable().and_then(|()| motor.disable()) {
+        failures.push(PreflightFailure::MotorEnable(e.to_string()));
+    }
+
+    if failures.is_empty() {
+        tracing::debug!("pre-flight passed");
+        Ok(())
+    } else {
+        let err = PreflightError { failures };
+        tracing::error!(error = %err, "pre-flight failed");
+        Err(err)
+    }
+}
+
+fn check_scale<S: Scale + ?Sized>(
+    scale: &mut S,
+    calibration: Option<&Calibration>,
+    cfg: &PreflightCfg,
+    read_timeout: Duration,
+    failures: &mut Vec<PreflightFailure>,
+) {
+    let cal = calibration.cloned().unwrap_or_default();
+    let mut grams = Vec::with_capacity(cfg.samples.max(1));
+    for _ in 0..cfg.samples.max(1) {
+        match scale.read(read_timeout) {
+            Ok(raw) if ADC_RAILS.contains(&raw) => {
+                failures.push(PreflightFailure::ScaleSaturated(raw));
+                return;
+            }
+            Ok(raw) => grams.push(cal.to_grams(raw)),
+            Err(e) => {
+                failures.push(match map_hw_error(&*e) {
+                    DoserError::Timeout => PreflightFailure::ScaleTimeout,
+                    DoserError::DataReadyTimeout => PreflightFailure::ScaleDataReadyTimeout,
+                    _ => PreflightFailure::ScaleUnreadable(e.to_string()),
+                });
+                return;
+            }
+        }
+    }
+
+    let min = grams.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = grams.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if max - min > cfg.max_spread_g {
+        failures.push(PreflightFailure::ScaleUnstable {
+            spread_g: max - min,
+            max_spread_g: cfg.max_spread_g,
+        });
+    }
+    let mean = grams.iter().sum::<f32>() / grams.len() as f32;
+    if cfg.min_g.is_some_and(|lo| mean < lo) || cfg.max_g.is_some_and(|hi| mean > hi) {
+        failures.push(PreflightFailure::WeightOutOfBand { grams: mean });
+    }
+    // A wrong calibration (another cell, another unit) rarely lands near the
+    // known starting weight.
+    if let Some(band_g) = cfg.zero_band_g {
+        let expected_g = cfg.container_g.unwrap_or(0.0);
+        if (mean - expected_g).abs() > band_g {
+            failures.push(PreflightFailure::UnitsMismatch {
+                grams: mean,
+                expected_g,
+                band_g,
+            });
+        }
+    }
+}
+
+/// Check that the hopper holds at least `target_g` plus the reserve, so a dose
+/// that would run dry fails here rather than with `NoProgress` halfway through.
+/// Returns the estimated hopper contents in grams.
+pub fn check_hopper<H: Scale + ?Sized>(
+    hopper: &mut H,
+    cfg: &HopperCfg,
+    target_g: f32,
+    read_timeout: Duration,
+) -> Result<f32, PreflightError> {
+    let fail = |failure| {
+        let err = PreflightError {
+            failures: vec![failure],
+        };
+        tracing::error!(error = %err, "hopper check failed");
+        err
+    };
+    let samples = cfg.samples.max(1);
+    let mut sum = 0.0;
+    for _ in 0..samples {
+        match hopper.read(read_timeout) {
+            Ok(raw) if ADC_RAILS.contains(&raw) => {
+                return Err(fail(PreflightFailure::HopperUnreadable(format!(
+                    "saturated (raw {raw})"
+                ))));
+            }
+            Ok(raw) => sum += cfg.calibration.to_grams(raw),
+            Err(e) => return Err(fail(PreflightFailure::HopperUnreadable(e.to_string()))),
+        }
+    }
+    let available_g = sum / samples as f32;
+    let needed_g = target_g + cfg.reserve_g;
+    if available_g < needed_g {
+        return Err(fail(PreflightFailure::HopperLow {
+            available_g,
+            needed_g,
+        }));
+    }
+    tracing::info!(available_g, needed_g, "hopper check passed");
+    Ok(available_g)
+}
@@ -0,0 +1,101 @@
+//! Continuing a dose after a transient abort.
+//!
+//! A dose that stops because the scale briefly stopped answering (a read or
+//! data-ready timeout) has not failed unsafely; restarting it from scratch
+//! risks dosing the full target on top of what already landed. Instead the
+//! caller records a [`ResumePoint`] (the target and the tare the dose ran
+//! against), and, once the operator confirms, reads the scale, re-tares at the
+//! current weight, and doses only [`ResumePoint::remaining_g`].
+//!
+//! If the weight at the pause was captured, a change by the time of the resume
+//! ([`ResumePoint::disturbance_g`], e.g. the container was bumped or re-seated)
+//! can be re-zeroed with the operator's confirmation: measuring against
+//! `paused_counts` instead of the current reading keeps it out of the dose.
+//!
+//! Safety aborts (E-stop, overshoot, no progress, runtime cap) are never
+//! resumable: they need a person to look at the machine first.
+
+use std::time::Duration;
+
+use doser_traits::Scale;
+
+use crate::calibration::Calibration;
+use crate::error::{DoserError, Result};
+use crate::hw_error::map_hw_error;
+
+/// True if `err` ended a dose in a way that may be resumed.
+pub fn is_resumable(err: &eyre::Report) -> bool {
+    matches!(
+        err.downcast_ref::<DoserError>(),
+        Some(DoserError::Timeout | DoserError::DataReadyTimeout)
+    )
+}
+
+/// Where an interrupted dose stood: its target and the tare it ran against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResumePoint {
+    /// Target of the original dose, in grams.
+    pub target_g: f32,
+    /// Tare (raw counts) of the original dose.
+    pub zero_counts: i32,
+    /// Mean raw reading once the dose had stopped, if the scale answered.
+    pub paused_counts: Option<i32>,
+}
+
+impl ResumePoint {
+    /// Resume point for a dose of `target_g` against `calibration`.
+    pub fn new(target_g: f32, calibration: &Calibration) -> Self {
+        Self {
+            target_g,
+            zero_counts: calibration.zero_counts,
+            paused_counts: None,
+        }
+    }
+
+    /// Weight change (grams) between the pause and a reading of `raw`; `None`
+    /// if the pause weight was not captured.
+    pub fn disturbance_g(&self, calibration: &Calibration, raw: i32) -> Option<f32> {
+        let paused = self.paused_counts?;
+        Some(calibration.to_grams(raw) - calibration.to_grams(paused))
+    }
+
+    /// Grams dosed so far, given the scale now reads `raw`.
+    pub fn dosed_g(&self, calibration: &Calibration, raw: i32) -> f32 {
+        Calibration {
+            zero_counts: self.zero_counts,
+            ..calibration.clone()
+        }
+        .to_grams(raw)
+    }
+
+    /// Grams still to dose toward the original target (never negative).
+    pub fn remaining_g(&self, calibration: &Calibration, raw: i32) -> f32 {
+        (self.target_g - self.dosed_g(calibration, raw)).max(0.0)
+    }
+}
+
+/// `calibration` re-tared so that `raw` reads zero.
+pub fn retare(calibration: &Calibration, raw: i32) -> Calibration {
+    Calibration {
+        zero_counts: raw,
+        offset_g: 0.0,
+        ..calibration.clone()
+    }
+}
+
+/// Mean of `samples` raw reads (at least one).
+pub fn read_mean_counts<S: Scale + ?Sized>(
+    scale: &mut S,
+    samples: usize,
+    read_timeout: Duration,
+) -> Result<i32> {
+    let samples = samples.max(1);
+    let mut sum = 0i64;
+    for _ in 0..samples {
+        let raw = scale
+            .read(read_timeout)
+            .map_err(|e| eyre::Report::new(map_hw_error(&*e)))?;
+        sum += i64::from(raw);
+    }
+    Ok((sum / samples as i64) as i32)
+}
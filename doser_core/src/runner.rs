@@ -2,13 +2,20 @@
 //!
 //! Chooses sampling mode (Direct/Event/Paced), computes stall thresholds,
 //! wires `Sampler` when needed, and enforces safety constraints (timeouts,
-//! max runtime). Returns success grams or domain abort errors.
+//! max runtime). Returns a [`DoseReport`] (or just the final grams) on success,
+//! or domain abort errors.
 use crate::calibration::Calibration;
-use crate::config::{ControlCfg, FilterCfg, SafetyCfg, Timeouts};
+use crate::config::{
+    ControlCfg, FilterCfg, PreflightCfg, ReadRetryCfg, SafetyCfg, Timeouts, WarmupCfg,
+};
+use crate::core::DoserCore;
 use crate::error::{AbortReason, DoserError, Result as CoreResult};
+use crate::safe_state::SafeState;
 use crate::sampler::Sampler;
-use crate::status::DosingStatus;
-use doser_traits::clock::MonotonicClock;
+use crate::status::{DosingStatus, PhaseTimings, RunState, StatusBoard};
+use crate::warmup::ProgressSink;
+use doser_traits::clock::{Clock, MonotonicClock};
+use doser_traits::pacing::OverrunPolicy;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
@@ -21,6 +28,34 @@ fn shutdown_requested(flag: &Option<ShutdownFlag>) -> bool {
     flag.as_ref().is_some_and(|f| f.load(Ordering::Relaxed))
 }
 
+/// Clock for the control loop; real time unless one is given.
+///
+/// Used in [`SamplingMode::Direct`] only: the sampler modes pace a second
+/// thread, which cannot share a virtual timeline with the loop, so they always
+/// run in real time.
+#[derive(Clone, Default)]
+pub struct RunClock(Option<Arc<dyn Clock + Send + Sync>>);
+
+impl RunClock {
+    pub fn new(clock: impl Clock + Send + Sync + 'static) -> Self {
+        Self(Some(Arc::new(clock)))
+    }
+
+    fn boxed(&self) -> Option<Box<dyn Clock + Send + Sync>> {
+        self.0
+            .clone()
+            .map(|c| -> Box<dyn Clock + Send + Sync> { Box::new(c) })
+    }
+}
+
+impl core::fmt::Debug for RunClock {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("RunClock")
+            .field(&self.0.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
 /// How sampling should be orchestrated
 #[derive(Debug, Clone, Copy)]
 pub enum SamplingMode {
@@ -39,15 +74,90 @@ pub struct RunParams {
     pub control: ControlCfg,
     pub safety: SafetyCfg,
     pub timeouts: Timeouts,
+    /// Retries of timed-out scale reads (direct mode; the sampler modes ride
+    /// out missed samples with the stall watchdog instead).
+    pub read_retry: ReadRetryCfg,
     pub calibration: Option<Calibration>,
     pub target_g: f32,
     pub estop_debounce_n: u8,
     pub prefer_timeout_first: bool,
     pub mode: SamplingMode,
     pub predictor: Option<crate::PredictorCfg>,
+    /// How a control iteration that overruns its period is handled.
+    pub overrun: OverrunPolicy,
+    /// Follow-up to the motor stop when the dose aborts.
+    pub safe_state: SafeState,
+    /// Scale warm-up run before the pre-flight checks.
+    pub warmup: WarmupCfg,
+    /// Receiver for warm-up progress (e.g. to show "stabilizing…").
+    pub progress: ProgressSink,
+    /// Where the loop publishes its status after each step.
+    pub status: StatusBoard,
+    /// Health gate run before the motor first starts.
+    pub preflight: PreflightCfg,
     /// Optional cooperative shutdown flag; when set true mid-run the motor is
     /// stopped and the run aborts with `AbortReason::Estop`.
     pub shutdown: Option<ShutdownFlag>,
+    /// Control-loop clock (direct mode), e.g. a virtual clock for simulation.
+    pub clock: RunClock,
+}
+
+/// Outcome of a successful dose.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DoseReport {
+    /// Settled weight in grams.
+    pub final_g: f32,
+    /// Display weight at completion (`FilterCfg::display`).
+    pub display_g: f32,
+    /// Uncertainty (one sigma) of `final_g` from the recent reading noise.
+    pub sigma_g: f32,
+    /// Mean weight over the final settle window (the stable-hold value).
+    pub hold_g: Option<f32>,
+    /// Time spent in each dosing phase.
+    pub phases: PhaseTimings,
+    /// Last slope EMA in grams per second, if the predictor ran.
+    pub slope_ema_gps: Option<f32>,
+    /// Weight at which the predictor stopped the motor early, if it did.
+    pub stop_at_g: Option<f32>,
+    /// Last in-flight mass estimate in grams, if the predictor ran.
+    pub coast_comp_g: Option<f32>,
+    /// Shortfall after an early stop settled below target, if it did.
+    pub undershoot_g: Option<f32>,
+    /// Control iterations that overran their period.
+    pub loop_overruns: u64,
+    /// Timed-out scale reads that were retried.
+    pub read_retries: u64,
+    /// Retried reads that returned a sample.
+    pub reads_recovered: u64,
+    /// Motor steps commanded during the dose.
+    pub motor_steps: u64,
+    /// Weight that landed after the motor's last stop, in grams.
+    pub coast_g: Option<f32>,
+    /// Motor approaches: 1, plus one per top-up (see
+    /// [`DoserCore::attempts`] for each one).
+    pub attempts: u32,
+}
+
+impl DoseReport {
+    fn from_core<S: doser_traits::Scale, M: doser_traits::Motor>(doser: &DoserCore<S, M>) -> Self {
+        Self {
+            final_g: doser.last_weight(),
+            display_g: doser.display_weight(),
+            sigma_g: doser.current_weight().sigma_g,
+            hold_g: doser.hold_weight(),
+            phases: doser.phase_timings(),
+            slope_ema_gps: doser.last_slope_ema_gps(),
+            stop_at_g: doser.early_stop_at_g(),
+            coast_comp_g: doser.last_inflight_g(),
+            undershoot_g: doser.undershoot_g(),
+            loop_overruns: doser.loop_overruns(),
+            read_retries: doser.read_retries(),
+            reads_recovered: doser.reads_recovered(),
+            motor_steps: doser.motor_steps(),
+            coast_g: doser.coast_g(),
+            attempts: doser.attempt_count(),
+        }
+    }
 }
 
 /// Compute the stall watchdog threshold in milliseconds.
@@ -111,6 +221,29 @@ fn stalled_now(elapsed_ms: u64, stalled_ms: u64, threshold_ms: u64) -> bool {
     elapsed_ms >= threshold_ms && stalled_ms > threshold_ms
 }
 
+/// Stop the motor and run the safe-state sequence for an abort detected by the
+/// orchestrator (rather than by the control loop itself).
+fn abort_run<S, M>(
+    doser: &mut DoserCore<S, M>,
+    err: DoserError,
+    ctx: &'static str,
+) -> crate::error::Report
+where
+    S: doser_traits::Scale,
+    M: doser_traits::Motor,
+{
+    // A shutdown or E-stop de-energizes at once, like the loop's own aborts.
+    if matches!(err, DoserError::Abort(AbortReason::Estop)) {
+        if let Err(e) = doser.motor_halt() {
+            tracing::warn!(error = %e, "motor_halt failed on {ctx}");
+        }
+    } else if let Err(e) = doser.motor_stop() {
+        tracing::warn!(error = %e, "motor_stop failed on {ctx}");
+    }
+    doser.enter_safe_state(&err);
+    crate::error::Report::new(err)
+}
+
 /// Run the controller until completion or abort, returning final grams on success.
 /// The caller should pre-merge any safety overrides (e.g., max_run_ms) into `safety`.
 pub fn run<S, M>(
@@ -123,6 +256,70 @@ where
     S: doser_traits::Scale + Send + 'static,
     M: doser_traits::Motor + 'static,
 {
+    run_report(scale, motor, estop_check, params).map(|r| r.final_g)
+}
+
+/// Like [`run`], but returns the full [`DoseReport`] (phase timings and
+/// predictor telemetry) on success.
+///
+/// The scale warm-up (`params.warmup`) and pre-flight checks
+/// (`params.preflight`) run first; on a pre-flight failure a
+/// [`PreflightError`](crate::error::PreflightError) is returned and the motor is
+/// never started. The loop's status goes to `params.status` after each step,
+/// and how the run ended once it returns.
+pub fn run_report<S, M>(
+    scale: S,
+    motor: M,
+    estop_check: Option<Box<dyn Fn() -> bool + Send + Sync>>,
+    params: RunParams,
+) -> CoreResult<DoseReport>
+where
+    S: doser_traits::Scale + Send + 'static,
+    M: doser_traits::Motor + 'static,
+{
+    let status = params.status.clone();
+    let result = run_gated(scale, motor, estop_check, params);
+    status.finish(if result.is_ok() {
+        RunState::Complete
+    } else {
+        RunState::Aborted
+    });
+    result
+}
+
+/// [`run_report`] without the final status.
+fn run_gated<S, M>(
+    mut scale: S,
+    mut motor: M,
+    estop_check: Option<Box<dyn Fn() -> bool + Send + Sync>>,
+    params: RunParams,
+) -> CoreResult<DoseReport>
+where
+    S: doser_traits::Scale + Send + 'static,
+    M: doser_traits::Motor + 'static,
+{
+    let read_timeout = Duration::from_millis(params.timeouts.sensor_ms);
+    crate::warmup::run(
+        &mut scale,
+        params.calibration.as_ref(),
+        &params.warmup,
+        read_timeout,
+        &params.progress,
+    )?;
+    crate::preflight::check(
+        &mut scale,
+        &mut motor,
+        estop_check.as_deref().map(|f| f as &dyn Fn() -> bool),
+        params.calibration.as_ref(),
+        &params.preflight,
+        read_timeout,
+    )?;
+    if params.clock.0.is_some() && !matches!(params.mode, SamplingMode::Direct) {
+        tracing::warn!("run clock ignored: sampler modes run in real time");
+    }
+    if params.control.loop_hz > 0 && matches!(params.mode, SamplingMode::Direct) {
+        tracing::warn!("control.loop_hz ignored: direct mode steps once per read");
+    }
     match params.mode {
         SamplingMode::Direct => run_direct(
             scale,
@@ -131,12 +328,17 @@ where
             params.control,
             params.safety,
             params.timeouts,
+            params.read_retry,
             params.calibration,
             params.target_g,
             estop_check,
             params.estop_debounce_n,
             params.predictor,
+            params.overrun,
+            params.safe_state,
             params.shutdown,
+            &params.clock,
+            &params.status,
         ),
         SamplingMode::Event | SamplingMode::Paced(_) => run_with_sampler(
             scale,
@@ -152,7 +354,10 @@ where
             params.prefer_timeout_first,
             params.mode,
             params.predictor,
+            params.overrun,
+            params.safe_state,
             params.shutdown,
+            &params.status,
         ),
     }
 }
@@ -165,13 +370,18 @@ fn run_direct<S, M>(
     control: ControlCfg,
     safety: SafetyCfg,
     timeouts: Timeouts,
+    read_retry: ReadRetryCfg,
     calibration: Option<Calibration>,
     target_g: f32,
     estop_check: Option<Box<dyn Fn() -> bool + Send + Sync>>,
     estop_debounce_n: u8,
     predictor: Option<crate::PredictorCfg>,
+    overrun: OverrunPolicy,
+    safe_state: SafeState,
     shutdown: Option<ShutdownFlag>,
-) -> CoreResult<f32>
+    clock: &RunClock,
+    status: &StatusBoard,
+) -> CoreResult<DoseReport>
 where
     S: doser_traits::Scale + 'static,
     M: doser_traits::Motor + 'static,
@@ -189,33 +399,46 @@ where
         target_g,
         estop_check_core,
         predictor,
-        None,
+        clock.boxed(),
         Some(estop_debounce_n),
     )?;
+    doser.set_overrun_policy(overrun);
+    doser.set_safe_state(safe_state);
+    doser.set_read_retry(read_retry);
     doser.begin();
     tracing::info!(target_g, mode = "direct", "dose start");
 
     loop {
         if shutdown_requested(&shutdown) {
-            if let Err(e) = doser.motor_stop() {
-                tracing::warn!(error = %e, "motor_stop failed on shutdown");
-            }
             tracing::info!("shutdown requested; aborting dose");
-            return Err(crate::error::Report::new(DoserError::Abort(
-                AbortReason::Estop,
-            )));
+            return Err(abort_run(
+                &mut doser,
+                DoserError::Abort(AbortReason::Estop),
+                "shutdown",
+            ));
         }
-        match doser.step()? {
+        let step = doser.step()?;
+        status.publish(&doser);
+        match step {
             DosingStatus::Running => continue,
             DosingStatus::Complete => {
-                let final_g = doser.last_weight();
-                tracing::info!(final_g, "dose complete");
-                return Ok(final_g);
+                let report = DoseReport::from_core(&doser);
+                tracing::info!(
+                    final_g = report.final_g,
+                    loop_overruns = report.loop_overruns,
+                    read_retries = report.read_retries,
+                    coarse_ms = report.phases.coarse_ms,
+                    fine_ms = report.phases.fine_ms,
+                    settle_ms = report.phases.settle_ms,
+                    verify_ms = report.phases.verify_ms,
+                    "dose complete"
+                );
+                return Ok(report);
             }
             DosingStatus::Aborted(e) => {
                 let _ = doser.motor_stop();
                 tracing::error!(error = %e, "dose aborted");
-                return Err(crate::error::Report::new(e));
+                return Err(doser.abort_report(e));
             }
         }
     }
@@ -236,8 +459,11 @@ fn run_with_sampler<S, M>(
     prefer_timeout_first: bool,
     mode: SamplingMode,
     predictor: Option<crate::PredictorCfg>,
+    overrun: OverrunPolicy,
+    safe_state: SafeState,
     shutdown: Option<ShutdownFlag>,
-) -> CoreResult<f32>
+    status: &StatusBoard,
+) -> CoreResult<DoseReport>
 where
     S: doser_traits::Scale + Send + 'static,
     M: doser_traits::Motor + 'static,
@@ -247,10 +473,20 @@ where
 
     let period_us = crate::util::period_us(filter.sample_rate_hz);
     let period_ms = crate::util::period_ms(filter.sample_rate_hz);
+    // Control ticks between samples (`ControlCfg::loop_hz`).
+    let hold_ticks = control.loop_hz > filter.sample_rate_hz;
     // Bound stall threshold by max_run_ms to keep it meaningful and allow early watchdog firing
     let stall_threshold_ms =
         compute_stall_threshold_ms(timeouts.sensor_ms, period_ms, safety.max_run_ms);
 
+    // The controller below only sees a NoopScale; check the real one here.
+    crate::builder::warn_if_below_resolution(
+        scale.resolution_counts(),
+        &calibration.clone().unwrap_or_default(),
+        control.epsilon_g,
+    );
+
+    let raw_range = scale.raw_range();
     let sampler_timeout = Duration::from_millis(timeouts.sensor_ms);
     let sampler = match mode {
         SamplingMode::Event => Sampler::spawn_event(scale, sampler_timeout, MonotonicClock::new()),
@@ -279,6 +515,12 @@ where
         None,
         Some(estop_debounce_n),
     )?;
+    doser.set_overrun_policy(overrun);
+    doser.set_safe_state(safe_state);
+    doser.set_raw_range(raw_range);
+    if hold_ticks {
+        doser.set_loop_hz(control.loop_hz);
+    }
     doser.begin();
 
     tracing::info!(target_g, mode = "sampler", "dose start");
@@ -286,13 +528,12 @@ where
     let start = std::time::Instant::now();
     loop {
         if shutdown_requested(&shutdown) {
-            if let Err(e) = doser.motor_stop() {
-                tracing::warn!(error = %e, "motor_stop failed on shutdown");
-            }
             tracing::info!("shutdown requested; aborting dose");
-            return Err(crate::error::Report::new(DoserError::Abort(
-                AbortReason::Estop,
-            )));
+            return Err(abort_run(
+                &mut doser,
+                DoserError::Abort(AbortReason::Estop),
+                "shutdown",
+            ));
         }
         // Out-of-band E-stop poll: decouples E-stop latency from sample arrival.
         if doser.poll_estop_stop() {
@@ -308,48 +549,55 @@ where
         // Timeout vs max-run precedence
         let stalled_ms = sampler.stalled_for_now();
         if prefer_timeout_first && stalled_now(elapsed_ms, stalled_ms, stall_threshold_ms) {
-            if let Err(e) = doser.motor_stop() {
-                tracing::warn!(error = %e, "motor_stop failed on timeout");
-            }
-            return Err(crate::error::Report::new(DoserError::Timeout));
+            return Err(abort_run(&mut doser, sampler.stall_error(), "timeout"));
         }
 
         // Max run enforcement
         if elapsed_ms >= safety.max_run_ms {
-            if let Err(e) = doser.motor_stop() {
-                tracing::warn!(error = %e, "motor_stop failed on max-run cap");
-            }
-            return Err(crate::error::Report::new(DoserError::Abort(
-                AbortReason::MaxRuntime,
-            )));
+            return Err(abort_run(
+                &mut doser,
+                DoserError::Abort(AbortReason::MaxRuntime),
+                "max-run cap",
+            ));
         }
 
         if !prefer_timeout_first && stalled_now(elapsed_ms, stalled_ms, stall_threshold_ms) {
-            if let Err(e) = doser.motor_stop() {
-                tracing::warn!(error = %e, "motor_stop failed on timeout");
-            }
-            return Err(crate::error::Report::new(DoserError::Timeout));
+            return Err(abort_run(&mut doser, sampler.stall_error(), "timeout"));
         }
 
-        if let Some(raw) = sampler.latest() {
-            match doser.step_from_raw(raw)? {
-                DosingStatus::Running => continue,
-                DosingStatus::Complete => {
-                    let final_g = doser.last_weight();
-                    tracing::info!(final_g, "dose complete");
-                    return Ok(final_g);
-                }
-                DosingStatus::Aborted(e) => {
-                    if let Err(me) = doser.motor_stop() {
-                        tracing::warn!(error = %me, "motor_stop failed on abort");
-                    }
-                    tracing::error!(error = %e, "dose aborted");
-                    return Err(crate::error::Report::new(e));
+        let step = match sampler.latest() {
+            Some(raw) => doser.step_from_raw(raw)?,
+            None if hold_ticks => doser.step_hold()?,
+            None => {
+                // avoid busy spin if no sample yet
+                std::thread::sleep(Duration::from_micros(period_us));
+                continue;
+            }
+        };
+        status.publish(&doser);
+        match step {
+            DosingStatus::Running => continue,
+            DosingStatus::Complete => {
+                let report = DoseReport::from_core(&doser);
+                tracing::info!(
+                    final_g = report.final_g,
+                    dropped_samples = sampler.dropped_updates(),
+                    loop_overruns = report.loop_overruns,
+                    coarse_ms = report.phases.coarse_ms,
+                    fine_ms = report.phases.fine_ms,
+                    settle_ms = report.phases.settle_ms,
+                    verify_ms = report.phases.verify_ms,
+                    "dose complete"
+                );
+                return Ok(report);
+            }
+            DosingStatus::Aborted(e) => {
+                if let Err(me) = doser.motor_stop() {
+                    tracing::warn!(error = %me, "motor_stop failed on abort");
                 }
+                tracing::error!(error = %e, "dose aborted");
+                return Err(doser.abort_report(e));
             }
-        } else {
-            // avoid busy spin if no sample yet
-            std::thread::sleep(Duration::from_micros(period_us));
         }
     }
 }
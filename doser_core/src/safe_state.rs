@@ -0,0 +1,90 @@
+//! Abort-to-safe-state sequence.
+//!
+//! Stopping the motor is always the first reaction to an abort (a hard
+//! `Motor::halt` after an E-stop or overshoot, else `Motor::stop`). A
+//! [`SafeState`] adds an optional, declarative follow-up ([`SafeStateCfg`]):
+//! reverse a few steps, release named actuators (e.g. close a solenoid), and
+//! disable the driver. Every action is best-effort: failures are logged and the
+//! remaining actions still run.
+
+use std::sync::{Arc, Mutex};
+
+use doser_traits::{Actuator, Motor};
+
+use crate::config::SafeStateCfg;
+
+/// An actuator shared between its owner and the safe-state sequence.
+pub type SharedActuator = Arc<Mutex<dyn Actuator + Send>>;
+
+/// Safe-state configuration plus the actuators it may drive.
+#[derive(Clone, Default)]
+pub struct SafeState {
+    cfg: SafeStateCfg,
+    actuators: Vec<(String, SharedActuator)>,
+}
+
+impl core::fmt::Debug for SafeState {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let names: Vec<&str> = self.actuators.iter().map(|(n, _)| n.as_str()).collect();
+        f.debug_struct("SafeState")
+            .field("cfg", &self.cfg)
+            .field("actuators", &names)
+            .finish()
+    }
+}
+
+impl SafeState {
+    pub fn new(cfg: SafeStateCfg) -> Self {
+        Self {
+            cfg,
+            actuators: Vec::new(),
+        }
+    }
+
+    /// Register an actuator that `SafeStateCfg::release` can refer to by name.
+    pub fn with_actuator(mut self, name: impl Into<String>, actuator: SharedActuator) -> Self {
+        self.actuators.push((name.into(), actuator));
+        self
+    }
+
+    pub fn cfg(&self) -> &SafeStateCfg {
+        &self.cfg
+    }
+
+    /// True when the sequence does nothing beyond the initial motor stop.
+    pub fn is_noop(&self) -> bool {
+        self.cfg.reverse_steps == 0 && self.cfg.release.is_empty() && !self.cfg.disable_driver
+    }
+
+    /// Run the sequence. Motion (the reversal) is skipped unless `allow_motion`.
+    pub(crate) fn run<M: Motor + ?Sized>(&self, motor: &mut M, allow_motion: bool) {
+        let cfg = &self.cfg;
+        if cfg.reverse_steps > 0 {
+            if allow_motion {
+                if let Err(e) = motor.reverse(cfg.reverse_steps, cfg.reverse_sps) {
+                    tracing::warn!(error = %e, steps = cfg.reverse_steps, "safe-state reverse failed");
+                }
+            } else {
+                tracing::info!("safe-state reverse skipped after E-stop");
+            }
+        }
+        for name in &cfg.release {
+            let Some((_, actuator)) = self.actuators.iter().find(|(n, _)| n == name) else {
+                tracing::warn!(actuator = %name, "safe-state actuator not registered");
+                continue;
+            };
+            // A poisoned lock only means another holder panicked; the output
+            // must still be released.
+            let mut guard = actuator.lock().unwrap_or_else(|p| p.into_inner());
+            if let Err(e) = guard.set_active(false) {
+                tracing::error!(error = %e, actuator = %name, "safe-state actuator release failed");
+            }
+        }
+        if cfg.disable_driver
+            && let Err(e) = motor.disable()
+        {
+            tracing::error!(error = %e, "safe-state driver disable failed");
+        }
+        tracing::info!(?cfg, "safe-state sequence complete");
+    }
+}
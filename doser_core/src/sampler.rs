@@ -1,22 +1,27 @@
 //! Background sensor sampling utilities.
 //!
-//! Spawns a thread that owns the `Scale`, pushes latest readings via a
-//! bounded channel, and tracks the last-ok timestamp for watchdog logic.
+//! Spawns a thread that owns the `Scale`, publishes the latest reading through
+//! a wait-free [`LatestValue`] seqlock, and tracks the last-ok timestamp for watchdog logic.
 //! Event-driven and paced variants are provided.
 //!
 //! Safety: Each `Sampler` spawns exactly one thread that is automatically
 //! shut down when the `Sampler` is dropped, preventing thread leaks.
-use crossbeam_channel as xch;
+use crate::handoff::{LatestValue, Sample};
 use doser_traits::Scale;
 use doser_traits::clock::Clock;
+use doser_traits::pacing::{OverrunPolicy, Pacer};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
 pub struct Sampler {
-    rx: xch::Receiver<i32>,
+    latest: Arc<LatestValue>,
     last_ok: Arc<AtomicU64>,
     epoch: Instant,
+    /// Average wake-up jitter (µs) of the paced sampler; 0 for event-driven.
+    avg_jitter_us: Arc<AtomicU32>,
+    /// Whether the most recent failed read was a data-ready timeout.
+    last_err_drdy: Arc<AtomicBool>,
     /// Shutdown flag for immediate response (atomic for lock-free check)
     shutdown: Arc<AtomicBool>,
     /// Join handle for graceful thread cleanup
@@ -30,15 +35,27 @@ impl Sampler {
         timeout: Duration,
         clock: C,
     ) -> Self {
-        let (tx, rx) = xch::bounded(1);
+        let latest = Arc::new(LatestValue::new());
+        let tx = latest.clone();
         let shutdown = Arc::new(AtomicBool::new(false));
         let shutdown_clone = shutdown.clone();
         let last_ok = Arc::new(AtomicU64::new(0));
         let last_ok_clone = last_ok.clone();
-        let period = Duration::from_micros(crate::util::period_us(hz));
+        let period_us = crate::util::period_us(hz);
         let epoch = clock.now();
+        let avg_jitter_us = Arc::new(AtomicU32::new(0));
+        let avg_jitter_us_bg = avg_jitter_us.clone();
+        let last_err_drdy = Arc::new(AtomicBool::new(false));
+        let last_err_drdy_bg = last_err_drdy.clone();
 
+        // Log the sampler thread under the caller's span (e.g. its run_id).
+        let span = tracing::Span::current();
         let join_handle = std::thread::spawn(move || {
+            let _span = span.entered();
+            // Drift-free: a slow read shortens the following sleep instead of
+            // stretching the sampling period. Missed slots are not made up.
+            let mut pacer = Pacer::new().with_overrun_policy(OverrunPolicy::SkipSleep);
+            pacer.reset_at(epoch);
             loop {
                 // Immediate shutdown check (lock-free atomic)
                 if shutdown_clone.load(Ordering::Relaxed) {
@@ -52,22 +69,13 @@ impl Sampler {
                         // Release so the watchdog reader (Acquire) observes a fresh timestamp.
                         // Mark liveness on a successful read, independent of delivery.
                         last_ok_clone.store(now, Ordering::Release);
-                        // Non-blocking publish (latest-value, best effort). A blocking send
-                        // on the bounded(1) channel could deadlock the Drop join if the
-                        // consumer stops while the channel is full, so never block here.
-                        match tx.try_send(v) {
-                            Ok(()) => {}
-                            Err(xch::TrySendError::Full(_)) => {
-                                // Consumer is behind; drop this sample.
-                            }
-                            Err(xch::TrySendError::Disconnected(_)) => {
-                                tracing::debug!("Sampler consumer disconnected, exiting thread");
-                                break;
-                            }
-                        }
+                        // Overwrite the slot; an unread older sample is superseded. Never
+                        // blocks on the consumer, so the Drop join cannot deadlock.
+                        tx.publish(v, clock.now());
                     }
-                    Err(_) => {
-                        // Optional: send special value or skip; controller has watchdog
+                    Err(e) => {
+                        // Skip; the controller's watchdog decides when to give up.
+                        record_error(&last_err_drdy_bg, &*e);
                     }
                 }
 
@@ -75,15 +83,19 @@ impl Sampler {
                 if shutdown_clone.load(Ordering::Relaxed) {
                     break;
                 }
-                clock.sleep(period);
+                if let Some(avg) = pacer.tick(&clock, period_us) {
+                    avg_jitter_us_bg.store(avg, Ordering::Relaxed);
+                }
             }
             tracing::trace!("Sampler thread exiting cleanly");
         });
 
         Self {
-            rx,
+            latest,
             last_ok,
             epoch,
+            avg_jitter_us,
+            last_err_drdy,
             shutdown,
             join_handle: Some(join_handle),
         }
@@ -96,14 +108,19 @@ impl Sampler {
         timeout: Duration,
         clock: C,
     ) -> Self {
-        let (tx, rx) = xch::bounded(1);
+        let latest = Arc::new(LatestValue::new());
+        let tx = latest.clone();
         let shutdown = Arc::new(AtomicBool::new(false));
         let shutdown_clone = shutdown.clone();
         let last_ok = Arc::new(AtomicU64::new(0));
         let last_ok_clone = last_ok.clone();
         let epoch = clock.now();
+        let last_err_drdy = Arc::new(AtomicBool::new(false));
+        let last_err_drdy_bg = last_err_drdy.clone();
 
+        let span = tracing::Span::current();
         let join_handle = std::thread::spawn(move || {
+            let _span = span.entered();
             loop {
                 // Immediate shutdown check (lock-free atomic)
                 if shutdown_clone.load(Ordering::Relaxed) {
@@ -117,24 +134,13 @@ impl Sampler {
                         // Release so the watchdog reader (Acquire) observes a fresh timestamp.
                         // Mark liveness on a successful read, independent of delivery.
                         last_ok_clone.store(now, Ordering::Release);
-                        // Non-blocking publish (latest-value, best effort): never block on a
-                        // full channel, so the thread always observes shutdown and the Drop
-                        // join cannot deadlock.
-                        match tx.try_send(v) {
-                            Ok(()) => {}
-                            Err(xch::TrySendError::Full(_)) => {
-                                // Consumer is behind; drop this sample.
-                            }
-                            Err(xch::TrySendError::Disconnected(_)) => {
-                                tracing::debug!(
-                                    "Sampler event consumer disconnected, exiting thread"
-                                );
-                                break;
-                            }
-                        }
+                        // Overwrite the slot; an unread older sample is superseded. Never
+                        // blocks on the consumer, so the Drop join cannot deadlock.
+                        tx.publish(v, clock.now());
                     }
-                    Err(_) => {
+                    Err(e) => {
                         // On timeout or transient error, just continue; controller will watchdog
+                        record_error(&last_err_drdy_bg, &*e);
                     }
                 }
 
@@ -148,16 +154,40 @@ impl Sampler {
         });
 
         Self {
-            rx,
+            latest,
             last_ok,
             epoch,
+            avg_jitter_us: Arc::new(AtomicU32::new(0)),
+            last_err_drdy,
             shutdown,
             join_handle: Some(join_handle),
         }
     }
 
+    /// Take the newest raw value published since the previous call, if any.
     pub fn latest(&self) -> Option<i32> {
-        self.rx.try_iter().last()
+        self.latest.take().map(|s| s.raw)
+    }
+    /// Like [`Self::latest`], but with the publish timestamp and sequence number.
+    pub fn latest_sample(&self) -> Option<Sample> {
+        self.latest.take()
+    }
+    /// Samples that were overwritten before the consumer took them.
+    pub fn dropped_updates(&self) -> u64 {
+        self.latest.dropped()
+    }
+    /// Average wake-up jitter (µs) of the paced sampler over the last full window.
+    pub fn avg_jitter_us(&self) -> u32 {
+        self.avg_jitter_us.load(Ordering::Relaxed)
+    }
+    /// The error a stall should be reported as: a data-ready timeout if that is
+    /// how the last read failed, else the generic sensor timeout.
+    pub fn stall_error(&self) -> crate::error::DoserError {
+        if self.last_err_drdy.load(Ordering::Relaxed) {
+            crate::error::DoserError::DataReadyTimeout
+        } else {
+            crate::error::DoserError::Timeout
+        }
     }
     pub fn stalled_for(&self, now_ms: u64) -> u64 {
         now_ms.saturating_sub(self.last_ok.load(Ordering::Acquire))
@@ -173,6 +203,14 @@ impl Sampler {
     }
 }
 
+fn record_error(last_err_drdy: &AtomicBool, e: &(dyn std::error::Error + 'static)) {
+    let drdy = matches!(
+        crate::hw_error::map_hw_error(e),
+        crate::error::DoserError::DataReadyTimeout
+    );
+    last_err_drdy.store(drdy, Ordering::Relaxed);
+}
+
 impl Drop for Sampler {
     fn drop(&mut self) {
         // Signal shutdown immediately (atomic store is very fast, <10ns)
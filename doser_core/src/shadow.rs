@@ -0,0 +1,203 @@
+//! Shadow (read-only) operation alongside an existing controller.
+//!
+//! In shadow mode the control loop runs against the real scale but drives a
+//! [`ShadowMotor`], which only records what would have been commanded. A
+//! [`ShadowMonitor`] compares that command with the flow actually observed on
+//! the scale (produced by the externally driven process) and logs where the two
+//! diverge, so the controller can be piloted on a line still run by legacy logic.
+
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Commanded state shared between a [`ShadowMotor`] and its observers.
+#[derive(Debug, Default)]
+struct Commanded {
+    started: AtomicBool,
+    sps: AtomicU32,
+}
+
+/// Motor stand-in that records commands and never touches hardware.
+#[derive(Debug, Default)]
+pub struct ShadowMotor {
+    cmd: Arc<Commanded>,
+}
+
+/// Read-only view of what a [`ShadowMotor`] was last commanded to do.
+#[derive(Debug, Clone)]
+pub struct ShadowProbe {
+    cmd: Arc<Commanded>,
+}
+
+impl ShadowMotor {
+    /// Create a shadow motor and a probe observing its commands.
+    pub fn new() -> (Self, ShadowProbe) {
+        let cmd = Arc::new(Commanded::default());
+        (Self { cmd: cmd.clone() }, ShadowProbe { cmd })
+    }
+}
+
+impl ShadowProbe {
+    /// True when the controller would currently be running the motor.
+    pub fn running(&self) -> bool {
+        self.cmd.started.load(Ordering::Acquire) && self.sps() > 0
+    }
+
+    /// Last commanded speed in steps per second.
+    pub fn sps(&self) -> u32 {
+        self.cmd.sps.load(Ordering::Acquire)
+    }
+}
+
+impl doser_traits::Motor for ShadowMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.cmd.started.store(true, Ordering::Release);
+        Ok(())
+    }
+    fn set_speed(&mut self, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.cmd.sps.store(sps, Ordering::Release);
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.cmd.started.store(false, Ordering::Release);
+        self.cmd.sps.store(0, Ordering::Release);
+        Ok(())
+    }
+    fn reverse(&mut self, _steps: u32, _sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.stop()
+    }
+}
+
+/// Kind of disagreement between the shadow controller and the external process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    /// The controller would run the motor, but no material is flowing.
+    WouldRunExternalIdle,
+    /// The controller would have stopped, but material is still flowing.
+    WouldStopExternalRunning,
+}
+
+/// Summary of a shadow run.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ShadowReport {
+    /// Weight at which the controller would first have stopped the motor.
+    pub would_stop_at_g: Option<f32>,
+    /// Last observed weight.
+    pub last_g: f32,
+    /// Total time the controller and the external process disagreed.
+    pub divergent_ms: u64,
+    /// Number of separate disagreement episodes.
+    pub episodes: u32,
+}
+
+/// Flow EMA weight given to each new slope sample.
+const FLOW_ALPHA: f32 = 0.3;
+
+/// Compares commanded motor state with observed flow and logs divergence.
+#[derive(Debug, Clone)]
+pub struct ShadowMonitor {
+    flow_threshold_gps: f32,
+    prev: Option<(u64, f32)>,
+    flow_gps: f32,
+    was_running: bool,
+    /// Since when the observed flow has been below the threshold.
+    quiet_since: Option<u64>,
+    current: Option<(Divergence, u64)>,
+    report: ShadowReport,
+}
+
+impl ShadowMonitor {
+    /// `flow_threshold_gps`: observed flow (g/s) above which the external
+    /// process is considered to be dispensing.
+    pub fn new(flow_threshold_gps: f32) -> Self {
+        Self {
+            flow_threshold_gps,
+            prev: None,
+            flow_gps: 0.0,
+            was_running: false,
+            quiet_since: None,
+            current: None,
+            report: ShadowReport::default(),
+        }
+    }
+
+    /// Smoothed observed flow in grams per second.
+    pub fn flow_gps(&self) -> f32 {
+        self.flow_gps
+    }
+
+    /// Feed one observation: time (ms), weight (g), and whether the controller
+    /// would be running the motor. Returns the divergence in effect, if any.
+    pub fn observe(&mut self, now_ms: u64, weight_g: f32, would_run: bool) -> Option<Divergence> {
+        if let Some((t, w)) = self.prev
+            && now_ms > t
+        {
+            let slope = (weight_g - w) * 1000.0 / (now_ms - t) as f32;
+            self.flow_gps += FLOW_ALPHA * (slope - self.flow_gps);
+        }
+        self.prev = Some((now_ms, weight_g));
+        self.report.last_g = weight_g;
+
+        if self.was_running && !would_run && self.report.would_stop_at_g.is_none() {
+            self.report.would_stop_at_g = Some(weight_g);
+            tracing::info!(weight_g, "shadow: controller would stop the motor here");
+        }
+        self.was_running = would_run;
+
+        let flowing = self.flow_gps > self.flow_threshold_gps;
+        if flowing {
+            self.quiet_since = None;
+        } else if self.quiet_since.is_none() {
+            self.quiet_since = Some(now_ms);
+        }
+        let now = match (would_run, flowing) {
+            (true, false) => Some(Divergence::WouldRunExternalIdle),
+            (false, true) => Some(Divergence::WouldStopExternalRunning),
+            _ => None,
+        };
+        match (self.current, now) {
+            (Some((kind, _)), Some(k)) if kind == k => {}
+            (prev, next) => {
+                if let Some((kind, since)) = prev {
+                    self.close(kind, since, now_ms);
+                }
+                if let Some(kind) = next {
+                    tracing::warn!(
+                        ?kind,
+                        weight_g,
+                        flow_gps = self.flow_gps,
+                        "shadow: divergence"
+                    );
+                    self.report.episodes += 1;
+                    self.current = Some((kind, now_ms));
+                }
+            }
+        }
+        now
+    }
+
+    /// True once the controller would have stopped and the external process has
+    /// also been quiet for `quiet_ms`: nothing more to compare for this dose.
+    pub fn settled(&self, now_ms: u64, quiet_ms: u64) -> bool {
+        self.report.would_stop_at_g.is_some()
+            && !self.was_running
+            && self
+                .quiet_since
+                .is_some_and(|t| now_ms.saturating_sub(t) >= quiet_ms)
+    }
+
+    /// Close any open divergence episode at `now_ms` and return the summary.
+    pub fn finish(&mut self, now_ms: u64) -> ShadowReport {
+        if let Some((kind, since)) = self.current {
+            self.close(kind, since, now_ms);
+        }
+        self.report
+    }
+
+    fn close(&mut self, kind: Divergence, since: u64, now_ms: u64) {
+        let ms = now_ms.saturating_sub(since);
+        self.report.divergent_ms = self.report.divergent_ms.saturating_add(ms);
+        self.current = None;
+        tracing::info!(?kind, duration_ms = ms, "shadow: divergence ended");
+    }
+}
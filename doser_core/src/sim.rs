@@ -0,0 +1,398 @@
+//! Seeded Monte Carlo simulation: many doses of one tuning against a simple
+//! plant model, summarised as percentiles, so a config change can be judged on
+//! its predicted spread before it reaches a machine.
+//!
+//! The plant turns motor steps into material at a flow per step that varies
+//! from run to run, delivers it to the scale after a transport delay, and adds
+//! reading noise ([`NoiseModel`]: white, pink drift and mains hum). Each run
+//! drives the real controller ([`crate::build_doser`]) on a [`VirtualClock`],
+//! so a run takes microseconds and the same seed always gives the same report.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use doser_traits::clock::{Clock, VirtualClock};
+
+use crate::calibration::Calibration;
+use crate::config::{ControlCfg, FilterCfg, PredictorCfg, SafetyCfg, Timeouts};
+use crate::error::{AbortReason, DoserError};
+use crate::status::DosingStatus;
+
+/// The simulated mechanism.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plant {
+    /// Mean material flow per motor step (grams)
+    pub g_per_step: f32,
+    /// Run-to-run spread of the flow, as a fraction (uniform within ±)
+    pub flow_spread: f32,
+    /// Time from the auger to the scale; material in flight when the motor
+    /// stops still lands
+    pub latency_ms: u64,
+    /// Reading noise
+    pub noise: NoiseModel,
+}
+
+impl Default for Plant {
+    fn default() -> Self {
+        Self {
+            g_per_step: 0.001,
+            flow_spread: 0.1,
+            latency_ms: 100,
+            noise: NoiseModel::default(),
+        }
+    }
+}
+
+/// Reading noise of a simulated load cell, in grams; the components add up.
+///
+/// Real cells are not white: amplifier and thermal effects wander slowly
+/// (pink, 1/f), which averaging filters barely touch, and wiring picks up
+/// mains hum, which the scale's sample rate aliases down to a slow beat (or a
+/// fixed offset at a rate that divides the mains frequency).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseModel {
+    /// White Gaussian noise, one sigma
+    pub white_g: f32,
+    /// Pink noise, one sigma, over eight octaves below the sample rate
+    pub pink_g: f32,
+    /// Mains hum amplitude
+    pub hum_g: f32,
+    /// Mains frequency (Hz)
+    pub mains_hz: f32,
+}
+
+impl Default for NoiseModel {
+    fn default() -> Self {
+        Self {
+            white_g: 0.01,
+            pink_g: 0.0,
+            hum_g: 0.0,
+            mains_hz: 50.0,
+        }
+    }
+}
+
+/// Rows of the Voss-McCartney pink generator: one octave each.
+const PINK_ROWS: usize = 8;
+
+/// A seeded source of [`NoiseModel`] noise, one value per reading.
+#[derive(Debug, Clone)]
+pub struct NoiseSource {
+    model: NoiseModel,
+    rng: XorShift64,
+    /// Row `k` is redrawn every `2^k` readings.
+    pink_rows: [f32; PINK_ROWS],
+    readings: u64,
+    /// Hum phase at time zero (radians)
+    hum_phase: f32,
+}
+
+impl NoiseSource {
+    pub fn new(model: NoiseModel, seed: u64) -> Self {
+        let mut rng = XorShift64::new(seed);
+        let pink_rows = core::array::from_fn(|_| rng.next_gaussian());
+        let hum_phase = std::f32::consts::TAU * rng.next_f32();
+        Self {
+            model,
+            rng,
+            pink_rows,
+            readings: 0,
+            hum_phase,
+        }
+    }
+
+    /// Noise of the reading taken `t_s` seconds into the run (grams).
+    pub fn sample(&mut self, t_s: f64) -> f32 {
+        let m = self.model;
+        let mut g = 0.0;
+        if m.white_g > 0.0 {
+            g += m.white_g * self.rng.next_gaussian();
+        }
+        if m.pink_g > 0.0 {
+            for (k, row) in self.pink_rows.iter_mut().enumerate() {
+                if self.readings.is_multiple_of(1 << k) {
+                    *row = self.rng.next_gaussian();
+                }
+            }
+            let sum: f32 = self.pink_rows.iter().sum();
+            g += m.pink_g * sum / (PINK_ROWS as f32).sqrt();
+        }
+        if m.hum_g > 0.0 {
+            // In f64: the phase grows by the mains frequency every second.
+            let cycles = (f64::from(m.mains_hz) * t_s).fract();
+            let angle = std::f64::consts::TAU * cycles + f64::from(self.hum_phase);
+            g += m.hum_g * angle.sin() as f32;
+        }
+        self.readings += 1;
+        g
+    }
+}
+
+/// The tuning under test.
+#[derive(Debug, Clone)]
+pub struct Tuning {
+    pub filter: FilterCfg,
+    pub control: ControlCfg,
+    pub safety: SafetyCfg,
+    pub timeouts: Timeouts,
+    pub predictor: Option<PredictorCfg>,
+}
+
+/// One simulated dose.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunOutcome {
+    /// Everything dispensed, including material still in flight when the
+    /// dose ended (grams)
+    pub final_g: f32,
+    /// Time from start to completion or abort (virtual)
+    pub duration_ms: u64,
+    pub abort: Option<AbortReason>,
+}
+
+/// Outcome of a Monte Carlo batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimReport {
+    pub target_g: f32,
+    pub seed: u64,
+    pub runs: Vec<RunOutcome>,
+}
+
+impl SimReport {
+    /// Names accepted by [`Self::metric`].
+    pub const METRICS: &'static [&'static str] = &[
+        "overshoot_p50",
+        "overshoot_p95",
+        "overshoot_max",
+        "abs_error_p95",
+        "error_mean",
+        "duration_p50_ms",
+        "duration_p95_ms",
+        "abort_rate",
+    ];
+
+    /// A summary statistic by name. Weight and duration figures cover the
+    /// completed runs only (`None` if none completed); `abort_rate` is the
+    /// share of runs that aborted.
+    pub fn metric(&self, name: &str) -> Option<f64> {
+        let done: Vec<&RunOutcome> = self.runs.iter().filter(|r| r.abort.is_none()).collect();
+        let target = f64::from(self.target_g);
+        let errors = || done.iter().map(|r| f64::from(r.final_g) - target);
+        let overshoot = || percentiles(errors().map(|e| e.max(0.0)));
+        let durations = || percentiles(done.iter().map(|r| r.duration_ms as f64));
+        match name {
+            "overshoot_p50" => overshoot().map(|p| p.at(0.50)),
+            "overshoot_p95" => overshoot().map(|p| p.at(0.95)),
+            "overshoot_max" => overshoot().map(|p| p.at(1.0)),
+            "abs_error_p95" => percentiles(errors().map(f64::abs)).map(|p| p.at(0.95)),
+            "error_mean" => (!done.is_empty()).then(|| errors().sum::<f64>() / done.len() as f64),
+            "duration_p50_ms" => durations().map(|p| p.at(0.50)),
+            "duration_p95_ms" => durations().map(|p| p.at(0.95)),
+            "abort_rate" => (!self.runs.is_empty())
+                .then(|| (self.runs.len() - done.len()) as f64 / self.runs.len() as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Sorted samples for nearest-rank percentiles.
+struct Percentiles(Vec<f64>);
+
+impl Percentiles {
+    fn at(&self, q: f64) -> f64 {
+        let rank = (q * self.0.len() as f64).ceil() as usize;
+        self.0[rank.clamp(1, self.0.len()) - 1]
+    }
+}
+
+fn percentiles(values: impl Iterator<Item = f64>) -> Option<Percentiles> {
+    let mut v: Vec<f64> = values.collect();
+    v.sort_by(f64::total_cmp);
+    (!v.is_empty()).then_some(Percentiles(v))
+}
+
+/// Run `runs` doses of `target_g` under `tuning` against `plant`.
+pub fn monte_carlo(
+    tuning: &Tuning,
+    plant: &Plant,
+    target_g: f32,
+    runs: u32,
+    seed: u64,
+) -> crate::error::Result<SimReport> {
+    let mut rng = XorShift64::new(seed);
+    let mut out = Vec::with_capacity(runs as usize);
+    for _ in 0..runs {
+        out.push(run_one(tuning, plant, target_g, rng.next_u64())?);
+    }
+    Ok(SimReport {
+        target_g,
+        seed,
+        runs: out,
+    })
+}
+
+fn run_one(
+    tuning: &Tuning,
+    plant: &Plant,
+    target_g: f32,
+    seed: u64,
+) -> crate::error::Result<RunOutcome> {
+    let mut rng = XorShift64::new(seed);
+    let flow = plant.g_per_step * (1.0 + plant.flow_spread * (2.0 * rng.next_f32() - 1.0));
+    let clock = VirtualClock::new();
+    let state = Arc::new(Mutex::new(PlantState::default()));
+    let plant_state = Arc::clone(&state);
+    let scale = PlantScale {
+        state: Arc::clone(&state),
+        clock: clock.clone(),
+        g_per_step: flow.max(0.0),
+        latency: Duration::from_millis(plant.latency_ms),
+        noise: NoiseSource::new(plant.noise, rng.next_u64()),
+        start: clock.now(),
+        in_flight: VecDeque::new(),
+        landed_g: 0.0,
+        last_read: None,
+    };
+    let period = Duration::from_secs(1) / tuning.filter.sample_rate_hz.max(1);
+    let mut doser = crate::build_doser(
+        scale,
+        PlantMotor(state),
+        tuning.filter.clone(),
+        tuning.control.clone(),
+        tuning.safety.clone(),
+        tuning.timeouts.clone(),
+        Some(Calibration {
+            gain_g_per_count: 0.01,
+            zero_counts: 0,
+            offset_g: 0.0,
+        }),
+        target_g,
+        None,
+        tuning.predictor.clone(),
+        Some(Box::new(clock.clone())),
+        None,
+    )?;
+    doser.begin();
+    loop {
+        clock.advance(period);
+        let status = doser.step()?;
+        let abort = match status {
+            DosingStatus::Running => continue,
+            DosingStatus::Complete => None,
+            DosingStatus::Aborted(DoserError::Abort(reason)) => Some(reason),
+            DosingStatus::Aborted(e) => return Err(e.into()),
+        };
+        let final_g = plant_state.lock().map(|s| s.released_g).unwrap_or(0.0);
+        return Ok(RunOutcome {
+            final_g,
+            duration_ms: doser.phase_timings().total_ms(),
+            abort,
+        });
+    }
+}
+
+#[derive(Debug, Default)]
+struct PlantState {
+    sps: u32,
+    /// Everything the auger has released, landed or not (grams)
+    released_g: f32,
+}
+
+struct PlantMotor(Arc<Mutex<PlantState>>);
+
+impl doser_traits::Motor for PlantMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+
+    fn set_speed(&mut self, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Ok(mut s) = self.0.lock() {
+            s.sps = sps;
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.set_speed(0)
+    }
+}
+
+/// Material leaves the auger at the commanded speed and reaches the scale
+/// `latency` later.
+struct PlantScale {
+    state: Arc<Mutex<PlantState>>,
+    clock: VirtualClock,
+    g_per_step: f32,
+    latency: Duration,
+    noise: NoiseSource,
+    start: Instant,
+    /// Material released at each read, still in flight: `(released, grams)`
+    in_flight: VecDeque<(Instant, f32)>,
+    landed_g: f32,
+    last_read: Option<Instant>,
+}
+
+impl doser_traits::Scale for PlantScale {
+    fn read(&mut self, _timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        let now = self.clock.now();
+        let dt = self
+            .last_read
+            .map_or(0.0, |t| now.saturating_duration_since(t).as_secs_f32());
+        self.last_read = Some(now);
+        let released = match self.state.lock() {
+            Ok(mut s) => {
+                let g = s.sps as f32 * self.g_per_step * dt;
+                s.released_g += g;
+                g
+            }
+            Err(_) => 0.0,
+        };
+        self.in_flight.push_back((now, released));
+        while let Some(&(t, g)) = self.in_flight.front() {
+            if now.saturating_duration_since(t) < self.latency {
+                break;
+            }
+            self.landed_g += g;
+            self.in_flight.pop_front();
+        }
+        let t_s = now.saturating_duration_since(self.start).as_secs_f64();
+        let reading = self.landed_g + self.noise.sample(t_s);
+        Ok((reading * 100.0).round() as i32)
+    }
+}
+
+/// Small deterministic generator (xorshift64*); not for anything but noise.
+#[derive(Debug, Clone)]
+pub(crate) struct XorShift64(u64);
+
+impl XorShift64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        // Zero is a fixed point; splitmix the seed so nearby seeds diverge.
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Self((z ^ (z >> 31)).max(1))
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in [0, 1).
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Standard normal (Box-Muller).
+    pub(crate) fn next_gaussian(&mut self) -> f32 {
+        let u1 = self.next_f32().max(f32::MIN_POSITIVE);
+        let u2 = self.next_f32();
+        (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+    }
+}
@@ -1,4 +1,7 @@
-//! Dosing status returned from each control loop iteration.
+//! Dosing status returned from each control loop iteration, and the run
+//! status a supervisor polls while the loop runs.
+
+use std::sync::{Arc, Mutex, PoisonError};
 
 use crate::error::DoserError;
 
@@ -12,3 +15,223 @@ pub enum DosingStatus {
     /// Aborted with a typed error; motor has been asked to stop.
     Aborted(DoserError),
 }
+
+/// Coarse-grained stage of a dose, used for per-phase timing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DosePhase {
+    /// Motor running at the fastest configured speed.
+    Coarse,
+    /// Motor running at a reduced speed as the target approaches.
+    Fine,
+    /// Motor stopped (settle zone or predictive early stop), waiting for the
+    /// reading to come to rest within the acceptance band.
+    Settle,
+    /// Motor stopped and the reading within the acceptance band: the stable
+    /// hold (`stable_ms`) that verifies the final weight.
+    Verify,
+}
+
+/// Speed the motor is held at, for display (see `DoserCore::current_band`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpeedBand {
+    /// `control.speed_bands[index]`: `sps` while the remaining error is at
+    /// least `threshold_g` (the last band below that).
+    Band {
+        index: usize,
+        threshold_g: f32,
+        sps: u32,
+    },
+    /// Two-speed mode (no `speed_bands`): `coarse_speed`.
+    Coarse,
+    /// Two-speed mode: the taper below `slow_at_g`, currently at `sps`.
+    Fine { sps: u32 },
+}
+
+/// Wall time spent in each [`DosePhase`] during the current dose, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    pub coarse_ms: u64,
+    pub fine_ms: u64,
+    pub settle_ms: u64,
+    pub verify_ms: u64,
+}
+
+impl PhaseTimings {
+    /// Sum of all phases.
+    pub fn total_ms(&self) -> u64 {
+        self.coarse_ms + self.fine_ms + self.settle_ms + self.verify_ms
+    }
+
+    pub(crate) fn add(&mut self, phase: DosePhase, ms: u64) {
+        let slot = match phase {
+            DosePhase::Coarse => &mut self.coarse_ms,
+            DosePhase::Fine => &mut self.fine_ms,
+            DosePhase::Settle => &mut self.settle_ms,
+            DosePhase::Verify => &mut self.verify_ms,
+        };
+        *slot = slot.saturating_add(ms);
+    }
+}
+
+/// One approach of the motor towards the target, from its start to the next
+/// stop (see `ControlCfg::max_attempts`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attempt {
+    /// 1 for the first run, 2 for the first top-up, ...
+    pub n: u32,
+    /// Since the dose began.
+    pub start_ms: u64,
+    /// Control weight when the motor started.
+    pub start_g: f32,
+    /// When the motor stopped; `None` while it runs.
+    pub stop_ms: Option<u64>,
+    /// Control weight when the motor stopped.
+    pub stop_g: Option<f32>,
+}
+
+/// Where a run stands, for [`RunStatus`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RunState {
+    /// Not started (or still in warm-up and pre-flight).
+    #[default]
+    Idle,
+    /// The control loop is running.
+    Running,
+    /// Settled at target.
+    Complete,
+    /// Stopped by an abort or error.
+    Aborted,
+}
+
+impl RunState {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::Running => "running",
+            Self::Complete => "complete",
+            Self::Aborted => "aborted",
+        }
+    }
+}
+
+/// Snapshot of a dose as of its latest control step, for a supervisor on
+/// another thread (see [`StatusBoard`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RunStatus {
+    pub state: RunState,
+    pub phase: Option<DosePhase>,
+    /// Speed band while the motor runs.
+    pub band: Option<SpeedBand>,
+    /// Control weight, in grams.
+    pub weight_g: f32,
+    pub target_g: f32,
+    /// Weight plus the predictor's in-flight estimate, when it runs.
+    pub est_final_g: Option<f32>,
+    /// `weight_g` as a percentage of `target_g`.
+    pub pct: f32,
+    /// Time to target at the current flow, when material is flowing.
+    pub eta_ms: Option<u64>,
+    /// Time in the control loop so far.
+    pub elapsed_ms: u64,
+    /// Control iterations that overran their period.
+    pub loop_overruns: u64,
+    /// Timed-out scale reads retried.
+    pub read_retries: u64,
+    /// The motor backend's stepping counters, if it keeps them.
+    pub motor: Option<doser_traits::MotorDiagSnapshot>,
+}
+
+impl RunStatus {
+    /// Snapshot of `doser` after its latest step.
+    pub fn of<S: doser_traits::Scale, M: doser_traits::Motor>(
+        doser: &crate::DoserCore<S, M>,
+        state: RunState,
+    ) -> Self {
+        let weight_g = doser.last_weight();
+        let target_g = doser.target_g();
+        let remaining_g = target_g - weight_g;
+        let eta_ms = doser
+            .last_slope_ema_gps()
+            .filter(|&gps| gps > 0.0 && remaining_g > 0.0)
+            .map(|gps| (remaining_g / gps * 1000.0) as u64);
+        Self {
+            state,
+            phase: doser.phase(),
+            band: doser.current_band(),
+            weight_g,
+            target_g,
+            est_final_g: doser.last_inflight_g().map(|g| weight_g + g),
+            pct: if target_g > 0.0 {
+                weight_g / target_g * 100.0
+            } else {
+                0.0
+            },
+            eta_ms,
+            elapsed_ms: doser.phase_timings().total_ms(),
+            loop_overruns: doser.loop_overruns(),
+            read_retries: doser.read_retries(),
+            motor: doser.motor_diagnostics(),
+        }
+    }
+
+    /// Short names of what went wrong so far without stopping the dose.
+    pub fn warnings(&self) -> Vec<&'static str> {
+        let mut w = Vec::new();
+        if self.loop_overruns > 0 {
+            w.push("loop_overruns");
+        }
+        if self.read_retries > 0 {
+            w.push("read_retries");
+        }
+        if self.motor.is_some_and(|m| m.missed_periods > 0) {
+            w.push("missed_step_periods");
+        }
+        w
+    }
+}
+
+/// Latest [`RunStatus`] of a run, shared with other threads. The control loop
+/// only publishes when the board is free, so a slow reader never delays a
+/// step (it sees the status one step later instead). The default board is
+/// inert.
+#[derive(Clone, Default)]
+pub struct StatusBoard(Option<Arc<Mutex<RunStatus>>>);
+
+impl StatusBoard {
+    pub fn new() -> Self {
+        Self(Some(Arc::default()))
+    }
+
+    /// The latest status (`Idle` until the loop starts).
+    pub fn latest(&self) -> RunStatus {
+        self.0
+            .as_ref()
+            .map(|m| *m.lock().unwrap_or_else(PoisonError::into_inner))
+            .unwrap_or_default()
+    }
+
+    /// Publish `doser`'s status as running, unless a reader holds the board.
+    pub fn publish<S: doser_traits::Scale, M: doser_traits::Motor>(
+        &self,
+        doser: &crate::DoserCore<S, M>,
+    ) {
+        if let Some(m) = &self.0
+            && let Ok(mut s) = m.try_lock()
+        {
+            *s = RunStatus::of(doser, RunState::Running);
+        }
+    }
+
+    /// Record how the run ended, keeping the last published snapshot.
+    pub fn finish(&self, state: RunState) {
+        if let Some(m) = &self.0 {
+            m.lock().unwrap_or_else(PoisonError::into_inner).state = state;
+        }
+    }
+}
+
+impl core::fmt::Debug for StatusBoard {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("StatusBoard").field(&self.latest()).finish()
+    }
+}
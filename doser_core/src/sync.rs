@@ -0,0 +1,15 @@
+//! Synchronization primitives used by cross-thread handoffs.
+//!
+//! Under `RUSTFLAGS="--cfg loom"` these resolve to `loom`'s model-checked
+//! equivalents so the handoff protocols can be exhaustively explored; otherwise
+//! they are plain `std` types with zero overhead.
+
+#[cfg(loom)]
+pub(crate) use loom::hint::spin_loop;
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{AtomicI32, AtomicU64, Ordering, fence};
+
+#[cfg(not(loom))]
+pub(crate) use std::hint::spin_loop;
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic::{AtomicI32, AtomicU64, Ordering, fence};
@@ -0,0 +1,454 @@
+//! Offline tuning by Bayesian optimization: speed bands and predictor
+//! settings are searched for the cheapest dose, each candidate scored by a
+//! Monte Carlo batch ([`crate::sim`]) on a weighted cost of overshoot and dose
+//! time, so a tuning can be proposed for a plant without running the machine.
+//!
+//! After a few uniformly sampled candidates, a Gaussian-process surrogate
+//! (squared-exponential kernel, length scale chosen by marginal likelihood)
+//! is fitted to the log costs seen so far, and the next candidate is the one
+//! with the largest expected improvement over the best, among a seeded pool
+//! of points spread over the space and around the best. Every candidate runs
+//! its batch with the same seed, so cost differences come from the tuning
+//! rather than the draw, and the same inputs always give the same result.
+
+use crate::config::{ControlCfg, PredictorCfg};
+use crate::sim::{Plant, SimReport, Tuning, XorShift64, monte_carlo};
+
+/// Weights of the search cost.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    /// Cost per gram of 95th-percentile overshoot
+    pub overshoot_per_g: f64,
+    /// Cost per second of 95th-percentile dose time
+    pub per_s: f64,
+}
+
+impl Default for Weights {
+    /// 0.1 g of overshoot weighs as much as 10 s of dosing.
+    fn default() -> Self {
+        Self {
+            overshoot_per_g: 1.0,
+            per_s: 0.01,
+        }
+    }
+}
+
+/// Cost of the share of runs that aborted: at the default weights a 1 %
+/// abort rate costs as much as 1 g of overshoot.
+const ABORT_COST: f64 = 100.0;
+
+/// Cost of a batch: weighted 95th-percentile overshoot and duration of the
+/// completed runs, plus a penalty for aborts. Infinite when no run completed.
+pub fn cost(report: &SimReport, w: &Weights) -> f64 {
+    let (Some(overshoot), Some(duration_ms), Some(aborts)) = (
+        report.metric("overshoot_p95"),
+        report.metric("duration_p95_ms"),
+        report.metric("abort_rate"),
+    ) else {
+        return f64::INFINITY;
+    };
+    w.overshoot_per_g * overshoot + w.per_s * duration_ms / 1000.0 + ABORT_COST * aborts
+}
+
+/// Search budget and cost weights.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Search {
+    /// Candidate tunings to simulate besides the starting one
+    pub candidates: u32,
+    /// Simulated doses per candidate
+    pub runs: u32,
+    pub seed: u64,
+    pub weights: Weights,
+}
+
+/// A tuning with its batch and cost.
+#[derive(Debug, Clone)]
+pub struct Scored {
+    pub tuning: Tuning,
+    pub report: SimReport,
+    pub cost: f64,
+}
+
+/// Outcome of [`optimize`].
+#[derive(Debug, Clone)]
+pub struct TuneReport {
+    /// The starting tuning
+    pub baseline: Scored,
+    /// The cheapest tuning found; the baseline when no candidate beat it
+    pub best: Scored,
+    /// Candidates simulated (ones the controller rejects are skipped)
+    pub evaluated: u32,
+}
+
+impl TuneReport {
+    /// Whether a candidate beat the starting tuning.
+    pub fn improved(&self) -> bool {
+        self.best.cost < self.baseline.cost
+    }
+}
+
+/// Dimensions of the search space, each over `[0, 1]`; see [`decode`].
+const DIMS: usize = 9;
+
+/// Slowest band speed a candidate may use (steps per second).
+const MIN_SPS: u32 = 10;
+
+/// Candidates sampled uniformly before the surrogate picks them.
+const INITIAL: u32 = DIMS as u32 + 1;
+
+/// Points of the acquisition pool spread uniformly over the space, and
+/// scattered around the best candidate so far.
+const POOL_UNIFORM: usize = 256;
+const POOL_LOCAL: usize = 64;
+
+/// Spread of the pool points around the best candidate (fraction of each range).
+const LOCAL_STEP: f32 = 0.08;
+
+/// Search `search.candidates` tunings derived from `base` for the cheapest
+/// dose of `target_g` against `plant`. Filter, safety and the other control
+/// and predictor settings stay those of `base`, and no band runs faster than
+/// its fastest speed.
+pub fn optimize(
+    base: &Tuning,
+    plant: &Plant,
+    target_g: f32,
+    search: &Search,
+) -> crate::error::Result<TuneReport> {
+    let score = |tuning: Tuning| -> crate::error::Result<Scored> {
+        let report = monte_carlo(&tuning, plant, target_g, search.runs, search.seed)?;
+        let cost = cost(&report, &search.weights);
+        Ok(Scored {
+            tuning,
+            report,
+            cost,
+        })
+    };
+    let baseline = score(base.clone())?;
+    let max_sps = base
+        .control
+        .speed_bands
+        .iter()
+        .map(|&(_, sps)| sps)
+        .max()
+        .unwrap_or(base.control.coarse_speed)
+        .max(MIN_SPS);
+
+    let mut rng = XorShift64::new(search.seed);
+    let mut seen: Vec<([f32; DIMS], f64)> = Vec::new();
+    let mut best: Option<Scored> = None;
+    let mut evaluated = 0;
+    for i in 0..search.candidates {
+        let x = if i < INITIAL {
+            uniform(&mut rng)
+        } else {
+            propose(&seen, &mut rng)
+        };
+        // A tuning the controller rejects is skipped, and recorded as the
+        // worst cost so the surrogate steers away from it.
+        let Ok(scored) = score(decode(base, &x, target_g, max_sps)) else {
+            seen.push((x, f64::INFINITY));
+            continue;
+        };
+        evaluated += 1;
+        seen.push((x, scored.cost));
+        if best.as_ref().is_none_or(|b| scored.cost < b.cost) {
+            best = Some(scored);
+        }
+    }
+    let best = match best {
+        Some(b) if b.cost < baseline.cost => b,
+        _ => baseline.clone(),
+    };
+    Ok(TuneReport {
+        baseline,
+        best,
+        evaluated,
+    })
+}
+
+fn uniform(rng: &mut XorShift64) -> [f32; DIMS] {
+    core::array::from_fn(|_| rng.next_f32())
+}
+
+/// The next candidate: the pool point with the largest expected improvement
+/// under a surrogate fitted to `seen`.
+fn propose(seen: &[([f32; DIMS], f64)], rng: &mut XorShift64) -> [f32; DIMS] {
+    // Log costs: aborts cost orders of magnitude more than the overshoot and
+    // time trade-off the surrogate has to resolve. Infinite costs (no run
+    // completed, or a rejected tuning) sit one unit above the worst finite one.
+    let log = |c: f64| c.max(1e-9).ln();
+    let worst = seen
+        .iter()
+        .map(|&(_, c)| c)
+        .filter(|c| c.is_finite())
+        .map(log)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let worst = if worst.is_finite() { worst } else { 0.0 };
+    let ys: Vec<f64> = seen
+        .iter()
+        .map(|&(_, c)| if c.is_finite() { log(c) } else { worst + 1.0 })
+        .collect();
+    let (best_i, best_y) = ys
+        .iter()
+        .copied()
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.0));
+    let centre = seen.get(best_i).map_or([0.5; DIMS], |s| s.0);
+
+    let xs: Vec<[f64; DIMS]> = seen.iter().map(|(x, _)| x.map(f64::from)).collect();
+    let Some(gp) = Gp::fit(xs, &ys) else {
+        return uniform(rng);
+    };
+    let mut pool: Vec<[f32; DIMS]> = (0..POOL_UNIFORM).map(|_| uniform(rng)).collect();
+    pool.extend((0..POOL_LOCAL).map(|_| {
+        core::array::from_fn(|d| (centre[d] + LOCAL_STEP * rng.next_gaussian()).clamp(0.0, 1.0))
+    }));
+    pool.into_iter()
+        .map(|x| {
+            let (mu, sigma) = gp.predict(&x.map(f64::from));
+            (expected_improvement(mu, sigma, best_y), x)
+        })
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map_or_else(|| uniform(rng), |(_, x)| x)
+}
+
+/// Expected amount by which a cost distributed `N(mu, sigma²)` falls below
+/// `best`.
+fn expected_improvement(mu: f64, sigma: f64, best: f64) -> f64 {
+    let gain = best - mu;
+    if sigma <= 1e-12 {
+        return gain.max(0.0);
+    }
+    let z = gain / sigma;
+    let pdf = (-0.5 * z * z).exp() / (2.0 * std::f64::consts::PI).sqrt();
+    let cdf = 0.5 * erfc(-z / std::f64::consts::SQRT_2);
+    gain * cdf + sigma * pdf
+}
+
+/// Complementary error function (Chebyshev fit, relative error < 1.2e-7).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let r = t * poly.exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
+/// Gaussian-process regression on standardized targets with a
+/// squared-exponential kernel.
+struct Gp {
+    xs: Vec<[f64; DIMS]>,
+    /// Lower Cholesky factor of the kernel matrix
+    chol: Vec<Vec<f64>>,
+    /// `K⁻¹ (y - mean) / scale`
+    alpha: Vec<f64>,
+    length: f64,
+    mean: f64,
+    scale: f64,
+}
+
+/// Length scales tried, in units of the search space; the one with the
+/// largest marginal likelihood is kept.
+const LENGTHS: [f64; 5] = [0.1, 0.2, 0.4, 0.8, 1.6];
+
+/// Kernel diagonal jitter: the costs are deterministic per seed, so only
+/// enough for a stable factorization.
+const JITTER: f64 = 1e-6;
+
+impl Gp {
+    fn fit(xs: Vec<[f64; DIMS]>, ys: &[f64]) -> Option<Self> {
+        let n = ys.len() as f64;
+        let mean = ys.iter().sum::<f64>() / n;
+        let var = ys.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / n;
+        let scale = if var > 1e-12 { var.sqrt() } else { 1.0 };
+        let z: Vec<f64> = ys.iter().map(|y| (y - mean) / scale).collect();
+        LENGTHS
+            .iter()
+            .filter_map(|&length| {
+                let k: Vec<Vec<f64>> = xs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, a)| {
+                        xs.iter()
+                            .enumerate()
+                            .map(|(j, b)| kernel(a, b, length) + if i == j { JITTER } else { 0.0 })
+                            .collect()
+                    })
+                    .collect();
+                let chol = cholesky(k)?;
+                let alpha = solve_upper(&chol, &solve_lower(&chol, &z));
+                let fit: f64 = z.iter().zip(&alpha).map(|(a, b)| a * b).sum();
+                let log_det: f64 = chol.iter().enumerate().map(|(i, r)| r[i].ln()).sum();
+                Some((-0.5 * fit - log_det, chol, alpha, length))
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, chol, alpha, length)| Self {
+                xs,
+                chol,
+                alpha,
+                length,
+                mean,
+                scale,
+            })
+    }
+
+    /// Predicted mean and standard deviation of the target at `x`.
+    fn predict(&self, x: &[f64; DIMS]) -> (f64, f64) {
+        let k: Vec<f64> = self.xs.iter().map(|a| kernel(a, x, self.length)).collect();
+        let mu: f64 = k.iter().zip(&self.alpha).map(|(a, b)| a * b).sum();
+        let v = solve_lower(&self.chol, &k);
+        let var = (1.0 - v.iter().map(|a| a * a).sum::<f64>()).max(0.0);
+        (self.mean + self.scale * mu, self.scale * var.sqrt())
+    }
+}
+
+fn kernel(a: &[f64; DIMS], b: &[f64; DIMS], length: f64) -> f64 {
+    let d2: f64 = a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum();
+    (-0.5 * d2 / (length * length)).exp()
+}
+
+/// Lower Cholesky factor of a symmetric matrix; `None` unless positive definite.
+fn cholesky(mut a: Vec<Vec<f64>>) -> Option<Vec<Vec<f64>>> {
+    let n = a.len();
+    for j in 0..n {
+        let d = a[j][j] - a[j][..j].iter().map(|v| v * v).sum::<f64>();
+        if d <= 0.0 {
+            return None;
+        }
+        a[j][j] = d.sqrt();
+        for i in j + 1..n {
+            let dot: f64 = a[i][..j].iter().zip(&a[j][..j]).map(|(x, y)| x * y).sum();
+            a[i][j] = (a[i][j] - dot) / a[j][j];
+        }
+    }
+    for (i, row) in a.iter_mut().enumerate() {
+        row[i + 1..].fill(0.0);
+    }
+    Some(a)
+}
+
+/// `L x = b` for lower-triangular `L`.
+fn solve_lower(l: &[Vec<f64>], b: &[f64]) -> Vec<f64> {
+    let mut x = vec![0.0; b.len()];
+    for i in 0..b.len() {
+        let dot: f64 = l[i][..i].iter().zip(&x[..i]).map(|(a, b)| a * b).sum();
+        x[i] = (b[i] - dot) / l[i][i];
+    }
+    x
+}
+
+/// `Lᵀ x = b` for lower-triangular `L`.
+fn solve_upper(l: &[Vec<f64>], b: &[f64]) -> Vec<f64> {
+    let n = b.len();
+    let mut x = vec![0.0; n];
+    for i in (0..n).rev() {
+        let dot: f64 = (i + 1..n).map(|k| l[k][i] * x[k]).sum();
+        x[i] = (b[i] - dot) / l[i][i];
+    }
+    x
+}
+
+fn lerp(lo: f32, hi: f32, x: f32) -> f32 {
+    lo + (hi - lo) * x
+}
+
+/// The tuning a point of the search space stands for: three speed bands,
+/// each slower and nearer the target than the one before, the fastest between
+/// a quarter of `max_sps` and `max_sps` and its threshold between 2 % and 50 %
+/// of the target; and the predictor enabled with a window of 3..=20 samples,
+/// a latency margin of 0..=300 ms and activation at 50..=95 % of the target.
+/// Grams and ratios are rounded to 0.01 so the tuning prints as it was scored.
+fn decode(base: &Tuning, x: &[f32; DIMS], target_g: f32, max_sps: u32) -> Tuning {
+    let hundredths = |v: f32| ((v * 100.0).round() / 100.0).max(0.01);
+    let sps = |v: f32| (v.round() as u32).max(MIN_SPS);
+    let max = max_sps as f32;
+    let s1 = lerp(0.25 * max, max, x[0]);
+    let s2 = s1 * lerp(0.15, 0.8, x[1]);
+    let s3 = s2 * lerp(0.15, 0.8, x[2]);
+    let t1 = target_g * lerp(0.02, 0.5, x[3]);
+    let t2 = t1 * lerp(0.2, 0.8, x[4]);
+    let t3 = t2 * lerp(0.2, 0.8, x[5]);
+    Tuning {
+        control: ControlCfg {
+            speed_bands: vec![
+                (hundredths(t1), sps(s1)),
+                (hundredths(t2), sps(s2)),
+                (hundredths(t3), sps(s3)),
+            ],
+            ..base.control.clone()
+        },
+        predictor: Some(PredictorCfg {
+            enabled: true,
+            window: lerp(3.0, 20.0, x[6]).round() as usize,
+            extra_latency_ms: lerp(0.0, 300.0, x[7]).round() as u64,
+            min_progress_ratio: hundredths(lerp(0.5, 0.95, x[8])),
+            ..base.predictor.clone().unwrap_or_default()
+        }),
+        ..base.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A bowl with its minimum at 0.3 in every dimension.
+    fn bowl(x: &[f32; DIMS]) -> f64 {
+        x.iter().map(|&v| (f64::from(v) - 0.3).powi(2)).sum()
+    }
+
+    fn sampled(n: usize) -> Vec<([f32; DIMS], f64)> {
+        let mut rng = XorShift64::new(5);
+        (0..n)
+            .map(|_| {
+                let x = uniform(&mut rng);
+                (x, bowl(&x))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn surrogate_reproduces_what_it_has_seen() {
+        let seen = sampled(12);
+        let xs: Vec<[f64; DIMS]> = seen.iter().map(|(x, _)| x.map(f64::from)).collect();
+        let ys: Vec<f64> = seen.iter().map(|s| s.1).collect();
+        let gp = Gp::fit(xs.clone(), &ys).unwrap();
+        for (x, y) in xs.iter().zip(&ys) {
+            let (mu, sigma) = gp.predict(x);
+            assert!((mu - y).abs() < 1e-2, "{mu} vs {y}");
+            assert!(sigma < 1e-2, "{sigma}");
+        }
+        // Away from every sample the surrogate is unsure.
+        let (_, far) = gp.predict(&[0.0; DIMS]);
+        assert!(far > 1e-2, "{far}");
+    }
+
+    #[test]
+    fn expected_improvement_weighs_mean_and_spread() {
+        assert!((erfc(0.0) - 1.0).abs() < 1e-7);
+        assert!((erfc(1.0) - 0.157_299_2).abs() < 1e-6);
+        assert!((erfc(-1.0) - 1.842_700_8).abs() < 1e-6);
+        // At the incumbent, only the spread can improve: sigma * pdf(0).
+        let ei = expected_improvement(1.0, 1.0, 1.0);
+        assert!((ei - 0.398_942_3).abs() < 1e-6, "{ei}");
+        assert_eq!(expected_improvement(0.5, 0.0, 1.0), 0.5);
+        assert_eq!(expected_improvement(2.0, 0.0, 1.0), 0.0);
+        assert!(expected_improvement(1.0, 2.0, 1.0) > ei);
+    }
+
+    #[test]
+    fn proposal_heads_for_the_minimum() {
+        let seen = sampled(20);
+        let mean = seen.iter().map(|s| s.1).sum::<f64>() / seen.len() as f64;
+        let x = propose(&seen, &mut XorShift64::new(9));
+        assert!(bowl(&x) < mean, "{} !< {mean}", bowl(&x));
+    }
+}
@@ -28,6 +28,27 @@ pub fn period_ms(hz: u32) -> u64 {
     (MILLIS_PER_SEC / u64::from(hz)).max(1)
 }
 
+/// Decimal places that show one `resolution_g` step (0.1 g → 1, 0.05 g → 2).
+/// Unknown resolution keeps two decimals; at most two, as the core works in
+/// centigrams.
+pub fn display_decimals(resolution_g: Option<f32>) -> usize {
+    match resolution_g {
+        Some(r) if r.is_finite() && r > 0.0 => {
+            // The small bias keeps exact powers of ten (0.1 → 1) from rounding up.
+            let d = (-r.log10() - 1e-4).ceil().clamp(0.0, 2.0);
+            d as usize
+        }
+        _ => 2,
+    }
+}
+
+/// [`display_decimals`], but no finer than noise of standard deviation
+/// `sigma_g` leaves meaningful (0.03 g → 2, 0.2 g → 1). Without noise
+/// (`sigma_g == 0`) the resolution decides alone.
+pub fn display_decimals_for(resolution_g: Option<f32>, sigma_g: f32) -> usize {
+    display_decimals(resolution_g).min(display_decimals(Some(sigma_g)))
+}
+
 /// Integer division rounded to nearest, with consistent behavior for negatives.
 ///
 /// Behavior:
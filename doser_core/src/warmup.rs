@@ -0,0 +1,121 @@
+//! Scale warm-up before a dose.
+//!
+//! HX711 output drifts for the first seconds after power-on. The warm-up phase
+//! discards a fixed number of samples and/or waits until the rolling standard
+//! deviation of the reading falls below a threshold, bounded by `max_ms`. It
+//! runs before the pre-flight checks and reports progress through a
+//! [`ProgressSink`] so a front-end can show "stabilizing…".
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use doser_traits::Scale;
+
+use crate::calibration::Calibration;
+use crate::config::WarmupCfg;
+use crate::hw_error::map_hw_error;
+
+/// Warm-up progress, in order: one `Started`, any number of `Progress`, one `Done`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WarmupEvent {
+    Started,
+    /// Samples read so far and the current rolling standard deviation, once
+    /// the window is full.
+    Progress {
+        samples: u32,
+        stddev_g: Option<f32>,
+    },
+    /// Warm-up finished; `stable` is false when `max_ms` ran out first.
+    Done {
+        samples: u32,
+        elapsed_ms: u64,
+        stable: bool,
+    },
+}
+
+/// Optional receiver for [`WarmupEvent`]s.
+#[derive(Clone, Default)]
+pub struct ProgressSink(Option<Arc<dyn Fn(WarmupEvent) + Send + Sync>>);
+
+impl ProgressSink {
+    pub fn new(f: impl Fn(WarmupEvent) + Send + Sync + 'static) -> Self {
+        Self(Some(Arc::new(f)))
+    }
+
+    fn emit(&self, ev: WarmupEvent) {
+        if let Some(f) = &self.0 {
+            f(ev);
+        }
+    }
+}
+
+impl core::fmt::Debug for ProgressSink {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ProgressSink")
+            .field(&self.0.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+/// Run the warm-up. Returns immediately when `cfg` asks for nothing. Read
+/// errors are returned as-is (mapped like control-loop reads).
+pub fn run<S: Scale + ?Sized>(
+    scale: &mut S,
+    calibration: Option<&Calibration>,
+    cfg: &WarmupCfg,
+    read_timeout: Duration,
+    progress: &ProgressSink,
+) -> crate::error::Result<()> {
+    if !cfg.is_active() {
+        return Ok(());
+    }
+    let cal = calibration.cloned().unwrap_or_default();
+    let window = cfg.window.max(2);
+    let mut recent: VecDeque<f32> = VecDeque::with_capacity(window);
+    let start = Instant::now();
+    let mut samples = 0u32;
+    progress.emit(WarmupEvent::Started);
+    tracing::info!(?cfg, "scale warm-up");
+
+    let stable = loop {
+        let raw = scale
+            .read(read_timeout)
+            .map_err(|e| eyre::Report::new(map_hw_error(&*e)))?;
+        samples = samples.saturating_add(1);
+        if recent.len() == window {
+            recent.pop_front();
+        }
+        recent.push_back(cal.to_grams(raw));
+        let stddev_g = (recent.len() == window).then(|| stddev(&recent));
+        progress.emit(WarmupEvent::Progress { samples, stddev_g });
+
+        let discarded = samples >= cfg.discard_samples;
+        let settled = match cfg.stable_stddev_g {
+            Some(limit) => stddev_g.is_some_and(|s| s <= limit),
+            None => true,
+        };
+        if discarded && settled {
+            break true;
+        }
+        if start.elapsed() >= Duration::from_millis(cfg.max_ms) {
+            tracing::warn!(samples, ?stddev_g, "scale warm-up timed out");
+            break false;
+        }
+    };
+
+    let elapsed_ms = u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX);
+    tracing::info!(samples, elapsed_ms, stable, "scale warm-up done");
+    progress.emit(WarmupEvent::Done {
+        samples,
+        elapsed_ms,
+        stable,
+    });
+    Ok(())
+}
+
+fn stddev(xs: &VecDeque<f32>) -> f32 {
+    let n = xs.len() as f32;
+    let mean = xs.iter().sum::<f32>() / n;
+    (xs.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / n).sqrt()
+}
@@ -0,0 +1,220 @@
+//! Weigh-only access to a scale, without a motor.
+//!
+//! Commands that read, tare or verify the scale but never dose (calibration
+//! checks, monitoring, shadowing) need the same calibration and `[filter]`
+//! pipeline as a dose, but building a [`Doser`](crate::Doser) for them means
+//! constructing a motor that is never driven. A [`WeightReader`] holds only
+//! the scale. Readings can come from the scale directly ([`WeightReader::read_g`])
+//! or from a [`Sampler`](crate::sampler::Sampler) thread, fed in with
+//! [`WeightReader::filter_raw`].
+//!
+//! Weights come with an uncertainty ([`Weight::sigma_g`]) estimated from the
+//! recent readings, so callers can show confidence intervals and round to the
+//! digits the noise leaves meaningful ([`crate::util::display_decimals_for`]).
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use doser_traits::Scale;
+
+use crate::calibration::Calibration;
+use crate::config::{FilterCfg, Timeouts};
+use crate::error::Result;
+use crate::filter::FilterPipeline;
+use crate::hw_error::map_hw_error;
+use crate::resume::{read_mean_counts, retare};
+
+/// A weight with its one-sigma uncertainty.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weight {
+    pub grams: f32,
+    /// Standard deviation of `grams` (0 while too few readings are known)
+    pub sigma_g: f32,
+}
+
+/// Readings the noise estimate looks back over.
+pub(crate) const NOISE_WINDOW: usize = 16;
+
+/// Noise of the unfiltered readings, from the residuals of predicting each
+/// reading by linear extrapolation of the two before it. A steady ramp (the
+/// motor running) leaves no residual, so the estimate tracks sensor noise
+/// rather than the filter lag while dosing. The spread is taken as the median
+/// absolute residual, so the single large residual where the flow starts or
+/// stops does not count as noise.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct NoiseWindow {
+    /// The two readings before the next one, oldest first.
+    prev: [Option<i32>; 2],
+    residuals: VecDeque<i32>,
+}
+
+impl NoiseWindow {
+    pub(crate) fn push(&mut self, w_cg: i32) {
+        if let [Some(a), Some(b)] = self.prev {
+            // w - (2b - a): the second difference.
+            let r = i64::from(w_cg) - 2 * i64::from(b) + i64::from(a);
+            if self.residuals.len() == NOISE_WINDOW {
+                self.residuals.pop_front();
+            }
+            self.residuals
+                .push_back(r.unsigned_abs().min(i32::MAX as u64) as i32);
+        }
+        self.prev = [self.prev[1], Some(w_cg)];
+    }
+
+    /// Standard deviation of one reading in grams.
+    pub(crate) fn sigma_g(&self) -> f32 {
+        let n = self.residuals.len();
+        if n == 0 {
+            return 0.0;
+        }
+        let mut sorted = [0i32; NOISE_WINDOW];
+        for (s, r) in sorted.iter_mut().zip(&self.residuals) {
+            *s = *r;
+        }
+        let sorted = &mut sorted[..n];
+        sorted.sort_unstable();
+        let mad_cg = if n % 2 == 1 {
+            f64::from(sorted[n / 2])
+        } else {
+            (f64::from(sorted[n / 2 - 1]) + f64::from(sorted[n / 2])) / 2.0
+        };
+        // For Gaussian noise of standard deviation σ the second difference
+        // has standard deviation √6·σ, and 1.4826·MAD estimates a standard
+        // deviation.
+        (1.4826 * mad_cg / 6f64.sqrt() / 100.0) as f32
+    }
+
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// A scale with calibration and filtering, but no motor.
+pub struct WeightReader<S: Scale> {
+    scale: S,
+    calibration: Calibration,
+    pipeline: FilterPipeline,
+    timeout: Duration,
+    noise: NoiseWindow,
+    last_cg: i32,
+}
+
+impl<S: Scale> WeightReader<S> {
+    /// Read `scale` through `calibration` and the pipeline built from `filter`.
+    pub fn new(scale: S, filter: &FilterCfg, calibration: Calibration, timeouts: Timeouts) -> Self {
+        Self {
+            scale,
+            calibration,
+            pipeline: FilterPipeline::from_cfg(filter),
+            timeout: Duration::from_millis(timeouts.sensor_ms),
+            noise: NoiseWindow::default(),
+            last_cg: 0,
+        }
+    }
+
+    /// One raw reading (ADC counts), unfiltered.
+    pub fn read_raw(&mut self) -> Result<i32> {
+        self.scale
+            .read(self.timeout)
+            .map_err(|e| eyre::Report::new(map_hw_error(&*e)))
+    }
+
+    /// One reading through the calibration and filter, in grams.
+    pub fn read_g(&mut self) -> Result<f32> {
+        let raw = self.read_raw()?;
+        Ok(self.filter_raw(raw))
+    }
+
+    /// Feed a raw reading taken elsewhere (e.g. by a sampler) through the
+    /// calibration and filter; returns the filtered weight in grams.
+    pub fn filter_raw(&mut self, raw: i32) -> f32 {
+        let w_cg = self.calibration.to_cg(raw);
+        self.noise.push(w_cg);
+        self.last_cg = self.pipeline.process(w_cg);
+        self.last_cg as f32 / 100.0
+    }
+
+    /// The last filtered weight and its uncertainty: the noise of one reading
+    /// over the recent readings, a bound the filter's averaging only tightens.
+    pub fn current_weight(&self) -> Weight {
+        Weight {
+            grams: self.last_cg as f32 / 100.0,
+            sigma_g: self.noise.sigma_g(),
+        }
+    }
+
+    /// Mean of `samples` raw readings (at least one), unfiltered.
+    pub fn read_mean_counts(&mut self, samples: usize) -> Result<i32> {
+        read_mean_counts(&mut self.scale, samples, self.timeout)
+    }
+
+    /// Mean of `samples` readings in grams, unfiltered.
+    pub fn read_mean_g(&mut self, samples: usize) -> Result<f32> {
+        let raw = self.read_mean_counts(samples)?;
+        Ok(self.calibration.to_grams(raw))
+    }
+
+    /// Mean of `samples` readings (at least one), unfiltered, with the
+    /// standard error of the mean from the readings' spread around it.
+    pub fn read_mean_weight(&mut self, samples: usize) -> Result<Weight> {
+        let samples = samples.max(1);
+        let mut grams = Vec::with_capacity(samples);
+        for _ in 0..samples {
+            let raw = self.read_raw()?;
+            grams.push(f64::from(self.calibration.to_grams(raw)));
+        }
+        let n = grams.len() as f64;
+        let mean = grams.iter().sum::<f64>() / n;
+        let sigma = if grams.len() > 1 {
+            let var = grams.iter().map(|g| (g - mean).powi(2)).sum::<f64>() / (n - 1.0);
+            (var / n).sqrt()
+        } else {
+            0.0
+        };
+        Ok(Weight {
+            grams: mean as f32,
+            sigma_g: sigma as f32,
+        })
+    }
+
+    /// Zero the calibration at the mean of `samples` readings and restart the
+    /// filter; returns the new zero in counts.
+    pub fn tare(&mut self, samples: usize) -> Result<i32> {
+        let zero = self.read_mean_counts(samples)?;
+        self.calibration = retare(&self.calibration, zero);
+        self.reset_filter();
+        Ok(zero)
+    }
+
+    /// The calibration readings are converted with (re-zeroed by [`tare`](Self::tare)).
+    pub fn calibration(&self) -> &Calibration {
+        &self.calibration
+    }
+
+    /// Forget the filter history, e.g. after the load changed abruptly.
+    pub fn reset_filter(&mut self) {
+        self.pipeline.reset();
+        self.noise.clear();
+        self.last_cg = 0;
+    }
+
+    /// The scale, e.g. for driver-specific calls.
+    pub fn scale_mut(&mut self) -> &mut S {
+        &mut self.scale
+    }
+
+    /// Give the scale back.
+    pub fn into_scale(self) -> S {
+        self.scale
+    }
+}
+
+impl<S: Scale> core::fmt::Debug for WeightReader<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WeightReader")
+            .field("calibration", &self.calibration)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
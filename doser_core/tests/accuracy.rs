@@ -217,12 +217,14 @@ fn accuracy_p95_and_max_under_noise() {
         window: 5,
         extra_latency_ms: DELAY_MS,
         min_progress_ratio: 0.1,
+        ..PredictorCfg::default()
     };
     let filter = FilterCfg {
         ma_window: 1,
         median_window: 1,
         sample_rate_hz: SAMPLE_RATE_HZ,
         ema_alpha: 0.0,
+        ..FilterCfg::default()
     };
     let safety = SafetyCfg {
         max_run_ms: 60_000,
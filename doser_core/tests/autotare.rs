@@ -0,0 +1,61 @@
+//! Auto-tare: the container is found once its readings settle.
+
+use std::error::Error;
+use std::time::Duration;
+
+use doser_core::autotare::wait_for_container;
+use doser_core::{AutoTareCfg, Calibration};
+use doser_traits::Scale;
+
+/// Plays back centigram readings, then repeats the last one.
+struct Script(Vec<i32>, usize);
+impl Scale for Script {
+    fn read(&mut self, _t: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        let v = self.0[self.1.min(self.0.len() - 1)];
+        self.1 += 1;
+        Ok(v)
+    }
+}
+
+fn cal() -> Calibration {
+    Calibration {
+        gain_g_per_count: 0.01,
+        zero_counts: 0,
+        offset_g: 0.0,
+    }
+}
+
+#[test]
+fn tares_on_the_container_once_it_settles() {
+    // Empty, then a 50 g container set down with some bounce.
+    let mut readings = vec![0; 30];
+    readings.extend([2000, 5600, 4700, 5150, 4980, 5010]);
+    readings.extend([5000, 5001, 4999, 5000]);
+    let mut scale = Script(readings, 0);
+    let cfg = AutoTareCfg {
+        min_container_g: 5.0,
+        plateau_g: 0.05,
+        plateau_ms: 100,
+        max_wait_ms: 5_000,
+    };
+    // 100 ms at 40 Hz: a plateau is four readings.
+    let p = wait_for_container(&mut scale, &cal(), &cfg, 40, Duration::from_millis(10)).unwrap();
+    assert_eq!(p.raw, 5000);
+    assert!((p.container_g - 50.0).abs() < 0.01, "{p:?}");
+    assert_eq!(p.samples, 40);
+    assert_eq!(p.tare(&cal()).to_grams(5100), 1.0);
+}
+
+#[test]
+fn gives_up_without_a_container() {
+    let mut scale = Script(vec![0], 0);
+    let cfg = AutoTareCfg {
+        max_wait_ms: 1_000,
+        ..AutoTareCfg::default()
+    };
+    let err = wait_for_container(&mut scale, &cal(), &cfg, 80, Duration::from_millis(10))
+        .expect_err("nothing placed");
+    assert!(err.to_string().contains("no container settled"), "{err}");
+    // 1 s at 80 Hz.
+    assert_eq!(scale.1, 80);
+}
@@ -0,0 +1,126 @@
+//! Remediation codes for no-progress aborts, told apart by the flow history.
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use doser_core::error::{AbortReason, DoserError};
+use doser_core::{
+    Calibration, ControlCfg, Doser, DosingStatus, FilterCfg, Remediation, SafetyCfg, Timeouts,
+};
+use doser_traits::clock::Clock;
+use doser_traits::{Motor, Scale};
+
+/// `sleep` advances virtual time, so each paced step is exactly 10 ms.
+#[derive(Clone)]
+struct ManualClock {
+    origin: Instant,
+    offset: Arc<Mutex<Duration>>,
+}
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.origin + *self.offset.lock().unwrap()
+    }
+    fn sleep(&self, d: Duration) {
+        *self.offset.lock().unwrap() += d;
+    }
+}
+
+struct NoopMotor;
+impl Motor for NoopMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn set_speed(&mut self, _sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+struct NoopScale;
+impl Scale for NoopScale {
+    fn read(&mut self, _t: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        Ok(0)
+    }
+}
+
+/// Dose 500 g at one speed, feeding centigram readings from `flow_cg(step)`
+/// (the weight gained on that step) until the no-progress watchdog trips.
+fn stall(flow_cg: impl Fn(usize) -> f64) -> Doser {
+    let mut d = Doser::builder()
+        .with_scale(NoopScale)
+        .with_motor(NoopMotor)
+        .with_filter(FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 100,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            speed_bands: vec![],
+            ..ControlCfg::default()
+        })
+        .with_safety(SafetyCfg {
+            max_run_ms: 60_000,
+            max_overshoot_g: 2.0,
+            no_progress_epsilon_g: 0.05,
+            no_progress_ms: 500,
+        })
+        .with_calibration(Calibration {
+            gain_g_per_count: 0.01,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_timeouts(Timeouts { sensor_ms: 5 })
+        .with_target_grams(500.0)
+        .with_clock(Box::new(ManualClock {
+            origin: Instant::now(),
+            offset: Arc::new(Mutex::new(Duration::ZERO)),
+        }))
+        .build()
+        .unwrap();
+    d.begin();
+    let mut w = 0.0;
+    for i in 0..5_000 {
+        w += flow_cg(i);
+        match d.step_from_raw(w.round() as i32).unwrap() {
+            DosingStatus::Running => {}
+            DosingStatus::Aborted(DoserError::Abort(AbortReason::NoProgress)) => return d,
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+    panic!("no stall");
+}
+
+#[test]
+fn no_progress_is_classified_by_the_flow_before_it() {
+    // Steady 1 g/s, then nothing: blocked.
+    let d = stall(|i| if i < 300 { 1.0 } else { 0.0 });
+    assert_eq!(d.remediation(), Some(Remediation::Jam));
+
+    // The same flow fading out over four seconds: the hopper ran empty.
+    let d = stall(|i| (1.0 - i as f64 / 400.0).max(0.0));
+    assert_eq!(d.remediation(), Some(Remediation::HopperEmpty));
+
+    // Nothing ever landed.
+    let d = stall(|_| 0.0);
+    assert_eq!(d.remediation(), Some(Remediation::NoFlow));
+}
+
+#[test]
+fn abort_report_carries_the_remediation() {
+    let d = stall(|i| if i < 300 { 1.0 } else { 0.0 });
+    let report = d.abort_report(DoserError::Abort(AbortReason::NoProgress));
+    assert_eq!(
+        report.downcast_ref::<Remediation>(),
+        Some(&Remediation::Jam)
+    );
+    // The abort itself stays reachable for exit codes and messages.
+    assert!(matches!(
+        report.downcast_ref::<DoserError>(),
+        Some(DoserError::Abort(AbortReason::NoProgress))
+    ));
+    assert_eq!(Remediation::Jam.code(), "E_JAM");
+}
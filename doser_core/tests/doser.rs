@@ -81,6 +81,7 @@ fn ema_converges_on_step_input() {
             median_window: 1,
             sample_rate_hz: 50,
             ema_alpha: 0.5,
+            ..FilterCfg::default()
         })
         .with_control(ControlCfg {
             speed_bands: vec![],
@@ -116,6 +117,7 @@ fn completes_when_in_band_and_settled() {
         coarse_speed: 1200,
         fine_speed: 250,
         epsilon_g: 0.0,
+        ..ControlCfg::default()
     };
 
     let mut doser = Doser::builder()
@@ -380,6 +382,7 @@ fn median_filter_suppresses_spike() {
             median_window: 3,
             sample_rate_hz: 50,
             ema_alpha: 0.0,
+            ..FilterCfg::default()
         })
         .with_control(ControlCfg {
             speed_bands: vec![],
@@ -389,6 +392,7 @@ fn median_filter_suppresses_spike() {
             coarse_speed: 1,
             fine_speed: 1,
             epsilon_g: 0.0,
+            ..ControlCfg::default()
         })
         .with_timeouts(Timeouts { sensor_ms: 1 })
         .with_target_grams(1000.0)
@@ -424,6 +428,7 @@ fn requires_time_to_settle_when_stable_ms_positive() {
             coarse_speed: 1200,
             fine_speed: 250,
             epsilon_g: 0.0,
+            ..ControlCfg::default()
         })
         .with_timeouts(Timeouts { sensor_ms: 1 })
         .with_target_grams(10.0)
@@ -505,6 +510,7 @@ fn aborts_on_no_progress_watchdog() {
             coarse_speed: 1200,
             fine_speed: 250,
             epsilon_g: 0.0,
+            ..ControlCfg::default()
         })
         .with_safety(safety)
         .with_timeouts(Timeouts { sensor_ms: 1 })
@@ -630,6 +636,7 @@ fn estop_condition_latches_until_begin() {
             coarse_speed: 1200,
             fine_speed: 250,
             epsilon_g: 0.0,
+            ..ControlCfg::default()
         })
         .with_timeouts(Timeouts { sensor_ms: 1 })
         .with_target_grams(10.0)
@@ -673,6 +680,7 @@ fn overshoot_epsilon_regression() {
         median_window: 1,
         sample_rate_hz: 50,
         ema_alpha: 0.0,
+        ..FilterCfg::default()
     };
     let base_timeouts = Timeouts { sensor_ms: 10 };
     let safety = SafetyCfg {
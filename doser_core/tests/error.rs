@@ -64,6 +64,7 @@ fn timeouts_map_to_dosererror_timeout() {
             coarse_speed: 1000,
             fine_speed: 200,
             epsilon_g: 0.0,
+            ..ControlCfg::default()
         })
         .with_timeouts(Timeouts { sensor_ms: 10 })
         .with_target_grams(0.5)
@@ -212,3 +213,23 @@ fn typed_hw_other_maps_to_hardware_fault() {
         other => panic!("unexpected: {other:?}"),
     }
 }
+
+#[rstest]
+#[case::typed_read(Box::new(doser_hardware::error::HwError::Timeout), DoserError::Timeout)]
+#[case::typed_drdy(
+    Box::new(doser_hardware::error::HwError::DataReadyTimeout),
+    DoserError::DataReadyTimeout
+)]
+#[case::text_read("sensor timeout".into(), DoserError::Timeout)]
+#[case::text_drdy("ADC data-ready timeout".into(), DoserError::DataReadyTimeout)]
+fn timeouts_keep_their_kind(
+    #[case] err: Box<dyn Error + Send + Sync>,
+    #[case] expected: DoserError,
+) {
+    let mapped = doser_core::hw_error::map_hw_error(&*err);
+    assert_eq!(
+        std::mem::discriminant(&mapped),
+        std::mem::discriminant(&expected),
+        "{mapped:?}"
+    );
+}
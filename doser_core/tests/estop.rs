@@ -143,3 +143,42 @@ fn estop_long_press_trips_and_latches_until_begin() {
         other => panic!("expected Aborted, got {other:?}"),
     }
 }
+
+#[rstest]
+fn monitor_trips_the_latch_without_the_loop() {
+    use doser_core::EstopMonitor;
+    use doser_traits::StopLatch;
+
+    let pressed = Arc::new(AtomicBool::new(false));
+    let polls = Arc::new(AtomicUsize::new(0));
+    let (p, n) = (pressed.clone(), polls.clone());
+    let latch = StopLatch::new();
+    latch.trip(); // left over from an earlier run
+    let monitor = EstopMonitor::spawn(
+        Arc::new(move || {
+            n.fetch_add(1, Ordering::Relaxed);
+            p.load(Ordering::Relaxed)
+        }),
+        latch.clone(),
+        Duration::from_millis(1),
+        2,
+    );
+    assert!(!latch.is_tripped(), "a new monitor starts a new run");
+
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(!monitor.tripped());
+    pressed.store(true, Ordering::Relaxed);
+    let deadline = std::time::Instant::now() + Duration::from_secs(2);
+    while !latch.is_tripped() {
+        assert!(std::time::Instant::now() < deadline, "latch never tripped");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert!(monitor.tripped());
+
+    // The monitor stops polling once tripped, and on drop.
+    let after_trip = polls.load(Ordering::Relaxed);
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(polls.load(Ordering::Relaxed), after_trip);
+    drop(monitor);
+    assert!(latch.is_tripped(), "the latch holds until the next run");
+}
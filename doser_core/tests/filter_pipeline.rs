@@ -0,0 +1,218 @@
+use std::error::Error;
+use std::time::Duration;
+
+use doser_core::filter::{EmaStage, MedianStage};
+use doser_core::{Doser, DosingStatus, FilterCfg, FilterPipeline, FilterStage};
+use doser_traits::{Motor, Scale};
+
+struct SeqScale {
+    seq: Vec<i32>,
+    idx: usize,
+}
+impl Scale for SeqScale {
+    fn read(&mut self, _timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        let v = self
+            .seq
+            .get(self.idx)
+            .copied()
+            .unwrap_or_else(|| self.seq.last().copied().unwrap_or(0));
+        self.idx += 1;
+        Ok(v)
+    }
+}
+
+struct NoopMotor;
+impl Motor for NoopMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn set_speed(&mut self, _sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+/// User-provided stage: limit the change between consecutive outputs.
+struct RateLimiter {
+    max_step_cg: i32,
+    prev: Option<i32>,
+}
+impl FilterStage for RateLimiter {
+    fn process(&mut self, x_cg: i32) -> i32 {
+        let y = match self.prev {
+            None => x_cg,
+            Some(p) => p + (x_cg - p).clamp(-self.max_step_cg, self.max_step_cg),
+        };
+        self.prev = Some(y);
+        y
+    }
+    fn reset(&mut self) {
+        self.prev = None;
+    }
+}
+
+#[test]
+fn custom_stage_runs_inside_the_control_loop() {
+    let pipeline = FilterPipeline::new().with_stage(RateLimiter {
+        max_step_cg: 10,
+        prev: None,
+    });
+    let mut doser = Doser::builder()
+        .with_scale(SeqScale {
+            seq: vec![0, 500],
+            idx: 0,
+        })
+        .with_motor(NoopMotor)
+        .with_filter(FilterCfg {
+            sample_rate_hz: 1000,
+            ..FilterCfg::default()
+        })
+        .with_filter_pipeline(pipeline)
+        .with_target_grams(50.0)
+        .build()
+        .unwrap();
+    doser.begin();
+
+    let _ = doser.step().unwrap();
+    assert_eq!(doser.last_weight(), 0.0);
+    let _ = doser.step().unwrap();
+    // Raw jumped 0 → 5.00 g; the limiter only lets 0.10 g through per sample.
+    assert!((doser.last_weight() - 0.10).abs() < 1e-4);
+}
+
+#[test]
+fn cfg_pipeline_matches_explicit_stages() {
+    let cfg = FilterCfg {
+        ma_window: 1,
+        median_window: 3,
+        sample_rate_hz: 50,
+        ema_alpha: 0.3,
+        ..FilterCfg::default()
+    };
+    let mut from_cfg = FilterPipeline::from_cfg(&cfg);
+    let mut explicit = FilterPipeline::new()
+        .with_stage(MedianStage::new(3))
+        .with_stage(EmaStage::new(0.3));
+    for x in [0, 100, 5000, 120, 130, -40, 150, 160] {
+        assert_eq!(from_cfg.process(x), explicit.process(x));
+    }
+}
+
+#[test]
+fn begin_resets_pipeline_state() {
+    let mut doser = Doser::builder()
+        .with_scale(SeqScale {
+            seq: vec![1000, 1000, 0],
+            idx: 0,
+        })
+        .with_motor(NoopMotor)
+        .with_filter(FilterCfg {
+            ma_window: 2,
+            sample_rate_hz: 1000,
+            ..FilterCfg::default()
+        })
+        .with_target_grams(50.0)
+        .build()
+        .unwrap();
+    doser.begin();
+    let _ = doser.step().unwrap();
+    let _ = doser.step().unwrap();
+    doser.begin();
+    // With history cleared the MA sees only the new sample.
+    assert!(matches!(doser.step().unwrap(), DosingStatus::Running));
+    assert_eq!(doser.last_weight(), 0.0);
+}
+
+#[test]
+fn fine_phase_switches_to_its_own_filter() {
+    let mut doser = Doser::builder()
+        // 0.01 g per count; default bands slow down within 1 g of target.
+        .with_scale(SeqScale {
+            seq: vec![0, 2000, 4920, 4950, 4850, 4950, 4850],
+            idx: 0,
+        })
+        .with_motor(NoopMotor)
+        .with_filter(FilterCfg {
+            sample_rate_hz: 1000,
+            fine: Some(Box::new(FilterCfg {
+                ma_window: 4,
+                ..FilterCfg::default()
+            })),
+            ..FilterCfg::default()
+        })
+        .with_target_grams(50.0)
+        .build()
+        .unwrap();
+    doser.begin();
+    // Coarse phase: unfiltered.
+    let _ = doser.step().unwrap();
+    let _ = doser.step().unwrap();
+    assert_eq!(doser.last_weight(), 20.0);
+    let _ = doser.step().unwrap();
+    assert_eq!(doser.last_weight(), 49.2);
+    // Fine phase: the moving average starts from the first fine sample, not
+    // from the coarse ramp, and then smooths the ±0.5 g noise.
+    let _ = doser.step().unwrap();
+    assert_eq!(doser.last_weight(), 49.5);
+    for _ in 0..3 {
+        let _ = doser.step().unwrap();
+    }
+    assert_eq!(doser.last_weight(), 49.0);
+    assert!(matches!(doser.step().unwrap(), DosingStatus::Running));
+
+    // The next dose starts on the coarse filter again.
+    doser.begin();
+    let _ = doser.step().unwrap();
+    assert_eq!(doser.last_weight(), 48.5);
+}
+
+#[test]
+fn display_weight_is_filtered_apart_from_control() {
+    let mut doser = Doser::builder()
+        .with_scale(SeqScale {
+            seq: vec![0],
+            idx: 0,
+        })
+        .with_motor(NoopMotor)
+        .with_filter(FilterCfg {
+            sample_rate_hz: 1000,
+            display: Some(Box::new(FilterCfg {
+                ma_window: 3,
+                ..FilterCfg::default()
+            })),
+            ..FilterCfg::default()
+        })
+        .with_target_grams(50.0)
+        .build()
+        .unwrap();
+    doser.begin();
+    for raw in [900, 1200, 1500] {
+        let _ = doser.step_from_raw(raw).unwrap();
+    }
+    // Control follows the reading; the display averages the last three.
+    assert_eq!(doser.last_weight(), 15.0);
+    assert_eq!(doser.display_weight(), 12.0);
+
+    // Without a display filter the two are the same.
+    let mut doser = Doser::builder()
+        .with_scale(SeqScale {
+            seq: vec![0],
+            idx: 0,
+        })
+        .with_motor(NoopMotor)
+        .with_filter(FilterCfg {
+            ma_window: 2,
+            sample_rate_hz: 1000,
+            ..FilterCfg::default()
+        })
+        .with_target_grams(50.0)
+        .build()
+        .unwrap();
+    doser.begin();
+    for raw in [900, 1200] {
+        let _ = doser.step_from_raw(raw).unwrap();
+    }
+    assert_eq!(doser.display_weight(), doser.last_weight());
+}
@@ -0,0 +1,231 @@
+//! Property-based checks of the fixed-point conversions, the built-in filter
+//! stages against float/i128 reference implementations, and time invariants of
+//! the control state machine under a virtual clock.
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use doser_core::filter::{EmaStage, FilterStage, MedianStage, MovingAverageStage};
+use doser_core::fixed_point::quantize_to_cg_i32;
+use doser_core::{Calibration, ControlCfg, Doser, DosingStatus, FilterCfg, SafetyCfg};
+use doser_traits::clock::Clock;
+use doser_traits::{Motor, Scale};
+use proptest::prelude::*;
+
+// ── Reference implementations ────────────────────────────────────────────────
+
+fn round_half_away(num: i128, den: i128) -> i128 {
+    if num >= 0 {
+        (num + den / 2) / den
+    } else {
+        (num - den / 2) / den
+    }
+}
+
+fn ref_median(win: &[i32]) -> i32 {
+    let mut v = win.to_vec();
+    v.sort_unstable();
+    let n = v.len();
+    if n % 2 == 1 {
+        v[n / 2]
+    } else {
+        round_half_away(v[n / 2 - 1] as i128 + v[n / 2] as i128, 2) as i32
+    }
+}
+
+fn ref_mean(win: &[i32]) -> i32 {
+    let sum: i128 = win.iter().map(|&x| x as i128).sum();
+    round_half_away(sum, win.len() as i128) as i32
+}
+
+// ── Conversions ──────────────────────────────────────────────────────────────
+
+proptest! {
+    #[test]
+    fn to_cg_tracks_float_reference(
+        gain in 1e-5f32..0.1,
+        zero in -(1i32 << 23)..(1i32 << 23),
+        raw in -(1i32 << 23)..(1i32 << 23),
+        offset_g in -100.0f32..100.0,
+    ) {
+        let cal = Calibration { gain_g_per_count: gain, zero_counts: zero, offset_g };
+        let delta = (raw as i64 - zero as i64) as f64;
+        let gain_cg = 100.0 * (gain as f64) * delta;
+        let expected = gain_cg + 100.0 * offset_g as f64;
+        let got = cal.to_cg(raw) as f64;
+        // The gain's 31-bit mantissa is off by at most half a unit, 2^-31 of
+        // the gain; the offset is quantized to a centigram (in f32) and the
+        // sum rounded to one.
+        let tol = gain_cg.abs() / (1u64 << 31) as f64 + 0.51 + 0.5;
+        prop_assert!((got - expected).abs() <= tol, "got {got}, expected {expected}, tol {tol}");
+    }
+
+    #[test]
+    fn to_cg_keeps_fine_gains_to_a_centigram(
+        gain in 1e-8f32..1e-4,
+        raw in -(1i32 << 23)..(1i32 << 23),
+    ) {
+        let cal = Calibration { gain_g_per_count: gain, zero_counts: 0, offset_g: 0.0 };
+        let expected = 100.0 * (gain as f64) * raw as f64;
+        let got = cal.to_cg(raw) as f64;
+        // The final rounding to a centigram, plus the gain's 31-bit mantissa,
+        // whatever the gain's magnitude.
+        let tol = 0.5 + expected.abs() / (1u64 << 30) as f64;
+        prop_assert!((got - expected).abs() <= tol, "got {got}, expected {expected}");
+    }
+
+    #[test]
+    fn quantize_matches_rounding_and_is_monotonic(a in -1.0e5f32..1.0e5, b in -1.0e5f32..1.0e5) {
+        prop_assert_eq!(quantize_to_cg_i32(a), (a * 100.0).round() as i32);
+        let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+        prop_assert!(quantize_to_cg_i32(lo) <= quantize_to_cg_i32(hi));
+    }
+}
+
+// ── Filter stages ────────────────────────────────────────────────────────────
+
+proptest! {
+    #[test]
+    fn median_stage_matches_sorted_reference(
+        window in 1usize..12,
+        xs in prop::collection::vec(-1_000_000i32..1_000_000, 1..80),
+    ) {
+        let mut stage = MedianStage::new(window);
+        for (i, &x) in xs.iter().enumerate() {
+            let lo = (i + 1).saturating_sub(window);
+            prop_assert_eq!(stage.process(x), ref_median(&xs[lo..=i]));
+        }
+    }
+
+    #[test]
+    fn moving_average_matches_i128_reference(
+        window in 1usize..12,
+        xs in prop::collection::vec(any::<i32>(), 1..80),
+    ) {
+        let mut stage = MovingAverageStage::new(window);
+        for (i, &x) in xs.iter().enumerate() {
+            let lo = (i + 1).saturating_sub(window);
+            prop_assert_eq!(stage.process(x), ref_mean(&xs[lo..=i]));
+        }
+    }
+
+    #[test]
+    fn ema_stays_close_to_f64_reference(
+        alpha in 0.01f32..=1.0,
+        xs in prop::collection::vec(-100_000i32..100_000, 1..80),
+    ) {
+        let mut stage = EmaStage::new(alpha);
+        let a = alpha as f64;
+        let mut y: Option<f64> = None;
+        for &x in &xs {
+            let next = match y {
+                None => x as f64,
+                Some(prev) => a * x as f64 + (1.0 - a) * prev,
+            };
+            y = Some(next);
+            let got = stage.process(x) as f64;
+            // f32 state accumulates rounding; allow a small relative slack.
+            prop_assert!((got - next).abs() <= 1.0 + next.abs() * 1e-5, "got {got}, ref {next}");
+        }
+    }
+}
+
+// ── State machine time invariants ────────────────────────────────────────────
+
+#[derive(Clone)]
+struct VirtualClock {
+    origin: Instant,
+    offset: Arc<Mutex<Duration>>,
+}
+impl VirtualClock {
+    fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            offset: Arc::new(Mutex::new(Duration::ZERO)),
+        }
+    }
+    fn elapsed_ms(&self) -> u64 {
+        self.offset.lock().unwrap().as_millis() as u64
+    }
+}
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.origin + *self.offset.lock().unwrap()
+    }
+    fn sleep(&self, d: Duration) {
+        *self.offset.lock().unwrap() += d;
+    }
+}
+
+struct RampScale {
+    cg: i32,
+    step_cg: i32,
+}
+impl Scale for RampScale {
+    fn read(&mut self, _timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        self.cg = self.cg.saturating_add(self.step_cg);
+        Ok(self.cg)
+    }
+}
+
+struct NoopMotor;
+impl Motor for NoopMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn set_speed(&mut self, _sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+proptest! {
+    #[test]
+    fn completion_and_runtime_cap_respect_elapsed_time(
+        step_cg in 0i32..40,
+        target_g in 1u32..30,
+        stable_ms in 0u64..400,
+        max_run_ms in 50u64..3_000,
+        hz in prop::sample::select(vec![10u32, 50, 80, 200]),
+    ) {
+        let clock = VirtualClock::new();
+        let mut doser = Doser::builder()
+            .with_scale(RampScale { cg: 0, step_cg })
+            .with_motor(NoopMotor)
+            .with_filter(FilterCfg { sample_rate_hz: hz, ..FilterCfg::default() })
+            .with_control(ControlCfg { stable_ms, ..ControlCfg::default() })
+            .with_safety(SafetyCfg { max_run_ms, max_overshoot_g: 1000.0, ..SafetyCfg::default() })
+            .with_clock(Box::new(clock.clone()))
+            .with_target_grams(target_g as f32)
+            .build()
+            .unwrap();
+        doser.begin();
+
+        let target_cg = (target_g * 100) as i32;
+        let mut zone_entered_at: Option<u64> = None;
+        for _ in 0..100_000 {
+            let before = clock.elapsed_ms();
+            let status = doser.step().unwrap();
+            let w_cg = (doser.last_weight() * 100.0).round() as i32;
+            if zone_entered_at.is_none() && w_cg + 8 >= target_cg {
+                zone_entered_at = Some(before);
+            }
+            match status {
+                DosingStatus::Running => {}
+                DosingStatus::Complete => {
+                    let since = zone_entered_at.expect("completed without entering the zone");
+                    prop_assert!(before - since >= stable_ms);
+                    prop_assert!(before < max_run_ms);
+                    break;
+                }
+                DosingStatus::Aborted(_) => {
+                    prop_assert!(before >= max_run_ms, "aborted early at {before} ms");
+                    break;
+                }
+            }
+        }
+    }
+}
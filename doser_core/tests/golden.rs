@@ -0,0 +1,207 @@
+//! Golden (snapshot) tests of control decisions.
+//!
+//! Each case feeds a recorded raw trace from `tests/golden/<name>.csv` through the
+//! core with a fixed config and a virtual clock, records every motor command and
+//! status transition, and compares the log against `tests/golden/<name>.snap`.
+//!
+//! After an intentional behavior change, regenerate the snapshots with
+//! `UPDATE_GOLDEN=1 cargo test -p doser_core --test golden` and review the diff.
+
+use std::error::Error;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use doser_core::{ControlCfg, Doser, DosingStatus, FilterCfg, PredictorCfg, SafetyCfg};
+use doser_traits::clock::Clock;
+use doser_traits::{Motor, Scale};
+use rstest::rstest;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+#[derive(Clone)]
+struct VirtualClock {
+    origin: Instant,
+    offset: Arc<Mutex<Duration>>,
+}
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.origin + *self.offset.lock().unwrap()
+    }
+    fn sleep(&self, d: Duration) {
+        *self.offset.lock().unwrap() += d;
+    }
+}
+
+struct TraceScale {
+    raw: Vec<i32>,
+    idx: usize,
+}
+impl Scale for TraceScale {
+    fn read(&mut self, _timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        let v = self.raw[self.idx.min(self.raw.len() - 1)];
+        self.idx += 1;
+        Ok(v)
+    }
+}
+
+/// Records motor commands, collapsing consecutive duplicates.
+struct RecordingMotor {
+    log: Log,
+    step: Arc<Mutex<usize>>,
+}
+impl RecordingMotor {
+    fn record(&self, ev: String) {
+        let step = *self.step.lock().unwrap();
+        let mut log = self.log.lock().unwrap();
+        let line = format!("{step} {ev}");
+        let dup = log
+            .last()
+            .is_some_and(|l| l.split_once(' ').map(|x| x.1) == Some(ev.as_str()));
+        if !dup {
+            log.push(line);
+        }
+    }
+}
+impl Motor for RecordingMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.record("start".into());
+        Ok(())
+    }
+    fn set_speed(&mut self, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.record(format!("speed {sps}"));
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.record("stop".into());
+        Ok(())
+    }
+}
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn load_trace(name: &str) -> Vec<i32> {
+    let text = std::fs::read_to_string(golden_dir().join(format!("{name}.csv"))).unwrap();
+    text.lines()
+        .skip(1)
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.trim().parse().unwrap())
+        .collect()
+}
+
+fn run_trace(name: &str, control: ControlCfg, filter: FilterCfg, predictor: bool) -> String {
+    let raw = load_trace(name);
+    let log: Log = Arc::default();
+    let step = Arc::new(Mutex::new(0usize));
+    let clock = VirtualClock {
+        origin: Instant::now(),
+        offset: Arc::default(),
+    };
+    let mut doser = Doser::builder()
+        .with_scale(TraceScale {
+            raw: raw.clone(),
+            idx: 0,
+        })
+        .with_motor(RecordingMotor {
+            log: log.clone(),
+            step: step.clone(),
+        })
+        .with_filter(filter)
+        .with_control(control)
+        .with_safety(SafetyCfg {
+            max_run_ms: 60_000,
+            max_overshoot_g: 2.0,
+            no_progress_epsilon_g: 0.02,
+            no_progress_ms: 1_000,
+        })
+        .with_predictor(PredictorCfg {
+            enabled: predictor,
+            ..PredictorCfg::default()
+        })
+        .with_clock(Box::new(clock))
+        .with_target_grams(10.0)
+        .build()
+        .unwrap();
+    doser.begin();
+
+    let mut out = String::new();
+    writeln!(out, "# trace: {name}").unwrap();
+    for i in 0..raw.len() * 2 {
+        *step.lock().unwrap() = i;
+        let status = doser.step().unwrap();
+        let terminal = match &status {
+            DosingStatus::Running => None,
+            DosingStatus::Complete => Some("Complete".to_string()),
+            DosingStatus::Aborted(e) => Some(format!("Aborted({e})")),
+        };
+        if let Some(t) = terminal {
+            for l in log.lock().unwrap().iter() {
+                writeln!(out, "{l}").unwrap();
+            }
+            writeln!(out, "{i} {t} final_g={:.2}", doser.last_weight()).unwrap();
+            return out;
+        }
+    }
+    panic!("trace {name} did not terminate");
+}
+
+fn check_snapshot(name: &str, actual: &str) {
+    let path = golden_dir().join(format!("{name}.snap"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing snapshot {path:?}; run with UPDATE_GOLDEN=1"));
+    assert_eq!(
+        expected, actual,
+        "control decisions for {name} changed; review and regenerate with UPDATE_GOLDEN=1"
+    );
+}
+
+fn light_filter() -> FilterCfg {
+    FilterCfg {
+        ma_window: 1,
+        median_window: 1,
+        sample_rate_hz: 50,
+        ema_alpha: 0.0,
+        ..FilterCfg::default()
+    }
+}
+
+fn heavy_filter() -> FilterCfg {
+    FilterCfg {
+        ma_window: 4,
+        median_window: 5,
+        sample_rate_hz: 50,
+        ema_alpha: 0.0,
+        ..FilterCfg::default()
+    }
+}
+
+#[rstest]
+#[case("ramp_clean", light_filter(), false)]
+#[case("ramp_noisy", heavy_filter(), false)]
+#[case("stall", light_filter(), false)]
+fn golden_speed_bands(#[case] trace: &str, #[case] filter: FilterCfg, #[case] predictor: bool) {
+    let control = ControlCfg {
+        stable_ms: 100,
+        ..ControlCfg::default()
+    };
+    let out = run_trace(trace, control, filter, predictor);
+    check_snapshot(trace, &out);
+}
+
+#[test]
+fn golden_legacy_two_speed_with_predictor() {
+    let control = ControlCfg {
+        speed_bands: Vec::new(),
+        stable_ms: 100,
+        ..ControlCfg::default()
+    };
+    let out = run_trace("ramp_clean", control, light_filter(), true);
+    check_snapshot("ramp_clean_legacy_predictor", &out);
+}
@@ -1,5 +1,6 @@
 //! Regression tests for the hardening pass:
 //! - the motor is actually stopped on every safety-abort path,
+//! - a motor backend that stops driving the motor fails the run,
 //! - persisted calibration `offset_g` survives the conversion pipeline,
 //! - realistic small calibration gains are no longer quantized to zero,
 //! - hysteresis resets the settle timer on out-of-band (noisy) readings.
@@ -98,6 +99,7 @@ fn passthrough_filter(sample_rate_hz: u32) -> FilterCfg {
         median_window: 1,
         sample_rate_hz,
         ema_alpha: 0.0,
+        ..FilterCfg::default()
     }
 }
 
@@ -189,6 +191,58 @@ fn motor_stops_on_estop() {
     );
 }
 
+/// Motor whose backend dies once started (e.g. its stepping thread stalled).
+#[derive(Clone, Default)]
+struct DeadWorkerMotor {
+    started: bool,
+    stopped: Arc<AtomicBool>,
+}
+impl Motor for DeadWorkerMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.started = true;
+        Ok(())
+    }
+    fn set_speed(&mut self, _sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.stopped.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+    fn check_alive(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.started {
+            return Err("stepper thread stalled (no heartbeat for 2000 ms)".into());
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn dead_motor_backend_fails_the_run_and_halts() {
+    let motor = DeadWorkerMotor::default();
+    let stopped = motor.stopped.clone();
+    let mut doser = Doser::builder()
+        .with_scale(ConstScale(1))
+        .with_motor(motor)
+        .with_filter(passthrough_filter(100))
+        .with_safety(SafetyCfg::default())
+        .with_calibration(unit_cal())
+        .with_timeouts(Timeouts { sensor_ms: 5 })
+        .with_target_grams(5.0)
+        .with_clock(Box::new(ManualClock::new()))
+        .build()
+        .unwrap();
+    doser.begin();
+
+    assert!(matches!(doser.step().unwrap(), DosingStatus::Running));
+    let err = doser.step().expect_err("dead backend");
+    assert!(format!("{err:#}").contains("stalled"), "{err:#}");
+    assert!(
+        stopped.load(Ordering::SeqCst),
+        "motor must be halted when its backend dies"
+    );
+}
+
 #[test]
 fn motor_stops_on_max_runtime() {
     let stopped = Arc::new(AtomicBool::new(false));
@@ -277,6 +331,7 @@ fn persisted_offset_g_survives_conversion() {
         gain_g_per_count: 0.01,
         zero_counts: 100,
         offset_g: 0.5,
+        verified: None,
     };
     // PersistedCalibration -> doser_config::Calibration keeps offset_g (previously dropped).
     let cfg_cal = CfgCal::from(pc);
@@ -327,6 +382,7 @@ fn steps_to_complete(readings: Vec<i32>) -> usize {
             coarse_speed: 1200,
             fine_speed: 250,
             epsilon_g: 0.0,
+            ..ControlCfg::default()
         })
         .with_safety(SafetyCfg {
             max_run_ms: 100_000,
@@ -172,3 +172,80 @@ fn simulated_hardware_error_in_core() {
         other => panic!("expected hardware-mapped error, got: {other:?}"),
     }
 }
+
+/// Legal counts of an HX711: 24-bit signed without the saturation rails.
+const HX711_RANGE: std::ops::RangeInclusive<i32> = -0x7F_FFFF..=0x7F_FFFE;
+
+/// A 24-bit ADC returning a reading it cannot produce (a corrupted transfer).
+struct WideScale;
+impl Scale for WideScale {
+    fn read(&mut self, _timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        Ok(0x100_0000)
+    }
+
+    fn raw_range(&self) -> Option<std::ops::RangeInclusive<i32>> {
+        Some(HX711_RANGE)
+    }
+}
+
+/// An HX711 whose input is beyond full scale: it reads its saturation code.
+struct SaturatedScale(i32);
+impl Scale for SaturatedScale {
+    fn read(&mut self, _timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        Ok(self.0)
+    }
+
+    fn raw_range(&self) -> Option<std::ops::RangeInclusive<i32>> {
+        Some(HX711_RANGE)
+    }
+}
+
+#[rstest]
+fn raw_reading_outside_the_adc_range_is_a_fault() {
+    let mut doser = Doser::builder()
+        .with_scale(WideScale)
+        .with_motor(NopMotor)
+        .with_filter(FilterCfg::default())
+        .with_control(ControlCfg::default())
+        .with_timeouts(Timeouts { sensor_ms: 5 })
+        .with_target_grams(5.0)
+        .apply_calibration::<()>(None)
+        .build()
+        .unwrap_or_else(|e| panic!("build should succeed: {e}"));
+
+    let err = doser.step().expect_err("out-of-range reading must fail");
+    match err.downcast_ref::<doser_core::error::DoserError>() {
+        Some(doser_core::error::DoserError::HardwareFault(s)) => {
+            assert!(s.contains("16777216"), "{s}")
+        }
+        other => panic!("expected a hardware fault, got: {other:?}"),
+    }
+    // Readings handed in by a sampler are checked the same way.
+    assert!(doser.step_from_raw(-0x80_0001).is_err());
+    assert!(doser.step_from_raw(0x7F_FFFF).is_err());
+    assert!(doser.step_from_raw(0x7F_FFFE).is_ok());
+}
+
+#[rstest]
+#[case(0x7F_FFFF)]
+#[case(-0x80_0000)]
+fn hx711_saturation_code_is_a_fault(#[case] code: i32) {
+    let mut doser = Doser::builder()
+        .with_scale(SaturatedScale(code))
+        .with_motor(NopMotor)
+        .with_filter(FilterCfg::default())
+        .with_control(ControlCfg::default())
+        .with_timeouts(Timeouts { sensor_ms: 5 })
+        .with_target_grams(5.0)
+        .apply_calibration::<()>(None)
+        .build()
+        .unwrap_or_else(|e| panic!("build should succeed: {e}"));
+
+    let err = doser.step().expect_err("saturated reading must fail");
+    match err.downcast_ref::<doser_core::error::DoserError>() {
+        Some(doser_core::error::DoserError::HardwareFault(s)) => {
+            assert!(s.contains(&code.to_string()), "{s}")
+        }
+        other => panic!("expected a hardware fault, got: {other:?}"),
+    }
+}
@@ -0,0 +1,109 @@
+//! `ControlCfg::loop_hz`: control ticks between samples slow the motor on the
+//! extrapolated weight and poll the E-stop, but leave completion to samples.
+
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use doser_core::error::{AbortReason, DoserError};
+use doser_core::{Calibration, ControlCfg, Doser, DosingStatus, FilterCfg};
+use doser_traits::Motor;
+use doser_traits::clock::VirtualClock;
+
+/// Records every speed command.
+#[derive(Clone, Default)]
+struct SpyMotor(Arc<Mutex<Vec<u32>>>);
+impl Motor for SpyMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn set_speed(&mut self, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.0.lock().unwrap().push(sps);
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+/// 10 Hz samples, a 100 Hz loop, 0.1 g per count, default speed bands.
+fn doser(motor: SpyMotor, estop: Option<Arc<AtomicBool>>) -> Doser {
+    let mut b = Doser::builder()
+        .with_scale(doser_core::mocks::NoopScale)
+        .with_motor(motor)
+        .with_filter(FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 10,
+            ema_alpha: 0.0,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            stable_ms: 0,
+            loop_hz: 100,
+            ..ControlCfg::default()
+        })
+        .with_calibration(Calibration {
+            gain_g_per_count: 0.1,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_clock(Box::new(VirtualClock::new()))
+        .with_target_grams(10.0);
+    if let Some(flag) = estop {
+        b = b
+            .with_estop_check(move || flag.load(Ordering::Relaxed))
+            .with_estop_debounce(1);
+    }
+    let mut d = b.apply_calibration::<()>(None).build().unwrap();
+    d.set_loop_hz(100);
+    d.begin();
+    d
+}
+
+#[test]
+fn hold_ticks_slow_the_motor_between_samples() {
+    let motor = SpyMotor::default();
+    let mut d = doser(motor.clone(), None);
+    // Each call takes one 10 ms loop period; a sample every tenth.
+    d.step_from_raw(0).unwrap();
+    for _ in 0..9 {
+        d.step_hold().unwrap();
+    }
+    d.step_from_raw(40).unwrap();
+    for _ in 0..9 {
+        d.step_hold().unwrap();
+    }
+    // 8 g at 4 g per 100 ms: 2 g short, still the fastest band.
+    d.step_from_raw(80).unwrap();
+    assert_eq!(*motor.0.lock().unwrap(), [1100]);
+
+    // Extrapolated 8.4, 8.8, 9.2 and 9.6 g.
+    for _ in 0..4 {
+        d.step_hold().unwrap();
+    }
+    assert_eq!(*motor.0.lock().unwrap(), [1100, 450, 200]);
+
+    // At the target by extrapolation only: keep running until a sample says so.
+    for _ in 0..5 {
+        assert!(matches!(d.step_hold().unwrap(), DosingStatus::Running));
+    }
+    assert_eq!(*motor.0.lock().unwrap(), [1100, 450, 200]);
+    assert!(matches!(
+        d.step_from_raw(100).unwrap(),
+        DosingStatus::Complete
+    ));
+}
+
+#[test]
+fn hold_ticks_poll_the_estop() {
+    let flag = Arc::new(AtomicBool::new(false));
+    let mut d = doser(SpyMotor::default(), Some(Arc::clone(&flag)));
+    d.step_from_raw(0).unwrap();
+    assert!(matches!(d.step_hold().unwrap(), DosingStatus::Running));
+    flag.store(true, Ordering::Relaxed);
+    assert!(matches!(
+        d.step_hold().unwrap(),
+        DosingStatus::Aborted(DoserError::Abort(AbortReason::Estop))
+    ));
+}
@@ -0,0 +1,129 @@
+//! Direct-mode pacing: iterations are scheduled on absolute deadlines, so the
+//! time spent reading the scale does not accumulate on top of the period.
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use doser_core::{Doser, DosingStatus, FilterCfg, OverrunPolicy, SafetyCfg};
+use doser_traits::clock::Clock;
+use doser_traits::{Motor, Scale};
+
+#[derive(Clone)]
+struct VirtualClock {
+    origin: Instant,
+    offset: Arc<Mutex<Duration>>,
+}
+impl VirtualClock {
+    fn elapsed(&self) -> Duration {
+        *self.offset.lock().unwrap()
+    }
+}
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.origin + *self.offset.lock().unwrap()
+    }
+    fn sleep(&self, d: Duration) {
+        *self.offset.lock().unwrap() += d;
+    }
+}
+
+/// Scale whose reads take `latency` of (virtual) time and never gain weight.
+struct SlowScale {
+    clock: VirtualClock,
+    latency: Duration,
+}
+impl Scale for SlowScale {
+    fn read(&mut self, _timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        self.clock.sleep(self.latency);
+        Ok(0)
+    }
+}
+
+struct NoopMotor;
+impl Motor for NoopMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn set_speed(&mut self, _sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+fn run_steps(latency: Duration, steps: u32) -> Duration {
+    run_steps_with(latency, steps, OverrunPolicy::SkipSleep).0
+}
+
+fn run_steps_with(latency: Duration, steps: u32, policy: OverrunPolicy) -> (Duration, u64) {
+    let clock = VirtualClock {
+        origin: Instant::now(),
+        offset: Arc::default(),
+    };
+    let mut doser = Doser::builder()
+        .with_scale(SlowScale {
+            clock: clock.clone(),
+            latency,
+        })
+        .with_motor(NoopMotor)
+        .with_filter(FilterCfg {
+            sample_rate_hz: 50,
+            ..FilterCfg::default()
+        })
+        .with_safety(SafetyCfg {
+            max_run_ms: 600_000,
+            no_progress_ms: 0,
+            ..SafetyCfg::default()
+        })
+        .with_clock(Box::new(clock.clone()))
+        .with_overrun_policy(policy)
+        .with_target_grams(100.0)
+        .build()
+        .unwrap();
+    doser.begin();
+    let start = clock.elapsed();
+    for _ in 0..steps {
+        assert!(matches!(doser.step().unwrap(), DosingStatus::Running));
+    }
+    (clock.elapsed() - start, doser.loop_overruns())
+}
+
+#[test]
+fn read_latency_does_not_accumulate_as_drift() {
+    // 50 Hz → 20 ms period; each read eats 5 ms of it.
+    let elapsed = run_steps(Duration::from_millis(5), 100);
+    assert_eq!(elapsed, Duration::from_millis(20 * 100));
+}
+
+#[test]
+fn overrun_reanchors_instead_of_bursting() {
+    // Reads slower than the period: no extra sleep is added, and the loop does
+    // not try to "catch up" with back-to-back iterations.
+    let elapsed = run_steps(Duration::from_millis(30), 10);
+    assert_eq!(elapsed, Duration::from_millis(30 * 10));
+}
+
+#[test]
+fn overrun_policy_trades_cadence_for_latency() {
+    let read = Duration::from_millis(30);
+    let (skip, n_skip) = run_steps_with(read, 10, OverrunPolicy::SkipSleep);
+    let (degrade, n_degrade) = run_steps_with(read, 10, OverrunPolicy::LogAndDegrade);
+    assert_eq!(skip, Duration::from_millis(300));
+    // Every iteration overruns and then waits a full 20 ms period.
+    assert_eq!(degrade, Duration::from_millis(500));
+    assert_eq!((n_skip, n_degrade), (10, 10));
+
+    let (_, n_fast) = run_steps_with(Duration::from_millis(5), 10, OverrunPolicy::SkipSleep);
+    assert_eq!(n_fast, 0);
+}
+
+#[test]
+fn overrun_policy_defaults_agree_with_the_config() {
+    assert_eq!(OverrunPolicy::default(), OverrunPolicy::SkipSleep);
+    assert_eq!(
+        doser_core::conversions::overrun_policy(doser_config::OverrunPolicy::default()),
+        OverrunPolicy::default()
+    );
+}
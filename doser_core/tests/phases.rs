@@ -0,0 +1,185 @@
+//! Per-phase timing: time between steps is charged to the phase the loop was in.
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use doser_core::{Calibration, ControlCfg, Doser, DosingStatus, FilterCfg, PhaseTimings};
+use doser_traits::Motor;
+use doser_traits::clock::Clock;
+
+#[derive(Clone)]
+struct VirtualClock {
+    origin: Instant,
+    offset: Arc<Mutex<Duration>>,
+}
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.origin + *self.offset.lock().unwrap()
+    }
+    fn sleep(&self, d: Duration) {
+        *self.offset.lock().unwrap() += d;
+    }
+}
+
+struct NoopMotor;
+impl Motor for NoopMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn set_speed(&mut self, _sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+#[test]
+fn phases_partition_the_dose() {
+    let clock = VirtualClock {
+        origin: Instant::now(),
+        offset: Arc::default(),
+    };
+    let mut doser = Doser::builder()
+        .with_scale(doser_core::mocks::NoopScale)
+        .with_motor(NoopMotor)
+        .with_filter(FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 50,
+            ema_alpha: 0.0,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            stable_ms: 40,
+            ..ControlCfg::default()
+        })
+        .with_calibration(Calibration {
+            gain_g_per_count: 0.1,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_clock(Box::new(clock.clone()))
+        .with_target_grams(10.0)
+        .apply_calibration::<()>(None)
+        .build()
+        .unwrap();
+    doser.begin();
+
+    // 20 ms per step: three at full speed, two in the slower bands, one over
+    // the acceptance band (settle), then the stable hold (verify).
+    let raws = [0, 0, 0, 95, 97, 103, 100, 100];
+    let mut last = None;
+    for raw in raws {
+        last = Some(doser.step_from_raw(raw).unwrap());
+    }
+    assert!(matches!(last, Some(DosingStatus::Complete)));
+
+    let t = doser.phase_timings();
+    assert_eq!(
+        t,
+        PhaseTimings {
+            coarse_ms: 60,
+            fine_ms: 40,
+            settle_ms: 20,
+            verify_ms: 20,
+        }
+    );
+    assert_eq!(t.total_ms(), 140);
+
+    // A new dose starts from zero.
+    doser.begin();
+    assert_eq!(doser.phase_timings(), PhaseTimings::default());
+}
+
+#[test]
+fn hold_weight_averages_the_in_band_settle_window() {
+    let clock = VirtualClock {
+        origin: Instant::now(),
+        offset: Arc::default(),
+    };
+    let mut doser = Doser::builder()
+        .with_scale(doser_core::mocks::NoopScale)
+        .with_motor(NoopMotor)
+        .with_filter(FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 50,
+            ema_alpha: 0.0,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            stable_ms: 60,
+            hysteresis_g: 0.5,
+            ..ControlCfg::default()
+        })
+        .with_calibration(Calibration {
+            gain_g_per_count: 0.01,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_clock(Box::new(clock.clone()))
+        .with_target_grams(10.0)
+        .apply_calibration::<()>(None)
+        .build()
+        .unwrap();
+    doser.begin();
+
+    // A spike out of the band restarts the window; it is not part of the hold.
+    let raws = [0, 0, 1_000, 1_100, 1_004, 996, 1_002];
+    let mut last = None;
+    for raw in raws {
+        assert!(!matches!(last, Some(DosingStatus::Complete)));
+        last = Some(doser.step_from_raw(raw).unwrap());
+    }
+    assert!(matches!(last, Some(DosingStatus::Complete)));
+    assert!((doser.hold_weight().unwrap() - 10.0067).abs() < 1e-3);
+    assert!((doser.last_weight() - 10.02).abs() < 1e-4);
+}
+
+#[test]
+fn motor_steps_and_coast_are_measured() {
+    let clock = VirtualClock {
+        origin: Instant::now(),
+        offset: Arc::default(),
+    };
+    let mut doser = Doser::builder()
+        .with_scale(doser_core::mocks::NoopScale)
+        .with_motor(NoopMotor)
+        .with_filter(FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 50,
+            ema_alpha: 0.0,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            speed_bands: vec![(1.0, 1000)],
+            epsilon_g: 0.6,
+            hysteresis_g: 0.6,
+            stable_ms: 40,
+            ..ControlCfg::default()
+        })
+        .with_calibration(Calibration {
+            gain_g_per_count: 0.01,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_clock(Box::new(clock.clone()))
+        .with_target_grams(10.0)
+        .apply_calibration::<()>(None)
+        .build()
+        .unwrap();
+    doser.begin();
+
+    // 20 ms per step at 1000 sps; the motor stops at 9.5 g and 0.5 g lands after.
+    let raws = [0, 200, 400, 600, 800, 950, 1000, 1000];
+    let mut last = None;
+    for raw in raws {
+        last = Some(doser.step_from_raw(raw).unwrap());
+    }
+    assert!(matches!(last, Some(DosingStatus::Complete)));
+    assert_eq!(doser.motor_steps(), 100);
+    assert!((doser.coast_g().unwrap() - 0.5).abs() < 1e-6);
+}
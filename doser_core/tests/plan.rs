@@ -0,0 +1,64 @@
+use doser_core::ControlCfg;
+use doser_core::plan::plan;
+use rstest::rstest;
+
+#[rstest]
+fn plan_follows_the_speed_bands() {
+    let control = ControlCfg {
+        // Unsorted on purpose: the builder sorts, so the plan must too.
+        speed_bands: vec![(1.0, 400), (5.0, 1000), (0.2, 100)],
+        epsilon_g: 0.05,
+        stable_ms: 300,
+        ..ControlCfg::default()
+    };
+    let p = plan(&control, 10.0, Some(0.001));
+    let legs: Vec<_> = p
+        .steps
+        .iter()
+        .map(|s| (s.from_g, s.to_g, s.sps, s.exit_sps))
+        .collect();
+    assert_eq!(
+        legs,
+        vec![
+            (0.0, 5.0, 1000, 1000),
+            (5.0, 9.0, 400, 400),
+            // The last band runs on below its threshold until the stop.
+            (9.0, 9.95, 100, 100),
+        ]
+    );
+    // 5 g at 1 g/s, 4 g at 0.4 g/s, 0.95 g at 0.1 g/s.
+    let ms: Vec<_> = p.steps.iter().map(|s| s.est_ms.unwrap()).collect();
+    assert_eq!(ms, vec![5000, 10000, 9500]);
+    assert_eq!(p.stop_at_g, 9.95);
+    assert_eq!(p.est_ms, Some(5000 + 10000 + 9500 + 300));
+
+    // A small dose starts in the band its whole error falls into.
+    let p = plan(&control, 3.0, None);
+    assert_eq!(p.steps.len(), 2);
+    assert_eq!((p.steps[0].from_g, p.steps[0].sps), (0.0, 400));
+    assert!(p.steps.iter().all(|s| s.est_ms.is_none()));
+    assert_eq!(p.est_ms, None);
+}
+
+#[rstest]
+fn plan_tapers_the_fine_speed_in_two_speed_mode() {
+    let control = ControlCfg {
+        speed_bands: vec![],
+        coarse_speed: 1000,
+        fine_speed: 200,
+        slow_at_g: 1.0,
+        epsilon_g: 0.0,
+        stable_ms: 0,
+        ..ControlCfg::default()
+    };
+    let p = plan(&control, 10.0, Some(0.001));
+    assert_eq!(p.steps.len(), 2);
+    assert_eq!((p.steps[0].to_g, p.steps[0].sps), (9.0, 1000));
+    assert_eq!(p.steps[0].est_ms, Some(9000));
+    // 200 sps tapering to 20% as the error closes.
+    let fine = &p.steps[1];
+    assert_eq!((fine.sps, fine.exit_sps), (200, 40));
+    // ∫₀¹ de / (0.2 g/s · (0.2 + 0.8 e)) = ln(5) / 0.16 s
+    let expected = (5f64.ln() / 0.16 * 1000.0).round() as u64;
+    assert_eq!(fine.est_ms, Some(expected));
+}
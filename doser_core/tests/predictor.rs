@@ -1,9 +1,12 @@
-use doser_core::{ControlCfg, Doser, FilterCfg, PredictorCfg, Timeouts};
+use doser_core::error::{AbortReason, DoserError};
+use doser_core::{
+    ControlCfg, Doser, DosingStatus, FilterCfg, PredictorCfg, Timeouts, UndershootPolicy,
+};
 use rstest::rstest;
 use std::error::Error;
 use std::sync::{
     Arc,
-    atomic::{AtomicU64, Ordering},
+    atomic::{AtomicBool, AtomicU64, Ordering},
 };
 
 /// Use NoopScale and drive the loop via step_from_raw() to simulate weights.
@@ -61,6 +64,7 @@ fn early_stop_triggers_before_target_cross() {
         window: 4,
         extra_latency_ms: 40,
         min_progress_ratio: 0.05,
+        ..PredictorCfg::default()
     };
 
     let tclk = TestClock::new();
@@ -73,6 +77,7 @@ fn early_stop_triggers_before_target_cross() {
             median_window: 1,
             sample_rate_hz: 50,
             ema_alpha: 0.0,
+            ..FilterCfg::default()
         })
         .with_control(ControlCfg {
             speed_bands: vec![],
@@ -119,3 +124,224 @@ fn early_stop_triggers_before_target_cross() {
         idx
     );
 }
+
+/// Records the last commanded speed (0 after stop) and whether it runs.
+#[derive(Clone, Default)]
+struct SpeedMotor {
+    sps: Arc<AtomicU64>,
+    running: Arc<AtomicBool>,
+}
+impl doser_traits::Motor for SpeedMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.running.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+    fn set_speed(&mut self, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.sps.store(u64::from(sps), Ordering::Relaxed);
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.sps.store(0, Ordering::Relaxed);
+        self.running.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[rstest]
+#[case::top_up(UndershootPolicy::TopUp)]
+#[case::accept(UndershootPolicy::Accept)]
+#[case::abort(UndershootPolicy::Abort)]
+fn undershoot_after_early_stop_applies_policy(#[case] policy: UndershootPolicy) {
+    let tclk = TestClock::new();
+    let motor = SpeedMotor::default();
+    let mut doser = Doser::builder()
+        .with_scale(doser_core::mocks::NoopScale)
+        .with_motor(motor.clone())
+        .with_filter(FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 50,
+            ema_alpha: 0.0,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            speed_bands: vec![],
+            stable_ms: 0,
+            epsilon_g: 0.1,
+            ..ControlCfg::default()
+        })
+        .with_timeouts(Timeouts { sensor_ms: 1 })
+        .with_calibration(doser_core::Calibration {
+            gain_g_per_count: 0.1,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_target_grams(10.0)
+        .with_clock(Box::new(tclk.clone()))
+        .with_predictor(PredictorCfg {
+            enabled: true,
+            window: 4,
+            extra_latency_ms: 40,
+            min_progress_ratio: 0.05,
+            undershoot: policy,
+            ..PredictorCfg::default()
+        })
+        .build()
+        .unwrap();
+    doser.begin();
+
+    // 0.5 g per sample: the predictor (~1.5 g in flight) stops at 8.5 g, but
+    // only 0.5 g more arrives and the weight settles at 9.0 g.
+    let raws = (1..=17).map(|i| i * 5).chain(std::iter::repeat(90));
+    let mut outcome = None;
+    for raw in raws.take(40) {
+        tclk.advance(20);
+        match doser.step_from_raw(raw).unwrap() {
+            DosingStatus::Running if doser.undershoot_g().is_none() => {}
+            status => {
+                outcome = Some(status);
+                break;
+            }
+        }
+    }
+
+    assert!(doser.early_stop_at_g().is_some());
+    let short = doser.undershoot_g().expect("undershoot recorded");
+    assert!((short - 1.0).abs() < 1e-3, "undershoot_g = {short}");
+    let speed = motor.sps.load(Ordering::Relaxed);
+    match (policy, outcome.expect("loop ended without a decision")) {
+        (UndershootPolicy::TopUp, DosingStatus::Running) => {
+            assert!(speed > 0, "not resumed");
+            assert!(motor.running.load(Ordering::Relaxed), "motor not restarted");
+        }
+        (UndershootPolicy::Accept, DosingStatus::Complete) => assert_eq!(speed, 0),
+        (UndershootPolicy::Abort, DosingStatus::Aborted(DoserError::Abort(reason))) => {
+            assert_eq!(reason, AbortReason::Undershoot);
+            assert_eq!(speed, 0);
+        }
+        (p, s) => panic!("{p:?}: unexpected {s:?}"),
+    }
+}
+
+#[rstest]
+#[case::no_top_up_left(1)]
+#[case::one_top_up(2)]
+fn top_up_is_bounded_by_max_attempts(#[case] max_attempts: u32) {
+    let tclk = TestClock::new();
+    let motor = SpeedMotor::default();
+    let mut doser = Doser::builder()
+        .with_scale(doser_core::mocks::NoopScale)
+        .with_motor(motor.clone())
+        .with_filter(FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 50,
+            ema_alpha: 0.0,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            speed_bands: vec![],
+            stable_ms: 0,
+            epsilon_g: 0.1,
+            max_attempts,
+            ..ControlCfg::default()
+        })
+        .with_timeouts(Timeouts { sensor_ms: 1 })
+        .with_calibration(doser_core::Calibration {
+            gain_g_per_count: 0.1,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_target_grams(10.0)
+        .with_clock(Box::new(tclk.clone()))
+        .with_predictor(PredictorCfg {
+            enabled: true,
+            window: 4,
+            extra_latency_ms: 40,
+            min_progress_ratio: 0.05,
+            undershoot: UndershootPolicy::TopUp,
+            ..PredictorCfg::default()
+        })
+        .build()
+        .unwrap();
+    doser.begin();
+
+    // As above: the early stop settles 1 g short and the top-up is due.
+    let raws = (1..=17).map(|i| i * 5).chain(std::iter::repeat(90));
+    let mut outcome = DosingStatus::Running;
+    for raw in raws.take(40) {
+        tclk.advance(20);
+        outcome = doser.step_from_raw(raw).unwrap();
+        if doser.undershoot_g().is_some() {
+            break;
+        }
+    }
+
+    let attempts = doser.attempts();
+    let first = attempts[0];
+    assert_eq!((first.n, first.start_g), (1, 0.5));
+    assert!(first.stop_g.is_some_and(|g| g < 9.0), "{first:?}");
+    match outcome {
+        DosingStatus::Aborted(DoserError::Abort(reason)) => {
+            assert_eq!(max_attempts, 1);
+            assert_eq!(reason, AbortReason::MaxAttempts);
+            assert_eq!(attempts.len(), 1);
+            assert!(!motor.running.load(Ordering::Relaxed));
+        }
+        DosingStatus::Running => {
+            assert_eq!(max_attempts, 2);
+            assert_eq!(attempts.len(), 2);
+            assert_eq!((attempts[1].n, attempts[1].stop_ms), (2, None));
+            assert!(motor.running.load(Ordering::Relaxed), "top-up not started");
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn settle_zone_restarts_count_as_attempts() {
+    let tclk = TestClock::new();
+    let motor = SpeedMotor::default();
+    let mut doser = Doser::builder()
+        .with_scale(doser_core::mocks::NoopScale)
+        .with_motor(motor.clone())
+        .with_filter(FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 50,
+            ema_alpha: 0.0,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            speed_bands: vec![],
+            stable_ms: 10_000,
+            epsilon_g: 0.1,
+            ..ControlCfg::default()
+        })
+        .with_timeouts(Timeouts { sensor_ms: 1 })
+        .with_calibration(doser_core::Calibration {
+            gain_g_per_count: 0.1,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_target_grams(10.0)
+        .with_clock(Box::new(tclk.clone()))
+        .build()
+        .unwrap();
+    doser.begin();
+
+    // Each dip out of the settle zone starts the motor again: 40 approaches
+    // with max_attempts unbounded, of which the first 15 and the latest are kept.
+    for raw in [95, 100].repeat(40) {
+        tclk.advance(20);
+        let status = doser.step_from_raw(raw).unwrap();
+        assert!(matches!(status, DosingStatus::Running), "{status:?}");
+    }
+    assert_eq!(doser.attempt_count(), 40);
+    let attempts = doser.attempts();
+    assert_eq!(attempts.len(), 16);
+    assert_eq!(attempts[14].n, 15);
+    assert_eq!(attempts[15].n, 40);
+    assert!(attempts[15].stop_g.is_some());
+    assert!(!motor.running.load(Ordering::Relaxed));
+}
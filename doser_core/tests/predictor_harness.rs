@@ -1,4 +1,6 @@
-use doser_core::{ControlCfg, Doser, FilterCfg, PredictorCfg, SafetyCfg, Timeouts};
+use doser_core::{
+    ControlCfg, Doser, FilterCfg, InflightModel, PredictorCfg, SafetyCfg, SlopeSource, Timeouts,
+};
 use rstest::rstest;
 use std::collections::VecDeque;
 use std::error::Error;
@@ -232,6 +234,7 @@ fn predictor_reduces_overshoot_and_failures_under_latency() {
                 median_window: 1,
                 sample_rate_hz: SAMPLE_RATE_HZ,
                 ema_alpha: 0.0,
+                ..FilterCfg::default()
             };
             let safety = SafetyCfg {
                 max_run_ms: 60_000,
@@ -302,6 +305,7 @@ fn predictor_reduces_overshoot_and_failures_under_latency() {
                 median_window: 1,
                 sample_rate_hz: SAMPLE_RATE_HZ,
                 ema_alpha: 0.0,
+                ..FilterCfg::default()
             };
             let safety = SafetyCfg {
                 max_run_ms: 60_000,
@@ -315,6 +319,7 @@ fn predictor_reduces_overshoot_and_failures_under_latency() {
                 window: 5,
                 extra_latency_ms: DELAY_MS,
                 min_progress_ratio: 0.1,
+                ..PredictorCfg::default()
             };
             let mut d = Doser::builder()
                 .with_scale(scale)
@@ -376,3 +381,154 @@ fn predictor_reduces_overshoot_and_failures_under_latency() {
         "overshoot aborts did not decrease: A={aborts_a}, B={aborts_b}"
     );
 }
+
+/// Plant weight (true, not delayed) when the predictor first stops the motor.
+/// The coarse→fine band switch happens just before the predictor would fire,
+/// so the window still holds coarse-speed samples.
+fn first_stop_weight(model: InflightModel, seed: u32) -> Option<f32> {
+    const SAMPLE_RATE_HZ: u32 = 50;
+    let st = Arc::new(Mutex::new(SimState::default()));
+    let scale = SimScaleLatency::new(st.clone(), 0.0025, 0.02, 2, 0.01, SAMPLE_RATE_HZ, seed);
+    let tclk = TestClock::new();
+    let mut d = Doser::builder()
+        .with_scale(scale)
+        .with_motor(SimMotor { st: st.clone() })
+        .with_filter(FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: SAMPLE_RATE_HZ,
+            ema_alpha: 0.0,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            speed_bands: vec![(1.0, 1200), (0.5, 100)],
+            stable_ms: 0,
+            epsilon_g: 0.0,
+            ..ControlCfg::default()
+        })
+        .with_predictor(PredictorCfg {
+            enabled: true,
+            window: 5,
+            extra_latency_ms: 300,
+            min_progress_ratio: 0.1,
+            model,
+            ..PredictorCfg::default()
+        })
+        .with_timeouts(Timeouts { sensor_ms: 5 })
+        .with_calibration(doser_core::Calibration {
+            gain_g_per_count: 0.01,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_target_grams(5.0)
+        .with_clock(Box::new(tclk.clone()))
+        .build()
+        .unwrap();
+    d.begin();
+    for _ in 0..2000 {
+        tclk.advance(20);
+        if !matches!(d.step().unwrap(), doser_core::DosingStatus::Running) {
+            return None;
+        }
+        if d.early_stop_at_g().is_some() {
+            return Some(st.lock().unwrap().weight_g);
+        }
+    }
+    None
+}
+
+#[rstest]
+fn decel_model_reduces_early_stop_undershoot() {
+    const TARGET_G: f32 = 5.0;
+    let mean_undershoot = |model| {
+        let stops: Vec<f32> = (0..20)
+            .filter_map(|i| first_stop_weight(model, 0xD0 + i))
+            .collect();
+        assert_eq!(stops.len(), 20, "{model:?}: predictor never stopped");
+        stops.iter().map(|w| (TARGET_G - w).max(0.0)).sum::<f32>() / stops.len() as f32
+    };
+    let linear = mean_undershoot(InflightModel::Linear);
+    let decel = mean_undershoot(InflightModel::Decel);
+    assert!(
+        decel < 0.5 * linear,
+        "decel model did not cut undershoot: linear={linear:.3} g, decel={decel:.3} g"
+    );
+}
+
+/// True plant overshoot of a completed dose with a heavy moving average
+/// (10 samples, ~90 ms of lag at 50 Hz) ahead of the controller.
+fn overshoot_with_heavy_filter(slope_source: SlopeSource, seed: u32) -> Option<f32> {
+    const SAMPLE_RATE_HZ: u32 = 50;
+    const TARGET_G: f32 = 5.0;
+    let st = Arc::new(Mutex::new(SimState::default()));
+    let scale = SimScaleLatency::new(st.clone(), 0.0025, 0.02, 2, 0.01, SAMPLE_RATE_HZ, seed);
+    let tclk = TestClock::new();
+    let mut d = Doser::builder()
+        .with_scale(scale)
+        .with_motor(SimMotor { st: st.clone() })
+        .with_filter(FilterCfg {
+            ma_window: 10,
+            median_window: 1,
+            sample_rate_hz: SAMPLE_RATE_HZ,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            speed_bands: vec![(1.0, 1200), (0.5, 450)],
+            stable_ms: 0,
+            epsilon_g: 0.0,
+            ..ControlCfg::default()
+        })
+        .with_predictor(PredictorCfg {
+            enabled: true,
+            window: 8,
+            extra_latency_ms: 40,
+            min_progress_ratio: 0.1,
+            slope_source,
+            ..PredictorCfg::default()
+        })
+        .with_safety(SafetyCfg {
+            max_run_ms: 60_000,
+            max_overshoot_g: 100.0,
+            no_progress_epsilon_g: 0.0,
+            no_progress_ms: 0,
+        })
+        .with_timeouts(Timeouts { sensor_ms: 5 })
+        .with_calibration(doser_core::Calibration {
+            gain_g_per_count: 0.01,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_target_grams(TARGET_G)
+        .with_clock(Box::new(tclk.clone()))
+        .build()
+        .unwrap();
+    d.begin();
+    for _ in 0..2000 {
+        tclk.advance(20);
+        match d.step().unwrap() {
+            doser_core::DosingStatus::Running => {}
+            doser_core::DosingStatus::Complete => {
+                return Some((st.lock().unwrap().weight_g - TARGET_G).max(0.0));
+            }
+            doser_core::DosingStatus::Aborted(_) => return None,
+        }
+    }
+    None
+}
+
+#[rstest]
+fn fast_slope_source_offsets_filter_lag() {
+    let mean_overshoot = |source| {
+        let over: Vec<f32> = (0..20)
+            .filter_map(|i| overshoot_with_heavy_filter(source, 0xF0 + i))
+            .collect();
+        assert_eq!(over.len(), 20, "{source:?}: dose did not complete");
+        over.iter().sum::<f32>() / over.len() as f32
+    };
+    let filtered = mean_overshoot(SlopeSource::Filtered);
+    let fast = mean_overshoot(SlopeSource::Fast);
+    assert!(
+        fast < 0.75 * filtered,
+        "fast slope source did not cut overshoot: filtered={filtered:.3} g, fast={fast:.3} g"
+    );
+}
@@ -0,0 +1,221 @@
+//! Pre-flight gate: failures are collected and the motor never starts.
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use doser_core::error::{PreflightError, PreflightFailure};
+use doser_core::runner::{RunClock, RunParams, SamplingMode, run};
+use doser_core::warmup::ProgressSink;
+use doser_core::{
+    ControlCfg, FilterCfg, OverrunPolicy, PreflightCfg, ReadRetryCfg, SafeState, SafetyCfg,
+    Timeouts, WarmupCfg,
+};
+use doser_traits::{Motor, Scale};
+
+type Log = Arc<Mutex<Vec<&'static str>>>;
+
+struct LogMotor(Log);
+impl Motor for LogMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.0.lock().unwrap().push("start");
+        Ok(())
+    }
+    fn set_speed(&mut self, _sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn enable(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.0.lock().unwrap().push("enable");
+        Ok(())
+    }
+    fn disable(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.0.lock().unwrap().push("disable");
+        Ok(())
+    }
+}
+
+/// Cycles through the given raw readings.
+struct Seq(Vec<i32>, usize);
+impl Scale for Seq {
+    fn read(&mut self, _t: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        let v = self.0[self.1 % self.0.len()];
+        self.1 += 1;
+        Ok(v)
+    }
+}
+
+fn params(preflight: PreflightCfg) -> RunParams {
+    RunParams {
+        filter: FilterCfg {
+            sample_rate_hz: 1000,
+            ..FilterCfg::default()
+        },
+        control: ControlCfg::default(),
+        safety: SafetyCfg {
+            max_run_ms: 200,
+            ..SafetyCfg::default()
+        },
+        timeouts: Timeouts::default(),
+        read_retry: ReadRetryCfg::default(),
+        calibration: None,
+        target_g: 1.0,
+        estop_debounce_n: 1,
+        prefer_timeout_first: true,
+        mode: SamplingMode::Direct,
+        predictor: None,
+        overrun: OverrunPolicy::default(),
+        safe_state: SafeState::default(),
+        warmup: WarmupCfg::default(),
+        progress: ProgressSink::default(),
+        status: Default::default(),
+        preflight,
+        shutdown: None,
+        clock: RunClock::default(),
+    }
+}
+
+#[test]
+fn all_failures_reported_and_motor_never_starts() {
+    let log = Log::default();
+    // 0 g then 3 g: unstable beyond the default 1 g spread.
+    let err = run(
+        Seq(vec![0, 300], 0),
+        LogMotor(log.clone()),
+        Some(Box::new(|| true)),
+        params(PreflightCfg::default()),
+    )
+    .unwrap_err();
+    let pe = err
+        .downcast_ref::<PreflightError>()
+        .expect("PreflightError");
+    assert_eq!(pe.failures.len(), 2, "{pe}");
+    assert_eq!(pe.failures[0], PreflightFailure::EstopActive);
+    assert!(matches!(
+        pe.failures[1],
+        PreflightFailure::ScaleUnstable { .. }
+    ));
+    assert_eq!(*log.lock().unwrap(), ["enable", "disable"]);
+}
+
+#[test]
+fn railed_adc_and_band_are_checked() {
+    let err = run(
+        Seq(vec![0x7F_FFFF], 0),
+        LogMotor(Log::default()),
+        None,
+        params(PreflightCfg::default()),
+    )
+    .unwrap_err();
+    let pe = err.downcast_ref::<PreflightError>().unwrap();
+    assert_eq!(pe.failures, [PreflightFailure::ScaleSaturated(0x7F_FFFF)]);
+
+    let err = run(
+        Seq(vec![100], 0),
+        LogMotor(Log::default()),
+        None,
+        params(PreflightCfg {
+            min_g: Some(5.0),
+            ..PreflightCfg::default()
+        }),
+    )
+    .unwrap_err();
+    let pe = err.downcast_ref::<PreflightError>().unwrap();
+    assert_eq!(
+        pe.failures,
+        [PreflightFailure::WeightOutOfBand { grams: 1.0 }]
+    );
+}
+
+#[test]
+fn disabled_preflight_lets_the_dose_run() {
+    let log = Log::default();
+    let _ = run(
+        Seq(vec![0x7F_FFFF], 0),
+        LogMotor(log.clone()),
+        Some(Box::new(|| false)),
+        params(PreflightCfg {
+            enabled: false,
+            ..PreflightCfg::default()
+        }),
+    );
+    assert!(!log.lock().unwrap().contains(&"enable"));
+}
+
+#[test]
+fn hopper_check_requires_target_plus_reserve() {
+    use doser_core::HopperCfg;
+    use doser_core::preflight::check_hopper;
+
+    let t = Duration::from_millis(10);
+    let cfg = HopperCfg {
+        reserve_g: 5.0,
+        ..HopperCfg::default()
+    };
+    // 1 count = 0.01 g: readings average to about 20 g.
+    let available = check_hopper(&mut Seq(vec![1990, 2010], 0), &cfg, 14.0, t).unwrap();
+    assert!((available - 20.0).abs() < 0.05);
+
+    let err = check_hopper(&mut Seq(vec![2000], 0), &cfg, 15.5, t).unwrap_err();
+    assert_eq!(
+        err.failures,
+        [PreflightFailure::HopperLow {
+            available_g: 20.0,
+            needed_g: 20.5
+        }]
+    );
+    assert!(
+        err.to_string()
+            .contains("hopper holds 20.0 g, dose needs 20.5 g")
+    );
+
+    let err = check_hopper(&mut Seq(vec![0x7F_FFFF], 0), &cfg, 1.0, t).unwrap_err();
+    assert!(matches!(
+        err.failures[..],
+        [PreflightFailure::HopperUnreadable(_)]
+    ));
+}
+
+#[test]
+fn units_self_test_flags_a_foreign_calibration() {
+    let band = |container_g| PreflightCfg {
+        zero_band_g: Some(2.0),
+        container_g,
+        ..PreflightCfg::default()
+    };
+    // The dose itself times out on the constant reading; only the gate matters.
+    let passes = |raw, cfg| match run(
+        Seq(vec![raw], 0),
+        LogMotor(Log::default()),
+        None,
+        params(cfg),
+    ) {
+        Ok(_) => true,
+        Err(e) => e.downcast_ref::<PreflightError>().is_none(),
+    };
+    // 1 count = 0.01 g: 100 counts read 1 g, near zero.
+    assert!(passes(100, band(None)));
+
+    // A 50 g container read as 500 g: another scale's calibration.
+    let err = run(
+        Seq(vec![50_000], 0),
+        LogMotor(Log::default()),
+        None,
+        params(band(Some(50.0))),
+    )
+    .unwrap_err();
+    let pe = err.downcast_ref::<PreflightError>().unwrap();
+    assert_eq!(
+        pe.failures,
+        [PreflightFailure::UnitsMismatch {
+            grams: 500.0,
+            expected_g: 50.0,
+            band_g: 2.0
+        }]
+    );
+    assert!(pe.to_string().contains("wrong calibration file?"), "{pe}");
+
+    assert!(passes(5_050, band(Some(50.0))));
+}
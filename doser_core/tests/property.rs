@@ -76,7 +76,7 @@ proptest! {
         let scale = BoundedScale::new(deltas);
         let motor = NoopMotor;
 
-        let filter = FilterCfg { ma_window: 1, median_window: 1, sample_rate_hz: 500, ema_alpha: 0.0 };
+        let filter = FilterCfg { ma_window: 1, median_window: 1, sample_rate_hz: 500, ema_alpha: 0.0, ..FilterCfg::default() };
         let control = ControlCfg { stable_ms: 0, ..ControlCfg::default() };
         let safety = SafetyCfg {
             max_run_ms: 5_000,
@@ -0,0 +1,102 @@
+//! Retry of timed-out scale reads in the direct control loop.
+
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use doser_core::error::DoserError;
+use doser_core::{Doser, ReadRetryCfg, Timeouts};
+use doser_traits::{Motor, Scale};
+use rstest::rstest;
+
+/// Fails its first `failures` reads with `err`, then reads 0.
+struct FailingScale {
+    failures: u32,
+    err: &'static str,
+    reads: Arc<AtomicU32>,
+}
+
+impl Scale for FailingScale {
+    fn read(&mut self, _timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        let n = self.reads.fetch_add(1, Ordering::SeqCst);
+        if n < self.failures {
+            Err(self.err.into())
+        } else {
+            Ok(0)
+        }
+    }
+}
+
+struct NopMotor;
+
+impl Motor for NopMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn set_speed(&mut self, _sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+fn doser(failures: u32, err: &'static str, max_retries: u32) -> (Doser, Arc<AtomicU32>) {
+    let reads = Arc::new(AtomicU32::new(0));
+    let doser = Doser::builder()
+        .with_scale(FailingScale {
+            failures,
+            err,
+            reads: reads.clone(),
+        })
+        .with_motor(NopMotor)
+        .with_timeouts(Timeouts { sensor_ms: 1 })
+        .with_read_retry(ReadRetryCfg {
+            max_retries,
+            backoff_ms: 1,
+        })
+        .with_target_grams(5.0)
+        .build()
+        .unwrap();
+    (doser, reads)
+}
+
+#[rstest]
+#[case::read_timeout("sensor timeout")]
+#[case::data_ready("data-ready timeout")]
+fn timeouts_within_budget_are_ridden_out(#[case] err: &'static str) {
+    let (mut d, reads) = doser(2, err, 2);
+    d.begin();
+    d.step().expect("recovered on the last retry");
+    assert_eq!(reads.load(Ordering::SeqCst), 3);
+    assert_eq!(d.read_retries(), 2);
+    assert_eq!(d.reads_recovered(), 1);
+}
+
+#[test]
+fn exhausted_retries_surface_the_timeout() {
+    let (mut d, reads) = doser(3, "sensor timeout", 2);
+    d.begin();
+    let err = d.step().expect_err("retries exhausted");
+    assert!(matches!(
+        err.downcast_ref::<DoserError>(),
+        Some(DoserError::Timeout)
+    ));
+    assert_eq!(reads.load(Ordering::SeqCst), 3);
+    assert_eq!(d.read_retries(), 2);
+    assert_eq!(d.reads_recovered(), 0);
+}
+
+#[test]
+fn other_errors_are_not_retried() {
+    let (mut d, reads) = doser(1, "sensor disconnected", 2);
+    d.begin();
+    let err = d.step().expect_err("hardware error");
+    assert!(matches!(
+        err.downcast_ref::<DoserError>(),
+        Some(DoserError::Hardware(_))
+    ));
+    assert_eq!(reads.load(Ordering::SeqCst), 1);
+    assert_eq!(d.read_retries(), 0);
+}
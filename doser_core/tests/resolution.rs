@@ -0,0 +1,44 @@
+// Scale resolution: counts to grams, and display rounding.
+use doser_core::Calibration;
+use doser_core::util::{display_decimals, display_decimals_for};
+use rstest::rstest;
+
+#[rstest]
+#[case::unknown(None, 2)]
+#[case::centigram(Some(0.01), 2)]
+#[case::five_kg_cell(Some(0.05), 2)]
+#[case::decigram(Some(0.1), 1)]
+#[case::fifth_gram(Some(0.2), 1)]
+#[case::gram(Some(1.0), 0)]
+#[case::coarse(Some(5.0), 0)]
+#[case::finer_than_core(Some(0.001), 2)]
+#[case::invalid(Some(0.0), 2)]
+#[case::nan(Some(f32::NAN), 2)]
+fn display_decimals_show_one_step(#[case] resolution_g: Option<f32>, #[case] expected: usize) {
+    assert_eq!(display_decimals(resolution_g), expected);
+}
+
+#[rstest]
+#[case::quiet(Some(0.01), 0.0, 2)]
+#[case::small_noise(Some(0.01), 0.03, 2)]
+#[case::noisy(Some(0.01), 0.2, 1)]
+#[case::very_noisy(Some(0.01), 2.0, 0)]
+#[case::coarse_scale(Some(1.0), 0.03, 0)]
+fn noise_limits_display_decimals(
+    #[case] resolution_g: Option<f32>,
+    #[case] sigma_g: f32,
+    #[case] expected: usize,
+) {
+    assert_eq!(display_decimals_for(resolution_g, sigma_g), expected);
+}
+
+#[test]
+fn resolution_counts_scale_by_gain_magnitude() {
+    let cal = Calibration {
+        gain_g_per_count: -0.0025,
+        zero_counts: 1000,
+        offset_g: 3.0,
+    };
+    assert!((cal.counts_to_g(20) - 0.05).abs() < 1e-6);
+    assert_eq!(Calibration::default().counts_to_g(1), 0.01);
+}
@@ -0,0 +1,78 @@
+//! Resume accounting after a transient abort.
+
+use std::error::Error;
+use std::time::Duration;
+
+use doser_core::Calibration;
+use doser_core::error::{AbortReason, DoserError, PreflightError, PreflightFailure};
+use doser_core::resume::{ResumePoint, is_resumable, read_mean_counts, retare};
+use doser_traits::Scale;
+use rstest::rstest;
+
+fn cal() -> Calibration {
+    Calibration {
+        gain_g_per_count: 0.01,
+        zero_counts: 1_000,
+        offset_g: 0.0,
+    }
+}
+
+#[test]
+fn remaining_is_measured_against_the_original_tare() {
+    let point = ResumePoint::new(20.0, &cal());
+    // 12 g landed before the abort.
+    let raw = 1_000 + 1_200;
+    assert!((point.dosed_g(&cal(), raw) - 12.0).abs() < 1e-4);
+    assert!((point.remaining_g(&cal(), raw) - 8.0).abs() < 1e-4);
+    // Already past the target: nothing left to dose.
+    assert_eq!(point.remaining_g(&cal(), 1_000 + 2_100), 0.0);
+
+    // Re-taring at the current weight makes it read zero.
+    let fresh = retare(&cal(), raw);
+    assert_eq!(fresh.to_grams(raw), 0.0);
+    assert_eq!(fresh.gain_g_per_count, 0.01);
+}
+
+#[test]
+fn disturbance_while_paused_can_be_left_out() {
+    let mut point = ResumePoint::new(20.0, &cal());
+    assert_eq!(point.disturbance_g(&cal(), 2_200), None);
+    // 12 g had landed when the dose stopped; the container was then bumped (+0.5 g).
+    point.paused_counts = Some(1_000 + 1_200);
+    let raw = 1_000 + 1_250;
+    assert!((point.disturbance_g(&cal(), raw).unwrap() - 0.5).abs() < 1e-4);
+    // Counted as product the bump shortens the dose; re-zeroed it does not.
+    assert!((point.remaining_g(&cal(), raw) - 7.5).abs() < 1e-4);
+    assert!((point.remaining_g(&cal(), point.paused_counts.unwrap()) - 8.0).abs() < 1e-4);
+}
+
+#[rstest]
+#[case::timeout(DoserError::Timeout.into(), true)]
+#[case::data_ready(DoserError::DataReadyTimeout.into(), true)]
+#[case::wrapped(eyre::Report::new(DoserError::Timeout).wrap_err("reading scale"), true)]
+#[case::estop(DoserError::Abort(AbortReason::Estop).into(), false)]
+#[case::no_progress(DoserError::Abort(AbortReason::NoProgress).into(), false)]
+#[case::preflight(
+    PreflightError { failures: vec![PreflightFailure::ScaleTimeout] }.into(),
+    false
+)]
+fn only_sensor_timeouts_are_resumable(#[case] err: eyre::Report, #[case] expected: bool) {
+    assert_eq!(is_resumable(&err), expected, "{err}");
+}
+
+struct Seq(Vec<i32>);
+
+impl Scale for Seq {
+    fn read(&mut self, _timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        self.0.pop().ok_or_else(|| "sensor timeout".into())
+    }
+}
+
+#[test]
+fn mean_counts_average_the_samples() {
+    let mut scale = Seq(vec![110, 90, 100]);
+    let t = Duration::from_millis(1);
+    assert_eq!(read_mean_counts(&mut scale, 3, t).unwrap(), 100);
+    let err = read_mean_counts(&mut scale, 1, t).unwrap_err();
+    assert!(is_resumable(&err));
+}
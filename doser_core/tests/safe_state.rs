@@ -0,0 +1,162 @@
+//! Abort safe-state sequence: ordering, E-stop motion guard, once per dose;
+//! hard stop (`Motor::halt`) on E-stop and overshoot.
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use doser_core::error::{AbortReason, DoserError};
+use doser_core::{
+    Calibration, Doser, DosingStatus, FilterCfg, SafeState, SafeStateCfg, SafetyCfg, SharedActuator,
+};
+use doser_traits::{Actuator, Motor, Scale};
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+struct LogMotor(Log);
+impl Motor for LogMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn set_speed(&mut self, _sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.0.lock().unwrap().push("stop".into());
+        Ok(())
+    }
+    fn reverse(&mut self, steps: u32, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("reverse {steps}@{sps}"));
+        Ok(())
+    }
+    fn disable(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.0.lock().unwrap().push("disable".into());
+        Ok(())
+    }
+    fn halt(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.0.lock().unwrap().push("halt".into());
+        Ok(())
+    }
+}
+
+struct LogActuator(&'static str, Log);
+impl Actuator for LogActuator {
+    fn set_active(&mut self, active: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.1.lock().unwrap().push(format!("{} {active}", self.0));
+        Ok(())
+    }
+}
+
+struct ConstScale(i32);
+impl Scale for ConstScale {
+    fn read(&mut self, _t: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        Ok(self.0)
+    }
+}
+
+fn safe_state(log: &Log) -> SafeState {
+    let valve: SharedActuator = Arc::new(Mutex::new(LogActuator("valve", log.clone())));
+    SafeState::new(SafeStateCfg {
+        reverse_steps: 200,
+        reverse_sps: 400,
+        release: vec!["valve".into(), "missing".into()],
+        disable_driver: true,
+    })
+    .with_actuator("valve", valve)
+}
+
+fn doser(log: &Log, raw: i32, estop: bool) -> Doser {
+    Doser::builder()
+        .with_scale(ConstScale(raw))
+        .with_motor(LogMotor(log.clone()))
+        .with_filter(FilterCfg {
+            sample_rate_hz: 1000,
+            ..FilterCfg::default()
+        })
+        .with_safety(SafetyCfg {
+            max_overshoot_g: 1.0,
+            ..SafetyCfg::default()
+        })
+        .with_calibration(Calibration {
+            gain_g_per_count: 0.01,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_estop_check(move || estop)
+        .with_estop_debounce(1)
+        .with_safe_state(safe_state(log))
+        .with_target_grams(10.0)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn overshoot_runs_sequence_in_order_after_halt() {
+    let log = Log::default();
+    let mut d = doser(&log, 2000, false);
+    d.begin();
+    let st = d.step().unwrap();
+    assert!(matches!(
+        st,
+        DosingStatus::Aborted(DoserError::Abort(AbortReason::Overshoot))
+    ));
+    // Unknown actuators are skipped (logged), the rest still runs.
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["halt", "reverse 200@400", "valve false", "disable"]
+    );
+
+    // Runs at most once per dose...
+    let _ = d.step().unwrap();
+    d.enter_safe_state(&DoserError::Timeout);
+    assert_eq!(log.lock().unwrap().len(), 5, "{:?}", log.lock().unwrap());
+
+    // ...and again after `begin()`.
+    d.begin();
+    let _ = d.step().unwrap();
+    assert_eq!(
+        log.lock()
+            .unwrap()
+            .iter()
+            .filter(|e| *e == "disable")
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn estop_never_reverses() {
+    let log = Log::default();
+    let mut d = doser(&log, 0, true);
+    d.begin();
+    let st = d.step().unwrap();
+    assert!(matches!(
+        st,
+        DosingStatus::Aborted(DoserError::Abort(AbortReason::Estop))
+    ));
+    assert_eq!(*log.lock().unwrap(), ["halt", "valve false", "disable"]);
+}
+
+#[test]
+fn other_aborts_stop_gracefully() {
+    let log = Log::default();
+    let mut d = Doser::builder()
+        .with_scale(ConstScale(0))
+        .with_motor(LogMotor(log.clone()))
+        .with_safety(SafetyCfg {
+            max_run_ms: 0,
+            ..SafetyCfg::default()
+        })
+        .with_target_grams(10.0)
+        .build()
+        .unwrap();
+    d.begin();
+    assert!(matches!(
+        d.step().unwrap(),
+        DosingStatus::Aborted(DoserError::Abort(AbortReason::MaxRuntime))
+    ));
+    assert_eq!(*log.lock().unwrap(), ["stop"]);
+}
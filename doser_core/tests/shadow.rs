@@ -0,0 +1,124 @@
+//! Shadow mode: the controller only observes; divergence from the external
+//! process is measured, and the (shadow) motor is never driven.
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use doser_core::shadow::{Divergence, ShadowMonitor, ShadowMotor};
+use doser_core::{Calibration, ControlCfg, Doser, DosingStatus, FilterCfg};
+use doser_traits::Scale;
+use doser_traits::clock::Clock;
+
+#[derive(Clone)]
+struct VirtualClock {
+    origin: Instant,
+    offset: Arc<Mutex<Duration>>,
+}
+impl VirtualClock {
+    fn ms(&self) -> u64 {
+        self.offset.lock().unwrap().as_millis() as u64
+    }
+}
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.origin + *self.offset.lock().unwrap()
+    }
+    fn sleep(&self, d: Duration) {
+        *self.offset.lock().unwrap() += d;
+    }
+}
+
+/// External process: dispenses 0.1 g per read, idles for the first 5 reads,
+/// and keeps going up to 10.5 g (past a 10 g target) before it stops.
+struct ExternalLine {
+    reads: u32,
+    grams: f32,
+}
+impl Scale for ExternalLine {
+    fn read(&mut self, _t: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        self.reads += 1;
+        if self.reads > 5 && self.grams < 10.5 {
+            self.grams += 0.1;
+        }
+        Ok((self.grams * 100.0).round() as i32)
+    }
+}
+
+#[test]
+fn shadow_run_reports_stop_point_and_divergence() {
+    let clock = VirtualClock {
+        origin: Instant::now(),
+        offset: Arc::default(),
+    };
+    let (motor, probe) = ShadowMotor::new();
+    let mut doser = Doser::builder()
+        .with_scale(ExternalLine {
+            reads: 0,
+            grams: 0.0,
+        })
+        .with_motor(motor)
+        .with_filter(FilterCfg {
+            sample_rate_hz: 100,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            stable_ms: 50,
+            ..ControlCfg::default()
+        })
+        .with_calibration(Calibration {
+            gain_g_per_count: 0.01,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_clock(Box::new(clock.clone()))
+        .with_target_grams(10.0)
+        .build()
+        .unwrap();
+    let mut monitor = ShadowMonitor::new(0.5);
+    doser.begin();
+
+    let mut seen = Vec::new();
+    let status = loop {
+        let st = doser.step().unwrap();
+        let t = clock.ms();
+        if let Some(d) = monitor.observe(t, doser.last_weight(), probe.running()) {
+            seen.push(d);
+        }
+        if !matches!(st, DosingStatus::Running) || monitor.settled(t, 100) {
+            break st;
+        }
+        assert!(t < 10_000, "shadow run did not terminate");
+    };
+    let report = monitor.finish(clock.ms());
+
+    // The external line overshoots past the acceptance band, so the controller
+    // never completes; the run ends once both sides have gone quiet.
+    assert!(matches!(status, DosingStatus::Running), "{status:?}");
+    let stop_at = report
+        .would_stop_at_g
+        .expect("controller would have stopped");
+    assert!((9.8..=10.0).contains(&stop_at), "would stop at {stop_at}");
+    assert!(report.last_g > 10.0);
+    // Idle start and post-stop flow both count as divergence.
+    assert!(seen.contains(&Divergence::WouldRunExternalIdle));
+    assert!(seen.contains(&Divergence::WouldStopExternalRunning));
+    assert_eq!(report.episodes, 2, "{report:?}");
+    assert!(report.divergent_ms > 0);
+    assert!(!probe.running());
+}
+
+#[test]
+fn agreement_is_not_divergence() {
+    let mut m = ShadowMonitor::new(0.5);
+    // Steady 1 g/s flow while the controller would run: only the first samples,
+    // before the smoothed flow crosses the threshold, disagree.
+    let seen: Vec<_> = (0..20u64)
+        .map(|i| m.observe(i * 100, i as f32 * 0.1, true))
+        .collect();
+    assert_eq!(seen[..2], [Some(Divergence::WouldRunExternalIdle); 2]);
+    assert!(seen[2..].iter().all(Option::is_none));
+    let r = m.finish(2000);
+    assert_eq!((r.episodes, r.divergent_ms), (1, 200));
+    assert_eq!(r.would_stop_at_g, None);
+}
@@ -0,0 +1,134 @@
+//! Monte Carlo simulation: reproducible per seed, and sensitive to the plant;
+//! the load-cell noise models.
+
+use doser_core::sim::{NoiseModel, NoiseSource, Plant, SimReport, Tuning, monte_carlo};
+use doser_core::{ControlCfg, FilterCfg, SafetyCfg, Timeouts};
+
+fn tuning() -> Tuning {
+    Tuning {
+        filter: FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 100,
+            ..FilterCfg::default()
+        },
+        control: ControlCfg::default(),
+        safety: SafetyCfg {
+            max_run_ms: 60_000,
+            max_overshoot_g: 5.0,
+            no_progress_epsilon_g: 0.0,
+            no_progress_ms: 0,
+        },
+        timeouts: Timeouts { sensor_ms: 5 },
+        predictor: None,
+    }
+}
+
+#[test]
+fn same_seed_same_report() {
+    let plant = Plant::default();
+    let a = monte_carlo(&tuning(), &plant, 10.0, 20, 7).unwrap();
+    let b = monte_carlo(&tuning(), &plant, 10.0, 20, 7).unwrap();
+    assert_eq!(a, b);
+    assert_eq!(a.runs.len(), 20);
+    for name in SimReport::METRICS {
+        assert!(a.metric(name).is_some(), "{name}");
+    }
+    assert_eq!(a.metric("abort_rate"), Some(0.0));
+    assert_eq!(a.metric("no_such_metric"), None);
+    let c = monte_carlo(&tuning(), &plant, 10.0, 20, 8).unwrap();
+    assert_ne!(a, c);
+}
+
+#[test]
+fn transport_delay_costs_overshoot() {
+    let quick = Plant {
+        latency_ms: 0,
+        ..Plant::default()
+    };
+    let slow = Plant {
+        latency_ms: 1000,
+        ..Plant::default()
+    };
+    let quick = monte_carlo(&tuning(), &quick, 10.0, 30, 1).unwrap();
+    let slow = monte_carlo(&tuning(), &slow, 10.0, 30, 1).unwrap();
+    let p95 = |r: &SimReport| r.metric("overshoot_p95").unwrap();
+    assert!(
+        p95(&slow) > p95(&quick),
+        "{} vs {}",
+        p95(&slow),
+        p95(&quick)
+    );
+}
+
+/// Sample `n` readings at `rate_hz`.
+fn samples(model: NoiseModel, rate_hz: f64, n: usize) -> Vec<f32> {
+    let mut src = NoiseSource::new(model, 3);
+    (0..n).map(|i| src.sample(i as f64 / rate_hz)).collect()
+}
+
+fn sigma_and_lag1(x: &[f32]) -> (f32, f32) {
+    let n = x.len() as f32;
+    let mean = x.iter().sum::<f32>() / n;
+    let var = x.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n;
+    let cov = x
+        .windows(2)
+        .map(|w| (w[0] - mean) * (w[1] - mean))
+        .sum::<f32>()
+        / (n - 1.0);
+    (var.sqrt(), cov / var)
+}
+
+#[test]
+fn pink_noise_wanders_where_white_does_not() {
+    let off = NoiseModel {
+        white_g: 0.0,
+        ..NoiseModel::default()
+    };
+    let (white_sigma, white_lag) = sigma_and_lag1(&samples(
+        NoiseModel {
+            white_g: 0.1,
+            ..off
+        },
+        100.0,
+        20_000,
+    ));
+    let (pink_sigma, pink_lag) =
+        sigma_and_lag1(&samples(NoiseModel { pink_g: 0.1, ..off }, 100.0, 20_000));
+    assert!((white_sigma - 0.1).abs() < 0.01, "{white_sigma}");
+    assert!((pink_sigma - 0.1).abs() < 0.02, "{pink_sigma}");
+    assert!(white_lag.abs() < 0.05, "{white_lag}");
+    assert!(pink_lag > 0.5, "{pink_lag}");
+}
+
+#[test]
+fn mains_hum_aliases_at_the_sample_rate() {
+    let hum = NoiseModel {
+        white_g: 0.0,
+        hum_g: 0.2,
+        mains_hz: 50.0,
+        ..NoiseModel::default()
+    };
+    // 10 Hz divides 50 Hz: every reading lands on the same phase.
+    let fixed = samples(hum, 10.0, 100);
+    assert!(
+        fixed.iter().all(|v| (v - fixed[0]).abs() < 1e-3),
+        "{fixed:?}"
+    );
+    // 49 Hz sees a 1 Hz beat that spans the full amplitude.
+    let beat = samples(hum, 49.0, 49);
+    let (lo, hi) = beat
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    assert!(lo < -0.19 && hi > 0.19, "{lo}..{hi}");
+}
+
+#[test]
+fn noise_is_reproducible_per_seed() {
+    let model = NoiseModel {
+        pink_g: 0.05,
+        hum_g: 0.05,
+        ..NoiseModel::default()
+    };
+    assert_eq!(samples(model, 50.0, 200), samples(model, 50.0, 200));
+}
@@ -1,15 +1,16 @@
 use std::error::Error;
 use std::sync::{Arc, Mutex};
 
-use doser_core::{ControlCfg, Doser, FilterCfg, Timeouts};
+use doser_core::{ControlCfg, DosePhase, Doser, FilterCfg, SpeedBand, Timeouts};
 use rstest::rstest;
 
 // No custom clock needed for these tests
 
-// Motor spy that records the last commanded speed
+// Motor spy that records the last commanded speed and the number of commands
 #[derive(Default, Clone)]
 struct SpyMotor {
     pub last_sps: Arc<Mutex<u32>>,
+    pub commands: Arc<Mutex<u32>>,
 }
 impl doser_traits::Motor for SpyMotor {
     fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
@@ -17,6 +18,7 @@ impl doser_traits::Motor for SpyMotor {
     }
     fn set_speed(&mut self, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
         *self.last_sps.lock().unwrap() = sps;
+        *self.commands.lock().unwrap() += 1;
         Ok(())
     }
     fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
@@ -37,6 +39,7 @@ fn band_selection_matches_thresholds() {
             median_window: 1,
             sample_rate_hz: 50,
             ema_alpha: 0.0,
+            ..FilterCfg::default()
         })
         .with_control(ControlCfg::default())
         .with_timeouts(Timeouts { sensor_ms: 1 })
@@ -51,7 +54,7 @@ fn band_selection_matches_thresholds() {
         .unwrap();
 
     // Helper: step once at current_g and capture sps via spy
-    let check = |current_g: f32, expect_sps: u32| {
+    let check = |current_g: f32, expect_sps: u32, expect_band: usize| {
         let spy = SpyMotor::default();
         let spy_ref = spy.clone();
         let mut d = Doser::builder()
@@ -62,6 +65,7 @@ fn band_selection_matches_thresholds() {
                 median_window: 1,
                 sample_rate_hz: 50,
                 ema_alpha: 0.0,
+                ..FilterCfg::default()
             })
             .with_control(ControlCfg::default())
             .with_timeouts(Timeouts { sensor_ms: 1 })
@@ -79,12 +83,67 @@ fn band_selection_matches_thresholds() {
         let _ = d.step_from_raw(raw).unwrap();
         let sps = *spy_ref.last_sps.lock().unwrap();
         assert_eq!(sps, expect_sps, "current_g={current_g}");
+        match d.current_band() {
+            Some(SpeedBand::Band { index, sps, .. }) => {
+                assert_eq!(
+                    (index, sps),
+                    (expect_band, expect_sps),
+                    "current_g={current_g}"
+                )
+            }
+            other => panic!("current_g={current_g}: {other:?}"),
+        }
     };
 
-    check(8.8, 1100); // err_g=1.2
-    check(9.3, 450); // err_g=0.7
-    check(9.7, 200); // err_g=0.3
-    check(9.9, 200); // err_g=0.1 -> lowest band (avoid rounding to 10.0)
+    check(8.8, 1100, 0); // err_g=1.2
+    check(9.3, 450, 1); // err_g=0.7
+    check(9.7, 200, 2); // err_g=0.3
+    check(9.9, 200, 2); // err_g=0.1 -> lowest band (avoid rounding to 10.0)
+}
+
+#[rstest]
+fn legacy_band_follows_the_taper() {
+    let mut d = Doser::builder()
+        .with_scale(doser_core::mocks::NoopScale)
+        .with_motor(SpyMotor::default())
+        .with_filter(FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 50,
+            ema_alpha: 0.0,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            speed_bands: vec![],
+            ..ControlCfg::default()
+        })
+        .with_timeouts(Timeouts { sensor_ms: 1 })
+        .with_calibration(doser_core::Calibration {
+            gain_g_per_count: 0.1,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_target_grams(10.0)
+        .build()
+        .unwrap();
+    d.begin();
+    assert_eq!((d.phase(), d.current_band()), (None, None));
+
+    d.step_from_raw(50).unwrap();
+    assert_eq!(d.phase(), Some(DosePhase::Coarse));
+    assert_eq!(d.current_band(), Some(SpeedBand::Coarse));
+
+    d.step_from_raw(95).unwrap(); // err_g=0.5, inside slow_at_g
+    assert_eq!(d.phase(), Some(DosePhase::Fine));
+    assert!(
+        matches!(d.current_band(), Some(SpeedBand::Fine { sps }) if sps > 0 && sps < 250),
+        "{:?}",
+        d.current_band()
+    );
+
+    d.step_from_raw(100).unwrap(); // at target: stopped, verifying the hold
+    assert_eq!(d.phase(), Some(DosePhase::Verify));
+    assert_eq!(d.current_band(), None);
 }
 
 // Simple sim types for integration test
@@ -141,6 +200,7 @@ fn banded_vs_legacy_overshoot() {
             median_window: 1,
             sample_rate_hz: 50,
             ema_alpha: 0.0,
+            ..FilterCfg::default()
         })
         .with_control(ControlCfg {
             speed_bands: vec![],
@@ -169,6 +229,7 @@ fn banded_vs_legacy_overshoot() {
             median_window: 1,
             sample_rate_hz: 50,
             ema_alpha: 0.0,
+            ..FilterCfg::default()
         })
         .with_control(ControlCfg {
             stable_ms: 0,
@@ -202,3 +263,124 @@ fn banded_vs_legacy_overshoot() {
     let o2 = (doser_band.last_weight() - 5.0).max(0.0);
     assert!(o2 <= o1 + 1e-3, "banded overshoot={o2} legacy={o1}");
 }
+
+// Virtual clock advanced by the loop's own pacing sleeps (one period per step).
+#[derive(Clone)]
+struct StepClock {
+    origin: std::time::Instant,
+    elapsed: Arc<Mutex<std::time::Duration>>,
+}
+impl doser_traits::clock::Clock for StepClock {
+    fn now(&self) -> std::time::Instant {
+        self.origin + *self.elapsed.lock().unwrap()
+    }
+    fn sleep(&self, d: std::time::Duration) {
+        *self.elapsed.lock().unwrap() += d;
+    }
+}
+
+#[rstest]
+#[case::no_hysteresis(0.0, 0, 19)]
+#[case::hysteresis(0.2, 0, 1)]
+#[case::dwell(0.0, 100, 7)]
+fn noisy_error_around_threshold_does_not_chatter(
+    #[case] band_hysteresis_g: f32,
+    #[case] band_min_dwell_ms: u64,
+    #[case] expect_changes: usize,
+) {
+    let spy = SpyMotor::default();
+    let seen = spy.last_sps.clone();
+    let mut d = Doser::builder()
+        .with_scale(doser_core::mocks::NoopScale)
+        .with_motor(spy)
+        .with_filter(FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 50,
+            ema_alpha: 0.0,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            band_hysteresis_g,
+            band_min_dwell_ms,
+            ..ControlCfg::default()
+        })
+        .with_timeouts(Timeouts { sensor_ms: 1 })
+        .with_calibration(doser_core::Calibration {
+            gain_g_per_count: 0.01,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_target_grams(10.0)
+        .with_clock(Box::new(StepClock {
+            origin: std::time::Instant::now(),
+            elapsed: Arc::default(),
+        }))
+        .build()
+        .unwrap();
+    d.begin();
+
+    // err_g alternates 1.05 / 0.95 around the (1.0 g, 1100 sps) band edge.
+    let mut speeds = Vec::new();
+    for i in 0..20 {
+        let raw = if i % 2 == 0 { 895 } else { 905 };
+        d.step_from_raw(raw).unwrap();
+        speeds.push(*seen.lock().unwrap());
+    }
+    let changes = speeds.windows(2).filter(|w| w[0] != w[1]).count();
+    assert_eq!(changes, expect_changes, "{speeds:?}");
+    assert_eq!(speeds[0], 1100);
+    assert!(speeds.iter().all(|&s| s == 1100 || s == 450));
+}
+
+#[rstest]
+// Bands: 1100, 1020, 200 sps, ten samples each.
+#[case::bands(vec![(1.0, 1100), (0.5, 1020), (0.2, 200)], 0, vec![80, 93, 97], 3)]
+// Legacy taper: 1200 sps, then ~230 down to ~110 in ~20 sps steps.
+#[case::taper_any_change(vec![], 0, vec![80, 91, 92, 93, 94, 95, 96, 97], 8)]
+#[case::taper_min_delta(vec![], 30, vec![80, 91, 92, 93, 94, 95, 96, 97], 5)]
+fn set_speed_is_sent_only_on_change(
+    #[case] speed_bands: Vec<(f32, u32)>,
+    #[case] min_speed_delta_sps: u32,
+    #[case] raws: Vec<i32>,
+    #[case] expect: u32,
+) {
+    let spy = SpyMotor::default();
+    let commands = spy.commands.clone();
+    let mut d = Doser::builder()
+        .with_scale(doser_core::mocks::NoopScale)
+        .with_motor(spy)
+        .with_filter(FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 50,
+            ema_alpha: 0.0,
+            ..FilterCfg::default()
+        })
+        .with_control(ControlCfg {
+            speed_bands,
+            min_speed_delta_sps,
+            ..ControlCfg::default()
+        })
+        .with_timeouts(Timeouts { sensor_ms: 1 })
+        .with_calibration(doser_core::Calibration {
+            gain_g_per_count: 0.1,
+            zero_counts: 0,
+            offset_g: 0.0,
+        })
+        .with_target_grams(10.0)
+        .with_clock(Box::new(StepClock {
+            origin: std::time::Instant::now(),
+            elapsed: Arc::default(),
+        }))
+        .build()
+        .unwrap();
+    d.begin();
+
+    for raw in raws {
+        for _ in 0..10 {
+            d.step_from_raw(raw).unwrap();
+        }
+    }
+    assert_eq!(*commands.lock().unwrap(), expect);
+}
@@ -0,0 +1,152 @@
+//! Run status: published after each control step and readable from another
+//! thread while the loop runs.
+
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use doser_core::runner::{RunClock, RunParams, SamplingMode, run};
+use doser_core::warmup::ProgressSink;
+use doser_core::{
+    ControlCfg, FilterCfg, OverrunPolicy, PreflightCfg, ReadRetryCfg, RunState, RunStatus,
+    SafeState, SafetyCfg, StatusBoard, Timeouts, WarmupCfg,
+};
+use doser_traits::{Motor, Scale};
+
+/// Gains 20 counts (0.2 g) per reading while the motor runs.
+struct FlowScale {
+    counts: i32,
+    running: Arc<AtomicBool>,
+}
+impl Scale for FlowScale {
+    fn read(&mut self, _t: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        if self.running.load(Ordering::Relaxed) {
+            self.counts += 20;
+        }
+        Ok(self.counts)
+    }
+}
+
+struct FlagMotor(Arc<AtomicBool>);
+impl Motor for FlagMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.0.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+    fn set_speed(&mut self, _sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.0.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+fn params(status: StatusBoard) -> RunParams {
+    RunParams {
+        filter: FilterCfg {
+            sample_rate_hz: 1000,
+            ma_window: 1,
+            median_window: 1,
+            ..FilterCfg::default()
+        },
+        control: ControlCfg::default(),
+        safety: SafetyCfg {
+            max_run_ms: 5000,
+            ..SafetyCfg::default()
+        },
+        timeouts: Timeouts::default(),
+        read_retry: ReadRetryCfg::default(),
+        calibration: None,
+        target_g: 2.0,
+        estop_debounce_n: 1,
+        prefer_timeout_first: true,
+        mode: SamplingMode::Direct,
+        predictor: None,
+        overrun: OverrunPolicy::default(),
+        safe_state: SafeState::default(),
+        warmup: WarmupCfg::default(),
+        progress: ProgressSink::default(),
+        status,
+        preflight: PreflightCfg::default(),
+        shutdown: None,
+        clock: RunClock::default(),
+    }
+}
+
+#[test]
+fn polled_status_follows_the_run_to_completion() {
+    let running = Arc::new(AtomicBool::new(false));
+    let board = StatusBoard::new();
+    assert_eq!(board.latest().state, RunState::Idle);
+
+    let done = Arc::new(AtomicBool::new(false));
+    let poller = {
+        let (board, done) = (board.clone(), done.clone());
+        std::thread::spawn(move || {
+            let mut seen: Vec<RunStatus> = Vec::new();
+            while !done.load(Ordering::Relaxed) {
+                seen.push(board.latest());
+                std::thread::sleep(Duration::from_micros(200));
+            }
+            seen
+        })
+    };
+    let final_g = run(
+        FlowScale {
+            counts: 0,
+            running: running.clone(),
+        },
+        FlagMotor(running),
+        None,
+        params(board.clone()),
+    )
+    .unwrap();
+    done.store(true, Ordering::Relaxed);
+    let seen = poller.join().unwrap();
+
+    let last = board.latest();
+    assert_eq!(last.state, RunState::Complete);
+    assert_eq!(last.weight_g, final_g);
+    assert_eq!(last.target_g, 2.0);
+    assert!(last.pct >= 95.0, "{last:?}");
+    assert!(last.elapsed_ms > 0, "{last:?}");
+    let running: Vec<&RunStatus> = seen
+        .iter()
+        .filter(|s| s.state == RunState::Running)
+        .collect();
+    assert!(!running.is_empty(), "no running status seen");
+    assert!(
+        running.windows(2).all(|w| w[0].weight_g <= w[1].weight_g),
+        "weight went backwards"
+    );
+}
+
+#[test]
+fn a_failed_run_is_reported_aborted() {
+    let running = Arc::new(AtomicBool::new(false));
+    let board = StatusBoard::new();
+    let err = run(
+        FlowScale {
+            counts: 0,
+            running: running.clone(),
+        },
+        FlagMotor(running),
+        Some(Box::new(|| true)),
+        params(board.clone()),
+    );
+    assert!(err.is_err());
+    assert_eq!(board.latest().state, RunState::Aborted);
+}
+
+#[test]
+fn warnings_name_the_nonzero_counters() {
+    let s = RunStatus {
+        loop_overruns: 2,
+        ..RunStatus::default()
+    };
+    assert_eq!(s.warnings(), ["loop_overruns"]);
+    assert!(RunStatus::default().warnings().is_empty());
+    assert_eq!(StatusBoard::default().latest(), RunStatus::default());
+}
@@ -0,0 +1,93 @@
+//! Offline tuner: reproducible per seed, never worse than the starting tuning,
+//! and within the search bounds.
+
+use doser_core::sim::{Plant, Tuning};
+use doser_core::tune::{Search, Weights, optimize};
+use doser_core::{ControlCfg, FilterCfg, SafetyCfg, Timeouts};
+
+fn tuning() -> Tuning {
+    Tuning {
+        filter: FilterCfg {
+            ma_window: 1,
+            median_window: 1,
+            sample_rate_hz: 100,
+            ..FilterCfg::default()
+        },
+        control: ControlCfg::default(),
+        safety: SafetyCfg {
+            max_run_ms: 60_000,
+            max_overshoot_g: 5.0,
+            no_progress_epsilon_g: 0.0,
+            no_progress_ms: 0,
+        },
+        timeouts: Timeouts { sensor_ms: 5 },
+        predictor: None,
+    }
+}
+
+fn search(seed: u64) -> Search {
+    Search {
+        candidates: 24,
+        runs: 8,
+        seed,
+        weights: Weights::default(),
+    }
+}
+
+#[test]
+fn same_seed_same_recommendation() {
+    let plant = Plant::default();
+    let a = optimize(&tuning(), &plant, 10.0, &search(3)).unwrap();
+    let b = optimize(&tuning(), &plant, 10.0, &search(3)).unwrap();
+    assert_eq!(a.best.report, b.best.report);
+    assert_eq!(
+        a.best.tuning.control.speed_bands,
+        b.best.tuning.control.speed_bands
+    );
+    assert_eq!(a.evaluated, 24);
+}
+
+#[test]
+fn finds_bands_that_stop_in_time() {
+    // 5.5 g/s at the top band and 300 ms in flight: the default bands run
+    // about a gram past the target and every run ends at the runtime cap.
+    let plant = Plant {
+        g_per_step: 0.005,
+        latency_ms: 300,
+        ..Plant::default()
+    };
+    let report = optimize(&tuning(), &plant, 10.0, &search(1)).unwrap();
+    assert_eq!(report.baseline.report.metric("abort_rate"), Some(1.0));
+    assert!(report.baseline.cost.is_infinite());
+
+    assert!(report.improved());
+    assert_eq!(report.best.report.metric("abort_rate"), Some(0.0));
+    assert!(report.best.report.metric("overshoot_p95").unwrap() < 0.1);
+    let bands = &report.best.tuning.control.speed_bands;
+    assert_eq!(bands.len(), 3);
+    assert!(bands[0].1 <= 1100, "{bands:?}");
+    assert!(
+        bands
+            .windows(2)
+            .all(|w| w[0].0 > w[1].0 && w[0].1 >= w[1].1),
+        "{bands:?}"
+    );
+    assert!(report.best.tuning.predictor.as_ref().unwrap().enabled);
+}
+
+#[test]
+fn keeps_the_starting_tuning_when_nothing_beats_it() {
+    let report = optimize(
+        &tuning(),
+        &Plant::default(),
+        10.0,
+        &Search {
+            candidates: 0,
+            ..search(1)
+        },
+    )
+    .unwrap();
+    assert_eq!(report.evaluated, 0);
+    assert!(!report.improved());
+    assert_eq!(report.best.report, report.baseline.report);
+}
@@ -0,0 +1,98 @@
+//! Scale warm-up: discard count, rolling-stddev settle, timeout, and events.
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use doser_core::WarmupCfg;
+use doser_core::warmup::{ProgressSink, WarmupEvent, run};
+use doser_traits::Scale;
+
+/// Drifts by `step` counts per read for `drift_reads`, then holds.
+struct Drifting {
+    reads: u32,
+    drift_reads: u32,
+    step: i32,
+}
+impl Scale for Drifting {
+    fn read(&mut self, _t: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        self.reads += 1;
+        Ok(self.reads.min(self.drift_reads) as i32 * self.step)
+    }
+}
+
+fn recorder() -> (ProgressSink, Arc<Mutex<Vec<WarmupEvent>>>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = {
+        let events = events.clone();
+        ProgressSink::new(move |ev| events.lock().unwrap().push(ev))
+    };
+    (sink, events)
+}
+
+const T: Duration = Duration::from_millis(1);
+
+#[test]
+fn inactive_by_default() {
+    let mut s = Drifting {
+        reads: 0,
+        drift_reads: 0,
+        step: 0,
+    };
+    let (sink, events) = recorder();
+    run(&mut s, None, &WarmupCfg::default(), T, &sink).unwrap();
+    assert_eq!(s.reads, 0);
+    assert!(events.lock().unwrap().is_empty());
+}
+
+#[test]
+fn waits_for_drift_to_settle() {
+    // 1 g per read for 20 reads, then flat: a 5-sample window is flat from read 24.
+    let mut s = Drifting {
+        reads: 0,
+        drift_reads: 20,
+        step: 100,
+    };
+    let (sink, events) = recorder();
+    let cfg = WarmupCfg {
+        discard_samples: 3,
+        stable_stddev_g: Some(0.05),
+        window: 5,
+        ..WarmupCfg::default()
+    };
+    run(&mut s, None, &cfg, T, &sink).unwrap();
+    assert_eq!(s.reads, 24);
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.first(), Some(&WarmupEvent::Started));
+    assert!(matches!(
+        events.last(),
+        Some(WarmupEvent::Done {
+            samples: 24,
+            stable: true,
+            ..
+        })
+    ));
+    assert_eq!(events.len(), 24 + 2);
+}
+
+#[test]
+fn gives_up_after_max_ms() {
+    let mut s = Drifting {
+        reads: 0,
+        drift_reads: u32::MAX,
+        step: 100,
+    };
+    let (sink, events) = recorder();
+    let cfg = WarmupCfg {
+        stable_stddev_g: Some(0.05),
+        max_ms: 0,
+        ..WarmupCfg::default()
+    };
+    run(&mut s, None, &cfg, T, &sink).unwrap();
+    assert_eq!(s.reads, 1);
+    assert!(matches!(
+        events.lock().unwrap().last(),
+        Some(WarmupEvent::Done { stable: false, .. })
+    ));
+}
@@ -0,0 +1,136 @@
+//! Weigh-only reading: calibration and filtering without a motor.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::time::Duration;
+
+use doser_core::error::DoserError;
+use doser_core::{Calibration, Doser, FilterCfg, Timeouts, WeightReader};
+use doser_traits::{Motor, Scale};
+
+/// Returns the queued raw readings, then times out.
+struct Script(VecDeque<i32>);
+
+impl Scale for Script {
+    fn read(&mut self, _timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        self.0.pop_front().ok_or_else(|| {
+            Box::new(std::io::Error::new(std::io::ErrorKind::TimedOut, "timeout")) as _
+        })
+    }
+}
+
+fn reader(raw: &[i32], ma_window: usize) -> WeightReader<Script> {
+    let filter = FilterCfg {
+        ma_window,
+        median_window: 1,
+        ..FilterCfg::default()
+    };
+    let cal = Calibration {
+        gain_g_per_count: 0.01,
+        zero_counts: 1_000,
+        offset_g: 0.0,
+    };
+    WeightReader::new(
+        Script(raw.iter().copied().collect()),
+        &filter,
+        cal,
+        Timeouts { sensor_ms: 10 },
+    )
+}
+
+#[test]
+fn reads_calibrated_and_filtered_grams() {
+    let mut r = reader(&[2_000, 3_000, 3_000], 2);
+    assert!((r.read_g().unwrap() - 10.0).abs() < 1e-4);
+    // Moving average over the last two: (10 + 20) / 2.
+    assert!((r.read_g().unwrap() - 15.0).abs() < 1e-4);
+    // Samples from elsewhere go through the same filter.
+    assert!((r.filter_raw(3_000) - 20.0).abs() < 1e-4);
+    assert!((r.read_g().unwrap() - 20.0).abs() < 1e-4);
+
+    // An empty script reads like a silent sensor.
+    let err = r.read_g().unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<DoserError>(),
+        Some(DoserError::Timeout)
+    ));
+}
+
+#[test]
+fn tare_zeroes_at_the_current_mean() {
+    let mut r = reader(&[1_500, 1_520, 1_510, 2_510], 1);
+    assert_eq!(r.tare(3).unwrap(), 1_510);
+    assert_eq!(r.calibration().zero_counts, 1_510);
+    assert_eq!(r.calibration().gain_g_per_count, 0.01);
+    assert!((r.read_g().unwrap() - 10.0).abs() < 1e-4);
+    assert!(r.into_scale().0.is_empty());
+}
+
+#[test]
+fn mean_weight_carries_its_standard_error() {
+    let mut r = reader(&[1_000, 1_002, 1_000, 1_002], 1);
+    let w = r.read_mean_weight(4).unwrap();
+    assert!((w.grams - 0.01).abs() < 1e-6);
+    // Sample sd 0.011547 g over four readings.
+    assert!((w.sigma_g - 0.005_773_5).abs() < 1e-6, "{w:?}");
+    // One reading has no spread to go by.
+    let mut r = reader(&[1_500], 1);
+    assert_eq!(r.read_mean_weight(1).unwrap().sigma_g, 0.0);
+}
+
+struct NoopMotor;
+impl Motor for NoopMotor {
+    fn set_speed(&mut self, _: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+fn doser() -> Doser {
+    let mut d = Doser::builder()
+        .with_scale(Script(VecDeque::new()))
+        .with_motor(NoopMotor)
+        .with_filter(FilterCfg {
+            ma_window: 4,
+            sample_rate_hz: 1000,
+            ..FilterCfg::default()
+        })
+        .with_target_grams(500.0)
+        .build()
+        .unwrap();
+    d.begin();
+    d
+}
+
+#[test]
+fn uncertainty_tracks_noise_not_the_dosing_ramp() {
+    // A steady ramp of 0.5 g per reading: the filter lags, but nothing is noise.
+    let mut d = doser();
+    for i in 0..40 {
+        let _ = d.step_from_raw(i * 50).unwrap();
+    }
+    let w = d.current_weight();
+    assert!(w.grams < 19.5, "the filter lags the ramp: {w:?}");
+    assert_eq!(w.sigma_g, 0.0);
+
+    // Uniform noise of ±0.3 g (sd 0.173 g) on a constant load.
+    let mut d = doser();
+    let mut seed = 12_345u32;
+    for _ in 0..40 {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        let noise = (seed >> 16) % 61;
+        let _ = d.step_from_raw(10_000 + noise as i32 - 30).unwrap();
+    }
+    let w = d.current_weight();
+    assert!((w.grams - 100.0).abs() < 0.5, "{w:?}");
+    assert!(w.sigma_g > 0.1 && w.sigma_g < 0.35, "{w:?}");
+
+    // A new dose starts without history.
+    d.begin();
+    assert_eq!(d.current_weight().sigma_g, 0.0);
+}
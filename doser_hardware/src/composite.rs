@@ -0,0 +1,91 @@
+//! Composite scale: two load cells (two HX711s) combined into one reading.
+//!
+//! Both cells are read every sample and must agree within a tolerance (raw
+//! counts, so the cells are assumed matched; calibration applies to the combined
+//! reading). Agreeing readings are combined as a weighted average. A single
+//! out-of-tolerance pair is logged and averaged anyway; only a persistent
+//! disagreement is reported as a fault, so one noisy sample does not abort a dose.
+
+use std::error::Error;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use doser_traits::Scale;
+
+use crate::error::HwError;
+
+/// Two scales read as one.
+pub struct CompositeScale<A, B> {
+    a: A,
+    b: B,
+    /// Share of `a` in the average; `b` gets `1 - weight`.
+    weight: f32,
+    tolerance_counts: u32,
+    fault_after: u32,
+    disagree_streak: u32,
+}
+
+impl<A: Scale, B: Scale> CompositeScale<A, B> {
+    /// Equal-weight composite with the given tolerance (raw counts) and number
+    /// of consecutive disagreements tolerated before a fault.
+    pub fn new(a: A, b: B, tolerance_counts: u32, fault_after: u32) -> Self {
+        Self {
+            a,
+            b,
+            weight: 0.5,
+            tolerance_counts,
+            fault_after: fault_after.max(1),
+            disagree_streak: 0,
+        }
+    }
+
+    /// Set the share of the first cell in the average (clamped to `0.0..=1.0`).
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Consecutive out-of-tolerance reads so far.
+    pub fn disagree_streak(&self) -> u32 {
+        self.disagree_streak
+    }
+}
+
+impl<A: Scale, B: Scale> Scale for CompositeScale<A, B> {
+    fn read(&mut self, timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        let a = self.a.read(timeout)?;
+        let b = self.b.read(timeout)?;
+        if a.abs_diff(b) > self.tolerance_counts {
+            self.disagree_streak = self.disagree_streak.saturating_add(1);
+            tracing::warn!(a, b, streak = self.disagree_streak, "load cells disagree");
+            if self.disagree_streak >= self.fault_after {
+                return Err(Box::new(HwError::CellDisagreement { a, b }));
+            }
+        } else {
+            self.disagree_streak = 0;
+        }
+        let w = f64::from(self.weight);
+        let avg = w * f64::from(a) + (1.0 - w) * f64::from(b);
+        // Both inputs are i32, so the weighted average is in range.
+        #[allow(clippy::cast_possible_truncation)]
+        Ok(avg.round() as i32)
+    }
+
+    fn reinit(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.disagree_streak = 0;
+        let a = self.a.reinit();
+        let b = self.b.reinit();
+        a.and(b)
+    }
+
+    /// The coarser of the two cells (averaging only helps with matched noise).
+    fn resolution_counts(&self) -> Option<u32> {
+        Some(self.a.resolution_counts()?.max(self.b.resolution_counts()?))
+    }
+
+    /// The span of both cells' ranges (the reading is their weighted average).
+    fn raw_range(&self) -> Option<RangeInclusive<i32>> {
+        let (a, b) = (self.a.raw_range()?, self.b.raw_range()?);
+        Some(*a.start().min(b.start())..=*a.end().max(b.end()))
+    }
+}
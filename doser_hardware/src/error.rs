@@ -4,10 +4,28 @@ use thiserror::Error;
 pub enum HwError {
     #[error("gpio error: {0}")]
     Gpio(String),
+    /// The caller's per-read timeout (core `timeouts.sample_ms`) expired.
     #[error("scale timeout")]
     Timeout,
+    /// The HX711 data-ready wait (`hardware.sensor_read_timeout_ms`) expired.
     #[error("hx711 data-ready timeout")]
     DataReadyTimeout,
+    #[error("load cells disagree persistently (a={a}, b={b} counts)")]
+    CellDisagreement { a: i32, b: i32 },
+    #[error("{kind} driver {name:?} is not available in this build (available: {available})")]
+    UnknownDriver {
+        kind: &'static str,
+        name: String,
+        available: String,
+    },
+    /// Another process holds the GPIO chip lock.
+    #[error(transparent)]
+    Busy(#[from] crate::lock::BusyError),
+    /// The stepping thread stopped polling while the motor was running.
+    #[error("stepper thread stalled (no heartbeat for {ms} ms)")]
+    StepperStalled { ms: u64 },
+    #[error("plugin error: {0}")]
+    Plugin(String),
     #[error("io: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -0,0 +1,331 @@
+//! GPIO character-device backend (`/dev/gpiochipN`, the libgpiod kernel ABI).
+//!
+//! Works on any Linux SBC with a GPIO chip driver (Orange Pi, BeagleBone, CM4
+//! carrier boards, I/O expanders exposing a gpiochip), where the `hardware`
+//! backend's rppal only knows the Raspberry Pi SoC. Pins are line offsets on
+//! the configured chip. The protocol logic (HX711, stepping thread) is shared
+//! with the rppal backend.
+//!
+//! The v1 character-device ABI cannot set pull resistors: an E-stop input needs
+//! an external pull-up (or a driven signal).
+
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use doser_traits::clock::{Clock, MonotonicClock};
+use doser_traits::{Actuator, Motor, MotorDiagnostics, Scale};
+use gpio_cdev::{Chip, LineHandle, LineRequestFlags};
+use tracing::{info, warn};
+
+use crate::error::{HwError, Result as HwResult};
+use crate::hx711::{Hx711, Hx711Pins};
+use crate::pacing::{Pacer, RealSleeper};
+use crate::polarity::MotorPolarity;
+use crate::step_stats::StepStats;
+use crate::stepper::{MAX_SPS, StepCmd, StepperShared, Watchdog};
+
+/// Consumer label shown by `gpioinfo` for claimed lines.
+const CONSUMER: &str = "doser";
+
+fn request_input(chip: &mut Chip, line: u8, what: &str) -> HwResult<LineHandle> {
+    chip.get_line(u32::from(line))
+        .and_then(|l| l.request(LineRequestFlags::INPUT, 0, CONSUMER))
+        .map_err(|e| HwError::Gpio(format!("get {what} line {line}: {e}")))
+}
+
+fn request_output(chip: &mut Chip, line: u8, high: bool, what: &str) -> HwResult<LineHandle> {
+    chip.get_line(u32::from(line))
+        .and_then(|l| l.request(LineRequestFlags::OUTPUT, u8::from(high), CONSUMER))
+        .map_err(|e| HwError::Gpio(format!("get {what} line {line}: {e}")))
+}
+
+fn open_chip(path: &str) -> HwResult<Chip> {
+    Chip::new(path).map_err(|e| HwError::Gpio(format!("open {path}: {e}")))
+}
+
+fn set(line: &LineHandle, high: bool) -> HwResult<()> {
+    line.set_value(u8::from(high))
+        .map_err(|e| HwError::Gpio(format!("set line: {e}")))
+}
+
+struct GpiodPins {
+    dt: LineHandle,
+    sck: LineHandle,
+}
+
+impl Hx711Pins for GpiodPins {
+    fn dt_is_high(&self) -> HwResult<bool> {
+        self.dt
+            .get_value()
+            .map(|v| v != 0)
+            .map_err(|e| HwError::Gpio(format!("read HX711 DT: {e}")))
+    }
+
+    fn set_sck(&mut self, high: bool) -> HwResult<()> {
+        set(&self.sck, high)
+    }
+}
+
+/// HX711 scale on character-device GPIO lines.
+pub struct GpiodScale {
+    hx: Hx711<GpiodPins>,
+}
+
+impl GpiodScale {
+    /// Claim DT (input) and SCK (output) on `chip`, with the given data-ready
+    /// timeout in ms (0 = 150 ms).
+    pub fn try_new(chip: &str, dt: u8, sck: u8, data_ready_timeout_ms: u64) -> HwResult<Self> {
+        let mut chip = open_chip(chip)?;
+        let pins = GpiodPins {
+            dt: request_input(&mut chip, dt, "HX711 DT")?,
+            sck: request_output(&mut chip, sck, false, "HX711 SCK")?,
+        };
+        let drt = if data_ready_timeout_ms == 0 {
+            150
+        } else {
+            data_ready_timeout_ms
+        };
+        // Channel A / gain 128: 1 extra SCK pulse after the 24 data bits (25 total).
+        let hx = Hx711::new(pins, 1, Duration::from_millis(drt))?;
+        Ok(Self { hx })
+    }
+}
+
+impl Scale for GpiodScale {
+    fn read(&mut self, timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        Ok(self.hx.read_with_timeout(timeout)?)
+    }
+
+    fn reinit(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.hx.power_cycle()?;
+        Ok(())
+    }
+
+    fn resolution_counts(&self) -> Option<u32> {
+        Some(crate::hx711::NOISE_COUNTS)
+    }
+
+    fn raw_range(&self) -> Option<std::ops::RangeInclusive<i32>> {
+        Some(crate::hx711::RAW_RANGE)
+    }
+}
+
+/// Step/dir motor driver on character-device GPIO lines.
+pub struct GpiodMotor {
+    dir: LineHandle,
+    en: Option<LineHandle>,
+    polarity: MotorPolarity,
+    shared: Arc<StepperShared>,
+    watchdog: Watchdog,
+    handle: Option<JoinHandle<()>>,
+    stats: Arc<StepStats>,
+}
+
+impl GpiodMotor {
+    /// Claim STEP, DIR and optional EN on `chip`; the driver starts disabled.
+    pub fn try_new(
+        chip: &str,
+        step: u8,
+        dir: u8,
+        en: Option<u8>,
+        polarity: MotorPolarity,
+    ) -> HwResult<Self> {
+        let mut chip = open_chip(chip)?;
+        let step = request_output(&mut chip, step, false, "STEP")?;
+        let dir = request_output(&mut chip, dir, polarity.dir_high(false), "DIR")?;
+        let en = en
+            .map(|pin| request_output(&mut chip, pin, polarity.enable_high(false), "EN"))
+            .transpose()?;
+
+        let shared = Arc::new(StepperShared::new());
+        let shared_bg = shared.clone();
+        let stats = Arc::new(StepStats::new());
+        let stats_bg = stats.clone();
+        let handle = thread::spawn(move || {
+            let clock = MonotonicClock::new();
+            let mut pacer = Pacer::new();
+            let sleeper = RealSleeper;
+            loop {
+                // See `stepper` for the ordering protocol (model-checked with loom).
+                let period_us = match shared_bg.next() {
+                    StepCmd::Shutdown => break,
+                    StepCmd::Idle => {
+                        clock.sleep(Duration::from_millis(2));
+                        pacer.reset();
+                        continue;
+                    }
+                    StepCmd::Step { period_us } => period_us,
+                };
+                let _ = step.set_value(1);
+                crate::util::busy_wait_min_1us();
+                let avg = pacer.step_with(&sleeper, period_us, || {
+                    let _ = step.set_value(0);
+                    crate::util::busy_wait_min_1us();
+                });
+                stats_bg.record(&pacer, avg);
+            }
+        });
+
+        Ok(Self {
+            dir,
+            en,
+            polarity,
+            shared,
+            watchdog: Watchdog::default(),
+            handle: Some(handle),
+            stats,
+        })
+    }
+
+    /// Set direction: true = clockwise (reverse), false = counterclockwise (dispense).
+    pub fn set_direction(&mut self, clockwise: bool) -> HwResult<()> {
+        set(&self.dir, self.polarity.dir_high(clockwise))
+    }
+
+    /// Enable or disable the driver, if an EN line is configured.
+    pub fn set_enabled(&mut self, enabled: bool) -> HwResult<()> {
+        match &self.en {
+            Some(en) => set(en, self.polarity.enable_high(enabled)),
+            None => Ok(()),
+        }
+    }
+
+    /// Average step jitter in microseconds over the last window (approximate).
+    pub fn avg_jitter_us(&self) -> u32 {
+        self.stats.avg_jitter_us()
+    }
+}
+
+impl Drop for GpiodMotor {
+    fn drop(&mut self) {
+        self.shared.request_shutdown();
+        if let Some(h) = self.handle.take() {
+            let _ = h.join();
+        }
+        let _ = self.set_enabled(false);
+    }
+}
+
+impl Motor for GpiodMotor {
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.set_enabled(true)?;
+        self.shared.start();
+        info!("motor started");
+        Ok(())
+    }
+
+    fn set_speed(&mut self, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let clamped = sps.min(MAX_SPS);
+        if clamped == 0 {
+            warn!("requested 0 sps; motor will idle");
+        }
+        self.shared.set_sps(clamped);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.shared.stop();
+        info!("motor stopped");
+        Ok(())
+    }
+
+    /// Reverse by flipping DIR and stepping for `steps / sps` seconds (±1 period).
+    fn reverse(&mut self, steps: u32, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let sps = sps.clamp(1, MAX_SPS);
+        self.shared.stop();
+        self.set_direction(true)?;
+        self.set_enabled(true)?;
+        self.shared.set_sps(sps);
+        self.shared.start();
+        thread::sleep(Duration::from_micros(
+            u64::from(steps) * 1_000_000 / u64::from(sps),
+        ));
+        self.shared.stop();
+        self.set_direction(false)?;
+        info!(steps, sps, "motor reversed");
+        Ok(())
+    }
+
+    fn disable(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.shared.stop();
+        self.set_enabled(false)?;
+        info!("motor driver disabled");
+        Ok(())
+    }
+
+    fn enable(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.set_enabled(true)?;
+        info!("motor driver enabled");
+        Ok(())
+    }
+
+    /// Stop stepping and drop EN in one go, without logging in between.
+    fn halt(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.shared.stop();
+        let disabled = self.set_enabled(false);
+        warn!("motor halted");
+        Ok(disabled?)
+    }
+
+    fn stop_latch(&self) -> Option<doser_traits::StopLatch> {
+        Some(self.shared.latch())
+    }
+
+    fn check_alive(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(self.watchdog.check(&self.shared)?)
+    }
+
+    fn diagnostics(&self) -> Option<Arc<dyn MotorDiagnostics>> {
+        Some(self.stats.clone())
+    }
+}
+
+/// Binary output (valve, gate relay) on a character-device GPIO line.
+pub struct GpiodActuator {
+    line: LineHandle,
+    active_low: bool,
+}
+
+impl GpiodActuator {
+    /// Claim `line` as an output, initially inactive.
+    pub fn try_new(chip: &str, line: u8, active_low: bool) -> HwResult<Self> {
+        let mut chip = open_chip(chip)?;
+        let line = request_output(&mut chip, line, active_low, "actuator")?;
+        Ok(Self { line, active_low })
+    }
+}
+
+impl Actuator for GpiodActuator {
+    fn set_active(&mut self, active: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(set(&self.line, active != self.active_low)?)
+    }
+}
+
+/// E-stop checker polling a character-device GPIO line; see the rppal
+/// `make_estop_checker` for the thread lifetime. Needs an external pull-up.
+pub fn make_estop_checker(
+    chip: &str,
+    line: u8,
+    active_low: bool,
+    poll_ms: u64,
+) -> HwResult<Box<dyn Fn() -> bool + Send + Sync>> {
+    let mut chip = open_chip(chip)?;
+    let input = request_input(&mut chip, line, "E-STOP")?;
+    let flag = Arc::new(AtomicBool::new(false));
+    let flag_weak: Weak<AtomicBool> = Arc::downgrade(&flag);
+    thread::spawn(move || {
+        let clock = MonotonicClock::new();
+        while let Some(flag) = flag_weak.upgrade() {
+            // A failed read is treated as active (fail-safe).
+            let active = input.get_value().map_or(true, |v| (v == 0) == active_low);
+            flag.store(active, Ordering::Release);
+            drop(flag); // release the strong ref before sleeping
+            clock.sleep(Duration::from_millis(poll_ms.max(1)));
+        }
+        tracing::trace!("E-stop checker thread exiting (checker dropped)");
+    });
+    Ok(Box::new(move || flag.load(Ordering::Acquire)))
+}
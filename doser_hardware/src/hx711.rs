@@ -1,13 +1,53 @@
 use std::time::Duration;
 use tracing::trace;
 
-use crate::error::Result;
+use crate::error::{HwError, Result};
 use crate::util::{busy_wait_min_1us, wait_until_low_with_timeout};
 use doser_traits::clock::MonotonicClock;
 
-pub struct Hx711 {
-    dt: rppal::gpio::InputPin,
-    sck: rppal::gpio::OutputPin,
+/// GPIO access the HX711 protocol needs, so the driver is shared by the GPIO
+/// backends (rppal, gpiod).
+pub trait Hx711Pins {
+    /// Level of the DT (data) line.
+    fn dt_is_high(&self) -> Result<bool>;
+    /// Drive the SCK (clock) line.
+    fn set_sck(&mut self, high: bool) -> Result<()>;
+}
+
+#[cfg(feature = "hardware")]
+pub struct RppalPins {
+    pub dt: rppal::gpio::InputPin,
+    pub sck: rppal::gpio::OutputPin,
+}
+
+#[cfg(feature = "hardware")]
+impl Hx711Pins for RppalPins {
+    fn dt_is_high(&self) -> Result<bool> {
+        Ok(self.dt.is_high())
+    }
+
+    fn set_sck(&mut self, high: bool) -> Result<()> {
+        if high {
+            self.sck.set_high();
+        } else {
+            self.sck.set_low();
+        }
+        Ok(())
+    }
+}
+
+/// Input-referred noise at gain 128 and 10 SPS (50 nV rms per the datasheet)
+/// in counts: the step a single reading resolves.
+pub const NOISE_COUNTS: u32 = 20;
+
+/// Counts of a valid conversion. The 24-bit two's-complement rails,
+/// 0x7FFFFF and 0x800000, are the chip's saturation codes for an input
+/// beyond full scale (a broken or overloaded cell), not weights, so both are
+/// excluded.
+pub const RAW_RANGE: std::ops::RangeInclusive<i32> = -0x7F_FFFF..=0x7F_FFFE;
+
+pub struct Hx711<P> {
+    pins: P,
     // Extra SCK pulses sent after the 24 data bits; they select the next
     // conversion's gain/channel: 1 = ch A/gain 128, 2 = ch B/gain 32,
     // 3 = ch A/gain 64 (i.e. 25, 26, or 27 total pulses per read).
@@ -15,22 +55,28 @@ pub struct Hx711 {
     data_ready_timeout: Duration,
 }
 
-impl Hx711 {
-    pub fn new(
-        dt_pin: rppal::gpio::InputPin,
-        mut sck_pin: rppal::gpio::OutputPin,
-        gain_pulses: u8,
-        data_ready_timeout: Duration,
-    ) -> Result<Self> {
-        sck_pin.set_low(); // clock idle low
+impl<P: Hx711Pins> Hx711<P> {
+    pub fn new(mut pins: P, gain_pulses: u8, data_ready_timeout: Duration) -> Result<Self> {
+        pins.set_sck(false)?; // clock idle low
         Ok(Self {
-            dt: dt_pin,
-            sck: sck_pin,
+            pins,
             gain_pulses,
             data_ready_timeout,
         })
     }
 
+    /// Power-cycle the chip: holding SCK high for more than 60 µs powers it
+    /// down; taking SCK low powers it back up (channel A, gain 128, as after
+    /// power-on). The next [`read_with_timeout`](Self::read_with_timeout)
+    /// re-applies `gain_pulses`.
+    pub fn power_cycle(&mut self) -> Result<()> {
+        self.pins.set_sck(true)?;
+        std::thread::sleep(Duration::from_micros(100));
+        self.pins.set_sck(false)?;
+        trace!("hx711 power cycled");
+        Ok(())
+    }
+
     pub fn read_with_timeout(&mut self, timeout: Duration) -> Result<i32> {
         // Use the smaller of the per-call timeout and configured data-ready timeout
         let eff = if timeout < self.data_ready_timeout {
@@ -39,23 +85,29 @@ impl Hx711 {
             self.data_ready_timeout
         };
 
-        // Wait for data ready (DT goes low) with micro-sleeps
+        // Wait for data ready (DT goes low) with micro-sleeps. A failed line read
+        // counts as not ready. The error names the limit that expired: the
+        // caller's read timeout, or the configured data-ready timeout.
         let clock = MonotonicClock::new();
         wait_until_low_with_timeout(
-            || self.dt.is_high(),
+            || self.pins.dt_is_high().unwrap_or(true),
             eff,
             Duration::from_micros(200),
             &clock,
-        )?;
+        )
+        .map_err(|e| match e {
+            HwError::DataReadyTimeout if timeout < self.data_ready_timeout => HwError::Timeout,
+            other => other,
+        })?;
 
         // Clock out 24 bits. The HX711 requires SCK high/low times ≥ ~0.2µs and
         // samples DT while SCK is high, so each edge is followed by a ~1µs busy-wait.
         let mut value: i32 = 0;
         for _ in 0..24 {
-            self.sck.set_high();
+            self.pins.set_sck(true)?;
             busy_wait_min_1us();
-            value = (value << 1) | i32::from(self.dt.is_high());
-            self.sck.set_low();
+            value = (value << 1) | i32::from(self.pins.dt_is_high()?);
+            self.pins.set_sck(false)?;
             busy_wait_min_1us();
         }
 
@@ -63,9 +115,9 @@ impl Hx711 {
         // above this gives 24 + gain_pulses = 25/26/27 total, selecting the
         // gain/channel for the next conversion.
         for _ in 0..self.gain_pulses {
-            self.sck.set_high();
+            self.pins.set_sck(true)?;
             busy_wait_min_1us();
-            self.sck.set_low();
+            self.pins.set_sck(false)?;
             busy_wait_min_1us();
         }
 
@@ -77,3 +129,44 @@ impl Hx711 {
         Ok(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// DT shifting out one 24-bit code, MSB first, on each SCK rising edge.
+    struct CodePins {
+        code: u32,
+        bit: u32,
+    }
+
+    impl Hx711Pins for CodePins {
+        fn dt_is_high(&self) -> Result<bool> {
+            // Data ready (low) until the first clock; then the current bit.
+            Ok(self.bit > 0 && self.bit <= 24 && (self.code >> (24 - self.bit)) & 1 == 1)
+        }
+
+        fn set_sck(&mut self, high: bool) -> Result<()> {
+            if high {
+                self.bit += 1;
+            }
+            Ok(())
+        }
+    }
+
+    fn read(code: u32) -> i32 {
+        let mut hx = Hx711::new(CodePins { code, bit: 0 }, 1, Duration::from_millis(10)).unwrap();
+        hx.read_with_timeout(Duration::from_millis(10)).unwrap()
+    }
+
+    #[test]
+    fn saturation_codes_are_outside_the_raw_range() {
+        assert_eq!(read(0x7F_FFFF), 0x7F_FFFF);
+        assert_eq!(read(0x80_0000), -0x80_0000);
+        assert!(!RAW_RANGE.contains(&read(0x7F_FFFF)));
+        assert!(!RAW_RANGE.contains(&read(0x80_0000)));
+        for code in [0x7F_FFFE, 0x80_0001, 0, 0xFF_FFFF] {
+            assert!(RAW_RANGE.contains(&read(code)), "{code:#x}");
+        }
+    }
+}
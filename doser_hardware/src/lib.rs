@@ -9,7 +9,12 @@
 //!
 //! Features:
 //! - `hardware`: enable Raspberry Pi GPIO/HX711-backed implementations.
-//! - (default) no `hardware` feature: use simulation types that satisfy the traits.
+//! - (default) no `hardware` feature: use simulation types that satisfy the traits
+//!   (the simulation is compiled into every build).
+//! - `gpiod`: GPIO character-device backend for non-Raspberry Pi boards.
+//! - `plugin`: load out-of-tree backends from shared libraries (see `plugin`).
+//!
+//! Which compiled-in driver runs is chosen by name at runtime (see `registry`).
 //!
 //! Note: The `rppal` dependency is optional and only enabled when the `hardware`
 //!       feature is active. This lets CI on x86 build without pulling GPIO libs.
@@ -18,22 +23,39 @@
 //! - Where `unsafe` is required (GPIO, libc), calls are isolated with explicit
 //!   invariants and error paths. RT elevation is feature-gated and optional.
 
+pub mod composite;
 pub mod error;
+pub mod lock;
+pub mod mcp23017;
+pub mod plugin;
+pub mod polarity;
+pub mod registry;
+pub mod retry;
+#[cfg(unix)]
+pub mod socket;
+pub mod step_stats;
+pub mod stepper;
 pub mod util;
 
-// Make the HX711 driver module available when hardware feature is enabled on Linux.
-#[cfg(all(feature = "hardware", target_os = "linux"))]
+/// This crate's version.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Make the HX711 driver module available when a GPIO backend is enabled on Linux.
+#[cfg(all(any(feature = "hardware", feature = "gpiod"), target_os = "linux"))]
 mod hx711;
 
-// Provide the simulation backend when hardware is disabled OR when not on Linux.
-// This ensures cross-platform builds work even if the `hardware` feature is toggled on.
-#[cfg(any(not(feature = "hardware"), not(target_os = "linux")))]
+// Character-device (libgpiod) backend for non-Raspberry Pi boards.
+#[cfg(all(feature = "gpiod", target_os = "linux"))]
+pub mod gpiod;
+
+// The simulation backend is in every build, so a GPIO build can still run
+// simulated (`--backend sim`) for training and demos.
 pub mod sim {
-    use doser_traits::{Motor, Scale};
+    use doser_traits::{Actuator, Motor, MotorDiagnostics, Scale};
     use std::error::Error;
-    use std::sync::Arc;
     use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-    use std::time::Duration;
+    use std::sync::{Arc, Mutex, PoisonError};
+    use std::time::{Duration, Instant};
 
     /// State shared by a linked simulated scale and motor, so the scale's reading
     /// responds to the motor running. Each linked pair owns its own state, which
@@ -43,19 +65,213 @@ pub mod sim {
     struct SimState {
         running: AtomicBool,
         sps: AtomicU32,
+        /// Fastest rate the rotor can follow (0 = unlimited)
+        max_follow_sps: AtomicU32,
+        /// Load torque as `f32` bits, as a fraction of holding torque
+        load: AtomicU32,
+        /// Holds the motor stopped when tripped (see `Motor::stop_latch`)
+        latch: doser_traits::StopLatch,
+        /// Steps issued up to the latest motor command, and when it came
+        steps: Mutex<(u64, Option<Instant>)>,
     }
 
     impl SimState {
         fn shared() -> Arc<Self> {
             Arc::new(Self::default())
         }
+
+        /// Commanded to run and not held by the stop latch.
+        fn stepping(&self) -> bool {
+            self.running.load(Ordering::Acquire) && !self.latch.is_tripped()
+        }
+
+        /// Steps issued since `since` at the current commanded rate.
+        fn steps_since(&self, since: Option<Instant>, now: Instant) -> u64 {
+            match since {
+                Some(t) if self.stepping() => {
+                    let sps = self.sps.load(Ordering::Acquire);
+                    (now.duration_since(t).as_secs_f64() * f64::from(sps)) as u64
+                }
+                _ => 0,
+            }
+        }
+
+        /// Bank the steps of the segment ending now; called before every
+        /// command that may change the rate.
+        fn bank_steps(&self) {
+            let now = Instant::now();
+            let mut steps = self.steps.lock().unwrap_or_else(PoisonError::into_inner);
+            steps.0 += self.steps_since(steps.1, now);
+            steps.1 = Some(now);
+        }
+
+        /// Steps per second that actually turn the rotor at a commanded `sps`.
+        ///
+        /// Available torque falls linearly from holding torque at standstill to
+        /// zero at `max_follow_sps`; the rotor stalls once the load reaches it.
+        /// Below a stall, steps faster than `max_follow_sps` are missed.
+        fn effective_sps(&self, sps: u32) -> u32 {
+            let max = self.max_follow_sps.load(Ordering::Acquire);
+            let load = f32::from_bits(self.load.load(Ordering::Acquire));
+            let available = if max == 0 {
+                1.0
+            } else {
+                (1.0 - sps as f32 / max as f32).max(0.0)
+            };
+            if load > 0.0 && load >= available {
+                0
+            } else if max == 0 {
+                sps
+            } else {
+                sps.min(max)
+            }
+        }
+    }
+
+    /// Steps are counted at the commanded rate while stepping; the sim has no
+    /// pacing thread, so jitter and missed periods are zero.
+    impl MotorDiagnostics for SimState {
+        fn steps_issued(&self) -> u64 {
+            let steps = self.steps.lock().unwrap_or_else(PoisonError::into_inner);
+            steps.0 + self.steps_since(steps.1, Instant::now())
+        }
+
+        fn avg_jitter_us(&self) -> u32 {
+            0
+        }
+
+        fn max_jitter_us(&self) -> u32 {
+            0
+        }
+
+        fn missed_periods(&self) -> u64 {
+            0
+        }
+    }
+
+    /// Handle to a simulated motor's mechanical limits, adjustable while the
+    /// motor is owned by a controller. The default motor is ideal: no missed
+    /// steps and no load.
+    #[derive(Debug, Clone)]
+    pub struct SimMechanics(Arc<SimState>);
+
+    impl SimMechanics {
+        /// Fastest step rate the rotor can follow; `None` = unlimited.
+        pub fn set_max_follow_sps(&self, sps: Option<u32>) {
+            self.0
+                .max_follow_sps
+                .store(sps.unwrap_or(0), Ordering::Release);
+        }
+
+        /// Load torque as a fraction of holding torque (0 = free, >= 1 = jammed).
+        pub fn set_load(&self, load: f32) {
+            self.0
+                .load
+                .store(load.max(0.0).to_bits(), Ordering::Release);
+        }
+
+        /// Steps per second that move material at the commanded `sps`.
+        pub fn effective_sps(&self, sps: u32) -> u32 {
+            self.0.effective_sps(sps)
+        }
+
+        /// True while the motor runs but the rotor does not turn.
+        pub fn is_stalled(&self) -> bool {
+            let sps = self.0.sps.load(Ordering::Acquire);
+            self.0.stepping() && sps > 0 && self.effective_sps(sps) == 0
+        }
+
+        /// Apply `DOSER_TEST_SIM_MAX_SPS` / `DOSER_TEST_SIM_LOAD` when set.
+        fn apply_env(&self) {
+            if let Some(max) = std::env::var("DOSER_TEST_SIM_MAX_SPS")
+                .ok()
+                .and_then(|s| s.parse::<u32>().ok())
+            {
+                self.set_max_follow_sps(Some(max));
+            }
+            if let Some(load) = std::env::var("DOSER_TEST_SIM_LOAD")
+                .ok()
+                .and_then(|s| s.parse::<f32>().ok())
+            {
+                self.set_load(load);
+            }
+        }
+    }
+
+    /// Slow error sources of a real load cell, for soak tests: baseline (zero)
+    /// drift, a temperature-like swing of the baseline, and creep, where the
+    /// reading keeps rising by a fraction of a constant load. All zero = ideal.
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    pub struct SimDrift {
+        /// Baseline drift in grams per minute
+        pub zero_g_per_min: f32,
+        /// Amplitude of the temperature-like baseline swing, in grams
+        pub thermal_g: f32,
+        /// Period of the swing in seconds (<= 0 disables it)
+        pub thermal_period_s: f32,
+        /// Fraction of the load the reading gains through creep
+        pub creep_fraction: f32,
+        /// Creep time constant in seconds (<= 0 = immediate)
+        pub creep_tau_s: f32,
+    }
+
+    impl SimDrift {
+        /// From `DOSER_TEST_SIM_ZERO_DRIFT` (g/min), `DOSER_TEST_SIM_THERMAL_G`
+        /// (10 min period) and `DOSER_TEST_SIM_CREEP` (fraction, 30 s time constant).
+        pub fn from_env() -> Self {
+            let var = |k: &str| {
+                std::env::var(k)
+                    .ok()
+                    .and_then(|s| s.parse::<f32>().ok())
+                    .unwrap_or(0.0)
+            };
+            Self {
+                zero_g_per_min: var("DOSER_TEST_SIM_ZERO_DRIFT"),
+                thermal_g: var("DOSER_TEST_SIM_THERMAL_G"),
+                thermal_period_s: 600.0,
+                creep_fraction: var("DOSER_TEST_SIM_CREEP"),
+                creep_tau_s: 30.0,
+            }
+        }
+
+        /// Baseline offset `t_s` seconds into the simulation.
+        pub fn baseline_g(&self, t_s: f32) -> f32 {
+            let swing = if self.thermal_period_s > 0.0 {
+                self.thermal_g * (std::f32::consts::TAU * t_s / self.thermal_period_s).sin()
+            } else {
+                0.0
+            };
+            self.zero_g_per_min * t_s / 60.0 + swing
+        }
+
+        /// Creep `dt_s` seconds after it was `creep_g`, under `load_g`: an
+        /// exponential approach to `creep_fraction * load_g`.
+        pub fn creep_g(&self, creep_g: f32, load_g: f32, dt_s: f32) -> f32 {
+            let target = self.creep_fraction * load_g;
+            if self.creep_tau_s <= 0.0 {
+                return target;
+            }
+            creep_g + (target - creep_g) * (1.0 - (-dt_s / self.creep_tau_s).exp())
+        }
+    }
+
+    /// Start of simulated time for baseline drift, shared by every simulated
+    /// scale in the process so drift carries over when drivers are reopened.
+    fn sim_epoch() -> std::time::Instant {
+        static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+        *EPOCH.get_or_init(std::time::Instant::now)
     }
 
     /// Minimal simulated scale that increments by an optional env-configured delta
-    /// (`DOSER_TEST_SIM_INC`) on each read while the linked motor is running.
+    /// (`DOSER_TEST_SIM_INC`) on each read while the linked motor is running,
+    /// scaled down by the share of steps the motor actually takes (see
+    /// [`SimMechanics`]). Readings include any [`SimDrift`].
     pub struct SimulatedScale {
         grams: f32,
         state: Arc<SimState>,
+        drift: SimDrift,
+        creep_g: f32,
+        last_read: Option<std::time::Instant>,
     }
 
     impl Default for SimulatedScale {
@@ -68,14 +284,38 @@ pub mod sim {
         /// Create an unlinked scale (no motor coupling). Use [`sim_pair`] to link a
         /// scale and motor so the reading responds to the motor running.
         pub fn new() -> Self {
+            Self::with_state(SimState::shared())
+        }
+
+        fn with_state(state: Arc<SimState>) -> Self {
             Self {
                 grams: 0.0,
-                state: SimState::shared(),
+                state,
+                drift: SimDrift::default(),
+                creep_g: 0.0,
+                last_read: None,
             }
         }
 
-        fn with_state(state: Arc<SimState>) -> Self {
-            Self { grams: 0.0, state }
+        /// Add slow drift and creep to the readings.
+        pub fn with_drift(mut self, drift: SimDrift) -> Self {
+            self.drift = drift;
+            self
+        }
+
+        /// Apply drift and creep to the dispensed weight.
+        fn drifted_g(&mut self) -> f32 {
+            if self.drift == SimDrift::default() {
+                return self.grams;
+            }
+            let now = std::time::Instant::now();
+            let dt_s = self
+                .last_read
+                .map_or(0.0, |t| now.duration_since(t).as_secs_f32());
+            self.last_read = Some(now);
+            self.creep_g = self.drift.creep_g(self.creep_g, self.grams, dt_s);
+            let t_s = now.duration_since(sim_epoch()).as_secs_f32();
+            self.grams + self.creep_g + self.drift.baseline_g(t_s)
         }
     }
 
@@ -96,17 +336,37 @@ pub mod sim {
                 let err = std::io::Error::new(std::io::ErrorKind::TimedOut, "timeout");
                 return Err(Box::new(err));
             }
+            // Optional: simulate an ADC that never signals data-ready.
+            if std::env::var_os("DOSER_TEST_SIM_DRDY_TIMEOUT").is_some_and(|v| v != "0") {
+                std::thread::sleep(_timeout.min(Duration::from_millis(10)));
+                return Err(Box::new(crate::error::HwError::DataReadyTimeout));
+            }
             let delta = std::env::var("DOSER_TEST_SIM_INC")
                 .ok()
                 .and_then(|s| s.parse::<f32>().ok())
                 .unwrap_or(0.0);
-            if self.state.running.load(Ordering::Acquire) && delta != 0.0 {
-                let _sps = self.state.sps.load(Ordering::Acquire);
-                // Keep it simple for now: one delta per read while running
-                self.grams = (self.grams + delta).max(0.0);
+            if self.state.stepping() && delta != 0.0 {
+                // One delta per read at full speed, less for missed steps.
+                let sps = self.state.sps.load(Ordering::Acquire);
+                let share = if sps == 0 {
+                    1.0
+                } else {
+                    self.state.effective_sps(sps) as f32 / sps as f32
+                };
+                self.grams = (self.grams + delta * share).max(0.0);
             }
             // For the sim, return raw counts with 0.01 g resolution (centigrams)
-            Ok((self.grams * 100.0) as i32)
+            Ok((self.drifted_g() * 100.0) as i32)
+        }
+
+        /// Nothing to reset in the sim; re-init always succeeds.
+        fn reinit(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            Ok(())
+        }
+
+        /// Noise-free: one count (0.01 g).
+        fn resolution_counts(&self) -> Option<u32> {
+            Some(1)
         }
     }
 
@@ -125,54 +385,162 @@ pub mod sim {
         fn with_state(state: Arc<SimState>) -> Self {
             Self { state }
         }
+
+        /// Handle to this motor's mechanical limits (missed steps, load).
+        pub fn mechanics(&self) -> SimMechanics {
+            SimMechanics(Arc::clone(&self.state))
+        }
     }
 
     impl Motor for SimulatedMotor {
         fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.state.bank_steps();
             self.state.running.store(true, Ordering::Release);
             Ok(())
         }
 
         fn set_speed(&mut self, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.state.bank_steps();
             self.state.sps.store(sps, Ordering::Release);
             Ok(())
         }
 
         fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.state.bank_steps();
             self.state.sps.store(0, Ordering::Release);
             self.state.running.store(false, Ordering::Release);
             Ok(())
         }
+
+        /// The simulated scale only models dispensing, so a reversal is a no-op
+        /// that leaves the motor stopped.
+        fn reverse(&mut self, _steps: u32, _sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.stop()
+        }
+
+        /// The sim has no driver to de-energize: a halt is an immediate stop.
+        fn halt(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            tracing::debug!("simulated motor halted");
+            self.stop()
+        }
+
+        fn stop_latch(&self) -> Option<doser_traits::StopLatch> {
+            Some(self.state.latch.clone())
+        }
+
+        fn diagnostics(&self) -> Option<Arc<dyn MotorDiagnostics>> {
+            Some(self.state.clone())
+        }
+    }
+
+    /// Simulated binary output; records its state and logs transitions.
+    #[derive(Debug, Default)]
+    pub struct SimulatedActuator {
+        active: bool,
+    }
+
+    impl SimulatedActuator {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn is_active(&self) -> bool {
+            self.active
+        }
+    }
+
+    impl Actuator for SimulatedActuator {
+        fn set_active(&mut self, active: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.active = active;
+            tracing::debug!(active, "simulated actuator set");
+            Ok(())
+        }
+    }
+
+    /// Test-controlled binary input (E-stop, interlock switch). Clones share
+    /// state, so a test keeps one handle and gives the controller
+    /// [`SimInput::checker`].
+    #[derive(Debug, Clone, Default)]
+    pub struct SimInput {
+        active: Arc<AtomicBool>,
+    }
+
+    impl SimInput {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn set_active(&self, active: bool) {
+            self.active.store(active, Ordering::Release);
+        }
+
+        pub fn is_active(&self) -> bool {
+            self.active.load(Ordering::Acquire)
+        }
+
+        /// Polling closure in the shape of the hardware E-stop checkers.
+        pub fn checker(&self) -> Box<dyn Fn() -> bool + Send + Sync> {
+            let active = Arc::clone(&self.active);
+            Box::new(move || active.load(Ordering::Acquire))
+        }
+    }
+
+    /// E-stop checker driven by environment toggles, so CLI-level tests can
+    /// exercise the E-stop path off-hardware:
+    /// - `DOSER_TEST_SIM_ESTOP_FILE=<path>`: active while `<path>` exists
+    /// - `DOSER_TEST_SIM_ESTOP_AFTER_MS=<ms>`: active from `<ms>` after this call
+    ///
+    /// Returns `None` when neither is set.
+    pub fn sim_estop_from_env() -> Option<Box<dyn Fn() -> bool + Send + Sync>> {
+        let file = std::env::var_os("DOSER_TEST_SIM_ESTOP_FILE").map(std::path::PathBuf::from);
+        let after = std::env::var("DOSER_TEST_SIM_ESTOP_AFTER_MS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Duration::from_millis);
+        if file.is_none() && after.is_none() {
+            return None;
+        }
+        let start = std::time::Instant::now();
+        Some(Box::new(move || {
+            file.as_deref().is_some_and(std::path::Path::exists)
+                || after.is_some_and(|d| start.elapsed() >= d)
+        }))
     }
 
     /// Create a linked simulated `(scale, motor)` pair that share state, so the
     /// scale's reading responds to the motor running. Each pair is independent,
-    /// keeping parallel simulations (e.g. tests) isolated.
+    /// keeping parallel simulations (e.g. tests) isolated. The motor starts
+    /// ideal unless `DOSER_TEST_SIM_MAX_SPS` / `DOSER_TEST_SIM_LOAD` are set, and
+    /// the scale drift-free unless the [`SimDrift::from_env`] variables are.
     pub fn sim_pair() -> (SimulatedScale, SimulatedMotor) {
         let state = SimState::shared();
+        SimMechanics(state.clone()).apply_env();
         (
-            SimulatedScale::with_state(state.clone()),
+            SimulatedScale::with_state(state.clone()).with_drift(SimDrift::from_env()),
             SimulatedMotor::with_state(state),
         )
     }
 }
 
-// Generic absolute-deadline pacer with pluggable sleeper for testability.
-// Expose publicly so other crates/binaries can reuse pacing on any platform.
+// Absolute-deadline pacing. `Pacer` and `Sleeper` live in `doser_traits::pacing`
+// and are re-exported here; this module adds the (optionally RT) real-time sleeper.
 pub mod pacing {
+    use doser_traits::clock::Clock;
     use std::time::{Duration, Instant};
 
-    #[allow(dead_code)]
-    pub trait Sleeper {
-        fn now(&self) -> Instant;
-        fn sleep_until(&self, deadline: Instant);
-    }
+    pub use doser_traits::pacing::{Pacer, Sleeper};
 
+    /// Real-time clock whose `sleep_until` uses `clock_nanosleep(TIMER_ABSTIME)`
+    /// when the `rt` feature is enabled on Linux, and `thread::sleep` otherwise.
+    #[derive(Debug, Default, Clone, Copy)]
     pub struct RealSleeper;
-    impl Sleeper for RealSleeper {
+    impl Clock for RealSleeper {
         fn now(&self) -> Instant {
             Instant::now()
         }
+        fn sleep(&self, d: Duration) {
+            Clock::sleep_until(self, Instant::now() + d);
+        }
         fn sleep_until(&self, deadline: Instant) {
             let now = Instant::now();
             if deadline <= now {
@@ -217,82 +585,6 @@ pub mod pacing {
         }
     }
 
-    /// Absolute-deadline pacer; measures jitter and exposes rolling average.
-    pub struct Pacer {
-        next_deadline: Instant,
-        initialized: bool,
-        jitter_accum_us: u128,
-        jitter_count: u32,
-        pub avg_jitter_us: u32,
-    }
-
-    impl Default for Pacer {
-        fn default() -> Self {
-            Self::new()
-        }
-    }
-
-    impl Pacer {
-        pub fn new() -> Self {
-            Self {
-                next_deadline: Instant::now(),
-                initialized: false,
-                jitter_accum_us: 0,
-                jitter_count: 0,
-                avg_jitter_us: 0,
-            }
-        }
-        pub fn reset(&mut self) {
-            self.initialized = false;
-        }
-
-        /// Run one full period paced by absolute deadlines, invoking `mid_hook` exactly at half period.
-        /// Returns Some(avg_jitter_us) whenever the internal window (256) completes.
-        pub fn step_with<F, S: Sleeper>(
-            &mut self,
-            sleeper: &S,
-            period_us: u64,
-            mut mid_hook: F,
-        ) -> Option<u32>
-        where
-            F: FnMut(),
-        {
-            let period = Duration::from_micros(period_us.max(1));
-            let half = period / 2;
-            if !self.initialized {
-                self.next_deadline = sleeper.now() + period;
-                self.initialized = true;
-            }
-            let mid = self.next_deadline - half;
-            sleeper.sleep_until(mid);
-            mid_hook();
-            sleeper.sleep_until(self.next_deadline);
-
-            let now = sleeper.now();
-            let jitter = if now >= self.next_deadline {
-                now - self.next_deadline
-            } else {
-                self.next_deadline - now
-            };
-            self.jitter_accum_us = self.jitter_accum_us.saturating_add(jitter.as_micros());
-            self.jitter_count = self.jitter_count.saturating_add(1);
-            self.next_deadline += period;
-            if self.jitter_count >= 256 {
-                let avg = (self.jitter_accum_us / (self.jitter_count as u128)) as u32;
-                self.avg_jitter_us = avg;
-                self.jitter_accum_us = 0;
-                self.jitter_count = 0;
-                Some(avg)
-            } else {
-                None
-            }
-        }
-
-        pub fn step<S: Sleeper>(&mut self, sleeper: &S, period_us: u64) -> Option<u32> {
-            self.step_with(sleeper, period_us, || {})
-        }
-    }
-
     /// Add a Duration to a timespec-like (sec, nsec) pair, normalizing nanoseconds and saturating seconds.
     #[cfg_attr(not(all(feature = "rt", target_os = "linux")), allow(dead_code))]
     #[inline]
@@ -317,36 +609,6 @@ pub mod pacing {
     mod tests {
         use super::*;
 
-        // Test-only fake sleeper that advances a virtual clock.
-        pub struct FakeSleeper {
-            origin: Instant,
-            offset: std::sync::Arc<std::sync::Mutex<Duration>>,
-        }
-        impl FakeSleeper {
-            pub fn new() -> Self {
-                Self {
-                    origin: Instant::now(),
-                    offset: std::sync::Arc::new(std::sync::Mutex::new(Duration::ZERO)),
-                }
-            }
-            pub fn elapsed(&self) -> Duration {
-                // Test helper: if mutex is poisoned, default to zero elapsed
-                self.offset.lock().map(|g| *g).unwrap_or(Duration::ZERO)
-            }
-        }
-        impl Sleeper for FakeSleeper {
-            fn now(&self) -> Instant {
-                let off = self.offset.lock().map(|g| *g).unwrap_or(Duration::ZERO);
-                self.origin + off
-            }
-            fn sleep_until(&self, deadline: Instant) {
-                if let Ok(mut off) = self.offset.lock() {
-                    let dur = deadline.saturating_duration_since(self.origin);
-                    *off = dur;
-                }
-            }
-        }
-
         #[test]
         fn add_no_carry() {
             let (s, ns) =
@@ -371,34 +633,24 @@ pub mod pacing {
             assert_eq!(s, i64::MAX);
             assert!(ns < 1_000_000_000);
         }
-
-        #[test]
-        fn no_drift_after_many_cycles_with_fake_sleep() {
-            let mut pacer = Pacer::new();
-            let sleeper = FakeSleeper::new();
-            let period_us = 1000u64;
-            for _ in 0..10_000u32 {
-                let _ = pacer.step(&sleeper, period_us);
-            }
-            let expected = Duration::from_micros(period_us * 10_000);
-            assert_eq!(sleeper.elapsed(), expected);
-        }
     }
 }
 
 #[cfg(all(feature = "hardware", target_os = "linux"))]
 pub mod hardware {
     use crate::error::{HwError, Result as HwResult};
-    use crate::hx711::Hx711;
+    use crate::hx711::{Hx711, RppalPins};
     use crate::pacing::{Pacer, RealSleeper};
+    use crate::polarity::MotorPolarity;
+    use crate::step_stats::StepStats;
+    use crate::stepper::{MAX_SPS, StepCmd, StepperShared, Watchdog};
     use doser_traits::clock::{Clock, MonotonicClock};
-    use doser_traits::{Motor, Scale};
+    use doser_traits::{Actuator, Motor, MotorDiagnostics, Scale};
     use rppal::gpio::{Gpio, OutputPin};
     use std::error::Error;
     use std::sync::{
         Arc,
-        atomic::{AtomicBool, AtomicU32, Ordering},
-        mpsc,
+        atomic::{AtomicBool, Ordering},
     };
     use std::thread::{self, JoinHandle};
     use std::time::Duration;
@@ -406,7 +658,7 @@ pub mod hardware {
 
     /// Hardware scale backed by HX711.
     pub struct HardwareScale {
-        hx: Hx711,
+        hx: Hx711<RppalPins>,
     }
 
     impl HardwareScale {
@@ -423,7 +675,7 @@ pub mod hardware {
                 .map_err(|e| HwError::Gpio(format!("get HX711 SCK pin: {e}")))?
                 .into_output_low();
             // Channel A / gain 128: 1 extra SCK pulse after the 24 data bits (25 total).
-            let hx = Hx711::new(dt, sck, 1, Duration::from_millis(150))?;
+            let hx = Hx711::new(RppalPins { dt, sck }, 1, Duration::from_millis(150))?;
             Ok(Self { hx })
         }
 
@@ -449,7 +701,7 @@ pub mod hardware {
                 data_ready_timeout_ms
             };
             // Channel A / gain 128: 1 extra SCK pulse after the 24 data bits (25 total).
-            let hx = Hx711::new(dt, sck, 1, Duration::from_millis(drt))?;
+            let hx = Hx711::new(RppalPins { dt, sck }, 1, Duration::from_millis(drt))?;
             Ok(Self { hx })
         }
 
@@ -468,18 +720,31 @@ pub mod hardware {
         fn read(&mut self, timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
             self.read_raw_timeout(timeout)
         }
+
+        fn reinit(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.hx.power_cycle()?;
+            Ok(())
+        }
+
+        fn resolution_counts(&self) -> Option<u32> {
+            Some(crate::hx711::NOISE_COUNTS)
+        }
+
+        fn raw_range(&self) -> Option<std::ops::RangeInclusive<i32>> {
+            Some(crate::hx711::RAW_RANGE)
+        }
     }
 
     /// Raspberry Pi step/dir motor driver with optional enable pin.
     pub struct HardwareMotor {
         dir: OutputPin,
         en: Option<OutputPin>,
-        running: Arc<AtomicBool>,
-        sps: Arc<AtomicU32>,
+        polarity: MotorPolarity,
+        shared: Arc<StepperShared>,
+        watchdog: Watchdog,
         handle: Option<JoinHandle<()>>,
-        shutdown_tx: mpsc::Sender<()>,
         // Expose rough jitter stat (average over last window) for observability
-        avg_jitter_us: Arc<AtomicU32>,
+        stats: Arc<StepStats>,
     }
 
     impl HardwareMotor {
@@ -494,6 +759,16 @@ pub mod hardware {
         /// Create a motor from GPIO pin numbers with an optional enable pin.
         /// Note: On A4988/DRV8825, EN is active-low (low = enabled). We default to disabled (high).
         pub fn try_new_with_en(step_pin: u8, dir_pin: u8, en_pin: Option<u8>) -> HwResult<Self> {
+            Self::try_new_with_polarity(step_pin, dir_pin, en_pin, MotorPolarity::default())
+        }
+
+        /// Like [`Self::try_new_with_en`], with DIR/EN levels mapped through `polarity`.
+        pub fn try_new_with_polarity(
+            step_pin: u8,
+            dir_pin: u8,
+            en_pin: Option<u8>,
+            polarity: MotorPolarity,
+        ) -> HwResult<Self> {
             let gpio = Gpio::new().map_err(|e| HwError::Gpio(format!("open GPIO: {e}")))?;
             let mut step = gpio
                 .get(step_pin)
@@ -501,27 +776,32 @@ pub mod hardware {
                 .into_output_low();
             let dir = gpio
                 .get(dir_pin)
-                .map_err(|e| HwError::Gpio(format!("get DIR pin: {e}")))?
-                .into_output_low();
+                .map_err(|e| HwError::Gpio(format!("get DIR pin: {e}")))?;
+            let dir = if polarity.dir_high(false) {
+                dir.into_output_high()
+            } else {
+                dir.into_output_low()
+            };
 
+            // Start disabled.
             let en = match en_pin {
-                Some(pin) => Some(
-                    gpio.get(pin)
-                        .map_err(|e| HwError::Gpio(format!("get EN pin: {e}")))?
-                        .into_output_high(),
-                ), // high = disabled
+                Some(pin) => {
+                    let en = gpio
+                        .get(pin)
+                        .map_err(|e| HwError::Gpio(format!("get EN pin: {e}")))?;
+                    Some(if polarity.enable_high(false) {
+                        en.into_output_high()
+                    } else {
+                        en.into_output_low()
+                    })
+                }
                 None => None,
             };
 
-            let running = Arc::new(AtomicBool::new(false));
-            let sps = Arc::new(AtomicU32::new(0));
-            let (shutdown_tx, shutdown_rx): (mpsc::Sender<()>, mpsc::Receiver<()>) =
-                mpsc::channel();
-
-            let running_bg = running.clone();
-            let sps_bg = sps.clone();
-            let avg_jitter_us = Arc::new(AtomicU32::new(0));
-            let avg_jitter_us_bg = avg_jitter_us.clone();
+            let shared = Arc::new(StepperShared::new());
+            let shared_bg = shared.clone();
+            let stats = Arc::new(StepStats::new());
+            let stats_bg = stats.clone();
             // Move STEP into the background thread; not used elsewhere.
             let handle = thread::spawn(move || {
                 let clock = MonotonicClock::new();
@@ -535,81 +815,83 @@ pub mod hardware {
                 let sleeper = RealSleeper;
 
                 loop {
-                    if shutdown_rx.try_recv().is_ok() {
-                        break;
-                    }
-
-                    // Acquire pairs with the Release stores in start/stop/set_speed so the
-                    // stepping thread promptly observes commanded state changes.
-                    let is_running = running_bg.load(Ordering::Acquire);
-                    let sps_val = sps_bg.load(Ordering::Acquire).clamp(0, 5_000);
-                    if !(is_running && sps_val > 0) {
-                        clock.sleep(Duration::from_millis(2));
-                        pacer.reset();
-                        continue;
-                    }
-
-                    let period_us = (1_000_000u32 / sps_val).max(1) as u64; // us
+                    // See `stepper` for the ordering protocol (model-checked with loom).
+                    let period_us = match shared_bg.next() {
+                        StepCmd::Shutdown => break,
+                        StepCmd::Idle => {
+                            clock.sleep(Duration::from_millis(2));
+                            pacer.reset();
+                            continue;
+                        }
+                        StepCmd::Step { period_us } => period_us,
+                    };
                     // Rising edge
                     let _ = step.set_high();
                     spin_delay_min();
                     crate::util::busy_wait_min_1us();
                     // High hold until mid, then fall and hold until end
-                    if let Some(avg) = pacer.step_with(&sleeper, period_us, || {
+                    let avg = pacer.step_with(&sleeper, period_us, || {
                         let _ = step.set_low();
                         spin_delay_min();
                         crate::util::busy_wait_min_1us();
-                    }) {
-                        avg_jitter_us_bg.store(avg, Ordering::Relaxed);
-                    }
+                    });
+                    stats_bg.record(&pacer, avg);
                 }
             });
 
             let mut motor = Self {
                 dir,
                 en,
-                running,
-                sps,
+                polarity,
+                shared,
+                watchdog: Watchdog::default(),
                 handle: Some(handle),
-                shutdown_tx,
-                avg_jitter_us,
+                stats,
             };
             // Default: disabled
             let _ = motor.set_enabled(false);
             Ok(motor)
         }
 
-        /// Set direction: true = clockwise (DIR high), false = counterclockwise (DIR low)
+        /// Set direction: true = clockwise (DIR high), false = counterclockwise (DIR low),
+        /// both swapped when the direction is inverted. Dispensing is counterclockwise.
         pub fn set_direction(&mut self, clockwise: bool) {
-            if clockwise {
+            if self.polarity.dir_high(clockwise) {
                 let _ = self.dir.set_high();
             } else {
                 let _ = self.dir.set_low();
             }
         }
 
-        /// Enable or disable the driver (active-low enable pin, if present)
+        /// Enable or disable the driver (active-low enable pin unless inverted, if present)
         pub fn set_enabled(&mut self, enabled: bool) -> HwResult<()> {
+            let high = self.polarity.enable_high(enabled);
             if let Some(en) = self.en.as_mut() {
-                if enabled {
-                    en.set_low();
-                } else {
+                if high {
                     en.set_high();
+                } else {
+                    en.set_low();
                 }
             }
             Ok(())
         }
 
+        /// Signed speed: positive dispenses, negative runs in reverse, 0 idles.
+        /// Direction follows the configured polarity.
+        pub fn set_velocity(&mut self, sps: i32) {
+            self.set_direction(sps < 0);
+            self.set_speed_sps(sps.unsigned_abs().min(MAX_SPS));
+        }
+
         /// Set speed in steps-per-second; worker thread reads this atomically.
         pub fn set_speed_sps(&mut self, sps: u32) {
-            self.sps.store(sps, Ordering::Release);
+            self.shared.set_sps(sps);
         }
     }
 
     impl Drop for HardwareMotor {
         fn drop(&mut self) {
-            let _ = self.shutdown_tx.send(());
-            self.running.store(false, Ordering::Release);
+            self.shared.request_shutdown();
             if let Some(h) = self.handle.take() {
                 let _ = h.join();
             }
@@ -622,13 +904,13 @@ pub mod hardware {
         fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
             self.set_enabled(true)
                 .map_err(|e| Box::<dyn Error + Send + Sync>::from(e))?;
-            self.running.store(true, Ordering::Release);
+            self.shared.start();
             info!("motor started");
             Ok(())
         }
 
         fn set_speed(&mut self, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
-            let clamped = sps.clamp(0, 5_000);
+            let clamped = sps.min(MAX_SPS);
             if clamped == 0 {
                 warn!("requested 0 sps; motor will idle");
             }
@@ -637,17 +919,70 @@ pub mod hardware {
         }
 
         fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
-            self.running.store(false, Ordering::Release);
-            self.set_speed_sps(0);
+            self.shared.stop();
             info!("motor stopped");
             Ok(())
         }
+
+        /// Reverse by flipping DIR and stepping for `steps / sps` seconds. The
+        /// worker has no step counter, so the count is approximate (±1 period).
+        fn reverse(&mut self, steps: u32, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+            let sps = sps.clamp(1, MAX_SPS);
+            self.shared.stop();
+            self.set_direction(true);
+            self.set_enabled(true)
+                .map_err(|e| Box::<dyn Error + Send + Sync>::from(e))?;
+            self.shared.set_sps(sps);
+            self.shared.start();
+            thread::sleep(Duration::from_micros(
+                u64::from(steps) * 1_000_000 / u64::from(sps),
+            ));
+            self.shared.stop();
+            self.set_direction(false);
+            info!(steps, sps, "motor reversed");
+            Ok(())
+        }
+
+        fn disable(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.shared.stop();
+            self.set_enabled(false)
+                .map_err(|e| Box::<dyn Error + Send + Sync>::from(e))?;
+            info!("motor driver disabled");
+            Ok(())
+        }
+
+        fn enable(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.set_enabled(true)
+                .map_err(|e| Box::<dyn Error + Send + Sync>::from(e))?;
+            info!("motor driver enabled");
+            Ok(())
+        }
+
+        /// Stop stepping and drop EN in one go, without logging in between.
+        fn halt(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.shared.stop();
+            let disabled = self.set_enabled(false);
+            warn!("motor halted");
+            disabled.map_err(|e| Box::<dyn Error + Send + Sync>::from(e))
+        }
+
+        fn stop_latch(&self) -> Option<doser_traits::StopLatch> {
+            Some(self.shared.latch())
+        }
+
+        fn check_alive(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            Ok(self.watchdog.check(&self.shared)?)
+        }
+
+        fn diagnostics(&self) -> Option<Arc<dyn MotorDiagnostics>> {
+            Some(self.stats.clone())
+        }
     }
 
     /// Return average jitter in microseconds over the last window (approximate).
     impl HardwareMotor {
         pub fn avg_jitter_us(&self) -> u32 {
-            self.avg_jitter_us.load(Ordering::Relaxed)
+            self.stats.avg_jitter_us()
         }
     }
 
@@ -683,6 +1018,35 @@ pub mod hardware {
         Ok(())
     }
 
+    /// GPIO-driven binary output (solenoid valve, gate relay).
+    pub struct HardwareActuator {
+        pin: OutputPin,
+        active_low: bool,
+    }
+
+    impl HardwareActuator {
+        /// Claim `pin` as an output without changing its current level.
+        pub fn try_new(pin: u8, active_low: bool) -> HwResult<Self> {
+            let gpio = Gpio::new().map_err(|e| HwError::Gpio(format!("open GPIO: {e}")))?;
+            let pin = gpio
+                .get(pin)
+                .map_err(|e| HwError::Gpio(format!("get actuator pin: {e}")))?
+                .into_output();
+            Ok(Self { pin, active_low })
+        }
+    }
+
+    impl Actuator for HardwareActuator {
+        fn set_active(&mut self, active: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+            if active != self.active_low {
+                self.pin.set_high();
+            } else {
+                self.pin.set_low();
+            }
+            Ok(())
+        }
+    }
+
     /// E-stop checker: on ARM, read from a GPIO and expose as closure.
     pub fn make_estop_checker(
         pin: u8,
@@ -725,11 +1089,29 @@ pub mod hardware {
     }
 }
 
+pub use composite::CompositeScale;
+pub use lock::{BusyError, LockError, LockFile, Locked, chip_lock};
+pub use mcp23017::{ExpanderActuator, Mcp23017, RegisterBus, SharedExpander};
+#[cfg(feature = "plugin")]
+pub use plugin::PluginLibrary;
+pub use plugin::{PluginMotor, PluginScale};
+pub use polarity::MotorPolarity;
+pub use registry::Registry;
+pub use retry::{RetryStats, RetryingScale};
+#[cfg(unix)]
+pub use socket::SocketScale;
+pub use step_stats::StepStats;
+
 // Re-exports for callers (CLI/tests) to pick the right backend easily.
-#[cfg(any(not(feature = "hardware"), not(target_os = "linux")))]
-pub use sim::{SimulatedMotor, SimulatedScale, sim_pair};
+pub use sim::{
+    SimDrift, SimInput, SimMechanics, SimulatedActuator, SimulatedMotor, SimulatedScale,
+    sim_estop_from_env, sim_pair,
+};
 
 #[cfg(all(feature = "hardware", target_os = "linux"))]
-pub use hardware::{HardwareMotor, HardwareScale, make_estop_checker};
+pub use hardware::{HardwareActuator, HardwareMotor, HardwareScale, make_estop_checker};
+
+#[cfg(all(feature = "gpiod", target_os = "linux"))]
+pub use gpiod::{GpiodActuator, GpiodMotor, GpiodScale};
 
 // Note: end-to-end pacing behavior is covered in the pacing::tests module using FakeSleeper.
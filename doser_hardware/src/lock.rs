@@ -0,0 +1,213 @@
+//! Exclusive advisory lock files, so two processes never drive the same
+//! hardware at once.
+//!
+//! The lock is an OS file lock (`flock` on Unix), released when the
+//! [`LockFile`] is dropped or the process exits, even on a crash; a stale file
+//! left behind does not block the next run. The holder writes its PID into the
+//! file so a refused process can say who has the hardware.
+//!
+//! The GPIO drivers in the registry take [`chip_lock`] for their chip, shared
+//! by every driver of this process, so a second process fails on open with
+//! the holder's PID instead of halfway through pin setup.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+
+use doser_traits::{Motor, Scale};
+use thiserror::Error;
+
+/// Where chip locks live unless configured otherwise.
+pub const DEFAULT_LOCK_DIR: &str = "/run/doser";
+
+/// Another process holds the lock.
+#[derive(Debug, Error)]
+#[error("busy: {} is held by {}", path.display(), holder(*pid))]
+pub struct BusyError {
+    /// The lock file
+    pub path: PathBuf,
+    /// PID recorded by the holder, if it could be read
+    pub pid: Option<u32>,
+}
+
+fn holder(pid: Option<u32>) -> String {
+    pid.map_or_else(|| "another process".to_string(), |p| format!("pid {p}"))
+}
+
+#[derive(Debug, Error)]
+pub enum LockError {
+    #[error(transparent)]
+    Busy(#[from] BusyError),
+    #[error("lock file {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// An exclusive lock on a file, held until dropped.
+#[derive(Debug)]
+pub struct LockFile {
+    path: PathBuf,
+    _file: File,
+}
+
+impl LockFile {
+    /// Lock `path` (created if missing) without waiting; fails with
+    /// [`LockError::Busy`] while another process holds it.
+    pub fn acquire(path: impl AsRef<Path>) -> Result<Self, LockError> {
+        Self::lock(path.as_ref(), false)
+    }
+
+    /// Lock `path` (created if missing), waiting while another process holds
+    /// it; for short critical sections such as a store's read-modify-write.
+    pub fn acquire_wait(path: impl AsRef<Path>) -> Result<Self, LockError> {
+        Self::lock(path.as_ref(), true)
+    }
+
+    fn lock(path: &Path, wait: bool) -> Result<Self, LockError> {
+        let path = path.to_path_buf();
+        let io = |source| LockError::Io {
+            path: path.clone(),
+            source,
+        };
+        // No truncate: until the lock is ours the content is the holder's PID.
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(io)?;
+        let locked = if wait {
+            file.lock().map_err(TryLockError::Error)
+        } else {
+            file.try_lock()
+        };
+        match locked {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut text = String::new();
+                let pid = file
+                    .read_to_string(&mut text)
+                    .ok()
+                    .and_then(|_| text.trim().parse().ok());
+                return Err(BusyError { path, pid }.into());
+            }
+            Err(TryLockError::Error(e)) => return Err(io(e)),
+        }
+        file.set_len(0).map_err(io)?;
+        file.rewind().map_err(io)?;
+        writeln!(file, "{}", std::process::id()).map_err(io)?;
+        file.flush().map_err(io)?;
+        Ok(Self { path, _file: file })
+    }
+
+    /// The locked file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// The lock for GPIO `chip` (a device path such as `/dev/gpiochip0`, or just
+/// its name): `<dir>/<name>.lock`, with `dir` created if missing. Drivers of
+/// one process share the same lock; it is released when the last one drops.
+pub fn chip_lock(dir: &Path, chip: &str) -> Result<Arc<LockFile>, LockError> {
+    static HELD: Mutex<BTreeMap<PathBuf, Weak<LockFile>>> = Mutex::new(BTreeMap::new());
+
+    let name = Path::new(chip)
+        .file_name()
+        .map_or_else(|| chip.into(), |n| n.to_string_lossy());
+    let path = dir.join(format!("{name}.lock"));
+    let mut held = HELD
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let Some(lock) = held.get(&path).and_then(Weak::upgrade) {
+        return Ok(lock);
+    }
+    std::fs::create_dir_all(dir).map_err(|source| LockError::Io {
+        path: dir.to_path_buf(),
+        source,
+    })?;
+    let lock = Arc::new(LockFile::acquire(&path)?);
+    held.retain(|_, l| l.strong_count() > 0);
+    held.insert(path, Arc::downgrade(&lock));
+    Ok(lock)
+}
+
+/// A driver that keeps its chip lock for as long as it lives.
+#[derive(Debug)]
+pub struct Locked<T> {
+    inner: T,
+    _lock: Option<Arc<LockFile>>,
+}
+
+impl<T> Locked<T> {
+    /// Wrap `inner`; `lock` is `None` when no lock could be taken.
+    pub fn new(inner: T, lock: Option<Arc<LockFile>>) -> Self {
+        Self { inner, _lock: lock }
+    }
+}
+
+impl<T: Scale> Scale for Locked<T> {
+    fn read(
+        &mut self,
+        timeout: std::time::Duration,
+    ) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.read(timeout)
+    }
+
+    fn reinit(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.reinit()
+    }
+
+    fn resolution_counts(&self) -> Option<u32> {
+        self.inner.resolution_counts()
+    }
+
+    fn raw_range(&self) -> Option<std::ops::RangeInclusive<i32>> {
+        self.inner.raw_range()
+    }
+}
+
+impl<T: Motor> Motor for Locked<T> {
+    fn set_speed(
+        &mut self,
+        steps_per_sec: u32,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.set_speed(steps_per_sec)
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.stop()
+    }
+    fn start(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.start()
+    }
+    fn reverse(
+        &mut self,
+        steps: u32,
+        steps_per_sec: u32,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.reverse(steps, steps_per_sec)
+    }
+    fn disable(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.disable()
+    }
+    fn enable(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.enable()
+    }
+    fn halt(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.halt()
+    }
+    fn stop_latch(&self) -> Option<doser_traits::StopLatch> {
+        self.inner.stop_latch()
+    }
+    fn check_alive(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.check_alive()
+    }
+    fn diagnostics(&self) -> Option<std::sync::Arc<dyn doser_traits::MotorDiagnostics>> {
+        self.inner.diagnostics()
+    }
+}
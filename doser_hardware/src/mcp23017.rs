@@ -0,0 +1,178 @@
+//! MCP23017 16-bit I2C GPIO expander, for auxiliary outputs (valves, gates,
+//! indicators) once the header runs out of pins.
+//!
+//! Pins are numbered 0..=15: port A is 0..=7, port B is 8..=15. The driver
+//! keeps a shadow of the output latches, so setting one pin is a single
+//! register write that leaves the other pins untouched. Register access goes
+//! through [`RegisterBus`], implemented for the platform I2C device of the
+//! enabled backend (and by tests with an in-memory register file).
+//!
+//! Several outputs usually share one chip: wrap it in [`SharedExpander`] and
+//! hand each output an [`ExpanderActuator`].
+
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+use doser_traits::Actuator;
+
+use crate::error::{HwError, Result};
+
+/// IODIRA; IODIRB follows (IOCON.BANK = 0, the power-on layout).
+const IODIRA: u8 = 0x00;
+/// OLATA; OLATB follows.
+const OLATA: u8 = 0x14;
+
+/// Byte-register access to one I2C device.
+pub trait RegisterBus {
+    fn write_reg(&mut self, reg: u8, value: u8) -> Result<()>;
+}
+
+impl<T: RegisterBus + ?Sized> RegisterBus for Box<T> {
+    fn write_reg(&mut self, reg: u8, value: u8) -> Result<()> {
+        (**self).write_reg(reg, value)
+    }
+}
+
+/// MCP23017 driver. All pins stay inputs (the power-on state) until claimed
+/// with [`Mcp23017::configure_output`].
+pub struct Mcp23017<B> {
+    bus: B,
+    iodir: [u8; 2],
+    olat: [u8; 2],
+}
+
+/// One expander shared by several outputs.
+pub type SharedExpander = Arc<Mutex<Mcp23017<Box<dyn RegisterBus + Send>>>>;
+
+/// (port, bit mask) of `pin`; port 0 is A, 1 is B.
+fn port_bit(pin: u8) -> Result<(u8, u8)> {
+    if pin > 15 {
+        return Err(HwError::Gpio(format!(
+            "MCP23017 pin {pin} out of range 0..=15"
+        )));
+    }
+    Ok((pin / 8, 1 << (pin % 8)))
+}
+
+impl<B: RegisterBus> Mcp23017<B> {
+    /// Take over the chip: all latches low, all pins inputs.
+    pub fn new(mut bus: B) -> Result<Self> {
+        for port in 0..2 {
+            bus.write_reg(OLATA + port, 0)?;
+            bus.write_reg(IODIRA + port, 0xFF)?;
+        }
+        Ok(Self {
+            bus,
+            iodir: [0xFF; 2],
+            olat: [0; 2],
+        })
+    }
+
+    /// Make `pin` an output at level `high`. The latch is written before the
+    /// direction so the pin never glitches to the other level.
+    pub fn configure_output(&mut self, pin: u8, high: bool) -> Result<()> {
+        self.write_pin(pin, high)?;
+        let (port, bit) = port_bit(pin)?;
+        let iodir = &mut self.iodir[usize::from(port)];
+        *iodir &= !bit;
+        self.bus.write_reg(IODIRA + port, *iodir)
+    }
+
+    /// Drive `pin` high or low.
+    pub fn write_pin(&mut self, pin: u8, high: bool) -> Result<()> {
+        let (port, bit) = port_bit(pin)?;
+        let olat = &mut self.olat[usize::from(port)];
+        if high {
+            *olat |= bit;
+        } else {
+            *olat &= !bit;
+        }
+        self.bus.write_reg(OLATA + port, *olat)
+    }
+}
+
+/// A binary output on a shared expander pin.
+pub struct ExpanderActuator {
+    expander: SharedExpander,
+    pin: u8,
+    active_low: bool,
+}
+
+impl ExpanderActuator {
+    /// Claim `pin` as an output, initially inactive.
+    pub fn try_new(expander: SharedExpander, pin: u8, active_low: bool) -> Result<Self> {
+        expander
+            .lock()
+            .map_err(|_| HwError::Gpio("expander lock poisoned".into()))?
+            .configure_output(pin, active_low)?;
+        Ok(Self {
+            expander,
+            pin,
+            active_low,
+        })
+    }
+}
+
+impl Actuator for ExpanderActuator {
+    fn set_active(
+        &mut self,
+        active: bool,
+    ) -> std::result::Result<(), Box<dyn Error + Send + Sync>> {
+        self.expander
+            .lock()
+            .map_err(|_| HwError::Gpio("expander lock poisoned".into()))?
+            .write_pin(self.pin, active != self.active_low)?;
+        Ok(())
+    }
+}
+
+/// Open the MCP23017 at `address` on `/dev/i2c-<bus>` with the platform I2C
+/// driver of the enabled backend.
+#[cfg(all(any(feature = "hardware", feature = "gpiod"), target_os = "linux"))]
+pub fn open(bus: u8, address: u8) -> Result<SharedExpander> {
+    let dev = platform::open(bus, address)?;
+    Ok(Arc::new(Mutex::new(Mcp23017::new(dev)?)))
+}
+
+#[cfg(all(feature = "hardware", target_os = "linux"))]
+mod platform {
+    use super::RegisterBus;
+    use crate::error::{HwError, Result};
+    use rppal::i2c::I2c;
+
+    impl RegisterBus for I2c {
+        fn write_reg(&mut self, reg: u8, value: u8) -> Result<()> {
+            self.smbus_write_byte(reg, value)
+                .map_err(|e| HwError::Gpio(format!("MCP23017 write reg {reg:#04x}: {e}")))
+        }
+    }
+
+    pub(super) fn open(bus: u8, address: u8) -> Result<Box<dyn RegisterBus + Send>> {
+        let mut i2c =
+            I2c::with_bus(bus).map_err(|e| HwError::Gpio(format!("open I2C bus {bus}: {e}")))?;
+        i2c.set_slave_address(u16::from(address))
+            .map_err(|e| HwError::Gpio(format!("I2C address {address:#04x}: {e}")))?;
+        Ok(Box::new(i2c))
+    }
+}
+
+#[cfg(all(feature = "gpiod", not(feature = "hardware"), target_os = "linux"))]
+mod platform {
+    use super::RegisterBus;
+    use crate::error::{HwError, Result};
+    use i2cdev::core::I2CDevice;
+    use i2cdev::linux::LinuxI2CDevice;
+
+    impl RegisterBus for LinuxI2CDevice {
+        fn write_reg(&mut self, reg: u8, value: u8) -> Result<()> {
+            self.smbus_write_byte_data(reg, value)
+                .map_err(|e| HwError::Gpio(format!("MCP23017 write reg {reg:#04x}: {e}")))
+        }
+    }
+
+    pub(super) fn open(bus: u8, address: u8) -> Result<Box<dyn RegisterBus + Send>> {
+        let dev = LinuxI2CDevice::new(format!("/dev/i2c-{bus}"), u16::from(address))
+            .map_err(|e| HwError::Gpio(format!("open I2C bus {bus} @ {address:#04x}: {e}")))?;
+        Ok(Box::new(dev))
+    }
+}
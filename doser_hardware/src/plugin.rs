@@ -0,0 +1,301 @@
+//! Out-of-tree scale and motor backends, loaded from shared libraries.
+//!
+//! Rust trait objects have no stable ABI, so a plugin exposes C function
+//! tables instead. A plugin library exports:
+//!
+//! ```c
+//! uint32_t doser_plugin_abi_version(void);              // must return 1
+//! int32_t  doser_plugin_create_scale(const char *args, DoserScaleVTable *out);
+//! int32_t  doser_plugin_create_motor(const char *args, DoserMotorVTable *out);
+//! ```
+//!
+//! `args` is the NUL-terminated argument string from the config, passed
+//! through verbatim. A factory returns [`DOSER_OK`] after filling `out`, or
+//! any other status on failure (`out` is then ignored). Every call on a table
+//! returns [`DOSER_OK`], [`DOSER_ERR_TIMEOUT`] (a scale read that saw no
+//! data in time) or another non-zero status for a failure. `destroy` is
+//! called exactly once, when the backend is dropped.
+//!
+//! Objects are used from one thread at a time but may move between threads,
+//! and the library stays loaded while any of them is alive. The ABI types
+//! are always available so plugin crates can depend on this crate without
+//! features; only the loader ([`PluginLibrary`]) needs the `plugin` feature.
+
+use std::any::Any;
+use std::error::Error;
+use std::ffi::c_void;
+use std::sync::Arc;
+use std::time::Duration;
+
+use doser_traits::{Motor, Scale};
+
+use crate::error::HwError;
+
+/// ABI version implemented by this crate.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Call succeeded.
+pub const DOSER_OK: i32 = 0;
+/// Scale read timed out (mapped to [`HwError::Timeout`]).
+pub const DOSER_ERR_TIMEOUT: i32 = 1;
+
+/// Scale function table filled in by `doser_plugin_create_scale`.
+#[repr(C)]
+pub struct DoserScaleVTable {
+    /// Plugin-owned state passed to every call.
+    pub ctx: *mut c_void,
+    /// Read one raw sample into `out`, blocking up to `timeout_ms`.
+    pub read: unsafe extern "C" fn(ctx: *mut c_void, timeout_ms: u64, out: *mut i32) -> i32,
+    /// Re-initialize the sensor; null if unsupported.
+    pub reinit: Option<unsafe extern "C" fn(ctx: *mut c_void) -> i32>,
+    /// Free `ctx`.
+    pub destroy: unsafe extern "C" fn(ctx: *mut c_void),
+}
+
+/// Motor function table filled in by `doser_plugin_create_motor`.
+#[repr(C)]
+pub struct DoserMotorVTable {
+    /// Plugin-owned state passed to every call.
+    pub ctx: *mut c_void,
+    pub set_speed: unsafe extern "C" fn(ctx: *mut c_void, sps: u32) -> i32,
+    pub start: unsafe extern "C" fn(ctx: *mut c_void) -> i32,
+    pub stop: unsafe extern "C" fn(ctx: *mut c_void) -> i32,
+    /// Energize the driver; null falls back to the `Motor` default.
+    pub enable: Option<unsafe extern "C" fn(ctx: *mut c_void) -> i32>,
+    /// De-energize the driver; null falls back to the `Motor` default.
+    pub disable: Option<unsafe extern "C" fn(ctx: *mut c_void) -> i32>,
+    /// Free `ctx`.
+    pub destroy: unsafe extern "C" fn(ctx: *mut c_void),
+}
+
+/// Keeps the providing library loaded while a backend is alive.
+type KeepAlive = Option<Arc<dyn Any + Send + Sync>>;
+
+fn status(code: i32, what: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match code {
+        DOSER_OK => Ok(()),
+        DOSER_ERR_TIMEOUT => Err(Box::new(HwError::Timeout)),
+        other => Err(Box::new(HwError::Plugin(format!(
+            "{what} failed with status {other}"
+        )))),
+    }
+}
+
+/// [`Scale`] backed by a plugin function table.
+pub struct PluginScale {
+    vt: DoserScaleVTable,
+    _lib: KeepAlive,
+}
+
+impl PluginScale {
+    /// Wrap a filled-in table, e.g. from a statically linked plugin.
+    ///
+    /// # Safety
+    /// The table must follow the module-level contract: valid function
+    /// pointers, and a `ctx` they accept until `destroy`.
+    pub unsafe fn from_vtable(vt: DoserScaleVTable) -> Self {
+        Self { vt, _lib: None }
+    }
+}
+
+// SAFETY: the plugin contract allows moving objects between threads; `&mut self`
+// on every call keeps use single-threaded.
+unsafe impl Send for PluginScale {}
+
+impl Scale for PluginScale {
+    fn read(&mut self, timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        let ms = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+        let mut out = 0i32;
+        // SAFETY: per the contract; `out` outlives the call.
+        status(
+            unsafe { (self.vt.read)(self.vt.ctx, ms, &mut out) },
+            "scale read",
+        )?;
+        Ok(out)
+    }
+
+    fn reinit(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self.vt.reinit {
+            // SAFETY: per the contract.
+            Some(f) => status(unsafe { f(self.vt.ctx) }, "scale reinit"),
+            None => Err("re-init not supported by this scale plugin".into()),
+        }
+    }
+}
+
+impl Drop for PluginScale {
+    fn drop(&mut self) {
+        // SAFETY: called once; the library is still loaded (`_lib` drops after).
+        unsafe { (self.vt.destroy)(self.vt.ctx) }
+    }
+}
+
+/// [`Motor`] backed by a plugin function table.
+pub struct PluginMotor {
+    vt: DoserMotorVTable,
+    _lib: KeepAlive,
+}
+
+impl PluginMotor {
+    /// Wrap a filled-in table, e.g. from a statically linked plugin.
+    ///
+    /// # Safety
+    /// As for [`PluginScale::from_vtable`].
+    pub unsafe fn from_vtable(vt: DoserMotorVTable) -> Self {
+        Self { vt, _lib: None }
+    }
+}
+
+// SAFETY: as for `PluginScale`.
+unsafe impl Send for PluginMotor {}
+
+impl Motor for PluginMotor {
+    fn set_speed(&mut self, sps: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // SAFETY: per the contract.
+        status(
+            unsafe { (self.vt.set_speed)(self.vt.ctx, sps) },
+            "motor set_speed",
+        )
+    }
+
+    fn start(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // SAFETY: per the contract.
+        status(unsafe { (self.vt.start)(self.vt.ctx) }, "motor start")
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // SAFETY: per the contract.
+        status(unsafe { (self.vt.stop)(self.vt.ctx) }, "motor stop")
+    }
+
+    fn enable(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self.vt.enable {
+            // SAFETY: per the contract.
+            Some(f) => status(unsafe { f(self.vt.ctx) }, "motor enable"),
+            None => Ok(()),
+        }
+    }
+
+    fn disable(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self.vt.disable {
+            // SAFETY: per the contract.
+            Some(f) => status(unsafe { f(self.vt.ctx) }, "motor disable"),
+            None => self.stop(),
+        }
+    }
+}
+
+impl Drop for PluginMotor {
+    fn drop(&mut self) {
+        // SAFETY: called once; the library is still loaded (`_lib` drops after).
+        unsafe { (self.vt.destroy)(self.vt.ctx) }
+    }
+}
+
+#[cfg(feature = "plugin")]
+pub use loader::PluginLibrary;
+
+#[cfg(feature = "plugin")]
+mod loader {
+    use std::ffi::{CString, c_char};
+    use std::sync::Arc;
+
+    use libloading::{Library, Symbol};
+
+    use super::{
+        DOSER_OK, DoserMotorVTable, DoserScaleVTable, PLUGIN_ABI_VERSION, PluginMotor, PluginScale,
+    };
+    use crate::error::{HwError, Result};
+
+    type AbiVersionFn = unsafe extern "C" fn() -> u32;
+    type CreateScaleFn = unsafe extern "C" fn(*const c_char, *mut DoserScaleVTable) -> i32;
+    type CreateMotorFn = unsafe extern "C" fn(*const c_char, *mut DoserMotorVTable) -> i32;
+
+    fn err(msg: String) -> HwError {
+        HwError::Plugin(msg)
+    }
+
+    /// A loaded plugin library.
+    pub struct PluginLibrary {
+        lib: Arc<Library>,
+        path: String,
+    }
+
+    impl PluginLibrary {
+        /// Load `path` and check its ABI version.
+        ///
+        /// Loading runs the library's initializers: only load trusted files.
+        pub fn open(path: &str) -> Result<Self> {
+            // SAFETY: loading a library runs arbitrary code; the path comes
+            // from the operator's config, which is trusted like the binary.
+            let lib =
+                unsafe { Library::new(path) }.map_err(|e| err(format!("load {path}: {e}")))?;
+            // SAFETY: the symbol type matches the documented ABI.
+            let version = unsafe {
+                let f: Symbol<AbiVersionFn> = lib
+                    .get(b"doser_plugin_abi_version\0")
+                    .map_err(|e| err(format!("{path}: {e}")))?;
+                f()
+            };
+            if version != PLUGIN_ABI_VERSION {
+                return Err(err(format!(
+                    "{path} implements ABI v{version}, expected v{PLUGIN_ABI_VERSION}"
+                )));
+            }
+            tracing::info!(path, version, "plugin loaded");
+            Ok(Self {
+                lib: Arc::new(lib),
+                path: path.to_string(),
+            })
+        }
+
+        fn c_args(&self, args: &str) -> Result<CString> {
+            CString::new(args).map_err(|_| err(format!("{} args contain NUL", self.path)))
+        }
+
+        /// Create the plugin's scale with `args`.
+        pub fn create_scale(&self, args: &str) -> Result<PluginScale> {
+            let args = self.c_args(args)?;
+            let mut vt = std::mem::MaybeUninit::<DoserScaleVTable>::uninit();
+            // SAFETY: the symbol type matches the documented ABI; `vt` is only
+            // read after the factory reports success.
+            let vt = unsafe {
+                let f: Symbol<CreateScaleFn> = self
+                    .lib
+                    .get(b"doser_plugin_create_scale\0")
+                    .map_err(|e| err(format!("{}: {e}", self.path)))?;
+                let rc = f(args.as_ptr(), vt.as_mut_ptr());
+                if rc != DOSER_OK {
+                    return Err(err(format!("{} scale factory failed ({rc})", self.path)));
+                }
+                vt.assume_init()
+            };
+            Ok(PluginScale {
+                vt,
+                _lib: Some(self.lib.clone()),
+            })
+        }
+
+        /// Create the plugin's motor with `args`.
+        pub fn create_motor(&self, args: &str) -> Result<PluginMotor> {
+            let args = self.c_args(args)?;
+            let mut vt = std::mem::MaybeUninit::<DoserMotorVTable>::uninit();
+            // SAFETY: as in `create_scale`.
+            let vt = unsafe {
+                let f: Symbol<CreateMotorFn> = self
+                    .lib
+                    .get(b"doser_plugin_create_motor\0")
+                    .map_err(|e| err(format!("{}: {e}", self.path)))?;
+                let rc = f(args.as_ptr(), vt.as_mut_ptr());
+                if rc != DOSER_OK {
+                    return Err(err(format!("{} motor factory failed ({rc})", self.path)));
+                }
+                vt.assume_init()
+            };
+            Ok(PluginMotor {
+                vt,
+                _lib: Some(self.lib.clone()),
+            })
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! Direction and enable pin polarity for step/dir drivers.
+//!
+//! Wiring varies: a motor mounted the other way round dispenses with DIR high,
+//! and some drivers (e.g. TMC boards in standalone mode) use an active-high
+//! enable. [`MotorPolarity`] maps logical states to pin levels so these are
+//! configuration rather than code changes.
+
+/// Pin-level mapping for the DIR and EN outputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MotorPolarity {
+    /// Dispense with DIR high instead of low.
+    pub invert_direction: bool,
+    /// Drive EN high to enable instead of low (A4988/DRV8825 are active-low).
+    pub invert_enable: bool,
+}
+
+impl MotorPolarity {
+    /// DIR level for the given direction: `true` = high. Clockwise (reverse)
+    /// is DIR high unless inverted; dispensing is the other direction.
+    pub fn dir_high(self, clockwise: bool) -> bool {
+        clockwise ^ self.invert_direction
+    }
+
+    /// EN level for the given driver state: `true` = high.
+    pub fn enable_high(self, enabled: bool) -> bool {
+        !enabled ^ self.invert_enable
+    }
+}
@@ -0,0 +1,329 @@
+//! Backend registry: driver names mapped to constructors, so the CLI picks the
+//! scale and motor from config (`[scale] driver`, `[motor] driver`) instead of
+//! hard-coding one backend per feature set.
+//!
+//! [`Registry::builtin`] registers the drivers compiled into this build:
+//!
+//! | name          | kind  | available with                          |
+//! |---------------|-------|-----------------------------------------|
+//! | `hx711`       | scale | `hardware` (rppal) or `gpiod`           |
+//! | `composite`   | scale | as `hx711`; needs [`CompositeWiring`]   |
+//! | `gpio-thread` | motor | as `hx711`; step/dir stepping thread    |
+//! | `sim`         | both  | all builds                              |
+//! | `socket`      | scale | Unix; readings from [`Wiring::socket`]  |
+//!
+//! With both GPIO features enabled, the rppal backend wins (as elsewhere).
+//! Drivers that must share state, like the simulated scale and motor, register
+//! as a pair and are used when both names match. Callers can register more.
+
+use std::collections::BTreeMap;
+
+use doser_traits::{Motor, Scale};
+
+use crate::error::{HwError, Result};
+use crate::polarity::MotorPolarity;
+
+pub type BoxedScale = Box<dyn Scale + Send>;
+pub type BoxedMotor = Box<dyn Motor>;
+
+/// Builds a scale from the wiring.
+pub type ScaleCtor = fn(&Wiring) -> Result<BoxedScale>;
+/// Builds a motor from the wiring.
+pub type MotorCtor = fn(&Wiring) -> Result<BoxedMotor>;
+/// Builds a linked scale and motor.
+pub type PairCtor = fn(&Wiring) -> Result<(BoxedScale, BoxedMotor)>;
+
+/// Scale driver used when the config names none.
+pub const DEFAULT_SCALE: &str = if cfg!(all(
+    any(feature = "hardware", feature = "gpiod"),
+    target_os = "linux"
+)) {
+    "hx711"
+} else {
+    "sim"
+};
+
+/// Motor driver used when the config names none.
+pub const DEFAULT_MOTOR: &str = if cfg!(all(
+    any(feature = "hardware", feature = "gpiod"),
+    target_os = "linux"
+)) {
+    "gpio-thread"
+} else {
+    "sim"
+};
+
+/// Second load cell of the `composite` scale.
+#[derive(Debug, Clone, Copy)]
+pub struct CompositeWiring {
+    pub dt: u8,
+    pub sck: u8,
+    pub tolerance_counts: u32,
+    pub fault_after: u32,
+    pub weight: f32,
+}
+
+/// Everything a built-in driver may need, filled in from config.
+#[derive(Debug, Clone, Default)]
+pub struct Wiring {
+    /// GPIO character device (`gpiod` backend only)
+    pub chip: Option<String>,
+    pub hx711_dt: u8,
+    pub hx711_sck: u8,
+    /// HX711 data-ready timeout in ms (0 = driver default)
+    pub sensor_read_timeout_ms: u64,
+    pub composite: Option<CompositeWiring>,
+    pub motor_step: u8,
+    pub motor_dir: u8,
+    pub motor_en: Option<u8>,
+    pub polarity: MotorPolarity,
+    /// Directory for GPIO chip lock files (default [`crate::lock::DEFAULT_LOCK_DIR`])
+    pub lock_dir: Option<String>,
+    /// Unix socket the `socket` scale reads from
+    pub socket: Option<String>,
+}
+
+/// Driver names and their constructors.
+#[derive(Default)]
+pub struct Registry {
+    scales: BTreeMap<&'static str, ScaleCtor>,
+    motors: BTreeMap<&'static str, MotorCtor>,
+    pairs: BTreeMap<&'static str, PairCtor>,
+}
+
+impl Registry {
+    /// A registry without any drivers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The drivers compiled into this build.
+    pub fn builtin() -> Self {
+        let mut r = Self::new();
+        #[cfg(all(any(feature = "hardware", feature = "gpiod"), target_os = "linux"))]
+        r.register_scale("hx711", gpio::hx711)
+            .register_scale("composite", gpio::composite)
+            .register_motor("gpio-thread", gpio::stepper);
+        r.register_pair("sim", sim_pair);
+        #[cfg(unix)]
+        r.register_scale("socket", socket_scale);
+        r
+    }
+
+    pub fn register_scale(&mut self, name: &'static str, ctor: ScaleCtor) -> &mut Self {
+        self.scales.insert(name, ctor);
+        self
+    }
+
+    pub fn register_motor(&mut self, name: &'static str, ctor: MotorCtor) -> &mut Self {
+        self.motors.insert(name, ctor);
+        self
+    }
+
+    /// Register `name` as both a scale and a motor driver that, when chosen
+    /// together, are built by one constructor. Chosen alone, each half is still
+    /// available (unlinked from the other driver).
+    pub fn register_pair(&mut self, name: &'static str, ctor: PairCtor) -> &mut Self {
+        self.pairs.insert(name, ctor);
+        self
+    }
+
+    /// Registered scale driver names, sorted.
+    pub fn scale_drivers(&self) -> Vec<&'static str> {
+        let mut v: Vec<_> = self
+            .scales
+            .keys()
+            .chain(self.pairs.keys())
+            .copied()
+            .collect();
+        v.sort_unstable();
+        v.dedup();
+        v
+    }
+
+    /// Registered motor driver names, sorted.
+    pub fn motor_drivers(&self) -> Vec<&'static str> {
+        let mut v: Vec<_> = self
+            .motors
+            .keys()
+            .chain(self.pairs.keys())
+            .copied()
+            .collect();
+        v.sort_unstable();
+        v.dedup();
+        v
+    }
+
+    /// Build the named scale and motor.
+    pub fn open(
+        &self,
+        scale: &str,
+        motor: &str,
+        wiring: &Wiring,
+    ) -> Result<(BoxedScale, BoxedMotor)> {
+        if scale == motor
+            && let Some(ctor) = self.pairs.get(scale)
+        {
+            return ctor(wiring);
+        }
+        Ok((
+            self.open_scale(scale, wiring)?,
+            self.open_motor(motor, wiring)?,
+        ))
+    }
+
+    /// Build the named scale alone (e.g. an auxiliary hopper scale).
+    pub fn open_scale(&self, name: &str, wiring: &Wiring) -> Result<BoxedScale> {
+        if let Some(ctor) = self.scales.get(name) {
+            return ctor(wiring);
+        }
+        match self.pairs.get(name) {
+            Some(ctor) => Ok(ctor(wiring)?.0),
+            None => Err(unknown("scale", name, &self.scale_drivers())),
+        }
+    }
+
+    /// Build the named motor alone.
+    pub fn open_motor(&self, name: &str, wiring: &Wiring) -> Result<BoxedMotor> {
+        if let Some(ctor) = self.motors.get(name) {
+            return ctor(wiring);
+        }
+        match self.pairs.get(name) {
+            Some(ctor) => Ok(ctor(wiring)?.1),
+            None => Err(unknown("motor", name, &self.motor_drivers())),
+        }
+    }
+}
+
+fn unknown(kind: &'static str, name: &str, available: &[&str]) -> HwError {
+    HwError::UnknownDriver {
+        kind,
+        name: name.to_string(),
+        available: available.join(", "),
+    }
+}
+
+/// Default scale driver for a config with or without a second load cell.
+pub fn default_scale_driver(composite: bool) -> &'static str {
+    if composite && DEFAULT_SCALE == "hx711" {
+        "composite"
+    } else {
+        DEFAULT_SCALE
+    }
+}
+
+fn sim_pair(wiring: &Wiring) -> Result<(BoxedScale, BoxedMotor)> {
+    if wiring.composite.is_some() {
+        tracing::warn!("scale.composite is ignored by the simulation backend");
+    }
+    let (scale, motor) = crate::sim::sim_pair();
+    Ok((Box::new(scale), Box::new(motor)))
+}
+
+#[cfg(unix)]
+fn socket_scale(wiring: &Wiring) -> Result<BoxedScale> {
+    let Some(path) = &wiring.socket else {
+        return Err(HwError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "the socket scale needs a socket path ([scale] socket)",
+        )));
+    };
+    Ok(Box::new(crate::socket::SocketScale::connect(path)?))
+}
+
+#[cfg(all(any(feature = "hardware", feature = "gpiod"), target_os = "linux"))]
+mod gpio {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use super::{BoxedMotor, BoxedScale, Wiring};
+    use crate::composite::CompositeScale;
+    use crate::error::{HwError, Result};
+    use crate::lock::{DEFAULT_LOCK_DIR, LockError, LockFile, Locked, chip_lock};
+
+    /// Take the lock for the wired chip before touching any pin (rppal drives
+    /// the Pi's gpiochip0). Only another holder fails the open; a lock that
+    /// cannot be created (e.g. no permission for the directory) is skipped.
+    fn lock(w: &Wiring) -> Result<Option<Arc<LockFile>>> {
+        let dir = w.lock_dir.as_deref().unwrap_or(DEFAULT_LOCK_DIR);
+        let chip = w.chip.as_deref().unwrap_or("/dev/gpiochip0");
+        match chip_lock(Path::new(dir), chip) {
+            Ok(l) => Ok(Some(l)),
+            Err(LockError::Busy(b)) => Err(b.into()),
+            Err(e) => {
+                tracing::warn!(error = %e, "GPIO lock not taken; another process could open the same pins");
+                Ok(None)
+            }
+        }
+    }
+
+    /// rppal addresses BCM pins directly; a chip path means a gpiod config.
+    #[cfg(feature = "hardware")]
+    fn reject_chip(w: &Wiring) -> Result<()> {
+        match &w.chip {
+            Some(chip) => Err(HwError::Gpio(format!(
+                "pins.chip = {chip:?} is only used by the gpiod backend (build with --features gpiod)"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(feature = "hardware")]
+    fn open_hx711(w: &Wiring, dt: u8, sck: u8) -> Result<crate::HardwareScale> {
+        reject_chip(w)?;
+        crate::HardwareScale::try_new_with_timeout(dt, sck, w.sensor_read_timeout_ms)
+    }
+
+    #[cfg(not(feature = "hardware"))]
+    fn open_hx711(w: &Wiring, dt: u8, sck: u8) -> Result<crate::GpiodScale> {
+        let chip = w.chip.as_deref().unwrap_or("/dev/gpiochip0");
+        crate::GpiodScale::try_new(chip, dt, sck, w.sensor_read_timeout_ms)
+    }
+
+    pub(super) fn hx711(w: &Wiring) -> Result<BoxedScale> {
+        let lock = lock(w)?;
+        Ok(Box::new(Locked::new(
+            open_hx711(w, w.hx711_dt, w.hx711_sck)?,
+            lock,
+        )))
+    }
+
+    pub(super) fn composite(w: &Wiring) -> Result<BoxedScale> {
+        let c = w.composite.ok_or_else(|| {
+            HwError::Gpio("composite scale needs a second cell ([scale.composite])".into())
+        })?;
+        let lock = lock(w)?;
+        let primary = open_hx711(w, w.hx711_dt, w.hx711_sck)?;
+        let secondary = open_hx711(w, c.dt, c.sck)?;
+        Ok(Box::new(Locked::new(
+            CompositeScale::new(primary, secondary, c.tolerance_counts, c.fault_after)
+                .with_weight(c.weight),
+            lock,
+        )))
+    }
+
+    #[cfg(feature = "hardware")]
+    pub(super) fn stepper(w: &Wiring) -> Result<BoxedMotor> {
+        reject_chip(w)?;
+        let lock = lock(w)?;
+        Ok(Box::new(Locked::new(
+            crate::HardwareMotor::try_new_with_polarity(
+                w.motor_step,
+                w.motor_dir,
+                w.motor_en,
+                w.polarity,
+            )?,
+            lock,
+        )))
+    }
+
+    #[cfg(not(feature = "hardware"))]
+    pub(super) fn stepper(w: &Wiring) -> Result<BoxedMotor> {
+        let chip = w.chip.as_deref().unwrap_or("/dev/gpiochip0");
+        let lock = lock(w)?;
+        Ok(Box::new(Locked::new(
+            crate::GpiodMotor::try_new(chip, w.motor_step, w.motor_dir, w.motor_en, w.polarity)?,
+            lock,
+        )))
+    }
+}
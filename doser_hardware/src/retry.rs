@@ -0,0 +1,127 @@
+//! Bounded automatic re-initialization of a scale on transient read failures.
+//!
+//! [`RetryingScale`] wraps any [`Scale`]. When a read fails with a recoverable
+//! error (a data-ready or read timeout), it calls [`Scale::reinit`] (for the
+//! HX711: a power cycle), waits for the ADC to settle, and reads again, up to
+//! `max_attempts` times before surfacing the original error. Other errors pass
+//! straight through. Counters are shared through [`RetryStats`] so callers can
+//! report them after a run.
+//!
+//! A retry blocks the read for up to `max_attempts * (settle + timeout)`; keep
+//! that below the controller's stall watchdog (`timeouts.sample_ms`) or the
+//! dose will abort anyway.
+
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use doser_traits::Scale;
+
+use crate::error::HwError;
+
+/// Re-init counters shared between a [`RetryingScale`] and its observers.
+#[derive(Debug, Default)]
+pub struct RetryStats {
+    reinits: AtomicU32,
+    recovered: AtomicU32,
+    exhausted: AtomicU32,
+}
+
+impl RetryStats {
+    /// Re-initializations attempted.
+    pub fn reinits(&self) -> u32 {
+        self.reinits.load(Ordering::Relaxed)
+    }
+
+    /// Failed reads that succeeded after re-initialization.
+    pub fn recovered(&self) -> u32 {
+        self.recovered.load(Ordering::Relaxed)
+    }
+
+    /// Failed reads surfaced after all attempts were used.
+    pub fn exhausted(&self) -> u32 {
+        self.exhausted.load(Ordering::Relaxed)
+    }
+}
+
+/// True for failures a sensor re-init can plausibly fix.
+fn is_recoverable(e: &(dyn Error + Send + Sync + 'static)) -> bool {
+    if let Some(hw) = e.downcast_ref::<HwError>() {
+        return matches!(hw, HwError::Timeout | HwError::DataReadyTimeout);
+    }
+    e.downcast_ref::<std::io::Error>()
+        .is_some_and(|io| io.kind() == std::io::ErrorKind::TimedOut)
+}
+
+/// Scale wrapper that re-initializes the sensor on recoverable read failures.
+pub struct RetryingScale<S> {
+    inner: S,
+    max_attempts: u32,
+    settle: Duration,
+    stats: Arc<RetryStats>,
+}
+
+impl<S: Scale> RetryingScale<S> {
+    /// `max_attempts` re-inits per failed read (0 = pass-through); `settle` is
+    /// the wait after each re-init before reading again.
+    pub fn new(inner: S, max_attempts: u32, settle: Duration) -> Self {
+        Self {
+            inner,
+            max_attempts,
+            settle,
+            stats: Arc::default(),
+        }
+    }
+
+    /// Shared handle to the retry counters.
+    pub fn stats(&self) -> Arc<RetryStats> {
+        Arc::clone(&self.stats)
+    }
+}
+
+impl<S: Scale> Scale for RetryingScale<S> {
+    fn read(&mut self, timeout: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        let mut err = match self.inner.read(timeout) {
+            Ok(v) => return Ok(v),
+            Err(e) => e,
+        };
+        if self.max_attempts == 0 || !is_recoverable(err.as_ref()) {
+            return Err(err);
+        }
+        for attempt in 1..=self.max_attempts {
+            self.stats.reinits.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(error = %err, attempt, "scale read failed; re-initializing");
+            if let Err(e) = self.inner.reinit() {
+                tracing::error!(error = %e, "scale re-init failed");
+                break;
+            }
+            if !self.settle.is_zero() {
+                std::thread::sleep(self.settle);
+            }
+            match self.inner.read(timeout) {
+                Ok(v) => {
+                    self.stats.recovered.fetch_add(1, Ordering::Relaxed);
+                    tracing::info!(attempt, "scale recovered after re-init");
+                    return Ok(v);
+                }
+                Err(e) if is_recoverable(e.as_ref()) => err = e,
+                Err(e) => return Err(e),
+            }
+        }
+        self.stats.exhausted.fetch_add(1, Ordering::Relaxed);
+        Err(err)
+    }
+
+    fn reinit(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.inner.reinit()
+    }
+
+    fn resolution_counts(&self) -> Option<u32> {
+        self.inner.resolution_counts()
+    }
+
+    fn raw_range(&self) -> Option<std::ops::RangeInclusive<i32>> {
+        self.inner.raw_range()
+    }
+}
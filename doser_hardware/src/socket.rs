@@ -0,0 +1,77 @@
+//! `socket` scale: raw readings from another process over a Unix socket, so
+//! the stack can run without hardware across process boundaries (e.g. a UI
+//! under development against `doser sim-scale --serve`).
+//!
+//! The protocol is one reading per line, in decimal raw counts (`"1234\n"`).
+//! The server paces the readings; each [`Scale::read`] takes the next line.
+
+use std::error::Error;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+use doser_traits::Scale;
+
+use crate::error::{HwError, Result};
+
+/// Scale reading lines of raw counts from a Unix socket.
+pub struct SocketScale {
+    reader: BufReader<UnixStream>,
+    /// Partial line carried over a timed-out read.
+    line: String,
+}
+
+impl SocketScale {
+    pub fn connect(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let stream = UnixStream::connect(path).map_err(|e| {
+            HwError::Io(std::io::Error::new(
+                e.kind(),
+                format!("connect to scale socket {}: {e}", path.display()),
+            ))
+        })?;
+        Ok(Self {
+            reader: BufReader::new(stream),
+            line: String::new(),
+        })
+    }
+}
+
+impl Scale for SocketScale {
+    fn read(
+        &mut self,
+        timeout: Duration,
+    ) -> std::result::Result<i32, Box<dyn Error + Send + Sync>> {
+        // A zero timeout would mean "block forever" to the socket.
+        self.reader
+            .get_ref()
+            .set_read_timeout(Some(timeout.max(Duration::from_millis(1))))?;
+        match self.reader.read_line(&mut self.line) {
+            Ok(0) => Err(Box::new(HwError::Io(std::io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "scale socket closed by the server",
+            )))),
+            Ok(_) if !self.line.ends_with('\n') => Err(Box::new(HwError::Timeout)),
+            Ok(_) => {
+                let raw = self.line.trim().parse::<i32>().map_err(|e| {
+                    HwError::Io(std::io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("scale socket sent {:?}: {e}", self.line.trim()),
+                    ))
+                });
+                self.line.clear();
+                Ok(raw?)
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                Err(Box::new(HwError::Timeout))
+            }
+            Err(e) => Err(Box::new(HwError::Io(e))),
+        }
+    }
+}
+
+/// Send one reading in the [`SocketScale`] protocol.
+pub fn write_reading(w: &mut impl Write, raw: i32) -> std::io::Result<()> {
+    writeln!(w, "{raw}")
+}
@@ -0,0 +1,80 @@
+//! Stepping counters kept by the step/dir backends' stepping threads and read
+//! by supervisors through [`doser_traits::Motor::diagnostics`].
+
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use doser_traits::MotorDiagnostics;
+use doser_traits::pacing::Pacer;
+
+/// Counters updated by a stepping thread after every pulse.
+#[derive(Debug, Default)]
+pub struct StepStats {
+    steps: AtomicU64,
+    avg_jitter_us: AtomicU32,
+    max_jitter_us: AtomicU32,
+    missed_periods: AtomicU64,
+}
+
+impl StepStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count one pulse paced by `pacer`; `window_avg` is what its step
+    /// returned (a completed jitter window, if any).
+    pub fn record(&self, pacer: &Pacer, window_avg: Option<u32>) {
+        self.steps.fetch_add(1, Ordering::Relaxed);
+        self.max_jitter_us
+            .fetch_max(pacer.last_jitter_us(), Ordering::Relaxed);
+        self.missed_periods
+            .store(pacer.overruns(), Ordering::Relaxed);
+        if let Some(avg) = window_avg {
+            self.avg_jitter_us.store(avg, Ordering::Relaxed);
+        }
+    }
+}
+
+impl MotorDiagnostics for StepStats {
+    fn steps_issued(&self) -> u64 {
+        self.steps.load(Ordering::Relaxed)
+    }
+
+    fn avg_jitter_us(&self) -> u32 {
+        self.avg_jitter_us.load(Ordering::Relaxed)
+    }
+
+    fn max_jitter_us(&self) -> u32 {
+        self.max_jitter_us.load(Ordering::Relaxed)
+    }
+
+    fn missed_periods(&self) -> u64 {
+        self.missed_periods.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use doser_traits::clock::VirtualClock;
+    use std::time::Duration;
+
+    #[test]
+    fn counts_pulses_peak_jitter_and_missed_periods() {
+        let stats = StepStats::new();
+        let clock = VirtualClock::new();
+        let mut pacer = Pacer::new();
+        for _ in 0..3 {
+            let avg = pacer.step(&clock, 1000);
+            stats.record(&pacer, avg);
+        }
+        // A 5 ms stall: the next periods are already due.
+        clock.advance(Duration::from_millis(5));
+        let avg = pacer.step(&clock, 1000);
+        stats.record(&pacer, avg);
+
+        let snap = stats.snapshot();
+        assert_eq!(snap.steps_issued, 4);
+        assert_eq!(snap.missed_periods, 1);
+        assert!(snap.max_jitter_us > 0, "{snap:?}");
+    }
+}
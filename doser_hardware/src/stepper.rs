@@ -0,0 +1,340 @@
+//! Control-plane state shared between a motor handle and its stepping thread.
+//!
+//! The owner issues commands (`start`, `stop`, `set_sps`, `request_shutdown`)
+//! with Release stores; the stepping thread polls [`StepperShared::next`], which
+//! loads with Acquire, and acts on the returned [`StepCmd`]. Keeping the protocol
+//! free of GPIO lets it be model-checked with loom (`RUSTFLAGS="--cfg loom"`).
+//!
+//! A [`StopLatch`] tripped from any thread (the E-stop monitor) overrides the
+//! commanded state: the worker idles until the latch is reset.
+//!
+//! Every poll also advances a heartbeat counter. A [`Watchdog`] on the owner's
+//! side flags a worker that stopped polling (panicked, or blocked) while the
+//! motor is commanded to run, which would otherwise silently not step.
+
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use std::time::{Duration, Instant};
+
+use doser_traits::StopLatch;
+
+use crate::error::HwError;
+
+/// Upper bound on the commanded step rate (steps per second).
+pub const MAX_SPS: u32 = 5_000;
+
+/// Default heartbeat gap after which a running stepper counts as stalled;
+/// longer than the slowest step period (1 s at 1 sps).
+pub const STALL_AFTER: Duration = Duration::from_millis(1_500);
+
+/// What the stepping thread should do on its next iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepCmd {
+    /// Exit the thread loop.
+    Shutdown,
+    /// Not running (or zero speed): idle briefly and poll again.
+    Idle,
+    /// Emit one step pulse with the given period in microseconds.
+    Step { period_us: u64 },
+}
+
+/// Commanded state of a stepper, shared by `Arc` with its worker thread.
+#[derive(Debug)]
+pub struct StepperShared {
+    running: AtomicBool,
+    sps: AtomicU32,
+    shutdown: AtomicBool,
+    latch: StopLatch,
+    heartbeat: AtomicU32,
+}
+
+impl Default for StepperShared {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StepperShared {
+    pub fn new() -> Self {
+        Self {
+            running: AtomicBool::new(false),
+            sps: AtomicU32::new(0),
+            shutdown: AtomicBool::new(false),
+            latch: StopLatch::new(),
+            heartbeat: AtomicU32::new(0),
+        }
+    }
+
+    /// The latch that holds this stepper stopped when tripped.
+    pub fn latch(&self) -> StopLatch {
+        self.latch.clone()
+    }
+
+    /// Allow stepping at the current speed.
+    pub fn start(&self) {
+        self.running.store(true, Ordering::Release);
+    }
+
+    /// Stop stepping and zero the speed.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::Release);
+        self.sps.store(0, Ordering::Release);
+    }
+
+    /// Set the step rate; values above [`MAX_SPS`] are clamped.
+    pub fn set_sps(&self, sps: u32) {
+        self.sps.store(sps.min(MAX_SPS), Ordering::Release);
+    }
+
+    /// Ask the worker to exit. Also clears `running`, so a worker that observes
+    /// the shutdown can never be mid-way through a run that was not stopped.
+    pub fn request_shutdown(&self) {
+        self.running.store(false, Ordering::Release);
+        self.shutdown.store(true, Ordering::Release);
+    }
+
+    /// True when the motor is commanded to run.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Worker polls so far (wrapping); see [`Watchdog`].
+    pub fn heartbeat(&self) -> u32 {
+        self.heartbeat.load(Ordering::Relaxed)
+    }
+
+    /// Poll the commanded state; called once per iteration by the worker.
+    pub fn next(&self) -> StepCmd {
+        self.heartbeat.fetch_add(1, Ordering::Relaxed);
+        if self.shutdown.load(Ordering::Acquire) {
+            return StepCmd::Shutdown;
+        }
+        if self.latch.is_tripped() {
+            return StepCmd::Idle;
+        }
+        let running = self.running.load(Ordering::Acquire);
+        let sps = self.sps.load(Ordering::Acquire).min(MAX_SPS);
+        if !(running && sps > 0) {
+            return StepCmd::Idle;
+        }
+        StepCmd::Step {
+            period_us: u64::from((1_000_000 / sps).max(1)),
+        }
+    }
+}
+
+/// Owner-side liveness check of a stepping thread.
+#[derive(Debug)]
+pub struct Watchdog {
+    timeout: Duration,
+    last_beat: u32,
+    since: Instant,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self::new(STALL_AFTER)
+    }
+}
+
+impl Watchdog {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            last_beat: 0,
+            since: Instant::now(),
+        }
+    }
+
+    /// Err when `shared` is commanded to run but its heartbeat has not moved
+    /// for longer than the timeout. Time spent stopped does not count.
+    pub fn check(&mut self, shared: &StepperShared) -> Result<(), HwError> {
+        let beat = shared.heartbeat();
+        if beat != self.last_beat || !shared.is_running() {
+            self.last_beat = beat;
+            self.since = Instant::now();
+            return Ok(());
+        }
+        let quiet = self.since.elapsed();
+        if quiet > self.timeout {
+            return Err(HwError::StepperStalled {
+                ms: quiet.as_millis() as u64,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn idle_until_started_with_nonzero_speed() {
+        let s = StepperShared::new();
+        assert_eq!(s.next(), StepCmd::Idle);
+        s.set_sps(1000);
+        assert_eq!(s.next(), StepCmd::Idle);
+        s.start();
+        assert_eq!(s.next(), StepCmd::Step { period_us: 1000 });
+        s.stop();
+        assert_eq!(s.next(), StepCmd::Idle);
+    }
+
+    #[test]
+    fn tripped_latch_holds_the_stepper_idle() {
+        let s = StepperShared::new();
+        s.set_sps(1000);
+        s.start();
+        let latch = s.latch();
+        latch.trip();
+        assert_eq!(s.next(), StepCmd::Idle);
+        s.start();
+        assert_eq!(
+            s.next(),
+            StepCmd::Idle,
+            "the owner cannot override the latch"
+        );
+        latch.reset();
+        assert_eq!(s.next(), StepCmd::Step { period_us: 1000 });
+    }
+
+    #[test]
+    fn watchdog_flags_a_running_stepper_whose_worker_stopped_polling() {
+        let s = StepperShared::new();
+        let mut dog = Watchdog::new(Duration::from_millis(20));
+        s.set_sps(1000);
+        // Stopped: no worker polling is fine.
+        thread::sleep(Duration::from_millis(30));
+        dog.check(&s).unwrap();
+
+        s.start();
+        s.next();
+        dog.check(&s).unwrap();
+        thread::sleep(Duration::from_millis(30));
+        assert!(matches!(
+            dog.check(&s),
+            Err(HwError::StepperStalled { ms }) if ms >= 20
+        ));
+
+        // A poll clears it.
+        s.next();
+        dog.check(&s).unwrap();
+    }
+
+    #[test]
+    fn speed_is_clamped() {
+        let s = StepperShared::new();
+        s.set_sps(u32::MAX);
+        s.start();
+        assert_eq!(s.next(), StepCmd::Step { period_us: 200 });
+    }
+
+    #[test]
+    fn shutdown_wins_and_clears_running() {
+        let s = StepperShared::new();
+        s.set_sps(100);
+        s.start();
+        s.request_shutdown();
+        assert_eq!(s.next(), StepCmd::Shutdown);
+        assert!(!s.is_running());
+    }
+
+    #[test]
+    fn worker_exits_after_shutdown() {
+        let s = Arc::new(StepperShared::new());
+        let w = s.clone();
+        let h = thread::spawn(move || {
+            while w.next() != StepCmd::Shutdown {
+                thread::yield_now();
+            }
+        });
+        s.start();
+        s.set_sps(500);
+        s.request_shutdown();
+        h.join().unwrap();
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::*;
+    use loom::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn speed_published_before_start_is_visible_when_stepping() {
+        loom::model(|| {
+            let s = Arc::new(StepperShared::new());
+            let w = s.clone();
+            let worker = thread::spawn(move || w.next());
+            s.set_sps(100);
+            s.start();
+            match worker.join().unwrap() {
+                StepCmd::Step { period_us } => assert_eq!(period_us, 10_000),
+                StepCmd::Idle => {}
+                StepCmd::Shutdown => panic!("shutdown was never requested"),
+            }
+        });
+    }
+
+    #[test]
+    fn no_lost_stop_on_shutdown() {
+        loom::model(|| {
+            let s = Arc::new(StepperShared::new());
+            s.set_sps(100);
+            s.start();
+            let w = s.clone();
+            let worker = thread::spawn(move || {
+                let mut pulses = 0;
+                loop {
+                    match w.next() {
+                        StepCmd::Shutdown => {
+                            // Shutdown is published after running=false.
+                            assert!(!w.is_running());
+                            return pulses;
+                        }
+                        StepCmd::Step { .. } => pulses += 1,
+                        StepCmd::Idle => {}
+                    }
+                    thread::yield_now();
+                }
+            });
+            s.request_shutdown();
+            // Join must return: the worker always observes the shutdown.
+            let _pulses = worker.join().unwrap();
+            assert_eq!(s.next(), StepCmd::Shutdown);
+        });
+    }
+
+    #[test]
+    fn stop_then_shutdown_never_steps_after_observing_stop() {
+        loom::model(|| {
+            let s = Arc::new(StepperShared::new());
+            s.set_sps(100);
+            s.start();
+            let w = s.clone();
+            let worker = thread::spawn(move || {
+                let mut seen_idle = false;
+                loop {
+                    match w.next() {
+                        StepCmd::Shutdown => return,
+                        StepCmd::Idle => seen_idle = true,
+                        StepCmd::Step { .. } => {
+                            assert!(!seen_idle, "stepped again after observing stop");
+                        }
+                    }
+                    thread::yield_now();
+                }
+            });
+            s.stop();
+            s.request_shutdown();
+            worker.join().unwrap();
+        });
+    }
+}
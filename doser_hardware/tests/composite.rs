@@ -0,0 +1,73 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::time::Duration;
+
+use doser_hardware::CompositeScale;
+use doser_hardware::error::HwError;
+use doser_traits::Scale;
+
+struct Seq(VecDeque<i32>);
+impl Scale for Seq {
+    fn read(&mut self, _t: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        Ok(self.0.pop_front().unwrap_or(0))
+    }
+}
+
+fn seq(v: &[i32]) -> Seq {
+    Seq(v.iter().copied().collect())
+}
+
+const T: Duration = Duration::from_millis(10);
+
+#[test]
+fn averages_agreeing_cells_with_weight() {
+    let mut s = CompositeScale::new(seq(&[1000, 2000]), seq(&[1010, 1990]), 50, 3);
+    assert_eq!(s.read(T).unwrap(), 1005);
+    let mut s = s.with_weight(0.75);
+    assert_eq!(s.read(T).unwrap(), 1998); // 0.75*2000 + 0.25*1990 = 1997.5
+}
+
+#[test]
+fn faults_only_on_persistent_disagreement() {
+    let a = seq(&[1000, 1000, 1000, 1000, 1000, 1000]);
+    let b = seq(&[1200, 1200, 1000, 1200, 1200, 1200]);
+    let mut s = CompositeScale::new(a, b, 50, 3);
+
+    // Two disagreements are tolerated (and averaged), then agreement resets.
+    assert_eq!(s.read(T).unwrap(), 1100);
+    assert_eq!(s.read(T).unwrap(), 1100);
+    assert_eq!(s.disagree_streak(), 2);
+    assert_eq!(s.read(T).unwrap(), 1000);
+    assert_eq!(s.disagree_streak(), 0);
+
+    assert!(s.read(T).is_ok());
+    assert!(s.read(T).is_ok());
+    let err = s.read(T).unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref::<HwError>(),
+            Some(HwError::CellDisagreement { a: 1000, b: 1200 })
+        ),
+        "{err}"
+    );
+}
+
+/// Scale that only reports a resolution.
+struct Res(Option<u32>);
+impl Scale for Res {
+    fn read(&mut self, _t: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        Ok(0)
+    }
+    fn resolution_counts(&self) -> Option<u32> {
+        self.0
+    }
+}
+
+#[test]
+fn reports_the_coarser_cell_resolution() {
+    let s = CompositeScale::new(Res(Some(20)), Res(Some(35)), 50, 3);
+    assert_eq!(s.resolution_counts(), Some(35));
+    let s = CompositeScale::new(Res(Some(20)), Res(None), 50, 3);
+    assert_eq!(s.resolution_counts(), None);
+    assert_eq!(seq(&[]).resolution_counts(), None);
+}
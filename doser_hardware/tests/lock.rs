@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use doser_hardware::{LockError, LockFile, chip_lock};
+
+#[test]
+fn second_lock_is_busy_and_names_the_holder() {
+    let path = std::env::temp_dir().join(format!("doser-lock-test-{}.lock", std::process::id()));
+    let held = LockFile::acquire(&path).unwrap();
+    assert_eq!(held.path(), path);
+
+    match LockFile::acquire(&path) {
+        Err(LockError::Busy(b)) => {
+            assert_eq!(b.pid, Some(std::process::id()));
+            assert!(
+                b.to_string()
+                    .contains(&format!("pid {}", std::process::id()))
+            );
+        }
+        other => panic!("expected busy, got {other:?}"),
+    }
+
+    // Dropping releases it; the leftover file does not block the next run.
+    drop(held);
+    let again = LockFile::acquire(&path).unwrap();
+    drop(again);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn chip_lock_is_shared_in_process_and_refused_to_others() {
+    let dir = std::env::temp_dir().join(format!("doser-chip-lock-test-{}", std::process::id()));
+    let a = chip_lock(&dir, "/dev/gpiochip7").unwrap();
+    let b = chip_lock(&dir, "gpiochip7").unwrap();
+    assert!(Arc::ptr_eq(&a, &b), "drivers of one process share the lock");
+    assert_eq!(a.path(), dir.join("gpiochip7.lock"));
+
+    // Anyone else (here a separate open of the same file) is refused.
+    assert!(matches!(
+        LockFile::acquire(dir.join("gpiochip7.lock")),
+        Err(LockError::Busy(_))
+    ));
+    drop((a, b));
+
+    let other = LockFile::acquire(dir.join("gpiochip7.lock")).unwrap();
+    match chip_lock(&dir, "/dev/gpiochip7") {
+        Err(LockError::Busy(b)) => assert_eq!(b.pid, Some(std::process::id())),
+        other => panic!("expected busy, got {other:?}"),
+    }
+    drop(other);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn waiting_lock_is_taken_once_the_holder_drops() {
+    let path =
+        std::env::temp_dir().join(format!("doser-lock-wait-test-{}.lock", std::process::id()));
+    let held = LockFile::acquire(&path).unwrap();
+    let waiter = {
+        let path = path.clone();
+        std::thread::spawn(move || LockFile::acquire_wait(&path).map(|_| ()))
+    };
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert!(!waiter.is_finished(), "took a held lock");
+    drop(held);
+    waiter.join().unwrap().unwrap();
+    let _ = std::fs::remove_file(&path);
+}
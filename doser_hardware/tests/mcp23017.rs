@@ -0,0 +1,83 @@
+//! MCP23017 register sequencing against an in-memory register file.
+
+use std::sync::{Arc, Mutex};
+
+use doser_hardware::error::Result;
+use doser_hardware::{ExpanderActuator, Mcp23017, RegisterBus, SharedExpander};
+use doser_traits::Actuator;
+
+const IODIRA: u8 = 0x00;
+const IODIRB: u8 = 0x01;
+const OLATA: u8 = 0x14;
+const OLATB: u8 = 0x15;
+
+/// Register file plus the order of writes.
+#[derive(Clone, Default)]
+struct FakeBus {
+    regs: Arc<Mutex<[u8; 0x16]>>,
+    log: Arc<Mutex<Vec<(u8, u8)>>>,
+}
+
+impl FakeBus {
+    fn reg(&self, reg: u8) -> u8 {
+        self.regs.lock().unwrap()[usize::from(reg)]
+    }
+
+    fn writes(&self) -> Vec<(u8, u8)> {
+        self.log.lock().unwrap().clone()
+    }
+}
+
+impl RegisterBus for FakeBus {
+    fn write_reg(&mut self, reg: u8, value: u8) -> Result<()> {
+        self.regs.lock().unwrap()[usize::from(reg)] = value;
+        self.log.lock().unwrap().push((reg, value));
+        Ok(())
+    }
+}
+
+fn shared(bus: &FakeBus) -> SharedExpander {
+    let boxed: Box<dyn RegisterBus + Send> = Box::new(bus.clone());
+    Arc::new(Mutex::new(Mcp23017::new(boxed).unwrap()))
+}
+
+#[test]
+fn new_leaves_all_pins_as_inputs() {
+    let bus = FakeBus::default();
+    let _dev = Mcp23017::new(bus.clone()).unwrap();
+    assert_eq!(bus.reg(IODIRA), 0xFF);
+    assert_eq!(bus.reg(IODIRB), 0xFF);
+    assert_eq!(bus.reg(OLATA), 0);
+    assert_eq!(bus.reg(OLATB), 0);
+}
+
+#[test]
+fn output_latch_is_set_before_direction() {
+    let bus = FakeBus::default();
+    let mut dev = Mcp23017::new(bus.clone()).unwrap();
+    let before = bus.writes().len();
+    dev.configure_output(9, true).unwrap();
+    assert_eq!(
+        bus.writes()[before..],
+        [(OLATB, 0b10), (IODIRB, 0b1111_1101)]
+    );
+    assert!(dev.configure_output(16, false).is_err());
+}
+
+#[test]
+fn actuators_share_one_expander_without_clobbering() {
+    let bus = FakeBus::default();
+    let dev = shared(&bus);
+    let mut valve = ExpanderActuator::try_new(dev.clone(), 7, false).unwrap();
+    let mut relay = ExpanderActuator::try_new(dev, 6, true).unwrap();
+    // Active-low output starts inactive, i.e. high.
+    assert_eq!(bus.reg(OLATA), 0b0100_0000);
+    assert_eq!(bus.reg(IODIRA), 0b0011_1111);
+
+    valve.set_active(true).unwrap();
+    assert_eq!(bus.reg(OLATA), 0b1100_0000);
+    relay.set_active(true).unwrap();
+    assert_eq!(bus.reg(OLATA), 0b1000_0000);
+    valve.set_active(false).unwrap();
+    assert_eq!(bus.reg(OLATA), 0);
+}
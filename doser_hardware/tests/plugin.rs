@@ -0,0 +1,115 @@
+//! Plugin function tables driven through the `Scale`/`Motor` adapters, with an
+//! in-process "plugin" standing in for a shared library.
+
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use doser_hardware::error::HwError;
+use doser_hardware::plugin::{DOSER_ERR_TIMEOUT, DOSER_OK, DoserMotorVTable, DoserScaleVTable};
+use doser_hardware::{PluginMotor, PluginScale};
+use doser_traits::{Motor, Scale};
+
+/// Plugin state; `reads` counts down to a timeout, then errors.
+struct Dev {
+    reads: u32,
+    sps: u32,
+    destroyed: &'static AtomicU32,
+}
+
+unsafe extern "C" fn read(ctx: *mut c_void, timeout_ms: u64, out: *mut i32) -> i32 {
+    let dev = unsafe { &mut *ctx.cast::<Dev>() };
+    match dev.reads {
+        0 => -5,
+        1 => {
+            dev.reads = 0;
+            DOSER_ERR_TIMEOUT
+        }
+        n => {
+            dev.reads = n - 1;
+            unsafe { *out = i32::try_from(timeout_ms).unwrap() };
+            DOSER_OK
+        }
+    }
+}
+
+unsafe extern "C" fn set_speed(ctx: *mut c_void, sps: u32) -> i32 {
+    unsafe { (*ctx.cast::<Dev>()).sps = sps };
+    DOSER_OK
+}
+
+unsafe extern "C" fn ok(_ctx: *mut c_void) -> i32 {
+    DOSER_OK
+}
+
+unsafe extern "C" fn destroy(ctx: *mut c_void) {
+    let dev = unsafe { Box::from_raw(ctx.cast::<Dev>()) };
+    dev.destroyed.fetch_add(1, Ordering::SeqCst);
+}
+
+fn dev(reads: u32, destroyed: &'static AtomicU32) -> *mut c_void {
+    Box::into_raw(Box::new(Dev {
+        reads,
+        sps: 0,
+        destroyed,
+    }))
+    .cast()
+}
+
+#[test]
+fn scale_maps_status_codes() {
+    static DESTROYED: AtomicU32 = AtomicU32::new(0);
+    let mut scale = unsafe {
+        PluginScale::from_vtable(DoserScaleVTable {
+            ctx: dev(3, &DESTROYED),
+            read,
+            reinit: None,
+            destroy,
+        })
+    };
+    let t = Duration::from_millis(42);
+    assert_eq!(scale.read(t).unwrap(), 42);
+    assert_eq!(scale.read(t).unwrap(), 42);
+
+    let e = scale.read(t).unwrap_err();
+    assert!(matches!(
+        e.downcast_ref::<HwError>(),
+        Some(HwError::Timeout)
+    ));
+    let e = scale.read(t).unwrap_err();
+    assert!(matches!(
+        e.downcast_ref::<HwError>(),
+        Some(HwError::Plugin(_))
+    ));
+    assert!(scale.reinit().is_err(), "null reinit is unsupported");
+
+    drop(scale);
+    assert_eq!(DESTROYED.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn motor_forwards_calls_and_destroys_once() {
+    static DESTROYED: AtomicU32 = AtomicU32::new(0);
+    let ctx = dev(0, &DESTROYED);
+    let mut motor = unsafe {
+        PluginMotor::from_vtable(DoserMotorVTable {
+            ctx,
+            set_speed,
+            start: ok,
+            stop: ok,
+            enable: None,
+            disable: Some(ok),
+            destroy,
+        })
+    };
+    motor.set_speed(750).unwrap();
+    assert_eq!(unsafe { (*ctx.cast::<Dev>()).sps }, 750);
+    motor.start().unwrap();
+    motor.enable().unwrap();
+    motor.disable().unwrap();
+    motor.stop().unwrap();
+
+    // Backends move into the control thread.
+    std::thread::spawn(move || drop(motor)).join().unwrap();
+    assert_eq!(DESTROYED.load(Ordering::SeqCst), 1);
+}
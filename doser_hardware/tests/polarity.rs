@@ -0,0 +1,26 @@
+//! DIR/EN level mapping for wiring variations.
+
+use doser_hardware::MotorPolarity;
+
+#[test]
+fn default_polarity_matches_a4988_wiring() {
+    let p = MotorPolarity::default();
+    // Dispense (counterclockwise) = DIR low; reverse = DIR high.
+    assert!(!p.dir_high(false));
+    assert!(p.dir_high(true));
+    // Active-low enable.
+    assert!(!p.enable_high(true));
+    assert!(p.enable_high(false));
+}
+
+#[test]
+fn inversion_swaps_levels() {
+    let p = MotorPolarity {
+        invert_direction: true,
+        invert_enable: true,
+    };
+    assert!(p.dir_high(false));
+    assert!(!p.dir_high(true));
+    assert!(p.enable_high(true));
+    assert!(!p.enable_high(false));
+}
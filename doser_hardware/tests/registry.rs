@@ -0,0 +1,107 @@
+//! Driver selection by name through the backend registry.
+
+use std::error::Error;
+use std::time::Duration;
+
+use doser_hardware::error::{HwError, Result};
+use doser_hardware::registry::{BoxedMotor, BoxedScale, Registry, Wiring};
+use doser_traits::{Motor, Scale};
+
+/// Scale reading the wiring's DT pin back, so tests see which wiring was used.
+struct PinScale(i32);
+
+impl Scale for PinScale {
+    fn read(
+        &mut self,
+        _timeout: Duration,
+    ) -> std::result::Result<i32, Box<dyn Error + Send + Sync>> {
+        Ok(self.0)
+    }
+}
+
+struct NullMotor;
+
+impl Motor for NullMotor {
+    fn start(&mut self) -> std::result::Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn set_speed(&mut self, _sps: u32) -> std::result::Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+    fn stop(&mut self) -> std::result::Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+fn pin_scale(w: &Wiring) -> Result<BoxedScale> {
+    Ok(Box::new(PinScale(i32::from(w.hx711_dt))))
+}
+
+fn null_motor(_w: &Wiring) -> Result<BoxedMotor> {
+    Ok(Box::new(NullMotor))
+}
+
+/// Pair whose scale reads a marker, to tell it apart from `pin_scale`.
+fn marked_pair(_w: &Wiring) -> Result<(BoxedScale, BoxedMotor)> {
+    Ok((Box::new(PinScale(-1)), Box::new(NullMotor)))
+}
+
+fn registry() -> Registry {
+    let mut r = Registry::new();
+    r.register_scale("pin", pin_scale)
+        .register_motor("null", null_motor)
+        .register_pair("pair", marked_pair);
+    r
+}
+
+#[test]
+fn opens_drivers_by_name() {
+    let wiring = Wiring {
+        hx711_dt: 17,
+        ..Wiring::default()
+    };
+    let (mut scale, _motor) = registry().open("pin", "null", &wiring).unwrap();
+    assert_eq!(scale.read(Duration::from_millis(1)).unwrap(), 17);
+
+    // Pair halves mix with other drivers.
+    let (mut scale, _motor) = registry().open("pair", "null", &wiring).unwrap();
+    assert_eq!(scale.read(Duration::from_millis(1)).unwrap(), -1);
+    assert!(registry().open("pin", "pair", &wiring).is_ok());
+}
+
+#[test]
+fn lists_drivers_including_pairs() {
+    let r = registry();
+    assert_eq!(r.scale_drivers(), ["pair", "pin"]);
+    assert_eq!(r.motor_drivers(), ["null", "pair"]);
+}
+
+#[test]
+fn unknown_driver_names_the_alternatives() {
+    let Err(err) = registry().open("pin", "tmc-uart", &Wiring::default()) else {
+        panic!("unknown motor driver accepted");
+    };
+    assert!(
+        matches!(&err, HwError::UnknownDriver { kind: "motor", name, .. } if name == "tmc-uart")
+    );
+    assert_eq!(
+        err.to_string(),
+        "motor driver \"tmc-uart\" is not available in this build (available: null, pair)"
+    );
+}
+
+#[test]
+fn builtin_sim_pair_is_linked() {
+    // SAFETY: the only test in this binary that reads the variable.
+    unsafe {
+        std::env::set_var("DOSER_TEST_SIM_INC", "1.0");
+    }
+    let (mut scale, mut motor) = Registry::builtin()
+        .open("sim", "sim", &Wiring::default())
+        .unwrap();
+    let t = Duration::from_millis(10);
+    assert_eq!(scale.read(t).unwrap(), 0);
+    motor.set_speed(1000).unwrap();
+    motor.start().unwrap();
+    assert_eq!(scale.read(t).unwrap(), 100, "sim scale ignores the motor");
+}
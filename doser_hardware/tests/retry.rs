@@ -0,0 +1,84 @@
+use std::error::Error;
+use std::io;
+use std::time::Duration;
+
+use doser_hardware::RetryingScale;
+use doser_traits::Scale;
+
+/// Fails with `err` for the first `fail` reads, then returns 42.
+struct Flaky {
+    fail: u32,
+    err: fn() -> Box<dyn Error + Send + Sync>,
+    reinits: u32,
+}
+impl Scale for Flaky {
+    fn read(&mut self, _t: Duration) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        if self.fail > 0 {
+            self.fail -= 1;
+            return Err((self.err)());
+        }
+        Ok(42)
+    }
+    fn reinit(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.reinits += 1;
+        Ok(())
+    }
+}
+
+fn timeout() -> Box<dyn Error + Send + Sync> {
+    Box::new(io::Error::new(io::ErrorKind::TimedOut, "timeout"))
+}
+
+fn other() -> Box<dyn Error + Send + Sync> {
+    "bus fault".into()
+}
+
+const T: Duration = Duration::from_millis(1);
+
+#[test]
+fn recovers_within_budget() {
+    let flaky = Flaky {
+        fail: 2,
+        err: timeout,
+        reinits: 0,
+    };
+    let mut s = RetryingScale::new(flaky, 2, Duration::ZERO);
+    let stats = s.stats();
+    assert_eq!(s.read(T).unwrap(), 42);
+    assert_eq!(
+        (stats.reinits(), stats.recovered(), stats.exhausted()),
+        (2, 1, 0)
+    );
+}
+
+#[test]
+fn surfaces_error_when_exhausted() {
+    let flaky = Flaky {
+        fail: 5,
+        err: timeout,
+        reinits: 0,
+    };
+    let mut s = RetryingScale::new(flaky, 2, Duration::ZERO);
+    let stats = s.stats();
+    let err = s.read(T).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<io::Error>().map(io::Error::kind),
+        Some(io::ErrorKind::TimedOut)
+    );
+    assert_eq!((stats.reinits(), stats.exhausted()), (2, 1));
+    // The next read gets a fresh budget.
+    assert_eq!(s.read(T).unwrap(), 42);
+    assert_eq!((stats.reinits(), stats.recovered()), (4, 1));
+}
+
+#[test]
+fn non_recoverable_errors_pass_through() {
+    let flaky = Flaky {
+        fail: 1,
+        err: other,
+        reinits: 0,
+    };
+    let mut s = RetryingScale::new(flaky, 3, Duration::ZERO);
+    assert!(s.read(T).is_err());
+    assert_eq!(s.stats().reinits(), 0);
+}
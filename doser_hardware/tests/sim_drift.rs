@@ -0,0 +1,63 @@
+//! Sim scale creep and baseline drift.
+
+use std::time::Duration;
+
+use doser_hardware::{SimDrift, SimulatedScale};
+use doser_traits::Scale;
+use rstest::rstest;
+
+fn drift() -> SimDrift {
+    SimDrift {
+        zero_g_per_min: 0.6,
+        thermal_g: 0.2,
+        thermal_period_s: 600.0,
+        creep_fraction: 0.01,
+        creep_tau_s: 30.0,
+    }
+}
+
+#[rstest]
+#[case::start(0.0, 0.0)]
+// 0.01 g/s of zero drift plus the crest of the swing.
+#[case::quarter_period(150.0, 1.5 + 0.2)]
+#[case::half_period(300.0, 3.0)]
+fn baseline_drifts_and_swings(#[case] t_s: f32, #[case] expect_g: f32) {
+    assert!((drift().baseline_g(t_s) - expect_g).abs() < 1e-3);
+}
+
+#[test]
+fn creep_approaches_a_fraction_of_the_load() {
+    let d = drift();
+    // One time constant under 100 g: 63% of the way to 1 g.
+    let c = d.creep_g(0.0, 100.0, 30.0);
+    assert!((c - (1.0 - (-1.0f32).exp())).abs() < 1e-4, "{c}");
+    // Long after, it settles; with the load gone it recovers.
+    assert!((d.creep_g(c, 100.0, 1e4) - 1.0).abs() < 1e-4);
+    assert!(d.creep_g(1.0, 0.0, 1e4).abs() < 1e-4);
+    // Without a time constant it is immediate.
+    let instant = SimDrift {
+        creep_tau_s: 0.0,
+        ..d
+    };
+    assert_eq!(instant.creep_g(0.0, 100.0, 0.0), 1.0);
+}
+
+#[test]
+fn readings_drift_over_time() {
+    // 60 g/min: 1 count (0.01 g) every 10 ms.
+    let mut scale = SimulatedScale::new().with_drift(SimDrift {
+        zero_g_per_min: 60.0,
+        ..SimDrift::default()
+    });
+    let t = Duration::from_millis(1);
+    let first = scale.read(t).unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    let later = scale.read(t).unwrap();
+    assert!(later - first >= 4, "{first} -> {later}");
+
+    // The default scale stays put.
+    let mut ideal = SimulatedScale::new();
+    assert_eq!(ideal.read(t).unwrap(), 0);
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(ideal.read(t).unwrap(), 0);
+}
@@ -0,0 +1,14 @@
+//! Test-controlled sim inputs.
+
+use doser_hardware::SimInput;
+
+#[test]
+fn checker_follows_shared_state() {
+    let input = SimInput::new();
+    let check = input.checker();
+    assert!(!check());
+    input.clone().set_active(true);
+    assert!(check() && input.is_active());
+    input.set_active(false);
+    assert!(!check());
+}
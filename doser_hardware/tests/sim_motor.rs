@@ -0,0 +1,88 @@
+//! Sim motor mechanical limits (missed steps, stalls), its stop latch and its
+//! step counter.
+
+use std::time::Duration;
+
+use doser_hardware::{SimulatedMotor, sim_pair};
+use doser_traits::{Motor, Scale};
+use rstest::rstest;
+
+#[rstest]
+#[case::ideal(None, 0.0, 5000, 5000)]
+#[case::below_limit(Some(2000), 0.0, 1500, 1500)]
+#[case::misses_steps_above_limit(Some(2000), 0.0, 3000, 2000)]
+#[case::light_load_at_low_speed(Some(2000), 0.5, 500, 500)]
+#[case::load_stalls_at_speed(Some(2000), 0.5, 1200, 0)]
+#[case::jammed(None, 1.0, 100, 0)]
+fn effective_sps_follows_model(
+    #[case] max_follow: Option<u32>,
+    #[case] load: f32,
+    #[case] sps: u32,
+    #[case] expect: u32,
+) {
+    let m = SimulatedMotor::new().mechanics();
+    m.set_max_follow_sps(max_follow);
+    m.set_load(load);
+    assert_eq!(m.effective_sps(sps), expect);
+}
+
+#[test]
+fn stalled_motor_delivers_nothing() {
+    // SAFETY: every test in this binary that reads the variable sets it to the same value.
+    unsafe {
+        std::env::set_var("DOSER_TEST_SIM_INC", "1.0");
+    }
+    let (mut scale, mut motor) = sim_pair();
+    let mech = motor.mechanics();
+    let t = Duration::from_millis(10);
+    motor.set_speed(1000).unwrap();
+    motor.start().unwrap();
+    assert_eq!(scale.read(t).unwrap(), 100);
+    assert!(!mech.is_stalled());
+
+    // Half the steps are missed: half the material per read.
+    mech.set_max_follow_sps(Some(500));
+    assert_eq!(scale.read(t).unwrap(), 150);
+
+    mech.set_max_follow_sps(None);
+    mech.set_load(1.0);
+    assert!(mech.is_stalled());
+    assert_eq!(scale.read(t).unwrap(), 150);
+}
+
+#[test]
+fn tripped_stop_latch_holds_the_motor() {
+    // SAFETY: every test in this binary that reads the variable sets it to the same value.
+    unsafe {
+        std::env::set_var("DOSER_TEST_SIM_INC", "1.0");
+    }
+    let (mut scale, mut motor) = sim_pair();
+    let latch = motor.stop_latch().expect("sim motor honors a stop latch");
+    let t = Duration::from_millis(10);
+    motor.set_speed(1000).unwrap();
+    motor.start().unwrap();
+    assert_eq!(scale.read(t).unwrap(), 100);
+
+    latch.trip();
+    motor.start().unwrap();
+    assert_eq!(scale.read(t).unwrap(), 100, "held despite the owner");
+
+    latch.reset();
+    assert_eq!(scale.read(t).unwrap(), 200);
+}
+
+#[test]
+fn diagnostics_count_steps_at_the_commanded_rate() {
+    let mut motor = SimulatedMotor::new();
+    let diag = motor.diagnostics().expect("sim motor keeps counters");
+    motor.set_speed(1000).unwrap();
+    assert_eq!(diag.steps_issued(), 0, "not started");
+    motor.start().unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    motor.stop().unwrap();
+    let steps = diag.steps_issued();
+    assert!((50..200).contains(&steps), "{steps}");
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(diag.steps_issued(), steps, "stopped");
+    assert_eq!(diag.snapshot().missed_periods, 0);
+}
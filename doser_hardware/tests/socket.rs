@@ -0,0 +1,64 @@
+#![cfg(unix)]
+//! `socket` scale: lines of raw counts over a Unix socket.
+
+use std::io::Write;
+use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use doser_hardware::error::HwError;
+use doser_hardware::registry::{Registry, Wiring};
+use doser_hardware::socket::{SocketScale, write_reading};
+use doser_traits::Scale;
+
+fn socket_path(name: &str) -> PathBuf {
+    let p = std::env::temp_dir().join(format!("doser-{name}-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&p);
+    p
+}
+
+fn is_timeout(e: Box<dyn std::error::Error + Send + Sync>) -> bool {
+    matches!(e.downcast_ref::<HwError>(), Some(HwError::Timeout))
+}
+
+#[test]
+fn reads_lines_and_carries_a_partial_one_over_a_timeout() {
+    let path = socket_path("lines");
+    let listener = UnixListener::bind(&path).unwrap();
+    let mut scale = SocketScale::connect(&path).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+    let t = Duration::from_millis(20);
+
+    write_reading(&mut server, 100).unwrap();
+    assert_eq!(scale.read(t).unwrap(), 100);
+
+    assert!(is_timeout(scale.read(t).unwrap_err()), "nothing sent");
+    server.write_all(b"-2").unwrap();
+    assert!(is_timeout(scale.read(t).unwrap_err()), "half a line");
+    server.write_all(b"5\n").unwrap();
+    assert_eq!(scale.read(t).unwrap(), -25);
+
+    server.write_all(b"heavy\n").unwrap();
+    assert!(scale.read(t).unwrap_err().to_string().contains("heavy"));
+
+    drop(server);
+    assert!(scale.read(t).unwrap_err().to_string().contains("closed"));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn registry_opens_the_configured_socket() {
+    let path = socket_path("registry");
+    let _listener = UnixListener::bind(&path).unwrap();
+    let wiring = Wiring {
+        socket: Some(path.display().to_string()),
+        ..Wiring::default()
+    };
+    assert!(Registry::builtin().open_scale("socket", &wiring).is_ok());
+    let err = Registry::builtin()
+        .open_scale("socket", &Wiring::default())
+        .err()
+        .expect("no socket path");
+    assert!(err.to_string().contains("[scale] socket"));
+    let _ = std::fs::remove_file(&path);
+}
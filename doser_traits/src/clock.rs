@@ -1,3 +1,4 @@
+use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -5,16 +6,53 @@ use std::time::{Duration, Instant};
 ///
 /// - now(): returns a monotonic Instant
 /// - sleep(): sleeps for the provided duration (implementations may simulate)
-/// - ms_since(): helper to compute elapsed milliseconds from an epoch Instant
+/// - sleep_until(): sleeps until an absolute deadline (drift-free pacing)
+/// - ms_since()/ns_since(): elapsed time from an epoch Instant
+/// - now_ns(): nanoseconds on a process-wide monotonic timeline
+/// - spin_hint(): called inside busy-wait loops
 pub trait Clock {
     fn now(&self) -> Instant;
     fn sleep(&self, d: Duration);
 
+    /// Sleep until `deadline`; returns immediately if it has already passed.
+    ///
+    /// Pacing against absolute deadlines (rather than sleeping a fixed period
+    /// after each iteration) keeps per-iteration work from accumulating as drift.
+    fn sleep_until(&self, deadline: Instant) {
+        let now = self.now();
+        if deadline > now {
+            self.sleep(deadline - now);
+        }
+    }
+
+    /// Hint issued on each iteration of a busy-wait loop.
+    #[inline]
+    fn spin_hint(&self) {
+        std::hint::spin_loop();
+    }
+
     /// Milliseconds elapsed since `epoch`, saturating at 0 on underflow.
     fn ms_since(&self, epoch: Instant) -> u64 {
         let dur = self.now().saturating_duration_since(epoch);
         dur.as_millis() as u64
     }
+
+    /// Nanoseconds elapsed since `epoch`, saturating at 0 on underflow.
+    fn ns_since(&self, epoch: Instant) -> u64 {
+        let ns = self.now().saturating_duration_since(epoch).as_nanos();
+        ns.min(u128::from(u64::MAX)) as u64
+    }
+
+    /// Nanoseconds since a process-wide anchor (taken on first use). Only
+    /// differences between values are meaningful.
+    fn now_ns(&self) -> u64 {
+        self.ns_since(process_anchor())
+    }
+}
+
+fn process_anchor() -> Instant {
+    static ANCHOR: OnceLock<Instant> = OnceLock::new();
+    *ANCHOR.get_or_init(Instant::now)
 }
 
 /// Default, real-time monotonic clock backed by std::time::Instant.
@@ -41,6 +79,83 @@ impl Clock for MonotonicClock {
         }
         thread::sleep(d);
     }
+
+    #[inline]
+    fn sleep_until(&self, deadline: Instant) {
+        self.sleep(deadline.saturating_duration_since(Instant::now()));
+    }
+}
+
+/// Shared clocks (e.g. one [`VirtualClock`] handed to several owners).
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    #[inline]
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+
+    #[inline]
+    fn sleep(&self, d: Duration) {
+        (**self).sleep(d);
+    }
+
+    #[inline]
+    fn sleep_until(&self, deadline: Instant) {
+        (**self).sleep_until(deadline);
+    }
+
+    #[inline]
+    fn spin_hint(&self) {
+        (**self).spin_hint();
+    }
+}
+
+/// Virtual time for simulation: `sleep` advances the clock instead of
+/// blocking, so a paced loop runs as fast as it computes. Clones share one
+/// timeline.
+///
+/// Meant for a single paced thread; two threads sleeping on the same virtual
+/// clock each push it forward, and time runs ahead of both.
+#[derive(Debug, Clone)]
+pub struct VirtualClock {
+    origin: Instant,
+    offset: Arc<Mutex<Duration>>,
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VirtualClock {
+    /// A clock starting at the current real instant.
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            offset: Arc::new(Mutex::new(Duration::ZERO)),
+        }
+    }
+
+    /// Move the clock forward by `d`.
+    pub fn advance(&self, d: Duration) {
+        if let Ok(mut off) = self.offset.lock() {
+            *off = off.saturating_add(d);
+        }
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        let off = self.offset.lock().map(|g| *g).unwrap_or(Duration::ZERO);
+        self.origin + off
+    }
+
+    fn sleep(&self, d: Duration) {
+        self.advance(d);
+    }
+
+    /// Nothing else can move virtual time; don't spin.
+    fn spin_hint(&self) {}
 }
 
 #[cfg(test)]
@@ -97,3 +212,42 @@ pub mod test_clock {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::test_clock::TestClock;
+    use super::*;
+
+    #[test]
+    fn sleep_until_advances_to_deadline_and_ignores_past() {
+        let c = TestClock::new();
+        let t0 = c.now();
+        c.sleep_until(t0 + Duration::from_millis(7));
+        assert_eq!(c.now(), t0 + Duration::from_millis(7));
+        c.sleep_until(t0);
+        assert_eq!(c.now(), t0 + Duration::from_millis(7));
+    }
+
+    #[test]
+    fn virtual_clock_advances_on_sleep_across_clones() {
+        let c = VirtualClock::new();
+        let shared: Arc<dyn Clock + Send + Sync> = Arc::new(c.clone());
+        let t0 = c.now();
+        shared.sleep_until(t0 + Duration::from_secs(3600));
+        assert_eq!(c.ms_since(t0), 3_600_000);
+        c.sleep(Duration::from_millis(5));
+        assert_eq!(shared.ms_since(t0), 3_600_005);
+    }
+
+    #[test]
+    fn ns_since_is_finer_than_ms_since() {
+        let c = TestClock::new();
+        let t0 = c.now();
+        c.advance(Duration::from_micros(1_500));
+        assert_eq!(c.ms_since(t0), 1);
+        assert_eq!(c.ns_since(t0), 1_500_000);
+        let a = c.now_ns();
+        c.advance(Duration::from_nanos(250));
+        assert_eq!(c.now_ns() - a, 250);
+    }
+}
@@ -0,0 +1,37 @@
+//! Stepping diagnostics of a motor backend.
+//!
+//! A backend that drives its motor from its own thread (or models one) keeps
+//! counters a supervisor may read at any time, from any thread, through the
+//! handle [`crate::Motor::diagnostics`] returns; it stays valid after the
+//! motor moves into a run.
+
+/// Counters of a motor backend, shared with its stepping thread.
+pub trait MotorDiagnostics: Send + Sync {
+    /// Step pulses issued since the motor was opened.
+    fn steps_issued(&self) -> u64;
+    /// Mean wake-up jitter of the step pulses over the latest window (µs).
+    fn avg_jitter_us(&self) -> u32;
+    /// Largest wake-up jitter of any step pulse so far (µs).
+    fn max_jitter_us(&self) -> u32;
+    /// Step periods whose deadline had already passed when waited for.
+    fn missed_periods(&self) -> u64;
+
+    /// All counters at once.
+    fn snapshot(&self) -> MotorDiagSnapshot {
+        MotorDiagSnapshot {
+            steps_issued: self.steps_issued(),
+            avg_jitter_us: self.avg_jitter_us(),
+            max_jitter_us: self.max_jitter_us(),
+            missed_periods: self.missed_periods(),
+        }
+    }
+}
+
+/// [`MotorDiagnostics`] counters read at one point in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MotorDiagSnapshot {
+    pub steps_issued: u64,
+    pub avg_jitter_us: u32,
+    pub max_jitter_us: u32,
+    pub missed_periods: u64,
+}
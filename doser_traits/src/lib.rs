@@ -12,13 +12,29 @@
 //!   grams/centigrams. (The simulation backend happens to use a 1 count = 0.01 g
 //!   scale, so its raw counts equal centigrams, but that is not part of the contract.)
 //! - `Motor` configures/starts/stops motor stepping at steps-per-second.
+//! - `Actuator` drives a binary output (valve, gate) used by abort safe-state
+//!   sequences.
 //! - `clock` offers a `MonotonicClock` for deterministic timing and testability.
+//! - `pacing` provides the absolute-deadline `Pacer` shared by the control loop,
+//!   the sampler, and the hardware stepper.
+//! - `stop` provides the `StopLatch` through which another thread (an E-stop
+//!   monitor) stops a motor without its owner.
+//! - `diagnostics` provides `MotorDiagnostics`, the stepping counters (steps,
+//!   jitter, missed periods) a motor backend may expose.
 //!
 //! Other crates depend only on these traits, enabling simulation and multiple hardware
 //! backends while keeping `doser_core` hardware-agnostic.
 pub mod clock;
+pub mod diagnostics;
+pub mod pacing;
+pub mod stop;
 
 pub use clock::{Clock, MonotonicClock};
+pub use diagnostics::{MotorDiagSnapshot, MotorDiagnostics};
+pub use stop::StopLatch;
+
+/// This crate's version.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub trait Scale {
     /// Read one raw ADC sample in counts, blocking up to `timeout`.
@@ -26,6 +42,26 @@ pub trait Scale {
         &mut self,
         timeout: std::time::Duration,
     ) -> Result<i32, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Re-initialize the sensor after a failed read (e.g. power-cycle the ADC).
+    /// Defaults to an "unsupported" error.
+    fn reinit(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Err("re-init not supported by this scale".into())
+    }
+
+    /// Smallest change in counts a single reading resolves (the sensor's
+    /// noise floor), if the backend knows it. Used to round displayed weights
+    /// and to flag tolerances finer than the sensor can see.
+    fn resolution_counts(&self) -> Option<u32> {
+        None
+    }
+
+    /// Raw counts the ADC can legally produce (e.g. 24-bit signed for the
+    /// HX711), if the backend knows them. A reading outside is a fault, not a
+    /// weight.
+    fn raw_range(&self) -> Option<std::ops::RangeInclusive<i32>> {
+        None
+    }
 }
 
 pub trait Motor {
@@ -35,6 +71,63 @@ pub trait Motor {
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
     fn stop(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
     fn start(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Run `steps` steps backwards at `steps_per_sec`, blocking until done, and
+    /// leave the motor stopped. Used by abort safe-state sequences (e.g. to relieve
+    /// auger pressure). Defaults to an "unsupported" error.
+    fn reverse(
+        &mut self,
+        steps: u32,
+        steps_per_sec: u32,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _ = (steps, steps_per_sec);
+        Err("reverse not supported by this motor".into())
+    }
+
+    /// De-energize the driver (coils unpowered). Defaults to [`Motor::stop`] for
+    /// drivers without an enable line.
+    fn disable(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.stop()
+    }
+
+    /// Energize the driver without stepping. A no-op by default, for drivers
+    /// without an enable line.
+    fn enable(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    /// Emergency stop: halt at once and de-energize the driver, bypassing any
+    /// deceleration [`Motor::stop`] may apply. Used on E-stop and overshoot
+    /// aborts. Defaults to [`Motor::disable`].
+    fn halt(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.disable()
+    }
+
+    /// Latch through which another thread can stop this motor, if the backend
+    /// honors one. A tripped latch holds the motor stopped, whatever its owner
+    /// commands, until it is reset. `None` by default.
+    fn stop_latch(&self) -> Option<StopLatch> {
+        None
+    }
+
+    /// Err when the backend can no longer drive the motor although it is
+    /// commanded to run (e.g. its stepping thread stopped). Polled by the
+    /// control loop while the motor runs. `Ok` by default.
+    fn check_alive(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    /// Stepping counters of this backend, if it keeps them. `None` by default.
+    fn diagnostics(&self) -> Option<std::sync::Arc<dyn MotorDiagnostics>> {
+        None
+    }
+}
+
+/// A binary output such as a solenoid valve or gate, driven by abort safe-state
+/// sequences.
+pub trait Actuator {
+    /// Energize (`true`) or release (`false`) the output.
+    fn set_active(&mut self, active: bool) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 }
 
 // Allow boxed trait objects (Box<dyn Scale/Motor>) to be used where a generic S: Scale / M: Motor is expected.
@@ -45,6 +138,18 @@ impl<T: ?Sized + Scale> Scale for Box<T> {
     ) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
         (**self).read(timeout)
     }
+
+    fn reinit(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        (**self).reinit()
+    }
+
+    fn resolution_counts(&self) -> Option<u32> {
+        (**self).resolution_counts()
+    }
+
+    fn raw_range(&self) -> Option<std::ops::RangeInclusive<i32>> {
+        (**self).raw_range()
+    }
 }
 
 impl<T: ?Sized + Motor> Motor for Box<T> {
@@ -60,4 +165,35 @@ impl<T: ?Sized + Motor> Motor for Box<T> {
     fn start(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         (**self).start()
     }
+    fn reverse(
+        &mut self,
+        steps: u32,
+        steps_per_sec: u32,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        (**self).reverse(steps, steps_per_sec)
+    }
+    fn disable(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        (**self).disable()
+    }
+    fn enable(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        (**self).enable()
+    }
+    fn halt(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        (**self).halt()
+    }
+    fn stop_latch(&self) -> Option<StopLatch> {
+        (**self).stop_latch()
+    }
+    fn check_alive(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        (**self).check_alive()
+    }
+    fn diagnostics(&self) -> Option<std::sync::Arc<dyn MotorDiagnostics>> {
+        (**self).diagnostics()
+    }
+}
+
+impl<T: ?Sized + Actuator> Actuator for Box<T> {
+    fn set_active(&mut self, active: bool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        (**self).set_active(active)
+    }
 }
@@ -0,0 +1,260 @@
+//! Absolute-deadline pacing shared by the control loop, the sampler and the
+//! hardware stepper thread.
+//!
+//! Each period's deadline is derived from the previous deadline rather than from
+//! "now", so work done inside the period does not accumulate as drift. The pacer
+//! also measures wake-up jitter against the deadline.
+
+use std::time::{Duration, Instant};
+
+use crate::clock::Clock;
+
+/// Time source used by [`Pacer`]. Every [`Clock`] is a `Sleeper`.
+pub trait Sleeper {
+    fn now(&self) -> Instant;
+    fn sleep_until(&self, deadline: Instant);
+}
+
+impl<C: Clock + ?Sized> Sleeper for C {
+    #[inline]
+    fn now(&self) -> Instant {
+        Clock::now(self)
+    }
+    #[inline]
+    fn sleep_until(&self, deadline: Instant) {
+        Clock::sleep_until(self, deadline);
+    }
+}
+
+/// What to do when a period's deadline has already passed when the pacer is
+/// asked to wait for it (the iteration overran its slot). The default matches
+/// the config's `runner.overrun` default, `skip-sleep`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverrunPolicy {
+    /// Keep the original schedule and run late periods back-to-back until
+    /// caught up. Preserves the long-run rate (e.g. total steps emitted).
+    CatchUp,
+    /// Don't sleep after an overrun; re-anchor the schedule at the current time
+    /// so the next period starts immediately. Minimizes latency.
+    #[default]
+    SkipSleep,
+    /// Re-anchor and then wait a full period before the next iteration, giving
+    /// the system room to recover at the cost of cadence. Callers typically log
+    /// each overrun under this policy.
+    LogAndDegrade,
+}
+
+/// Number of periods averaged into one jitter sample.
+const JITTER_WINDOW: u32 = 256;
+
+/// Absolute-deadline pacer; measures jitter and exposes rolling average.
+#[derive(Debug, Clone)]
+pub struct Pacer {
+    prev_deadline: Option<Instant>,
+    policy: OverrunPolicy,
+    overruns: u64,
+    jitter_accum_us: u128,
+    jitter_count: u32,
+    last_jitter_us: u32,
+    pub avg_jitter_us: u32,
+}
+
+impl Default for Pacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pacer {
+    /// A pacer on [`OverrunPolicy::CatchUp`] rather than the policy's default:
+    /// the stepper threads pace with it and must not drop steps.
+    pub fn new() -> Self {
+        Self {
+            prev_deadline: None,
+            policy: OverrunPolicy::CatchUp,
+            overruns: 0,
+            jitter_accum_us: 0,
+            jitter_count: 0,
+            last_jitter_us: 0,
+            avg_jitter_us: 0,
+        }
+    }
+
+    /// Choose how missed deadlines are handled (see [`Pacer::new`]).
+    pub fn with_overrun_policy(mut self, policy: OverrunPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Change the overrun policy; the current schedule is kept.
+    pub fn set_overrun_policy(&mut self, policy: OverrunPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn overrun_policy(&self) -> OverrunPolicy {
+        self.policy
+    }
+
+    /// Number of periods whose deadline had already passed when waited for.
+    pub fn overruns(&self) -> u64 {
+        self.overruns
+    }
+
+    /// Wake-up jitter of the latest period (µs).
+    pub fn last_jitter_us(&self) -> u32 {
+        self.last_jitter_us
+    }
+
+    /// Forget the schedule; the next period starts from the time of the next call.
+    pub fn reset(&mut self) {
+        self.prev_deadline = None;
+    }
+
+    /// Anchor the schedule so the first deadline is `start + period`.
+    pub fn reset_at(&mut self, start: Instant) {
+        self.prev_deadline = Some(start);
+    }
+
+    /// Run one full period paced by absolute deadlines, invoking `mid_hook` exactly at half period.
+    /// Returns Some(avg_jitter_us) whenever the internal window (256) completes.
+    pub fn step_with<F, S: Sleeper + ?Sized>(
+        &mut self,
+        sleeper: &S,
+        period_us: u64,
+        mut mid_hook: F,
+    ) -> Option<u32>
+    where
+        F: FnMut(),
+    {
+        let period = Duration::from_micros(period_us.max(1));
+        let deadline = self.next_deadline(sleeper, period);
+        sleeper.sleep_until(deadline - period / 2);
+        mid_hook();
+        self.finish(sleeper, deadline)
+    }
+
+    pub fn step<S: Sleeper + ?Sized>(&mut self, sleeper: &S, period_us: u64) -> Option<u32> {
+        self.step_with(sleeper, period_us, || {})
+    }
+
+    /// Sleep until the end of the current period (no mid-period wake-up).
+    pub fn tick<S: Sleeper + ?Sized>(&mut self, sleeper: &S, period_us: u64) -> Option<u32> {
+        let period = Duration::from_micros(period_us.max(1));
+        let deadline = self.next_deadline(sleeper, period);
+        self.finish(sleeper, deadline)
+    }
+
+    fn next_deadline<S: Sleeper + ?Sized>(&mut self, sleeper: &S, period: Duration) -> Instant {
+        let now = sleeper.now();
+        let Some(prev) = self.prev_deadline else {
+            return now + period;
+        };
+        let deadline = prev + period;
+        if deadline > now {
+            return deadline;
+        }
+        self.overruns = self.overruns.saturating_add(1);
+        match self.policy {
+            OverrunPolicy::CatchUp => deadline,
+            OverrunPolicy::SkipSleep => now,
+            OverrunPolicy::LogAndDegrade => now + period,
+        }
+    }
+
+    fn finish<S: Sleeper + ?Sized>(&mut self, sleeper: &S, deadline: Instant) -> Option<u32> {
+        sleeper.sleep_until(deadline);
+        let now = sleeper.now();
+        let jitter = if now >= deadline {
+            now - deadline
+        } else {
+            deadline - now
+        };
+        self.last_jitter_us = u32::try_from(jitter.as_micros()).unwrap_or(u32::MAX);
+        self.jitter_accum_us = self.jitter_accum_us.saturating_add(jitter.as_micros());
+        self.jitter_count = self.jitter_count.saturating_add(1);
+        self.prev_deadline = Some(deadline);
+        if self.jitter_count >= JITTER_WINDOW {
+            let avg = (self.jitter_accum_us / u128::from(self.jitter_count)) as u32;
+            self.avg_jitter_us = avg;
+            self.jitter_accum_us = 0;
+            self.jitter_count = 0;
+            Some(avg)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::test_clock::TestClock;
+
+    #[test]
+    fn no_drift_after_many_cycles_with_fake_sleep() {
+        let mut pacer = Pacer::new();
+        let clock = TestClock::new();
+        let t0 = Clock::now(&clock);
+        let period_us = 1000u64;
+        for _ in 0..10_000u32 {
+            let _ = pacer.step(&clock, period_us);
+        }
+        let expected = Duration::from_micros(period_us * 10_000);
+        assert_eq!(Clock::now(&clock) - t0, expected);
+    }
+
+    #[test]
+    fn work_inside_the_period_is_absorbed() {
+        let mut pacer = Pacer::new();
+        let clock = TestClock::new();
+        let t0 = Clock::now(&clock);
+        pacer.reset_at(t0);
+        for _ in 0..100 {
+            clock.advance(Duration::from_micros(300));
+            let _ = pacer.tick(&clock, 1000);
+        }
+        assert_eq!(Clock::now(&clock) - t0, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn overrun_policies() {
+        for (policy, expected_ms) in [
+            (OverrunPolicy::CatchUp, 5),
+            (OverrunPolicy::SkipSleep, 7),
+            (OverrunPolicy::LogAndDegrade, 8),
+        ] {
+            let mut pacer = Pacer::new().with_overrun_policy(policy);
+            let clock = TestClock::new();
+            let t0 = Clock::now(&clock);
+            pacer.reset_at(t0);
+            // One 3 ms stall, then four idle 1 ms periods.
+            clock.advance(Duration::from_millis(3));
+            for _ in 0..5 {
+                let _ = pacer.tick(&clock, 1000);
+            }
+            assert_eq!(
+                Clock::now(&clock) - t0,
+                Duration::from_millis(expected_ms),
+                "{policy:?}"
+            );
+            let expected_overruns = if policy == OverrunPolicy::CatchUp {
+                3
+            } else {
+                1
+            };
+            assert_eq!(pacer.overruns(), expected_overruns, "{policy:?}");
+        }
+    }
+
+    #[test]
+    fn reports_average_jitter_per_window() {
+        let mut pacer = Pacer::new();
+        let clock = TestClock::new();
+        let mut reported = None;
+        for _ in 0..JITTER_WINDOW {
+            reported = pacer.tick(&clock, 500);
+        }
+        assert_eq!(reported, Some(0));
+        assert_eq!(pacer.avg_jitter_us, 0);
+    }
+}
@@ -0,0 +1,34 @@
+//! Asynchronous motor stop.
+//!
+//! A [`StopLatch`] is shared between a motor backend and whoever may need to
+//! stop it from another thread (e.g. an E-stop monitor) without going through
+//! the motor's owner. Tripping it is a single atomic store; the backend
+//! checks it wherever it would step and holds the motor stopped until the
+//! latch is reset.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Shared stop flag honored by a motor backend (see [`crate::Motor::stop_latch`]).
+#[derive(Debug, Clone, Default)]
+pub struct StopLatch(Arc<AtomicBool>);
+
+impl StopLatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop the motor and hold it stopped until [`Self::reset`].
+    pub fn trip(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// Release the hold; the motor steps again once its owner commands it.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Release);
+    }
+
+    pub fn is_tripped(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}